@@ -1,5 +1,5 @@
 use riscv::register::{
-    sie, sstatus,
+    sie, sip, sstatus,
     stvec::{self, TrapMode},
 };
 
@@ -25,6 +25,15 @@ pub unsafe fn enable_external_interrupt() {
     sie::set_sext();
 }
 
+pub unsafe fn enable_software_interrupt() {
+    sie::set_ssoft();
+}
+
+/// Clear the pending supervisor software interrupt, i.e. acknowledge an IPI.
+pub unsafe fn clear_software_interrupt() {
+    sip::clear_ssoft();
+}
+
 pub fn get_trap_handler() -> usize {
     stvec::read().bits()
 }
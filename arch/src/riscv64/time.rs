@@ -1,36 +1,117 @@
-use core::time::Duration;
+use core::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
 
 use config::{board::clock_freq, time::INTERRUPTS_PER_SECOND};
-use riscv::register::time;
+use riscv::register::{cycle, instret, time};
+
+const NSEC_PER_SEC: u64 = 1_000_000_000;
+
+/// Fraction bits of `MULT`.
+const SHIFT: u32 = 32;
+
+/// The default of `CLOCK_FREQ`, in use until the device tree is read.
+const BOOT_CLOCK_FREQ: usize = 10_000_000;
+
+/// Nanoseconds per cycle of the time CSR, as a fixed point number with
+/// `SHIFT` fraction bits, so that a conversion is a multiplication and a shift
+/// rather than a division.
+static MULT: AtomicU64 = AtomicU64::new(mult_of(BOOT_CLOCK_FREQ));
+
+/// Cycles of the time CSR between two timer ticks.
+static TICK_CYCLES: AtomicUsize = AtomicUsize::new(BOOT_CLOCK_FREQ / INTERRUPTS_PER_SECOND);
+
+const fn mult_of(freq: usize) -> u64 {
+    ((((NSEC_PER_SEC as u128) << SHIFT) + freq as u128 / 2) / freq as u128) as u64
+}
+
+/// Set up the conversions for the frequency of the time CSR, once it has been
+/// read from the device tree. The conversion is checked against the exact one
+/// up to a day of uptime, where the rounding of `MULT` adds up to no more than
+/// a few nanoseconds.
+pub fn init_clock() {
+    let freq = clock_freq();
+    MULT.store(mult_of(freq), Ordering::Relaxed);
+    TICK_CYCLES.store(freq / INTERRUPTS_PER_SECOND, Ordering::Relaxed);
+    let now = time::read();
+    for cycles in [freq, now, now + 86400 * freq] {
+        let exact = (cycles as u128 * NSEC_PER_SEC as u128 / freq as u128) as u64;
+        let ns = cycles_to_ns(cycles);
+        // the error of `MULT` is at most half of its last bit
+        let tolerance = 1 + (cycles as u64 >> (SHIFT + 1));
+        assert!(
+            ns.abs_diff(exact) <= tolerance,
+            "clock conversion of {cycles} cycles at {freq} Hz is {ns} ns, expected {exact} ns"
+        );
+    }
+    log::info!(
+        "[init_clock] {freq} Hz, {} ns per cycle >> {SHIFT}",
+        MULT.load(Ordering::Relaxed)
+    );
+}
+
+/// Convert cycles of the time CSR to nanoseconds. It never goes backwards as
+/// the cycles go forward, so the times derived from it are monotonic.
+#[inline]
+pub fn cycles_to_ns(cycles: usize) -> u64 {
+    ((cycles as u128 * MULT.load(Ordering::Relaxed) as u128) >> SHIFT) as u64
+}
 
 pub fn get_time() -> usize {
     time::read()
 }
 
+/// Cycles executed by this hart.
+pub fn get_cycle() -> usize {
+    cycle::read()
+}
+
+/// Instructions retired by this hart.
+pub fn get_instret() -> usize {
+    instret::read()
+}
+
+/// nanoseconds 纳秒
+pub fn get_time_ns() -> u64 {
+    cycles_to_ns(time::read())
+}
+
 /// milliseconds 毫秒
 pub fn get_time_ms() -> usize {
-    time::read() / (clock_freq() / 1_000)
+    (get_time_ns() / 1_000_000) as usize
 }
 
 pub fn get_time_sec() -> usize {
-    time::read() / clock_freq()
+    (get_time_ns() / NSEC_PER_SEC) as usize
 }
 
 /// microseconds 微秒
 pub fn get_time_us() -> usize {
-    time::read() / (clock_freq() / 1_000_000)
+    (get_time_ns() / 1_000) as usize
 }
 
 pub fn get_time_duration() -> Duration {
-    Duration::from_micros(get_time_us() as u64)
+    Duration::from_nanos(get_time_ns())
+}
+
+/// Time of the last timer tick, in cycles of the time CSR.
+static LAST_TICK: AtomicUsize = AtomicUsize::new(0);
+
+/// Time of the last timer tick, which lags behind `get_time_duration` by at
+/// most one tick but saves a read of the time CSR.
+pub fn get_time_coarse() -> Duration {
+    Duration::from_nanos(cycles_to_ns(LAST_TICK.load(Ordering::Relaxed)))
 }
 
 pub unsafe fn set_next_timer_irq() {
-    let next_trigger: u64 = (time::read() + clock_freq() / INTERRUPTS_PER_SECOND) as u64;
+    let now = time::read();
+    LAST_TICK.fetch_max(now, Ordering::Relaxed);
+    let next_trigger: u64 = (now + TICK_CYCLES.load(Ordering::Relaxed)) as u64;
     sbi_rt::set_timer(next_trigger);
 }
 
 pub unsafe fn set_timer_irq(times: usize) {
-    let next_trigger: u64 = (time::read() + times * clock_freq() / INTERRUPTS_PER_SECOND) as u64;
+    let next_trigger: u64 = (time::read() + times * TICK_CYCLES.load(Ordering::Relaxed)) as u64;
     sbi_rt::set_timer(next_trigger);
 }
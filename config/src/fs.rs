@@ -1,6 +1,8 @@
 use super::mm::PAGE_SIZE;
 
-/// Max file descriptors counts
+/// Max file descriptors counts, the default limit of a process.
 pub const MAX_FDS: usize = 1024;
+/// Ceiling of the limit of file descriptors a process can be given.
+pub const NR_OPEN: usize = 1024 * 1024;
 
 pub const PIPE_BUF_LEN: usize = 16 * PAGE_SIZE;
@@ -33,8 +33,6 @@ pub const HART_START_ADDR: usize = RAM_START + KERNEL_START;
 
 register_mut_const!(pub DTB_ADDR, usize, 0);
 
-pub const USER_ELF_PRE_ALLOC_PAGE_CNT: usize = 0;
-
 pub const PAGE_SIZE: usize = 1 << PAGE_SIZE_BITS;
 pub const PAGE_MASK: usize = PAGE_SIZE - 1;
 pub const PAGE_SIZE_BITS: usize = 12;
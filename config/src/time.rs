@@ -1,6 +1,9 @@
 use core::time::Duration;
 
 pub const INTERRUPTS_PER_SECOND: usize = 100;
+/// Clock ticks per second of the times given to user space, e.g. by times(2)
+/// and in `/proc`, which is `sysconf(_SC_CLK_TCK)`.
+pub const USER_HZ: usize = 100;
 pub const NANOSECONDS_PER_SECOND: usize = 1_000_000_000;
 pub const TIME_SLICE_DUATION: Duration =
     Duration::new(0, (NANOSECONDS_PER_SECOND / INTERRUPTS_PER_SECOND) as u32);
@@ -1,4 +1,5 @@
 mod dw_mshc;
+mod ramdisk;
 mod vf2;
 mod virtio;
 
@@ -7,6 +8,7 @@ use alloc::sync::Arc;
 use device_core::DeviceType;
 use fdt::Fdt;
 use memory::{pte::PTEFlags, PhysAddr};
+pub use ramdisk::RamDisk;
 pub use virtio::*;
 use visionfive2_sd::Vf2SdDriver;
 
@@ -0,0 +1,133 @@
+use alloc::{string::ToString, sync::Arc, vec, vec::Vec};
+
+use config::board::BLOCK_SIZE;
+use device_core::{BlockDevice, DevId, Device, DeviceMajor, DeviceMeta, DeviceType};
+use sync::mutex::SpinNoIrqLock;
+
+/// A block device backed by memory, used to check the write ordering of file
+/// systems.
+///
+/// A crash point can be armed so that only a given number of further block
+/// writes reach the disk while the rest are dropped, as if power was lost right
+/// there. The surviving image can then be mounted again to check consistency.
+pub struct RamDisk {
+    meta: DeviceMeta,
+    inner: SpinNoIrqLock<RamDiskInner>,
+}
+
+struct RamDiskInner {
+    data: Vec<u8>,
+    /// Block writes still allowed before crashing, `None` if not armed.
+    writes_left: Option<usize>,
+    /// Block ids written, with `None` marking a barrier.
+    log: Vec<Option<usize>>,
+}
+
+impl RamDisk {
+    pub fn new(size: usize) -> Arc<Self> {
+        debug_assert!(size % BLOCK_SIZE == 0);
+        Arc::new(Self {
+            meta: DeviceMeta {
+                dev_id: DevId {
+                    major: DeviceMajor::Block,
+                    minor: 1,
+                },
+                name: "ramdisk".to_string(),
+                mmio_base: 0,
+                mmio_size: 0,
+                irq_no: None,
+                dtype: DeviceType::Block,
+            },
+            inner: SpinNoIrqLock::new(RamDiskInner {
+                data: vec![0; size],
+                writes_left: None,
+                log: Vec::new(),
+            }),
+        })
+    }
+
+    /// Crash after `writes` more block writes.
+    pub fn set_crash_point(&self, writes: usize) {
+        self.inner.lock().writes_left = Some(writes)
+    }
+
+    /// Power the disk on again, keeping whatever reached it before the crash.
+    pub fn recover(&self) {
+        let mut inner = self.inner.lock();
+        inner.writes_left = None;
+        inner.log.clear();
+    }
+
+    pub fn has_crashed(&self) -> bool {
+        self.inner.lock().writes_left == Some(0)
+    }
+
+    /// Take the block writes and barriers issued so far, in order.
+    pub fn take_log(&self) -> Vec<Option<usize>> {
+        core::mem::take(&mut self.inner.lock().log)
+    }
+}
+
+impl Device for RamDisk {
+    fn meta(&self) -> &DeviceMeta {
+        &self.meta
+    }
+
+    fn init(&self) {}
+
+    fn handle_irq(&self) {}
+
+    fn as_blk(self: Arc<Self>) -> Option<Arc<dyn BlockDevice>> {
+        Some(self)
+    }
+}
+
+impl BlockDevice for RamDisk {
+    fn size(&self) -> u64 {
+        self.inner.lock().data.len() as u64
+    }
+
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn buffer_head_cnts(&self) -> usize {
+        0
+    }
+
+    fn remove_buffer_page(&self, _block_id: usize) {}
+
+    fn base_read_blocks(&self, block_id: usize, buf: &mut [u8]) {
+        let start = block_id * BLOCK_SIZE;
+        buf.copy_from_slice(&self.inner.lock().data[start..start + buf.len()]);
+    }
+
+    fn base_write_blocks(&self, block_id: usize, buf: &[u8]) {
+        let mut inner = self.inner.lock();
+        for (i, block) in buf.chunks(BLOCK_SIZE).enumerate() {
+            match inner.writes_left {
+                Some(0) => return,
+                Some(ref mut left) => *left -= 1,
+                None => {}
+            }
+            let start = (block_id + i) * BLOCK_SIZE;
+            inner.data[start..start + block.len()].copy_from_slice(block);
+            inner.log.push(Some(block_id + i));
+        }
+    }
+
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        self.base_read_blocks(block_id, buf)
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.base_write_blocks(block_id, buf)
+    }
+
+    fn flush(&self) {
+        let mut inner = self.inner.lock();
+        if inner.writes_left != Some(0) {
+            inner.log.push(None);
+        }
+    }
+}
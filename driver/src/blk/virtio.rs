@@ -69,6 +69,16 @@ impl BlockDevice for VirtIoBlkDev {
     fn write_block(&self, block_id: usize, buf: &[u8]) {
         self.cache.lock().write_block(block_id, buf)
     }
+
+    fn flush(&self) {
+        self.cache.lock().flush();
+        // requests complete synchronously, so only the volatile write cache of
+        // the device is left to drain, which a device without
+        // `VIRTIO_BLK_F_FLUSH` does not have
+        if let Err(e) = self.device.lock().flush() {
+            log::warn!("[VirtIoBlkDev::flush] device not flushed: {e:?}");
+        }
+    }
 }
 
 impl VirtIoBlkDev {
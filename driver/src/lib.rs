@@ -24,8 +24,10 @@ use memory::PageTable;
 use sbi_print::SbiStdout;
 use spin::Once;
 use sync::mutex::{SpinLock, SpinNoIrqLock};
+use systype::{SysError, SysResult};
 use virtio_drivers::transport;
 
+pub use crate::blk::RamDisk;
 use crate::{
     net::loopback::LoopbackDev,
     serial::{Serial, UART0},
@@ -41,11 +43,27 @@ pub mod virtio;
 
 type Mutex<T> = SpinLock<T>;
 
-pub fn init() {
+/// The boot arguments in the DTB, which is mapped with the kernel page table.
+pub fn bootargs() -> Option<&'static str> {
+    let device_tree = unsafe { fdt::Fdt::from_ptr(K_SEG_DTB_BEG as _) }.ok()?;
+    device_tree.chosen().bootargs()
+}
+
+/// Set the clock frequency from the DTB, which timers and the drivers below
+/// depend on.
+fn init_clock() -> SysResult<()> {
     let device_tree = unsafe { fdt::Fdt::from_ptr(K_SEG_DTB_BEG as _).expect("Parse DTB failed") };
-    config::board::set_clock_freq(device_tree.cpus().next().unwrap().timebase_frequency());
+    let cpu = device_tree.cpus().next().ok_or(SysError::ENODEV)?;
+    config::board::set_clock_freq(cpu.timebase_frequency());
     log::info!("clock freq set to {} Hz", clock_freq());
+    arch::time::init_clock();
+    Ok(())
+}
+systype::core_initcall!(init_clock);
 
+/// Probe and init all devices, with the PLIC before their interrupts are
+/// enabled, and find the console and the block device for the file systems.
+fn init() -> SysResult<()> {
     init_device_manager();
     let manager = get_device_manager_mut();
     manager.probe();
@@ -60,7 +78,7 @@ pub fn init() {
         .into_iter()
         .map(|device| device.as_char().unwrap())
         .next()
-        .unwrap();
+        .ok_or(SysError::ENODEV)?;
     UART0.call_once(|| serial.clone());
 
     let blk = manager
@@ -68,10 +86,12 @@ pub fn init() {
         .into_iter()
         .map(|device| device.as_blk().unwrap())
         .next()
-        .unwrap();
+        .ok_or(SysError::ENODEV)?;
     BLOCK_DEVICE.call_once(|| blk.clone());
     manager.init_net();
+    Ok(())
 }
+systype::driver_initcall!(init);
 
 pub static BLOCK_DEVICE: Once<Arc<dyn BlockDevice>> = Once::new();
 
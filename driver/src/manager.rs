@@ -14,6 +14,7 @@ use device_core::{DevId, Device, DeviceMajor, DeviceMeta, DeviceType};
 use log::{info, warn};
 use memory::{pte::PTEFlags, PhysAddr};
 use net::init_network;
+use sync::mutex::SpinNoIrqLock;
 
 use crate::{
     blk::{probe_sdio_blk, probe_vf2_sd, probe_virtio_blk},
@@ -50,6 +51,10 @@ pub struct DeviceManager {
     /// (Arc<dyn Device>). This map is used to quickly locate the device
     /// responsible for handling a specific interrupt.
     pub irq_map: BTreeMap<usize, Arc<dyn Device>>,
+
+    /// Interrupt numbers of devices whose deferred half is still to run, see
+    /// `Device::handle_deferred`.
+    deferred: SpinNoIrqLock<Vec<usize>>,
 }
 
 impl DeviceManager {
@@ -63,6 +68,7 @@ impl DeviceManager {
             devices: BTreeMap::new(),
             net: None,
             irq_map: BTreeMap::new(),
+            deferred: SpinNoIrqLock::new(Vec::new()),
         }
     }
 
@@ -186,7 +192,9 @@ impl DeviceManager {
         unsafe { enable_external_interrupt() }
     }
 
-    pub fn handle_irq(&mut self) {
+    /// Handle the hard half of an external interrupt, and return whether the
+    /// deferred half of the device is to run, see [`Self::handle_deferred`].
+    pub fn handle_irq(&mut self) -> bool {
         unsafe { disable_interrupt() }
 
         log::trace!("Handling interrupt");
@@ -201,12 +209,28 @@ impl DeviceManager {
                 dev.handle_irq();
                 // Complete interrupt when done
                 self.plic().complete_irq(irq_number, self.irq_context());
-                return;
+                let mut deferred = self.deferred.lock();
+                if !deferred.contains(&irq_number) {
+                    deferred.push(irq_number);
+                }
+                return true;
             }
             warn!("Unknown interrupt: {}", irq_number);
         } else {
             warn!("No interrupt available");
         }
+        false
+    }
+
+    /// Run the deferred halves of the devices interrupted since the last
+    /// call, with interrupts enabled.
+    pub fn handle_deferred(&self) {
+        let deferred = core::mem::take(&mut *self.deferred.lock());
+        for irq_number in deferred {
+            if let Some(dev) = self.irq_map.get(&irq_number) {
+                dev.handle_deferred();
+            }
+        }
     }
 
     // Calculate the interrupt context from current hart id
@@ -61,14 +61,14 @@ impl NetDevice for LoopbackDev {
 
     fn transmit(&mut self, tx_buf: Box<dyn NetBufPtrOps>) -> DevResult {
         let data = tx_buf.packet().to_vec();
-        log::warn!("[NetDriverOps::transmit] now transmit {} bytes", data.len());
+        log::debug!("[NetDriverOps::transmit] now transmit {} bytes", data.len());
         self.queue.push_back(data);
         Ok(())
     }
 
     fn receive(&mut self) -> DevResult<Box<dyn NetBufPtrOps>> {
         if let Some(buf) = self.queue.pop_front() {
-            log::warn!(
+            log::debug!(
                 "[NetDriverOps::receive] now receive {} bytes from LoopbackDev.queue",
                 buf.len()
             );
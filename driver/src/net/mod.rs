@@ -33,7 +33,7 @@ pub fn probe_virtio_net(root: &Fdt) -> Option<DeviceMeta> {
             )
             .is_some()
             {
-                log::warn!("[probe_virtio_net] find a net device");
+                log::info!("[probe_virtio_net] find a net device");
                 net_meta = {
                     Some(DeviceMeta {
                         mmio_base: mmio_base_paddr.bits(),
@@ -55,7 +55,7 @@ pub fn probe_virtio_net(root: &Fdt) -> Option<DeviceMeta> {
         }
     }
     if net_meta.is_none() {
-        log::warn!("No virtio net device found");
+        log::info!("No virtio net device found");
     }
     net_meta
 }
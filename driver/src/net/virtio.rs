@@ -171,7 +171,7 @@ impl<T: Transport + 'static, const QS: usize> NetDevice for VirtIoNetDev<T, QS>
 
     fn receive(&mut self) -> DevResult<Box<dyn NetBufPtrOps>> {
         if let Some(token) = self.inner.poll_receive() {
-            log::warn!("[VirtioNetDev::receive] token {}", token);
+            log::debug!("[VirtioNetDev::receive] token {}", token);
             let mut rx_buf = self.rx_buffers[token as usize]
                 .take()
                 .ok_or(DevError::BadState)?;
@@ -49,6 +49,10 @@ pub struct SerialInner {
     read_buf: RingBuffer,
     /// Hold wakers of pollin tasks.
     pollin_queue: VecDeque<Waker>,
+    /// Called when input arrives while none is buffered.
+    input_hook: Option<Arc<dyn Fn() + Send + Sync>>,
+    /// Input arrived while none was buffered since the last deferred half.
+    input_arrived: bool,
 }
 
 unsafe impl Send for Serial {}
@@ -74,6 +78,8 @@ impl Serial {
             inner: SpinNoIrqLock::new(SerialInner {
                 read_buf: RingBuffer::new(UART_BUF_LEN),
                 pollin_queue: VecDeque::new(),
+                input_hook: None,
+                input_arrived: false,
             }),
         }
     }
@@ -82,6 +88,12 @@ impl Serial {
         unsafe { &mut *self.uart.get() }
     }
 
+    /// Set `hook` to be called from the deferred half of the interrupt every
+    /// time input arrives while none is buffered.
+    pub fn set_input_hook(&self, hook: Arc<dyn Fn() + Send + Sync>) {
+        self.with_mut_inner(|inner| inner.input_hook = Some(hook));
+    }
+
     with_methods!(inner: SerialInner);
 }
 
@@ -100,24 +112,39 @@ impl Device for Serial {
         unsafe { &mut *self.uart.get() }.as_mut().init()
     }
 
+    /// Drain the receive FIFO, which clears the interrupt.
     fn handle_irq(&self) {
         let uart = self.uart();
         self.with_mut_inner(|inner| {
+            let was_empty = inner.read_buf.is_empty();
             while uart.poll_in() {
                 let byte = uart.getc();
-                log::info!(
-                    "Serial interrupt handler got byte: {}, ascii: {byte}",
-                    core::str::from_utf8(&[byte]).unwrap()
-                );
+                log::trace!("Serial interrupt handler got byte: {byte:#x}");
                 if inner.read_buf.enqueue(byte).is_none() {
                     break;
                 }
             }
+            if was_empty && !inner.read_buf.is_empty() {
+                inner.input_arrived = true;
+            }
+        });
+    }
+
+    /// Wake a reader and run the input hook, i.e. the line discipline of the
+    /// tty on top.
+    fn handle_deferred(&self) {
+        let hook = self.with_mut_inner(|inner| {
             // Round Robin
             if let Some(waiting) = inner.pollin_queue.pop_front() {
                 waiting.wake();
             }
+            core::mem::take(&mut inner.input_arrived)
+                .then(|| inner.input_hook.clone())
+                .flatten()
         });
+        if let Some(hook) = hook {
+            hook();
+        }
     }
 
     fn as_char(self: Arc<Self>) -> Option<Arc<dyn CharDevice>> {
@@ -1,5 +1,7 @@
+use arch::time::get_time_duration;
 use config::{board, mm::HART_START_ADDR};
 use driver::println;
+use systype::initcall::{registered_initcalls, InitcallLevel};
 
 const BOOT_BANNER: &str = r#"
     ____  __                     _
@@ -35,6 +37,41 @@ pub fn clear_bss() {
     }
 }
 
+/// Run all initcalls level by level, see `systype::initcall`, and panic with
+/// the name of the first one failing.
+///
+/// With `initcall_debug` in the boot arguments, each initcall is printed with
+/// its duration. Early initcalls run before the DTB is mapped, so they are
+/// only logged.
+pub fn run_initcalls() {
+    let initcalls = registered_initcalls();
+    let mut debug = false;
+    for level in InitcallLevel::ALL {
+        if level == InitcallLevel::Core {
+            debug = driver::bootargs()
+                .is_some_and(|args| args.split_whitespace().any(|arg| arg == "initcall_debug"));
+        }
+        for initcall in initcalls.iter().filter(|initcall| initcall.level == level) {
+            if debug {
+                println!("[initcall] calling {} @ {level:?}", initcall.name);
+            }
+            let start = get_time_duration();
+            if let Err(err) = (initcall.func)() {
+                panic!("[initcall] {} @ {level:?} failed: {err:?}", initcall.name);
+            }
+            let duration = get_time_duration() - start;
+            if debug {
+                println!("[initcall] {} returned after {duration:?}", initcall.name);
+            } else {
+                log::info!(
+                    "[initcall] {} @ {level:?} done in {duration:?}",
+                    initcall.name
+                );
+            }
+        }
+    }
+}
+
 pub fn start_other_harts(hart_id: usize) {
     for i in 0..board::harts() {
         if i == hart_id {
@@ -1,19 +1,39 @@
 //! Impls of traits defined in other crates.
 
-use alloc::{fmt, string::ToString, sync::Arc};
+use alloc::{fmt, string::ToString, sync::Arc, vec::Vec};
+use core::{task::Waker, time::Duration};
 
-use config::mm::VIRT_RAM_OFFSET;
+use config::{
+    board::MAX_HARTS,
+    mm::{PAGE_SIZE, VIRT_RAM_OFFSET},
+};
 use driver::KernelPageTableIf;
+use executor::KernelExecutorIf;
 use log::Level;
 use logging::{ColorCode, LogIf};
 use memory::{KernelMappingIf, PageTable, PhysAddr, VirtAddr};
 use net::HasSignalIf;
-use vfs::{procfs::KernelProcIf, sys_root_dentry};
-use vfs_core::{Dentry, SysRootDentryIf};
+use sbi_rt::HartMask;
+use signal::{
+    siginfo::{SigDetails, SigInfo},
+    sigset::{Sig, SigSet},
+};
+use systype::SysResult;
+use vfs::{
+    devfs::tty::KernelTtyIf, procfs::KernelProcIf, signalfd::KernelSignalIf, sys_root_dentry,
+    sysfs::KernelHartIf,
+};
+use vfs_core::{
+    Dentry, File, FileOwner, IoBand, KernelFasyncIf, KernelIoPrioIf, PollEvents, SysRootDentryIf,
+};
 
 use crate::{
     mm::kernel_page_table_mut,
-    processor::hart::{current_task_ref, local_hart},
+    processor::{
+        hart::{current_task_ref, local_hart, HART_TIME_STATS},
+        hotplug, membarrier, shutdown, softirq,
+    },
+    task::{AcctEntry, Task, PROCESS_GROUP_MANAGER, TASK_MANAGER, TID_ALLOCATOR},
 };
 
 /// Print msg with color
@@ -85,6 +105,30 @@ impl HasSignalIf for HasSignalIfImpl {
     }
 }
 
+struct KernelExecutorIfImpl;
+
+#[crate_interface::impl_interface]
+impl KernelExecutorIf for KernelExecutorIfImpl {
+    fn kick_hart(hart_id: usize) {
+        if hart_id != local_hart().hart_id() && shutdown::is_hart_online(hart_id) {
+            sbi_rt::send_ipi(HartMask::from_mask_base(1 << hart_id, 0));
+        }
+    }
+}
+
+struct KernelIoPrioIfImpl;
+
+#[crate_interface::impl_interface]
+impl KernelIoPrioIf for KernelIoPrioIfImpl {
+    fn current_io_band() -> IoBand {
+        if !local_hart().has_task() {
+            return IoBand::BeLow;
+        }
+        let task = current_task_ref();
+        task.ioprio_ref().band(*task.nice_ref())
+    }
+}
+
 struct KernelMappingIfImpl;
 
 #[crate_interface::impl_interface]
@@ -109,6 +153,207 @@ impl KernelProcIf for KernelProcIfImpl {
     fn exe() -> alloc::string::String {
         current_task_ref().elf().dentry().path()
     }
+
+    fn file(fd: usize) -> Option<Arc<dyn File>> {
+        current_task_ref()
+            .with_fd_table(|table| table.get_file(fd))
+            .ok()
+    }
+
+    fn has_task(pid: usize) -> bool {
+        TASK_MANAGER.get(pid).is_some()
+    }
+
+    fn maps(pid: Option<usize>) -> Option<alloc::string::String> {
+        let task = match pid {
+            Some(pid) => TASK_MANAGER.get(pid)?,
+            None => current_task_ref().clone(),
+        };
+        let exe = task.elf().dentry().path();
+        Some(task.with_memory_space(|m| m.maps(&exe)))
+    }
+
+    fn mem_pages(pid: Option<usize>) -> Option<[usize; 6]> {
+        let task = match pid {
+            Some(pid) => TASK_MANAGER.get(pid)?,
+            None => current_task_ref().clone(),
+        };
+        Some(task.with_memory_space(|m| {
+            let rss = m.rss();
+            let (size, text, data) = m.vm_pages();
+            [size, rss.anon, rss.file, rss.shmem, text, data]
+        }))
+    }
+
+    fn cpu_times() -> Vec<(usize, [Duration; 3])> {
+        (0..MAX_HARTS)
+            .filter(|&hart_id| shutdown::is_hart_online(hart_id))
+            .map(|hart_id| {
+                let (user, system, idle) = HART_TIME_STATS[hart_id].times();
+                (hart_id, [user, system, idle])
+            })
+            .collect()
+    }
+
+    fn membarrier_acks() -> Vec<(usize, usize)> {
+        (0..MAX_HARTS)
+            .filter(|&hart_id| shutdown::is_hart_online(hart_id))
+            .map(|hart_id| (hart_id, membarrier::membarrier_acks(hart_id)))
+            .collect()
+    }
+
+    fn softirqs() -> (Vec<usize>, Vec<(&'static str, Vec<u64>)>) {
+        let hart_ids: Vec<_> = (0..MAX_HARTS)
+            .filter(|&hart_id| shutdown::is_hart_online(hart_id))
+            .collect();
+        let rows = softirq::softirq_stats(&hart_ids);
+        (hart_ids, rows)
+    }
+
+    fn pgid_accts() -> Vec<(usize, usize, usize, [Duration; 2], usize)> {
+        PROCESS_GROUP_MANAGER
+            .accts()
+            .0
+            .iter()
+            .map(acct_entry)
+            .collect()
+    }
+
+    fn sid_accts() -> Vec<(usize, usize, usize, [Duration; 2], usize)> {
+        PROCESS_GROUP_MANAGER
+            .accts()
+            .1
+            .iter()
+            .map(acct_entry)
+            .collect()
+    }
+
+    fn pid_max() -> usize {
+        TID_ALLOCATOR.lock().pid_max()
+    }
+
+    fn set_pid_max(pid_max: usize) -> SysResult<()> {
+        TID_ALLOCATOR.lock().set_pid_max(pid_max)
+    }
+}
+
+fn acct_entry(entry: &AcctEntry) -> (usize, usize, usize, [Duration; 2], usize) {
+    let acct = &entry.acct;
+    (
+        entry.id,
+        entry.sid,
+        entry.members,
+        [acct.utime, acct.stime],
+        acct.peak_rss * PAGE_SIZE / 1024,
+    )
+}
+
+struct KernelSignalIfImpl;
+
+#[crate_interface::impl_interface]
+impl KernelSignalIf for KernelSignalIfImpl {
+    fn take_signal(mask: SigSet, waker: Option<&Waker>) -> Option<SigInfo> {
+        current_task_ref().with_mut_sig_pending(|pending| {
+            let si = pending.dequeue_expect(mask);
+            if let (None, Some(waker)) = (si, waker) {
+                pending.wait(waker);
+            }
+            si
+        })
+    }
+
+    fn has_signal(mask: SigSet, waker: &Waker) -> bool {
+        current_task_ref().with_mut_sig_pending(|pending| {
+            let has = pending.has_expect_signals(mask);
+            if !has {
+                pending.wait(waker);
+            }
+            has
+        })
+    }
+}
+
+struct KernelTtyIfImpl;
+
+#[crate_interface::impl_interface]
+impl KernelTtyIf for KernelTtyIfImpl {
+    fn kill_pgrp(pgid: usize, sig: usize) {
+        let Some(group) = PROCESS_GROUP_MANAGER.get_group(pgid) else {
+            return;
+        };
+        for task in group.into_iter().filter_map(|t| t.upgrade()) {
+            task.receive_siginfo(
+                SigInfo {
+                    sig: Sig::from_i32(sig as i32),
+                    code: SigInfo::KERNEL,
+                    details: SigDetails::None,
+                },
+                false,
+            );
+        }
+    }
+
+    fn pgid() -> usize {
+        current_task_ref().pgid()
+    }
+}
+
+struct KernelFasyncIfImpl;
+
+#[crate_interface::impl_interface]
+impl KernelFasyncIf for KernelFasyncIfImpl {
+    fn send_sigio(owner: FileOwner, fd: usize, band: PollEvents) {
+        // NOTE: details come along only with the signal set by `F_SETSIG`, as on
+        // Linux, where plain `SIGIO` is not queued
+        let si = if owner.sig == 0 {
+            SigInfo {
+                sig: Sig::SIGIO,
+                code: SigInfo::KERNEL,
+                details: SigDetails::None,
+            }
+        } else {
+            SigInfo {
+                sig: Sig::from_i32(owner.sig as i32),
+                code: if band.contains(PollEvents::IN) {
+                    SigInfo::POLL_IN
+                } else if band.contains(PollEvents::OUT) {
+                    SigInfo::POLL_OUT
+                } else {
+                    SigInfo::POLL_HUP
+                },
+                details: SigDetails::Poll {
+                    band: band.bits() as usize,
+                    fd,
+                },
+            }
+        };
+        if owner.process.is_some() {
+            if let Some(task) = Task::sigio_owner(&owner) {
+                task.receive_siginfo(si, false);
+            }
+        } else if let Some(group) = PROCESS_GROUP_MANAGER.get_group(-owner.pid as usize) {
+            for task in group.into_iter().filter_map(|t| t.upgrade()) {
+                task.receive_siginfo(si, false);
+            }
+        }
+    }
+}
+
+struct KernelHartIfImpl;
+
+#[crate_interface::impl_interface]
+impl KernelHartIf for KernelHartIfImpl {
+    fn is_hart_online(hart_id: usize) -> bool {
+        hotplug::is_hart_active(hart_id)
+    }
+
+    fn set_hart_online(hart_id: usize, online: bool) -> SysResult<()> {
+        if online {
+            hotplug::online_hart(hart_id)
+        } else {
+            hotplug::offline_hart(hart_id)
+        }
+    }
 }
 
 struct SysRootDentryIfImpl;
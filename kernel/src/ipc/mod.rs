@@ -1,13 +1,32 @@
 pub mod futex;
 pub mod shm;
+
+/// `struct ipc64_perm`, the ownership and permissions of a System V IPC
+/// object.
 #[repr(C)]
 #[derive(Default, Clone, Copy, Debug)]
 pub struct IpcPerm {
-    key: i32,
-    uid: u32,
-    gid: u32,
-    cuid: u32,
-    cgid: u32,
-    mode: u16,
-    seq: u16,
+    pub key: i32,
+    pub uid: u32,
+    pub gid: u32,
+    pub cuid: u32,
+    pub cgid: u32,
+    pub mode: u32,
+    pub seq: u16,
+    pad: u16,
+    unused: [usize; 2],
+}
+
+impl IpcPerm {
+    pub fn new(key: i32, uid: u32, gid: u32, mode: u32) -> Self {
+        Self {
+            key,
+            uid,
+            gid,
+            cuid: uid,
+            cgid: gid,
+            mode,
+            ..Default::default()
+        }
+    }
 }
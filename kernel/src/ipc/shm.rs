@@ -1,4 +1,4 @@
-use alloc::{sync::Weak, vec::Vec};
+use alloc::{sync::Arc, vec::Vec};
 
 use arch::time::get_time_sec;
 use config::mm::PAGE_SIZE;
@@ -7,20 +7,34 @@ use page::Page;
 use recycle_allocator::RecycleAllocator;
 use spin::Lazy;
 use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SysResult};
 
 use super::IpcPerm;
 
+/// The key of a segment that can not be looked up by key, which shmget(2)
+/// always creates anew.
+pub const IPC_PRIVATE: i32 = 0;
+
+/// Largest size of a segment, in bytes.
+pub const SHMMAX: usize = 1 << 30;
+
+/// The segment is marked for removal, set in `IpcPerm::mode`.
+pub const SHM_DEST: u32 = 0o1000;
+
 pub struct SharedMemory {
     pub shmid_ds: ShmIdDs,
-    pub pages: Vec<Weak<Page>>,
+    /// The frames of the segment, which live as long as it does, attached or
+    /// not.
+    pub pages: Vec<Arc<Page>>,
 }
 
+/// `struct shmid64_ds`.
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct ShmIdDs {
     // Ownership and permissions
     pub shm_perm: IpcPerm,
-    // Size of segment (bytes). In our system, this must be aligned
+    // Size of segment (bytes) as requested, its pages are rounded up
     pub shm_segsz: usize,
     // Last attach time
     pub shm_atime: usize,
@@ -29,24 +43,26 @@ pub struct ShmIdDs {
     // Creation time/time of last modification via shmctl()
     pub shm_ctime: usize,
     // PID of creator
-    pub shm_cpid: usize,
+    pub shm_cpid: i32,
     // PID of last shmat(2)/shmdt(2)
-    pub shm_lpid: usize,
+    pub shm_lpid: i32,
     // No. of current attaches
     pub shm_nattch: usize,
+    unused: [usize; 2],
 }
 
 impl ShmIdDs {
-    pub fn new(sz: usize, cpid: usize) -> Self {
+    pub fn new(perm: IpcPerm, sz: usize, cpid: usize) -> Self {
         Self {
-            shm_perm: IpcPerm::default(),
+            shm_perm: perm,
             shm_segsz: sz,
             shm_atime: 0,
             shm_dtime: 0,
             shm_ctime: get_time_sec(),
-            shm_cpid: cpid,
+            shm_cpid: cpid as i32,
             shm_lpid: 0,
             shm_nattch: 0,
+            unused: [0; 2],
         }
     }
 
@@ -54,40 +70,60 @@ impl ShmIdDs {
         // shm_atime is set to the current time.
         self.shm_atime = get_time_sec();
         // shm_lpid is set to the process-ID of the calling process.
-        self.shm_lpid = lpid;
+        self.shm_lpid = lpid as i32;
         // shm_nattch is incremented by one.
         self.shm_nattch += 1;
     }
 
     /// return whether the SHARED_MEMORY_MANAGER should remove the SharedMemory
-    /// which self ShmIdDs belongs to;
+    /// which self ShmIdDs belongs to, i.e. the last attach is gone from a
+    /// segment marked for removal;
     pub fn detach(&mut self, lpid: usize) -> bool {
         // shm_dtime is set to the current time.
         self.shm_dtime = get_time_sec();
         // shm_lpid is set to the process-ID of the calling process.
-        self.shm_lpid = lpid;
+        self.shm_lpid = lpid as i32;
         // shm_nattch is decremented by one.
+        debug_assert!(self.shm_nattch > 0);
         self.shm_nattch -= 1;
-        debug_assert!(self.shm_nattch >= 0);
-        if self.shm_nattch == 0 {
-            return true;
-        }
-        false
+        self.shm_nattch == 0 && self.is_dest()
+    }
+
+    pub fn is_dest(&self) -> bool {
+        self.shm_perm.mode & SHM_DEST != 0
     }
 }
 
 impl SharedMemory {
-    pub fn new(sz: usize, pid: usize) -> Self {
+    /// Create a segment of `sz` bytes with zeroed pages.
+    pub fn new(perm: IpcPerm, sz: usize, pid: usize) -> Self {
+        let pages = (0..sz.div_ceil(PAGE_SIZE))
+            .map(|_| {
+                let page = Page::new();
+                page.fill_zero();
+                page
+            })
+            .collect();
         Self {
-            shmid_ds: ShmIdDs::new(sz, pid),
-            pages: Vec::with_capacity(sz / PAGE_SIZE + 1),
+            shmid_ds: ShmIdDs::new(perm, sz, pid),
+            pages,
         }
     }
+
     pub fn size(&self) -> usize {
         self.shmid_ds.shm_segsz
     }
+
+    /// Size of the segment mapped, in whole pages.
+    pub fn mapped_size(&self) -> usize {
+        self.pages.len() * PAGE_SIZE
+    }
 }
 
+/// All the segments, by their shmid.
+///
+/// A segment outlives its attaches until it is removed by `IPC_RMID`, and
+/// then it is dropped with the last detach, see `SharedMemoryManager::detach`.
 pub struct SharedMemoryManager(pub SpinNoIrqLock<HashMap<usize, SharedMemory>>);
 
 impl SharedMemoryManager {
@@ -95,6 +131,41 @@ impl SharedMemoryManager {
         Self(SpinNoIrqLock::new(HashMap::new()))
     }
 
+    /// Find the segment of `key`, which must hold at least `size` bytes, or
+    /// create it with `create` if there is none. Fails with `EEXIST` if
+    /// `excl` and it exists, and with `ENOENT` if it does not and no `create`
+    /// is given.
+    pub fn get_or_create(
+        &self,
+        key: i32,
+        size: usize,
+        excl: bool,
+        create: Option<impl FnOnce() -> SharedMemory>,
+    ) -> SysResult<usize> {
+        let mut shm_manager = self.0.lock();
+        if key != IPC_PRIVATE {
+            if let Some((&shm_id, shm)) = shm_manager
+                .iter()
+                .find(|(_, shm)| shm.shmid_ds.shm_perm.key == key)
+            {
+                if excl && create.is_some() {
+                    return Err(SysError::EEXIST);
+                }
+                if shm.size() < size {
+                    return Err(SysError::EINVAL);
+                }
+                return Ok(shm_id);
+            }
+        }
+        let create = create.ok_or(SysError::ENOENT)?;
+        if size == 0 || size > SHMMAX {
+            return Err(SysError::EINVAL);
+        }
+        let shm_id = SHARED_MEMORY_KEY_ALLOCATOR.lock().alloc();
+        shm_manager.insert(shm_id, create());
+        Ok(shm_id)
+    }
+
     pub fn attach(&self, shm_id: usize, lpid: usize) {
         let mut shm_manager = self.0.lock();
         let shm = shm_manager.get_mut(&shm_id).unwrap();
@@ -109,6 +180,20 @@ impl SharedMemoryManager {
             SHARED_MEMORY_KEY_ALLOCATOR.lock().dealloc(shm_id);
         }
     }
+
+    /// Mark the segment for removal, which drops it now if it is not
+    /// attached. Its key is free for a new segment from now on.
+    pub fn remove(&self, shm_id: usize) -> SysResult<()> {
+        let mut shm_manager = self.0.lock();
+        let shm = shm_manager.get_mut(&shm_id).ok_or(SysError::EINVAL)?;
+        shm.shmid_ds.shm_perm.mode |= SHM_DEST;
+        shm.shmid_ds.shm_perm.key = IPC_PRIVATE;
+        if shm.shmid_ds.shm_nattch == 0 {
+            shm_manager.remove(&shm_id);
+            SHARED_MEMORY_KEY_ALLOCATOR.lock().dealloc(shm_id);
+        }
+        Ok(())
+    }
 }
 
 pub static SHARED_MEMORY_MANAGER: Lazy<SharedMemoryManager> = Lazy::new(SharedMemoryManager::init);
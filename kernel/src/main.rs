@@ -35,7 +35,7 @@ use core::{
 
 use ::net::poll_interfaces;
 
-use crate::processor::hart;
+use crate::processor::{hart, hotplug, shutdown};
 
 extern crate alloc;
 
@@ -48,6 +48,9 @@ extern crate driver;
 #[macro_use]
 extern crate logging;
 
+// Registers itself as a file system type once linked.
+extern crate romfs;
+
 global_asm!(include_str!("trampoline.asm"));
 
 static FIRST_HART: AtomicBool = AtomicBool::new(true);
@@ -67,14 +70,7 @@ fn rust_main(hart_id: usize, dtb_addr: usize) {
         println!("[kernel] ---------- main hart {hart_id} started ---------- ");
         config::mm::set_dtb_addr(dtb_addr);
 
-        mm::init();
-        trap::init();
-        driver::init();
-        vfs::init();
-
-        task::spawn_kernel_task(async move {
-            task::spawn_init_proc();
-        });
+        boot::run_initcalls();
 
         // utils::spawn_timer_tasks_ms(
         //     || {
@@ -96,20 +92,25 @@ fn rust_main(hart_id: usize, dtb_addr: usize) {
 
     unsafe {
         arch::interrupts::enable_timer_interrupt();
+        arch::interrupts::enable_software_interrupt();
         arch::time::set_next_timer_irq()
     };
+    shutdown::mark_local_hart_online();
 
     println!("[kernel] ---------- hart {hart_id} start to fetch task... ---------- ");
-    let mut try_count = 0usize;
     loop {
-        let tasks = executor::run_until_idle();
-        if tasks == 0 {
-            try_count += 1;
-        } else {
-            try_count = 0;
+        // NOTE: run tasks one by one instead of `executor::run_until_idle` so that a
+        // quiesce request is observed right after the current task poll, and so is
+        // an offline request.
+        while executor::has_task(hart_id)
+            && !shutdown::quiesce_requested()
+            && !hotplug::offline_requested()
+        {
+            executor::run_one(hart_id);
         }
-        if try_count >= 0x10000000 {
-            panic!("no tasks")
+        if shutdown::quiesce_requested() || hotplug::offline_requested() {
+            shutdown::quiesce_local_hart();
         }
+        hart::idle();
     }
 }
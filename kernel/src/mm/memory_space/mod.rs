@@ -1,6 +1,8 @@
 use alloc::{
+    collections::BTreeSet,
+    format,
     string::{String, ToString},
-    sync::{Arc, Weak},
+    sync::Arc,
     vec,
     vec::Vec,
 };
@@ -8,39 +10,115 @@ use core::{
     cell::SyncUnsafeCell,
     cmp,
     ops::{Range, RangeBounds},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
 };
 
-use arch::memory::sfence_vma_vaddr;
-use async_utils::block_on;
+use arch::{
+    memory::{sfence_vma_all, sfence_vma_vaddr},
+    time::get_time_duration,
+};
+use async_utils::{block_on, yield_now};
 use config::{
     mm::{
         is_aligned_to_page, round_down_to_page, DL_INTERP_OFFSET, MMAP_PRE_ALLOC_PAGES, PAGE_SIZE,
-        USER_ELF_PRE_ALLOC_PAGE_CNT, U_SEG_FILE_BEG, U_SEG_FILE_END, U_SEG_HEAP_BEG,
-        U_SEG_HEAP_END, U_SEG_SHARE_BEG, U_SEG_SHARE_END, U_SEG_STACK_BEG, U_SEG_STACK_END,
+        U_SEG_FILE_BEG, U_SEG_FILE_END, U_SEG_HEAP_BEG, U_SEG_HEAP_END, U_SEG_SHARE_BEG,
+        U_SEG_SHARE_END, U_SEG_STACK_BEG, U_SEG_STACK_END,
     },
     process::USER_STACK_PRE_ALLOC_SIZE,
 };
-use memory::{pte::PTEFlags, PageTable, PhysAddr, VirtAddr, VirtPageNum};
+use memory::{
+    pte::PTEFlags,
+    vmstat::{vm_stat_add, VmStat},
+    PageTable, PhysAddr, PhysPageNum, VirtAddr, VirtPageNum,
+};
 use page::Page;
 use range_map::RangeMap;
+use sync::mutex::SpinNoIrqLock;
 use systype::{SysError, SysResult};
-use vfs_core::{Dentry, File};
+use vfs_core::{Dentry, File, Inode};
 use xmas_elf::ElfFile;
 
 use self::vm_area::VmArea;
 use super::{kernel_page_table, PageFaultAccessType};
 use crate::{
-    mm::memory_space::vm_area::{MapPerm, VmAreaType},
-    processor::{env::SumGuard, hart::current_task_ref},
+    mm::memory_space::vm_area::{MapPerm, RssStat, VmAreaType},
+    processor::{
+        env::SumGuard,
+        hart::{current_task_ref, local_hart},
+    },
     syscall::MmapFlags,
     task::{
         aux::{generate_early_auxv, AuxHeader, AT_BASE, AT_NULL, AT_PHDR, AT_RANDOM},
-        Task,
+        Task, TASK_MANAGER,
     },
 };
 
 pub mod vm_area;
 
+/// Drop the pages of `inode` that were truncated from every address space
+/// mapping it, so that later accesses fault and see the new end of file.
+pub fn sync_file_mappings(inode: &Arc<dyn Inode>) {
+    let mut visited = BTreeSet::new();
+    for task in TASK_MANAGER.tasks() {
+        // threads of a process share one address space
+        if !visited.insert(task.raw_mm_pointer()) {
+            continue;
+        }
+        task.with_mut_memory_space(|m| m.sync_backed_file(inode));
+    }
+}
+
+/// Read the elf header and the program headers at the start of `file`, which
+/// is all that is needed to map it, see `MemorySpace::map_elf`.
+pub async fn read_elf_headers(file: &Arc<dyn File>) -> SysResult<Vec<u8>> {
+    const ELF_MAGIC: [u8; 4] = [0x7f, 0x45, 0x4c, 0x46];
+
+    let mut data = vec![0; PAGE_SIZE.min(file.size())];
+    file.read_at(0, &mut data).await?;
+    let ph_end = {
+        let elf = ElfFile::new(&data).map_err(|_| SysError::ENOEXEC)?;
+        if elf.header.pt1.magic != ELF_MAGIC {
+            return Err(SysError::ENOEXEC);
+        }
+        let pt2 = &elf.header.pt2;
+        pt2.ph_offset() as usize + pt2.ph_count() as usize * pt2.ph_entry_size() as usize
+    };
+    if ph_end > file.size() {
+        return Err(SysError::ENOEXEC);
+    }
+    if ph_end > data.len() {
+        data.resize(ph_end, 0);
+        file.read_at(0, &mut data).await?;
+    }
+    Ok(data)
+}
+
+/// Denies writing the executable mapped by a memory space, from the time it is
+/// executed until the memory space is replaced or the process exits, like
+/// linux. A fork denies it once more.
+pub struct DenyWrite(Arc<dyn Inode>);
+
+impl DenyWrite {
+    pub fn new(inode: Arc<dyn Inode>) -> SysResult<Self> {
+        inode.deny_write_access()?;
+        Ok(Self(inode))
+    }
+}
+
+impl Clone for DenyWrite {
+    fn clone(&self) -> Self {
+        // NOTE: never fails, as writing is denied already
+        Self::new(self.0.clone()).unwrap()
+    }
+}
+
+impl Drop for DenyWrite {
+    fn drop(&mut self) {
+        self.0.allow_write_access();
+    }
+}
+
 /// Virtual memory space for user.
 pub struct MemorySpace {
     // NOTE: The reason why `page_table` and `areas` are `SyncUnsafeCell` is because they both
@@ -50,6 +128,14 @@ pub struct MemorySpace {
     /// Map of `VmArea`s in this memory space.
     /// NOTE: stores range that is lazy allocated
     areas: SyncUnsafeCell<RangeMap<VirtAddr, VmArea>>,
+    /// Whether `MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED` was issued.
+    membarrier_registered: bool,
+    /// Harts that switched to this page table and may cache its entries, one
+    /// bit each. A bit is cleared when the hart leaves a user task, a stale
+    /// bit only costs a needless shootdown.
+    cpumask: AtomicUsize,
+    /// Executable mapped, which can not be written meanwhile.
+    exe: Option<DenyWrite>,
 }
 
 impl MemorySpace {
@@ -58,6 +144,9 @@ impl MemorySpace {
         Self {
             page_table: SyncUnsafeCell::new(PageTable::new()),
             areas: SyncUnsafeCell::new(RangeMap::new()),
+            membarrier_registered: false,
+            cpumask: AtomicUsize::new(0),
+            exe: None,
         }
     }
 
@@ -66,7 +155,71 @@ impl MemorySpace {
         Self {
             page_table: SyncUnsafeCell::new(PageTable::from_kernel(kernel_page_table())),
             areas: SyncUnsafeCell::new(RangeMap::new()),
+            membarrier_registered: false,
+            cpumask: AtomicUsize::new(0),
+            exe: None,
+        }
+    }
+
+    /// Harts that may cache entries of this page table, see `cpumask`.
+    pub fn cpumask(&self) -> usize {
+        self.cpumask.load(Ordering::Acquire)
+    }
+
+    /// Called when `hart_id` switches away from this page table, which
+    /// flushes its TLB.
+    pub fn leave_hart(&self, hart_id: usize) {
+        self.cpumask.fetch_and(!(1 << hart_id), Ordering::Release);
+    }
+
+    pub fn membarrier_registered(&self) -> bool {
+        self.membarrier_registered
+    }
+
+    pub fn register_membarrier(&mut self) {
+        self.membarrier_registered = true;
+    }
+
+    pub fn set_exe(&mut self, exe: DenyWrite) {
+        self.exe = Some(exe);
+    }
+
+    /// Allow the executable to be written again.
+    pub fn release_exe(&mut self) {
+        self.exe = None;
+    }
+
+    /// Pages present in the areas by kind, shared ones included.
+    pub fn rss(&self) -> RssStat {
+        let mut rss = RssStat::default();
+        for (_, vma) in self.areas().iter() {
+            rss += vma.rss();
+        }
+        rss
+    }
+
+    /// Number of pages present in the areas, shared ones included.
+    pub fn resident_pages(&self) -> usize {
+        self.rss().total()
+    }
+
+    /// Pages of all areas, of executable areas of the elf, and of private
+    /// writable areas, i.e. the size, text and data of `/proc/[pid]/statm`.
+    pub fn vm_pages(&self) -> (usize, usize, usize) {
+        let (mut size, mut text, mut data) = (0, 0, 0);
+        for (_, vma) in self.areas().iter() {
+            let pages = vma.end_vpn() - vma.start_vpn();
+            size += pages;
+            if vma.vma_type == VmAreaType::Elf && vma.perm().contains(MapPerm::X) {
+                text += pages;
+            } else if vma.perm().contains(MapPerm::W)
+                && vma.vma_type != VmAreaType::Shm
+                && !vma.mmap_flags.contains(MmapFlags::MAP_SHARED)
+            {
+                data += pages;
+            }
         }
+        (size, text, data)
     }
 
     pub fn areas(&self) -> &RangeMap<VirtAddr, VmArea> {
@@ -85,7 +238,14 @@ impl MemorySpace {
         unsafe { &mut *self.page_table.get() }
     }
 
-    /// Map the sections in the elf.
+    /// Map the loadable segments of the elf at `offset`.
+    ///
+    /// The file data of a segment is mapped privately from the page cache on
+    /// fault, like a private file mapping, so that an executable already
+    /// cached is mapped without any I/O, and its pages are only copied when
+    /// written. Only the page where the file data of a segment with bss ends
+    /// is copied, since the rest of it must read as zero, and the pages of the
+    /// bss after it are zero filled on fault.
     ///
     /// Return the max end vpn and the first section's va.
     pub fn map_elf(
@@ -96,6 +256,7 @@ impl MemorySpace {
     ) -> (VirtPageNum, VirtAddr) {
         let elf_header = elf.header;
         let ph_count = elf_header.pt2.ph_count();
+        let cached = elf_file.inode().page_cache().is_some();
 
         let mut max_end_vpn = offset.floor();
         let mut header_va = 0;
@@ -124,68 +285,67 @@ impl MemorySpace {
             if ph_flags.is_execute() {
                 map_perm |= MapPerm::X;
             }
-            let mut vm_area = VmArea::new(start_va..end_va, map_perm, VmAreaType::Elf);
-
-            log::debug!("[map_elf] [{start_va:#x}, {end_va:#x}], map_perm: {map_perm:?} start...",);
-
-            max_end_vpn = vm_area.end_vpn();
-
-            let map_offset = start_va - start_va.round_down();
+            max_end_vpn = end_va.ceil();
 
             log::debug!(
-                "[map_elf] ph offset {:#x}, file size {:#x}, mem size {:#x}",
+                "[map_elf] [{start_va:#x}, {end_va:#x}], map_perm: {map_perm:?}, ph offset {:#x}, file size {:#x}, mem size {:#x}",
                 ph.offset(),
                 ph.file_size(),
                 ph.mem_size()
             );
 
-            if ph.file_size() == ph.mem_size() && is_aligned_to_page(ph.offset() as usize) {
-                assert!(!map_perm.contains(MapPerm::W));
-                // NOTE: only add cow flag in elf page newly mapped.
-                // FIXME: mprotect is not checked yet
-                // WARN: the underlying elf file page cache may be edited, may cause unknown
-                // behavior
-                let mut pre_alloc_page_cnt = 0;
-                for vpn in vm_area.range_vpn() {
-                    let start_offset = ph.offset() as usize;
-                    let offset = start_offset + (vpn - vm_area.start_vpn()) * PAGE_SIZE;
-                    let offset_aligned = round_down_to_page(offset);
-                    if let Some(page) =
-                        block_on(async { elf_file.get_page_at(offset_aligned).await }).unwrap()
-                    {
-                        if pre_alloc_page_cnt < USER_ELF_PRE_ALLOC_PAGE_CNT {
-                            let new_page = Page::new();
-                            // WARN: area outer than region may should be set to zero
-                            new_page.copy_from_slice(page.bytes_array());
-                            self.page_table_mut()
-                                .map(vpn, new_page.ppn(), map_perm.into());
-                            vm_area.pages.insert(vpn, new_page);
-                            unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
-                        } else {
-                            let (pte_flags, ppn) = {
-                                let mut new_flags: PTEFlags = map_perm.into();
-                                new_flags |= PTEFlags::COW;
-                                new_flags.remove(PTEFlags::W);
-                                (new_flags, page.ppn())
-                            };
-                            self.page_table_mut().map(vpn, ppn, pte_flags);
-                            vm_area.pages.insert(vpn, page);
-                            unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
-                        }
-                        pre_alloc_page_cnt += 1;
-                    } else {
-                        break;
-                    }
-                }
-                self.push_vma_lazily(vm_area);
-                log::info!("[map_elf] [{start_va:#x}, {end_va:#x}], map_perm: {map_perm:?}",);
+            let file_offset = ph.offset() as usize;
+            let file_size = ph.file_size() as usize;
+            let file_end_va: VirtAddr = (start_va.0 + file_size).into();
+
+            if !cached || start_va.page_offset() != file_offset % PAGE_SIZE {
+                // NOTE: a file without page cache, or a segment not placed like the pages
+                // of the file, is copied as a whole
+                let mut data = vec![0; file_size];
+                block_on(async { elf_file.read_at(file_offset, &mut data).await }).unwrap();
+                let vm_area = VmArea::new(start_va..end_va, map_perm, VmAreaType::Elf);
+                self.push_vma_with_data(vm_area, start_va.page_offset(), &data);
+                continue;
+            }
+
+            // the whole last page is file data if there is no bss
+            let file_pages_end = if ph.mem_size() == ph.file_size() {
+                end_va.round_up()
             } else {
-                self.push_vma_with_data(
-                    vm_area,
-                    map_offset,
-                    &elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize],
+                file_end_va.round_down()
+            };
+            if start_va.round_down() < file_pages_end {
+                let mut vm_area = VmArea::new_mmap(
+                    start_va..file_pages_end,
+                    map_perm,
+                    MmapFlags::MAP_PRIVATE,
+                    Some(elf_file.clone()),
+                    round_down_to_page(file_offset),
                 );
+                vm_area.vma_type = VmAreaType::Elf;
+                self.push_vma_lazily(vm_area);
+            }
+
+            let bss_start = file_pages_end.max(start_va.round_down());
+            if bss_start < end_va {
+                let mut vm_area = VmArea::new(bss_start..end_va, map_perm, VmAreaType::Elf);
+                let data_start = bss_start.max(start_va);
+                if data_start < file_end_va {
+                    let mut data = vec![0; file_end_va.0 - data_start.0];
+                    let data_offset = file_offset + (data_start.0 - start_va.0);
+                    block_on(async { elf_file.read_at(data_offset, &mut data).await }).unwrap();
+                    let page_end: VirtAddr = (bss_start.0 + PAGE_SIZE).into();
+                    vm_area.map_range(self.page_table_mut(), bss_start..page_end);
+                    vm_area.fill_zero();
+                    vm_area.copy_data_with_offset(
+                        self.page_table_mut(),
+                        data_start.page_offset(),
+                        &data,
+                    );
+                }
+                self.push_vma_lazily(vm_area);
             }
+            log::info!("[map_elf] [{start_va:#x}, {end_va:#x}], map_perm: {map_perm:?}",);
         }
 
         (max_end_vpn, header_va.into())
@@ -196,12 +356,9 @@ impl MemorySpace {
         elf_file: Arc<dyn File>,
         elf_data: &[u8],
     ) -> (usize, Vec<AuxHeader>) {
-        const ELF_MAGIC: [u8; 4] = [0x7f, 0x45, 0x4c, 0x46];
-
         // map program headers of elf, with U flag
         let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
         let elf_header = elf.header;
-        assert_eq!(elf_header.pt1.magic, ELF_MAGIC, "invalid elf!");
         let entry = elf_header.pt2.entry_point() as usize;
         let ph_entry_size = elf_header.pt2.ph_entry_size() as usize;
         let ph_count = elf_header.pt2.ph_count() as usize;
@@ -225,23 +382,25 @@ impl MemorySpace {
     ///
     /// Return the interpreter's entry point(at the base of DL_INTERP_OFFSET) if
     /// so.
-    pub fn load_dl_interp_if_needed(&mut self, elf: &ElfFile) -> Option<usize> {
+    pub fn load_dl_interp_if_needed(
+        &mut self,
+        elf_file: &Arc<dyn File>,
+        elf: &ElfFile,
+    ) -> Option<usize> {
         let elf_header = elf.header;
         let ph_count = elf_header.pt2.ph_count();
 
-        let mut is_dl = false;
-        for i in 0..ph_count {
-            let ph = elf.program_header(i).unwrap();
-            if ph.get_type().unwrap() == xmas_elf::program::Type::Interp {
-                is_dl = true;
-                break;
-            }
-        }
+        // NOTE: the path is found by the program header rather than the `.interp`
+        // section, as only the headers at the start of the file are read
+        let interp_ph = (0..ph_count)
+            .map(|i| elf.program_header(i).unwrap())
+            .find(|ph| ph.get_type().unwrap() == xmas_elf::program::Type::Interp);
 
-        if is_dl {
+        if let Some(ph) = interp_ph {
             log::info!("[load_dl] encounter a dl elf");
-            let section = elf.find_section_by_name(".interp").unwrap();
-            let mut interp = String::from_utf8(section.raw_data(&elf).to_vec()).unwrap();
+            let mut interp = vec![0; ph.file_size() as usize];
+            block_on(async { elf_file.read_at(ph.offset() as usize, &mut interp).await }).ok()?;
+            let mut interp = String::from_utf8(interp).unwrap();
             interp = interp.strip_suffix("\0").unwrap_or(&interp).to_string();
             log::info!("[load_dl] interp {}", interp);
 
@@ -258,7 +417,7 @@ impl MemorySpace {
             }
             let interp_dentry: Arc<dyn Dentry> = interp_dentry.unwrap();
             let interp_file = interp_dentry.open().ok().unwrap();
-            let interp_elf_data = block_on(async { interp_file.read_all().await }).ok()?;
+            let interp_elf_data = block_on(read_elf_headers(&interp_file)).ok()?;
             let interp_elf = xmas_elf::ElfFile::new(&interp_elf_data).unwrap();
             self.map_elf(interp_file, &interp_elf, DL_INTERP_OFFSET.into());
 
@@ -269,52 +428,45 @@ impl MemorySpace {
         }
     }
 
-    /// Attach given `pages` to the MemorySpace. If pages is not given, it will
-    /// create pages according to the `size` and map them to the MemorySpace.
-    /// if `shmaddr` is set to `0`, it will chooses a suitable page-aligned
+    /// Attach the `pages` of a shared memory segment to the MemorySpace. If
+    /// `shmaddr` is set to `0`, it will chooses a suitable page-aligned
     /// address to attach.
     ///
-    /// `size` and `shmaddr` need to be page-aligned
+    /// `shmaddr` needs to be page-aligned. Fails with `EINVAL` if the range
+    /// at `shmaddr` is in use, unless `remap` is given to unmap it first, and
+    /// with `ENOMEM` if no range is free to choose.
     pub fn attach_shm(
         &mut self,
-        size: usize,
         shmaddr: VirtAddr,
         map_perm: MapPerm,
-        pages: &mut Vec<Weak<Page>>,
-    ) -> VirtAddr {
-        let mut ret_addr = shmaddr;
-        let mut vm_area = if shmaddr == 0.into() {
+        pages: &[Arc<Page>],
+        remap: bool,
+    ) -> SysResult<VirtAddr> {
+        let size = pages.len() * PAGE_SIZE;
+        let range = if shmaddr == 0.into() {
             const SHARED_RANGE: Range<VirtAddr> =
                 VirtAddr::from_usize_range(U_SEG_SHARE_BEG..U_SEG_SHARE_END);
-            let range = self
-                .areas()
+            self.areas()
                 .find_free_range(SHARED_RANGE, size)
-                .expect("no free shared area");
-            ret_addr = range.start;
-            VmArea::new(range, map_perm, VmAreaType::Shm)
+                .ok_or(SysError::ENOMEM)?
         } else {
             log::info!("[attach_shm] user defined addr");
-            let shm_end = shmaddr + size;
-            VmArea::new(shmaddr..shm_end, map_perm, VmAreaType::Shm)
-        };
-        if pages.is_empty() {
-            for vpn in vm_area.range_vpn() {
-                let page = Page::new();
-                self.page_table_mut().map(vpn, page.ppn(), map_perm.into());
-                pages.push(Arc::downgrade(&page));
-                vm_area.pages.insert(vpn, page);
-            }
-        } else {
-            debug_assert!(pages.len() == vm_area.range_vpn().end - vm_area.range_vpn().start);
-            let mut pages = pages.iter();
-            for vpn in vm_area.range_vpn() {
-                let page = pages.next().unwrap().upgrade().unwrap();
-                self.page_table_mut().map(vpn, page.ppn(), map_perm.into());
-                vm_area.pages.insert(vpn, page.clone());
+            let range = shmaddr..shmaddr + size;
+            if remap {
+                self.unmap(range.clone())?;
+            } else if self.areas().is_range_free(range.clone()).is_err() {
+                return Err(SysError::EINVAL);
             }
+            range
+        };
+        let ret_addr = range.start;
+        let mut vm_area = VmArea::new(range, map_perm, VmAreaType::Shm);
+        for (vpn, page) in vm_area.range_vpn().zip(pages.iter()) {
+            self.page_table_mut().map(vpn, page.ppn(), map_perm.into());
+            vm_area.insert_page(vpn, page.clone());
         }
         self.push_vma_lazily(vm_area);
-        return ret_addr;
+        Ok(ret_addr)
     }
 
     /// `shmaddr` must be the return value of shmget (i.e. `shmaddr` is page
@@ -433,44 +585,132 @@ impl MemorySpace {
         }
     }
 
-    /// Clone a same `MemorySpace` lazily.
-    pub fn from_user_lazily(user_space: &mut Self) -> Self {
+    /// Clone the user memory space `user_space` for fork, sharing private pages
+    /// copy-on-write.
+    ///
+    /// The lock of `user_space` is only taken for one VMA at a time, so that
+    /// forking a large process neither keeps interrupts off nor blocks other
+    /// threads of the parent for long, and the task yields once every
+    /// `FORK_YIELD_PAGES` pages. A VMA is marked copy-on-write and snapshotted
+    /// under the same lock, so a fault from another thread sees the whole VMA
+    /// either before or after.
+    ///
+    /// The longest time the lock is held, i.e. interrupts are off, is logged
+    /// together with the time of the whole clone.
+    ///
+    /// Each VMA is flushed from the TLB of the local hart in `clone_vma_cow`.
+    /// With smp, other threads of the parent may run on other harts meanwhile,
+    /// so the harts in the cpumask are sent a shootdown once all VMAs are
+    /// cloned, before the child can run and see a page the parent still
+    /// writes through a stale entry.
+    pub async fn from_user_lazily(user_space: &SpinNoIrqLock<Self>) -> Self {
+        /// Pages to clone before yielding to other tasks.
+        const FORK_YIELD_PAGES: usize = 4096;
+
+        let begin = get_time_duration();
+        let mut longest_locked = Duration::ZERO;
+        let mut total_pages = 0;
         let mut memory_space = Self::new_user();
-        for (range, area) in user_space.areas().iter() {
-            log::debug!("[MemorySpace::from_user_lazily] cloning {area:?}");
-            let mut new_area = area.clone();
-            debug_assert_eq!(range, new_area.range_va());
-            for vpn in area.range_vpn() {
-                if let Some(page) = area.pages.get(&vpn) {
-                    let pte = user_space.page_table_mut().find_leaf_pte(vpn).unwrap();
-                    let (pte_flags, ppn) = match area.vma_type {
-                        VmAreaType::Shm => {
-                            // If shared memory,
-                            // then we don't need to modify the pte flags,
-                            // i.e. no copy-on-write.
-                            log::info!("[from_user_lazily] clone Shared Memory");
-                            new_area.pages.insert(vpn, page.clone());
-                            (pte.flags(), page.ppn())
-                        }
-                        _ => {
-                            // copy on write
-                            // TODO: MmapFlags::MAP_SHARED
-                            let mut new_flags = pte.flags() | PTEFlags::COW;
-                            new_flags.remove(PTEFlags::W);
-                            pte.set_flags(new_flags);
-                            (new_flags, page.ppn())
-                        }
-                    };
-                    memory_space.page_table_mut().map(vpn, ppn, pte_flags);
-                } else {
-                    // lazy allocated area
-                }
+        memory_space.exe = user_space.lock().exe.clone();
+        let mut cursor = VirtAddr::from(0);
+        let mut pages = 0;
+        // NOTE: VMAs are looked up by a cursor rather than collected beforehand,
+        // since other threads may map, unmap or split them in between.
+        loop {
+            // NOTE: the guard must be dropped here rather than held across the
+            // yield below
+            let locked = get_time_duration();
+            let cloned = user_space.lock().clone_vma_cow(cursor);
+            longest_locked = longest_locked.max(get_time_duration() - locked);
+            let Some((new_area, ptes)) = cloned else {
+                break;
+            };
+            cursor = new_area.end_va();
+            pages += ptes.len();
+            total_pages += ptes.len();
+            for (vpn, ppn, pte_flags) in ptes {
+                memory_space.page_table_mut().map(vpn, ppn, pte_flags);
             }
             memory_space.push_vma_lazily(new_area);
+            if pages >= FORK_YIELD_PAGES {
+                pages = 0;
+                yield_now().await;
+            }
+        }
+        #[cfg(feature = "smp")]
+        if total_pages > 0 {
+            let cpumask = user_space.lock().cpumask();
+            crate::processor::tlb::flush_tlb_others(cpumask).await;
         }
+        log::info!(
+            "[MemorySpace::from_user_lazily] cloned {total_pages} pages in {:?}, lock held for at most {longest_locked:?}",
+            get_time_duration() - begin
+        );
         memory_space
     }
 
+    /// Clone the first VMA starting at or after `from` for a forked child, and
+    /// mark its private pages copy-on-write in this memory space.
+    ///
+    /// Returns the cloned VMA and the leaf PTEs to map in the child.
+    ///
+    /// The pages made read-only are flushed from the TLB of this hart before
+    /// returning, i.e. before the lock is released and another thread of the
+    /// parent may run here. Like Linux on RISC-V, they are flushed one by one
+    /// up to `TLB_FLUSH_ALL_THRESHOLD` pages and all at once beyond.
+    fn clone_vma_cow(
+        &mut self,
+        from: VirtAddr,
+    ) -> Option<(VmArea, Vec<(VirtPageNum, PhysPageNum, PTEFlags)>)> {
+        let (_, area) = self
+            .areas()
+            .range(from..VirtAddr::from(usize::MAX))
+            .next()?;
+        log::debug!("[MemorySpace::clone_vma_cow] cloning {area:?}");
+        // NOTE: pages of a shared mapping, e.g. the page cache pages of a
+        // `MAP_SHARED` file mapping, must stay the same pages in both processes
+        let shared =
+            area.vma_type == VmAreaType::Shm || area.mmap_flags.contains(MmapFlags::MAP_SHARED);
+        let new_area = area.clone();
+        let mut ptes = Vec::with_capacity(area.pages().len());
+        // NOTE: only pages present are visited, lazily allocated ones have no PTE
+        for (&vpn, page) in area.pages().iter() {
+            let pte = self.page_table_mut().find_leaf_pte(vpn).unwrap();
+            let pte_flags = if shared {
+                // If shared memory,
+                // then we don't need to modify the pte flags,
+                // i.e. no copy-on-write.
+                pte.flags()
+            } else {
+                // copy on write
+                let mut new_flags = pte.flags() | PTEFlags::COW;
+                // NOTE: a page of `PROT_NONE` keeps W, see
+                // `VmArea::set_perm_and_flush`
+                if new_flags.intersects(PTEFlags::R | PTEFlags::X) {
+                    new_flags.remove(PTEFlags::W);
+                }
+                pte.set_flags(new_flags);
+                new_flags
+            };
+            ptes.push((vpn, page.ppn(), pte_flags));
+        }
+        /// Pages beyond which flushing the whole TLB is cheaper than flushing
+        /// them one by one.
+        const TLB_FLUSH_ALL_THRESHOLD: usize = 64;
+
+        if !shared {
+            if ptes.len() > TLB_FLUSH_ALL_THRESHOLD {
+                // TODO: avoid flushing global entries like kernel mappings
+                unsafe { sfence_vma_all() };
+            } else {
+                for &(vpn, ..) in ptes.iter() {
+                    unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
+                }
+            }
+        }
+        Some((new_area, ptes))
+    }
+
     /// Push `VmArea` into `MemorySpace` and map it in page table.
     pub fn push_vma(&mut self, mut vma: VmArea) {
         vma.map(self.page_table_mut());
@@ -577,11 +817,11 @@ impl MemorySpace {
                         (new_flags, page.ppn())
                     };
                     page_table.map(vpn, ppn, pte_flags);
-                    vma.pages.insert(vpn, page);
+                    vma.insert_page(vpn, page);
                     unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
                 } else {
                     page_table.map(vpn, page.ppn(), perm.into());
-                    vma.pages.insert(vpn, page);
+                    vma.insert_page(vpn, page);
                     unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
                 }
             } else {
@@ -663,25 +903,236 @@ impl MemorySpace {
         Ok(())
     }
 
+    /// Change the permission of the pages in `range` to `perm`, splitting the
+    /// areas it covers in part. Fails with `ENOMEM` and changes nothing if
+    /// part of `range` is not mapped.
+    /// Get the ranges of the areas intersecting `range` in order. Fails with
+    /// `ENOMEM` if part of `range` is not mapped.
+    pub fn areas_covering(&self, range: &Range<VirtAddr>) -> SysResult<Vec<Range<VirtAddr>>> {
+        let mut ranges = Vec::new();
+        let mut end = range.start;
+        for (vma_range, _) in self
+            .areas()
+            .iter()
+            .filter(|(vma_range, _)| vma_range.end > range.start && vma_range.start < range.end)
+        {
+            if vma_range.start > end {
+                return Err(SysError::ENOMEM);
+            }
+            end = vma_range.end;
+            ranges.push(vma_range);
+        }
+        if end < range.end {
+            return Err(SysError::ENOMEM);
+        }
+        Ok(ranges)
+    }
+
     pub fn mprotect(&mut self, range: Range<VirtAddr>, perm: MapPerm) -> SysResult<()> {
         debug_assert!(range.start.is_aligned() && range.end.is_aligned());
-        let (old_range, area) = self
-            .areas_mut()
-            .get_key_value_mut(range.start)
-            .ok_or(SysError::ENOMEM)?;
-        if range == old_range {
+        let old_ranges = self.areas_covering(&range)?;
+        for old_range in old_ranges {
+            let split_range =
+                cmp::max(old_range.start, range.start)..cmp::min(old_range.end, range.end);
+            let area = if split_range == old_range {
+                self.areas_mut().get_mut(old_range.start).unwrap()
+            } else {
+                // do split and remap
+                let (_, middle, _) = self.split_area(old_range, split_range);
+                middle.unwrap()
+            };
             area.set_perm_and_flush(self.page_table_mut(), perm);
-        } else {
-            debug_assert!(old_range.end >= range.end);
-            // do split and remap
-            let (_, middle, _) = self.split_area(old_range, range);
-            if let Some(middle) = middle {
-                middle.set_perm_and_flush(self.page_table_mut(), perm);
+        }
+        Ok(())
+    }
+
+    /// Resize the mapping `old_range`, which must lie in one area, to
+    /// `new_size` bytes, for mremap(2). Returns the new start address.
+    ///
+    /// A mapping shrinks in place, and grows in place when it ends its area and
+    /// the range after it is free. Otherwise it is moved, if `may_move`, to a
+    /// free range, or to `new_addr` replacing what is mapped there. Moving
+    /// remaps the pages present rather than copying them, see
+    /// `VmArea::move_to`.
+    pub fn mremap(
+        &mut self,
+        old_range: Range<VirtAddr>,
+        new_size: usize,
+        may_move: bool,
+        new_addr: Option<VirtAddr>,
+    ) -> SysResult<VirtAddr> {
+        const MMAP_RANGE: Range<VirtAddr> =
+            VirtAddr::from_usize_range(U_SEG_FILE_BEG..U_SEG_FILE_END);
+        const SHARED_RANGE: Range<VirtAddr> =
+            VirtAddr::from_usize_range(U_SEG_SHARE_BEG..U_SEG_SHARE_END);
+        debug_assert!(old_range.start.is_aligned() && old_range.end.is_aligned());
+        debug_assert!(is_aligned_to_page(new_size));
+        let (vma_range, vma) = self
+            .areas()
+            .get_key_value(old_range.start)
+            .ok_or(SysError::EFAULT)?;
+        if vma_range.end < old_range.end {
+            return Err(SysError::EFAULT);
+        }
+        let vma_type = vma.vma_type;
+        let old_size = old_range.end - old_range.start;
+        let new_end = old_range.start + new_size;
+
+        if new_addr.is_none() {
+            if new_size <= old_size {
+                if new_size < old_size {
+                    self.unmap(new_end..old_range.end)?;
+                }
+                return Ok(old_range.start);
+            }
+            if old_range.end == vma_range.end
+                && self.areas().is_range_free(old_range.end..new_end).is_ok()
+            {
+                self.areas_mut()
+                    .extend_back(vma_range.start..new_end)
+                    .unwrap();
+                let (range_va, vma) = self.areas_mut().get_key_value_mut(vma_range.start).unwrap();
+                vma.set_range_va(range_va);
+                if vma_type == VmAreaType::Shm {
+                    // shared anonymous memory is not faulted in
+                    vma.map_range(self.page_table_mut(), old_range.end..new_end);
+                }
+                return Ok(old_range.start);
+            }
+            if !may_move {
+                return Err(SysError::ENOMEM);
+            }
+        }
+
+        let new_range = match new_addr {
+            Some(addr) => {
+                let new_range = addr..addr + new_size;
+                if new_range.start < old_range.end && old_range.start < new_range.end {
+                    return Err(SysError::EINVAL);
+                }
+                self.unmap(new_range.clone())?;
+                new_range
+            }
+            None => {
+                let segment = if vma_type == VmAreaType::Shm {
+                    SHARED_RANGE
+                } else {
+                    MMAP_RANGE
+                };
+                self.areas()
+                    .find_free_range(segment, new_size)
+                    .ok_or(SysError::ENOMEM)?
+            }
+        };
+        if vma_range != old_range {
+            self.split_area(vma_range, old_range.clone());
+        }
+        let mut vma = self.areas_mut().force_remove_one(old_range.clone());
+        if new_size < old_size {
+            let (_, kept, rest) = vma.split(old_range.start..new_end);
+            rest.unwrap().unmap(self.page_table_mut());
+            vma = kept.unwrap();
+        }
+        vma.move_to(self.page_table_mut(), new_range.start);
+        if new_size > old_size {
+            let grown = new_range.start + old_size..new_range.end;
+            vma.set_range_va(new_range.clone());
+            if vma_type == VmAreaType::Shm {
+                vma.map_range(self.page_table_mut(), grown);
             }
         }
+        self.push_vma_lazily(vma);
+        Ok(new_range.start)
+    }
+
+    /// Get the files of the shared writable mappings in `range`, with the
+    /// mapped pages marked dirty, for msync(2). Fails with `ENOMEM` if part of
+    /// `range` is not mapped.
+    pub fn shared_files_to_sync(&self, range: Range<VirtAddr>) -> SysResult<Vec<Arc<dyn File>>> {
+        let mut files = Vec::new();
+        for vma_range in self.areas_covering(&range)? {
+            let vma = self.areas().get(vma_range.start).unwrap();
+            let start = cmp::max(vma_range.start, range.start);
+            let vma_end = cmp::min(vma_range.end, range.end);
+            if let Some(file) = vma.mark_shared_pages_dirty(start..vma_end) {
+                files.push(file);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Drop the pages in `range`, keeping the areas, see
+    /// `VmArea::discard_pages`. Fails with `ENOMEM` if part of `range` is not
+    /// mapped, in which case nothing is dropped.
+    pub fn discard_pages(&mut self, range: Range<VirtAddr>) -> SysResult<()> {
+        for vma_range in self.areas_covering(&range)? {
+            let sub_range =
+                cmp::max(vma_range.start, range.start)..cmp::min(vma_range.end, range.end);
+            let vma = self.areas_mut().get_mut(vma_range.start).unwrap();
+            vma.discard_pages(self.page_table_mut(), sub_range);
+        }
+        Ok(())
+    }
+
+    /// Fault in the pages in `range` of file mappings, see
+    /// `VmArea::populate_pages`. Fails with `ENOMEM` if part of `range` is not
+    /// mapped.
+    pub fn populate_pages(&mut self, range: Range<VirtAddr>) -> SysResult<()> {
+        for vma_range in self.areas_covering(&range)? {
+            let sub_range =
+                cmp::max(vma_range.start, range.start)..cmp::min(vma_range.end, range.end);
+            let vma = self.areas_mut().get_mut(vma_range.start).unwrap();
+            vma.populate_pages(self.page_table_mut(), sub_range);
+        }
         Ok(())
     }
 
+    /// Drop the stale pages of the areas mapping `inode`, see
+    /// `VmArea::sync_backed_file`.
+    pub fn sync_backed_file(&mut self, inode: &Arc<dyn Inode>) {
+        let page_table = self.page_table_mut();
+        for (_, vma) in self.areas_mut().iter_mut() {
+            if vma
+                .backed_file
+                .as_ref()
+                .is_some_and(|file| file.inode().meta().ino == inode.meta().ino)
+            {
+                vma.sync_backed_file(page_table);
+            }
+        }
+    }
+
+    /// Format the areas as lines of `/proc/[pid]/maps`, i.e. `start-end perms
+    /// offset dev inode path`. `exe` names the areas mapped from the elf.
+    pub fn maps(&self, exe: &str) -> String {
+        let mut res = String::new();
+        for (range, vma) in self.areas().iter() {
+            let perm = vma.perm();
+            let shared =
+                vma.vma_type == VmAreaType::Shm || vma.mmap_flags.contains(MmapFlags::MAP_SHARED);
+            let (ino, path) = match (&vma.backed_file, vma.vma_type) {
+                (Some(file), _) => (file.inode().meta().ino, file.dentry().path()),
+                (None, VmAreaType::Elf) => (0, exe.to_string()),
+                (None, VmAreaType::Heap) => (0, "[heap]".to_string()),
+                (None, VmAreaType::Stack) => (0, "[stack]".to_string()),
+                (None, _) => (0, String::new()),
+            };
+            res += &format!(
+                "{:08x}-{:08x} {}{}{}{} {:08x} 00:00 {:<10} {}\n",
+                range.start.bits(),
+                range.end.bits(),
+                if perm.contains(MapPerm::R) { 'r' } else { '-' },
+                if perm.contains(MapPerm::W) { 'w' } else { '-' },
+                if perm.contains(MapPerm::X) { 'x' } else { '-' },
+                if shared { 's' } else { 'p' },
+                vma.offset,
+                ino,
+                path
+            );
+        }
+        res
+    }
+
     pub fn handle_page_fault(
         &mut self,
         va: VirtAddr,
@@ -692,11 +1143,14 @@ impl MemorySpace {
             log::error!("[handle_page_fault] no area containing {va:?}");
             SysError::EFAULT
         })?;
+        vm_stat_add(VmStat::PgFault, 1);
         vm_area.handle_page_fault(self.page_table_mut(), va.floor(), access_type)?;
         Ok(())
     }
 
     pub unsafe fn switch_page_table(&self) {
+        self.cpumask
+            .fetch_or(1 << local_hart().hart_id(), Ordering::AcqRel);
         self.page_table().switch();
     }
 }
@@ -3,9 +3,13 @@ use core::ops::{Range, RangeBounds};
 
 use arch::memory::sfence_vma_vaddr;
 use async_utils::block_on;
-use config::mm::{round_down_to_page, PAGE_SIZE};
-use memory::{pte::PTEFlags, VirtAddr, VirtPageNum};
-use page::Page;
+use config::mm::{round_down_to_page, round_up_to_page, PAGE_SIZE};
+use memory::{
+    pte::PTEFlags,
+    vmstat::{vm_stat_add, vm_stat_sub, VmStat},
+    VirtAddr, VirtPageNum,
+};
+use page::{Page, PageKind};
 use systype::{SysError, SysResult};
 use vfs_core::File;
 
@@ -96,13 +100,41 @@ impl From<MapPerm> for PTEFlags {
     }
 }
 
+/// Resident pages by kind, like the rss counters of an address space in linux.
+/// A page is counted in every area mapping it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RssStat {
+    /// Pages private to the area, including private copies of file pages.
+    pub anon: usize,
+    /// Pages of the page cache.
+    pub file: usize,
+    /// Pages of shared memory.
+    pub shmem: usize,
+}
+
+impl RssStat {
+    pub fn total(&self) -> usize {
+        self.anon + self.file + self.shmem
+    }
+}
+
+impl core::ops::AddAssign for RssStat {
+    fn add_assign(&mut self, rhs: Self) {
+        self.anon += rhs.anon;
+        self.file += rhs.file;
+        self.shmem += rhs.shmem;
+    }
+}
+
 /// A contiguous virtual memory area.
-#[derive(Clone)]
 pub struct VmArea {
     /// Aligned `VirtAddr` range for the `VmArea`.
     range_va: Range<VirtAddr>,
-    /// Hold pages with RAII.
-    pub pages: BTreeMap<VirtPageNum, Arc<Page>>,
+    /// Hold pages with RAII, changed through `insert_page` and `remove_page`
+    /// only, which keep `rss` in step.
+    pages: BTreeMap<VirtPageNum, Arc<Page>>,
+    /// Pages in `pages` by kind.
+    rss: RssStat,
     /// Map permission of this area.
     pub map_perm: MapPerm,
     /// Type of this area.
@@ -115,6 +147,9 @@ pub struct VmArea {
     pub backed_file: Option<Arc<dyn File>>,
     /// Start offset in the file.
     pub offset: usize,
+    /// Sequence number of the page cache of `backed_file` when the pages of
+    /// the file were last checked, see `VmArea::sync_backed_file`.
+    pub page_cache_seq: usize,
 }
 
 impl core::fmt::Debug for VmArea {
@@ -127,9 +162,27 @@ impl core::fmt::Debug for VmArea {
     }
 }
 
+impl Clone for VmArea {
+    fn clone(&self) -> Self {
+        vm_stat_add(VmStat::NrMapped, self.rss.total());
+        Self {
+            range_va: self.range_va(),
+            pages: self.pages.clone(),
+            rss: self.rss,
+            map_perm: self.map_perm,
+            vma_type: self.vma_type,
+            mmap_flags: self.mmap_flags,
+            backed_file: self.backed_file.clone(),
+            offset: self.offset,
+            page_cache_seq: self.page_cache_seq,
+        }
+    }
+}
+
 impl Drop for VmArea {
     fn drop(&mut self) {
         log::debug!("[VmArea::drop] drop {self:?}",);
+        vm_stat_sub(VmStat::NrMapped, self.rss.total());
     }
 }
 
@@ -140,11 +193,13 @@ impl VmArea {
         let new = Self {
             range_va,
             pages: BTreeMap::new(),
+            rss: RssStat::default(),
             vma_type,
             map_perm,
             backed_file: None,
             mmap_flags: MmapFlags::default(),
             offset: 0,
+            page_cache_seq: 0,
         };
         log::debug!("[VmArea::new] {new:?}");
         new
@@ -158,14 +213,20 @@ impl VmArea {
         offset: usize,
     ) -> Self {
         let range_va = range_va.start.floor().into()..range_va.end.ceil().into();
+        let page_cache_seq = file
+            .as_ref()
+            .and_then(|file| file.inode().page_cache().map(|page_cache| page_cache.seq()))
+            .unwrap_or(0);
         let new = Self {
             range_va,
             pages: BTreeMap::new(),
+            rss: RssStat::default(),
             vma_type: VmAreaType::Mmap,
             map_perm,
             backed_file: file,
             mmap_flags,
             offset,
+            page_cache_seq,
         };
         log::debug!("[VmArea::new_mmap] {new:?}");
         new
@@ -176,11 +237,13 @@ impl VmArea {
         Self {
             range_va: another.range_va(),
             pages: BTreeMap::new(),
+            rss: RssStat::default(),
             vma_type: another.vma_type,
             map_perm: another.map_perm,
             backed_file: another.backed_file.clone(),
             mmap_flags: another.mmap_flags,
             offset: another.offset,
+            page_cache_seq: another.page_cache_seq,
         }
     }
 
@@ -224,25 +287,82 @@ impl VmArea {
         self.pages.get(&vpn).expect("no page found for vpn")
     }
 
+    pub fn pages(&self) -> &BTreeMap<VirtPageNum, Arc<Page>> {
+        &self.pages
+    }
+
+    pub fn rss(&self) -> RssStat {
+        self.rss
+    }
+
+    /// Count `page` in `rss` if `mapped`, or take it out otherwise.
+    fn account_page(&mut self, page: &Page, mapped: bool) {
+        let counter = if matches!(page.kind(), PageKind::FileCache(_)) {
+            &mut self.rss.file
+        } else if self.vma_type == VmAreaType::Shm {
+            &mut self.rss.shmem
+        } else {
+            &mut self.rss.anon
+        };
+        if mapped {
+            *counter += 1;
+            vm_stat_add(VmStat::NrMapped, 1);
+        } else {
+            *counter -= 1;
+            vm_stat_sub(VmStat::NrMapped, 1);
+        }
+    }
+
+    /// Hold `page` mapped at `vpn`, in place of the page held there before.
+    pub fn insert_page(&mut self, vpn: VirtPageNum, page: Arc<Page>) {
+        self.account_page(&page, true);
+        if let Some(old) = self.pages.insert(vpn, page) {
+            self.account_page(&old, false);
+        }
+    }
+
+    /// Drop the page held at `vpn`, whose mapping is removed by the caller.
+    pub fn remove_page(&mut self, vpn: VirtPageNum) {
+        if let Some(old) = self.pages.remove(&vpn) {
+            self.account_page(&old, false);
+        }
+    }
+
     pub fn fill_zero(&self) {
         for page in self.pages.values() {
             page.fill_zero()
         }
     }
 
+    /// Change the permission of the area, and of the pages present to match.
+    ///
+    /// Pages copy-on-write stay read only, the page fault handler copies them
+    /// on write. A page of `PROT_NONE` is kept mapped with W only, which is
+    /// reserved, so that any access faults, from the kernel as well.
     pub fn set_perm_and_flush(&mut self, page_table: &mut PageTable, perm: MapPerm) {
         self.set_perm(perm);
-        let pte_flags = perm.into();
+        let mut perm_flags: PTEFlags = perm.into();
+        if perm_flags.contains(PTEFlags::W) {
+            // write only can not be expressed, like linux it is read write
+            perm_flags |= PTEFlags::R;
+        }
+        let accessible = perm_flags.intersects(PTEFlags::R | PTEFlags::X);
         // NOTE: should flush pages that already been allocated, page fault handler will
         // handle the permission of those unallocated pages
         for &vpn in self.pages.keys() {
-            let pte = page_table.find_leaf_pte(vpn).unwrap();
-            log::trace!(
-                "[origin pte:{:?}, new_flag:{:?}]",
-                pte.flags(),
-                pte.flags().union(pte_flags)
-            );
-            pte.set_flags(pte.flags().union(pte_flags));
+            let Some(pte) = page_table.find_leaf_pte(vpn) else {
+                continue;
+            };
+            let old_flags = pte.flags();
+            let mut new_flags = perm_flags
+                | (old_flags & (PTEFlags::V | PTEFlags::A | PTEFlags::D | PTEFlags::COW));
+            if !accessible {
+                new_flags |= PTEFlags::W;
+            } else if old_flags.contains(PTEFlags::COW) {
+                new_flags.remove(PTEFlags::W);
+            }
+            log::trace!("[VmArea::set_perm_and_flush] {vpn:?} pte {old_flags:?} -> {new_flags:?}");
+            pte.set_flags(new_flags);
             unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
         }
     }
@@ -265,7 +385,7 @@ impl VmArea {
             let page = Page::new();
             // page.clear();
             page_table.map(vpn, page.ppn(), pte_flags);
-            self.pages.insert(vpn, page);
+            self.insert_page(vpn, page);
         }
     }
 
@@ -277,7 +397,7 @@ impl VmArea {
             let page = Page::new();
             page_table.map(vpn, page.ppn(), pte_flags);
             unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
-            self.pages.insert(vpn, page);
+            self.insert_page(vpn, page);
         }
     }
 
@@ -286,8 +406,139 @@ impl VmArea {
         for vpn in vpns {
             page_table.unmap(vpn);
             unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
-            self.pages.remove(&vpn);
+            self.remove_page(vpn);
+        }
+    }
+
+    /// Move the area to start at `new_start`, for mremap(2). The pages present
+    /// are remapped with their PTE flags kept, so permissions and
+    /// copy-on-write carry over, and nothing is copied.
+    pub fn move_to(&mut self, page_table: &mut PageTable, new_start: VirtAddr) {
+        debug_assert!(new_start.is_aligned());
+        let old_start_vpn = self.start_vpn();
+        let new_start_vpn: VirtPageNum = new_start.into();
+        let pages = core::mem::take(&mut self.pages);
+        for (vpn, page) in pages {
+            let new_vpn = new_start_vpn + (vpn - old_start_vpn);
+            if let Some(pte) = page_table.find_leaf_pte(vpn) {
+                let flags = pte.flags();
+                page_table.unmap(vpn);
+                page_table.map(new_vpn, page.ppn(), flags);
+                unsafe {
+                    sfence_vma_vaddr(vpn.to_vaddr().into());
+                    sfence_vma_vaddr(new_vpn.to_vaddr().into());
+                }
+            }
+            self.pages.insert(new_vpn, page);
+        }
+        let len = self.end_va() - self.start_va();
+        self.set_range_va(new_start..new_start + len);
+    }
+
+    /// Drop the pages in `range`, keeping the area, for madvise(2)
+    /// `MADV_DONTNEED`. They fault in again zero filled, or from the page cache
+    /// for a file mapping, whose private copies are lost.
+    ///
+    /// The pages of shared anonymous memory are kept, since there is nowhere
+    /// to fault them in from, and linux keeps their data as well.
+    pub fn discard_pages(&mut self, page_table: &mut PageTable, range: Range<VirtAddr>) {
+        if self.vma_type == VmAreaType::Shm {
+            return;
+        }
+        // the stores through a shared mapping are still to be written back
+        self.mark_shared_pages_dirty(range.clone());
+        let vpns: Vec<_> = self
+            .pages
+            .range(range.start.floor()..range.end.ceil())
+            .map(|(&vpn, _)| vpn)
+            .collect();
+        for vpn in vpns {
+            page_table.unmap(vpn);
+            unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
+            self.remove_page(vpn);
+        }
+    }
+
+    /// Fault in the pages in `range` of a file mapping that are not present,
+    /// for madvise(2) `MADV_WILLNEED`. It is only advice, so it stops at the
+    /// first page that can not be read, e.g. beyond the end of file.
+    pub fn populate_pages(&mut self, page_table: &mut PageTable, range: Range<VirtAddr>) {
+        if self.backed_file.is_none() {
+            return;
+        }
+        let range_vpn: Range<VirtPageNum> = range.start.floor()..range.end.ceil();
+        for vpn in range_vpn {
+            if self.pages.contains_key(&vpn) {
+                continue;
+            }
+            if let Err(e) = self.handle_page_fault(page_table, vpn, PageFaultAccessType::READ) {
+                log::debug!("[VmArea::populate_pages] stop at {vpn:?} for {e:?}");
+                break;
+            }
+        }
+    }
+
+    /// Offset in the backed file of the page at `vpn`.
+    fn file_offset(&self, vpn: VirtPageNum) -> usize {
+        self.offset + (vpn - self.start_vpn()) * PAGE_SIZE
+    }
+
+    /// Mark the pages in `range` of a shared writable file mapping dirty in the
+    /// page cache, since stores through the mapping are not tracked. Returns
+    /// the backed file if there are such pages to write back.
+    pub fn mark_shared_pages_dirty(&self, range: Range<VirtAddr>) -> Option<Arc<dyn File>> {
+        if !self.mmap_flags.contains(MmapFlags::MAP_SHARED) || !self.map_perm.contains(MapPerm::W) {
+            return None;
+        }
+        let file = self.backed_file.clone()?;
+        let inode = file.inode();
+        let page_cache = inode.page_cache()?;
+        for (&vpn, _) in self.pages.range(range.start.floor()..range.end.ceil()) {
+            page_cache.mark_dirty(self.file_offset(vpn));
+        }
+        Some(file)
+    }
+
+    /// Drop the pages of the backed file that are no longer in its page cache
+    /// since the file was truncated, so that the next access either faults
+    /// in the current page or hits the end of file.
+    ///
+    /// Pages below the end of file are kept for a private mapping, as they may
+    /// be private copies already.
+    pub fn sync_backed_file(&mut self, page_table: &mut PageTable) {
+        let Some(file) = self.backed_file.clone() else {
+            return;
+        };
+        let inode = file.inode();
+        let Some(page_cache) = inode.page_cache() else {
+            return;
+        };
+        let seq = page_cache.seq();
+        if seq == self.page_cache_seq {
+            return;
+        }
+        let end = round_up_to_page(inode.size());
+        let shared = self.mmap_flags.contains(MmapFlags::MAP_SHARED);
+        let stale: Vec<_> = self
+            .pages
+            .iter()
+            .filter(|&(&vpn, page)| {
+                let offset = self.file_offset(vpn);
+                offset >= end
+                    || (shared
+                        && !page_cache
+                            .get_page(offset)
+                            .is_some_and(|cached| Arc::ptr_eq(&cached, page)))
+            })
+            .map(|(&vpn, _)| vpn)
+            .collect();
+        for vpn in stale {
+            log::debug!("[VmArea::sync_backed_file] drop stale page at {vpn:?}");
+            page_table.unmap(vpn);
+            unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
+            self.remove_page(vpn);
         }
+        self.page_cache_seq = seq;
     }
 
     /// Copy the data to start_va + offset.
@@ -335,36 +586,27 @@ impl VmArea {
         if !left_range.is_empty() {
             let mut left_vma = VmArea::from_another(&self);
             left_vma.set_range_va(left_range);
-            left_vma.pages.extend(
-                self.pages
-                    .range(left_vma.range_vpn())
-                    .into_iter()
-                    .map(|(&k, v)| (k, v.clone())),
-            );
+            for (&vpn, page) in self.pages.range(left_vma.range_vpn()) {
+                left_vma.insert_page(vpn, page.clone());
+            }
             left_vma.offset += left_vma.start_va() - self.start_va();
             left = Some(left_vma)
         }
         if !middle_range.is_empty() {
             let mut middle_vma = VmArea::from_another(&self);
             middle_vma.set_range_va(middle_range);
-            middle_vma.pages.extend(
-                self.pages
-                    .range(middle_vma.range_vpn())
-                    .into_iter()
-                    .map(|(&k, v)| (k, v.clone())),
-            );
+            for (&vpn, page) in self.pages.range(middle_vma.range_vpn()) {
+                middle_vma.insert_page(vpn, page.clone());
+            }
             middle_vma.offset += middle_vma.start_va() - self.start_va();
             middle = Some(middle_vma)
         }
         if !right_range.is_empty() {
             let mut right_vma = VmArea::from_another(&self);
             right_vma.set_range_va(right_range);
-            right_vma.pages.extend(
-                self.pages
-                    .range(right_vma.range_vpn())
-                    .into_iter()
-                    .map(|(&k, v)| (k, v.clone())),
-            );
+            for (&vpn, page) in self.pages.range(right_vma.range_vpn()) {
+                right_vma.insert_page(vpn, page.clone());
+            }
             right_vma.offset += right_vma.start_va() - self.start_va();
             right = Some(right_vma)
         }
@@ -388,7 +630,7 @@ impl VmArea {
         );
 
         if !access_type.can_access(self.perm()) {
-            log::warn!(
+            log::info!(
                 "[VmArea::handle_page_fault] permission not allowed, perm:{:?}",
                 self.perm()
             );
@@ -424,7 +666,7 @@ impl VmArea {
                 pte_flags.insert(PTEFlags::W);
                 page_table.map_force(vpn, page.ppn(), pte_flags);
                 // NOTE: track `Page` with great care
-                self.pages.insert(vpn, page);
+                self.insert_page(vpn, page);
                 unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
             } else {
                 // not shared
@@ -442,46 +684,21 @@ impl VmArea {
                 self.vma_type
             );
             match self.vma_type {
-                VmAreaType::Heap | VmAreaType::Stack => {
+                VmAreaType::Elf if self.backed_file.is_some() => {
+                    self.handle_file_page_fault(page_table, vpn, access_type)?;
+                }
+                // NOTE: an elf area without file is the bss of a segment
+                VmAreaType::Heap | VmAreaType::Stack | VmAreaType::Elf => {
                     // lazy allcation for heap
                     page = Page::new();
                     page.fill_zero();
                     page_table.map(vpn, page.ppn(), self.map_perm.into());
-                    self.pages.insert(vpn, page);
+                    self.insert_page(vpn, page);
                     unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
                 }
                 VmAreaType::Mmap => {
                     if !self.mmap_flags.contains(MmapFlags::MAP_ANONYMOUS) {
-                        // file mapping
-                        let file = self.backed_file.as_ref().unwrap();
-                        let offset = self.offset + (vpn - self.start_vpn()) * PAGE_SIZE;
-                        let offset_aligned = round_down_to_page(offset);
-                        if self.mmap_flags.contains(MmapFlags::MAP_SHARED) {
-                            let page = block_on(async { file.get_page_at(offset_aligned).await })?
-                                .unwrap();
-                            page_table.map(vpn, page.ppn(), self.map_perm.into());
-                            self.pages.insert(vpn, page);
-                            unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
-                        } else {
-                            let page = block_on(async { file.get_page_at(offset_aligned).await })?
-                                .unwrap();
-                            if access_type.contains(PageFaultAccessType::WRITE) {
-                                let new_page = Page::new();
-                                new_page.copy_from_slice(page.bytes_array());
-                                page_table.map(vpn, new_page.ppn(), self.map_perm.into());
-                                self.pages.insert(vpn, new_page);
-                            } else {
-                                let (pte_flags, ppn) = {
-                                    let mut new_flags: PTEFlags = self.map_perm.into();
-                                    new_flags |= PTEFlags::COW;
-                                    new_flags.remove(PTEFlags::W);
-                                    (new_flags, page.ppn())
-                                };
-                                page_table.map(vpn, ppn, pte_flags);
-                                self.pages.insert(vpn, page);
-                            }
-                            unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
-                        }
+                        self.handle_file_page_fault(page_table, vpn, access_type)?;
                     } else if self.mmap_flags.contains(MmapFlags::MAP_PRIVATE) {
                         if self.mmap_flags.contains(MmapFlags::MAP_SHARED) {
                             todo!()
@@ -490,7 +707,7 @@ impl VmArea {
                             page = Page::new();
                             page.fill_zero();
                             page_table.map(vpn, page.ppn(), self.map_perm.into());
-                            self.pages.insert(vpn, page);
+                            self.insert_page(vpn, page);
                             unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
                         }
                     }
@@ -500,4 +717,65 @@ impl VmArea {
         }
         Ok(())
     }
+
+    /// Map the page at `vpn` of a file mapping from the page cache. A shared
+    /// mapping maps the cached page itself, and so does a private one until
+    /// it is written, when the page is copied.
+    fn handle_file_page_fault(
+        &mut self,
+        page_table: &mut PageTable,
+        vpn: VirtPageNum,
+        access_type: PageFaultAccessType,
+    ) -> SysResult<()> {
+        self.sync_backed_file(page_table);
+        let file = self.backed_file.clone().unwrap();
+        let offset = self.file_offset(vpn);
+        let offset_aligned = round_down_to_page(offset);
+        // NOTE: whole pages beyond the end of file can not be accessed, the rest of
+        // the last page reads as zero
+        if offset_aligned >= round_up_to_page(file.size()) {
+            return Err(SysError::ENXIO);
+        }
+        if file
+            .inode()
+            .page_cache()
+            .is_some_and(|page_cache| !page_cache.contains(offset_aligned))
+        {
+            vm_stat_add(VmStat::PgMajFault, 1);
+        }
+        if self.mmap_flags.contains(MmapFlags::MAP_SHARED) {
+            let page = block_on(async { file.get_page_at(offset_aligned).await })?
+                .ok_or(SysError::ENXIO)?;
+            // NOTE: the page may be stored to from now on without faulting, so it is
+            // written back on the next sync
+            if self.map_perm.contains(MapPerm::W) {
+                if let Some(page_cache) = file.inode().page_cache() {
+                    page_cache.mark_dirty(offset_aligned);
+                }
+            }
+            page_table.map(vpn, page.ppn(), self.map_perm.into());
+            self.insert_page(vpn, page);
+            unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
+        } else {
+            let page = block_on(async { file.get_page_at(offset_aligned).await })?
+                .ok_or(SysError::ENXIO)?;
+            if access_type.contains(PageFaultAccessType::WRITE) {
+                let new_page = Page::new();
+                new_page.copy_from_slice(page.bytes_array());
+                page_table.map(vpn, new_page.ppn(), self.map_perm.into());
+                self.insert_page(vpn, new_page);
+            } else {
+                let (pte_flags, ppn) = {
+                    let mut new_flags: PTEFlags = self.map_perm.into();
+                    new_flags |= PTEFlags::COW;
+                    new_flags.remove(PTEFlags::W);
+                    (new_flags, page.ppn())
+                };
+                page_table.map(vpn, ppn, pte_flags);
+                self.insert_page(vpn, page);
+            }
+            unsafe { sfence_vma_vaddr(vpn.to_vaddr().into()) };
+        }
+        Ok(())
+    }
 }
@@ -18,12 +18,14 @@ use config::{
 pub use memory::page_table::PageTable;
 use memory::{frame, heap, pte::PTEFlags, VirtAddr};
 pub use memory_space::MemorySpace;
+use systype::SysResult;
 pub use user_ptr::{
     FutexAddr, PageFaultAccessType, UserMut, UserRdWrPtr, UserReadPtr, UserSlice, UserWritePtr,
 };
 
-/// Initialize heap allocator, frame allocator and kernel page table.
-pub fn init() {
+/// Initialize heap allocator, frame allocator and kernel page table, each
+/// depending on the one before.
+fn init() -> SysResult<()> {
     extern "C" {
         fn _ekernel();
     }
@@ -37,7 +39,9 @@ pub fn init() {
         switch_kernel_page_table()
     };
     log::info!("KERNEL SPACE activated");
+    Ok(())
 }
+systype::early_initcall!(init);
 
 /// Kernel space for all processes.
 ///
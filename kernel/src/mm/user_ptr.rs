@@ -284,7 +284,7 @@ impl<T: Clone + Copy + 'static, P: Read> UserPtr<T, P> {
 
     pub fn read(self, task: &Arc<Task>) -> SysResult<T> {
         if self.is_null() {
-            log::warn!("[UserReadPtr] null ptr");
+            log::info!("[UserReadPtr] null ptr");
             return Err(SysError::EFAULT);
         }
         task.just_ensure_user_area(
@@ -645,17 +645,32 @@ impl Task {
                 })
             }
             SaFamily::AF_UNIX => {
-                if unlikely(addrlen < mem::size_of::<SockAddrUn>()) {
+                // the path is usually shorter than `sun_path`, and need not be
+                // terminated by a null byte
+                if unlikely(addrlen > mem::size_of::<SockAddrUn>()) {
                     log::error!("[audit_sockaddr] AF_UNIX addrlen error");
                     return Err(SysError::EINVAL);
                 }
-                Ok(SockAddr {
-                    ipv6: unsafe { *(addr as *const _) },
-                })
+                let mut unix = SockAddrUn {
+                    family: SaFamily::AF_UNIX.into(),
+                    path: [0; 108],
+                };
+                let path_len = addrlen.saturating_sub(mem::size_of::<u16>());
+                let src = unsafe {
+                    core::slice::from_raw_parts(
+                        (addr + mem::size_of::<u16>()) as *const u8,
+                        path_len,
+                    )
+                };
+                unix.path[..path_len].copy_from_slice(src);
+                Ok(SockAddr { unix })
             }
         }
     }
 
+    /// Write `sockaddr` to `addr`, truncated to the length of the buffer read
+    /// from `addrlen`, which is then updated to the length of the whole
+    /// address.
     pub fn write_sockaddr(
         self: &Arc<Task>,
         addr: usize,
@@ -665,26 +680,17 @@ impl Task {
         if unlikely(addr == 0) {
             return Ok(());
         }
-        // TODO: check the permissions
-        unsafe {
-            match SaFamily::try_from(sockaddr.family).unwrap() {
-                SaFamily::AF_INET => {
-                    UserWritePtr::<SockAddrIn>::from(addr).write(self, sockaddr.ipv4)?;
-                    UserWritePtr::<u32>::from(addrlen)
-                        .write(self, mem::size_of::<SockAddrIn>() as u32)?;
-                }
-                SaFamily::AF_INET6 => {
-                    UserWritePtr::<SockAddrIn6>::from(addr).write(self, sockaddr.ipv6)?;
-                    UserWritePtr::<u32>::from(addrlen)
-                        .write(self, mem::size_of::<SockAddrIn6>() as u32)?;
-                }
-                SaFamily::AF_UNIX => {
-                    UserWritePtr::<SockAddrUn>::from(addr).write(self, sockaddr.unix)?;
-                    UserWritePtr::<u32>::from(addrlen)
-                        .write(self, mem::size_of::<SockAddrUn>() as u32)?;
-                }
-            }
+        let buf_len = UserReadPtr::<i32>::from(addrlen).read(self)?;
+        if buf_len < 0 {
+            return Err(SysError::EINVAL);
         }
+        let len = sockaddr.addr_len();
+        let n = len.min(buf_len as usize);
+        let bytes = unsafe { core::slice::from_raw_parts(&sockaddr as *const _ as *const u8, n) };
+        UserWritePtr::<u8>::from(addr)
+            .into_mut_slice(self, n)?
+            .copy_from_slice(bytes);
+        UserWritePtr::<u32>::from(addrlen).write(self, len as u32)?;
         Ok(())
     }
 }
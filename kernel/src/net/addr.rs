@@ -3,13 +3,15 @@
 //!
 //! `IpEndpoint` is host byte order
 
-use alloc::{format, string::String};
+use alloc::{format, string::String, vec::Vec};
 use core::{
     fmt::{self, Display},
+    mem::size_of,
     panic,
 };
 
 use net::{IpAddress, IpEndpoint, IpListenEndpoint, Ipv4Address, Ipv6Address};
+use systype::{SysError, SysResult};
 
 use super::SaFamily;
 
@@ -77,8 +79,33 @@ pub struct SockAddrUn {
     pub path: [u8; 108],
 }
 
+impl SockAddrUn {
+    /// Length of the address as reported to user: the family alone for an
+    /// unnamed socket, up to the terminating null byte of a path, or up to the
+    /// last byte of an abstract name.
+    pub fn addr_len(&self) -> usize {
+        let family_len = size_of::<u16>();
+        if self.path[0] != 0 {
+            let path_len = self
+                .path
+                .iter()
+                .position(|&x| x == 0)
+                .map_or(108, |pos| pos + 1);
+            family_len + path_len
+        } else {
+            family_len + self.path.iter().rposition(|&x| x != 0).map_or(0, |i| i + 1)
+        }
+    }
+}
+
 impl fmt::Display for SockAddrUn {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path[0] == 0 {
+            // an abstract name, or an empty address
+            let end = self.path.iter().rposition(|&x| x != 0).map_or(1, |i| i + 1);
+            let name = String::from_utf8_lossy(&self.path[1..end]);
+            return write!(f, "AF_UNIX: @{}", name);
+        }
         let path = match self.path.iter().position(|&x| x == 0) {
             Some(pos) => String::from_utf8_lossy(&self.path[..pos]),
             None => String::from_utf8_lossy(&self.path),
@@ -115,6 +142,17 @@ impl fmt::Display for SockAddr {
 }
 
 impl SockAddr {
+    /// Length of the address as reported to user.
+    pub fn addr_len(&self) -> usize {
+        unsafe {
+            match SaFamily::try_from(self.family).unwrap() {
+                SaFamily::AF_INET => size_of::<SockAddrIn>(),
+                SaFamily::AF_INET6 => size_of::<SockAddrIn6>(),
+                SaFamily::AF_UNIX => self.unix.addr_len(),
+            }
+        }
+    }
+
     /// You should make sure that `SockAddr` is IpEndpoint
     pub fn into_endpoint(&self) -> IpEndpoint {
         unsafe {
@@ -142,6 +180,36 @@ impl SockAddr {
         }
     }
 
+    /// The port of an `AF_INET` or `AF_INET6` address.
+    pub fn port(&self) -> Option<u16> {
+        unsafe {
+            match SaFamily::try_from(self.family).ok()? {
+                SaFamily::AF_INET => Some(u16::from_be_bytes(self.ipv4.port)),
+                SaFamily::AF_INET6 => Some(u16::from_be_bytes(self.ipv6.port)),
+                SaFamily::AF_UNIX => None,
+            }
+        }
+    }
+
+    /// The path or abstract name of an `AF_UNIX` address, fails with `EINVAL`
+    /// for another family or an empty address.
+    pub fn unix_addr(&self) -> SysResult<UnixAddr> {
+        if unsafe { self.family } != u16::from(SaFamily::AF_UNIX) {
+            return Err(SysError::EINVAL);
+        }
+        let path = unsafe { &self.unix.path };
+        if path[0] == 0 {
+            // NOTE: the length of the address is not kept, so an abstract name
+            // ends at its last non-null byte
+            let len = path.iter().rposition(|&c| c != 0).ok_or(SysError::EINVAL)? + 1;
+            return Ok(UnixAddr::Abstract(path[1..len].to_vec()));
+        }
+        let len = path.iter().position(|&c| c == 0).unwrap_or(path.len());
+        String::from_utf8(path[..len].to_vec())
+            .map(UnixAddr::Path)
+            .map_err(|_| SysError::EINVAL)
+    }
+
     pub fn from_endpoint(endpoint: IpEndpoint) -> Self {
         match endpoint.addr {
             IpAddress::Ipv4(v4) => Self {
@@ -246,6 +314,40 @@ impl fmt::Debug for SockAddrIn6 {
     }
 }
 
+/// Address of an `AF_UNIX` socket, a path in the file system or a name in the
+/// abstract namespace, which starts with a null byte in `sun_path`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnixAddr {
+    Path(String),
+    Abstract(Vec<u8>),
+}
+
+impl UnixAddr {
+    pub fn to_sockaddr(addr: Option<&Self>) -> SockAddrUn {
+        let mut sockaddr = SockAddrUn {
+            family: SaFamily::AF_UNIX.into(),
+            path: [0; 108],
+        };
+        let (start, bytes) = match addr {
+            None => return sockaddr,
+            Some(Self::Path(path)) => (0, path.as_bytes()),
+            Some(Self::Abstract(name)) => (1, name.as_slice()),
+        };
+        let len = bytes.len().min(sockaddr.path.len() - 1 - start);
+        sockaddr.path[start..start + len].copy_from_slice(&bytes[..len]);
+        sockaddr
+    }
+}
+
+impl fmt::Display for UnixAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "{path}"),
+            Self::Abstract(name) => write!(f, "@{}", String::from_utf8_lossy(name)),
+        }
+    }
+}
+
 impl fmt::Debug for SockAddrUn {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(self, f)
@@ -1,8 +1,11 @@
 use systype::SysError;
 pub mod addr;
 pub mod socket;
+mod sockopt;
 mod unix;
 
+pub use unix::UCred;
+
 #[repr(u16)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
@@ -95,8 +98,8 @@ impl TryFrom<usize> for SocketLevel {
             6 => Ok(Self::IPPROTO_TCP),
             41 => Ok(Self::IPPROTO_IPV6),
             level => {
-                log::warn!("[SocketLevel] unsupported level: {level}");
-                Err(Self::Error::EINVAL)
+                log::info!("[SocketLevel] unsupported level: {level}");
+                Err(Self::Error::ENOPROTOOPT)
             }
         }
     }
@@ -176,9 +179,8 @@ impl TryFrom<usize> for SocketOpt {
             32 => Ok(Self::SNDBUFFORCE),
             33 => Ok(Self::RCVBUFFORCE),
             opt => {
-                log::warn!("[SocketOpt] unsupported option: {opt}");
-                Ok(Self::DEBUG)
-                // Err(Self::Error::EINVAL)
+                log::info!("[SocketOpt] unsupported option: {opt}");
+                Err(Self::Error::ENOPROTOOPT)
             }
         }
     }
@@ -202,9 +204,9 @@ impl TryFrom<usize> for TcpSocketOpt {
             2 => Ok(Self::MAXSEG),
             11 => Ok(Self::INFO),
             13 => Ok(Self::CONGESTION),
-            level => {
-                log::warn!("[TcpSocketOpt] unsupported option: {level}");
-                Err(Self::Error::EINVAL)
+            opt => {
+                log::info!("[TcpSocketOpt] unsupported option: {opt}");
+                Err(Self::Error::ENOPROTOOPT)
             }
         }
     }
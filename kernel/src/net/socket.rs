@@ -1,8 +1,8 @@
 use alloc::{boxed::Box, sync::Arc};
+use core::{future::Future, sync::atomic::AtomicBool, time::Duration};
 
 use addr::SockAddr;
 use async_trait::async_trait;
-use log::warn;
 use net::{
     addr::{UNSPECIFIED_ENDPOINT_V4, UNSPECIFIED_IPV4},
     poll_interfaces,
@@ -10,9 +10,12 @@ use net::{
     udp::UdpSocket,
     IpEndpoint, IpListenEndpoint, NetPollState,
 };
+use sockopt::SockOpts;
 use spin::Mutex;
+use sync::mutex::SleepLock;
 use systype::{SysError, SysResult, SyscallResult};
-use unix::UnixSocket;
+use timer::timelimited_task::{TimeLimitedTaskFuture, TimeLimitedTaskOutput};
+use unix::{UCred, UnixSocket};
 use vfs_core::*;
 
 use super::*;
@@ -29,7 +32,7 @@ impl Sock {
         match self {
             Sock::Tcp(tcp) => tcp.set_nonblocking(true),
             Sock::Udp(udp) => udp.set_nonblocking(true),
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => unix.set_nonblocking(true),
         }
     }
 
@@ -54,26 +57,26 @@ impl Sock {
                 }
                 udp.bind(local_addr)
             }
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => unix.bind(local_addr.unix_addr()?),
         }
     }
 
-    pub fn listen(&self) -> SysResult<()> {
+    pub fn listen(&self, backlog: usize) -> SysResult<()> {
         match self {
             Sock::Tcp(tcp) => tcp.listen(current_task().waker_ref().as_ref().unwrap()),
             Sock::Udp(_udp) => Err(SysError::EOPNOTSUPP),
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => unix.listen(backlog),
         }
     }
 
-    pub async fn accept(&self) -> SysResult<TcpSocket> {
+    pub async fn accept(&self) -> SysResult<Sock> {
         match self {
             Sock::Tcp(tcp) => {
                 let new_tcp = tcp.accept().await?;
-                Ok(new_tcp)
+                Ok(Sock::Tcp(new_tcp))
             }
             Sock::Udp(_udp) => Err(SysError::EOPNOTSUPP),
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => Ok(Sock::Unix(unix.accept().await?)),
         }
     }
 
@@ -87,7 +90,7 @@ impl Sock {
                 let remote_addr = remote_addr.into_endpoint();
                 udp.connect(remote_addr)
             }
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => unix.connect(remote_addr.unix_addr()?).await,
         }
     }
 
@@ -101,7 +104,9 @@ impl Sock {
                 let peer_addr = SockAddr::from_endpoint(udp.peer_addr()?);
                 Ok(peer_addr)
             }
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => Ok(SockAddr {
+                unix: unix.peer_addr()?,
+            }),
         }
     }
 
@@ -115,7 +120,9 @@ impl Sock {
                 let local_addr = SockAddr::from_endpoint(udp.local_addr()?);
                 Ok(local_addr)
             }
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => Ok(SockAddr {
+                unix: unix.local_addr(),
+            }),
         }
     }
     pub async fn sendto(&self, buf: &[u8], remote_addr: Option<SockAddr>) -> SysResult<usize> {
@@ -125,7 +132,10 @@ impl Sock {
                 Some(addr) => udp.send_to(buf, addr.into_endpoint()).await,
                 None => udp.send(buf).await,
             },
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => {
+                let to = remote_addr.map(|addr| addr.unix_addr()).transpose()?;
+                unix.send(buf, to, None).await
+            }
         }
     }
     pub async fn recvfrom(&self, buf: &mut [u8]) -> SysResult<(usize, SockAddr)> {
@@ -138,14 +148,17 @@ impl Sock {
                 let (len, endpoint) = udp.recv_from(buf).await?;
                 Ok((len, SockAddr::from_endpoint(endpoint)))
             }
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => {
+                let (bytes, addr, _) = unix.recv(buf, false).await?;
+                Ok((bytes, SockAddr { unix: addr }))
+            }
         }
     }
     pub async fn poll(&self) -> NetPollState {
         match self {
             Sock::Tcp(tcp) => tcp.poll().await,
             Sock::Udp(udp) => udp.poll().await,
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => unix.poll().await,
         }
     }
 
@@ -153,9 +166,33 @@ impl Sock {
         match self {
             Sock::Tcp(tcp) => tcp.shutdown(how),
             Sock::Udp(udp) => udp.shutdown(),
-            Sock::Unix(_) => unimplemented!(),
+            Sock::Unix(unix) => unix.shutdown(how),
         }
     }
+
+    /// Takes the pending error of an operation that finished asynchronously.
+    pub fn take_error(&self) -> Option<SysError> {
+        match self {
+            Sock::Tcp(tcp) => tcp.take_error(),
+            Sock::Udp(_) | Sock::Unix(_) => None,
+        }
+    }
+}
+
+/// Run a blocking operation on a socket, which fails with `err` if it does
+/// not finish before `timeout`.
+async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    err: SysError,
+    future: impl Future<Output = SysResult<T>> + Send,
+) -> SysResult<T> {
+    match timeout {
+        Some(timeout) => match TimeLimitedTaskFuture::new(timeout, future).await {
+            TimeLimitedTaskOutput::Ok(ret) => ret,
+            TimeLimitedTaskOutput::TimeOut => Err(err),
+        },
+        None => future.await,
+    }
 }
 
 /// linux中，socket面向用户空间，sock面向内核空间
@@ -166,6 +203,8 @@ pub struct Socket {
     pub sk: Sock,
     /// File metadata, including metadata information related to sockets
     pub meta: FileMeta,
+    /// Options set by `setsockopt`
+    pub opts: Mutex<SockOpts>,
 }
 
 unsafe impl Sync for Socket {}
@@ -174,13 +213,29 @@ unsafe impl Send for Socket {}
 impl Socket {
     pub fn new(domain: SaFamily, types: SocketType, nonblock: bool) -> Self {
         let sk = match domain {
-            SaFamily::AF_UNIX => Sock::Unix(UnixSocket {}),
+            SaFamily::AF_UNIX => match types {
+                SocketType::DGRAM => Sock::Unix(UnixSocket::new_dgram()),
+                _ => Sock::Unix(UnixSocket::new()),
+            },
             SaFamily::AF_INET | SaFamily::AF_INET6 => match types {
                 SocketType::STREAM => Sock::Tcp(TcpSocket::new_v4()),
                 SocketType::DGRAM => Sock::Udp(UdpSocket::new()),
                 _ => unimplemented!(),
             },
         };
+        Self::from_sock(types, sk, nonblock)
+    }
+
+    /// Create a pair of unix sockets connected to each other.
+    pub fn new_unix_pair(types: SocketType, nonblock: bool) -> (Self, Self) {
+        let (sk0, sk1) = UnixSocket::pair();
+        (
+            Self::from_sock(types, Sock::Unix(sk0), nonblock),
+            Self::from_sock(types, Sock::Unix(sk1), nonblock),
+        )
+    }
+
+    fn from_sock(types: SocketType, sk: Sock, nonblock: bool) -> Self {
         let flags = if nonblock {
             sk.set_nonblocking();
             OpenFlags::O_RDWR | OpenFlags::O_NONBLOCK
@@ -194,12 +249,22 @@ impl Socket {
                 dentry: Arc::<usize>::new_zeroed(),
                 inode: Arc::<usize>::new_zeroed(),
                 pos: 0.into(),
+                pos_lock: SleepLock::new(()),
                 flags: Mutex::new(flags),
+                super_block: None,
+                write_access: AtomicBool::new(false),
             },
+            opts: Mutex::new(SockOpts::default()),
         }
     }
 
+    /// Create the socket of a connection accepted by `another`, which
+    /// inherits its options like linux.
     pub fn from_another(another: &Self, sk: Sock) -> Self {
+        let opts = *another.opts.lock();
+        if let Sock::Tcp(tcp) = &sk {
+            tcp.set_nodelay(opts.nodelay);
+        }
         Self {
             types: another.types,
             sk,
@@ -207,10 +272,70 @@ impl Socket {
                 dentry: Arc::<usize>::new_zeroed(),
                 inode: Arc::<usize>::new_zeroed(),
                 pos: 0.into(),
+                pos_lock: SleepLock::new(()),
                 flags: Mutex::new(OpenFlags::O_RDWR),
+                super_block: None,
+                write_access: AtomicBool::new(false),
             },
+            opts: Mutex::new(opts),
         }
     }
+
+    /// Accept a connection, giving up after `SO_RCVTIMEO`.
+    pub async fn accept(&self) -> SysResult<Sock> {
+        let timeout = self.opts.lock().rcvtimeo;
+        with_timeout(timeout, SysError::EAGAIN, self.sk.accept()).await
+    }
+
+    /// Connect to `remote_addr`, which goes on in the background after
+    /// `SO_SNDTIMEO`.
+    pub async fn connect(&self, remote_addr: SockAddr) -> SysResult<()> {
+        let timeout = self.opts.lock().sndtimeo;
+        with_timeout(timeout, SysError::EINPROGRESS, self.sk.connect(remote_addr)).await
+    }
+
+    /// Send `buf`, giving up after `SO_SNDTIMEO`.
+    pub async fn sendto(&self, buf: &[u8], remote_addr: Option<SockAddr>) -> SysResult<usize> {
+        let timeout = self.opts.lock().sndtimeo;
+        with_timeout(timeout, SysError::EAGAIN, self.sk.sendto(buf, remote_addr)).await
+    }
+
+    /// Receive into `buf`, giving up after `SO_RCVTIMEO`.
+    pub async fn recvfrom(&self, buf: &mut [u8]) -> SysResult<(usize, SockAddr)> {
+        let timeout = self.opts.lock().rcvtimeo;
+        with_timeout(timeout, SysError::EAGAIN, self.sk.recvfrom(buf)).await
+    }
+
+    /// Send `buf` like `sendto`, with the credentials `cred` on a unix socket.
+    pub async fn sendmsg(
+        &self,
+        buf: &[u8],
+        remote_addr: Option<SockAddr>,
+        cred: Option<UCred>,
+    ) -> SysResult<usize> {
+        let Sock::Unix(unix) = &self.sk else {
+            return self.sendto(buf, remote_addr).await;
+        };
+        let to = remote_addr.map(|addr| addr.unix_addr()).transpose()?;
+        let timeout = self.opts.lock().sndtimeo;
+        with_timeout(timeout, SysError::EAGAIN, unix.send(buf, to, cred)).await
+    }
+
+    /// Receive into `buf` like `recvfrom`, with the credentials of the sender
+    /// on a unix socket with `SO_PASSCRED`.
+    pub async fn recvmsg(&self, buf: &mut [u8]) -> SysResult<(usize, SockAddr, Option<UCred>)> {
+        let Sock::Unix(unix) = &self.sk else {
+            let (bytes, addr) = self.recvfrom(buf).await?;
+            return Ok((bytes, addr, None));
+        };
+        let (timeout, passcred) = {
+            let opts = self.opts.lock();
+            (opts.rcvtimeo, opts.passcred)
+        };
+        let (bytes, addr, cred) =
+            with_timeout(timeout, SysError::EAGAIN, unix.recv(buf, passcred)).await?;
+        Ok((bytes, SockAddr { unix: addr }, cred))
+    }
 }
 
 #[async_trait]
@@ -233,8 +358,8 @@ impl File for Socket {
         }
         // TODO: should add this?
         // poll_interfaces();
-        let bytes = self.sk.recvfrom(buf).await.map(|e| e.0)?;
-        warn!(
+        let bytes = self.recvfrom(buf).await.map(|e| e.0)?;
+        log::debug!(
             "[Socket::File::read_at] expect to recv: {:?} exact: {bytes}",
             buf.len()
         );
@@ -247,8 +372,8 @@ impl File for Socket {
         }
         // TODO: should add this?
         // poll_interfaces();
-        let bytes = self.sk.sendto(buf, None).await?;
-        warn!(
+        let bytes = self.sendto(buf, None).await?;
+        log::debug!(
             "[Socket::File::write_at] expect to send: {:?} bytes exact: {bytes}",
             buf.len()
         );
@@ -266,15 +391,22 @@ impl File for Socket {
             res |= PollEvents::OUT;
         }
         if netstate.hangup {
-            log::warn!("[Socket::bask_poll] PollEvents is hangup");
+            log::debug!("[Socket::bask_poll] PollEvents is hangup");
             res |= PollEvents::HUP;
         }
         log::info!("[Socket::base_poll] ret events:{res:?} {netstate:?}");
         res
     }
 
+    fn base_fasync(&self, file: &Arc<dyn File>, fd: usize, on: bool) {
+        // NOTE: tcp and udp sockets are polled without any event of getting ready
+        if let Sock::Unix(unix) = &self.sk {
+            unix.fasync(file, fd, on);
+        }
+    }
+
     fn ioctl(&self, _cmd: usize, _arg: usize) -> SyscallResult {
-        log::warn!("[Socket::File::ioctl] not supported now, return 0 instead");
+        log::info!("[Socket::File::ioctl] not supported now, return 0 instead");
         Ok(0)
     }
 }
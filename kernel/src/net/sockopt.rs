@@ -0,0 +1,216 @@
+//! Options of `setsockopt` and `getsockopt`, dispatched by level and then
+//! by the protocol of the socket.
+
+use alloc::vec::Vec;
+use core::{mem::size_of, time::Duration};
+
+use net::tcp::TcpSocket;
+use systype::{SysError, SysResult};
+use time::timeval::TimeVal;
+
+use super::{
+    socket::{Sock, Socket},
+    SocketLevel, SocketOpt, TcpSocketOpt, UCred,
+};
+
+/// Default and max size of the socket buffers, as linux `net.core.rmem_default`
+/// and `net.core.rmem_max`, the same goes for the send buffer.
+const SOCK_BUF_DEFAULT: usize = 212992;
+const SOCK_BUF_MAX: usize = 212992;
+/// Least size of the socket buffers, as linux `SOCK_MIN_RCVBUF` and
+/// `SOCK_MIN_SNDBUF`.
+const SOCK_MIN_RCVBUF: usize = 2304;
+const SOCK_MIN_SNDBUF: usize = 4608;
+
+/// Max segment size reported for `TCP_MAXSEG`.
+const TCP_MAX_SEGMENT_SIZE: i32 = 1460;
+/// Length of the name of a congestion control algorithm, as linux
+/// `TCP_CA_NAME_MAX`.
+const TCP_CA_NAME_MAX: usize = 16;
+
+/// Options of a socket, which an accepted socket inherits from the listening
+/// one.
+#[derive(Debug, Clone, Copy)]
+pub struct SockOpts {
+    pub rcvbuf: usize,
+    pub sndbuf: usize,
+    pub reuseaddr: bool,
+    pub keepalive: bool,
+    /// Timeout of blocking receives and accepts, `None` blocks forever.
+    pub rcvtimeo: Option<Duration>,
+    /// Timeout of blocking sends and connects, `None` blocks forever.
+    pub sndtimeo: Option<Duration>,
+    pub nodelay: bool,
+    /// Receive the credentials of the sender on a unix socket.
+    pub passcred: bool,
+}
+
+impl Default for SockOpts {
+    fn default() -> Self {
+        Self {
+            rcvbuf: SOCK_BUF_DEFAULT,
+            sndbuf: SOCK_BUF_DEFAULT,
+            reuseaddr: false,
+            keepalive: false,
+            rcvtimeo: None,
+            sndtimeo: None,
+            nodelay: false,
+            passcred: false,
+        }
+    }
+}
+
+fn read_int(optval: &[u8]) -> SysResult<i32> {
+    let bytes = optval.get(..size_of::<i32>()).ok_or(SysError::EINVAL)?;
+    Ok(i32::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_timeout(optval: &[u8]) -> SysResult<Option<Duration>> {
+    if optval.len() < size_of::<TimeVal>() {
+        return Err(SysError::EINVAL);
+    }
+    let timeout = unsafe { (optval.as_ptr() as *const TimeVal).read_unaligned() };
+    if !timeout.is_valid() {
+        return Err(SysError::EDOM);
+    }
+    // zero means no timeout
+    Ok((!timeout.is_zero()).then(|| timeout.into()))
+}
+
+fn int_val(val: i32) -> Vec<u8> {
+    val.to_ne_bytes().to_vec()
+}
+
+fn timeout_val(timeout: Option<Duration>) -> Vec<u8> {
+    let timeout = timeout.map_or(TimeVal::ZERO, TimeVal::from);
+    let bytes = unsafe {
+        core::slice::from_raw_parts(
+            &timeout as *const TimeVal as *const u8,
+            size_of::<TimeVal>(),
+        )
+    };
+    bytes.to_vec()
+}
+
+fn cred_val(cred: UCred) -> Vec<u8> {
+    let bytes = unsafe {
+        core::slice::from_raw_parts(&cred as *const UCred as *const u8, size_of::<UCred>())
+    };
+    bytes.to_vec()
+}
+
+/// Like linux, the size asked for is doubled to leave room for bookkeeping.
+fn buf_size(val: i32, min: usize) -> usize {
+    let val = (val.max(0) as usize).min(SOCK_BUF_MAX);
+    (val * 2).max(min)
+}
+
+impl Socket {
+    pub fn setsockopt(&self, level: SocketLevel, optname: usize, optval: &[u8]) -> SysResult<()> {
+        match level {
+            SocketLevel::SOL_SOCKET => self.set_socket_opt(SocketOpt::try_from(optname)?, optval),
+            SocketLevel::IPPROTO_TCP => match &self.sk {
+                Sock::Tcp(tcp) => self.set_tcp_opt(tcp, TcpSocketOpt::try_from(optname)?, optval),
+                _ => Err(SysError::EOPNOTSUPP),
+            },
+            SocketLevel::IPPROTO_IP | SocketLevel::IPPROTO_IPV6 => match &self.sk {
+                Sock::Unix(_) => Err(SysError::EOPNOTSUPP),
+                _ => {
+                    log::info!("[Socket::setsockopt] ignore {level:?} opt {optname}");
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    /// Returns the value of the option, of which the caller copies as much as
+    /// the user buffer holds.
+    pub fn getsockopt(&self, level: SocketLevel, optname: usize) -> SysResult<Vec<u8>> {
+        match level {
+            SocketLevel::SOL_SOCKET => self.get_socket_opt(SocketOpt::try_from(optname)?),
+            SocketLevel::IPPROTO_TCP => match &self.sk {
+                Sock::Tcp(_) => self.get_tcp_opt(TcpSocketOpt::try_from(optname)?),
+                _ => Err(SysError::EOPNOTSUPP),
+            },
+            SocketLevel::IPPROTO_IP | SocketLevel::IPPROTO_IPV6 => match &self.sk {
+                Sock::Unix(_) => Err(SysError::EOPNOTSUPP),
+                _ => Err(SysError::ENOPROTOOPT),
+            },
+        }
+    }
+
+    fn set_socket_opt(&self, opt: SocketOpt, optval: &[u8]) -> SysResult<()> {
+        let val = read_int(optval)?;
+        let mut opts = self.opts.lock();
+        match opt {
+            SocketOpt::RCVBUF | SocketOpt::RCVBUFFORCE => {
+                opts.rcvbuf = buf_size(val, SOCK_MIN_RCVBUF)
+            }
+            SocketOpt::SNDBUF | SocketOpt::SNDBUFFORCE => {
+                opts.sndbuf = buf_size(val, SOCK_MIN_SNDBUF)
+            }
+            SocketOpt::REUSEADDR => opts.reuseaddr = val != 0,
+            SocketOpt::KEEPALIVE => opts.keepalive = val != 0,
+            SocketOpt::PASSCRED => opts.passcred = val != 0,
+            SocketOpt::RCVTIMEO_OLD => opts.rcvtimeo = read_timeout(optval)?,
+            SocketOpt::SNDTIMEO_OLD => opts.sndtimeo = read_timeout(optval)?,
+            // read only
+            SocketOpt::TYPE | SocketOpt::ERROR | SocketOpt::PEERCRED => {
+                return Err(SysError::ENOPROTOOPT)
+            }
+            opt => log::info!("[Socket::setsockopt] ignore SOL_SOCKET opt {opt:?}"),
+        }
+        Ok(())
+    }
+
+    fn get_socket_opt(&self, opt: SocketOpt) -> SysResult<Vec<u8>> {
+        let opts = *self.opts.lock();
+        let val = match opt {
+            SocketOpt::RCVBUF => int_val(opts.rcvbuf as i32),
+            SocketOpt::SNDBUF => int_val(opts.sndbuf as i32),
+            SocketOpt::REUSEADDR => int_val(opts.reuseaddr as i32),
+            SocketOpt::KEEPALIVE => int_val(opts.keepalive as i32),
+            SocketOpt::PASSCRED => int_val(opts.passcred as i32),
+            SocketOpt::PEERCRED => match &self.sk {
+                Sock::Unix(unix) => cred_val(unix.peer_cred()),
+                _ => return Err(SysError::ENOPROTOOPT),
+            },
+            SocketOpt::RCVTIMEO_OLD => timeout_val(opts.rcvtimeo),
+            SocketOpt::SNDTIMEO_OLD => timeout_val(opts.sndtimeo),
+            SocketOpt::TYPE => int_val(self.types as i32),
+            SocketOpt::ERROR => int_val(self.sk.take_error().map_or(0, |err| err as i32)),
+            opt => {
+                log::info!("[Socket::getsockopt] unsupported SOL_SOCKET opt {opt:?}");
+                return Err(SysError::ENOPROTOOPT);
+            }
+        };
+        Ok(val)
+    }
+
+    fn set_tcp_opt(&self, tcp: &TcpSocket, opt: TcpSocketOpt, optval: &[u8]) -> SysResult<()> {
+        match opt {
+            TcpSocketOpt::NODELAY => {
+                let nodelay = read_int(optval)? != 0;
+                self.opts.lock().nodelay = nodelay;
+                tcp.set_nodelay(nodelay);
+            }
+            opt => log::info!("[Socket::setsockopt] ignore IPPROTO_TCP opt {opt:?}"),
+        }
+        Ok(())
+    }
+
+    fn get_tcp_opt(&self, opt: TcpSocketOpt) -> SysResult<Vec<u8>> {
+        let val = match opt {
+            TcpSocketOpt::NODELAY => int_val(self.opts.lock().nodelay as i32),
+            TcpSocketOpt::MAXSEG => int_val(TCP_MAX_SEGMENT_SIZE),
+            TcpSocketOpt::CONGESTION => {
+                let mut name = b"reno".to_vec();
+                name.resize(TCP_CA_NAME_MAX, 0);
+                name
+            }
+            // not tracked by the stack
+            TcpSocketOpt::INFO => Vec::new(),
+        };
+        Ok(val)
+    }
+}
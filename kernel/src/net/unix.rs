@@ -1 +1,986 @@
-pub struct UnixSocket {}
+//! Unix domain sockets of `SOCK_STREAM` and `SOCK_DGRAM`.
+//!
+//! A connection is a pair of pipes, one for each direction, so that closing
+//! one end makes the peer read EOF and fail to write with `EPIPE`, as for a
+//! pipe. `bind` creates a socket inode at the path, or takes one made by
+//! mknod, through which `connect` finds the listening socket. An address in
+//! the abstract namespace is only a name in the kernel, with no inode.
+//!
+//! The credentials of each send travel next to the bytes, so that receivers
+//! with `SO_PASSCRED` learn who sent them, see `CredRuns`.
+//!
+//! A datagram socket has a queue of the datagrams sent to it, each received
+//! whole at once with the address of its sender. Datagrams are sent to a
+//! socket bound to the destination, or to the one connected to without it.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use async_utils::{get_waker, suspend_now};
+use config::fs::PIPE_BUF_LEN;
+use net::NetPollState;
+use signal::{Sig, SigDetails, SigInfo};
+use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SysResult};
+use vfs::pipefs::new_pipe;
+use vfs_core::{AtFd, File, Inode, InodeMode, InodeType, OpenFlags, PollEvents};
+
+use super::addr::{SockAddrUn, UnixAddr};
+use crate::{
+    processor::hart::current_task,
+    task::cred::{Capabilities, Gid, Uid},
+};
+
+type Mutex<T> = SpinNoIrqLock<T>;
+
+/// What a socket is bound to, the socket inode at a path, by its address, or
+/// a name in the abstract namespace.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum BindKey {
+    Inode(usize),
+    Abstract(Vec<u8>),
+}
+
+impl BindKey {
+    fn of_inode(inode: &Arc<dyn Inode>) -> Self {
+        Self::Inode(Arc::as_ptr(inode) as *const () as usize)
+    }
+}
+
+/// Listening sockets, by what they are bound to.
+static LISTENERS: Mutex<BTreeMap<BindKey, Arc<UnixListener>>> = Mutex::new(BTreeMap::new());
+
+/// Receive queues of bound datagram sockets, by what they are bound to.
+static DGRAM_SOCKETS: Mutex<BTreeMap<BindKey, Arc<DgramQueue>>> = Mutex::new(BTreeMap::new());
+
+/// What some socket is bound to, which is also the table of abstract names in
+/// use.
+static BOUND: Mutex<BTreeSet<BindKey>> = Mutex::new(BTreeSet::new());
+
+/// Credentials of a process as `SO_PEERCRED` and `SCM_CREDENTIALS` pass them,
+/// `struct ucred`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct UCred {
+    pub pid: u32,
+    pub uid: Uid,
+    pub gid: Gid,
+}
+
+impl UCred {
+    /// What `SO_PEERCRED` reports without a peer, like linux.
+    const NONE: Self = Self {
+        pid: 0,
+        uid: Uid::MAX,
+        gid: Gid::MAX,
+    };
+
+    /// Credentials of the current process.
+    pub fn current() -> Self {
+        let task = current_task();
+        let cred = task.cred_ref();
+        Self {
+            pid: task.pid() as u32,
+            uid: cred.euid,
+            gid: cred.egid,
+        }
+    }
+
+    /// Check that the current process may send these credentials. It may send
+    /// its own pid and any of its user and group IDs, others need
+    /// `CAP_SYS_ADMIN`, `CAP_SETUID` and `CAP_SETGID` respectively.
+    pub fn check(&self) -> SysResult<()> {
+        let task = current_task();
+        let cred = task.cred_ref();
+        let pid_ok = self.pid == task.pid() as u32 || cred.has_cap(Capabilities::SYS_ADMIN);
+        let uid_ok = [cred.uid, cred.euid, cred.suid].contains(&self.uid)
+            || cred.has_cap(Capabilities::SETUID);
+        let gid_ok = [cred.gid, cred.egid, cred.sgid].contains(&self.gid)
+            || cred.has_cap(Capabilities::SETGID);
+        if !(pid_ok && uid_ok && gid_ok) {
+            return Err(SysError::EPERM);
+        }
+        Ok(())
+    }
+}
+
+/// Bytes sent at once with the same credentials.
+struct CredRun {
+    id: usize,
+    len: usize,
+    cred: UCred,
+}
+
+/// Credentials of the bytes in flight in one direction of a connection, as
+/// runs in the order the bytes were sent. They are taken when the send starts,
+/// so they stay right after the sender changed or is gone.
+// NOTE: concurrent sends on one socket may interleave their bytes in the pipe,
+// so that their runs only add up to the bytes in total
+#[derive(Default)]
+struct CredRuns {
+    runs: VecDeque<CredRun>,
+    next_id: usize,
+}
+
+impl CredRuns {
+    fn push(&mut self, len: usize, cred: UCred) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.runs.push_back(CredRun { id, len, cred });
+        id
+    }
+
+    /// The send of run `id` wrote `len` bytes only. The bytes not written
+    /// can not have been read, so the run is still there.
+    fn truncate(&mut self, id: usize, len: usize) {
+        if let Some(i) = self.runs.iter().position(|run| run.id == id) {
+            self.runs[i].len = self.runs[i].len.min(len);
+            if self.runs[i].len == 0 {
+                self.runs.remove(i);
+            }
+        }
+    }
+
+    /// Length of the bytes at the front sent with the same credentials.
+    fn front_len(&self) -> Option<usize> {
+        let cred = self.runs.front()?.cred;
+        Some(
+            self.runs
+                .iter()
+                .take_while(|run| run.cred == cred)
+                .map(|run| run.len)
+                .sum(),
+        )
+    }
+
+    /// Take `len` bytes read, returning the credentials of the first one.
+    fn consume(&mut self, mut len: usize) -> Option<UCred> {
+        let cred = self.runs.front().map(|run| run.cred);
+        while len > 0 {
+            let Some(run) = self.runs.front_mut() else {
+                break;
+            };
+            let n = run.len.min(len);
+            run.len -= n;
+            len -= n;
+            if run.len == 0 {
+                self.runs.pop_front();
+            }
+        }
+        cred
+    }
+}
+
+/// A datagram with the address of its sender, unnamed if `None`.
+struct Datagram {
+    from: Option<UnixAddr>,
+    data: Vec<u8>,
+    cred: UCred,
+}
+
+/// Datagrams sent to a datagram socket and not received yet.
+struct DgramQueue {
+    inner: Mutex<DgramQueueInner>,
+}
+
+struct DgramQueueInner {
+    datagrams: VecDeque<Datagram>,
+    /// Bytes of the datagrams in the queue, at most as many as a pipe holds.
+    len: usize,
+    /// Set once the socket is closed.
+    closed: bool,
+    /// Tasks waiting for a datagram, and for room to send one.
+    wakers: VecDeque<Waker>,
+}
+
+impl DgramQueue {
+    fn new() -> Self {
+        Self {
+            inner: Mutex::new(DgramQueueInner {
+                datagrams: VecDeque::new(),
+                len: 0,
+                closed: false,
+                wakers: VecDeque::new(),
+            }),
+        }
+    }
+}
+
+impl DgramQueueInner {
+    /// Whether a datagram of `len` bytes fits. One bigger than the queue can
+    /// hold is taken when the queue is empty.
+    fn has_room(&self, len: usize) -> bool {
+        self.datagrams.is_empty() || self.len + len <= PIPE_BUF_LEN
+    }
+
+    fn wait(&mut self, waker: &Waker) {
+        if !self.wakers.iter().any(|w| w.will_wake(waker)) {
+            self.wakers.push_back(waker.clone());
+        }
+    }
+
+    fn wake_all(&mut self) {
+        while let Some(waker) = self.wakers.pop_front() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct UnixSocket {
+    inner: Mutex<UnixSocketInner>,
+    nonblock: AtomicBool,
+}
+
+struct UnixSocketInner {
+    /// Address this socket is bound to.
+    local_addr: Option<UnixAddr>,
+    /// Address the peer is bound to.
+    peer_addr: Option<UnixAddr>,
+    /// Socket inode bound to, kept alive so that its key is not reused.
+    inode: Option<Arc<dyn Inode>>,
+    /// Key of what `bind` bound to.
+    bound: Option<BindKey>,
+    state: UnixState,
+}
+
+enum UnixState {
+    Unconnected,
+    Listening(Arc<UnixListener>),
+    Connected {
+        read_end: Arc<dyn File>,
+        write_end: Arc<dyn File>,
+        /// Credentials of the bytes to read.
+        recv_creds: Arc<Mutex<CredRuns>>,
+        /// Credentials of the bytes written, which the peer reads.
+        send_creds: Arc<Mutex<CredRuns>>,
+        /// Credentials of the peer when the connection was made.
+        peer_cred: UCred,
+    },
+    /// A datagram socket, which receives from `queue`, and sends to `peer`
+    /// when connected.
+    Datagram {
+        queue: Arc<DgramQueue>,
+        peer: Option<Arc<DgramQueue>>,
+    },
+}
+
+struct UnixListener {
+    inner: Mutex<UnixListenerInner>,
+    /// Credentials of the listening process, the peer of its clients.
+    cred: UCred,
+}
+
+struct UnixListenerInner {
+    /// Max count of connections waiting to be accepted.
+    backlog: usize,
+    /// Server ends of the connections not accepted yet.
+    pending: VecDeque<UnixSocket>,
+    /// Set once the listening socket is closed.
+    closed: bool,
+    /// Tasks in `accept` waiting for a connection, and in `connect` waiting
+    /// for room in the backlog.
+    wakers: VecDeque<Waker>,
+}
+
+impl UnixListenerInner {
+    fn wait(&mut self, waker: &Waker) {
+        if !self.wakers.iter().any(|w| w.will_wake(waker)) {
+            self.wakers.push_back(waker.clone());
+        }
+    }
+
+    fn wake_all(&mut self) {
+        while let Some(waker) = self.wakers.pop_front() {
+            waker.wake();
+        }
+    }
+}
+
+impl UnixSocket {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(UnixSocketInner {
+                local_addr: None,
+                peer_addr: None,
+                inode: None,
+                bound: None,
+                state: UnixState::Unconnected,
+            }),
+            nonblock: AtomicBool::new(false),
+        }
+    }
+
+    pub fn new_dgram() -> Self {
+        let socket = Self::new();
+        socket.inner.lock().state = UnixState::Datagram {
+            queue: Arc::new(DgramQueue::new()),
+            peer: None,
+        };
+        socket
+    }
+
+    fn new_connected(
+        local_addr: Option<UnixAddr>,
+        peer_addr: Option<UnixAddr>,
+        state: UnixState,
+    ) -> Self {
+        Self {
+            inner: Mutex::new(UnixSocketInner {
+                local_addr,
+                peer_addr,
+                inode: None,
+                bound: None,
+                state,
+            }),
+            nonblock: AtomicBool::new(false),
+        }
+    }
+
+    /// States of the two ends of a new connection, which reads from `pipes.0`
+    /// and writes to `pipes.1` on the first end, the other way round on the
+    /// second end. Each end has the credentials of the other as its peer's.
+    fn connection(
+        pipes: (
+            (Arc<dyn File>, Arc<dyn File>),
+            (Arc<dyn File>, Arc<dyn File>),
+        ),
+        creds: (UCred, UCred),
+    ) -> (UnixState, UnixState) {
+        let ((read_end0, write_end1), (read_end1, write_end0)) = pipes;
+        let creds0 = Arc::new(Mutex::new(CredRuns::default()));
+        let creds1 = Arc::new(Mutex::new(CredRuns::default()));
+        (
+            UnixState::Connected {
+                read_end: read_end0,
+                write_end: write_end0,
+                recv_creds: creds0.clone(),
+                send_creds: creds1.clone(),
+                peer_cred: creds.1,
+            },
+            UnixState::Connected {
+                read_end: read_end1,
+                write_end: write_end1,
+                recv_creds: creds1,
+                send_creds: creds0,
+                peer_cred: creds.0,
+            },
+        )
+    }
+
+    /// Create two sockets connected to each other, as by `socketpair`. Neither
+    /// of them is bound to a path, and both have the current process as peer.
+    pub fn pair() -> (UnixSocket, UnixSocket) {
+        let cred = UCred::current();
+        let (state0, state1) = Self::connection(
+            (
+                new_pipe(PIPE_BUF_LEN, OpenFlags::empty()),
+                new_pipe(PIPE_BUF_LEN, OpenFlags::empty()),
+            ),
+            (cred, cred),
+        );
+        (
+            Self::new_connected(None, None, state0),
+            Self::new_connected(None, None, state1),
+        )
+    }
+
+    pub fn set_nonblocking(&self, nonblock: bool) {
+        self.nonblock.store(nonblock, Ordering::Relaxed);
+        let flags = if nonblock {
+            OpenFlags::O_NONBLOCK
+        } else {
+            OpenFlags::empty()
+        };
+        if let UnixState::Connected {
+            read_end,
+            write_end,
+            ..
+        } = &self.inner.lock().state
+        {
+            read_end.set_flags(OpenFlags::O_RDONLY | flags);
+            write_end.set_flags(OpenFlags::O_WRONLY | flags);
+        }
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.nonblock.load(Ordering::Relaxed)
+    }
+
+    fn is_dgram(&self) -> bool {
+        matches!(self.inner.lock().state, UnixState::Datagram { .. })
+    }
+
+    /// Bind to `addr`. A path gets a socket inode created there, or the one
+    /// made there by mknod which no socket is bound to.
+    pub fn bind(&self, addr: UnixAddr) -> SysResult<()> {
+        let mut inner = self.inner.lock();
+        if inner.local_addr.is_some() {
+            return Err(SysError::EINVAL);
+        }
+        let (key, inode) = match &addr {
+            UnixAddr::Path(path) => {
+                let inode = bind_inode(path)?;
+                (BindKey::of_inode(&inode), Some(inode))
+            }
+            UnixAddr::Abstract(name) => (BindKey::Abstract(name.clone()), None),
+        };
+        if !BOUND.lock().insert(key.clone()) {
+            return Err(SysError::EADDRINUSE);
+        }
+        if let UnixState::Datagram { queue, .. } = &inner.state {
+            DGRAM_SOCKETS.lock().insert(key.clone(), queue.clone());
+        }
+        inner.inode = inode;
+        inner.bound = Some(key);
+        inner.local_addr = Some(addr);
+        Ok(())
+    }
+
+    pub fn listen(&self, backlog: usize) -> SysResult<()> {
+        let mut inner = self.inner.lock();
+        match &inner.state {
+            UnixState::Unconnected => {}
+            UnixState::Listening(listener) => {
+                listener.inner.lock().backlog = backlog.max(1);
+                return Ok(());
+            }
+            UnixState::Connected { .. } => return Err(SysError::EINVAL),
+            UnixState::Datagram { .. } => return Err(SysError::EOPNOTSUPP),
+        }
+        // NOTE: Linux autobinds an unbound socket here, which is not supported
+        let key = inner.bound.clone().ok_or(SysError::EINVAL)?;
+        let listener = Arc::new(UnixListener {
+            inner: Mutex::new(UnixListenerInner {
+                backlog: backlog.max(1),
+                pending: VecDeque::new(),
+                closed: false,
+                wakers: VecDeque::new(),
+            }),
+            cred: UCred::current(),
+        });
+        LISTENERS.lock().insert(key, listener.clone());
+        inner.state = UnixState::Listening(listener);
+        Ok(())
+    }
+
+    pub async fn accept(&self) -> SysResult<UnixSocket> {
+        let listener = match &self.inner.lock().state {
+            UnixState::Listening(listener) => listener.clone(),
+            UnixState::Datagram { .. } => return Err(SysError::EOPNOTSUPP),
+            _ => return Err(SysError::EINVAL),
+        };
+        if self.is_nonblocking() && listener.inner.lock().pending.is_empty() {
+            return Err(SysError::EAGAIN);
+        }
+        AcceptFuture { listener }.await
+    }
+
+    pub async fn connect(&self, addr: UnixAddr) -> SysResult<()> {
+        let dgram = match &self.inner.lock().state {
+            UnixState::Unconnected => false,
+            UnixState::Datagram { .. } => true,
+            UnixState::Listening(_) => return Err(SysError::EINVAL),
+            UnixState::Connected { .. } => return Err(SysError::EISCONN),
+        };
+        let key = peer_key(&addr)?;
+        if dgram {
+            // a datagram socket may connect again, to another peer
+            let queue = dgram_queue(&key)?;
+            let mut inner = self.inner.lock();
+            if let UnixState::Datagram { peer, .. } = &mut inner.state {
+                *peer = Some(queue);
+            }
+            inner.peer_addr = Some(addr);
+            return Ok(());
+        }
+        let listener = LISTENERS
+            .lock()
+            .get(&key)
+            .cloned()
+            .ok_or(SysError::ECONNREFUSED)?;
+        if self.is_nonblocking() {
+            let inner = listener.inner.lock();
+            if inner.pending.len() >= inner.backlog {
+                return Err(SysError::EAGAIN);
+            }
+        }
+        BacklogFuture {
+            listener: listener.clone(),
+        }
+        .await?;
+
+        let flags = if self.is_nonblocking() {
+            OpenFlags::O_NONBLOCK
+        } else {
+            OpenFlags::empty()
+        };
+        let to_server = new_pipe(PIPE_BUF_LEN, OpenFlags::empty());
+        let to_client = new_pipe(PIPE_BUF_LEN, flags);
+        to_server.1.set_flags(OpenFlags::O_WRONLY | flags);
+        let (server_state, client_state) =
+            Self::connection((to_server, to_client), (listener.cred, UCred::current()));
+
+        let mut inner = self.inner.lock();
+        let server =
+            UnixSocket::new_connected(Some(addr.clone()), inner.local_addr.clone(), server_state);
+        inner.peer_addr = Some(addr);
+        inner.state = client_state;
+        drop(inner);
+
+        let mut listener = listener.inner.lock();
+        listener.pending.push_back(server);
+        listener.wake_all();
+        Ok(())
+    }
+
+    /// Receive into `buf`, returning the address of the sender. With
+    /// `passcred`, the bytes read are sent with the same credentials, which
+    /// are returned.
+    pub async fn recv(
+        &self,
+        buf: &mut [u8],
+        passcred: bool,
+    ) -> SysResult<(usize, SockAddrUn, Option<UCred>)> {
+        if self.is_dgram() {
+            return self.recv_dgram(buf, passcred).await;
+        }
+        let (read_end, creds, peer_cred, peer_addr) = {
+            let inner = self.inner.lock();
+            match &inner.state {
+                UnixState::Connected {
+                    read_end,
+                    recv_creds,
+                    peer_cred,
+                    ..
+                } => (
+                    read_end.clone(),
+                    recv_creds.clone(),
+                    *peer_cred,
+                    UnixAddr::to_sockaddr(inner.peer_addr.as_ref()),
+                ),
+                _ => return Err(SysError::ENOTCONN),
+            }
+        };
+        let mut len = buf.len();
+        if passcred {
+            // the bytes must be there to tell how many were sent together
+            while read_end.base_poll(PollEvents::IN).await.is_empty() {
+                if self.is_nonblocking() {
+                    return Err(SysError::EAGAIN);
+                }
+                suspend_now().await;
+            }
+            if let Some(run_len) = creds.lock().front_len() {
+                len = len.min(run_len);
+            }
+        }
+        let n = read_end.read_at(0, &mut buf[..len]).await?;
+        let cred = creds.lock().consume(n);
+        Ok((n, peer_addr, passcred.then(|| cred.unwrap_or(peer_cred))))
+    }
+
+    /// Receive the next datagram into `buf`, the bytes which do not fit are
+    /// discarded.
+    async fn recv_dgram(
+        &self,
+        buf: &mut [u8],
+        passcred: bool,
+    ) -> SysResult<(usize, SockAddrUn, Option<UCred>)> {
+        let queue = match &self.inner.lock().state {
+            UnixState::Datagram { queue, .. } => queue.clone(),
+            _ => unreachable!(),
+        };
+        if self.is_nonblocking() && queue.inner.lock().datagrams.is_empty() {
+            return Err(SysError::EAGAIN);
+        }
+        let datagram = RecvDgramFuture { queue }.await;
+        let n = buf.len().min(datagram.data.len());
+        buf[..n].copy_from_slice(&datagram.data[..n]);
+        Ok((
+            n,
+            UnixAddr::to_sockaddr(datagram.from.as_ref()),
+            passcred.then_some(datagram.cred),
+        ))
+    }
+
+    /// Send `buf` with the credentials `cred`, or with those of the current
+    /// process if `None`. A datagram goes to `to`, or to the peer if `None`,
+    /// which a stream ignores.
+    pub async fn send(
+        &self,
+        buf: &[u8],
+        to: Option<UnixAddr>,
+        cred: Option<UCred>,
+    ) -> SysResult<usize> {
+        if self.is_dgram() {
+            return self.send_dgram(buf, to, cred).await;
+        }
+        let (write_end, creds) = match &self.inner.lock().state {
+            UnixState::Connected {
+                write_end,
+                send_creds,
+                ..
+            } => (write_end.clone(), send_creds.clone()),
+            _ => return Err(SysError::ENOTCONN),
+        };
+        let id = creds
+            .lock()
+            .push(buf.len(), cred.unwrap_or_else(UCred::current));
+        let ret = write_end.write_at(0, buf).await;
+        creds.lock().truncate(id, *ret.as_ref().unwrap_or(&0));
+        if ret == Err(SysError::EPIPE) {
+            current_task().receive_siginfo(
+                SigInfo {
+                    sig: Sig::SIGPIPE,
+                    code: SigInfo::KERNEL,
+                    details: SigDetails::None,
+                },
+                false,
+            );
+        }
+        ret
+    }
+
+    async fn send_dgram(
+        &self,
+        buf: &[u8],
+        to: Option<UnixAddr>,
+        cred: Option<UCred>,
+    ) -> SysResult<usize> {
+        let (peer, from) = {
+            let inner = self.inner.lock();
+            match &inner.state {
+                UnixState::Datagram { peer, .. } => (peer.clone(), inner.local_addr.clone()),
+                _ => unreachable!(),
+            }
+        };
+        let queue = match to {
+            Some(addr) => dgram_queue(&peer_key(&addr)?)?,
+            None => peer.ok_or(SysError::EDESTADDRREQ)?,
+        };
+        if self.is_nonblocking() && !queue.inner.lock().has_room(buf.len()) {
+            return Err(SysError::EAGAIN);
+        }
+        SendDgramFuture {
+            queue,
+            datagram: Some(Datagram {
+                from,
+                data: buf.to_vec(),
+                cred: cred.unwrap_or_else(UCred::current),
+            }),
+        }
+        .await?;
+        Ok(buf.len())
+    }
+
+    pub async fn poll(&self) -> NetPollState {
+        let dgram = match &self.inner.lock().state {
+            UnixState::Datagram { queue, peer } => Some((queue.clone(), peer.clone())),
+            _ => None,
+        };
+        if let Some((queue, peer)) = dgram {
+            return Self::poll_dgram(queue, peer).await;
+        }
+        let state = match &self.inner.lock().state {
+            UnixState::Unconnected => None,
+            UnixState::Datagram { .. } => unreachable!(),
+            UnixState::Listening(listener) => Some(Err(listener.clone())),
+            UnixState::Connected {
+                read_end,
+                write_end,
+                ..
+            } => Some(Ok((read_end.clone(), write_end.clone()))),
+        };
+        match state {
+            None => NetPollState {
+                readable: false,
+                writable: false,
+                hangup: true,
+            },
+            Some(Err(listener)) => {
+                let waker = get_waker().await;
+                let mut inner = listener.inner.lock();
+                let readable = !inner.pending.is_empty();
+                if !readable {
+                    inner.wait(&waker);
+                }
+                NetPollState {
+                    readable,
+                    writable: false,
+                    hangup: false,
+                }
+            }
+            Some(Ok((read_end, write_end))) => {
+                let revents = read_end.base_poll(PollEvents::IN).await;
+                let wevents = write_end.base_poll(PollEvents::OUT).await;
+                NetPollState {
+                    readable: revents.intersects(PollEvents::IN | PollEvents::HUP),
+                    writable: wevents.contains(PollEvents::OUT),
+                    hangup: revents.contains(PollEvents::HUP),
+                }
+            }
+        }
+    }
+
+    /// A datagram socket is readable with a datagram in its queue, and
+    /// writable unless the queue of its peer is full.
+    async fn poll_dgram(queue: Arc<DgramQueue>, peer: Option<Arc<DgramQueue>>) -> NetPollState {
+        let waker = get_waker().await;
+        let readable = {
+            let mut inner = queue.inner.lock();
+            let readable = !inner.datagrams.is_empty();
+            if !readable {
+                inner.wait(&waker);
+            }
+            readable
+        };
+        let writable = peer.map_or(true, |peer| {
+            let mut inner = peer.inner.lock();
+            let writable = inner.closed || inner.has_room(1);
+            if !writable {
+                inner.wait(&waker);
+            }
+            writable
+        });
+        NetPollState {
+            readable,
+            writable,
+            hangup: false,
+        }
+    }
+
+    /// Signal the owner of `file`, the socket file at `fd`, once the
+    /// connection gets ready, or stop if not `on`. Nothing to do for a socket
+    /// not connected, nor for a datagram socket.
+    pub fn fasync(&self, file: &Arc<dyn File>, fd: usize, on: bool) {
+        if let UnixState::Connected {
+            read_end,
+            write_end,
+            ..
+        } = &self.inner.lock().state
+        {
+            read_end.base_fasync(file, fd, on);
+            write_end.base_fasync(file, fd, on);
+        }
+    }
+
+    /// Shut down the read end if `how` is 0 or 2, and the write end if `how`
+    /// is 1 or 2.
+    pub fn shutdown(&self, how: u8) -> SysResult<()> {
+        if how > 2 {
+            return Err(SysError::EINVAL);
+        }
+        let mut inner = self.inner.lock();
+        let UnixState::Connected {
+            read_end,
+            write_end,
+            ..
+        } = &mut inner.state
+        else {
+            return Err(SysError::ENOTCONN);
+        };
+        // dropping a pipe end closes it, which is replaced by the end of a pipe
+        // closed already
+        let (closed_read, closed_write) = new_pipe(0, OpenFlags::empty());
+        if how != 1 {
+            *read_end = closed_read.clone();
+        }
+        if how != 0 {
+            *write_end = closed_write.clone();
+        }
+        drop((closed_read, closed_write));
+        Ok(())
+    }
+
+    pub fn local_addr(&self) -> SockAddrUn {
+        UnixAddr::to_sockaddr(self.inner.lock().local_addr.as_ref())
+    }
+
+    /// Credentials of the peer when the connection was made, or of the
+    /// process that made a listening socket listen.
+    pub fn peer_cred(&self) -> UCred {
+        match &self.inner.lock().state {
+            UnixState::Unconnected | UnixState::Datagram { .. } => UCred::NONE,
+            UnixState::Listening(listener) => listener.cred,
+            UnixState::Connected { peer_cred, .. } => *peer_cred,
+        }
+    }
+
+    pub fn peer_addr(&self) -> SysResult<SockAddrUn> {
+        let inner = self.inner.lock();
+        match inner.state {
+            UnixState::Connected { .. } | UnixState::Datagram { peer: Some(_), .. } => {
+                Ok(UnixAddr::to_sockaddr(inner.peer_addr.as_ref()))
+            }
+            _ => Err(SysError::ENOTCONN),
+        }
+    }
+}
+
+impl Drop for UnixSocket {
+    fn drop(&mut self) {
+        let inner = self.inner.lock();
+        if let Some(key) = &inner.bound {
+            BOUND.lock().remove(key);
+        }
+        if let UnixState::Listening(listener) = &inner.state {
+            LISTENERS.lock().remove(inner.bound.as_ref().unwrap());
+            let mut listener = listener.inner.lock();
+            listener.closed = true;
+            // connections not accepted are reset, i.e. their ends are closed
+            listener.pending.clear();
+            listener.wake_all();
+        }
+        if let UnixState::Datagram { queue, .. } = &inner.state {
+            if let Some(key) = &inner.bound {
+                DGRAM_SOCKETS.lock().remove(key);
+            }
+            let mut queue = queue.inner.lock();
+            queue.closed = true;
+            queue.datagrams.clear();
+            queue.wake_all();
+        }
+    }
+}
+
+/// The socket inode at `path` to bind to, created if there is none.
+fn bind_inode(path: &str) -> SysResult<Arc<dyn Inode>> {
+    let dentry = current_task().at_helper(AtFd::FdCwd, path, OpenFlags::O_NOFOLLOW)?;
+    if !dentry.is_negetive() {
+        let inode = dentry.inode()?;
+        if inode.itype() != InodeType::Socket {
+            return Err(SysError::EADDRINUSE);
+        }
+        return Ok(inode);
+    }
+    let parent = dentry.parent().ok_or(SysError::EADDRINUSE)?;
+    let mode =
+        InodeMode::SOCKET | InodeMode::OWNER_MASK | InodeMode::GROUP_MASK | InodeMode::OTHER_MASK;
+    parent.create(dentry.name(), mode)?.inode()
+}
+
+/// What the socket at `addr` is bound to.
+fn peer_key(addr: &UnixAddr) -> SysResult<BindKey> {
+    match addr {
+        UnixAddr::Path(path) => {
+            let inode = current_task()
+                .at_helper(AtFd::FdCwd, path, OpenFlags::empty())?
+                .inode()?;
+            if inode.itype() != InodeType::Socket {
+                return Err(SysError::ECONNREFUSED);
+            }
+            Ok(BindKey::of_inode(&inode))
+        }
+        UnixAddr::Abstract(name) => Ok(BindKey::Abstract(name.clone())),
+    }
+}
+
+/// Receive queue of the datagram socket bound to `key`.
+fn dgram_queue(key: &BindKey) -> SysResult<Arc<DgramQueue>> {
+    DGRAM_SOCKETS
+        .lock()
+        .get(key)
+        .cloned()
+        .ok_or(SysError::ECONNREFUSED)
+}
+
+/// Waits in `accept` for a pending connection.
+struct AcceptFuture {
+    listener: Arc<UnixListener>,
+}
+
+impl Future for AcceptFuture {
+    type Output = SysResult<UnixSocket>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.listener.inner.lock();
+        if let Some(socket) = inner.pending.pop_front() {
+            // there is room in the backlog for a connecting task now
+            inner.wake_all();
+            Poll::Ready(Ok(socket))
+        } else {
+            inner.wait(cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits in `connect` until the backlog of the listener has room.
+struct BacklogFuture {
+    listener: Arc<UnixListener>,
+}
+
+impl Future for BacklogFuture {
+    type Output = SysResult<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.listener.inner.lock();
+        if inner.closed {
+            Poll::Ready(Err(SysError::ECONNREFUSED))
+        } else if inner.pending.len() < inner.backlog {
+            Poll::Ready(Ok(()))
+        } else {
+            inner.wait(cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits for a datagram in the queue of a socket.
+struct RecvDgramFuture {
+    queue: Arc<DgramQueue>,
+}
+
+impl Future for RecvDgramFuture {
+    type Output = Datagram;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.queue.inner.lock();
+        if let Some(datagram) = inner.datagrams.pop_front() {
+            inner.len -= datagram.data.len();
+            // there is room in the queue for a sending task now
+            inner.wake_all();
+            Poll::Ready(datagram)
+        } else {
+            inner.wait(cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits in `sendto` until the queue of the destination has room for the
+/// datagram.
+struct SendDgramFuture {
+    queue: Arc<DgramQueue>,
+    datagram: Option<Datagram>,
+}
+
+impl Future for SendDgramFuture {
+    type Output = SysResult<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut inner = this.queue.inner.lock();
+        let len = this.datagram.as_ref().unwrap().data.len();
+        if inner.closed {
+            Poll::Ready(Err(SysError::ECONNREFUSED))
+        } else if inner.has_room(len) {
+            inner.len += len;
+            inner.datagrams.push_back(this.datagram.take().unwrap());
+            inner.wake_all();
+            Poll::Ready(Ok(()))
+        } else {
+            inner.wait(cx.waker());
+            Poll::Pending
+        }
+    }
+}
@@ -10,7 +10,10 @@ use logging::LOG_INITIALIZED;
 use sbi_print::sbi_println;
 use sbi_rt::legacy::shutdown;
 
-use crate::processor::hart::local_hart;
+use crate::processor::{
+    hart::local_hart,
+    shutdown::{power_off, PowerOff},
+};
 
 static PANIC_CNT: AtomicUsize = AtomicUsize::new(0);
 
@@ -78,5 +81,5 @@ fn panic(info: &PanicInfo) -> ! {
     backtrace();
     log::error!("=============== END BACKTRACE ================");
 
-    shutdown()
+    power_off(PowerOff::Panic)
 }
@@ -1,11 +1,20 @@
 use alloc::sync::Arc;
-use core::{arch::asm, sync::atomic::AtomicBool};
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
 
-use arch::interrupts::{disable_interrupt, enable_interrupt};
+use arch::{
+    interrupts::{disable_interrupt, enable_interrupt},
+    time::{get_time_duration, set_next_timer_irq},
+};
 use config::board::MAX_HARTS;
 use riscv::register::sstatus::{self, FS};
+use sbi_rt::Retentive;
+use timer::TIMER_MANAGER;
 
-use super::env::EnvContext;
+use super::{env::EnvContext, membarrier::ack_membarrier, tlb::ack_tlb_shootdown};
 use crate::{mm, task::Task};
 
 const HART_EACH: Hart = Hart::new();
@@ -14,11 +23,48 @@ pub static mut HARTS: [Hart; MAX_HARTS] = [HART_EACH; MAX_HARTS];
 const HART_PREEMPTABLE_EACH: AtomicBool = AtomicBool::new(true);
 pub static mut HART_PREEMPTABLE: [AtomicBool; MAX_HARTS] = [HART_PREEMPTABLE_EACH; MAX_HARTS];
 
+/// Cleared when SBI reports that HSM suspend is not supported, after which the
+/// idle loop falls back to `wfi`.
+static SUSPEND_SUPPORTED: AtomicBool = AtomicBool::new(true);
+
+/// Time a hart has spent in user tasks, in the kernel for them and idle, in
+/// microseconds.
+pub struct HartTimeStat {
+    user: AtomicU64,
+    system: AtomicU64,
+    idle: AtomicU64,
+}
+
+impl HartTimeStat {
+    const fn new() -> Self {
+        Self {
+            user: AtomicU64::new(0),
+            system: AtomicU64::new(0),
+            idle: AtomicU64::new(0),
+        }
+    }
+
+    fn add(counter: &AtomicU64, time: Duration) {
+        counter.fetch_add(time.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the user, system and idle time.
+    pub fn times(&self) -> (Duration, Duration, Duration) {
+        let load = |counter: &AtomicU64| Duration::from_micros(counter.load(Ordering::Relaxed));
+        (load(&self.user), load(&self.system), load(&self.idle))
+    }
+}
+
+const HART_TIME_STAT_EACH: HartTimeStat = HartTimeStat::new();
+pub static HART_TIME_STATS: [HartTimeStat; MAX_HARTS] = [HART_TIME_STAT_EACH; MAX_HARTS];
+
 /// Each cpu owns one `Hart`.
 pub struct Hart {
     hart_id: usize,
     task: Option<Arc<Task>>,
     env: EnvContext,
+    /// User and system time of `task` when it was switched in.
+    task_time_start: (Duration, Duration),
 }
 
 impl Hart {
@@ -27,6 +73,7 @@ impl Hart {
             hart_id: 0,
             task: None,
             env: EnvContext::new(),
+            task_time_start: (Duration::ZERO, Duration::ZERO),
         }
     }
 
@@ -76,6 +123,8 @@ impl Hart {
         unsafe { env.auto_sum() };
         self.set_task(Arc::clone(task));
         task.time_stat().record_switch_in();
+        task.perf_stat().record_switch_in();
+        self.task_time_start = task.time_stat().user_system_time();
         core::mem::swap(self.env_mut(), env);
         // NOTE: must switch page table even if it belongs to the same user in smp
         // situation
@@ -93,7 +142,13 @@ impl Hart {
         unsafe { mm::switch_kernel_page_table() };
         core::mem::swap(self.env_mut(), env);
         let task = self.task();
+        task.with_memory_space(|m| m.leave_hart(self.hart_id));
         task.time_stat().record_switch_out();
+        task.perf_stat().record_switch_out();
+        let (utime, stime) = task.time_stat().user_system_time();
+        let stat = &HART_TIME_STATS[self.hart_id];
+        HartTimeStat::add(&stat.user, utime - self.task_time_start.0);
+        HartTimeStat::add(&stat.system, stime - self.task_time_start.1);
         task.trap_context_mut().user_fx.yield_task();
         self.clear_task();
         unsafe { enable_interrupt() };
@@ -169,6 +224,34 @@ pub fn init(hart_id: usize) {
     }
 }
 
+/// Put the local hart into a retentive suspend until the next interrupt.
+pub fn idle() {
+    let hart_id = local_hart().hart_id();
+    let start = get_time_duration();
+    if SUSPEND_SUPPORTED.load(Ordering::Relaxed) {
+        let ret = sbi_rt::hart_suspend(Retentive, 0, 0);
+        if ret.error != 0 {
+            log::info!("[idle] hart suspend is not supported, fall back to wfi");
+            SUSPEND_SUPPORTED.store(false, Ordering::Relaxed);
+            unsafe { riscv::asm::wfi() };
+        }
+    } else {
+        unsafe { riscv::asm::wfi() };
+    }
+    // Registers should be preserved by a retentive suspend, but the firmware is
+    // not trusted for `tp` since the whole per-hart state hangs on it.
+    unsafe { set_local_hart(hart_id) };
+    HartTimeStat::add(&HART_TIME_STATS[hart_id].idle, get_time_duration() - start);
+    // The timer irq may have fired while interrupts were masked, expire timers and
+    // reprogram it so that this hart keeps ticking.
+    TIMER_MANAGER.check();
+    unsafe { set_next_timer_irq() };
+    // the membarrier or shootdown IPI may have woken this hart with interrupts
+    // masked
+    ack_membarrier();
+    ack_tlb_shootdown();
+}
+
 pub fn current_task() -> Arc<Task> {
     local_hart().task().clone()
 }
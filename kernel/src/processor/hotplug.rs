@@ -0,0 +1,104 @@
+//! Taking harts offline and back online at runtime, through SBI HSM.
+//!
+//! A hart asked to go offline finishes the task it is currently polling and
+//! stops in its executor loop, the same way as for a shutdown. Most tasks live
+//! in the global task queue, so the other harts simply pick up the rest, and
+//! the tasks queued on that hart alone are handed over to it. A stopped hart
+//! is started again at the kernel entry, as at boot.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use arch::time::get_time_ms;
+use config::{
+    board::{self, MAX_HARTS},
+    mm::HART_START_ADDR,
+};
+use sbi_rt::HartMask;
+use systype::{SysError, SysResult};
+
+use super::{hart::local_hart, shutdown::is_hart_online};
+
+/// How long to wait for a hart to stop or start.
+const HOTPLUG_TIMEOUT_MS: usize = 500;
+
+/// `hart_get_status` of a hart stopped by `hart_stop`, see the SBI HSM
+/// extension.
+const HSM_STATUS_STOPPED: usize = 1;
+
+const OFFLINE_REQUESTED_EACH: AtomicBool = AtomicBool::new(false);
+static OFFLINE_REQUESTED: [AtomicBool; MAX_HARTS] = [OFFLINE_REQUESTED_EACH; MAX_HARTS];
+
+/// Whether the local hart should stop once back in its executor loop.
+pub fn offline_requested() -> bool {
+    OFFLINE_REQUESTED[local_hart().hart_id()].load(Ordering::Acquire)
+}
+
+/// Whether `hart_id` runs tasks, i.e. it is online and not going offline.
+pub fn is_hart_active(hart_id: usize) -> bool {
+    hart_id < board::harts()
+        && is_hart_online(hart_id)
+        && !OFFLINE_REQUESTED[hart_id].load(Ordering::Acquire)
+}
+
+fn wait_until(cond: impl Fn() -> bool) -> bool {
+    let deadline = get_time_ms() + HOTPLUG_TIMEOUT_MS;
+    while !cond() {
+        if get_time_ms() >= deadline {
+            return false;
+        }
+        core::hint::spin_loop();
+    }
+    true
+}
+
+/// Take `hart_id` offline. The last active hart can not be.
+///
+/// A remote hart is waited for, while the local hart stops only after the
+/// current task gets back to the executor, so the caller should yield.
+pub fn offline_hart(hart_id: usize) -> SysResult<()> {
+    if !is_hart_active(hart_id) {
+        return Ok(());
+    }
+    if !(0..board::harts()).any(|i| i != hart_id && is_hart_active(i)) {
+        return Err(SysError::EBUSY);
+    }
+    OFFLINE_REQUESTED[hart_id].store(true, Ordering::Release);
+    log::info!("[offline_hart] hart {hart_id} going offline");
+    if hart_id == local_hart().hart_id() {
+        return Ok(());
+    }
+    // wake it up in case it is idle
+    sbi_rt::send_ipi(HartMask::from_mask_base(1 << hart_id, 0));
+    if !wait_until(|| !is_hart_online(hart_id)) {
+        log::info!("[offline_hart] hart {hart_id} is busy, it stops later");
+    }
+    Ok(())
+}
+
+/// Bring `hart_id` back online, waiting until it runs tasks.
+pub fn online_hart(hart_id: usize) -> SysResult<()> {
+    if hart_id >= board::harts() {
+        return Err(SysError::EINVAL);
+    }
+    if is_hart_active(hart_id) {
+        return Ok(());
+    }
+    // a hart still going offline has to stop first, or it can not be started
+    let stopped = wait_until(|| {
+        !is_hart_online(hart_id) && sbi_rt::hart_get_status(hart_id).value == HSM_STATUS_STOPPED
+    });
+    if !stopped {
+        return Err(SysError::EBUSY);
+    }
+    OFFLINE_REQUESTED[hart_id].store(false, Ordering::Release);
+    let ret = sbi_rt::hart_start(hart_id, HART_START_ADDR, 0);
+    if ret.error != 0 {
+        log::error!("[online_hart] failed to start hart {hart_id}: {ret:?}");
+        return Err(SysError::EIO);
+    }
+    if !wait_until(|| is_hart_online(hart_id)) {
+        return Err(SysError::EIO);
+    }
+    log::info!("[online_hart] hart {hart_id} online");
+    Ok(())
+}
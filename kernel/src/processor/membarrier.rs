@@ -0,0 +1,76 @@
+//! Memory barriers on every hart, for membarrier(2).
+//!
+//! The caller marks a request pending for every other online hart and sends
+//! them an IPI. A target runs a full fence, counts an acknowledgment and then
+//! clears its request, while the caller waits until no request is left
+//! pending. So once the caller returns, every hart has passed a fence after
+//! the call began.
+//!
+//! An idle hart may be woken with interrupts masked, so it acknowledges right
+//! after waking as well. A hart going offline stops without acknowledging,
+//! and is no longer waited for.
+
+use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+use async_utils::yield_now;
+use config::board::{self, MAX_HARTS};
+use sbi_rt::HartMask;
+
+use super::{hart::local_hart, shutdown::is_hart_online};
+
+const PENDING_EACH: AtomicBool = AtomicBool::new(false);
+static PENDING: [AtomicBool; MAX_HARTS] = [PENDING_EACH; MAX_HARTS];
+
+const ACKS_EACH: AtomicUsize = AtomicUsize::new(0);
+/// Requests acknowledged by each hart, reported in /proc/interrupts.
+static ACKS: [AtomicUsize; MAX_HARTS] = [ACKS_EACH; MAX_HARTS];
+
+/// Held by the caller of `membarrier_all_harts`, one request at a time.
+static IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Acknowledge a pending request on the local hart, if any. Called in the
+/// IPI handler and when the hart wakes from idle.
+pub fn ack_membarrier() {
+    let hart_id = local_hart().hart_id();
+    if PENDING[hart_id].load(Ordering::Acquire) {
+        fence(Ordering::SeqCst);
+        ACKS[hart_id].fetch_add(1, Ordering::Relaxed);
+        PENDING[hart_id].store(false, Ordering::Release);
+    }
+}
+
+/// Number of requests acknowledged by `hart_id`.
+pub fn membarrier_acks(hart_id: usize) -> usize {
+    ACKS[hart_id].load(Ordering::Relaxed)
+}
+
+/// Run a full fence on every online hart, and wait until all of them did.
+pub async fn membarrier_all_harts() {
+    while IN_PROGRESS
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        yield_now().await;
+    }
+    // order the stores of the caller before the requests
+    fence(Ordering::SeqCst);
+    let local = local_hart().hart_id();
+    let mut mask = 0;
+    for hart_id in (0..board::harts()).filter(|&i| i != local && is_hart_online(i)) {
+        PENDING[hart_id].store(true, Ordering::Release);
+        mask |= 1 << hart_id;
+    }
+    if mask != 0 {
+        sbi_rt::send_ipi(HartMask::from_mask_base(mask, 0));
+    }
+    // NOTE: yield instead of spinning, the caller may hold the only hart that
+    // can run a target which is waiting for this one
+    while (0..board::harts()).any(|i| PENDING[i].load(Ordering::Acquire) && is_hart_online(i)) {
+        yield_now().await;
+    }
+    for pending in PENDING.iter() {
+        pending.store(false, Ordering::Relaxed);
+    }
+    fence(Ordering::SeqCst);
+    IN_PROGRESS.store(false, Ordering::Release);
+}
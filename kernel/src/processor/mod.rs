@@ -1,2 +1,7 @@
 pub mod env;
 pub mod hart;
+pub mod hotplug;
+pub mod membarrier;
+pub mod shutdown;
+pub mod softirq;
+pub mod tlb;
@@ -0,0 +1,123 @@
+//! Orderly power off of a multi-hart system.
+//!
+//! The hart that initiates the shutdown broadcasts a quiesce IPI to every other
+//! online hart. Each of them finishes the task it is currently polling, marks
+//! itself offline and stops through SBI HSM. The initiator waits for those
+//! acknowledgments with a timeout, syncs filesystems and powers off.
+
+use core::sync::atomic::{fence, AtomicBool, Ordering};
+
+use arch::{
+    interrupts::{clear_software_interrupt, disable_interrupt},
+    time::get_time_ms,
+};
+use config::board::{self, MAX_HARTS};
+use sbi_rt::{ColdReboot, HartMask, NoReason, Shutdown, SystemFailure};
+
+use super::hart::local_hart;
+
+/// How long the initiator waits for other harts to go offline.
+const QUIESCE_TIMEOUT_MS: usize = 500;
+
+const HART_ONLINE_EACH: AtomicBool = AtomicBool::new(false);
+static HART_ONLINE: [AtomicBool; MAX_HARTS] = [HART_ONLINE_EACH; MAX_HARTS];
+
+/// Set once by the hart that initiates the shutdown.
+static QUIESCE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerOff {
+    Shutdown,
+    Reboot,
+    /// Power off after a kernel panic. Filesystems are not synced since their
+    /// state can not be trusted.
+    Panic,
+}
+
+pub fn mark_local_hart_online() {
+    HART_ONLINE[local_hart().hart_id()].store(true, Ordering::SeqCst);
+}
+
+pub fn is_hart_online(hart_id: usize) -> bool {
+    HART_ONLINE[hart_id].load(Ordering::SeqCst)
+}
+
+pub fn quiesce_requested() -> bool {
+    QUIESCE.load(Ordering::Acquire)
+}
+
+/// Acknowledge a quiesce IPI. The hart will stop itself once it returns to the
+/// executor loop.
+pub fn handle_ipi() {
+    unsafe { clear_software_interrupt() };
+    log::debug!(
+        "[handle_ipi] hart {} receive ipi, quiesce {}",
+        local_hart().hart_id(),
+        quiesce_requested()
+    );
+    super::membarrier::ack_membarrier();
+    super::tlb::ack_tlb_shootdown();
+}
+
+/// Called by a hart in its executor loop after it observes the quiesce request.
+pub fn quiesce_local_hart() -> ! {
+    unsafe { disable_interrupt() };
+    let hart_id = local_hart().hart_id();
+    // There is no per-hart allocator cache, only make sure every store of this
+    // hart is visible to the initiator before going offline.
+    fence(Ordering::SeqCst);
+    HART_ONLINE[hart_id].store(false, Ordering::SeqCst);
+    // the tasks queued for this hart alone are run elsewhere, or queued on
+    // another hart they are allowed on once polled
+    executor::release_local(hart_id);
+    log::info!("[quiesce_local_hart] hart {hart_id} offline");
+    sbi_rt::hart_stop();
+    // `hart_stop` should never return, park the hart in case it does.
+    loop {
+        unsafe { riscv::asm::wfi() };
+    }
+}
+
+/// Stop all other harts and power off the machine from the local hart.
+pub fn power_off(kind: PowerOff) -> ! {
+    let hart_id = local_hart().hart_id();
+    if QUIESCE
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        // Another hart is already shutting the system down.
+        quiesce_local_hart();
+    }
+    HART_ONLINE[hart_id].store(false, Ordering::SeqCst);
+    log::info!("[power_off] hart {hart_id} initiate {kind:?}");
+
+    let mut mask = 0;
+    for i in 0..board::harts() {
+        if is_hart_online(i) {
+            mask |= 1 << i;
+        }
+    }
+    if mask != 0 {
+        sbi_rt::send_ipi(HartMask::from_mask_base(mask, 0));
+    }
+
+    let deadline = get_time_ms() + QUIESCE_TIMEOUT_MS;
+    while (0..board::harts()).any(is_hart_online) && get_time_ms() < deadline {
+        core::hint::spin_loop();
+    }
+    for i in (0..board::harts()).filter(|&i| is_hart_online(i)) {
+        log::error!("[power_off] hart {i} failed to respond to quiesce ipi");
+    }
+
+    if kind != PowerOff::Panic {
+        vfs::sync_all();
+    }
+
+    match kind {
+        PowerOff::Shutdown => sbi_rt::system_reset(Shutdown, NoReason),
+        PowerOff::Reboot => sbi_rt::system_reset(ColdReboot, NoReason),
+        PowerOff::Panic => sbi_rt::system_reset(Shutdown, SystemFailure),
+    };
+    // SRST extension is not available, use the legacy one.
+    sbi_rt::legacy::shutdown()
+}
@@ -0,0 +1,235 @@
+//! Softirqs, the deferred halves of interrupt handlers.
+//!
+//! An interrupt handler does the least it can with interrupts disabled, i.e.
+//! acknowledges the device, and raises a softirq on the local hart for the
+//! rest. The softirqs pending are run with interrupts enabled by
+//! [`irq_exit`] when the handler returns. If they keep being raised beyond a
+//! budget, the backlog is left to the `ksoftirqd` task of the hart, so that
+//! an interrupt storm does not starve the tasks.
+//!
+//! Each hart counts the softirqs run, the longest a softirq was pending and
+//! the longest interrupts were off in a handler, reported in /proc/softirqs.
+
+use alloc::vec::Vec;
+use core::{
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    task::Waker,
+    time::Duration,
+};
+
+use arch::{
+    interrupts::{disable_interrupt, enable_interrupt, is_interrupt_enabled},
+    time::get_time_duration,
+};
+use async_utils::{get_waker, suspend_now, yield_now};
+use config::board::{self, MAX_HARTS};
+use sync::mutex::SpinNoIrqLock;
+use systype::SysResult;
+use timer::TIMER_MANAGER;
+
+use super::hart::local_hart;
+use crate::task::spawn_kernel_task;
+
+/// Rounds of softirqs raised again while running, after which the rest is
+/// left to `ksoftirqd`.
+const MAX_SOFTIRQ_RESTART: usize = 10;
+
+/// Time softirqs may run on return from an interrupt.
+const MAX_SOFTIRQ_TIME: Duration = Duration::from_millis(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Softirq {
+    /// Run the timers expired.
+    Timer,
+    /// Run the deferred halves of the devices interrupted.
+    Device,
+}
+
+impl Softirq {
+    pub const ALL: [Self; 2] = [Self::Timer, Self::Device];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Timer => "TIMER",
+            Self::Device => "DEVICE",
+        }
+    }
+
+    fn bit(self) -> usize {
+        1 << self as usize
+    }
+
+    fn handle(self) {
+        match self {
+            Self::Timer => TIMER_MANAGER.check(),
+            Self::Device => driver::get_device_manager().handle_deferred(),
+        }
+    }
+}
+
+const NR_SOFTIRQS: usize = Softirq::ALL.len();
+
+/// Softirqs of a hart.
+struct HartSoftirqs {
+    /// Bits of the softirqs raised.
+    pending: AtomicUsize,
+    /// Whether the softirqs are being run, by the hart or by its `ksoftirqd`.
+    running: AtomicBool,
+    /// When each softirq was raised, in microseconds.
+    raised_at: [AtomicU64; NR_SOFTIRQS],
+    /// Number of times each softirq was run.
+    counts: [AtomicUsize; NR_SOFTIRQS],
+    /// Longest time a softirq was pending, in microseconds.
+    max_latency: AtomicU64,
+    /// Longest time interrupts were off in a handler, in microseconds.
+    max_irq_off: AtomicU64,
+}
+
+impl HartSoftirqs {
+    const fn new() -> Self {
+        const ZERO_U64: AtomicU64 = AtomicU64::new(0);
+        const ZERO_USIZE: AtomicUsize = AtomicUsize::new(0);
+        Self {
+            pending: AtomicUsize::new(0),
+            running: AtomicBool::new(false),
+            raised_at: [ZERO_U64; NR_SOFTIRQS],
+            counts: [ZERO_USIZE; NR_SOFTIRQS],
+            max_latency: AtomicU64::new(0),
+            max_irq_off: AtomicU64::new(0),
+        }
+    }
+
+    /// Run the softirqs pending until none is left or the budget is used up,
+    /// and return whether some are left. The caller holds `running`.
+    fn run(&self) -> bool {
+        let start = get_time_duration();
+        for _ in 0..MAX_SOFTIRQ_RESTART {
+            let pending = self.pending.swap(0, Ordering::AcqRel);
+            if pending == 0 {
+                return false;
+            }
+            for softirq in Softirq::ALL {
+                if pending & softirq.bit() == 0 {
+                    continue;
+                }
+                let i = softirq as usize;
+                let latency = (get_time_duration().as_micros() as u64)
+                    .saturating_sub(self.raised_at[i].load(Ordering::Relaxed));
+                self.max_latency.fetch_max(latency, Ordering::Relaxed);
+                self.counts[i].fetch_add(1, Ordering::Relaxed);
+                softirq.handle();
+            }
+            if get_time_duration() - start >= MAX_SOFTIRQ_TIME {
+                break;
+            }
+        }
+        self.pending.load(Ordering::Acquire) != 0
+    }
+}
+
+const HART_SOFTIRQS_EACH: HartSoftirqs = HartSoftirqs::new();
+static SOFTIRQS: [HartSoftirqs; MAX_HARTS] = [HART_SOFTIRQS_EACH; MAX_HARTS];
+
+const KSOFTIRQD_EACH: SpinNoIrqLock<Option<Waker>> = SpinNoIrqLock::new(None);
+/// Wakers of the `ksoftirqd` tasks waiting for a backlog.
+static KSOFTIRQD: [SpinNoIrqLock<Option<Waker>>; MAX_HARTS] = [KSOFTIRQD_EACH; MAX_HARTS];
+
+/// Raise `softirq` on the local hart, to be run once the interrupt handler
+/// returns.
+pub fn raise_softirq(softirq: Softirq) {
+    let softirqs = &SOFTIRQS[local_hart().hart_id()];
+    if softirqs.pending.load(Ordering::Relaxed) & softirq.bit() == 0 {
+        softirqs.raised_at[softirq as usize]
+            .store(get_time_duration().as_micros() as u64, Ordering::Relaxed);
+    }
+    softirqs.pending.fetch_or(softirq.bit(), Ordering::Release);
+}
+
+/// Called at the end of the hard half of an interrupt handler, which was
+/// entered at `entry`: account the time interrupts were off, and run the
+/// softirqs pending with interrupts enabled.
+///
+/// Nothing is run if the softirqs are running already, i.e. this interrupt
+/// came while running them and they are picked up when that is done.
+pub fn irq_exit(entry: Duration) {
+    let hart_id = local_hart().hart_id();
+    let softirqs = &SOFTIRQS[hart_id];
+    let irq_off = (get_time_duration() - entry).as_micros() as u64;
+    softirqs.max_irq_off.fetch_max(irq_off, Ordering::Relaxed);
+    if softirqs.pending.load(Ordering::Acquire) == 0
+        || softirqs.running.swap(true, Ordering::Acquire)
+    {
+        return;
+    }
+    let irq_enabled = is_interrupt_enabled();
+    unsafe { enable_interrupt() };
+    let backlog = softirqs.run();
+    if !irq_enabled {
+        unsafe { disable_interrupt() };
+    }
+    softirqs.running.store(false, Ordering::Release);
+    if backlog {
+        if let Some(waker) = KSOFTIRQD[hart_id].lock().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Run the backlog of softirqs of `hart_id`, on whichever hart the task is
+/// scheduled.
+async fn ksoftirqd(hart_id: usize) {
+    let softirqs = &SOFTIRQS[hart_id];
+    loop {
+        if !softirqs.running.swap(true, Ordering::Acquire) {
+            while softirqs.run() {
+                yield_now().await;
+            }
+            softirqs.running.store(false, Ordering::Release);
+        }
+        *KSOFTIRQD[hart_id].lock() = Some(get_waker().await);
+        // raised while running, and not run by the hart since they were
+        if softirqs.pending.load(Ordering::Acquire) == 0 {
+            suspend_now().await;
+        } else {
+            yield_now().await;
+        }
+    }
+}
+
+fn spawn_ksoftirqd() -> SysResult<()> {
+    for hart_id in 0..board::harts() {
+        spawn_kernel_task(ksoftirqd(hart_id));
+    }
+    Ok(())
+}
+systype::late_initcall!(spawn_ksoftirqd);
+
+/// Rows of /proc/softirqs, the counts of each softirq, then the longest
+/// latency and interrupt off time in microseconds, with a value for each of
+/// `hart_ids`.
+pub fn softirq_stats(hart_ids: &[usize]) -> Vec<(&'static str, Vec<u64>)> {
+    let row = |f: &dyn Fn(&HartSoftirqs) -> u64| {
+        hart_ids
+            .iter()
+            .map(|&hart_id| f(&SOFTIRQS[hart_id]))
+            .collect()
+    };
+    let mut rows: Vec<_> = Softirq::ALL
+        .iter()
+        .map(|&softirq| {
+            (
+                softirq.name(),
+                row(&|s| s.counts[softirq as usize].load(Ordering::Relaxed) as u64),
+            )
+        })
+        .collect();
+    rows.push((
+        "MAX_LATENCY_US",
+        row(&|s| s.max_latency.load(Ordering::Relaxed)),
+    ));
+    rows.push((
+        "MAX_IRQ_OFF_US",
+        row(&|s| s.max_irq_off.load(Ordering::Relaxed)),
+    ));
+    rows
+}
@@ -0,0 +1,76 @@
+//! Remote TLB shootdown, for PTEs made stricter while other harts may run on
+//! the same page table.
+//!
+//! Like membarrier, the caller marks a request pending for each target hart
+//! and sends them an IPI. A target flushes its whole TLB and then clears its
+//! request, while the caller waits until no request is left pending. So once
+//! the caller returns, no target caches the old PTEs anymore.
+//!
+//! An idle hart may be woken with interrupts masked, so it acknowledges right
+//! after waking as well. A hart going offline stops without acknowledging,
+//! and is no longer waited for.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use arch::memory::sfence_vma_all;
+#[cfg(feature = "smp")]
+use async_utils::yield_now;
+#[cfg(feature = "smp")]
+use config::board;
+use config::board::MAX_HARTS;
+#[cfg(feature = "smp")]
+use sbi_rt::HartMask;
+
+use super::hart::local_hart;
+#[cfg(feature = "smp")]
+use super::shutdown::is_hart_online;
+
+const PENDING_EACH: AtomicBool = AtomicBool::new(false);
+static PENDING: [AtomicBool; MAX_HARTS] = [PENDING_EACH; MAX_HARTS];
+
+/// Held by the caller of `flush_tlb_others`, one request at a time.
+#[cfg(feature = "smp")]
+static IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Flush the TLB of the local hart if a shootdown is pending on it. Called in
+/// the IPI handler and when the hart wakes from idle.
+pub fn ack_tlb_shootdown() {
+    let hart_id = local_hart().hart_id();
+    if PENDING[hart_id].load(Ordering::Acquire) {
+        unsafe { sfence_vma_all() };
+        PENDING[hart_id].store(false, Ordering::Release);
+    }
+}
+
+/// Flush the TLB of the online harts in `cpumask` other than the local one,
+/// and wait until all of them did.
+#[cfg(feature = "smp")]
+pub async fn flush_tlb_others(cpumask: usize) {
+    while IN_PROGRESS
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        yield_now().await;
+    }
+    let local = local_hart().hart_id();
+    let mut mask = 0;
+    for hart_id in
+        (0..board::harts()).filter(|&i| i != local && cpumask & (1 << i) != 0 && is_hart_online(i))
+    {
+        // release the PTE stores of the caller to the target
+        PENDING[hart_id].store(true, Ordering::Release);
+        mask |= 1 << hart_id;
+    }
+    if mask != 0 {
+        sbi_rt::send_ipi(HartMask::from_mask_base(mask, 0));
+    }
+    // NOTE: yield instead of spinning, the caller may hold the only hart that
+    // can run a target which is waiting for this one
+    while (0..board::harts()).any(|i| PENDING[i].load(Ordering::Acquire) && is_hart_online(i)) {
+        yield_now().await;
+    }
+    for pending in PENDING.iter() {
+        pending.store(false, Ordering::Relaxed);
+    }
+    IN_PROGRESS.store(false, Ordering::Release);
+}
@@ -1,6 +1,9 @@
 //! # Syscall numbers
 //!
-//! Syscall numbers from <asm-generic/unistd.h>.
+//! Syscall numbers from <asm-generic/unistd.h>, which riscv64 uses, and the
+//! legacy numbers of the generic table some old static binaries still use.
+//!
+//! A number listed here without a handler fails with `ENOSYS`.
 
 #![allow(unused)]
 
@@ -254,6 +257,8 @@ pub enum SyscallNo {
     ACCEPT4 = 242,
     RECVMMSG = 243,
     ARCH_SPECIFIC_SYSCALL = 244,
+    RISCV_HWPROBE = 258,
+    RISCV_FLUSH_ICACHE = 259,
     WAIT4 = 260,
     PRLIMIT64 = 261,
     FANOTIFY_INIT = 262,
@@ -285,6 +290,70 @@ pub enum SyscallNo {
     PKEY_MPROTECT = 288,
     PKEY_ALLOC = 289,
     PKEY_FREE = 290,
+    STATX = 291,
+    IO_PGETEVENTS = 292,
+    RSEQ = 293,
+    KEXEC_FILE_LOAD = 294,
+    PIDFD_SEND_SIGNAL = 424,
+    IO_URING_SETUP = 425,
+    IO_URING_ENTER = 426,
+    IO_URING_REGISTER = 427,
+    OPEN_TREE = 428,
+    MOVE_MOUNT = 429,
+    FSOPEN = 430,
+    FSCONFIG = 431,
+    FSMOUNT = 432,
+    FSPICK = 433,
+    PIDFD_OPEN = 434,
+    CLONE3 = 435,
+    CLOSE_RANGE = 436,
+    OPENAT2 = 437,
+    PIDFD_GETFD = 438,
+    FACCESSAT2 = 439,
+    PROCESS_MADVISE = 440,
+    EPOLL_PWAIT2 = 441,
+    MOUNT_SETATTR = 442,
+    QUOTACTL_FD = 443,
+    LANDLOCK_CREATE_RULESET = 444,
+    LANDLOCK_ADD_RULE = 445,
+    LANDLOCK_RESTRICT_SELF = 446,
+    MEMFD_SECRET = 447,
+    PROCESS_MRELEASE = 448,
+    FUTEX_WAITV = 449,
+    SET_MEMPOLICY_HOME_NODE = 450,
+    CACHESTAT = 451,
+    FCHMODAT2 = 452,
+    MAP_SHADOW_STACK = 453,
+    FUTEX_WAKE = 454,
+    FUTEX_WAIT = 455,
+    FUTEX_REQUEUE = 456,
+    STATMOUNT = 457,
+    LISTMOUNT = 458,
+    LSM_GET_SELF_ATTR = 459,
+    LSM_SET_SELF_ATTR = 460,
+    LSM_LIST_MODULES = 461,
+    MSEAL = 462,
+
+    // Legacy numbers, mapped to the calls that replaced them
+    OPEN = 1024,
+    LINK = 1025,
+    UNLINK = 1026,
+    MKNOD = 1027,
+    CHMOD = 1028,
+    CHOWN = 1029,
+    MKDIR = 1030,
+    RMDIR = 1031,
+    LCHOWN = 1032,
+    ACCESS = 1033,
+    RENAME = 1034,
+    READLINK = 1035,
+    SYMLINK = 1036,
+    PIPE = 1040,
+    DUP2 = 1041,
+    ALARM = 1059,
+    CREAT = 1064,
+    POLL = 1068,
+    FORK = 1079,
 }
 
 impl core::fmt::Display for SyscallNo {
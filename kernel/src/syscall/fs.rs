@@ -1,28 +1,51 @@
-use alloc::{ffi::CString, sync::Arc, vec, vec::Vec};
+use alloc::{
+    ffi::CString,
+    sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
 use core::{
+    any::Any,
     cmp, default,
+    mem::size_of,
     ops::{Deref, DerefMut},
 };
 
 use arch::time::get_time_duration;
 use async_utils::{Select2Futures, SelectOutput};
-use config::{board::BLOCK_SIZE, fs::PIPE_BUF_LEN};
-use driver::BLOCK_DEVICE;
+use config::{fs::PIPE_BUF_LEN, mm::PAGE_SIZE};
+use device_core::DevId;
+use signal::sigset::{Sig, SigSet};
 use strum::FromRepr;
-use systype::{SysError, SyscallResult};
-use time::timespec::TimeSpec;
-use vfs::{fd_table::FdFlags, pipefs::new_pipe, simplefs::dentry, sys_root_dentry, FS_MANAGER};
+use systype::{SysError, SysResult, SyscallResult};
+use time::timespec::{ITimerSpec, TimeSpec};
+use vfs::{
+    devfs::block_device_of,
+    eventfd::{EventFdFile, EventFdFlags},
+    fd_table::FdFlags,
+    pipefs::{self, new_pipe},
+    signalfd::{SignalFdFile, SignalFdFlags},
+    simplefs::dentry,
+    sys_root_dentry,
+    timerfd::{TimerFdFile, TimerFdFlags, TimerFdSetFlags},
+};
 use vfs_core::{
-    is_absolute_path, split_parent_and_name, AtFd, Dentry, Inode, InodeMode, InodeType, MountFlags,
-    OpenFlags, Path, RenameFlags, SeekFrom, Stat, StatFs, AT_REMOVEDIR, AT_SYMLINK_FOLLOW,
-    AT_SYMLINK_NOFOLLOW,
+    bind_mount, find_mount, has_trailing_slash, is_absolute_path, umount, AtFd, Dentry,
+    DentryState, File, FileSystemFlags, Inode, InodeMode, InodeType, MountFlags, OpenFlags, Path,
+    RenameFlags, ResolveFlags, SeekFrom, Stat, StatFs, SuperBlock, UmountFlags, AT_EMPTY_PATH,
+    AT_REMOVEDIR, AT_SYMLINK_FOLLOW, AT_SYMLINK_NOFOLLOW,
 };
 
 use super::Syscall;
 use crate::{
-    mm::{UserRdWrPtr, UserReadPtr, UserWritePtr},
+    mm::{memory_space::sync_file_mappings, UserRdWrPtr, UserReadPtr, UserWritePtr},
+    net::socket::Socket,
     processor::env::within_sum,
-    task::signal::IntrBySignalFuture,
+    task::{
+        cred::{AccessMode, Capabilities, Credentials, Gid, Uid, ROOT_UID},
+        signal::IntrBySignalFuture,
+        Task, PROCESS_GROUP_MANAGER, TASK_MANAGER,
+    },
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -32,6 +55,15 @@ pub struct IoVec {
     pub len: usize,
 }
 
+/// Arguments of openat2, defined in <linux/openat2.h>.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct OpenHow {
+    pub flags: u64,
+    pub mode: u64,
+    pub resolve: u64,
+}
+
 // Defined in <bits/fcntl-linux.h>
 #[derive(FromRepr, Debug, Eq, PartialEq, Clone, Copy, Default)]
 #[allow(non_camel_case_types)]
@@ -43,6 +75,10 @@ pub enum FcntlOp {
     F_SETFD = 2,
     F_GETFL = 3,
     F_SETFL = 4,
+    F_SETOWN = 8,
+    F_GETOWN = 9,
+    F_SETSIG = 10,
+    F_GETSIG = 11,
     #[default]
     F_UNIMPL,
 }
@@ -112,6 +148,121 @@ impl Kstat {
     }
 }
 
+/// Request for `statmount` and `listmount`, defined in <linux/mount.h>.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct MntIdReq {
+    /// Size of this struct, used for versioning.
+    pub size: u32,
+    pub spare: u32,
+    /// Mount id to query, or `LSMT_ROOT` for `listmount`.
+    pub mnt_id: u64,
+    /// Request mask for `statmount`, or the last listed mount id for
+    /// `listmount`.
+    pub param: u64,
+}
+
+const MNT_ID_REQ_SIZE_VER0: u32 = 24;
+
+/// List the mounts under the root of the mount namespace.
+const LSMT_ROOT: u64 = u64::MAX;
+
+bitflags! {
+    /// Request mask for `statmount`, defined in <linux/mount.h>.
+    #[derive(Debug, Clone, Copy)]
+    pub struct StatMountMask: u64 {
+        /// Want/got sb_...
+        const SB_BASIC = 0x1;
+        /// Want/got mnt_...
+        const MNT_BASIC = 0x2;
+        /// Want/got propagate_from
+        const PROPAGATE_FROM = 0x4;
+        /// Want/got mnt_root
+        const MNT_ROOT = 0x8;
+        /// Want/got mnt_point
+        const MNT_POINT = 0x10;
+        /// Want/got fs_type
+        const FS_TYPE = 0x20;
+    }
+}
+
+/// Mount propagation type, see mount(2).
+const MS_PRIVATE: u64 = 1 << 18;
+
+/// Fixed part of the buffer filled by `statmount`. Strings are appended right
+/// after it and referenced by offsets into `str`.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct StatMount {
+    pub size: u32,
+    pub __spare1: u32,
+    pub mask: u64,
+    pub sb_dev_major: u32,
+    pub sb_dev_minor: u32,
+    pub sb_magic: u64,
+    pub sb_flags: u32,
+    /// Offset of the fs type string in `str`.
+    pub fs_type: u32,
+    pub mnt_id: u64,
+    pub mnt_parent_id: u64,
+    pub mnt_id_old: u32,
+    pub mnt_parent_id_old: u32,
+    pub mnt_attr: u64,
+    pub mnt_propagation: u64,
+    pub mnt_peer_group: u64,
+    pub mnt_master: u64,
+    pub propagate_from: u64,
+    /// Offset of the mount root string in `str`.
+    pub mnt_root: u32,
+    /// Offset of the mount point string in `str`.
+    pub mnt_point: u32,
+    pub __spare2: [u64; 50],
+}
+
+impl StatMount {
+    fn zeroed() -> Self {
+        unsafe { core::mem::zeroed() }
+    }
+}
+
+/// Get the magic number of a file system by its name, see statfs(2).
+fn fs_magic(fs_name: &str) -> u64 {
+    match fs_name {
+        "ext4" => 0xEF53,
+        "fat32" => 0x4d44,
+        "tmpfs" => 0x01021994,
+        "procfs" => 0x9fa0,
+        "devfs" => 0x1373,
+        "sockfs" => 0x534F434B,
+        _ => 0,
+    }
+}
+
+/// Magic number of the file system of anonymous pipes, see statfs(2).
+const PIPEFS_MAGIC: i64 = 0x50495045;
+
+/// Get the statistics of the file system that `file` lives in. Sockets and
+/// anonymous pipes are in no mounted file system, they report the magic of
+/// sockfs and pipefs like linux.
+fn file_stat_fs(file: &Arc<dyn File>) -> SysResult<StatFs> {
+    if file.is::<Socket>() {
+        return Ok(StatFs::in_memory(fs_magic("sockfs") as i64));
+    }
+    match file.inode().meta().super_block.upgrade() {
+        Some(sb) => sb.stat_fs(),
+        None => Ok(StatFs::in_memory(PIPEFS_MAGIC)),
+    }
+}
+
+/// Get the id of the mount that `sb` is mounted on. The root mount is its own
+/// parent.
+fn parent_mnt_id(sb: &Arc<dyn SuperBlock>) -> usize {
+    sb.root_dentry()
+        .parent()
+        .map(|p| p.super_block().mnt_id())
+        .unwrap_or(sb.mnt_id())
+}
+
 impl Syscall<'_> {
     /// read() attempts to read up to count bytes from file descriptor fd into
     /// the buffer starting at buf.
@@ -169,7 +320,7 @@ impl Syscall<'_> {
         let file = task.with_fd_table(|table| table.get_file(fd))?;
         log::info!("[sys_pread64] reading file {}", file.dentry().path());
         let mut buf = buf.into_mut_slice(&task, count)?;
-        let ret = file.read_at(offset, &mut buf).await?;
+        let ret = file.read_at_queued(offset, &mut buf).await?;
         Ok(ret)
     }
 
@@ -184,7 +335,7 @@ impl Syscall<'_> {
         let file = task.with_fd_table(|table| table.get_file(fd))?;
         log::info!("[sys_pwrite64] writing file {}", file.dentry().path());
         let buf = buf.into_slice(&task, count)?;
-        let ret = file.write_at(offset, &buf).await?;
+        let ret = file.write_at_queued(offset, &buf).await?;
         Ok(ret)
     }
 
@@ -220,26 +371,180 @@ impl Syscall<'_> {
         log::info!(
             "[sys_openat] dirfd: {dirfd}, pathname: {pathname}, flags: {flags:?}, mode: {mode:?}"
         );
+        if flags.contains(OpenFlags::O_CREAT) && has_trailing_slash(&pathname) {
+            return Err(SysError::EISDIR);
+        }
         let dentry = task.at_helper(dirfd, &pathname, flags)?;
+        self.do_open(dentry, flags, mode).await
+    }
+
+    /// The openat2() system call is an extension of openat(2) and provides a
+    /// superset of its functionality. `how` points to an `open_how` structure
+    /// of `size` bytes, which also carries the `resolve` flags that constrain
+    /// the path resolution.
+    pub async fn sys_openat2(
+        &self,
+        dirfd: AtFd,
+        pathname: UserReadPtr<u8>,
+        how: UserReadPtr<OpenHow>,
+        size: usize,
+    ) -> SyscallResult {
+        let task = self.task;
+        if size < size_of::<OpenHow>() {
+            return Err(SysError::EINVAL);
+        }
+        if size > PAGE_SIZE {
+            return Err(SysError::E2BIG);
+        }
+        let how_addr = how.as_usize();
+        let how = how.read(&task)?;
+        if size > size_of::<OpenHow>() {
+            // NOTE: a newer `open_how` is accepted only if the fields we do not
+            // know about are all zero
+            let ext = UserReadPtr::<u8>::from(how_addr + size_of::<OpenHow>())
+                .read_array(&task, size - size_of::<OpenHow>())?;
+            if ext.iter().any(|&b| b != 0) {
+                return Err(SysError::E2BIG);
+            }
+        }
+        let flags = i32::try_from(how.flags)
+            .ok()
+            .and_then(OpenFlags::from_bits)
+            .ok_or(SysError::EINVAL)?;
+        let resolve = ResolveFlags::from_bits(how.resolve).ok_or(SysError::EINVAL)?;
+        if how.mode & !0o7777 != 0
+            || (how.mode != 0
+                && !flags.contains(OpenFlags::O_CREAT)
+                && !flags.contains(OpenFlags::O_TMPFILE))
+        {
+            return Err(SysError::EINVAL);
+        }
+        let mode = InodeMode::from_bits_truncate(how.mode as u32);
+        let pathname = pathname.read_cstr(&task)?;
+        log::info!(
+            "[sys_openat2] dirfd: {dirfd}, pathname: {pathname}, flags: {flags:?}, mode: {mode:?}, resolve: {resolve:?}"
+        );
+        if flags.contains(OpenFlags::O_CREAT) && has_trailing_slash(&pathname) {
+            return Err(SysError::EISDIR);
+        }
+        let dentry = task.at_path(dirfd, &pathname)?.resolve(flags, resolve)?;
+        self.do_open(dentry, flags, mode).await
+    }
+
+    async fn do_open(
+        &self,
+        dentry: Arc<dyn Dentry>,
+        flags: OpenFlags,
+        mode: InodeMode,
+    ) -> SyscallResult {
+        let task = self.task;
         if flags.contains(OpenFlags::O_CREAT) {
             // If pathname does not exist, create it as a regular file.
-            if flags.contains(OpenFlags::O_EXCL) && !dentry.is_negetive() {
-                return Err(SysError::EEXIST);
-            }
             let parent = dentry.parent().expect("can not be root dentry");
-            parent.create(dentry.name(), InodeMode::FILE | mode)?;
+            if flags.contains(OpenFlags::O_EXCL) {
+                // NOTE: checked again under the lock of the parent, as a racing open may
+                // have created it since it was looked up
+                if !dentry.is_negetive() {
+                    return Err(SysError::EEXIST);
+                }
+                parent.create_exclusive(dentry.name(), InodeMode::FILE | mode)?;
+            } else {
+                parent.create(dentry.name(), InodeMode::FILE | mode)?;
+            }
         }
 
         let inode = dentry.inode()?;
         if flags.contains(OpenFlags::O_DIRECTORY) && !inode.itype().is_dir() {
             return Err(SysError::ENOTDIR);
         }
-
-        let file = dentry.open()?;
-        file.set_flags(flags);
+        let file = if inode.itype().is_fifo() {
+            self.open_fifo(dentry, flags).await?
+        } else {
+            dentry.open()?
+        };
+        if flags.writable() && inode.itype().is_file() {
+            file.get_write_access()?;
+            if flags.contains(OpenFlags::O_TRUNC) {
+                inode.truncate(0)?;
+                sync_file_mappings(&inode);
+            }
+        }
+        // NOTE: `O_CLOEXEC` is a flag of the fd rather than the open file, it is kept
+        // in fd table
+        file.set_flags(flags - OpenFlags::O_CLOEXEC);
         task.with_mut_fd_table(|table| table.alloc(file, flags))
     }
 
+    /// Open a FIFO, which may wait for the other end to be opened.
+    async fn open_fifo(
+        &self,
+        dentry: Arc<dyn Dentry>,
+        flags: OpenFlags,
+    ) -> SysResult<Arc<dyn File>> {
+        let task = self.task;
+        task.set_interruptable();
+        task.set_wake_up_signal(!*task.sig_mask_ref());
+        let intr_future = IntrBySignalFuture {
+            task: task.clone(),
+            mask: *task.sig_mask_ref(),
+        };
+        let ret = match Select2Futures::new(pipefs::open_fifo(dentry, flags), intr_future).await {
+            SelectOutput::Output1(ret) => ret,
+            SelectOutput::Output2(_) => Err(SysError::EINTR),
+        };
+        task.set_running();
+        ret
+    }
+
+    /// The system call mknodat() creates a filesystem node (file, device
+    /// special file, or named pipe) named pathname, with attributes specified
+    /// by mode and dev.
+    ///
+    /// A character or block device node is made for the device numbered `dev`,
+    /// and needs `CAP_MKNOD`. It opens the device of devfs with the same type
+    /// and number. A socket node is what an AF_UNIX socket may be bound to.
+    pub fn sys_mknodat(
+        &self,
+        dirfd: AtFd,
+        pathname: UserReadPtr<u8>,
+        mode: u32,
+        dev: usize,
+    ) -> SyscallResult {
+        let task = self.task;
+        let mut mode = InodeMode::from_bits_truncate(mode);
+        let pathname = pathname.read_cstr(&task)?;
+        log::info!("[sys_mknodat] dirfd: {dirfd}, pathname: {pathname}, mode: {mode:?}");
+        match mode.to_type() {
+            // zero file type means a regular file
+            InodeType::Unknown if mode.intersection(InodeMode::TYPE_MASK).is_empty() => {
+                mode |= InodeMode::FILE
+            }
+            InodeType::File | InodeType::Fifo | InodeType::Socket => {}
+            InodeType::CharDevice | InodeType::BlockDevice => {
+                if !task.cred_ref().has_cap(Capabilities::MKNOD) {
+                    return Err(SysError::EPERM);
+                }
+            }
+            _ => return Err(SysError::EINVAL),
+        }
+        let (parent, name) = task.resolve_parent_and_name(dirfd, &pathname)?;
+        if matches!(name.as_str(), "." | ".." | "/") || !parent.lookup(&name)?.is_negetive() {
+            return Err(SysError::EEXIST);
+        }
+        // a trailing slash names a directory, which mknod does not make
+        if has_trailing_slash(&pathname) {
+            return Err(SysError::ENOENT);
+        }
+        if mode.to_type().is_char_device() || mode.to_type().is_block_device() {
+            // NOTE: only the majors of devices the kernel has can be recorded
+            let dev_id = DevId::decode(dev as u64).ok_or(SysError::EINVAL)?;
+            parent.mknod(&name, mode, dev_id)?;
+        } else {
+            parent.create_exclusive(&name, mode)?;
+        }
+        Ok(0)
+    }
+
     /// close() closes a file descriptor, so that it no longer refers to any
     /// file and may be reused. Any record locks (see fcntl(2)) held on the
     /// file it was associated with, and owned by the process, are removed
@@ -257,6 +562,31 @@ impl Syscall<'_> {
         Ok(0)
     }
 
+    /// close_range() closes all open file descriptors from `first` to `last`
+    /// included. Errors closing a given file descriptor are ignored.
+    ///
+    /// `flags` may be the bitwise OR of:
+    /// + CLOSE_RANGE_UNSHARE: Unshare the file descriptor table first, as
+    ///   unshare(CLONE_FILES) does.
+    /// + CLOSE_RANGE_CLOEXEC: Set the close-on-exec flag on the file
+    ///   descriptors instead of closing them.
+    pub fn sys_close_range(&self, first: usize, last: usize, flags: u32) -> SyscallResult {
+        const CLOSE_RANGE_UNSHARE: u32 = 1 << 1;
+        const CLOSE_RANGE_CLOEXEC: u32 = 1 << 2;
+        let task = self.task;
+        log::info!("[sys_close_range] first: {first}, last: {last}, flags: {flags:#x}");
+        if flags & !(CLOSE_RANGE_UNSHARE | CLOSE_RANGE_CLOEXEC) != 0 || first > last {
+            return Err(SysError::EINVAL);
+        }
+        if flags & CLOSE_RANGE_UNSHARE != 0 {
+            task.unshare_fd_table();
+        }
+        task.with_mut_fd_table(|table| {
+            table.close_range(first, last, flags & CLOSE_RANGE_CLOEXEC != 0)
+        });
+        Ok(0)
+    }
+
     /// mkdirat() attempts to create a directory named pathname.
     ///
     /// mkdir() and mkdirat() return zero on success.  On error, -1 is returned
@@ -266,12 +596,13 @@ impl Syscall<'_> {
         let mode = InodeMode::from_bits_truncate(mode);
         let pathname = pathname.read_cstr(&task)?;
         log::debug!("[sys_mkdirat] {mode:?}");
-        let dentry = task.at_helper(dirfd, &pathname, OpenFlags::empty())?;
-        if !dentry.is_negetive() {
+        // NOTE: the directory to create may be named with a trailing slash, which
+        // must not fail with `ENOTDIR` on what exists there, nor follow a symlink
+        let (parent, name) = task.resolve_parent_and_name(dirfd, &pathname)?;
+        if matches!(name.as_str(), "." | ".." | "/") || !parent.lookup(&name)?.is_negetive() {
             return Err(SysError::EEXIST);
         }
-        let parent = dentry.parent().unwrap();
-        parent.create(dentry.name(), mode.union(InodeMode::DIR))?;
+        parent.create_exclusive(&name, mode.union(InodeMode::DIR))?;
         Ok(0)
     }
 
@@ -303,7 +634,11 @@ impl Syscall<'_> {
             return Err(SysError::EINVAL);
         }
         let task = self.task;
-        let abs_path = task.cwd().path();
+        // the cwd has been removed
+        if task.cwd().is_negetive() {
+            return Err(SysError::ENOENT);
+        }
+        let abs_path = task.cwd().path_from(&task.root());
         let c_path_len = abs_path.len() + 1;
         if c_path_len > size {
             return Err(SysError::ERANGE);
@@ -333,6 +668,20 @@ impl Syscall<'_> {
         Ok(0)
     }
 
+    /// fchdir() changes the current working directory to the directory open at
+    /// `fd`.
+    pub fn sys_fchdir(&self, fd: usize) -> SyscallResult {
+        let task = self.task;
+        log::debug!("[sys_fchdir] fd {fd}");
+        let file = task.with_fd_table(|table| table.get_file(fd))?;
+        // NOTE: check the inode first, as an anonymous file has no dentry
+        if !file.inode().itype().is_dir() {
+            return Err(SysError::ENOTDIR);
+        }
+        task.set_cwd(file.dentry());
+        Ok(0)
+    }
+
     /// The dup() system call allocates a new file descriptor that refers to the
     /// same open file description as the descriptor oldfd. (For an explanation
     /// of open file descriptions, see open(2).) The new file descriptor
@@ -387,12 +736,25 @@ impl Syscall<'_> {
         let task = self.task;
         let flags = OpenFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
         log::info!("[sys_dup3] oldfd: {oldfd}, new_fd: {newfd}, flags: {flags:?}");
-        if oldfd == newfd {
+        if oldfd == newfd || !OpenFlags::O_CLOEXEC.contains(flags) {
             return Err(SysError::EINVAL);
         }
         task.with_mut_fd_table(|table| table.dup3(oldfd, newfd, flags))
     }
 
+    /// The legacy dup2(), see `sys_dup3`.
+    pub fn sys_dup2(&self, oldfd: usize, newfd: usize) -> SyscallResult {
+        let task = self.task;
+        log::info!("[sys_dup2] oldfd: {oldfd}, new_fd: {newfd}");
+        task.with_mut_fd_table(|table| {
+            if oldfd == newfd {
+                table.get(oldfd)?;
+                return Ok(newfd);
+            }
+            table.dup3(oldfd, newfd, OpenFlags::empty())
+        })
+    }
+
     pub fn sys_fstat(&self, fd: usize, stat_buf: UserWritePtr<Kstat>) -> SyscallResult {
         let task = self.task;
         let file = task.with_fd_table(|table| table.get_file(fd))?;
@@ -408,19 +770,44 @@ impl Syscall<'_> {
         stat_buf: UserWritePtr<Kstat>,
         flags: i32,
     ) -> SyscallResult {
-        const AT_SYMLINK_NOFOLLOW: i32 = 0x100;
+        // NOTE: there is no automount, so it is never triggered anyway
+        const AT_NO_AUTOMOUNT: i32 = 0x800;
         let task = self.task;
+        if flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH | AT_NO_AUTOMOUNT) != 0 {
+            return Err(SysError::EINVAL);
+        }
         let path = pathname.read_cstr(&task)?;
-        let dentry = if flags == AT_SYMLINK_NOFOLLOW {
-            task.at_helper(dirfd, &path, OpenFlags::O_NOFOLLOW)?
-        } else {
-            task.at_helper(dirfd, &path, OpenFlags::empty())?
-        };
-        let kstat = Kstat::from_stat(dentry.inode()?.get_attr()?);
+        let inode = self.at_inode(dirfd, &path, flags)?;
+        let kstat = Kstat::from_stat(inode.get_attr()?);
         stat_buf.write(&task, kstat)?;
         Ok(0)
     }
 
+    /// Find the inode of `path` relative to `dirfd` for the *at() calls that
+    /// take `AT_SYMLINK_NOFOLLOW` and `AT_EMPTY_PATH`. With the latter an
+    /// empty path refers to `dirfd` itself, which may be any open file.
+    fn at_inode(&self, dirfd: AtFd, path: &str, flags: i32) -> SysResult<Arc<dyn Inode>> {
+        let task = self.task;
+        if path.is_empty() {
+            if flags & AT_EMPTY_PATH == 0 {
+                return Err(SysError::ENOENT);
+            }
+            return match dirfd {
+                AtFd::FdCwd => task.cwd().inode(),
+                AtFd::Normal(fd) => Ok(task.with_fd_table(|table| table.get_file(fd))?.inode()),
+            };
+        }
+        let dentry = if flags & AT_SYMLINK_NOFOLLOW != 0 {
+            task.at_helper(dirfd, path, OpenFlags::O_NOFOLLOW)?
+        } else {
+            task.at_helper(dirfd, path, OpenFlags::empty())?
+        };
+        dentry.inode()
+    }
+
+    /// mount() attaches the file system of type `fstype` at `target`, from the
+    /// block device `source` if the type needs one. With MS_BIND, the subtree
+    /// at `source` is attached at `target` instead.
     pub async fn sys_mount(
         &self,
         source: UserReadPtr<u8>,
@@ -438,58 +825,324 @@ impl Syscall<'_> {
         "[sys_mount] source:{source:?}, target:{target:?}, fstype:{fstype:?}, flags:{flags:?}, data:{data:?}",
     );
 
-        // adding this code is because the fs_type in test code is vfat, which should be
-        // turned into fat32
-        let fat32_type = FS_MANAGER.lock().get("fat32").unwrap().clone();
-        let fs_type = FS_MANAGER
-            .lock()
-            .get(&fstype)
-            .unwrap_or(&fat32_type.clone())
-            .clone();
-        let _fs_root = match fs_type.name() {
-            name @ "fat32" => {
-                let dev = if name.eq("fat32") {
-                    // here should be getting device according to inode
-                    // it seems that device hasn't been associated with inode yet.
-                    // so here just return a virtio_block
-                    // let path = Path::new(sys_root_dentry(), sys_root_dentry(), &*source);
-                    // let dev = path.walk(InodeMode::BLOCK)?;
-                    // let dev_ino = dev.inode()?;
-                    // if dev_ino.itype() != InodeType::BlockDevice {
-                    //     return Err(SysError::EINVAL);
-                    // }
-                    Some(BLOCK_DEVICE.get().unwrap().clone())
-                } else {
-                    None
-                };
-                let (parent, name) = split_parent_and_name(&target);
+        if !task.cred_ref().has_cap(Capabilities::SYS_ADMIN) {
+            return Err(SysError::EPERM);
+        }
+        let mount_point = task.resolve_path(&target)?;
+        if mount_point.is_negetive() {
+            return Err(SysError::ENOENT);
+        }
 
-                let parent = task.resolve_path(parent)?;
-                fs_type.mount(name.unwrap(), Some(parent), flags, dev)?
+        if flags.contains(MountFlags::MS_BIND) {
+            let source = task.resolve_path(&source)?;
+            if source.is_negetive() {
+                return Err(SysError::ENOENT);
             }
-            _ => return Err(SysError::EINVAL),
+            let source_is_dir = source.inode()?.itype().is_dir();
+            if source_is_dir != mount_point.inode()?.itype().is_dir() {
+                return Err(if source_is_dir {
+                    SysError::ENOTDIR
+                } else {
+                    SysError::EISDIR
+                });
+            }
+            bind_mount(source, mount_point)?;
+            return Ok(0);
+        }
+
+        if !mount_point.inode()?.itype().is_dir() {
+            return Err(SysError::ENOTDIR);
+        }
+        // the test code mounts the disk as vfat, which is fat32 here
+        let fstype = match fstype.as_str() {
+            "vfat" => "fat32",
+            fstype => fstype,
+        };
+        let fs_type = vfs::mount::fs_type(fstype)?;
+        let dev = if fs_type.fs_flags().contains(FileSystemFlags::REQUIRES_DEV) {
+            let source = task.resolve_path(&source)?;
+            Some(block_device_of(source.inode()?)?)
+        } else {
+            // no backing device, `source` is only a name
+            None
         };
-        // Need a mount_point struct to manage fs_root
+        let parent = mount_point.parent().ok_or(SysError::EBUSY)?;
+        let fs_root = fs_type.mount(mount_point.name(), Some(parent), flags, dev)?;
+        fs_root.set_state(DentryState::Sync);
         Ok(0)
     }
 
+    /// umount2() detaches the topmost mount at `target`, making the dentry it
+    /// covered visible again. It fails with `EBUSY` while another file system
+    /// is mounted below it or files are open on it, unless `MNT_DETACH` asks
+    /// for a lazy unmount.
     pub async fn sys_umount2(&self, target: UserReadPtr<u8>, flags: u32) -> SyscallResult {
         let task = self.task;
         let mount_path = target.read_cstr(&task)?;
-        let _flags = MountFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
-        log::info!("[sys_umount2] umount path:{mount_path:?}");
+        let flags = UmountFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
+        log::info!("[sys_umount2] umount path:{mount_path:?}, flags:{flags:?}");
+        // NOTE: no mount is ever expired, as nothing would unmount it
+        if flags.contains(UmountFlags::MNT_EXPIRE) {
+            return Err(SysError::EINVAL);
+        }
+        if !task.cred_ref().has_cap(Capabilities::SYS_ADMIN) {
+            return Err(SysError::EPERM);
+        }
+        let path = mount_path.trim_end_matches('/');
+        let (parent, name) = match path.rsplit_once('/') {
+            Some(("", name)) => (task.root(), name),
+            Some((parent, name)) => (task.resolve_path(parent)?, name),
+            None => (task.cwd(), path),
+        };
+        let mount = find_mount(&parent, name).ok_or(SysError::EINVAL)?;
+        // MNT_FORCE only aborts requests of network file systems
+        umount(&mount, flags.contains(UmountFlags::MNT_DETACH))?;
+        Ok(0)
+    }
+
+    /// pivot_root() moves the root mount to the directory `put_old` and makes
+    /// `new_root` the new root mount. Tasks whose root or cwd is the old root
+    /// are moved to the new root.
+    ///
+    /// There is a single mount namespace, so this changes the root of the
+    /// whole system. The mount of `new_root` is detached from its mount point
+    /// and the old root takes the place of `put_old`, the mount table records
+    /// where both stand now, see `vfs_core::pivot_root`. If `put_old` is
+    /// `new_root` itself, the old root is not reachable anymore.
+    pub fn sys_pivot_root(
+        &self,
+        new_root: UserReadPtr<u8>,
+        put_old: UserReadPtr<u8>,
+    ) -> SyscallResult {
+        let task = self.task;
+        if !task.cred_ref().has_cap(Capabilities::SYS_ADMIN) {
+            return Err(SysError::EPERM);
+        }
+        let new_root = new_root.read_cstr(&task)?;
+        let put_old = put_old.read_cstr(&task)?;
+        log::info!("[sys_pivot_root] new_root:{new_root}, put_old:{put_old}");
+        let new_root = task.resolve_path(&new_root)?;
+        let put_old = task.resolve_path(&put_old)?;
+        if !new_root.inode()?.itype().is_dir() || !put_old.inode()?.itype().is_dir() {
+            return Err(SysError::ENOTDIR);
+        }
+        let old_root = task.root();
+        if Arc::ptr_eq(&new_root, &old_root) {
+            return Err(SysError::EBUSY);
+        }
+        if !new_root.is_mount_root() || !old_root.is_mount_root() {
+            return Err(SysError::EINVAL);
+        }
+        if !Arc::ptr_eq(&put_old, &new_root) && !put_old.is_descendant_of(&new_root) {
+            return Err(SysError::EINVAL);
+        }
+        vfs_core::pivot_root(&new_root, &put_old)?;
+
+        vfs::set_sys_root_dentry(new_root.clone());
+        for t in TASK_MANAGER.tasks() {
+            if Arc::ptr_eq(&t.root(), &old_root) {
+                t.set_root(new_root.clone());
+            }
+            if Arc::ptr_eq(&t.cwd(), &old_root) {
+                t.set_cwd(new_root.clone());
+            }
+        }
         Ok(0)
     }
 
+    /// statmount() queries information about the mount identified by
+    /// `req.mnt_id`. `req.param` is a mask of `StatMountMask` selecting which
+    /// fields are filled in. String fields are stored after the fixed
+    /// `StatMount` struct in `buf`.
+    ///
+    /// If `bufsize` is too small to hold all requested information, EOVERFLOW
+    /// is returned.
+    pub fn sys_statmount(
+        &self,
+        req: UserReadPtr<MntIdReq>,
+        buf: UserWritePtr<u8>,
+        bufsize: usize,
+        flags: u32,
+    ) -> SyscallResult {
+        let task = self.task;
+        if flags != 0 {
+            return Err(SysError::EINVAL);
+        }
+        let req = req.read(task)?;
+        if req.size < MNT_ID_REQ_SIZE_VER0 {
+            return Err(SysError::EINVAL);
+        }
+        let mask = StatMountMask::from_bits_truncate(req.param);
+        log::info!("[sys_statmount] mnt_id:{}, mask:{mask:?}", req.mnt_id);
+        let (mount_path, sb) = vfs::mounts()
+            .into_iter()
+            .find(|(_, sb)| sb.mnt_id() as u64 == req.mnt_id)
+            .ok_or(SysError::ENOENT)?;
+
+        let mut stat = StatMount::zeroed();
+        let mut strs = Vec::new();
+        let mut push_str = |s: &str| {
+            let off = strs.len() as u32;
+            strs.extend_from_slice(s.as_bytes());
+            strs.push(0);
+            off
+        };
+        if mask.contains(StatMountMask::SB_BASIC) {
+            stat.sb_magic = fs_magic(sb.fs_type().name());
+        }
+        if mask.contains(StatMountMask::MNT_BASIC) {
+            stat.mnt_id = sb.mnt_id() as u64;
+            stat.mnt_parent_id = parent_mnt_id(&sb) as u64;
+            stat.mnt_id_old = stat.mnt_id as u32;
+            stat.mnt_parent_id_old = stat.mnt_parent_id as u32;
+            // NOTE: mount propagation is not supported, every mount is private
+            stat.mnt_propagation = MS_PRIVATE;
+        }
+        if mask.contains(StatMountMask::MNT_ROOT) {
+            stat.mnt_root = push_str("/");
+        }
+        if mask.contains(StatMountMask::MNT_POINT) {
+            stat.mnt_point = push_str(&mount_path);
+        }
+        if mask.contains(StatMountMask::FS_TYPE) {
+            stat.fs_type = push_str(sb.fs_type().name());
+        }
+        stat.mask = (mask
+            & (StatMountMask::SB_BASIC
+                | StatMountMask::MNT_BASIC
+                | StatMountMask::MNT_ROOT
+                | StatMountMask::MNT_POINT
+                | StatMountMask::FS_TYPE))
+            .bits();
+
+        let size = size_of::<StatMount>() + strs.len();
+        if bufsize < size {
+            return Err(SysError::EOVERFLOW);
+        }
+        stat.size = size as u32;
+        let mut buf = buf.into_mut_slice(task, size)?;
+        let stat_bytes = unsafe {
+            core::slice::from_raw_parts(
+                &stat as *const StatMount as *const u8,
+                size_of::<StatMount>(),
+            )
+        };
+        buf[..size_of::<StatMount>()].copy_from_slice(stat_bytes);
+        buf[size_of::<StatMount>()..].copy_from_slice(&strs);
+        Ok(0)
+    }
+
+    /// listmount() lists the ids of the mounts directly under the mount
+    /// `req.mnt_id`, or under the root mount if it is `LSMT_ROOT`. Ids are
+    /// returned in ascending order, starting after `req.param` so that the
+    /// caller can continue a listing.
+    ///
+    /// On success, the number of ids written to `mnt_ids` is returned.
+    pub fn sys_listmount(
+        &self,
+        req: UserReadPtr<MntIdReq>,
+        mnt_ids: UserWritePtr<u64>,
+        nr_mnt_ids: usize,
+        flags: u32,
+    ) -> SyscallResult {
+        let task = self.task;
+        if flags != 0 {
+            return Err(SysError::EINVAL);
+        }
+        let req = req.read(task)?;
+        if req.size < MNT_ID_REQ_SIZE_VER0 {
+            return Err(SysError::EINVAL);
+        }
+        log::info!(
+            "[sys_listmount] mnt_id:{}, last_mnt_id:{}, nr_mnt_ids:{nr_mnt_ids}",
+            req.mnt_id,
+            req.param
+        );
+        let mounts = vfs::mounts();
+        let target = if req.mnt_id == LSMT_ROOT {
+            sys_root_dentry().super_block().mnt_id()
+        } else {
+            mounts
+                .iter()
+                .find(|(_, sb)| sb.mnt_id() as u64 == req.mnt_id)
+                .ok_or(SysError::ENOENT)?
+                .1
+                .mnt_id()
+        };
+        let ids: Vec<u64> = mounts
+            .iter()
+            .filter(|(_, sb)| sb.mnt_id() != target && parent_mnt_id(sb) == target)
+            .map(|(_, sb)| sb.mnt_id() as u64)
+            .filter(|&id| id > req.param)
+            .take(nr_mnt_ids)
+            .collect();
+        mnt_ids.write_array(task, &ids)?;
+        Ok(ids.len())
+    }
+
     /// On success, the number of bytes read is returned. On end of directory, 0
     /// is returned. On error, -1 is returned, and errno is set to indicate
     /// the error.
+    ///
+    /// Entries that do not fit in `buf` are left for the next call. `EINVAL` is
+    /// returned if even the first one does not fit.
     pub fn sys_getdents64(&self, fd: usize, buf: usize, len: usize) -> SyscallResult {
+        #[derive(Debug, Clone, Copy)]
+        #[repr(C)]
+        struct LinuxDirent64 {
+            d_ino: u64,
+            d_off: u64,
+            d_reclen: u16,
+            d_type: u8,
+            // d_name follows here, which will be written later
+        }
+        // NOTE: Considering C struct align, we can not use `size_of` directly, because
+        // `size_of::<LinuxDirent64>` equals 24, which is not what we want.
+        const LEN_BEFORE_NAME: usize = 19;
+
         let task = self.task;
         let file = task.with_fd_table(|table| table.get_file(fd))?;
-        // let mut writen_len = 0;
+        if !file.itype().is_dir() {
+            return Err(SysError::ENOTDIR);
+        }
         let mut buf = UserWritePtr::<u8>::from(buf).into_mut_slice(&task, len)?;
-        file.read_dir(&mut buf)
+        let mut writen_len = 0;
+        loop {
+            let pos = file.pos();
+            let Some(entry) = file.read_dir()? else {
+                break;
+            };
+            // align to 8 bytes
+            let c_name_len = entry.name.len() + 1;
+            let rec_len = (LEN_BEFORE_NAME + c_name_len + 7) & !0x7;
+            if writen_len + rec_len > buf.len() {
+                // read again by the next call
+                file.set_pos(pos);
+                if writen_len == 0 {
+                    return Err(SysError::EINVAL);
+                }
+                break;
+            }
+            let linux_dirent = LinuxDirent64 {
+                d_ino: entry.ino,
+                d_off: entry.off,
+                d_reclen: rec_len as u16,
+                // `InodeType` takes the values of `DT_*`
+                d_type: entry.itype as u8,
+            };
+            log::debug!("[sys_getdents64] linux dirent {linux_dirent:?}");
+            let record = &mut buf[writen_len..writen_len + rec_len];
+            unsafe {
+                (record.as_mut_ptr() as *mut LinuxDirent64).write_unaligned(linux_dirent);
+            }
+            // the name is followed by a nul and the padding, which overlap the
+            // padding of the struct
+            record[LEN_BEFORE_NAME..].fill(0);
+            record[LEN_BEFORE_NAME..LEN_BEFORE_NAME + c_name_len - 1]
+                .copy_from_slice(entry.name.as_bytes());
+            writen_len += rec_len;
+        }
+        Ok(writen_len)
     }
 
     /// pipe() creates a pipe, a unidirectional data channel that can be used
@@ -509,9 +1162,8 @@ impl Syscall<'_> {
     /// pipefd on failure.
     pub fn sys_pipe2(&self, pipefd: UserWritePtr<[u32; 2]>, flags: i32) -> SyscallResult {
         let task = self.task;
-        let flags = OpenFlags::from_bits(flags)
-            .unwrap_or_else(|| unimplemented!("unknown flags, should add them"));
-        let (pipe_read, pipe_write) = new_pipe(PIPE_BUF_LEN);
+        let flags = OpenFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
+        let (pipe_read, pipe_write) = new_pipe(PIPE_BUF_LEN, flags);
         let pipe = task.with_mut_fd_table(|table| {
             let fd_read = table.alloc(pipe_read, flags)?;
             let fd_write = table.alloc(pipe_write, flags)?;
@@ -522,6 +1174,108 @@ impl Syscall<'_> {
         Ok(0)
     }
 
+    /// eventfd2() creates an event file whose counter starts at `initval`, and
+    /// returns a file descriptor of it. A write adds to the counter, and a
+    /// read returns and resets it, or only takes one with `EFD_SEMAPHORE`.
+    pub fn sys_eventfd2(&self, initval: u32, flags: u32) -> SyscallResult {
+        let task = self.task;
+        let flags = EventFdFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
+        let file = EventFdFile::new(initval, flags);
+        let fd_flags = if flags.contains(EventFdFlags::EFD_CLOEXEC) {
+            OpenFlags::O_CLOEXEC
+        } else {
+            OpenFlags::empty()
+        };
+        let fd = task.with_mut_fd_table(|table| table.alloc(file, fd_flags))?;
+        log::info!("[sys_eventfd2] fd: {fd}, initval: {initval}, flags: {flags:?}");
+        Ok(fd)
+    }
+
+    /// signalfd4() creates a file that can be read for the signals in `mask`
+    /// pending for the reading task, instead of having them delivered. With
+    /// `fd` of -1 a new file is created, otherwise `fd` must be a signal file
+    /// whose mask is replaced.
+    pub fn sys_signalfd4(
+        &self,
+        fd: isize,
+        mask: UserReadPtr<SigSet>,
+        sizemask: usize,
+        flags: u32,
+    ) -> SyscallResult {
+        let task = self.task;
+        let flags = SignalFdFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
+        if sizemask != size_of::<SigSet>() {
+            return Err(SysError::EINVAL);
+        }
+        let mask = mask.read(&task)?;
+        log::info!("[sys_signalfd4] fd: {fd}, mask: {mask:#x}, flags: {flags:?}");
+        if fd != -1 {
+            let file = task
+                .with_fd_table(|table| table.get_file(fd as usize))?
+                .downcast_arc::<SignalFdFile>()
+                .map_err(|_| SysError::EINVAL)?;
+            file.set_mask(mask);
+            return Ok(fd as usize);
+        }
+        let file = SignalFdFile::new(mask, flags);
+        let fd_flags = if flags.contains(SignalFdFlags::SFD_CLOEXEC) {
+            OpenFlags::O_CLOEXEC
+        } else {
+            OpenFlags::empty()
+        };
+        task.with_mut_fd_table(|table| table.alloc(file, fd_flags))
+    }
+
+    /// timerfd_create() creates a timer file on `clockid`, which is either
+    /// `CLOCK_REALTIME` or `CLOCK_MONOTONIC`, and returns a file descriptor of
+    /// it. A read returns the number of expirations since the last read.
+    pub fn sys_timerfd_create(&self, clockid: usize, flags: u32) -> SyscallResult {
+        let task = self.task;
+        let flags = TimerFdFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
+        let file = TimerFdFile::new(clockid, flags)?;
+        let fd_flags = if flags.contains(TimerFdFlags::TFD_CLOEXEC) {
+            OpenFlags::O_CLOEXEC
+        } else {
+            OpenFlags::empty()
+        };
+        let fd = task.with_mut_fd_table(|table| table.alloc(file, fd_flags))?;
+        log::info!("[sys_timerfd_create] fd: {fd}, clockid: {clockid}, flags: {flags:?}");
+        Ok(fd)
+    }
+
+    /// timerfd_settime() arms or, with a zero `it_value`, disarms the timer of
+    /// `fd`, and stores the setting before in `old_value` if not null.
+    pub fn sys_timerfd_settime(
+        &self,
+        fd: usize,
+        flags: u32,
+        new_value: UserReadPtr<ITimerSpec>,
+        old_value: UserWritePtr<ITimerSpec>,
+    ) -> SyscallResult {
+        let task = self.task;
+        let flags = TimerFdSetFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
+        let new_value = new_value.read(&task)?;
+        log::info!("[sys_timerfd_settime] fd: {fd}, flags: {flags:?}, new: {new_value:?}");
+        let old = task.timerfd_lookup(fd)?.set_time(flags, new_value)?;
+        if old_value.not_null() {
+            old_value.write(&task, old)?;
+        }
+        Ok(0)
+    }
+
+    /// timerfd_gettime() stores the time until the next expiration of the timer
+    /// of `fd` and its interval in `curr_value`.
+    pub fn sys_timerfd_gettime(
+        &self,
+        fd: usize,
+        curr_value: UserWritePtr<ITimerSpec>,
+    ) -> SyscallResult {
+        let task = self.task;
+        let curr = task.timerfd_lookup(fd)?.get_time();
+        curr_value.write(&task, curr)?;
+        Ok(0)
+    }
+
     /// unlink() deletes a name from the filesystem. If that name was the last
     /// link to a file and no processes have the file open, the file is
     /// deleted and the space it was using is made available for reuse.
@@ -540,6 +1294,11 @@ impl Syscall<'_> {
     /// + AT_REMOVEDIR: By default, unlinkat() performs the equivalent of
     ///   unlink() on pathname. If the AT_REMOVEDIR flag is specified, it
     ///   performs the equivalent of rmdir(2) on pathname.
+    ///
+    /// rmdir(2) fails with `ENOTEMPTY` for a directory with entries, `EINVAL`
+    /// for a path ending in ".", and `EBUSY` for a mount point or the root. A
+    /// directory that is the cwd of some task is removed all the same, see
+    /// `Dentry::unlink`.
     // FIXME: removal is not delayed, could be done in vfs layer
     pub fn sys_unlinkat(
         &self,
@@ -548,16 +1307,38 @@ impl Syscall<'_> {
         flags: i32,
     ) -> SyscallResult {
         let task = self.task;
+        if flags & !AT_REMOVEDIR != 0 {
+            return Err(SysError::EINVAL);
+        }
         let path = pathname.read_cstr(&task)?;
-        let dentry = task.at_helper(dirfd, &path, OpenFlags::O_NOFOLLOW)?;
-        let parent = dentry.parent().expect("can not remove root directory");
+        let (parent, name) = task.resolve_parent_and_name(dirfd, &path)?;
+        match (name.as_str(), flags == AT_REMOVEDIR) {
+            (".", true) => return Err(SysError::EINVAL),
+            ("..", true) => return Err(SysError::ENOTEMPTY),
+            ("/", true) => return Err(SysError::EBUSY),
+            (".." | "." | "/", false) => return Err(SysError::EISDIR),
+            _ => {}
+        }
+        let dentry = parent.lookup(&name)?;
+        if dentry.is_negetive() {
+            return Err(SysError::ENOENT);
+        }
+        // NOTE: a mount root may sit in the tree without a parent, e.g. the old
+        // root grafted by pivot_root
+        if dentry.is_mount_root() {
+            return Err(SysError::EBUSY);
+        }
         let is_dir = dentry.inode()?.itype().is_dir();
         if flags == AT_REMOVEDIR && !is_dir {
             return Err(SysError::ENOTDIR);
-        } else if flags != AT_REMOVEDIR && is_dir {
-            return Err(SysError::EISDIR);
+        } else if flags != AT_REMOVEDIR && (is_dir || has_trailing_slash(&path)) {
+            return Err(if is_dir {
+                SysError::EISDIR
+            } else {
+                SysError::ENOTDIR
+            });
         }
-        parent.unlink(dentry.name()).map(|_| 0)
+        parent.unlink(&name).map(|_| 0)
     }
 
     pub fn sys_ioctl(&self, fd: usize, cmd: usize, arg: usize) -> SyscallResult {
@@ -583,8 +1364,8 @@ impl Syscall<'_> {
                 Ok(fd_info.flags().bits() as usize)
             }),
             FcntlOp::F_SETFD => {
-                let arg = OpenFlags::from_bits_retain(arg as i32);
-                let fd_flags = FdFlags::from(arg);
+                // NOTE: `arg` is `FD_CLOEXEC` rather than `O_CLOEXEC`
+                let fd_flags = FdFlags::from_bits_truncate(arg as u8);
                 task.with_mut_fd_table(|table| {
                     let fd_info = table.get_mut(fd)?;
                     fd_info.set_flags(fd_flags);
@@ -598,11 +1379,59 @@ impl Syscall<'_> {
             FcntlOp::F_SETFL => {
                 let flags = OpenFlags::from_bits_truncate(arg as _);
                 let file = task.with_fd_table(|table| table.get_file(fd))?;
-                file.set_flags(flags.status());
+                let old_flags = file.flags();
+                // the access mode can not be changed
+                file.set_flags(old_flags.access_mode() | flags.status());
+                let on = flags.contains(OpenFlags::O_ASYNC);
+                if on != old_flags.contains(OpenFlags::O_ASYNC) {
+                    file.base_fasync(&file, fd, on);
+                }
                 Ok(0)
             }
+            FcntlOp::F_SETOWN => {
+                let file = task.with_fd_table(|table| table.get_file(fd))?;
+                let pid = arg as i32 as isize;
+                let process = if pid > 0 {
+                    let process = TASK_MANAGER
+                        .get(pid as usize)
+                        .filter(|t| t.is_leader())
+                        .ok_or(SysError::ESRCH)?;
+                    Some(Arc::downgrade(&process) as Weak<dyn Any + Send + Sync>)
+                } else {
+                    if pid < 0 && PROCESS_GROUP_MANAGER.get_group(-pid as usize).is_none() {
+                        return Err(SysError::ESRCH);
+                    }
+                    None
+                };
+                let mut owner = file.meta().owner.lock();
+                owner.pid = pid;
+                owner.process = process;
+                Ok(0)
+            }
+            FcntlOp::F_GETOWN => {
+                let file = task.with_fd_table(|table| table.get_file(fd))?;
+                let owner = file.meta().owner.lock().clone();
+                // NOTE: the file is no longer owned once the owner process exits
+                if owner.process.is_some() && Task::sigio_owner(&owner).is_none() {
+                    return Ok(0);
+                }
+                Ok(owner.pid as usize)
+            }
+            FcntlOp::F_SETSIG => {
+                let file = task.with_fd_table(|table| table.get_file(fd))?;
+                if !Sig::from_i32(arg as i32).is_valid() {
+                    return Err(SysError::EINVAL);
+                }
+                file.meta().owner.lock().sig = arg;
+                Ok(0)
+            }
+            FcntlOp::F_GETSIG => {
+                let file = task.with_fd_table(|table| table.get_file(fd))?;
+                let sig = file.meta().owner.lock().sig;
+                Ok(sig)
+            }
             _ => {
-                log::warn!("fcntl cmd: {op:?} not implemented");
+                log::info!("fcntl cmd: {op:?} not implemented");
                 Ok(0)
             }
         }
@@ -619,22 +1448,21 @@ impl Syscall<'_> {
     ) -> SyscallResult {
         let task = self.task;
         let file = task.with_fd_table(|f| f.get_file(fd))?;
-        let mut offset = file.pos();
-        let mut total_len = 0;
         let iovs = iov.into_slice(&task, iovcnt)?;
+        let mut slices = Vec::with_capacity(iovcnt);
         for (i, iov) in iovs.iter().enumerate() {
             if iov.len == 0 {
                 continue;
             }
             let ptr = UserReadPtr::<u8>::from(iov.base);
             log::debug!("[sys_writev] iov #{i}, ptr: {ptr}, len: {}", iov.len);
-            let buf = ptr.into_slice(&task, iov.len)?;
-            let write_len = file.write_at(offset, &buf).await?;
-            total_len += write_len;
-            offset += write_len;
+            slices.push(ptr.into_slice(&task, iov.len)?);
         }
-        file.seek(SeekFrom::Current(total_len as i64))?;
-        Ok(total_len)
+        let bufs: Vec<&[u8]> = slices.iter().map(|buf| &**buf).collect();
+        // NOTE: the whole vector is written under one offset lock and, with
+        // `O_APPEND`, one inode append lock, so it is never interleaved with
+        // other writes
+        file.write_vectored(&bufs).await
     }
 
     /// The readv() system call reads iovcnt buffers from the file associated
@@ -658,7 +1486,7 @@ impl Syscall<'_> {
             let ptr = UserWritePtr::<u8>::from(iov.base);
             log::debug!("[sys_readv] iov #{i}, ptr: {ptr}, len: {}", iov.len);
             let mut buf = ptr.into_mut_slice(&task, iov.len)?;
-            let write_len = file.read_at(offset, &mut buf).await?;
+            let write_len = file.read_at_queued(offset, &mut buf).await?;
             total_len += write_len;
             offset += write_len;
         }
@@ -727,25 +1555,68 @@ impl Syscall<'_> {
 
     /// access() checks whether the calling process can access the file
     /// pathname. If pathname is a symbolic link, it is dereferenced.
-    // TODO:
+    ///
+    /// The check is done using the calling process's real UID and GID, rather
+    /// than the effective IDs as is done when actually attempting an operation.
     pub fn sys_faccessat(
         &self,
         dirfd: AtFd,
         pathname: UserReadPtr<u8>,
-        _mode: usize,
+        mode: u32,
+    ) -> SyscallResult {
+        self.faccessat(dirfd, pathname, mode, 0)
+    }
+
+    /// faccessat2() is faccessat() with a `flags` argument, which is either 0,
+    /// or the bitwise OR of:
+    /// + AT_EACCESS: Perform access checks using the effective user and group
+    ///   IDs.
+    /// + AT_SYMLINK_NOFOLLOW: If pathname is a symbolic link, do not
+    ///   dereference it.
+    pub fn sys_faccessat2(
+        &self,
+        dirfd: AtFd,
+        pathname: UserReadPtr<u8>,
+        mode: u32,
+        flags: i32,
+    ) -> SyscallResult {
+        self.faccessat(dirfd, pathname, mode, flags)
+    }
+
+    fn faccessat(
+        &self,
+        dirfd: AtFd,
+        pathname: UserReadPtr<u8>,
+        mode: u32,
         flags: i32,
     ) -> SyscallResult {
-        const AT_SYMLINK_NOFOLLOW: usize = 0x100;
-        const AT_EACCESS: usize = 0x200;
-        const AT_EMPTY_PATH: usize = 0x1000;
+        const AT_SYMLINK_NOFOLLOW: i32 = 0x100;
+        const AT_EACCESS: i32 = 0x200;
         let task = self.task;
         let pathname = pathname.read_cstr(&task)?;
-        let dentry = if flags == AT_SYMLINK_NOFOLLOW as i32 {
+        log::info!("[sys_faccessat] {pathname}, mode {mode:#o}, flags {flags:#x}");
+        if flags & !(AT_SYMLINK_NOFOLLOW | AT_EACCESS) != 0 {
+            return Err(SysError::EINVAL);
+        }
+        let mode = AccessMode::from_bits(mode).ok_or(SysError::EINVAL)?;
+        let dentry = if flags & AT_SYMLINK_NOFOLLOW != 0 {
             task.at_helper(dirfd, &pathname, OpenFlags::O_NOFOLLOW)?
         } else {
             task.at_helper(dirfd, &pathname, OpenFlags::empty())?
         };
-        dentry.open()?;
+        let stat = dentry.inode()?.get_attr()?;
+        let cred = task.cred_ref();
+        let allowed = if flags & AT_EACCESS != 0 {
+            cred.may_access(&stat, mode)
+        } else {
+            // as the real user ID, which has all permitted capabilities if root
+            let dac_override =
+                cred.uid == ROOT_UID && cred.cap_permitted.contains(Capabilities::DAC_OVERRIDE);
+            Credentials::check_access(cred.uid, cred.gid, &cred.groups, dac_override, &stat, mode)
+        };
+        if !allowed {
+            return Err(SysError::EACCES);
+        }
         Ok(0)
     }
 
@@ -807,6 +1678,9 @@ impl Syscall<'_> {
     ///
     /// If times is NULL, then the access and modification times of the file are
     /// set to the current time.
+    ///
+    /// Changing both times to the current time needs write permission on the
+    /// file, and changing them to other values needs to own the file.
     pub fn sys_utimensat(
         &self,
         dirfd: AtFd,
@@ -816,19 +1690,26 @@ impl Syscall<'_> {
     ) -> SyscallResult {
         const UTIME_NOW: usize = 0x3fffffff;
         const UTIME_OMIT: usize = 0x3ffffffe;
+        const NSEC_PER_SEC: usize = 1_000_000_000;
 
         let task = self.task;
+        if flags & !AT_SYMLINK_NOFOLLOW != 0 {
+            return Err(SysError::EINVAL);
+        }
         let inode = if pathname.not_null() {
             let path = pathname.read_cstr(task)?;
-            log::info!("[sys_utimensat] dirfd: {dirfd}, path: {path}");
-            let flags = OpenFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
-            let dentry = task.at_helper(dirfd, &path, flags)?;
+            log::info!("[sys_utimensat] dirfd: {dirfd}, path: {path}, flags: {flags:#x}");
+            let dentry = if flags & AT_SYMLINK_NOFOLLOW != 0 {
+                task.at_helper(dirfd, &path, OpenFlags::O_NOFOLLOW)?
+            } else {
+                task.at_helper(dirfd, &path, OpenFlags::empty())?
+            };
             dentry.inode()?
         } else {
             // NOTE: if `pathname` is NULL, acts as futimens
             log::info!("[sys_utimensat] fd: {dirfd}");
             match dirfd {
-                AtFd::FdCwd => return Err(SysError::EINVAL),
+                AtFd::FdCwd => return Err(SysError::EFAULT),
                 AtFd::Normal(fd) => {
                     let file = task.with_fd_table(|table| table.get_file(fd))?;
                     file.inode()
@@ -836,29 +1717,40 @@ impl Syscall<'_> {
             }
         };
 
-        let mut inner = inode.meta().inner.lock();
-        let current_time = TimeSpec::from(get_time_duration());
-        if times.is_null() {
+        let now = TimeSpec::from(get_time_duration());
+        let mut to_now = true;
+        let (atime, mtime) = if times.is_null() {
             log::info!("[sys_utimensat] times is null, update with current time");
-            inner.atime = current_time;
-            inner.mtime = current_time;
-            inner.ctime = current_time;
+            (Some(now), Some(now))
         } else {
             let times = times.into_slice(task, 2)?;
             log::info!("[sys_utimensat] times {:?}", times);
-            match times[0].tv_nsec {
-                UTIME_NOW => inner.atime = current_time,
-                UTIME_OMIT => {}
-                _ => inner.atime = times[0],
-            };
-            match times[1].tv_nsec {
-                UTIME_NOW => inner.mtime = current_time,
-                UTIME_OMIT => {}
-                _ => inner.mtime = times[1],
+            to_now = times
+                .iter()
+                .all(|time| matches!(time.tv_nsec, UTIME_NOW | UTIME_OMIT));
+            let to_time = |time: TimeSpec| match time.tv_nsec {
+                UTIME_NOW => Ok(Some(now)),
+                UTIME_OMIT => Ok(None),
+                nsec if nsec < NSEC_PER_SEC => Ok(Some(time)),
+                _ => Err(SysError::EINVAL),
             };
-            inner.ctime = current_time;
+            (to_time(times[0])?, to_time(times[1])?)
+        };
+        if atime.is_none() && mtime.is_none() {
+            return Ok(0);
         }
 
+        let stat = inode.get_attr()?;
+        let cred = task.cred_ref();
+        if !cred.owns(&stat) {
+            if !to_now {
+                return Err(SysError::EPERM);
+            }
+            if !cred.may_access(&stat, AccessMode::W_OK) {
+                return Err(SysError::EACCES);
+            }
+        }
+        inode.set_times(atime, mtime, now)?;
         Ok(0)
     }
 
@@ -878,30 +1770,36 @@ impl Syscall<'_> {
 
         let old_dentry = task.at_helper(olddirfd, &oldpath, OpenFlags::O_NOFOLLOW)?;
         let new_dentry = task.at_helper(newdirfd, &newpath, OpenFlags::O_NOFOLLOW)?;
+        // only a directory may be renamed to a path with a trailing slash
+        if has_trailing_slash(&newpath)
+            && !old_dentry.is_negetive()
+            && !old_dentry.inode()?.itype().is_dir()
+        {
+            return Err(SysError::ENOTDIR);
+        }
 
         // TODO: currently don't care about `RENAME_WHITEOUT`
         old_dentry.rename_to(&new_dentry, flags).map(|_| 0)
     }
 
+    /// statfs() returns information about the mounted file system that the
+    /// file at `path` lives in.
     pub fn sys_statfs(&self, path: UserReadPtr<u8>, buf: UserWritePtr<StatFs>) -> SyscallResult {
         let task = self.task;
-        let _path = path.read_cstr(task)?;
-        let stfs = StatFs {
-            f_type: 0x2011BAB0 as i64,
-            f_bsize: BLOCK_SIZE as i64,
-            f_blocks: 1 << 27,
-            f_bfree: 1 << 26,
-            f_bavail: 1 << 20,
-            f_files: 1 << 10,
-            f_ffree: 1 << 9,
-            f_fsid: [0; 2],
-            f_namelen: 1 << 8,
-            f_frsize: 1 << 9,
-            f_flags: 1 << 1 as i64,
-            f_spare: [0; 4],
-        };
-        // TODO: find the target fs
-        buf.write(task, stfs)?;
+        let path = path.read_cstr(task)?;
+        log::info!("[sys_statfs] path:{path}");
+        let dentry = task.resolve_path(&path)?;
+        let stat_fs = dentry.inode()?.super_block().stat_fs()?;
+        buf.write(task, stat_fs)?;
+        Ok(0)
+    }
+
+    /// fstatfs() is like statfs() for the open file `fd`.
+    pub fn sys_fstatfs(&self, fd: usize, buf: UserWritePtr<StatFs>) -> SyscallResult {
+        let task = self.task;
+        let file = task.with_fd_table(|table| table.get_file(fd))?;
+        log::info!("[sys_fstatfs] fd:{fd}");
+        buf.write(task, file_stat_fs(&file)?)?;
         Ok(0)
     }
 
@@ -927,19 +1825,187 @@ impl Syscall<'_> {
         file.readlink(&mut buf).await
     }
 
-    pub async fn sys_ftruncate(&self, fd: usize, length: u64) -> SyscallResult {
+    /// The truncate() and ftruncate() functions cause the regular file named by
+    /// path or referenced by fd to be truncated to a size of precisely length
+    /// bytes.
+    ///
+    /// If the file previously was larger than this size, the extra data is
+    /// lost. If the file previously was shorter, it is extended, and the
+    /// extended part reads as null bytes ('\0').
+    pub fn sys_truncate(&self, path: UserReadPtr<u8>, length: isize) -> SyscallResult {
+        let task = self.task;
+        let path = path.read_cstr(&task)?;
+        log::info!("[sys_truncate] path {path}, length:{length}");
+        if length < 0 {
+            return Err(SysError::EINVAL);
+        }
+        let inode = task.resolve_path(&path)?.inode()?;
+        inode.get_write_access()?;
+        let ret = inode.truncate(length as usize);
+        inode.put_write_access();
+        ret?;
+        sync_file_mappings(&inode);
+        Ok(0)
+    }
+
+    pub fn sys_ftruncate(&self, fd: usize, length: isize) -> SyscallResult {
         let task = self.task;
         let file = task.with_fd_table(|table| table.get_file(fd))?;
-        log::warn!(
+        log::info!(
             "[sys_ftruncate] file path {}, length:{length}",
             file.dentry().path()
         );
-        file.inode().truncate(length as usize)
+        if length < 0 {
+            return Err(SysError::EINVAL);
+        }
+        if !file.flags().writable() {
+            return Err(SysError::EINVAL);
+        }
+        let inode = file.inode();
+        inode.truncate(length as usize)?;
+        sync_file_mappings(&inode);
+        Ok(0)
+    }
+
+    /// fsync() transfers all modified data of the file referred to by fd to
+    /// the disk device, and then its metadata such as the file size.
+    ///
+    /// The call blocks until the device reports that the transfer has
+    /// completed.
+    pub async fn sys_fsync(&self, fd: usize) -> SyscallResult {
+        let file = self.sync_file(fd)?;
+        log::info!("[sys_fsync] file path {}", file.dentry().path());
+        file.fsync(false).await?;
+        Ok(0)
+    }
+
+    /// fdatasync() is similar to fsync(), but does not flush modified metadata
+    /// unless that metadata is needed in order to allow a subsequent data
+    /// retrieval to be correctly handled, e.g. the file size.
+    pub async fn sys_fdatasync(&self, fd: usize) -> SyscallResult {
+        let file = self.sync_file(fd)?;
+        log::info!("[sys_fdatasync] file path {}", file.dentry().path());
+        file.fsync(true).await?;
+        Ok(0)
+    }
+
+    /// Get the file of `fd` to be synchronized. Pipes, FIFOs, sockets and
+    /// files without a type like perf events keep nothing to be synchronized.
+    fn sync_file(&self, fd: usize) -> SysResult<Arc<dyn File>> {
+        let file = self.task.with_fd_table(|table| table.get_file(fd))?;
+        if file.is::<Socket>() {
+            return Err(SysError::EINVAL);
+        }
+        let itype = file.inode().itype();
+        if itype.is_fifo() || itype.is_socket() || itype == InodeType::Unknown {
+            return Err(SysError::EINVAL);
+        }
+        Ok(file)
+    }
+
+    /// Change the mode of the file `pathname` relative to `dirfd`. A symbolic
+    /// link has no mode of its own, so `AT_SYMLINK_NOFOLLOW` on one fails with
+    /// `EOPNOTSUPP`.
+    pub fn sys_fchmodat(
+        &self,
+        dirfd: AtFd,
+        pathname: UserReadPtr<u8>,
+        mode: u32,
+        flags: i32,
+    ) -> SyscallResult {
+        let task = self.task;
+        if flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH) != 0 {
+            return Err(SysError::EINVAL);
+        }
+        let path = pathname.read_cstr(task)?;
+        log::info!(
+            "[sys_fchmodat] dirfd: {dirfd}, path: {path}, mode: {mode:#o}, flags: {flags:#x}"
+        );
+        let inode = self.at_inode(dirfd, &path, flags)?;
+        if inode.itype().is_symlink() {
+            return Err(SysError::EOPNOTSUPP);
+        }
+        self.chmod(inode, mode)
+    }
+
+    /// Change the mode of the open file `fd`.
+    pub fn sys_fchmod(&self, fd: usize, mode: u32) -> SyscallResult {
+        log::info!("[sys_fchmod] fd: {fd}, mode: {mode:#o}");
+        let file = self.task.with_fd_table(|table| table.get_file(fd))?;
+        self.chmod(file.inode(), mode)
     }
 
-    /// Modify the permissions of a file or directory relative to a certain
-    /// directory or location
-    pub fn sys_fchmodat(&self) -> SyscallResult {
+    /// fchownat() changes the owner and group of the file `pathname` relative
+    /// to `dirfd`. An ID of -1 is left unchanged. Unlike its mode, a symbolic
+    /// link has an owner of its own, which `AT_SYMLINK_NOFOLLOW` changes.
+    pub fn sys_fchownat(
+        &self,
+        dirfd: AtFd,
+        pathname: UserReadPtr<u8>,
+        uid: Uid,
+        gid: Gid,
+        flags: i32,
+    ) -> SyscallResult {
+        let task = self.task;
+        if flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH) != 0 {
+            return Err(SysError::EINVAL);
+        }
+        let path = pathname.read_cstr(task)?;
+        log::info!(
+            "[sys_fchownat] dirfd: {dirfd}, path: {path}, uid: {uid}, gid: {gid}, flags: {flags:#x}"
+        );
+        let inode = self.at_inode(dirfd, &path, flags)?;
+        self.chown(inode, uid, gid)
+    }
+
+    /// fchown() changes the owner and group of the file open at `fd`. An ID of
+    /// -1 is left unchanged.
+    pub fn sys_fchown(&self, fd: usize, uid: Uid, gid: Gid) -> SyscallResult {
+        log::info!("[sys_fchown] fd: {fd}, uid: {uid}, gid: {gid}");
+        let file = self.task.with_fd_table(|table| table.get_file(fd))?;
+        self.chown(file.inode(), uid, gid)
+    }
+
+    /// Changing the owner needs `CAP_CHOWN`. The owner may change the group to
+    /// one it is in, any other group change needs `CAP_CHOWN` as well.
+    fn chown(&self, inode: Arc<dyn Inode>, uid: Uid, gid: Gid) -> SyscallResult {
+        const UNCHANGED: u32 = u32::MAX;
+        let stat = inode.get_attr()?;
+        let cred = self.task.cred_ref();
+        let may_chown = cred.has_cap(Capabilities::CHOWN);
+        if uid != UNCHANGED && uid != stat.st_uid && !may_chown {
+            return Err(SysError::EPERM);
+        }
+        if gid != UNCHANGED
+            && gid != stat.st_gid
+            && !may_chown
+            && !(cred.fsuid == stat.st_uid && cred.in_group(gid))
+        {
+            return Err(SysError::EPERM);
+        }
+        inode.set_owner(
+            (uid != UNCHANGED).then_some(uid),
+            (gid != UNCHANGED).then_some(gid),
+        )?;
+        inode.meta().inner.lock().ctime = TimeSpec::from(get_time_duration());
+        Ok(0)
+    }
+
+    /// Only the owner may change the mode. An owner outside the group of the
+    /// file and without `CAP_FSETID` can not set the set-group-ID bit, which
+    /// is dropped as in linux.
+    fn chmod(&self, inode: Arc<dyn Inode>, mode: u32) -> SyscallResult {
+        let stat = inode.get_attr()?;
+        let cred = self.task.cred_ref();
+        if !cred.owns(&stat) {
+            return Err(SysError::EPERM);
+        }
+        let mut mode = InodeMode::from_bits_truncate(mode & 0o7777);
+        if !cred.has_cap(Capabilities::FSETID) && !cred.in_group(stat.st_gid) {
+            mode.remove(InodeMode::SET_GID);
+        }
+        inode.set_mode(mode)?;
+        inode.meta().inner.lock().ctime = TimeSpec::from(get_time_duration());
         Ok(0)
     }
 
@@ -954,8 +2020,11 @@ impl Syscall<'_> {
         let task = self.task;
         let linkpath = linkpath.read_cstr(task)?;
         let target = target.read_cstr(task)?;
-        let dentry = task.at_helper(newdirfd, &linkpath, OpenFlags::O_NOFOLLOW)?;
-        dentry.parent().unwrap().symlink(dentry.name(), &target)?;
+        let (parent, name) = task.resolve_parent_and_name(newdirfd, &linkpath)?;
+        if matches!(name.as_str(), "." | ".." | "/") || !parent.lookup(&name)?.is_negetive() {
+            return Err(SysError::EEXIST);
+        }
+        parent.symlink(&name, &target)?;
         Ok(0)
     }
 
@@ -1076,3 +2145,11 @@ impl Syscall<'_> {
         Ok(out_len)
     }
 }
+
+impl Task {
+    fn timerfd_lookup(&self, fd: usize) -> SysResult<Arc<TimerFdFile>> {
+        self.with_fd_table(|table| table.get_file(fd))?
+            .downcast_arc::<TimerFdFile>()
+            .map_err(|_| SysError::EINVAL)
+    }
+}
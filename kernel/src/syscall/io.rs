@@ -4,6 +4,7 @@ use core::{
     mem::{self, size_of},
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use async_utils::{Select2Futures, SelectOutput};
@@ -77,6 +78,16 @@ impl FdSet {
     }
 }
 
+/// The sixth argument of pselect6.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct PSelectSigMask {
+    /// Pointer to the signal mask, may be null.
+    ss: usize,
+    /// Size of the signal mask in bytes.
+    ss_len: usize,
+}
+
 pub struct PPollFuture {
     polls: Vec<(PollEvents, Arc<dyn File>)>,
 }
@@ -150,19 +161,41 @@ impl Syscall<'_> {
         sigmask: UserReadPtr<SigSet>,
     ) -> SyscallResult {
         let task = self.task;
-        let fds_va: VirtAddr = fds.as_usize().into();
-        let mut poll_fds = fds.read_array(&task, nfds)?;
         let timeout = if timeout.is_null() {
             None
         } else {
             Some(timeout.read(&task)?.into())
         };
-
         let new_mask = if sigmask.is_null() {
             None
         } else {
             Some(sigmask.read(task)?)
         };
+        self.poll(fds, nfds, timeout, new_mask).await
+    }
+
+    /// The legacy poll(), whose timeout is in milliseconds, and negative for
+    /// none.
+    pub async fn sys_poll(
+        &self,
+        fds: UserRdWrPtr<PollFd>,
+        nfds: usize,
+        timeout_ms: i32,
+    ) -> SyscallResult {
+        let timeout = u64::try_from(timeout_ms).ok().map(Duration::from_millis);
+        self.poll(fds, nfds, timeout, None).await
+    }
+
+    async fn poll(
+        &self,
+        fds: UserRdWrPtr<PollFd>,
+        nfds: usize,
+        timeout: Option<Duration>,
+        new_mask: Option<SigSet>,
+    ) -> SyscallResult {
+        let task = self.task;
+        let fds_va: VirtAddr = fds.as_usize().into();
+        let mut poll_fds = fds.read_array(&task, nfds)?;
         log::info!(
             "[sys_ppoll] fds:{poll_fds:?}, nfds:{nfds}, timeout:{timeout:?}, sigmask:{new_mask:?}"
         );
@@ -188,10 +221,10 @@ impl Syscall<'_> {
         task.set_wake_up_signal(!*task.sig_mask_ref());
         let ret_vec = if let Some(timeout) = timeout {
             match TimeLimitedTaskFuture::new(timeout, poll_future).await {
-                TimeLimitedTaskOutput::Ok(ret_vec) => ret_vec,
+                TimeLimitedTaskOutput::Ok(ret_vec) => Ok(ret_vec),
                 TimeLimitedTaskOutput::TimeOut => {
                     log::debug!("[sys_ppoll]: timeout");
-                    return Ok(0);
+                    Ok(Vec::new())
                 }
             }
         } else {
@@ -200,21 +233,21 @@ impl Syscall<'_> {
                 mask: *task.sig_mask_ref(),
             };
             match Select2Futures::new(poll_future, intr_future).await {
-                SelectOutput::Output1(ret_vec) => ret_vec,
-                SelectOutput::Output2(_) => return Err(SysError::EINTR),
+                SelectOutput::Output1(ret_vec) => Ok(ret_vec),
+                SelectOutput::Output2(_) => Err(SysError::EINTR),
             }
         };
         task.set_running();
+        if let Some(old_mask) = old_mask {
+            *task.sig_mask() = old_mask;
+        }
+        let ret_vec = ret_vec?;
 
         let ret = ret_vec.len();
         for (i, result) in ret_vec {
             poll_fds[i].revents |= result
         }
         poll_fds_slice.copy_from_slice(&poll_fds);
-
-        if let Some(old_mask) = old_mask {
-            *task.sig_mask() = old_mask;
-        }
         Ok(ret)
     }
 
@@ -223,7 +256,13 @@ impl Syscall<'_> {
     /// of I/O operation (e.g., input possible). A file descriptor is considered
     /// ready if it is possible to perform a corresponding I/O operation (e.g.,
     /// read(2), or a sufficiently small write(2)) without blocking.
-    // TODO: execptfds is not used
+    ///
+    /// On return, each of the fd sets is modified in place to indicate which
+    /// file descriptors are actually ready, and the total number of ready bits
+    /// in all three sets is returned.
+    ///
+    /// Unlike pselect(3), the last argument of the raw syscall points to a
+    /// `{ const sigset_t *ss; size_t ss_len; }` pair.
     pub async fn sys_pselect6(
         &self,
         nfds: i32,
@@ -231,22 +270,36 @@ impl Syscall<'_> {
         writefds: UserRdWrPtr<FdSet>,
         exceptfds: UserRdWrPtr<FdSet>,
         timeout: UserReadPtr<TimeSpec>,
-        sigmask: UserReadPtr<SigSet>,
+        sigmask: UserReadPtr<PSelectSigMask>,
     ) -> SyscallResult {
         let task = self.task;
-        if nfds < 0 {
+        if nfds < 0 || nfds as usize > FD_SETSIZE {
             return Err(SysError::EINVAL);
         }
         let nfds = nfds as usize;
         let timeout = if timeout.is_null() {
             None
         } else {
-            Some(timeout.read(task)?.into())
+            let timeout = timeout.read(task)?;
+            if timeout.tv_nsec >= 1_000_000_000 {
+                return Err(SysError::EINVAL);
+            }
+            Some(timeout.into())
         };
         let new_mask = if sigmask.is_null() {
             None
         } else {
-            Some(sigmask.read(task)?)
+            let sigmask = sigmask.read(task)?;
+            if sigmask.ss == 0 {
+                None
+            } else {
+                if sigmask.ss_len != size_of::<SigSet>() {
+                    return Err(SysError::EINVAL);
+                }
+                let mut mask = UserReadPtr::<SigSet>::from(sigmask.ss).read(task)?;
+                mask.remove(SigSet::SIGKILL | SigSet::SIGSTOP);
+                Some(mask)
+            }
         };
 
         log::info!("[sys_pselect6] nfds:{nfds}, readfds:{readfds}, writefds:{writefds}, exceptfds:{exceptfds}, timeout:{timeout:?}, sigmask:{new_mask:?}");
@@ -287,6 +340,11 @@ impl Syscall<'_> {
                     events.insert(PollEvents::OUT)
                 }
             });
+            exceptfds.as_ref().map(|fds| {
+                if fds.is_set(fd) {
+                    events.insert(PollEvents::PRI)
+                }
+            });
             if !events.is_empty() {
                 let file = task.with_fd_table(|f| f.get_file(fd))?;
                 log::debug!("fd:{fd}, file path:{}", file.dentry().path());
@@ -305,6 +363,9 @@ impl Syscall<'_> {
             task: task.clone(),
             mask: *task.sig_mask_ref(),
         };
+        // the sets each fd was asked for, a ready fd is only reported in those
+        let requested: Vec<(Fd, PollEvents)> =
+            polls.iter().map(|(fd, events, _)| (*fd, *events)).collect();
         let pselect_future = PSelectFuture { polls };
         let ret_vec = if let Some(timeout) = timeout {
             match Select2Futures::new(
@@ -313,52 +374,60 @@ impl Syscall<'_> {
             )
             .await
             {
-                SelectOutput::Output1(time_output) => match time_output {
-                    TimeLimitedTaskOutput::Ok(ret_vec) => ret_vec,
-                    TimeLimitedTaskOutput::TimeOut => {
-                        log::debug!("[sys_pselect6]: timeout");
-                        readfds.as_mut().map(|fds| fds.clear());
-                        writefds.as_mut().map(|fds| fds.clear());
-                        exceptfds.as_mut().map(|fds| fds.clear());
-                        task.set_running();
-                        // restore old signal mask
-                        if let Some(mask) = old_mask {
-                            *task.sig_mask() = mask;
-                        }
-                        return Ok(0);
-                    }
-                },
-                SelectOutput::Output2(_) => return Err(SysError::EINTR),
+                SelectOutput::Output1(TimeLimitedTaskOutput::Ok(ret_vec)) => Ok(ret_vec),
+                SelectOutput::Output1(TimeLimitedTaskOutput::TimeOut) => {
+                    log::debug!("[sys_pselect6]: timeout");
+                    Ok(Vec::new())
+                }
+                SelectOutput::Output2(_) => Err(SysError::EINTR),
             }
         } else {
             match Select2Futures::new(pselect_future, intr_future).await {
-                SelectOutput::Output1(ret_vec) => ret_vec,
-                SelectOutput::Output2(_) => return Err(SysError::EINTR),
+                SelectOutput::Output1(ret_vec) => Ok(ret_vec),
+                SelectOutput::Output2(_) => Err(SysError::EINTR),
             }
         };
-        // NOTE: we can not clear before since EINTR will redo the syscall
-        readfds.as_mut().map(|fds| fds.clear());
-        writefds.as_mut().map(|fds| fds.clear());
-        exceptfds.as_mut().map(|fds| fds.clear());
-
         task.set_running();
-
         // restore old signal mask
         if let Some(mask) = old_mask {
             *task.sig_mask() = mask;
         }
+        // NOTE: we can not clear before since EINTR will redo the syscall
+        let ret_vec = ret_vec?;
+        readfds.as_mut().map(|fds| fds.clear());
+        writefds.as_mut().map(|fds| fds.clear());
+        exceptfds.as_mut().map(|fds| fds.clear());
 
         let mut ret = 0;
         for (fd, events) in ret_vec {
-            if events.contains(PollEvents::IN) || events.contains(PollEvents::HUP) {
-                log::info!("read ready fd {fd}");
-                readfds.as_mut().map(|fds| fds.set(fd));
-                ret += 1;
+            let Ok(idx) = requested.binary_search_by_key(&fd, |(fd, _)| *fd) else {
+                continue;
+            };
+            let wanted = requested[idx].1;
+            if wanted.contains(PollEvents::IN)
+                && events.intersects(PollEvents::IN | PollEvents::HUP | PollEvents::ERR)
+            {
+                if let Some(fds) = readfds.as_mut() {
+                    log::info!("read ready fd {fd}");
+                    fds.set(fd);
+                    ret += 1;
+                }
             }
-            if events.contains(PollEvents::OUT) {
-                log::info!("write ready fd {fd}");
-                writefds.as_mut().map(|fds| fds.set(fd));
-                ret += 1;
+            if wanted.contains(PollEvents::OUT)
+                && events.intersects(PollEvents::OUT | PollEvents::ERR)
+            {
+                if let Some(fds) = writefds.as_mut() {
+                    log::info!("write ready fd {fd}");
+                    fds.set(fd);
+                    ret += 1;
+                }
+            }
+            if wanted.contains(PollEvents::PRI) && events.contains(PollEvents::PRI) {
+                if let Some(fds) = exceptfds.as_mut() {
+                    log::info!("except ready fd {fd}");
+                    fds.set(fd);
+                    ret += 1;
+                }
             }
         }
         Ok(ret)
@@ -0,0 +1,223 @@
+use arch::time::get_time_sec;
+use memory::VirtAddr;
+use systype::{SysError, SyscallResult};
+
+use super::Syscall;
+use crate::{
+    ipc::{
+        shm::{SharedMemory, ShmIdDs, IPC_PRIVATE, SHARED_MEMORY_MANAGER},
+        IpcPerm,
+    },
+    mm::{memory_space::vm_area::MapPerm, UserReadPtr, UserWritePtr},
+    task::cred::Capabilities,
+};
+
+bitflags! {
+    #[derive(Debug)]
+    struct ShmGetFlags: i32 {
+        /// Create a new segment. If this flag is not used, then shmget() will
+        /// find the segment associated with key and check to see if the user
+        /// has permission to access the segment.
+        const IPC_CREAT = 0o1000;
+        /// This flag is used with IPC_CREAT to ensure that this call creates
+        /// the segment.  If the segment already exists, the call fails.
+        const IPC_EXCL = 0o2000;
+    }
+}
+
+bitflags! {
+    #[derive(Debug)]
+    struct ShmAtFlags: i32 {
+        /// Attach the segment for read-only access.If this flag is not specified,
+        /// the segment is attached for read and write access, and the process
+        /// must have read and write permission for the segment.
+        const SHM_RDONLY = 0o10000;
+        /// round attach address to SHMLBA boundary
+        const SHM_RND = 0o20000;
+        /// take-over region on attach
+        const SHM_REMAP = 0o40000;
+        /// Allow the contents of the segment to be executed.
+        const SHM_EXEC = 0o100000;
+    }
+}
+
+impl Syscall<'_> {
+    /// allocates a System V shared memory segment
+    ///
+    /// shmget() returns the identifier of the System V shared memory segment
+    /// associated with the value of the argument key. It may be used either to
+    /// obtain the identifier of a previously created shared memory segment
+    /// (when shmflg is zero and key does not have the value IPC_PRIVATE),
+    /// or to create a new set.
+    ///
+    /// - `key`: Key values for shared memory
+    /// - `size`: The size of the shared memory to be created. A new shared
+    ///   memory segment has a size equal to the value of size rounded up to a
+    ///   multiple of PAGE_SIZE
+    /// - `shmflg`: Together with `key`, determine the function of shmget, the
+    ///   low 9 bits of which are the permissions of a new segment
+    ///
+    /// On success, a valid shared memory identifier is returned.
+    pub fn sys_shmget(&self, key: i32, size: usize, shmflg: i32) -> SyscallResult {
+        let task = self.task;
+        let flags = ShmGetFlags::from_bits_truncate(shmflg);
+        log::info!("[sys_shmget] {key} {size} {:?}", flags);
+        // NOTE: a segment of IPC_PRIVATE is always created, whatever the flags
+        let create = (key == IPC_PRIVATE || flags.contains(ShmGetFlags::IPC_CREAT)).then(|| {
+            let cred = task.cred_ref();
+            let perm = IpcPerm::new(key, cred.euid, cred.egid, shmflg as u32 & 0o777);
+            SharedMemory::new(perm, size, task.pid())
+        });
+        SHARED_MEMORY_MANAGER.get_or_create(
+            key,
+            size,
+            flags.contains(ShmGetFlags::IPC_EXCL),
+            create,
+        )
+    }
+
+    /// After creating a shared memory, if a process wants to use it, it needs
+    /// to attach this memory area to its own process space
+    ///
+    /// - `shmid`: the return value of `sys_shmget`
+    /// - `shmaddr`: Shared memory mapping address (if NULL, automatically
+    ///   specified by the system)
+    ///
+    /// On success, sys_shmat() returns an address pointer to the shared memory
+    /// segment
+    pub fn sys_shmat(&self, shmid: usize, shmaddr: VirtAddr, shmflg: i32) -> SyscallResult {
+        let task = self.task;
+        let shmflg = ShmAtFlags::from_bits_truncate(shmflg);
+        log::info!("[sys_shmat] {shmid} {shmaddr:?} {:?}", shmflg);
+
+        if !shmaddr.is_aligned() && !shmflg.contains(ShmAtFlags::SHM_RND) {
+            // unaligned (i.e., not page-aligned and SHM_RND was not specified) shmaddr
+            // value
+            return Err(SysError::EINVAL);
+        }
+        let shmaddr_aligned = shmaddr.round_down();
+        if shmflg.contains(ShmAtFlags::SHM_REMAP) && shmaddr_aligned == VirtAddr::from(0) {
+            return Err(SysError::EINVAL);
+        }
+        let mut map_perm = MapPerm::URW;
+        if shmflg.contains(ShmAtFlags::SHM_EXEC) {
+            map_perm.insert(MapPerm::X);
+        }
+        if shmflg.contains(ShmAtFlags::SHM_RDONLY) {
+            map_perm.remove(MapPerm::W);
+        }
+        let mut shm_manager = SHARED_MEMORY_MANAGER.0.lock();
+        // Invalid shmid value
+        let shm = shm_manager.get_mut(&shmid).ok_or(SysError::EINVAL)?;
+        let ret_addr = task.with_mut_memory_space(|m| {
+            m.attach_shm(
+                shmaddr_aligned,
+                map_perm,
+                &shm.pages,
+                shmflg.contains(ShmAtFlags::SHM_REMAP),
+            )
+        })?;
+        shm.shmid_ds.attach(task.pid());
+        task.with_mut_shm_ids(|ids| {
+            ids.insert(ret_addr, shmid);
+        });
+        Ok(ret_addr.into())
+    }
+
+    /// When a process no longer uses a shared memory block, it should detach
+    /// from the shared memory block by calling the shmdt (Shared Memory
+    /// Detach) function.
+    ///
+    /// The to-be-detached segment must be currently attached with shmaddr equal
+    /// to the value returned by the attaching shmat() call
+    ///
+    /// If the process that releases this memory block is the last process
+    /// to use it, and the block is marked for removal, then this memory block
+    /// will be deleted. Calling exit or any exec family function will
+    /// automatically cause the process to detach from the shared memory block.
+    ///
+    /// On success, shmdt() returns 0;
+    pub fn sys_shmdt(&self, shmaddr: VirtAddr) -> SyscallResult {
+        log::info!("[sys_shmdt] {:?}", shmaddr);
+        let task = self.task;
+        if !shmaddr.is_aligned() {
+            // shmaddr is not aligned on a page boundary
+            return Err(SysError::EINVAL);
+        }
+        let shm_id = task.with_mut_shm_ids(|ids| ids.remove(&shmaddr));
+        if let Some(shm_id) = shm_id {
+            task.with_mut_memory_space(|m| m.detach_shm(shmaddr));
+            SHARED_MEMORY_MANAGER.detach(shm_id, task.pid());
+            Ok(0)
+        } else {
+            // There is no shared memory segment attached at shmaddr;
+            Err(SysError::EINVAL)
+        }
+    }
+
+    /// sys_shmctl performs the control operation specified by cmd on the System
+    /// V shared memory segment whose identifier is given in shmid.
+    ///
+    /// Only the owner or creator of the segment, or a process with
+    /// `CAP_SYS_ADMIN`, may change or remove it.
+    pub fn sys_shmctl(&self, shmid: usize, cmd: i32, buf: usize) -> SyscallResult {
+        // Mark the segment to be destroyed after the last process detaches it.
+        const IPC_RMID: i32 = 0;
+        // Write the uid, gid and permissions of the shmid_ds structure pointed to
+        // by buf to the segment.
+        const IPC_SET: i32 = 1;
+        // Copy information from the kernel data structure associated with `shmid`
+        // into the shmid_ds structure pointed to by buf.
+        const IPC_STAT: i32 = 2;
+        let task = self.task;
+        let may_change = |perm: &IpcPerm| {
+            let cred = task.cred_ref();
+            cred.euid == perm.uid || cred.euid == perm.cuid || cred.has_cap(Capabilities::SYS_ADMIN)
+        };
+        match cmd {
+            IPC_STAT => {
+                let shmid_ds = SHARED_MEMORY_MANAGER
+                    .0
+                    .lock()
+                    .get(&shmid)
+                    .map(|shm| shm.shmid_ds)
+                    // shmid is not a valid identifier
+                    .ok_or(SysError::EINVAL)?;
+                UserWritePtr::<ShmIdDs>::from(buf).write(task, shmid_ds)?;
+                Ok(0)
+            }
+            IPC_SET => {
+                let new = UserReadPtr::<ShmIdDs>::from(buf).read(task)?;
+                let mut shm_manager = SHARED_MEMORY_MANAGER.0.lock();
+                let shm = shm_manager.get_mut(&shmid).ok_or(SysError::EINVAL)?;
+                let perm = &mut shm.shmid_ds.shm_perm;
+                if !may_change(perm) {
+                    return Err(SysError::EPERM);
+                }
+                perm.uid = new.shm_perm.uid;
+                perm.gid = new.shm_perm.gid;
+                perm.mode = (perm.mode & !0o777) | (new.shm_perm.mode & 0o777);
+                shm.shmid_ds.shm_ctime = get_time_sec();
+                Ok(0)
+            }
+            IPC_RMID => {
+                let perm = SHARED_MEMORY_MANAGER
+                    .0
+                    .lock()
+                    .get(&shmid)
+                    .map(|shm| shm.shmid_ds.shm_perm)
+                    .ok_or(SysError::EINVAL)?;
+                if !may_change(&perm) {
+                    return Err(SysError::EPERM);
+                }
+                SHARED_MEMORY_MANAGER.remove(shmid)?;
+                Ok(0)
+            }
+            cmd => {
+                log::error!("[sys_shmctl] unimplemented cmd {cmd}");
+                // cmd is not a valid command
+                Err(SysError::EINVAL)
+            }
+        }
+    }
+}
@@ -3,10 +3,36 @@
 use core::mem::size_of;
 
 use arch::time::get_time_duration;
-use systype::SyscallResult;
+use config::mm::PAGE_SIZE;
+use systype::{SysError, SyscallResult};
+use vfs_core::OpenFlags;
 
 use super::Syscall;
-use crate::mm::UserWritePtr;
+use crate::{
+    mm::{UserReadPtr, UserWritePtr},
+    processor::shutdown::{power_off, PowerOff},
+    task::{
+        perf::{PerfEventAttr, PerfEventFile, PerfHwEvent, PERF_ATTR_DISABLED, PERF_TYPE_HARDWARE},
+        TASK_MANAGER,
+    },
+};
+
+/// Size of the first published `struct perf_event_attr`, the smallest one.
+const PERF_ATTR_SIZE_VER0: u32 = 64;
+const PERF_FLAG_FD_CLOEXEC: usize = 1 << 3;
+
+// Defined in <linux/reboot.h>.
+const LINUX_REBOOT_MAGIC1: usize = 0xfee1dead;
+const LINUX_REBOOT_MAGIC2: usize = 672274793;
+const LINUX_REBOOT_MAGIC2A: usize = 85072278;
+const LINUX_REBOOT_MAGIC2B: usize = 369367448;
+const LINUX_REBOOT_MAGIC2C: usize = 537993216;
+
+const LINUX_REBOOT_CMD_RESTART: u32 = 0x01234567;
+const LINUX_REBOOT_CMD_HALT: u32 = 0xCDEF0123;
+const LINUX_REBOOT_CMD_CAD_ON: u32 = 0x89ABCDEF;
+const LINUX_REBOOT_CMD_CAD_OFF: u32 = 0x00000000;
+const LINUX_REBOOT_CMD_POWER_OFF: u32 = 0x4321FEDC;
 
 // Defined in <sys/utsname.h>.
 #[derive(Debug, Clone, Copy)]
@@ -85,21 +111,24 @@ pub struct Sysinfo {
 }
 
 impl Sysinfo {
+    /// Memory is counted in bytes by the frame allocator, and there is no load
+    /// average kept.
     pub fn collect() -> Self {
+        let (total, free) = memory::frame_stat();
         Self {
             uptime: get_time_duration().as_secs() as i64,
             loads: [0; 3],
-            totalram: 0,
-            freeram: 0,
+            totalram: (total * PAGE_SIZE) as u64,
+            freeram: (free * PAGE_SIZE) as u64,
             sharedram: 0,
             bufferram: 0,
             totalswap: 0,
             freeswap: 0,
-            procs: 0,
+            procs: TASK_MANAGER.len().min(u16::MAX as usize) as u16,
             pad: 0,
             totalhigh: 0,
             freehigh: 0,
-            mem_uint: 0,
+            mem_uint: 1,
             _f: [0; _F_SIZE],
         }
     }
@@ -115,7 +144,7 @@ impl Syscall<'_> {
 
     pub fn sys_syslog(&self, log_type: usize, bufp: UserWritePtr<u8>, len: usize) -> SyscallResult {
         let task = self.task;
-        log::warn!("[sys_log] unimplemeted");
+        log::info!("[sys_log] unimplemeted");
         match log_type {
             2 | 3 | 4 => {
                 // For type equal to 2, 3, or 4, a successful call to syslog() returns the
@@ -140,8 +169,87 @@ impl Syscall<'_> {
         }
     }
 
+    /// The reboot() call reboots the system, or enables/disables the reboot
+    /// keystroke.
+    ///
+    /// Other harts are quiesced and filesystems are synced before the machine
+    /// is powered off.
+    pub fn sys_reboot(&self, magic: usize, magic2: usize, cmd: u32, _arg: usize) -> SyscallResult {
+        if magic != LINUX_REBOOT_MAGIC1
+            || ![
+                LINUX_REBOOT_MAGIC2,
+                LINUX_REBOOT_MAGIC2A,
+                LINUX_REBOOT_MAGIC2B,
+                LINUX_REBOOT_MAGIC2C,
+            ]
+            .contains(&magic2)
+        {
+            return Err(SysError::EINVAL);
+        }
+        match cmd {
+            LINUX_REBOOT_CMD_RESTART => power_off(PowerOff::Reboot),
+            LINUX_REBOOT_CMD_HALT | LINUX_REBOOT_CMD_POWER_OFF => power_off(PowerOff::Shutdown),
+            LINUX_REBOOT_CMD_CAD_ON | LINUX_REBOOT_CMD_CAD_OFF => Ok(0),
+            _ => Err(SysError::EINVAL),
+        }
+    }
+
+    /// sync() causes all pending modifications to file system metadata and
+    /// cached file data to be written to the underlying file systems. It is
+    /// always successful.
+    pub fn sys_sync(&self) -> SyscallResult {
+        vfs::sync_all();
+        Ok(0)
+    }
+
+    /// Overall statistics of the system: uptime, memory and the number of
+    /// tasks.
     pub fn sys_sysinfo(&self, info: UserWritePtr<Sysinfo>) -> SyscallResult {
         info.write(self.task, Sysinfo::collect())?;
         Ok(0)
     }
+
+    /// Open a counting perf event for the task `pid`, or the caller if zero.
+    /// Only the hardware events of cpu cycles and retired instructions are
+    /// supported, counted per task on any cpu, without groups or sampling.
+    pub fn sys_perf_event_open(
+        &self,
+        attr: UserReadPtr<PerfEventAttr>,
+        pid: isize,
+        cpu: isize,
+        group_fd: isize,
+        flags: usize,
+    ) -> SyscallResult {
+        let task = self.task;
+        let attr = attr.read(task)?;
+        log::info!(
+            "[sys_perf_event_open] attr {attr:?}, pid {pid}, cpu {cpu}, group_fd {group_fd}"
+        );
+        if attr.size != 0 && attr.size < PERF_ATTR_SIZE_VER0 {
+            return Err(SysError::E2BIG);
+        }
+        if attr.type_ != PERF_TYPE_HARDWARE {
+            return Err(SysError::ENOSYS);
+        }
+        let event = PerfHwEvent::from_repr(attr.config).ok_or(SysError::ENOSYS)?;
+        if pid < 0 || cpu != -1 || group_fd != -1 || attr.read_format != 0 {
+            return Err(SysError::EINVAL);
+        }
+        if flags & !PERF_FLAG_FD_CLOEXEC != 0 {
+            return Err(SysError::EINVAL);
+        }
+        let target = if pid == 0 {
+            task.clone()
+        } else {
+            TASK_MANAGER.get(pid as usize).ok_or(SysError::ESRCH)?
+        };
+        let enabled = attr.flags & PERF_ATTR_DISABLED == 0;
+        let file = PerfEventFile::new(event, target.perf_stat().clone(), enabled);
+        let fd_flags = if flags & PERF_FLAG_FD_CLOEXEC != 0 {
+            OpenFlags::O_CLOEXEC
+        } else {
+            OpenFlags::empty()
+        };
+        task.with_mut_fd_table(|table| table.alloc(file, fd_flags))
+    }
 }
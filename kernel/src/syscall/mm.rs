@@ -3,10 +3,7 @@ use memory::VirtAddr;
 use systype::{SysError, SyscallResult};
 
 use super::Syscall;
-use crate::{
-    ipc::shm::{SharedMemory, SHARED_MEMORY_KEY_ALLOCATOR, SHARED_MEMORY_MANAGER},
-    mm::{memory_space::vm_area::MapPerm, UserWritePtr},
-};
+use crate::mm::{memory_space::vm_area::MapPerm, UserWritePtr};
 
 bitflags! {
     // Defined in <bits/mman-linux.h>
@@ -45,6 +42,30 @@ bitflags! {
     }
 }
 
+bitflags! {
+    // Defined in <linux/mman.h>
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct MremapFlags: i32 {
+        /// The mapping may be moved to a new address.
+        const MREMAP_MAYMOVE = 0x1;
+        /// Move the mapping to `new_addr`, replacing what is mapped there.
+        const MREMAP_FIXED = 0x2;
+    }
+}
+
+bitflags! {
+    // Defined in <bits/mman-linux.h>
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct MsyncFlags: i32 {
+        /// Sync memory asynchronously.
+        const MS_ASYNC = 0x1;
+        /// Invalidate the caches.
+        const MS_INVALIDATE = 0x2;
+        /// Synchronous memory sync.
+        const MS_SYNC = 0x4;
+    }
+}
+
 impl From<MmapProt> for MapPerm {
     fn from(prot: MmapProt) -> Self {
         let mut ret = Self::U;
@@ -142,7 +163,7 @@ impl Syscall<'_> {
                 } else {
                     let file = task.with_fd_table(|table| table.get_file(fd))?;
                     if offset + length > file.size() {
-                        log::warn!("offset plus length is bigger than file size");
+                        log::debug!("offset plus length is bigger than file size");
                     }
                     let start_va = task.with_mut_memory_space(|m| {
                         m.alloc_mmap_area_lazily(addr, length, perm, flags, file, offset)
@@ -159,7 +180,7 @@ impl Syscall<'_> {
                 } else {
                     let file = task.with_fd_table(|table| table.get_file(fd))?;
                     if offset + length > file.size() {
-                        log::warn!("offset plus length is bigger than file size");
+                        log::debug!("offset plus length is bigger than file size");
                     }
                     // TODO: private copy on write
                     let start_va = task.with_mut_memory_space(|m| {
@@ -199,192 +220,84 @@ impl Syscall<'_> {
         Ok(0)
     }
 
-    /// allocates a System V shared memory segment
+    /// mremap() expands (or shrinks) the existing memory mapping at `old_addr`
+    /// of `old_size` bytes to `new_size` bytes, keeping its contents and
+    /// permissions.
     ///
-    /// shmget() returns the identifier of the System V shared memory segment
-    /// associated with the value of the argument key. It may be used either to
-    /// obtain the identifier of a previously created shared memory segment
-    /// (when shmflg is zero and key does not have the value IPC_PRIVATE),
-    /// or to create a new set.
+    /// Without `MREMAP_MAYMOVE` the mapping stays where it is, and growing
+    /// fails with `ENOMEM` if the range after it is taken. With
+    /// `MREMAP_FIXED` it is moved to `new_addr`, and what was mapped there is
+    /// unmapped first.
     ///
-    /// - `key`: Key values for shared memory
-    /// - `size`: The size of the shared memory to be created. A new shared
-    ///   memory segment has a size equal to the value of size rounded up to a
-    ///   multiple of PAGE_SIZE
-    /// - `shmflg`: Together with `key`, determine the function of shmget
-    ///
-    /// On success, a valid shared memory identifier is returned.
-    pub fn sys_shmget(&self, key: usize, size: usize, shmflg: i32) -> SyscallResult {
-        bitflags! {
-            #[derive(Debug)]
-            struct ShmGetFlags: i32 {
-                /// Create a new segment. If this flag is not used, then shmget() will
-                /// find the segment associated with key and check to see if the user
-                /// has permission to access the segment.
-                const IPC_CREAT = 0o1000;
-                /// This flag is used with IPC_CREAT to ensure that this call creates
-                /// the segment.  If the segment already exists, the call fails.
-                const IPC_EXCL = 0o2000;
-            }
-        }
-        let shmflg = ShmGetFlags::from_bits_truncate(shmflg);
-        log::info!("[sys_shmget] {key} {size} {:?}", shmflg);
-
-        // Create a new shared memory. When it is specified, the shmflg is invalid
-        const IPC_PRIVATE: usize = 0;
-        let rounded_up_sz = (size + PAGE_MASK) & !PAGE_MASK;
-        if key == IPC_PRIVATE {
-            let new_key = SHARED_MEMORY_KEY_ALLOCATOR.lock().alloc();
-            let new_shm = SharedMemory::new(rounded_up_sz, self.task.pid());
-            SHARED_MEMORY_MANAGER.0.lock().insert(new_key, new_shm);
-            return Ok(new_key);
-        }
-        let mut shm_manager = SHARED_MEMORY_MANAGER.0.lock();
-        if let Some(shm) = shm_manager.get(&key) {
-            // IPC_CREAT and IPC_EXCL were specified in shmflg, but a shared memory segment
-            // already exists for key.
-            if shmflg.contains(ShmGetFlags::IPC_CREAT | ShmGetFlags::IPC_EXCL) {
-                return Err(SysError::EEXIST);
-            }
-            // A segment for the given key exists, but size is greater than the size of that
-            // segment.
-            if shm.size() < size {
-                return Err(SysError::EINVAL);
-            }
-            return Ok(key);
-        }
-        if shmflg.contains(ShmGetFlags::IPC_CREAT) {
-            let new_shm = SharedMemory::new(rounded_up_sz, self.task.pid());
-            shm_manager.insert(key, new_shm);
-            return Ok(key);
-        } else {
-            // No segment exists for the given key, and IPC_CREAT was not specified.
-            return Err(SysError::ENOENT);
-        }
-    }
-
-    /// After creating a shared memory, if a process wants to use it, it needs
-    /// to attach this memory area to its own process space
-    ///
-    /// - `shmid`: the return value of `sys_shmget`
-    /// - `shmaddr`: Shared memory mapping address (if NULL, automatically
-    ///   specified by the system)
-    ///
-    /// On success, sys_shmat() returns an address pointer to the shared memory
-    /// segment
-    pub fn sys_shmat(&self, shmid: usize, shmaddr: VirtAddr, shmflg: i32) -> SyscallResult {
-        bitflags! {
-            #[derive(Debug)]
-            struct ShmAtFlags: i32 {
-                /// Attach the segment for read-only access.If this flag is not specified,
-                /// the segment is attached for read and write access, and the process
-                /// must have read and write permission for the segment.
-                const SHM_RDONLY = 0o10000;
-                /// round attach address to SHMLBA boundary
-                const SHM_RND = 0o20000;
-                /// take-over region on attach (unimplemented)
-                const SHM_REMAP = 0o40000;
-                /// Allow the contents of the segment to be executed.
-                const SHM_EXEC = 0o100000;
-            }
-        }
-        let shmflg = ShmAtFlags::from_bits_truncate(shmflg as i32);
-        log::info!("[sys_shmat] {shmid} {shmaddr:?} {:?}", shmflg);
-
-        if !shmaddr.is_aligned() && !shmflg.contains(ShmAtFlags::SHM_RND) {
-            // unaligned (i.e., not page-aligned and SHM_RND was not specified) shmaddr
-            // value
+    /// On success, mremap() returns a pointer to the new virtual memory area.
+    // TODO: `MREMAP_DONTUNMAP`, and an `old_size` of zero duplicating a shared
+    // mapping, are not supported.
+    pub fn sys_mremap(
+        &self,
+        old_addr: VirtAddr,
+        old_size: usize,
+        new_size: usize,
+        flags: i32,
+        new_addr: VirtAddr,
+    ) -> SyscallResult {
+        let task = self.task;
+        let flags = MremapFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
+        log::info!(
+            "[sys_mremap] old_addr:{old_addr:?}, old_size:{old_size:#x}, new_size:{new_size:#x}, flags:{flags:?}, new_addr:{new_addr:?}"
+        );
+        if !old_addr.is_aligned() || old_size == 0 || new_size == 0 {
             return Err(SysError::EINVAL);
         }
-        let shmaddr_aligned = shmaddr.round_down();
-        let mut map_perm = MapPerm::URW;
-        if shmflg.contains(ShmAtFlags::SHM_EXEC) {
-            map_perm.insert(MapPerm::X);
-        }
-        if shmflg.contains(ShmAtFlags::SHM_RDONLY) {
-            map_perm.remove(MapPerm::W);
-        }
-        let mut ret = 0;
-        if let Some(shm) = SHARED_MEMORY_MANAGER.0.lock().get_mut(&shmid) {
-            let task = self.task;
-            let ret_addr = task.with_mut_memory_space(|m| {
-                m.attach_shm(shm.size(), shmaddr_aligned, map_perm, &mut shm.pages)
-            });
-            task.with_mut_shm_ids(|ids| {
-                ids.insert(ret_addr, shmid);
-            });
-            ret = ret_addr.into();
-        } else {
-            // Invalid shmid value
+        let fixed = flags.contains(MremapFlags::MREMAP_FIXED);
+        if fixed && (!flags.contains(MremapFlags::MREMAP_MAYMOVE) || !new_addr.is_aligned()) {
             return Err(SysError::EINVAL);
         }
-        SHARED_MEMORY_MANAGER.attach(shmid, self.task.pid());
-        return Ok(ret);
+        let old_end = old_addr
+            .bits()
+            .checked_add(old_size)
+            .ok_or(SysError::EINVAL)?;
+        let new_size = new_size.checked_add(PAGE_MASK).ok_or(SysError::ENOMEM)? & !PAGE_MASK;
+        let new_start = if fixed { new_addr } else { old_addr };
+        new_start
+            .bits()
+            .checked_add(new_size)
+            .ok_or(SysError::ENOMEM)?;
+        let old_range = old_addr..VirtAddr::from(old_end).round_up();
+        let new_addr = fixed.then_some(new_addr);
+        let may_move = flags.contains(MremapFlags::MREMAP_MAYMOVE);
+        let start =
+            task.with_mut_memory_space(|m| m.mremap(old_range, new_size, may_move, new_addr))?;
+        Ok(start.bits())
     }
 
-    /// When a process no longer uses a shared memory block, it should detach
-    /// from the shared memory block by calling the shmdt (Shared Memory
-    /// Detach) function.
-    ///
-    /// The to-be-detached segment must be currently attached with shmaddr equal
-    /// to the value returned by the attaching shmat() call
+    /// msync() flushes changes made to the in-core copy of a file that was
+    /// mapped into memory using mmap() back to the filesystem.
     ///
-    /// If the process that releases this memory block is the last process
-    /// to use it, then this memory block will be deleted. Calling exit or any
-    /// exec family function will automatically cause the process to detach
-    /// from the shared memory block.
-    ///
-    /// On success, shmdt() returns 0;
-    pub fn sys_shmdt(&self, shmaddr: VirtAddr) -> SyscallResult {
-        log::info!("[sys_shmdt] {:?}", shmaddr);
+    /// Stores through a shared mapping go straight to the page cache, which
+    /// read() also uses, so only the write back is left to do here. With
+    /// MS_ASYNC the pages are merely marked dirty, to be written back by a
+    /// later fsync() or when the inode is dropped. MS_INVALIDATE is a no-op,
+    /// since there are no other copies of the pages to invalidate.
+    pub async fn sys_msync(&self, addr: VirtAddr, length: usize, flags: i32) -> SyscallResult {
         let task = self.task;
-        if !shmaddr.is_aligned() {
-            // shmaddr is not aligned on a page boundary
+        let flags = MsyncFlags::from_bits(flags).ok_or(SysError::EINVAL)?;
+        if !addr.is_aligned() || flags.contains(MsyncFlags::MS_ASYNC | MsyncFlags::MS_SYNC) {
             return Err(SysError::EINVAL);
         }
-        let shm_id = task.with_mut_shm_ids(|ids| ids.remove(&shmaddr));
-        if let Some(shm_id) = shm_id {
-            task.with_mut_memory_space(|m| m.detach_shm(shmaddr));
-            SHARED_MEMORY_MANAGER.detach(shm_id, task.pid());
-            Ok(0)
-        } else {
-            // There is no shared memory segment attached at shmaddr;
-            Err(SysError::EINVAL)
-        }
-    }
-
-    /// sys_shmctl performs the control operation specified by cmd on the System
-    /// V shared memory segment whose identifier is given in shmid.
-    pub fn sys_shmctl(&self, shmid: usize, cmd: i32, buf: usize) -> SyscallResult {
-        const IPC_RMID: i32 = 0;
-        const IPC_SET: i32 = 1;
-        // Copy information from the kernel data structure associated with `shmid`
-        // into the shmid_ds structure pointed to by buf.
-        const IPC_STAT: i32 = 2;
-        match cmd {
-            IPC_STAT => {
-                let shm_manager = SHARED_MEMORY_MANAGER.0.lock();
-                if let Some(shm) = shm_manager.get(&shmid) {
-                    let buf = UserWritePtr::from(buf);
-                    buf.write(&self.task, shm.shmid_ds)?;
-                    Ok(0)
-                } else {
-                    // shmid is not a valid identifier
-                    Err(SysError::EINVAL)
-                }
-            }
-            IPC_RMID => {
-                log::warn!("[sys_shmctl] IPC_RMID, do nothing");
-                Ok(0)
-            }
-            cmd => {
-                log::error!("[sys_shmctl] unimplemented cmd {cmd}");
-                // cmd is not a valid command
-                Err(SysError::EINVAL)
+        let range = addr..(addr + length).round_up();
+        log::info!("[sys_msync] range {range:?}, flags {flags:?}");
+        let files = task.with_memory_space(|m| m.shared_files_to_sync(range))?;
+        if flags.contains(MsyncFlags::MS_SYNC) {
+            for file in files {
+                file.fsync(true).await?;
             }
         }
+        Ok(0)
     }
 
+    /// Change the protection of the pages in `[addr, addr + len)` to `prot`.
+    /// Fails with `ENOMEM` if part of the range is not mapped, in which case
+    /// nothing is changed.
     pub fn sys_mprotect(&self, addr: VirtAddr, len: usize, prot: i32) -> SyscallResult {
         let task = self.task;
         if !addr.is_aligned() {
@@ -392,9 +305,45 @@ impl Syscall<'_> {
         }
         let prot = MmapProt::from_bits(prot).ok_or(SysError::EINVAL)?;
         log::info!("[sys_mprotect] addr:{addr:?}, len:{len:#x}, prot:{prot:?}");
-        let new_range = addr..(addr + len).round_up();
+        if len == 0 {
+            return Ok(0);
+        }
+        let end = addr.bits().checked_add(len).ok_or(SysError::ENOMEM)?;
+        let new_range = addr..VirtAddr::from(end).round_up();
         let perm: MapPerm = prot.into();
         task.with_mut_memory_space(|m| m.mprotect(new_range, perm))
             .map(|_| 0)
     }
+
+    /// Give advice about the use of the pages in `[addr, addr + len)`.
+    ///
+    /// - `MADV_DONTNEED` drops the pages but keeps the mappings. They fault in
+    ///   again zero filled for anonymous memory, or from the page cache for a
+    ///   file mapping, see `VmArea::discard_pages`.
+    /// - `MADV_WILLNEED` faults in the pages of file mappings ahead.
+    ///
+    /// The other advices are accepted and ignored. Fails with `ENOMEM` if part
+    /// of the range is not mapped.
+    pub fn sys_madvise(&self, addr: VirtAddr, len: usize, advice: i32) -> SyscallResult {
+        const MADV_WILLNEED: i32 = 3;
+        const MADV_DONTNEED: i32 = 4;
+        /// The last advice known, `MADV_POPULATE_WRITE`.
+        const MADV_MAX: i32 = 23;
+        let task = self.task;
+        if !addr.is_aligned() || !(0..=MADV_MAX).contains(&advice) {
+            return Err(SysError::EINVAL);
+        }
+        log::info!("[sys_madvise] addr:{addr:?}, len:{len:#x}, advice:{advice}");
+        if len == 0 {
+            return Ok(0);
+        }
+        let end = addr.bits().checked_add(len).ok_or(SysError::EINVAL)?;
+        let range = addr..VirtAddr::from(end).round_up();
+        match advice {
+            MADV_DONTNEED => task.with_mut_memory_space(|m| m.discard_pages(range))?,
+            MADV_WILLNEED => task.with_mut_memory_space(|m| m.populate_pages(range))?,
+            _ => task.with_memory_space(|m| m.areas_covering(&range).map(|_| ()))?,
+        }
+        Ok(0)
+    }
 }
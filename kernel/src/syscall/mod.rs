@@ -4,12 +4,14 @@ mod consts;
 mod fs;
 pub mod futex;
 mod io;
+mod ipc;
 mod misc;
 mod mm;
 mod net;
 mod process;
 mod random;
 mod resource;
+mod restart;
 mod sched;
 mod signal;
 mod time;
@@ -19,7 +21,11 @@ use alloc::sync::Arc;
 pub use consts::SyscallNo;
 pub use mm::MmapFlags;
 pub use process::CloneFlags;
+pub use resource::Resource;
+pub use restart::RestartPolicy;
+use signal::Sig;
 use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{AtFd, OpenFlags, AT_REMOVEDIR, AT_SYMLINK_NOFOLLOW};
 
 use crate::task::Task;
 
@@ -89,15 +95,18 @@ impl<'a> Syscall<'a> {
                     .await
             }
             SCHED_YIELD => self.sys_sched_yield().await,
-            CLONE => self.sys_clone(
-                args[0],
-                args[1].into(),
-                args[2].into(),
-                args[3].into(),
-                args[4].into(),
-            ),
+            CLONE => {
+                self.sys_clone(
+                    args[0],
+                    args[1].into(),
+                    args[2].into(),
+                    args[3].into(),
+                    args[4].into(),
+                )
+                .await
+            }
             WAIT4 => {
-                self.sys_wait4(args[0] as _, args[1].into(), args[2] as _, args[3])
+                self.sys_wait4(args[0] as _, args[1].into(), args[2] as _, args[3].into())
                     .await
             }
             GETTID => self.sys_gettid(),
@@ -108,9 +117,22 @@ impl<'a> Syscall<'a> {
             GETUID => self.sys_getuid(),
             GETEUID => self.sys_geteuid(),
             SETSID => self.sys_setsid(),
-            GETEGID => self.sys_do_nothing("getegid"),
+            GETSID => self.sys_getsid(args[0]),
+            GETEGID => self.sys_getegid(),
             SETPGID => self.sys_setpgid(args[0], args[1]),
-            GETGID => self.sys_do_nothing("getgid"),
+            GETGID => self.sys_getgid(),
+            SETUID => self.sys_setuid(args[0] as _),
+            SETGID => self.sys_setgid(args[0] as _),
+            SETRESUID => self.sys_setresuid(args[0] as _, args[1] as _, args[2] as _),
+            GETRESUID => self.sys_getresuid(args[0].into(), args[1].into(), args[2].into()),
+            SETRESGID => self.sys_setresgid(args[0] as _, args[1] as _, args[2] as _),
+            GETRESGID => self.sys_getresgid(args[0].into(), args[1].into(), args[2].into()),
+            SETFSUID => self.sys_setfsuid(args[0] as _),
+            SETFSGID => self.sys_setfsgid(args[0] as _),
+            SETGROUPS => self.sys_setgroups(args[0], args[1].into()),
+            GETGROUPS => self.sys_getgroups(args[0], args[1].into()),
+            CAPGET => self.sys_capget(args[0].into(), args[1].into()),
+            CAPSET => self.sys_capset(args[0].into(), args[1].into()),
             // Memory
             BRK => self.sys_brk(args[0].into()),
             MMAP => self.sys_mmap(
@@ -122,12 +144,22 @@ impl<'a> Syscall<'a> {
                 args[5],
             ),
             MUNMAP => self.sys_munmap(args[0].into(), args[1]),
+            MREMAP => self.sys_mremap(
+                args[0].into(),
+                args[1],
+                args[2],
+                args[3] as _,
+                args[4].into(),
+            ),
             MPROTECT => self.sys_mprotect(args[0].into(), args[1], args[2] as _),
-            MSYNC => self.sys_do_nothing("msync"),
-            MEMBARRIER => self.sys_do_nothing("membarrier"),
-            MADVISE => self.sys_do_nothing("madvise"),
+            MSYNC => self.sys_msync(args[0].into(), args[1], args[2] as _).await,
+            MEMBARRIER => {
+                self.sys_membarrier(args[0] as _, args[1] as _, args[2] as _)
+                    .await
+            }
+            MADVISE => self.sys_madvise(args[0].into(), args[1], args[2] as _),
             // Shared Memory
-            SHMGET => self.sys_shmget(args[0], args[1], args[2] as _),
+            SHMGET => self.sys_shmget(args[0] as _, args[1], args[2] as _),
             SHMAT => self.sys_shmat(args[0], args[1].into(), args[2] as _),
             SHMDT => self.sys_shmdt(args[0].into()),
             SHMCTL => self.sys_shmctl(args[0], args[1] as _, args[2]),
@@ -146,12 +178,19 @@ impl<'a> Syscall<'a> {
                 self.sys_openat(args[0].into(), args[1].into(), args[2] as _, args[3] as _)
                     .await
             }
+            OPENAT2 => {
+                self.sys_openat2(args[0].into(), args[1].into(), args[2].into(), args[3])
+                    .await
+            }
             CLOSE => self.sys_close(args[0]),
             MKDIRAT => self.sys_mkdirat(args[0].into(), args[1].into(), args[2] as _),
+            MKNODAT => self.sys_mknodat(args[0].into(), args[1].into(), args[2] as _, args[3]),
             GETCWD => self.sys_getcwd(args[0].into(), args[1]),
             CHDIR => self.sys_chdir(args[0].into()),
+            FCHDIR => self.sys_fchdir(args[0]),
             DUP => self.sys_dup(args[0]),
             DUP3 => self.sys_dup3(args[0], args[1], args[2] as _),
+            CLOSE_RANGE => self.sys_close_range(args[0], args[1], args[2] as _),
             FSTAT => self.sys_fstat(args[0], args[1].into()),
             FSTATAT => {
                 self.sys_fstatat(args[0].into(), args[1].into(), args[2].into(), args[3] as _)
@@ -169,7 +208,17 @@ impl<'a> Syscall<'a> {
                 .await
             }
             UMOUNT2 => self.sys_umount2(args[0].into(), args[1] as _).await,
+            PIVOT_ROOT => self.sys_pivot_root(args[0].into(), args[1].into()),
+            STATMOUNT => self.sys_statmount(args[0].into(), args[1].into(), args[2], args[3] as _),
+            LISTMOUNT => self.sys_listmount(args[0].into(), args[1].into(), args[2], args[3] as _),
             PIPE2 => self.sys_pipe2(args[0].into(), args[1] as _),
+            EVENTFD2 => self.sys_eventfd2(args[0] as _, args[1] as _),
+            SIGNALFD4 => self.sys_signalfd4(args[0] as _, args[1].into(), args[2], args[3] as _),
+            TIMERFD_CREATE => self.sys_timerfd_create(args[0], args[1] as _),
+            TIMERFD_SETTIME => {
+                self.sys_timerfd_settime(args[0], args[1] as _, args[2].into(), args[3].into())
+            }
+            TIMERFD_GETTIME => self.sys_timerfd_gettime(args[0], args[1].into()),
             IOCTL => self.sys_ioctl(args[0], args[1], args[2]),
             FCNTL => self.sys_fcntl(args[0], args[1] as _, args[2]),
             WRITEV => self.sys_writev(args[0], args[1].into(), args[2]).await,
@@ -178,7 +227,10 @@ impl<'a> Syscall<'a> {
                 self.sys_sendfile(args[0], args[1], args[2].into(), args[3])
                     .await
             }
-            FACCESSAT => self.sys_faccessat(args[0].into(), args[1].into(), args[2], args[3] as _),
+            FACCESSAT => self.sys_faccessat(args[0].into(), args[1].into(), args[2] as _),
+            FACCESSAT2 => {
+                self.sys_faccessat2(args[0].into(), args[1].into(), args[2] as _, args[3] as _)
+            }
             LSEEK => self.sys_lseek(args[0], args[1] as _, args[2]),
             UMASK => self.sys_umask(args[0] as _),
             UTIMENSAT => {
@@ -192,15 +244,30 @@ impl<'a> Syscall<'a> {
                 args[4] as _,
             ),
             STATFS => self.sys_statfs(args[0].into(), args[1].into()),
+            FSTATFS => self.sys_fstatfs(args[0], args[1].into()),
             READLINKAT => {
                 self.sys_readlinkat(args[0].into(), args[1].into(), args[2].into(), args[3])
                     .await
             }
-            SYNC => self.sys_do_nothing("sync"),
-            FSYNC => self.sys_do_nothing("fsync"),
-            FTRUNCATE => self.sys_ftruncate(args[0], args[1] as _).await,
-            FCHMODAT => self.sys_fchmodat(),
-            FCHOWNAT => self.sys_do_nothing("fchownat"),
+            SYNC => self.sys_sync(),
+            FSYNC => self.sys_fsync(args[0]).await,
+            FDATASYNC => self.sys_fdatasync(args[0]).await,
+            TRUNCATE => self.sys_truncate(args[0].into(), args[1] as _),
+            FTRUNCATE => self.sys_ftruncate(args[0], args[1] as _),
+            // NOTE: only `fchmodat2` takes flags
+            FCHMODAT => self.sys_fchmodat(args[0].into(), args[1].into(), args[2] as _, 0),
+            FCHMODAT2 => {
+                self.sys_fchmodat(args[0].into(), args[1].into(), args[2] as _, args[3] as _)
+            }
+            FCHMOD => self.sys_fchmod(args[0], args[1] as _),
+            FCHOWN => self.sys_fchown(args[0], args[1] as _, args[2] as _),
+            FCHOWNAT => self.sys_fchownat(
+                args[0].into(),
+                args[1].into(),
+                args[2] as _,
+                args[3] as _,
+                args[4] as _,
+            ),
             FALLOCATE => self.sys_do_nothing("fallocate"),
             SYMLINKAT => self.sys_symlinkat(args[0].into(), args[1].into(), args[2].into()),
             LINKAT => self.sys_linkat(
@@ -246,11 +313,13 @@ impl<'a> Syscall<'a> {
             TKILL => self.sys_tkill(args[0] as _, args[1] as _),
             TGKILL => self.sys_tgkill(args[0] as _, args[1] as _, args[2] as _),
             RT_SIGRETURN => self.sys_rt_sigreturn(),
+            SIGALTSTACK => self.sys_sigaltstack(args[0].into(), args[1].into()),
             RT_SIGSUSPEND => self.sys_rt_sigsuspend(args[0].into()).await,
             RT_SIGTIMEDWAIT => {
-                self.sys_rt_sigtimedwait(args[0].into(), args[1].into(), args[2].into())
+                self.sys_rt_sigtimedwait(args[0].into(), args[1].into(), args[2].into(), args[3])
                     .await
             }
+            RT_SIGPENDING => self.sys_rt_sigpending(args[0].into(), args[1]),
             // Times
             GETTIMEOFDAY => self.sys_gettimeofday(args[0].into(), args[1]),
             TIMES => self.sys_times(args[0].into()),
@@ -260,6 +329,13 @@ impl<'a> Syscall<'a> {
             CLOCK_GETRES => self.sys_clock_getres(args[0], args[1].into()),
             GETITIMER => self.sys_getitimer(args[0] as _, args[1].into()),
             SETITIMER => self.sys_setitimer(args[0] as _, args[1].into(), args[2].into()),
+            TIMER_CREATE => self.sys_timer_create(args[0], args[1].into(), args[2].into()),
+            TIMER_SETTIME => {
+                self.sys_timer_settime(args[0], args[1], args[2].into(), args[3].into())
+            }
+            TIMER_GETTIME => self.sys_timer_gettime(args[0], args[1].into()),
+            TIMER_GETOVERRUN => self.sys_timer_getoverrun(args[0]),
+            TIMER_DELETE => self.sys_timer_delete(args[0]),
             CLOCK_NANOSLEEP => {
                 self.sys_clock_nanosleep(args[0], args[1], args[2].into(), args[3].into())
                     .await
@@ -283,11 +359,21 @@ impl<'a> Syscall<'a> {
             // Schedule
             SCHED_SETSCHEDULER => self.sys_sched_setscheduler(),
             SCHED_GETSCHEDULER => self.sys_sched_getscheduler(),
-            SCHED_GETPARAM => self.sys_sched_getparam(),
-            SCHED_SETAFFINITY => self.sys_sched_setaffinity(args[0], args[1], args[2].into()),
+            SCHED_GETPARAM => self.sys_sched_getparam(args[0], args[1].into()),
+            IOPRIO_SET => self.sys_ioprio_set(args[0] as _, args[1], args[2] as _),
+            IOPRIO_GET => self.sys_ioprio_get(args[0] as _, args[1]),
+            SETPRIORITY => self.sys_setpriority(args[0] as _, args[1], args[2] as _),
+            GETPRIORITY => self.sys_getpriority(args[0] as _, args[1]),
+            SCHED_SETAFFINITY => {
+                self.sys_sched_setaffinity(args[0], args[1], args[2].into())
+                    .await
+            }
             SCHED_GETAFFINITY => self.sys_sched_getaffinity(args[0], args[1], args[2].into()),
+            GETCPU => self.sys_getcpu(args[0].into(), args[1].into()),
             // Resource
             GETRUSAGE => self.sys_getrusage(args[0] as _, args[1].into()),
+            GETRLIMIT => self.sys_getrlimit(args[0] as _, args[1].into()),
+            SETRLIMIT => self.sys_setrlimit(args[0] as _, args[1].into()),
             PRLIMIT64 => self.sys_prlimit64(args[0], args[1] as _, args[2].into(), args[3].into()),
             // Net
             SOCKET => self.sys_socket(args[0], args[1] as _, args[2]),
@@ -305,22 +391,79 @@ impl<'a> Syscall<'a> {
                 self.sys_recvfrom(args[0], args[1].into(), args[2], args[3], args[4], args[5])
                     .await
             }
-            SETSOCKOPT => self.sys_setsockopt(args[0], args[1], args[2], args[3], args[4]),
-            GETSOCKOPT => self.sys_getsockopt(args[0], args[1], args[2], args[3], args[4]),
+            SETSOCKOPT => self.sys_setsockopt(args[0], args[1], args[2], args[3].into(), args[4]),
+            GETSOCKOPT => self.sys_getsockopt(args[0], args[1], args[2], args[3].into(), args[4]),
             SHUTDOWN => self.sys_shutdown(args[0], args[1]),
-            SOCKETPAIR => self.sys_socketpair(args[0], args[1], args[2], args[3].into()),
+            SOCKETPAIR => self.sys_socketpair(args[0], args[1] as _, args[2], args[3].into()),
             SENDMSG => self.sys_sendmsg(args[0], args[1].into(), args[2]).await,
+            RECVMSG => self.sys_recvmsg(args[0], args[1].into(), args[2]).await,
             // Miscellaneous
             UNAME => self.sys_uname(args[0].into()),
             SYSLOG => self.sys_syslog(args[0], args[1].into(), args[2]),
             SYSINFO => self.sys_sysinfo(args[0].into()),
+            PERF_EVENT_OPEN => self.sys_perf_event_open(
+                args[0].into(),
+                args[1] as _,
+                args[2] as _,
+                args[3] as _,
+                args[4],
+            ),
             PERSONALITY => self.sys_do_nothing("personality"),
+            REBOOT => self.sys_reboot(args[0], args[1], args[2] as u32, args[3]),
 
             // random
             GETRANDOM => self.sys_getrandom(args[0].into(), args[1], args[2]),
+
+            // legacy
+            OPEN => {
+                self.sys_openat(AtFd::FdCwd, args[0].into(), args[1] as _, args[2] as _)
+                    .await
+            }
+            CREAT => {
+                let flags = OpenFlags::O_CREAT | OpenFlags::O_WRONLY | OpenFlags::O_TRUNC;
+                self.sys_openat(AtFd::FdCwd, args[0].into(), flags.bits(), args[1] as _)
+                    .await
+            }
+            LINK => self.sys_linkat(AtFd::FdCwd, args[0].into(), AtFd::FdCwd, args[1].into(), 0),
+            UNLINK => self.sys_unlinkat(AtFd::FdCwd, args[0].into(), 0),
+            RMDIR => self.sys_unlinkat(AtFd::FdCwd, args[0].into(), AT_REMOVEDIR),
+            MKNOD => self.sys_mknodat(AtFd::FdCwd, args[0].into(), args[1] as _, args[2]),
+            MKDIR => self.sys_mkdirat(AtFd::FdCwd, args[0].into(), args[1] as _),
+            CHMOD => self.sys_fchmodat(AtFd::FdCwd, args[0].into(), args[1] as _, 0),
+            CHOWN => self.sys_fchownat(AtFd::FdCwd, args[0].into(), args[1] as _, args[2] as _, 0),
+            LCHOWN => self.sys_fchownat(
+                AtFd::FdCwd,
+                args[0].into(),
+                args[1] as _,
+                args[2] as _,
+                AT_SYMLINK_NOFOLLOW,
+            ),
+            ACCESS => self.sys_faccessat(AtFd::FdCwd, args[0].into(), args[1] as _),
+            RENAME => {
+                self.sys_renameat2(AtFd::FdCwd, args[0].into(), AtFd::FdCwd, args[1].into(), 0)
+            }
+            READLINK => {
+                self.sys_readlinkat(AtFd::FdCwd, args[0].into(), args[1].into(), args[2])
+                    .await
+            }
+            SYMLINK => self.sys_symlinkat(args[0].into(), AtFd::FdCwd, args[1].into()),
+            PIPE => self.sys_pipe2(args[0].into(), 0),
+            DUP2 => self.sys_dup2(args[0], args[1]),
+            ALARM => self.sys_alarm(args[0] as _),
+            POLL => self.sys_poll(args[0].into(), args[1], args[2] as _).await,
+            FORK => {
+                self.sys_clone(
+                    Sig::SIGCHLD.raw(),
+                    0usize.into(),
+                    0usize.into(),
+                    0usize.into(),
+                    0usize.into(),
+                )
+                .await
+            }
             _ => {
-                log::error!("Unsupported syscall: {}", syscall_no);
-                Ok(0)
+                log::info!("Unsupported syscall: {}", syscall_no);
+                Err(SysError::ENOSYS)
             }
         };
         match result {
@@ -329,14 +472,14 @@ impl<'a> Syscall<'a> {
                 ret
             }
             Err(e) => {
-                log::warn!("[syscall] {syscall_no} return err {e:?}");
+                log::info!("[syscall] {syscall_no} return err {e:?}");
                 -(e as isize) as usize
             }
         }
     }
 
     fn sys_do_nothing(&self, name: &str) -> SyscallResult {
-        log::warn!(
+        log::info!(
             "Not implemented syscall that specified to do nothing ({})",
             name
         );
@@ -1,20 +1,22 @@
-use alloc::{sync::Arc, vec::Vec};
-use core::intrinsics::unlikely;
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::{intrinsics::unlikely, mem::size_of};
 
-use addr::SockAddr;
+use async_utils::{Select2Futures, SelectOutput};
 use log::info;
 use socket::*;
 use systype::{SysError, SysResult, SyscallResult};
-use vfs::pipefs::new_pipe;
 use vfs_core::OpenFlags;
-use virtio_drivers::PAGE_SIZE;
 
 use super::{fs::IoVec, Syscall};
 use crate::{
     mm::{UserRdWrPtr, UserReadPtr, UserWritePtr},
     net::*,
-    task::Task,
+    task::{cred::Capabilities, signal::IntrBySignalFuture, Task},
 };
+
+/// Ports below this are reserved for privileged processes.
+const PROT_SOCK: u16 = 1024;
+
 impl Syscall<'_> {
     /// creates an endpoint for communication and returns a file descriptor that
     /// refers to that endpoint. The file descriptor returned by a successful
@@ -22,20 +24,12 @@ impl Syscall<'_> {
     /// for the process.
     pub fn sys_socket(&self, domain: usize, types: i32, _protocal: usize) -> SyscallResult {
         let domain = SaFamily::try_from(domain as u16)?;
-        let mut types = types;
-        let mut flags = OpenFlags::empty();
-        let mut nonblock = false;
-        // fixme：file flags should be placed in file meta
-        if types & NONBLOCK != 0 {
-            nonblock = true;
-            types &= !NONBLOCK;
-            flags |= OpenFlags::O_NONBLOCK;
-        }
-        if types & CLOEXEC != 0 {
-            types &= !CLOEXEC;
-            flags |= OpenFlags::O_CLOEXEC;
+        let (types, flags) = split_socket_type(types)?;
+        let nonblock = flags.contains(OpenFlags::O_NONBLOCK);
+        if domain == SaFamily::AF_UNIX && !matches!(types, SocketType::STREAM | SocketType::DGRAM) {
+            log::info!("[sys_socket] unsupported AF_UNIX socket type {types:?}");
+            return Err(SysError::EINVAL);
         }
-        let types = SocketType::try_from(types)?;
         let socket = Socket::new(domain, types, nonblock);
         let fd = self
             .task
@@ -50,11 +44,20 @@ impl Syscall<'_> {
     /// descriptor sockfd.  addrlen specifies the size, in  bytes,  of the
     /// address structure pointed to by addr.  Traditionally, this operation is
     /// called “assigning a name to a socket”.
+    ///
+    /// Ports below `PROT_SOCK` need `CAP_NET_BIND_SERVICE`.
     pub fn sys_bind(&self, sockfd: usize, addr: usize, addrlen: usize) -> SyscallResult {
         let task = self.task;
         let local_addr = task.read_sockaddr(addr, addrlen)?;
         let socket: Arc<Socket> = task.sockfd_lookup(sockfd)?;
         info!("[sys_bind] try to bind fd{sockfd} to {local_addr}");
+        if local_addr
+            .port()
+            .is_some_and(|port| port != 0 && port < PROT_SOCK)
+            && !task.cred_ref().has_cap(Capabilities::NET_BIND_SERVICE)
+        {
+            return Err(SysError::EACCES);
+        }
         socket.sk.bind(sockfd, local_addr)?;
         // info!(
         //     "[sys_bind] already bind fd{sockfd} to {}",
@@ -66,9 +69,9 @@ impl Syscall<'_> {
     /// Mark the stream socket referenced by the file descriptor `sockfd` as
     /// passive. This socket will be used later to accept connections from other
     /// (active) sockets
-    pub fn sys_listen(&self, sockfd: usize, _backlog: usize) -> SyscallResult {
+    pub fn sys_listen(&self, sockfd: usize, backlog: usize) -> SyscallResult {
         let socket = self.task.sockfd_lookup(sockfd)?;
-        socket.sk.listen()?;
+        socket.sk.listen(backlog)?;
         Ok(0)
     }
 
@@ -79,7 +82,7 @@ impl Syscall<'_> {
         let remote_addr = task.read_sockaddr(addr, addrlen)?;
         let socket = task.sockfd_lookup(sockfd)?;
         log::info!("[sys_connect] fd{sockfd} trys to connect {remote_addr}");
-        socket.sk.connect(remote_addr).await?;
+        socket.connect(remote_addr).await?;
         // TODO:
         // yield_now().await;
         Ok(0)
@@ -101,14 +104,21 @@ impl Syscall<'_> {
 
         task.set_interruptable();
         task.set_wake_up_signal(!*task.sig_mask_ref());
-        let new_sk = socket.sk.accept().await?;
+        let intr_future = IntrBySignalFuture {
+            task: task.clone(),
+            mask: *task.sig_mask_ref(),
+        };
+        let ret = match Select2Futures::new(socket.accept(), intr_future).await {
+            SelectOutput::Output1(ret) => ret,
+            SelectOutput::Output2(_) => Err(SysError::EINTR),
+        };
         task.set_running();
+        let new_sk = ret?;
 
         let peer_addr = new_sk.peer_addr()?;
-        let peer_addr = SockAddr::from_endpoint(peer_addr);
         log::info!("[sys_accept] peer addr: {peer_addr}");
         task.write_sockaddr(addr, addrlen, peer_addr)?;
-        let new_socket = Arc::new(Socket::from_another(&socket, Sock::Tcp(new_sk)));
+        let new_socket = Arc::new(Socket::from_another(&socket, new_sk));
         let fd = task.with_mut_fd_table(|table| table.alloc(new_socket, OpenFlags::empty()))?;
         Ok(fd)
     }
@@ -137,7 +147,9 @@ impl Syscall<'_> {
     }
 
     /// Usually used for sending UDP datagrams. If using `sys_sendto` for STEAM,
-    /// `dest_addr` and `addrlen` will be ignored.
+    /// `dest_addr` and `addrlen` will be ignored. A datagram socket without
+    /// `dest_addr` sends to the address it is connected to, and fails with
+    /// `EDESTADDRREQ` if it is not connected.
     ///
     /// On success returns the number of bytes sent
     pub async fn sys_sendto(
@@ -159,7 +171,7 @@ impl Syscall<'_> {
                 if dest_addr != 0 {
                     return Err(SysError::EISCONN);
                 }
-                socket.sk.sendto(&buf, None).await?
+                socket.sendto(&buf, None).await?
             }
             SocketType::DGRAM => {
                 let sockaddr = if dest_addr != 0 {
@@ -167,7 +179,7 @@ impl Syscall<'_> {
                 } else {
                     None
                 };
-                socket.sk.sendto(&buf, sockaddr).await?
+                socket.sendto(&buf, sockaddr).await?
             }
             _ => unimplemented!(),
         };
@@ -210,7 +222,7 @@ impl Syscall<'_> {
         unsafe { temp.set_len(len) };
         task.set_interruptable();
         // TODO: not sure if `len` is enough when call `socket.recvfrom`
-        let (bytes, remote_addr) = socket.sk.recvfrom(&mut temp).await?;
+        let (bytes, remote_addr) = socket.recvfrom(&mut temp).await?;
         task.set_running();
         let mut buf = buf.into_mut_slice(&task, bytes)?;
         buf[..bytes].copy_from_slice(&temp[..bytes]);
@@ -218,88 +230,57 @@ impl Syscall<'_> {
         Ok(bytes)
     }
 
-    /// Allow users to configure sockets
-    /// But since these configurations are too detailed, they are currently not
-    /// supported
+    /// Set the option `optname` at `level` of the socket `sockfd` to the
+    /// value of `optlen` bytes at `optval`.
     pub fn sys_setsockopt(
         &self,
         sockfd: usize,
         level: usize,
         optname: usize,
-        optval: usize,
+        optval: UserReadPtr<u8>,
         optlen: usize,
     ) -> SyscallResult {
-        // let task = self.task;
-        // let socket = task.sockfd_lookup(sockfd)?;
-        log::info!(
-            "[sys_setsockopt] fd{sockfd} {:?} {:?} optval:{} optlen:{optlen}",
-            SocketLevel::try_from(level)?,
-            SocketOpt::try_from(optname)?,
-            UserReadPtr::<usize>::from(optval).read(self.task)?
-        );
+        let task = self.task;
+        let socket = task.sockfd_lookup(sockfd)?;
+        let level = SocketLevel::try_from(level)?;
+        if optval.is_null() {
+            return Err(SysError::EFAULT);
+        }
+        let optval = optval.into_slice(&task, optlen)?;
+        log::info!("[sys_setsockopt] fd{sockfd} {level:?} opt {optname} optlen:{optlen}");
+        socket.setsockopt(level, optname, &optval)?;
         Ok(0)
     }
 
+    /// Get the option `optname` at `level` of the socket `sockfd`. At most
+    /// `*optlen` bytes of the value are copied to `optval`, and `*optlen` is
+    /// set to the count copied.
     pub fn sys_getsockopt(
         &self,
         sockfd: usize,
         level: usize,
         optname: usize,
-        optval: usize,
+        optval: UserWritePtr<u8>,
         optlen: usize,
     ) -> SyscallResult {
-        use core::mem::size_of;
         let task = self.task;
-        // task.sockfd_lookup(sockfd)?;
-        match SocketLevel::try_from(level)? {
-            SocketLevel::SOL_SOCKET => {
-                const SEND_BUFFER_SIZE: usize = 64 * 1024;
-                const RECV_BUFFER_SIZE: usize = 64 * 1024;
-                match SocketOpt::try_from(optname)? {
-                    SocketOpt::RCVBUF => {
-                        UserWritePtr::<u32>::from(optval).write(&task, RECV_BUFFER_SIZE as u32)?;
-                        UserWritePtr::<u32>::from(optlen).write(&task, size_of::<u32>() as u32)?
-                    }
-                    SocketOpt::SNDBUF => {
-                        UserWritePtr::<u32>::from(optval).write(&task, SEND_BUFFER_SIZE as u32)?;
-                        UserWritePtr::<u32>::from(optlen).write(&task, size_of::<u32>() as u32)?
-                    }
-                    SocketOpt::ERROR => {
-                        UserWritePtr::<u32>::from(optval).write(&task, 0)?;
-                        UserWritePtr::<u32>::from(optlen).write(&task, size_of::<u32>() as u32)?
-                    }
-                    opt => {
-                        log::error!(
-                            "[sys_getsockopt] unsupported SOL_SOCKET opt {opt:?} optlen:{optlen}"
-                        )
-                    }
-                };
-            }
-            SocketLevel::IPPROTO_IP | SocketLevel::IPPROTO_TCP => {
-                const MAX_SEGMENT_SIZE: usize = 1460;
-                match TcpSocketOpt::try_from(optname)? {
-                    TcpSocketOpt::MAXSEG => {
-                        UserWritePtr::<u32>::from(optval).write(&task, MAX_SEGMENT_SIZE as u32)?;
-                        UserWritePtr::<u32>::from(optlen).write(&task, size_of::<u32>() as u32)?
-                    }
-                    TcpSocketOpt::NODELAY => {
-                        UserWritePtr::<u32>::from(optval).write(&task, 0)?;
-                        UserWritePtr::<u32>::from(optlen).write(&task, size_of::<u32>() as u32)?
-                    }
-                    TcpSocketOpt::INFO => {}
-                    TcpSocketOpt::CONGESTION => {
-                        UserWritePtr::from(optval).write_cstr(&task, "reno")?;
-                        UserWritePtr::<u32>::from(optlen).write(&task, 4)?
-                    }
-                    opt => {
-                        log::error!(
-                            "[sys_getsockopt] unsupported IPPROTO_TCP opt {opt:?} optlen:{optlen}"
-                        )
-                    }
-                };
+        let socket = task.sockfd_lookup(sockfd)?;
+        let len = UserReadPtr::<u32>::from(optlen).read(&task)? as i32;
+        if len < 0 {
+            return Err(SysError::EINVAL);
+        }
+        let level = SocketLevel::try_from(level)?;
+        let val = socket.getsockopt(level, optname)?;
+        log::info!("[sys_getsockopt] fd{sockfd} {level:?} opt {optname} val:{val:?}");
+        let len = core::cmp::min(len as usize, val.len());
+        if len > 0 {
+            if optval.is_null() {
+                return Err(SysError::EFAULT);
             }
-            SocketLevel::IPPROTO_IPV6 => todo!(),
+            let mut optval = optval.into_mut_slice(&task, len)?;
+            optval.copy_from_slice(&val[..len]);
         }
+        UserWritePtr::<u32>::from(optlen).write(&task, len as u32)?;
         Ok(0)
     }
 
@@ -324,25 +305,63 @@ impl Syscall<'_> {
         Ok(0)
     }
 
+    /// Create a pair of connected sockets in `sv`. Only `AF_UNIX` stream
+    /// sockets are supported, each end of which reads what the other writes.
     pub fn sys_socketpair(
         &self,
-        _domain: usize,
-        _types: usize,
-        _protocol: usize,
+        domain: usize,
+        types: i32,
+        protocol: usize,
         sv: UserWritePtr<[u32; 2]>,
     ) -> SyscallResult {
         let task = self.task;
-        let (pipe_read, pipe_write) = new_pipe(PAGE_SIZE);
-        let pipe = task.with_mut_fd_table(|table| {
-            let fd_read = table.alloc(pipe_read, OpenFlags::empty())?;
-            let fd_write = table.alloc(pipe_write, OpenFlags::empty())?;
-            Ok([fd_read as u32, fd_write as u32])
+        let domain = SaFamily::try_from(domain as u16).map_err(|_| SysError::EAFNOSUPPORT)?;
+        if domain != SaFamily::AF_UNIX {
+            return Err(SysError::EOPNOTSUPP);
+        }
+        let (types, flags) = split_socket_type(types)?;
+        if protocol != 0 {
+            return Err(SysError::EPROTONOSUPPORT);
+        }
+        if types != SocketType::STREAM {
+            log::info!("[sys_socketpair] unsupported AF_UNIX socket type {types:?}");
+            return Err(SysError::EOPNOTSUPP);
+        }
+        let nonblock = flags.contains(OpenFlags::O_NONBLOCK);
+        let (socket0, socket1) = Socket::new_unix_pair(types, nonblock);
+        let fds = task.with_mut_fd_table(|table| {
+            let fd0 = table.alloc(Arc::new(socket0), flags)?;
+            let fd1 = match table.alloc(Arc::new(socket1), flags) {
+                Ok(fd) => fd,
+                Err(err) => {
+                    table.remove(fd0)?;
+                    return Err(err);
+                }
+            };
+            Ok([fd0 as u32, fd1 as u32])
         })?;
-        sv.write(&task, pipe)?;
+        log::info!("[sys_socketpair] new socket pair in fds {fds:?} {flags:?}");
+        sv.write(&task, fds)?;
         Ok(0)
     }
 }
 
+/// Split the `type` argument of `socket` into the socket type and the flags of
+/// the new file.
+fn split_socket_type(mut types: i32) -> SysResult<(SocketType, OpenFlags)> {
+    let mut flags = OpenFlags::empty();
+    // fixme：file flags should be placed in file meta
+    if types & NONBLOCK != 0 {
+        types &= !NONBLOCK;
+        flags |= OpenFlags::O_NONBLOCK;
+    }
+    if types & CLOEXEC != 0 {
+        types &= !CLOEXEC;
+        flags |= OpenFlags::O_CLOEXEC;
+    }
+    Ok((SocketType::try_from(types)?, flags))
+}
+
 /// ```c
 /// struct msghdr {
 ///     void         *msg_name;       /* Optional address */
@@ -381,7 +400,26 @@ pub struct CMsgHdr {
     type_: i32,
 }
 
+/// Level and type of a control message of credentials, `SCM_CREDENTIALS`.
+const SOL_SOCKET: i32 = 1;
+const SCM_CREDENTIALS: i32 = 2;
+/// The control data did not fit in the buffer of `recvmsg`.
+const MSG_CTRUNC: i32 = 0x8;
+
+/// Length of a control message with `len` bytes of data, `CMSG_LEN`.
+const fn cmsg_len(len: usize) -> usize {
+    size_of::<CMsgHdr>() + len
+}
+
+/// Space a control message with `len` bytes of data takes, `CMSG_SPACE`.
+const fn cmsg_space(len: usize) -> usize {
+    size_of::<CMsgHdr>() + len.next_multiple_of(size_of::<usize>())
+}
+
 impl Syscall<'_> {
+    /// Send the data of the iovecs of `msg`, to `msg_name` if not `NULL`.
+    /// Of the control messages, only `SCM_CREDENTIALS` is supported, whose
+    /// credentials are checked and received by a peer with `SO_PASSCRED`.
     pub async fn sys_sendmsg(
         &self,
         sockfd: usize,
@@ -395,10 +433,40 @@ impl Syscall<'_> {
         let task = self.task;
         let socket = task.sockfd_lookup(sockfd)?;
         let message = msg.read(&task)?;
+        let addr = if message.name != 0 {
+            Some(task.read_sockaddr(message.name, message.namelen as _)?)
+        } else {
+            None
+        };
+        let mut cred = None;
         if message.controllen != 0 {
-            log::warn!("[sys_sendmsg] unsupport msg control");
+            let control =
+                UserReadPtr::<u8>::from(message.control).read_array(&task, message.controllen)?;
+            let mut offset = 0;
+            while offset + size_of::<CMsgHdr>() <= control.len() {
+                let cmsg =
+                    unsafe { (control[offset..].as_ptr() as *const CMsgHdr).read_unaligned() };
+                if cmsg.len < size_of::<CMsgHdr>() || offset + cmsg.len > control.len() {
+                    return Err(SysError::EINVAL);
+                }
+                if cmsg.level == SOL_SOCKET && cmsg.type_ == SCM_CREDENTIALS {
+                    if cmsg.len != cmsg_len(size_of::<UCred>()) {
+                        return Err(SysError::EINVAL);
+                    }
+                    let data = &control[offset + size_of::<CMsgHdr>()..];
+                    let ucred = unsafe { (data.as_ptr() as *const UCred).read_unaligned() };
+                    ucred.check()?;
+                    cred = Some(ucred);
+                } else {
+                    log::info!(
+                        "[sys_sendmsg] unsupported cmsg level {} type {}",
+                        cmsg.level,
+                        cmsg.type_
+                    );
+                }
+                offset += cmsg.len.next_multiple_of(size_of::<usize>());
+            }
         }
-        let addr = task.read_sockaddr(message.name, message.namelen as _)?;
         let iovs = UserReadPtr::<IoVec>::from(message.iov).read_array(&task, message.iovlen)?;
         let mut total_len = 0;
         for (i, iov) in iovs.iter().enumerate() {
@@ -408,45 +476,71 @@ impl Syscall<'_> {
             let ptr = UserWritePtr::<u8>::from(iov.base);
             log::info!("[sys_sendmsg] iov #{i}, ptr: {ptr}, len: {}", iov.len);
             let buf = ptr.into_mut_slice(&task, iov.len)?;
-            let send_len = socket.sk.sendto(&buf, Some(addr)).await?;
+            let send_len = socket.sendmsg(&buf, addr, cred).await?;
             total_len += send_len;
         }
         Ok(total_len)
     }
 
-    /// 目前的实现是，如果Udp Socket收到多个不同Ip地址的数据报如ip1, ip1, ip2,
-    /// ip3, ip1，recvmsg
-    // pub async fn sys_recvmsg(
-    //     &self,
-    //     sockfd: usize,
-    //     msg: UserRdWrPtr<MsgHdr>,
-    //     flags: usize,
-    // ) -> SyscallResult {
-    //     if flags != 0 {
-    //         log::error!("[sys_sendmsg] unsupported flags {flags}");
-    //     }
-    //     // TODO: support flags
-    //     let task = self.task;
-    //     let socket = task.sockfd_lookup(sockfd)?;
-    //     let msg = msg.read(&task)?;
-    //     if msg.controllen != 0 {
-    //         log::warn!("[sys_sendmsg] unsupport msg control");
-    //     }
-    //     let addr = task.read_sockaddr(msg.name, msg.namelen as _)?;
-    //     let iovs = UserReadPtr::<IoVec>::from(msg.iov).read_array(&task,
-    // msg.iovlen)?;     let mut total_len = 0;
-    //     for (i, iov) in iovs.iter().enumerate() {
-    //         if unlikely(iov.len == 0) {
-    //             continue;
-    //         }
-    //         let ptr = UserWritePtr::<u8>::from(iov.base);
-    //         log::info!("[sys_recvmsg] iov #{i}, ptr: {ptr}, len: {}", iov.len);
-    //         let buf = ptr.into_mut_slice(&task, iov.len)?;
-    //         let send_len = socket.sk.sendto(&buf, Some(addr)).await?;
-    //         total_len += send_len;
-    //     }
-    //     Ok(total_len)
-    // }
+    /// Receive into the iovecs of `msg`, and write the address of the sender
+    /// to `msg_name` if not `NULL`. The credentials of the sender are passed
+    /// as a `SCM_CREDENTIALS` control message on a unix socket with
+    /// `SO_PASSCRED`, `MSG_CTRUNC` is set if they do not fit.
+    pub async fn sys_recvmsg(
+        &self,
+        sockfd: usize,
+        msg: UserRdWrPtr<MsgHdr>,
+        flags: usize,
+    ) -> SyscallResult {
+        if flags != 0 {
+            log::error!("[sys_recvmsg] unsupported flags {flags}");
+        }
+        let task = self.task;
+        let socket = task.sockfd_lookup(sockfd)?;
+        let msg_addr = msg.as_usize();
+        let mut message = msg.read(&task)?;
+        let iovs = UserReadPtr::<IoVec>::from(message.iov).read_array(&task, message.iovlen)?;
+        let len = iovs.iter().map(|iov| iov.len).sum();
+        let mut temp = vec![0; len];
+        task.set_interruptable();
+        let (bytes, remote_addr, cred) = socket.recvmsg(&mut temp).await?;
+        task.set_running();
+
+        let mut offset = 0;
+        for iov in iovs.iter() {
+            if offset == bytes {
+                break;
+            }
+            let n = iov.len.min(bytes - offset);
+            let mut buf = UserWritePtr::<u8>::from(iov.base).into_mut_slice(&task, n)?;
+            buf.copy_from_slice(&temp[offset..offset + n]);
+            offset += n;
+        }
+
+        message.flags = 0;
+        match cred {
+            Some(cred) if message.controllen >= cmsg_len(size_of::<UCred>()) => {
+                let cmsg = CMsgHdr {
+                    len: cmsg_len(size_of::<UCred>()),
+                    level: SOL_SOCKET,
+                    type_: SCM_CREDENTIALS,
+                };
+                UserWritePtr::<CMsgHdr>::from(message.control).write(&task, cmsg)?;
+                UserWritePtr::<UCred>::from(message.control + size_of::<CMsgHdr>())
+                    .write(&task, cred)?;
+                message.controllen = message.controllen.min(cmsg_space(size_of::<UCred>()));
+            }
+            Some(_) => {
+                message.flags |= MSG_CTRUNC;
+                message.controllen = 0;
+            }
+            None => message.controllen = 0,
+        }
+        UserWritePtr::<MsgHdr>::from(msg_addr).write(&task, message)?;
+        // `msg_namelen` is updated in place to the length of the address
+        task.write_sockaddr(message.name, msg_addr + size_of::<usize>(), remote_addr)?;
+        Ok(bytes)
+    }
 
     pub fn sys_sendmmsg(&self, sockfd: usize) -> SyscallResult {
         Ok(0)
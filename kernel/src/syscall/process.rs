@@ -2,18 +2,27 @@
 
 use alloc::{
     string::{String, ToString},
+    sync::Arc,
     vec::Vec,
 };
 
 use async_utils::{suspend_now, yield_now};
 use memory::VirtAddr;
 use signal::sigset::SigSet;
-use systype::{SysError, SysResult, SyscallResult};
+use systype::{Rusage, SysError, SysResult, SyscallResult};
 
 use super::Syscall;
 use crate::{
-    mm::{UserReadPtr, UserWritePtr},
-    task::{spawn_user_task, PGid, Pid, PROCESS_GROUP_MANAGER, TASK_MANAGER},
+    mm::{
+        memory_space::{read_elf_headers, DenyWrite},
+        UserRdWrPtr, UserReadPtr, UserWritePtr,
+    },
+    task::{
+        cred::{Capabilities, Gid, Uid, NGROUPS_MAX},
+        spawn_user_task,
+        task::JobEvent,
+        PGid, Pid, Task, PROCESS_GROUP_MANAGER, TASK_MANAGER,
+    },
 };
 
 bitflags! {
@@ -78,6 +87,29 @@ bitflags! {
     }
 }
 
+/// Capability versions, the first one has a single `CapUserData` and the
+/// later ones two, for the low and high 32 capabilities.
+const LINUX_CAPABILITY_VERSION_1: u32 = 0x19980330;
+const LINUX_CAPABILITY_VERSION_2: u32 = 0x20071026;
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x20080522;
+
+/// Defined in <linux/capability.h>.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct CapUserHeader {
+    pub version: u32,
+    pub pid: i32,
+}
+
+/// Defined in <linux/capability.h>.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct CapUserData {
+    pub effective: u32,
+    pub permitted: u32,
+    pub inheritable: u32,
+}
+
 impl Syscall<'_> {
     /// _exit() system call terminates only the calling thread, and actions such
     /// as reparenting child processes or sending SIGCHLD to the parent
@@ -97,13 +129,11 @@ impl Syscall<'_> {
     /// group.
     pub fn sys_exit_group(&self, exit_code: i32) -> SyscallResult {
         let task = self.task;
-        task.with_thread_group(|tg| {
-            for t in tg.iter() {
-                t.set_terminated();
-                log::info!("tid {} set terminated", t.tid());
-            }
-        });
-        task.set_exit_code((exit_code & 0xFF) << 8);
+        // an execve of another thread wins, which has terminated this one
+        let Some(_op) = task.begin_group_op() else {
+            return Ok(0);
+        };
+        task.exit_group((exit_code & 0xFF) << 8);
         Ok(0)
     }
 
@@ -124,20 +154,34 @@ impl Syscall<'_> {
         Ok(self.task.ppid())
     }
 
+    /// wait4() waits for a child matching `pid` to terminate and reaps it:
+    /// - `pid` < -1: any child in the process group `-pid`.
+    /// - `pid` == -1: any child.
+    /// - `pid` == 0: any child in the process group of the caller.
+    /// - `pid` > 0: the child `pid`.
+    ///
+    /// With `WNOHANG` it returns 0 at once if matching children exist but none
+    /// has terminated. It fails with `ECHILD` only if no child matches at all.
+    /// The resource usage of the child, including that of its own children
+    /// waited for, is stored in `rusage`.
+    ///
+    /// With `WUNTRACED` a child stopped by a signal is reported too, and with
+    /// `WCONTINUED` one continued by `SIGCONT`. Each change is reported once
+    /// and the child is not reaped.
+    ///
     /// NOTE: A thread can, and by default will, wait on children of other
     /// threads in the same thread group.
-    // TODO: More options and process group support.
     // PERF: use event bus to notify this task when child exits
     pub async fn sys_wait4(
         &self,
         pid: i32,
         wstatus: UserWritePtr<i32>,
         option: i32,
-        _rusage: usize,
+        rusage: UserWritePtr<Rusage>,
     ) -> SyscallResult {
         let task = self.task;
         let option = WaitOptions::from_bits_truncate(option);
-        #[derive(Debug)]
+        #[derive(Debug, Clone, Copy)]
         enum WaitFor {
             // wait for any child process in the specific process group
             PGid(PGid),
@@ -152,109 +196,94 @@ impl Syscall<'_> {
             -1 => WaitFor::AnyChild,
             0 => WaitFor::AnyChildInGroup,
             p if p > 0 => WaitFor::Pid(p as Pid),
-            p => WaitFor::PGid(p as PGid),
+            p => WaitFor::PGid(-p as PGid),
         };
         log::info!("[sys_wait4] target: {target:?}, option: {option:?}");
 
-        let res_task = {
-            // 首先检查一遍等待的进程是否已经是zombie了
+        let untraced = option.contains(WaitOptions::WUNTRACED);
+        let continued = option.contains(WaitOptions::WCONTINUED);
+        // A terminated child matching the target, or one with a job control event
+        // asked for, or `ECHILD` if no child matches
+        let find_child = || -> SysResult<Option<(Arc<Task>, Option<JobEvent>)>> {
             let children = task.children();
-            if children.is_empty() {
-                log::info!("[sys_wait4] fail: no child");
+            let mut matching = children
+                .values()
+                .filter(|c| match target {
+                    WaitFor::AnyChild => true,
+                    WaitFor::AnyChildInGroup => c.pgid() == task.pgid(),
+                    WaitFor::PGid(pgid) => c.pgid() == pgid,
+                    WaitFor::Pid(pid) => c.pid() == pid,
+                })
+                .peekable();
+            if matching.peek().is_none() {
+                log::info!("[sys_wait4] fail: no child matching {target:?}");
                 return Err(SysError::ECHILD);
             }
-
-            match target {
-                WaitFor::AnyChild => children
-                    .values()
-                    .find(|c| c.is_zombie() && c.with_thread_group(|tg| tg.len() == 1)),
-                WaitFor::Pid(pid) => {
-                    if let Some(child) = children.get(&pid) {
-                        if child.is_zombie() && child.with_thread_group(|tg| tg.len() == 1) {
-                            Some(child)
-                        } else {
-                            None
-                        }
-                    } else {
-                        log::info!("[sys_wait4] fail: no child with pid {pid}");
-                        return Err(SysError::ECHILD);
-                    }
+            for c in matching {
+                if c.is_zombie() && c.with_thread_group(|tg| tg.len() == 1) {
+                    return Ok(Some((c.clone(), None)));
+                }
+                let event = c.with_mut_thread_group(|tg| tg.take_job_event(untraced, continued));
+                if event.is_some() {
+                    return Ok(Some((c.clone(), event)));
                 }
-                WaitFor::PGid(_) => unimplemented!(),
-                WaitFor::AnyChildInGroup => unimplemented!(),
             }
-            .cloned()
+            Ok(None)
         };
 
-        if let Some(res_task) = res_task {
-            task.time_stat()
-                .update_child_time(res_task.time_stat().user_system_time());
-            if wstatus.not_null() {
-                // wstatus stores signal in the lowest 8 bits and exit code in higher 8 bits
-                // wstatus macros can be found in "bits/waitstatus.h"
-                let exit_code = res_task.exit_code();
-                log::debug!("[sys_wait4] wstatus: {exit_code:#x}");
-                wstatus.write(&task, exit_code)?;
-            }
-            let tid = res_task.tid();
-            task.remove_child(tid);
-            TASK_MANAGER.remove(tid);
-            PROCESS_GROUP_MANAGER.remove(task);
-            return Ok(tid);
-        } else if option.contains(WaitOptions::WNOHANG) {
-            return Ok(0);
-        } else {
-            log::info!("[sys_wait4] waiting for sigchld");
-            // 如果等待的进程还不是zombie，那么本进程进行await，
-            // 直到等待的进程do_exit然后发送SIGCHLD信号唤醒自己
-            let (child_pid, exit_code, child_utime, child_stime) = loop {
-                task.set_interruptable();
-                task.set_wake_up_signal(!*task.sig_mask_ref() | SigSet::SIGCHLD);
-                suspend_now().await;
-                task.set_running();
-                let si = task.with_mut_sig_pending(|pending| pending.get_expect(SigSet::SIGCHLD));
-                if let Some(_info) = si {
-                    let children = task.children();
-                    let child = match target {
-                        WaitFor::AnyChild => children
-                            .values()
-                            .find(|c| c.is_zombie() && c.with_thread_group(|tg| tg.len() == 1)),
-                        WaitFor::Pid(pid) => {
-                            let child = children.get(&pid).unwrap();
-                            if child.is_zombie() && child.with_thread_group(|tg| tg.len() == 1) {
-                                Some(child)
-                            } else {
-                                None
-                            }
-                        }
-                        WaitFor::PGid(_) => unimplemented!(),
-                        WaitFor::AnyChildInGroup => unimplemented!(),
-                    };
-                    if let Some(child) = child {
-                        break (
-                            child.pid(),
-                            child.exit_code(),
-                            child.time_stat_ref().user_time(),
-                            child.time_stat_ref().sys_time(),
-                        );
+        // 首先检查一遍等待的进程是否已经是zombie了
+        let (child, event) = match find_child()? {
+            Some(child) => child,
+            None if option.contains(WaitOptions::WNOHANG) => return Ok(0),
+            None => {
+                log::info!("[sys_wait4] waiting for sigchld");
+                // 如果等待的进程还不是zombie，那么本进程进行await，
+                // 直到等待的进程do_exit然后发送SIGCHLD信号唤醒自己
+                loop {
+                    task.set_interruptable();
+                    task.set_wake_up_signal(!*task.sig_mask_ref() | SigSet::SIGCHLD);
+                    suspend_now().await;
+                    task.set_running();
+                    let si =
+                        task.with_mut_sig_pending(|pending| pending.get_expect(SigSet::SIGCHLD));
+                    if si.is_none() {
+                        return Err(SysError::EINTR);
+                    }
+                    if let Some(found) = find_child()? {
+                        break found;
                     }
-                } else {
-                    return Err(SysError::EINTR);
                 }
-            };
-            task.time_stat()
-                .update_child_time((child_utime, child_stime));
-            if wstatus.not_null() {
-                // wstatus stores signal in the lowest 8 bits and exit code in higher 8 bits
-                // wstatus macros can be found in <bits/waitstatus.h>
-                log::trace!("[sys_wait4] wstatus: {:#x}", exit_code);
-                wstatus.write(&task, exit_code)?;
             }
-            task.remove_child(child_pid);
-            TASK_MANAGER.remove(child_pid);
-            PROCESS_GROUP_MANAGER.remove(task);
+        };
+
+        // the times are only taken over by the parent when reaped
+        if event.is_none() {
+            task.add_children_time(&child);
+        }
+        if wstatus.not_null() {
+            // wstatus stores signal in the lowest 8 bits and exit code in higher 8 bits
+            // wstatus macros can be found in <bits/waitstatus.h>
+            let status = event.map_or_else(|| child.exit_code(), |e| e.wstatus());
+            log::debug!("[sys_wait4] wstatus: {status:#x}");
+            wstatus.write(&task, status)?;
+        }
+        if rusage.not_null() {
+            let (utime, stime) = child.get_process_ustime();
+            let (cutime, cstime) = child.get_children_ustime();
+            let mut usage = Rusage::default();
+            usage.utime = (utime + cutime).into();
+            usage.stime = (stime + cstime).into();
+            rusage.write(&task, usage)?;
+        }
+        let child_pid = child.pid();
+        if let Some(event) = event {
+            log::info!("[sys_wait4] child {child_pid} {event:?}");
             return Ok(child_pid);
         }
+        task.remove_child(child_pid);
+        TASK_MANAGER.remove(child_pid);
+        PROCESS_GROUP_MANAGER.remove(&child);
+        Ok(child_pid)
     }
 
     /// execve() executes the program referred to by pathname. This causes the
@@ -304,12 +333,14 @@ impl Syscall<'_> {
         }
 
         let file = task.resolve_path(&path)?.open()?;
-        let elf_data = file.read_all().await?;
-        task.do_execve(file, &elf_data, argv, envp);
+        let elf_data = read_elf_headers(&file).await?;
+        let exe = DenyWrite::new(file.inode())?;
+        let _exec = task.begin_exec().await?;
+        task.do_execve(file, exe, &elf_data, argv, envp);
         Ok(0)
     }
 
-    pub fn sys_clone(
+    pub async fn sys_clone(
         &self,
         flags: usize,
         stack: VirtAddr,
@@ -325,7 +356,7 @@ impl Syscall<'_> {
             "[sys_clone] flags:{flags:?}, stack:{stack:#x}, tls:{tls:?}, parent_tid:{parent_tid:?}, child_tid:{child_tid:?}"
         );
         let task = self.task;
-        let new_task = task.do_clone(flags);
+        let new_task = task.do_clone(flags).await?;
         new_task.trap_context_mut().set_user_a0(0);
         let new_tid = new_task.tid();
         log::info!("[sys_clone] clone a new thread, tid {new_tid}, clone flags {flags:?}",);
@@ -409,27 +440,348 @@ impl Syscall<'_> {
         if pgid == 0 {
             PROCESS_GROUP_MANAGER.add_group(&target_task);
         } else {
-            if PROCESS_GROUP_MANAGER.get_group(pgid).is_none() {
-                PROCESS_GROUP_MANAGER.add_group(&target_task);
-            } else {
-                PROCESS_GROUP_MANAGER.add_process(pgid, &target_task);
+            match PROCESS_GROUP_MANAGER.sid(pgid) {
+                None => PROCESS_GROUP_MANAGER.add_group(&target_task),
+                Some(sid) => {
+                    if PROCESS_GROUP_MANAGER.sid(target_task.pgid()) != Some(sid) {
+                        return Err(SysError::EPERM);
+                    }
+                    PROCESS_GROUP_MANAGER.add_process(pgid, &target_task);
+                }
             }
         }
         Ok(0)
     }
 
-    // TODO:
     pub fn sys_getuid(&self) -> SyscallResult {
-        Ok(0)
+        Ok(self.task.cred_ref().uid as usize)
     }
 
-    // TODO:
     pub fn sys_geteuid(&self) -> SyscallResult {
+        Ok(self.task.cred_ref().euid as usize)
+    }
+
+    pub fn sys_getgid(&self) -> SyscallResult {
+        Ok(self.task.cred_ref().gid as usize)
+    }
+
+    pub fn sys_getegid(&self) -> SyscallResult {
+        Ok(self.task.cred_ref().egid as usize)
+    }
+
+    /// setuid() sets the effective user ID of the calling process. If the
+    /// calling process has `CAP_SETUID`, the real UID and saved set-user-ID
+    /// are also set.
+    pub fn sys_setuid(&self, uid: Uid) -> SyscallResult {
+        let cred = self.task.cred();
+        log::info!("[sys_setuid] uid {uid}, old {cred:?}");
+        let old = cred.uids();
+        if cred.has_cap(Capabilities::SETUID) {
+            cred.uid = uid;
+            cred.suid = uid;
+        } else if uid != cred.uid && uid != cred.suid {
+            return Err(SysError::EPERM);
+        }
+        cred.euid = uid;
+        cred.fsuid = uid;
+        cred.update_caps(old);
         Ok(0)
     }
 
-    pub fn sys_setsid(&self) -> SyscallResult {
+    /// setgid() sets the effective group ID of the calling process. If the
+    /// calling process has `CAP_SETGID`, the real GID and saved set-group-ID
+    /// are also set.
+    pub fn sys_setgid(&self, gid: Gid) -> SyscallResult {
+        let cred = self.task.cred();
+        log::info!("[sys_setgid] gid {gid}, old {cred:?}");
+        if cred.has_cap(Capabilities::SETGID) {
+            cred.gid = gid;
+            cred.sgid = gid;
+        } else if gid != cred.gid && gid != cred.sgid {
+            return Err(SysError::EPERM);
+        }
+        cred.egid = gid;
+        cred.fsgid = gid;
+        Ok(0)
+    }
+
+    /// setresuid() sets the real user ID, the effective user ID, and the saved
+    /// set-user-ID of the calling process. If one of the arguments equals -1,
+    /// the corresponding value is not changed.
+    ///
+    /// An unprivileged process may change each ID only to one of its current
+    /// real UID, effective UID, or saved set-user-ID.
+    pub fn sys_setresuid(&self, ruid: Uid, euid: Uid, suid: Uid) -> SyscallResult {
+        const UNCHANGED: Uid = u32::MAX;
+        let cred = self.task.cred();
+        log::info!("[sys_setresuid] ruid {ruid}, euid {euid}, suid {suid}, old {cred:?}");
+        let allowed = |id: Uid| {
+            id == UNCHANGED
+                || cred.has_cap(Capabilities::SETUID)
+                || id == cred.uid
+                || id == cred.euid
+                || id == cred.suid
+        };
+        if !(allowed(ruid) && allowed(euid) && allowed(suid)) {
+            return Err(SysError::EPERM);
+        }
+        let old = cred.uids();
+        if ruid != UNCHANGED {
+            cred.uid = ruid;
+        }
+        if euid != UNCHANGED {
+            cred.euid = euid;
+        }
+        if suid != UNCHANGED {
+            cred.suid = suid;
+        }
+        cred.fsuid = cred.euid;
+        cred.update_caps(old);
+        Ok(0)
+    }
+
+    /// getresuid() returns the real UID, the effective UID, and the saved
+    /// set-user-ID of the calling process.
+    pub fn sys_getresuid(
+        &self,
+        ruid: UserWritePtr<Uid>,
+        euid: UserWritePtr<Uid>,
+        suid: UserWritePtr<Uid>,
+    ) -> SyscallResult {
+        let task = self.task;
+        let cred = task.cred_ref().clone();
+        ruid.write(task, cred.uid)?;
+        euid.write(task, cred.euid)?;
+        suid.write(task, cred.suid)?;
+        Ok(0)
+    }
+
+    /// setresgid() sets the real group ID, the effective group ID, and the
+    /// saved set-group-ID of the calling process, like setresuid() does for
+    /// the user IDs.
+    pub fn sys_setresgid(&self, rgid: Gid, egid: Gid, sgid: Gid) -> SyscallResult {
+        const UNCHANGED: Gid = u32::MAX;
+        let cred = self.task.cred();
+        log::info!("[sys_setresgid] rgid {rgid}, egid {egid}, sgid {sgid}, old {cred:?}");
+        let allowed = |id: Gid| {
+            id == UNCHANGED
+                || cred.has_cap(Capabilities::SETGID)
+                || id == cred.gid
+                || id == cred.egid
+                || id == cred.sgid
+        };
+        if !(allowed(rgid) && allowed(egid) && allowed(sgid)) {
+            return Err(SysError::EPERM);
+        }
+        if rgid != UNCHANGED {
+            cred.gid = rgid;
+        }
+        if egid != UNCHANGED {
+            cred.egid = egid;
+        }
+        if sgid != UNCHANGED {
+            cred.sgid = sgid;
+        }
+        cred.fsgid = cred.egid;
+        Ok(0)
+    }
+
+    /// getresgid() returns the real GID, the effective GID, and the saved
+    /// set-group-ID of the calling process.
+    pub fn sys_getresgid(
+        &self,
+        rgid: UserWritePtr<Gid>,
+        egid: UserWritePtr<Gid>,
+        sgid: UserWritePtr<Gid>,
+    ) -> SyscallResult {
+        let task = self.task;
+        let cred = task.cred_ref().clone();
+        rgid.write(task, cred.gid)?;
+        egid.write(task, cred.egid)?;
+        sgid.write(task, cred.sgid)?;
+        Ok(0)
+    }
+
+    /// setfsuid() sets the user ID used for filesystem access checks. An
+    /// unprivileged process may only set it to its real UID, effective UID,
+    /// saved set-user-ID or current filesystem UID.
+    ///
+    /// Returns the previous filesystem UID in any case, so that -1 queries it.
+    pub fn sys_setfsuid(&self, fsuid: Uid) -> SyscallResult {
+        let cred = self.task.cred();
+        let old = cred.fsuid;
+        log::info!("[sys_setfsuid] fsuid {fsuid}, old {old}");
+        if fsuid != u32::MAX
+            && (cred.has_cap(Capabilities::SETUID)
+                || [cred.uid, cred.euid, cred.suid, cred.fsuid].contains(&fsuid))
+        {
+            let old_uids = cred.uids();
+            cred.fsuid = fsuid;
+            cred.update_caps(old_uids);
+        }
+        Ok(old as usize)
+    }
+
+    /// setfsgid() sets the group ID used for filesystem access checks, like
+    /// setfsuid() does for the user ID.
+    pub fn sys_setfsgid(&self, fsgid: Gid) -> SyscallResult {
+        let cred = self.task.cred();
+        let old = cred.fsgid;
+        log::info!("[sys_setfsgid] fsgid {fsgid}, old {old}");
+        if fsgid != u32::MAX
+            && (cred.has_cap(Capabilities::SETGID)
+                || [cred.gid, cred.egid, cred.sgid, cred.fsgid].contains(&fsgid))
+        {
+            cred.fsgid = fsgid;
+        }
+        Ok(old as usize)
+    }
+
+    /// setgroups() sets the supplementary group IDs of the calling process,
+    /// which needs privilege.
+    pub fn sys_setgroups(&self, size: usize, list: UserReadPtr<Gid>) -> SyscallResult {
         let task = self.task;
-        Ok(task.pid())
+        if size > NGROUPS_MAX {
+            return Err(SysError::EINVAL);
+        }
+        if !task.cred_ref().has_cap(Capabilities::SETGID) {
+            return Err(SysError::EPERM);
+        }
+        let mut groups = if size == 0 {
+            Vec::new()
+        } else {
+            list.read_array(task, size)?
+        };
+        groups.sort_unstable();
+        groups.dedup();
+        log::info!("[sys_setgroups] groups {groups:?}");
+        task.cred().groups = groups;
+        Ok(0)
+    }
+
+    /// getgroups() returns the supplementary group IDs in `list`. A `size` of
+    /// zero only returns their number.
+    pub fn sys_getgroups(&self, size: usize, list: UserWritePtr<Gid>) -> SyscallResult {
+        let task = self.task;
+        let groups = task.cred_ref().groups.clone();
+        if size == 0 {
+            return Ok(groups.len());
+        }
+        if size < groups.len() {
+            return Err(SysError::EINVAL);
+        }
+        list.write_array(task, &groups)?;
+        Ok(groups.len())
+    }
+
+    /// Check the version in `hdr`, and return the number of `CapUserData` it
+    /// takes. An unknown version is replaced with the preferred one.
+    fn cap_data_len(&self, hdr: UserRdWrPtr<CapUserHeader>) -> SysResult<(usize, i32)> {
+        let mut hdr = hdr.into_mut(self.task)?;
+        match hdr.version {
+            LINUX_CAPABILITY_VERSION_1 => Ok((1, hdr.pid)),
+            LINUX_CAPABILITY_VERSION_2 | LINUX_CAPABILITY_VERSION_3 => Ok((2, hdr.pid)),
+            _ => {
+                hdr.version = LINUX_CAPABILITY_VERSION_3;
+                Err(SysError::EINVAL)
+            }
+        }
+    }
+
+    /// capget() returns the capabilities of the process `pid` in `hdr`, or of
+    /// the calling process if `pid` is zero. With a null `data`, it only
+    /// probes the version, which succeeds after writing the preferred one.
+    pub fn sys_capget(
+        &self,
+        hdr: UserRdWrPtr<CapUserHeader>,
+        data: UserWritePtr<CapUserData>,
+    ) -> SyscallResult {
+        let (len, pid) = match self.cap_data_len(hdr) {
+            Err(SysError::EINVAL) if data.is_null() => return Ok(0),
+            res => res?,
+        };
+        if pid < 0 {
+            return Err(SysError::EINVAL);
+        }
+        let target = if pid == 0 {
+            self.task.clone()
+        } else {
+            TASK_MANAGER.get(pid as usize).ok_or(SysError::ESRCH)?
+        };
+        let cred = target.cred_ref().clone();
+        let split = |caps: Capabilities| [caps.bits() as u32, (caps.bits() >> 32) as u32];
+        let (effective, permitted, inheritable) = (
+            split(cred.cap_effective),
+            split(cred.cap_permitted),
+            split(cred.cap_inheritable),
+        );
+        let caps: Vec<CapUserData> = (0..len)
+            .map(|i| CapUserData {
+                effective: effective[i],
+                permitted: permitted[i],
+                inheritable: inheritable[i],
+            })
+            .collect();
+        data.write_array(self.task, &caps)?;
+        Ok(0)
+    }
+
+    /// capset() sets the capabilities of the calling process. The permitted
+    /// set may only shrink, the effective set must be within the permitted
+    /// one, and without `CAP_SETPCAP` the inheritable set must be within the
+    /// old inheritable and permitted ones.
+    pub fn sys_capset(
+        &self,
+        hdr: UserRdWrPtr<CapUserHeader>,
+        data: UserReadPtr<CapUserData>,
+    ) -> SyscallResult {
+        let task = self.task;
+        let (len, pid) = self.cap_data_len(hdr)?;
+        if pid != 0 && pid as usize != task.pid() {
+            return Err(SysError::EPERM);
+        }
+        let caps = data.read_array(task, len)?;
+        let join = |get: fn(&CapUserData) -> u32| {
+            let bits = caps.iter().enumerate().fold(0u64, |bits, (i, data)| {
+                bits | (get(data) as u64) << (32 * i)
+            });
+            Capabilities::from_bits_truncate(bits)
+        };
+        let effective = join(|data| data.effective);
+        let permitted = join(|data| data.permitted);
+        let inheritable = join(|data| data.inheritable);
+        let cred = task.cred();
+        log::info!("[sys_capset] effective {effective:?}, permitted {permitted:?}, inheritable {inheritable:?}");
+        if !cred.cap_permitted.contains(permitted)
+            || !permitted.contains(effective)
+            || !cred.has_cap(Capabilities::SETPCAP)
+                && !(cred.cap_inheritable | cred.cap_permitted).contains(inheritable)
+        {
+            return Err(SysError::EPERM);
+        }
+        cred.cap_effective = effective;
+        cred.cap_permitted = permitted;
+        cred.cap_inheritable = inheritable;
+        Ok(0)
+    }
+
+    /// setsid() creates a new session if the calling process is not a process
+    /// group leader. The calling process is the leader of the new session, and
+    /// of a new process group in it.
+    pub fn sys_setsid(&self) -> SyscallResult {
+        PROCESS_GROUP_MANAGER.add_session(self.task)
+    }
+
+    /// getsid() returns the session ID of the process with process ID pid. If
+    /// pid is 0, getsid() returns the session ID of the calling process.
+    pub fn sys_getsid(&self, pid: usize) -> SyscallResult {
+        let target_task = if pid == 0 {
+            self.task.clone()
+        } else {
+            TASK_MANAGER.get(pid).ok_or(SysError::ESRCH)?
+        };
+        PROCESS_GROUP_MANAGER
+            .sid(target_task.pgid())
+            .ok_or(SysError::ESRCH)
     }
 }
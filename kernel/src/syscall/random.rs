@@ -4,23 +4,43 @@ use vfs::devfs::urandom::RNG;
 use super::Syscall;
 use crate::mm::UserWritePtr;
 
+/// Most bytes returned by one call, as Linux does.
+const GETRANDOM_MAX: usize = (i32::MAX as usize) >> 6;
+
 bitflags! {
     struct GetRandomFlag: u8 {
         const GRND_NONBLOCK = 1 << 0;
         const GRND_RANDOM = 1 << 1;
+        const GRND_INSECURE = 1 << 2;
     }
 }
 
 impl Syscall<'_> {
+    /// The getrandom() system call fills the buffer pointed to by buf with up
+    /// to buflen random bytes from the kernel CSPRNG.
+    ///
+    /// The generator is seeded on first use and never blocks, so
+    /// `GRND_NONBLOCK` and `GRND_RANDOM` make no difference.
     pub fn sys_getrandom(
         &self,
         buf: UserWritePtr<u8>,
         buflen: usize,
-        _flags: usize,
+        flags: usize,
     ) -> SyscallResult {
         let task = self.task;
+        let flags = u8::try_from(flags)
+            .ok()
+            .and_then(GetRandomFlag::from_bits)
+            .ok_or(SysError::EINVAL)?;
+        if flags.contains(GetRandomFlag::GRND_INSECURE | GetRandomFlag::GRND_RANDOM) {
+            return Err(SysError::EINVAL);
+        }
+        let buflen = buflen.min(GETRANDOM_MAX);
         let mut buf = buf.into_mut_slice(&task, buflen)?;
-        unsafe { RNG.fill_buf(&mut buf) };
-        Ok(buf.len())
+        // do not keep interrupts off for the whole buffer
+        for chunk in buf.chunks_mut(4096) {
+            RNG.lock().fill_buf(chunk);
+        }
+        Ok(buflen)
     }
 }
@@ -1,12 +1,11 @@
-use config::{board::MAX_HARTS, process::USER_STACK_SIZE};
+use config::fs::NR_OPEN;
 use strum::FromRepr;
 use systype::{RLimit, Rusage, SysError, SyscallResult};
 
 use super::Syscall;
 use crate::{
     mm::{UserReadPtr, UserWritePtr},
-    syscall::resource,
-    task::TASK_MANAGER,
+    task::{cred::Capabilities, TASK_MANAGER},
 };
 
 #[derive(FromRepr, Clone, Copy, Debug, Eq, PartialEq)]
@@ -80,8 +79,7 @@ impl Syscall<'_> {
                 usage.write(&task, ret)?;
             }
             RUSAGE_CHILDREN => {
-                log::error!("rusage children not implemented");
-                let (total_utime, total_stime) = task.get_process_ustime();
+                let (total_utime, total_stime) = task.get_children_ustime();
                 ret.utime = total_utime.into();
                 ret.stime = total_stime.into();
                 usage.write(&task, ret)?;
@@ -98,6 +96,12 @@ impl Syscall<'_> {
         Ok(0)
     }
 
+    /// Get and set the resource limits of the process `pid`, or the calling
+    /// one if it is 0. Each of `new_limit` and `old_limit` may be null.
+    ///
+    /// The soft limit may not exceed the hard one, and only a privileged
+    /// process may raise a hard limit or change the limits of a process of
+    /// another user.
     pub fn sys_prlimit64(
         &self,
         pid: usize,
@@ -105,46 +109,61 @@ impl Syscall<'_> {
         new_limit: UserReadPtr<RLimit>,
         old_limit: UserWritePtr<RLimit>,
     ) -> SyscallResult {
-        use Resource::*;
-
         let task = if pid == 0 {
             self.task.clone()
-        } else if let Some(t) = TASK_MANAGER.get(pid) {
-            t
         } else {
-            return Err(SysError::ESRCH);
+            TASK_MANAGER.get(pid).ok_or(SysError::ESRCH)?
         };
-
         let resource = Resource::from_repr(resource).ok_or(SysError::EINVAL)?;
-        if old_limit.not_null() {
-            let limit = match resource {
-                STACK => RLimit {
-                    rlim_cur: USER_STACK_SIZE,
-                    rlim_max: USER_STACK_SIZE,
-                },
-                NOFILE => task.with_fd_table(|table| table.rlimit()),
-                r => {
-                    log::warn!("[sys_prlimit64] get old_limit : unimplemented {r:?}");
-                    RLimit {
-                        rlim_cur: 0,
-                        rlim_max: 0,
-                    }
-                }
-            };
-            old_limit.write(&task, limit)?;
+        let cred = self.task.cred_ref();
+        let privileged = cred.has_cap(Capabilities::SYS_RESOURCE);
+        if !privileged && task.pid() != self.task.pid() {
+            let target = task.cred_ref();
+            if [target.uid, target.euid, target.suid] != [cred.uid; 3]
+                || [target.gid, target.egid, target.sgid] != [cred.gid; 3]
+            {
+                return Err(SysError::EPERM);
+            }
         }
-        if new_limit.not_null() {
-            let limit = new_limit.read(&task)?;
-            log::info!("[sys_prlimit64] new_limit: {limit:?}");
-            match resource {
-                NOFILE => {
-                    task.with_mut_fd_table(|table| table.set_rlimit(limit));
-                }
-                r => {
-                    log::warn!("[sys_prlimit64] set new_limit : unimplemented {r:?}");
+
+        let old = if new_limit.not_null() {
+            let limit = new_limit.read(&self.task)?;
+            log::info!(
+                "[sys_prlimit64] set {resource:?} of task {}: {limit:?}",
+                task.tid()
+            );
+            if limit.rlim_cur > limit.rlim_max {
+                return Err(SysError::EINVAL);
+            }
+            if resource == Resource::NOFILE && limit.rlim_max > NR_OPEN {
+                return Err(SysError::EPERM);
+            }
+            let old = task.with_mut_rlimits(|rlimits| {
+                let old = rlimits[resource as usize];
+                if limit.rlim_max > old.rlim_max && !privileged {
+                    return Err(SysError::EPERM);
                 }
+                rlimits[resource as usize] = limit;
+                Ok(old)
+            })?;
+            if resource == Resource::NOFILE {
+                task.set_max_fds(limit.rlim_cur);
             }
+            old
+        } else {
+            task.rlimit(resource)
+        };
+        if old_limit.not_null() {
+            old_limit.write(&self.task, old)?;
         }
         Ok(0)
     }
+
+    pub fn sys_getrlimit(&self, resource: i32, rlim: UserWritePtr<RLimit>) -> SyscallResult {
+        self.sys_prlimit64(0, resource, UserReadPtr::null(), rlim)
+    }
+
+    pub fn sys_setrlimit(&self, resource: i32, rlim: UserReadPtr<RLimit>) -> SyscallResult {
+        self.sys_prlimit64(0, resource, rlim, UserWritePtr::null())
+    }
 }
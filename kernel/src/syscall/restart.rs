@@ -0,0 +1,33 @@
+//! Restart of syscalls interrupted by signals.
+//!
+//! A blocking syscall interrupted by a signal returns `EINTR`, which is
+//! either returned to the user or turned into a restart of the syscall once
+//! the signal is delivered, according to the syscall and the action taken.
+
+use super::SyscallNo;
+
+/// What becomes of a syscall that returned `EINTR`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RestartPolicy {
+    /// `EINTR` is returned to the user.
+    Never,
+    /// Restarted unless a handler installed without `SA_RESTART` runs, i.e.
+    /// signals ignored or stopping and continuing the task are transparent, as
+    /// `ERESTARTSYS` of linux.
+    Restart,
+}
+
+impl SyscallNo {
+    pub fn restart_policy(self) -> RestartPolicy {
+        use SyscallNo::*;
+        match self {
+            // wait for children, locks and i/o
+            WAIT4 | WAITID | FUTEX | FLOCK | FCNTL | OPENAT | READ | WRITE | READV | WRITEV
+            | PREAD64 | PWRITE64 | PREADV | PWRITEV | PREADV2 | PWRITEV2 | SENDFILE | SPLICE
+            | ACCEPT | ACCEPT4 | SENDTO | RECVFROM | SENDMSG | RECVMSG => RestartPolicy::Restart,
+            // `rt_sigsuspend`, `ppoll`, `pselect6` and `epoll_pwait` wait for the signal
+            // itself, the time slept by `nanosleep` is not kept for a restart, and so on
+            _ => RestartPolicy::Never,
+        }
+    }
+}
@@ -1,69 +1,292 @@
+use alloc::{sync::Arc, vec, vec::Vec};
 use core::intrinsics::size_of;
 
-use systype::{SysError, SyscallResult};
+use async_utils::yield_now;
+use systype::{SysError, SysResult, SyscallResult};
 
 use super::Syscall;
 use crate::{
     mm::{UserReadPtr, UserWritePtr},
-    task::{resource::CpuMask, TASK_MANAGER},
+    processor::{hart, membarrier::membarrier_all_harts},
+    task::{
+        cred::Capabilities,
+        resource::{CpuMask, IoPrio, IoPrioClass, NICE_MAX, NICE_MIN},
+        Task, TASK_MANAGER,
+    },
 };
 
+/// Argument of `sched_getparam`, see sched(7).
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct SchedParam {
+    sched_priority: i32,
+}
+
 impl Syscall<'_> {
     pub fn sys_sched_setscheduler(&self) -> SyscallResult {
-        log::warn!("[sys_sched_setscheduler] unimplemented");
+        log::info!("[sys_sched_setscheduler] unimplemented");
         Ok(0)
     }
 
     pub fn sys_sched_getscheduler(&self) -> SyscallResult {
-        log::warn!("[sys_sched_getscheduler] unimplemented");
+        log::info!("[sys_sched_getscheduler] unimplemented");
         Ok(0)
     }
 
-    pub fn sys_sched_getparam(&self) -> SyscallResult {
-        log::warn!("[sys_sched_getparam] unimplemented");
+    /// Only the normal policy exists, so the priority reported is the one
+    /// derived from the nice value, in the `20 - nice` form of getpriority(2).
+    pub fn sys_sched_getparam(&self, pid: usize, param: UserWritePtr<SchedParam>) -> SyscallResult {
+        let task = if pid == 0 {
+            self.task.clone()
+        } else {
+            TASK_MANAGER.get(pid).ok_or(SysError::ESRCH)?
+        };
+        param.write(
+            &self.task,
+            SchedParam {
+                sched_priority: 20 - *task.nice_ref(),
+            },
+        )?;
         Ok(0)
     }
 
-    pub fn sys_sched_setaffinity(
+    /// Set the nice value of a process, a process group or all processes of a
+    /// user. `prio` is clamped to the valid range of nice values.
+    ///
+    /// Only privileged tasks may set a negative nice value or change tasks of
+    /// another user. All matched tasks are checked before any is changed, so a
+    /// denied one leaves every nice value as it was.
+    pub fn sys_setpriority(&self, which: i32, who: usize, prio: i32) -> SyscallResult {
+        let nice = prio.clamp(NICE_MIN, NICE_MAX);
+        let cred = self.task.cred_ref();
+        if nice < 0 && !cred.has_cap(Capabilities::SYS_NICE) {
+            return Err(SysError::EPERM);
+        }
+        let tasks = self.priority_targets(which, who)?;
+        if !cred.has_cap(Capabilities::SYS_NICE)
+            && tasks
+                .iter()
+                .any(|task| task.cred_ref().uid != cred.euid && task.cred_ref().uid != cred.uid)
+        {
+            return Err(SysError::EPERM);
+        }
+        for task in tasks {
+            log::info!("[sys_setpriority] task {} nice {nice}", task.tid());
+            *task.nice() = nice;
+        }
+        Ok(0)
+    }
+
+    /// Get the highest priority of the matched tasks. The raw syscall returns
+    /// `20 - nice` so that the result is never negative.
+    pub fn sys_getpriority(&self, which: i32, who: usize) -> SyscallResult {
+        let nice = self
+            .priority_targets(which, who)?
+            .iter()
+            .map(|task| *task.nice_ref())
+            .min()
+            .unwrap();
+        Ok((20 - nice) as usize)
+    }
+
+    fn priority_targets(&self, which: i32, who: usize) -> SysResult<Vec<Arc<Task>>> {
+        const PRIO_PROCESS: i32 = 0;
+        const PRIO_PGRP: i32 = 1;
+        const PRIO_USER: i32 = 2;
+        let tasks: Vec<Arc<Task>> = match which {
+            PRIO_PROCESS if who == 0 => vec![self.task.clone()],
+            PRIO_PROCESS => TASK_MANAGER.get(who).into_iter().collect(),
+            PRIO_PGRP => {
+                let pgid = if who == 0 { self.task.pgid() } else { who };
+                TASK_MANAGER
+                    .tasks()
+                    .into_iter()
+                    .filter(|task| task.pgid() == pgid)
+                    .collect()
+            }
+            PRIO_USER => {
+                let uid = if who == 0 {
+                    self.task.cred_ref().uid
+                } else {
+                    who as _
+                };
+                TASK_MANAGER
+                    .tasks()
+                    .into_iter()
+                    .filter(|task| task.cred_ref().uid == uid)
+                    .collect()
+            }
+            _ => return Err(SysError::EINVAL),
+        };
+        if tasks.is_empty() {
+            return Err(SysError::ESRCH);
+        }
+        Ok(tasks)
+    }
+
+    /// Set the set of harts the task `pid` (0 for the calling task) may run on.
+    ///
+    /// The mask may only name harts present on this board, and must include
+    /// one that is not offline. If the calling task excludes the hart it is
+    /// running on, it yields so that the executor moves it to an allowed hart.
+    pub async fn sys_sched_setaffinity(
         &self,
         pid: usize,
         cpusetsize: usize,
         mask: UserReadPtr<CpuMask>,
     ) -> SyscallResult {
-        log::warn!("[sys_sched_setaffinity]");
         if cpusetsize < size_of::<CpuMask>() {
             return Err(SysError::EINVAL);
         }
-        if let Some(task) = TASK_MANAGER.get(pid) {
-            if !task.is_leader() {
-                return Err(SysError::ESRCH);
-            }
-            let mask = mask.read(&self.task)?;
-            *task.cpus_allowed() = mask;
-        } else {
-            return Err(SysError::ESRCH);
+        let task = self.affinity_target(pid)?;
+        let cred = self.task.cred_ref();
+        if !cred.has_cap(Capabilities::SYS_NICE)
+            && task.cred_ref().uid != cred.euid
+            && task.cred_ref().uid != cred.uid
+        {
+            return Err(SysError::EPERM);
+        }
+        let raw = mask.read(&self.task)?.bits();
+        let mask = CpuMask::from_bits(raw).ok_or(SysError::EINVAL)?;
+        if !CpuMask::online().contains(mask) || !mask.intersects(CpuMask::active()) {
+            return Err(SysError::EINVAL);
+        }
+        log::info!(
+            "[sys_sched_setaffinity] task {} mask {:#x}",
+            task.tid(),
+            raw
+        );
+        *task.cpus_allowed() = mask;
+        if Arc::ptr_eq(&task, &self.task) && !mask.has_hart(hart::local_hart().hart_id()) {
+            yield_now().await;
         }
         Ok(0)
     }
 
+    /// Get the set of harts the task `pid` (0 for the calling task) may run on.
+    /// Returns the size of the mask written, as the raw syscall does.
     pub fn sys_sched_getaffinity(
         &self,
         pid: usize,
         cpusetsize: usize,
         mask: UserWritePtr<CpuMask>,
     ) -> SyscallResult {
-        log::warn!("[sys_sched_getaffinity]");
         if cpusetsize < size_of::<CpuMask>() {
             return Err(SysError::EINVAL);
         }
-        if let Some(task) = TASK_MANAGER.get(pid) {
-            if !task.is_leader() {
-                return Err(SysError::ESRCH);
+        let task = self.affinity_target(pid)?;
+        mask.write(&self.task, *task.cpus_allowed_ref() & CpuMask::online())?;
+        Ok(size_of::<CpuMask>())
+    }
+
+    /// Get the hart the calling task is running on. There is a single NUMA
+    /// node, numbered 0.
+    pub fn sys_getcpu(&self, cpu: UserWritePtr<u32>, node: UserWritePtr<u32>) -> SyscallResult {
+        if cpu.not_null() {
+            cpu.write(&self.task, hart::local_hart().hart_id() as u32)?;
+        }
+        if node.not_null() {
+            node.write(&self.task, 0)?;
+        }
+        Ok(0)
+    }
+
+    /// Issue memory barriers on the harts that may run user code, see
+    /// membarrier(2).
+    ///
+    /// Every command fences all online harts, which is a superset of the harts
+    /// running threads of the caller. The call returns once each of them has
+    /// acknowledged, so `MEMBARRIER_CMD_GLOBAL` needs no grace period either.
+    pub async fn sys_membarrier(&self, cmd: i32, flags: u32, _cpu_id: i32) -> SyscallResult {
+        const MEMBARRIER_CMD_QUERY: i32 = 0;
+        const MEMBARRIER_CMD_GLOBAL: i32 = 1 << 0;
+        const MEMBARRIER_CMD_GLOBAL_EXPEDITED: i32 = 1 << 1;
+        const MEMBARRIER_CMD_REGISTER_GLOBAL_EXPEDITED: i32 = 1 << 2;
+        const MEMBARRIER_CMD_PRIVATE_EXPEDITED: i32 = 1 << 3;
+        const MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED: i32 = 1 << 4;
+
+        if flags != 0 {
+            return Err(SysError::EINVAL);
+        }
+        match cmd {
+            MEMBARRIER_CMD_QUERY => Ok((MEMBARRIER_CMD_GLOBAL
+                | MEMBARRIER_CMD_GLOBAL_EXPEDITED
+                | MEMBARRIER_CMD_REGISTER_GLOBAL_EXPEDITED
+                | MEMBARRIER_CMD_PRIVATE_EXPEDITED
+                | MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED)
+                as usize),
+            MEMBARRIER_CMD_GLOBAL | MEMBARRIER_CMD_GLOBAL_EXPEDITED => {
+                membarrier_all_harts().await;
+                Ok(0)
             }
-            mask.write(&self.task, *task.cpus_allowed())?;
-        } else {
-            return Err(SysError::ESRCH);
+            MEMBARRIER_CMD_PRIVATE_EXPEDITED => {
+                if !self.task.with_memory_space(|m| m.membarrier_registered()) {
+                    return Err(SysError::EPERM);
+                }
+                membarrier_all_harts().await;
+                Ok(0)
+            }
+            MEMBARRIER_CMD_REGISTER_GLOBAL_EXPEDITED => Ok(0),
+            MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED => {
+                self.task.with_mut_memory_space(|m| m.register_membarrier());
+                Ok(0)
+            }
+            _ => Err(SysError::EINVAL),
+        }
+    }
+
+    fn affinity_target(&self, pid: usize) -> SysResult<Arc<Task>> {
+        if pid == 0 {
+            return Ok(self.task.clone());
         }
+        TASK_MANAGER.get(pid).ok_or(SysError::ESRCH)
+    }
+
+    /// Set the I/O scheduling class and priority of a process. Only
+    /// `IOPRIO_WHO_PROCESS` is supported, `who` of 0 means the calling task.
+    ///
+    /// As with nice values, an unprivileged task may only change tasks of its
+    /// own user, and may not enter the real-time class or raise the priority
+    /// within a class.
+    pub fn sys_ioprio_set(&self, which: i32, who: usize, ioprio: u32) -> SyscallResult {
+        let task = self.ioprio_target(which, who)?;
+        let new = u16::try_from(ioprio)
+            .ok()
+            .and_then(IoPrio::from_raw)
+            .ok_or(SysError::EINVAL)?;
+        log::info!("[sys_ioprio_set] task {} ioprio {new:?}", task.tid());
+        let cred = self.task.cred_ref();
+        if !cred.has_cap(Capabilities::SYS_NICE) {
+            if task.cred_ref().uid != cred.euid && task.cred_ref().uid != cred.uid {
+                return Err(SysError::EPERM);
+            }
+            let old = *task.ioprio_ref();
+            let raises = match new.class {
+                IoPrioClass::Rt => true,
+                IoPrioClass::Be => old.class == new.class && new.level < old.level,
+                IoPrioClass::None | IoPrioClass::Idle => false,
+            };
+            if raises {
+                return Err(SysError::EPERM);
+            }
+        }
+        *task.ioprio() = new;
         Ok(0)
     }
+
+    pub fn sys_ioprio_get(&self, which: i32, who: usize) -> SyscallResult {
+        let task = self.ioprio_target(which, who)?;
+        Ok(task.ioprio_ref().to_raw() as usize)
+    }
+
+    fn ioprio_target(&self, which: i32, who: usize) -> SysResult<Arc<Task>> {
+        const IOPRIO_WHO_PROCESS: i32 = 1;
+        if which != IOPRIO_WHO_PROCESS {
+            return Err(SysError::EINVAL);
+        }
+        if who == 0 {
+            return Ok(self.task.clone());
+        }
+        TASK_MANAGER.get(who).ok_or(SysError::ESRCH)
+    }
 }
@@ -1,10 +1,14 @@
-use core::mem;
+use core::{
+    mem::{self, size_of},
+    time::Duration,
+};
 
+use arch::time::get_time_duration;
 use async_utils::suspend_now;
 use config::process::INIT_PROC_PID;
 use signal::{
     action::{Action, ActionType},
-    siginfo::{SigDetails, SigInfo},
+    siginfo::{LinuxSigInfo, SigDetails, SigInfo},
     signal_stack::{SignalStack, UContext},
     sigset::{Sig, SigSet},
 };
@@ -59,7 +63,10 @@ impl Syscall<'_> {
                 mask: action.sa_mask,
             };
             log::info!("[sys_rt_sigaction] new:{:?}", new);
-            task.with_mut_sig_handlers(|handlers| handlers.update(signum, new));
+            task.with_mut_sig_handlers(|handlers| match action.sa_handler {
+                SIG_IGN => handlers.ignore(signum, new),
+                _ => handlers.update(signum, new),
+            });
         }
         Ok(0)
     }
@@ -116,22 +123,45 @@ impl Syscall<'_> {
         let ucontext = ucontext_ptr.read(&task)?;
         // log::error!("[SA_SIGINFO] load ucontext {ucontext:?}");
         *task.sig_mask() = ucontext.uc_sigmask;
-        *task.sig_stack() = (ucontext.uc_stack.ss_size != 0).then_some(ucontext.uc_stack);
+        task.set_sig_ucontext_ptr(ucontext.uc_link);
+        // NOTE: like linux the alternate signal stack is set again with errors
+        // ignored, it is left as is while the handler returns on it, unless disarmed
+        let _ = task.set_sig_stack(ucontext.uc_stack, cx.user_x[2]);
         cx.sepc = ucontext.uc_mcontext.user_x[0];
         cx.user_x = ucontext.uc_mcontext.user_x;
         // log::error!("stask after {:#x}", cx.user_x[2]);
         Ok(cx.user_x[10])
     }
 
-    pub fn sys_rt_signalstack(
+    /// sigaltstack() allows a thread to define a new alternate signal stack
+    /// and/or retrieve the state of an existing alternate signal stack. An
+    /// alternate signal stack is used during the execution of a signal handler
+    /// if the establishment of that handler (see sigaction(2)) requested it
+    /// with `SA_ONSTACK`.
+    ///
+    /// `ss` is either enabled with a size of at least `MINSIGSTKSZ`, or
+    /// disabled with `SS_DISABLE`. It fails with `EPERM` while the thread runs
+    /// on the alternate signal stack.
+    pub fn sys_sigaltstack(
         &self,
-        _ss: UserReadPtr<SignalStack>,
+        ss: UserReadPtr<SignalStack>,
         old_ss: UserWritePtr<SignalStack>,
     ) -> SyscallResult {
-        if !old_ss.is_null() {
-            // old_ss.write(self.task, current_task())
+        let task = self.task;
+        let sp = task.trap_context_mut().user_x[2];
+        let new_ss = if ss.not_null() {
+            Some(ss.read(task)?)
+        } else {
+            None
+        };
+        if old_ss.not_null() {
+            old_ss.write(task, task.sig_stack_info(sp))?;
         }
-        todo!()
+        if let Some(new_ss) = new_ss {
+            log::info!("[sys_sigaltstack] {new_ss:?}");
+            task.set_sig_stack(new_ss, sp)?;
+        }
+        Ok(0)
     }
 
     /// The kill() system call can be used to send any signal to any process
@@ -157,6 +187,7 @@ impl Syscall<'_> {
         if !sig.is_valid() {
             return Err(SysError::EINVAL);
         }
+        let sender = self.task.pid();
         // log::debug!("[sys_kill] signal {sig:?}");
         match pid {
             0 => {
@@ -164,15 +195,15 @@ impl Syscall<'_> {
                 let pgid = self.task.pgid();
                 for task in PROCESS_GROUP_MANAGER
                     .get_group(pgid)
-                    .unwrap()
+                    .ok_or(SysError::ESRCH)?
                     .into_iter()
-                    .map(|t| t.upgrade().unwrap())
+                    .filter_map(|t| t.upgrade())
                 {
                     task.receive_siginfo(
                         SigInfo {
                             sig,
                             code: SigInfo::USER,
-                            details: SigDetails::Kill { pid: pgid },
+                            details: SigDetails::Kill { pid: sender },
                         },
                         false,
                     );
@@ -185,7 +216,7 @@ impl Syscall<'_> {
                             SigInfo {
                                 sig,
                                 code: SigInfo::USER,
-                                details: SigDetails::Kill { pid: task.pid() },
+                                details: SigDetails::Kill { pid: sender },
                             },
                             false,
                         );
@@ -194,23 +225,17 @@ impl Syscall<'_> {
                 })?;
             }
             _ if pid > 0 => {
-                if let Some(task) = TASK_MANAGER.get(pid as usize) {
-                    if task.is_leader() {
-                        task.receive_siginfo(
-                            SigInfo {
-                                sig,
-                                code: SigInfo::USER,
-                                details: SigDetails::Kill { pid: task.pid() },
-                            },
-                            false,
-                        );
-                    } else {
-                        // sys_kill is sent to process not thread
-                        return Err(SysError::ESRCH);
-                    }
-                } else {
-                    return Err(SysError::ESRCH);
-                }
+                // NOTE: like linux, the tid of any thread names its process, to which
+                // the signal is sent, and a thread not blocking it takes it
+                let task = TASK_MANAGER.get(pid as usize).ok_or(SysError::ESRCH)?;
+                task.leader().receive_siginfo(
+                    SigInfo {
+                        sig,
+                        code: SigInfo::USER,
+                        details: SigDetails::Kill { pid: sender },
+                    },
+                    false,
+                );
             }
             _ => {
                 // pid < -1
@@ -226,7 +251,7 @@ impl Syscall<'_> {
                         SigInfo {
                             sig,
                             code: SigInfo::USER,
-                            details: SigDetails::Kill { pid: pgid },
+                            details: SigDetails::Kill { pid: sender },
                         },
                         false,
                     );
@@ -243,29 +268,28 @@ impl Syscall<'_> {
     /// process.)
     pub fn sys_tgkill(&self, tgid: isize, tid: isize, signum: i32) -> SyscallResult {
         let sig = Sig::from_i32(signum);
-        if !sig.is_valid() || tgid < 0 || tid < 0 {
+        if !sig.is_valid() || tgid <= 0 || tid <= 0 {
             return Err(SysError::EINVAL);
         }
+        let sender = self.task.pid();
         let task = TASK_MANAGER.get(tgid as usize).ok_or(SysError::ESRCH)?;
         if !task.is_leader() {
             return Err(SysError::ESRCH);
         }
-        task.with_mut_thread_group(|tg| -> SyscallResult {
-            for thread in tg.iter() {
-                if thread.tid() == tid as usize {
-                    thread.receive_siginfo(
-                        SigInfo {
-                            sig,
-                            code: SigInfo::TKILL,
-                            details: SigDetails::Kill { pid: task.pid() },
-                        },
-                        true,
-                    );
-                    return Ok(0);
-                }
-            }
-            return Err(SysError::ESRCH);
-        })
+        // the thread group is not locked while receiving, since a job control
+        // signal takes effect on the whole group
+        let thread = task
+            .with_thread_group(|tg| tg.iter().find(|t| t.tid() == tid as usize))
+            .ok_or(SysError::ESRCH)?;
+        thread.receive_siginfo(
+            SigInfo {
+                sig,
+                code: SigInfo::TKILL,
+                details: SigDetails::Kill { pid: sender },
+            },
+            true,
+        );
+        Ok(0)
     }
 
     /// An obsolete predecessor to tgkill(). It allows only the target thread ID
@@ -274,15 +298,16 @@ impl Syscall<'_> {
     /// system call.
     pub fn sys_tkill(&self, tid: isize, signum: i32) -> SyscallResult {
         let sig = Sig::from_i32(signum);
-        if !sig.is_valid() || tid < 0 {
+        if !sig.is_valid() || tid <= 0 {
             return Err(SysError::EINVAL);
         }
+        let sender = self.task.pid();
         let task = TASK_MANAGER.get(tid as usize).ok_or(SysError::ESRCH)?;
         task.receive_siginfo(
             SigInfo {
                 sig,
                 code: SigInfo::TKILL,
-                details: SigDetails::Kill { pid: task.pid() },
+                details: SigDetails::Kill { pid: sender },
             },
             true,
         );
@@ -335,50 +360,79 @@ impl Syscall<'_> {
     /// - `timeout`: specifies the interval for which the thread is suspended
     ///   waiting for a signal.
     ///
+    /// The signals in `set` are taken without running their handlers, and are
+    /// meant to be blocked beforehand. The wait fails with `EAGAIN` when the
+    /// timeout expires, and with `EINTR` when a signal not blocked and not in
+    /// `set` arrives, which is then delivered.
+    ///
     /// On success, sigtimedwait() returns a signal number
     pub async fn sys_rt_sigtimedwait(
         &self,
         set: UserReadPtr<SigSet>,
-        info: UserWritePtr<SigInfo>,
+        info: UserWritePtr<LinuxSigInfo>,
         timeout: UserReadPtr<TimeSpec>,
+        sigset_size: usize,
     ) -> SyscallResult {
         let task = self.task;
+        if sigset_size != size_of::<SigSet>() {
+            return Err(SysError::EINVAL);
+        }
         let mut set = set.read(&task)?;
         set.remove(SigSet::SIGKILL | SigSet::SIGSTOP);
-        let sig = task.with_mut_sig_pending(|pending| {
-            if let Some(si) = pending.get_expect(set) {
-                Some(si.sig)
-            } else {
-                pending.should_wake = set | SigSet::SIGKILL | SigSet::SIGSTOP;
-                None
-            }
-        });
-        if let Some(sig) = sig {
-            return Ok(sig.raw());
-        }
-        task.set_interruptable();
-        if timeout.not_null() {
+        let expire = if timeout.not_null() {
             let timeout = timeout.read(&task)?;
             if !timeout.is_valid() {
                 return Err(SysError::EINVAL);
             }
-            log::warn!("[sys_rt_sigtimedwait] {:?}", timeout);
-            task.suspend_timeout(timeout.into()).await;
+            Some(get_time_duration() + Duration::from(timeout))
         } else {
-            suspend_now().await;
+            None
+        };
+        let ret = loop {
+            // NOTE: the task is interruptable before the pending signals are checked,
+            // so that no signal arriving in between is missed
+            task.set_interruptable();
+            task.set_wake_up_signal(set | !*task.sig_mask_ref());
+            if let Some(si) = task.with_mut_sig_pending(|pending| pending.dequeue_expect(set)) {
+                break Ok(si);
+            }
+            if task.with_sig_pending(|pending| pending.has_expect_signals(!*task.sig_mask_ref())) {
+                break Err(SysError::EINTR);
+            }
+            match expire {
+                Some(expire) => {
+                    let now = get_time_duration();
+                    if now >= expire {
+                        break Err(SysError::EAGAIN);
+                    }
+                    task.suspend_timeout(expire - now).await;
+                }
+                None => suspend_now().await,
+            }
+        };
+        task.set_running();
+        let si = ret?;
+        log::info!("[sys_rt_sigtimedwait] take {:?}", si);
+        if info.not_null() {
+            info.write(&task, LinuxSigInfo::from(si))?;
         }
+        Ok(si.sig.raw())
+    }
 
-        task.set_running();
-        let si = task.with_mut_sig_pending(|pending| pending.dequeue_expect(set));
-        if let Some(si) = si {
-            log::warn!("[sys_rt_sigtimedwait] I'm woken by {:?}", si);
-            if info.not_null() {
-                info.write(&task, si)?;
-            }
-            Ok(si.sig.raw())
-        } else {
-            log::warn!("[sys_rt_sigtimedwait] I'm woken by timeout");
-            Err(SysError::EAGAIN)
+    /// sigpending() returns the set of signals that are pending for delivery
+    /// to the calling thread (i.e., the signals which have been raised while
+    /// blocked).
+    pub fn sys_rt_sigpending(
+        &self,
+        set: UserWritePtr<SigSet>,
+        sigset_size: usize,
+    ) -> SyscallResult {
+        let task = self.task;
+        if sigset_size != size_of::<SigSet>() {
+            return Err(SysError::EINVAL);
         }
+        let pending = task.with_sig_pending(|pending| pending.bitmap) & *task.sig_mask_ref();
+        set.write(&task, pending)?;
+        Ok(0)
     }
 }
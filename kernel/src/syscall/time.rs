@@ -1,23 +1,27 @@
-use alloc::{boxed::Box, sync::Arc};
 use core::time::Duration;
 
-use arch::time::{get_time_duration, get_time_ms, get_time_us};
+use arch::time::{get_time_coarse, get_time_duration, get_time_ms, get_time_us};
+use config::time::TIME_SLICE_DUATION;
+use signal::SigEvent;
 use systype::{SysError, SyscallResult};
 use time::{
-    timespec::TimeSpec,
+    timespec::{ITimerSpec, TimeSpec},
     timeval::{ITimerVal, TimeVal},
     tms::TMS,
-    CLOCK_DEVIATION, CLOCK_MONOTONIC, CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME,
-    CLOCK_THREAD_CPUTIME_ID,
+    CLOCK_BOOTTIME, CLOCK_DEVIATION, CLOCK_MONOTONIC, CLOCK_MONOTONIC_COARSE, CLOCK_MONOTONIC_RAW,
+    CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME, CLOCK_THREAD_CPUTIME_ID,
 };
-use timer::{Timer, TIMER_MANAGER};
 
 use super::Syscall;
 use crate::{
     mm::{UserReadPtr, UserWritePtr},
-    task::signal::{alloc_timer_id, RealITimer},
+    task::signal::{ITIMER_PROF, ITIMER_REAL},
 };
 
+/// The time is absolute rather than relative, for clock_nanosleep(2) and
+/// timer_settime(2).
+const TIMER_ABSTIME: usize = 1;
+
 impl Syscall<'_> {
     /// Retrieves the current time of day.
     ///
@@ -42,12 +46,16 @@ impl Syscall<'_> {
         Ok(0)
     }
 
+    /// times() stores the current process times, and those of its children
+    /// waited for, in clock ticks. It returns the number of clock ticks that
+    /// have elapsed since boot.
     pub fn sys_times(&self, tms: UserWritePtr<TMS>) -> SyscallResult {
         let task = self.task;
         if tms.not_null() {
-            tms.write(&task, TMS::from_task_time_stat(task.time_stat()))?;
+            let tms_val = TMS::new(task.get_process_ustime(), task.get_children_ustime());
+            tms.write(&task, tms_val)?;
         }
-        Ok(0)
+        Ok(TMS::ticks(get_time_duration()))
     }
 
     /// nanosleep suspends the execution of the calling thread until either at
@@ -95,6 +103,21 @@ impl Syscall<'_> {
                     (unsafe { CLOCK_DEVIATION }[clockid] + current).into(),
                 )?;
             }
+            // There is no suspend, so the time since boot is the monotonic time.
+            CLOCK_MONOTONIC_RAW | CLOCK_BOOTTIME => {
+                let current = get_time_duration();
+                tp.write(
+                    &task,
+                    (unsafe { CLOCK_DEVIATION }[CLOCK_MONOTONIC] + current).into(),
+                )?;
+            }
+            CLOCK_MONOTONIC_COARSE => {
+                let current = get_time_coarse();
+                tp.write(
+                    &task,
+                    (unsafe { CLOCK_DEVIATION }[CLOCK_MONOTONIC] + current).into(),
+                )?;
+            }
             CLOCK_PROCESS_CPUTIME_ID => {
                 let cpu_time = task.get_process_cputime();
                 tp.write(&task, cpu_time.into())?;
@@ -103,12 +126,12 @@ impl Syscall<'_> {
                 tp.write(&task, task.time_stat().cpu_time().into())?;
             }
             5 => {
-                log::warn!("[sys_clock_gettime] unsupported clockid{}", clockid);
+                log::info!("[sys_clock_gettime] unsupported clockid{}", clockid);
                 return Err(SysError::EINTR);
             }
             _ => {
                 log::error!("[sys_clock_gettime] unsupported clockid{}", clockid);
-                return Err(SysError::EINTR);
+                return Err(SysError::EINVAL);
             }
         }
         Ok(0)
@@ -146,12 +169,16 @@ impl Syscall<'_> {
     }
 
     /// finds the resolution (precision) of the specified clock clockid
-    pub fn sys_clock_getres(&self, _clockid: usize, res: UserWritePtr<TimeSpec>) -> SyscallResult {
+    pub fn sys_clock_getres(&self, clockid: usize, res: UserWritePtr<TimeSpec>) -> SyscallResult {
         if res.is_null() {
             return Ok(0);
         }
         let task = self.task;
-        res.write(&task, Duration::from_nanos(1).into())?;
+        let resolution = match clockid {
+            CLOCK_MONOTONIC_COARSE => TIME_SLICE_DUATION,
+            _ => Duration::from_nanos(1),
+        };
+        res.write(&task, resolution.into())?;
         Ok(0)
     }
 
@@ -162,12 +189,10 @@ impl Syscall<'_> {
         t: UserReadPtr<TimeSpec>,
         rem: UserWritePtr<TimeSpec>,
     ) -> SyscallResult {
-        /// for clock_nanosleep
-        pub const TIMER_ABSTIME: usize = 1;
         let task = self.task;
         match clockid {
             // FIXME: what is CLOCK_MONOTONIC
-            CLOCK_REALTIME | CLOCK_MONOTONIC => {
+            CLOCK_REALTIME | CLOCK_MONOTONIC | CLOCK_BOOTTIME => {
                 let ts = t.read(task)?;
                 let req: Duration = ts.into();
                 let remain = if flags == TIMER_ABSTIME {
@@ -206,10 +231,9 @@ impl Syscall<'_> {
         new_value: UserReadPtr<ITimerVal>,
         old_value: UserWritePtr<ITimerVal>,
     ) -> SyscallResult {
-        if which > 2 {
+        if which > ITIMER_PROF {
             return Err(SysError::EINVAL);
         }
-        debug_assert!(which == CLOCK_REALTIME);
         let task = self.task;
         let new = new_value.read(&task)?;
 
@@ -217,43 +241,7 @@ impl Syscall<'_> {
             return Err(SysError::EINVAL);
         }
 
-        let timer_id = alloc_timer_id();
-        let (old, next_expire) = task.with_mut_itimers(|itimers| {
-            // only supports real itimer now
-            let itimer = &mut itimers[which];
-            let old = ITimerVal {
-                it_interval: itimer.interval.into(),
-                it_value: itimer
-                    .next_expire
-                    .saturating_sub(get_time_duration())
-                    .into(),
-            };
-
-            if new.it_value.is_zero() {
-                // timer is disarmed
-                itimer.interval = new.it_interval.into();
-                itimer.next_expire = Duration::ZERO;
-                itimer.id = timer_id;
-                (old, itimer.next_expire)
-            } else {
-                itimer.interval = new.it_interval.into();
-                itimer.next_expire = get_time_duration() + new.it_value.into();
-                itimer.id = timer_id;
-                (old, itimer.next_expire)
-            }
-        });
-
-        if !new.it_value.is_zero() {
-            let timer = Timer::new(
-                next_expire,
-                Box::new(RealITimer {
-                    task: Arc::downgrade(self.task),
-                    id: timer_id,
-                }),
-            );
-            TIMER_MANAGER.add_timer(timer);
-        }
-
+        let old = task.set_itimer(which, new);
         log::info!("[sys_setitimer] new ITimerVal {new} old ITimerVal {old}");
         if old_value.not_null() {
             old_value.write(&task, old)?;
@@ -266,23 +254,106 @@ impl Syscall<'_> {
         which: usize,
         curr_value: UserWritePtr<ITimerVal>,
     ) -> SyscallResult {
-        if which > 2 {
+        if which > ITIMER_PROF {
             return Err(SysError::EINVAL);
         }
         if curr_value.not_null() {
             let task = self.task;
-            let itimerval = task.with_itimers(|itimers| {
-                let itimer = &itimers[which];
-                ITimerVal {
-                    it_interval: itimer.interval.into(),
-                    it_value: itimer
-                        .next_expire
-                        .saturating_sub(get_time_duration())
-                        .into(),
-                }
-            });
-            curr_value.write(&task, itimerval)?;
+            curr_value.write(&task, task.get_itimer(which))?;
+        }
+        Ok(0)
+    }
+
+    /// Deliver `SIGALRM` to the process in `seconds`, or cancel the pending
+    /// alarm if it is 0. Returns the seconds left of the previous alarm.
+    pub fn sys_alarm(&self, seconds: u32) -> SyscallResult {
+        let new = ITimerVal {
+            it_interval: TimeVal::ZERO,
+            it_value: Duration::from_secs(seconds as u64).into(),
+        };
+        let left: Duration = self.task.set_itimer(ITIMER_REAL, new).it_value.into();
+        // rounded to the nearest second, but a pending alarm is never reported as
+        // none
+        let secs = left.as_secs() + (left.subsec_micros() >= 500_000) as u64;
+        if secs == 0 && !left.is_zero() {
+            Ok(1)
+        } else {
+            Ok(secs as usize)
         }
+    }
+
+    /// timer_create() creates a POSIX timer of the process on `clockid`, which
+    /// notifies its expirations as `sevp` tells, or by `SIGALRM` with the
+    /// timer id as value if it is null, and stores the id of the timer in
+    /// `timerid`. The timer is created disarmed.
+    pub fn sys_timer_create(
+        &self,
+        clockid: usize,
+        sevp: UserReadPtr<SigEvent>,
+        timerid: UserWritePtr<i32>,
+    ) -> SyscallResult {
+        let task = self.task;
+        let event = match sevp.is_null() {
+            true => None,
+            false => Some(sevp.read(&task)?),
+        };
+        let id = task.create_posix_timer(clockid, event)?;
+        log::info!("[sys_timer_create] timer {id}, clockid: {clockid}, event: {event:?}");
+        if let Err(err) = timerid.write(&task, id as i32) {
+            task.delete_posix_timer(id)?;
+            return Err(err);
+        }
+        Ok(0)
+    }
+
+    /// timer_settime() arms or, with a zero `it_value`, disarms the timer
+    /// `timerid`, and stores the setting before in `old_value` if not null.
+    /// With `TIMER_ABSTIME` in `flags`, `it_value` is a time of the clock of
+    /// the timer rather than relative to now.
+    pub fn sys_timer_settime(
+        &self,
+        timerid: usize,
+        flags: usize,
+        new_value: UserReadPtr<ITimerSpec>,
+        old_value: UserWritePtr<ITimerSpec>,
+    ) -> SyscallResult {
+        if flags & !TIMER_ABSTIME != 0 {
+            return Err(SysError::EINVAL);
+        }
+        let task = self.task;
+        let new_value = new_value.read(&task)?;
+        log::info!("[sys_timer_settime] timer {timerid}, flags: {flags}, new: {new_value:?}");
+        let old = task.set_posix_timer(timerid, flags == TIMER_ABSTIME, new_value)?;
+        if old_value.not_null() {
+            old_value.write(&task, old)?;
+        }
+        Ok(0)
+    }
+
+    /// timer_gettime() stores the time until the next expiration of the timer
+    /// `timerid` and its interval in `curr_value`.
+    pub fn sys_timer_gettime(
+        &self,
+        timerid: usize,
+        curr_value: UserWritePtr<ITimerSpec>,
+    ) -> SyscallResult {
+        let task = self.task;
+        let curr = task.get_posix_timer(timerid)?;
+        curr_value.write(&task, curr)?;
+        Ok(0)
+    }
+
+    /// timer_getoverrun() returns the expirations of the timer `timerid`
+    /// missed while the signal of its last notified one was pending.
+    pub fn sys_timer_getoverrun(&self, timerid: usize) -> SyscallResult {
+        let overrun = self.task.posix_timer_overrun(timerid)?;
+        Ok(overrun as usize)
+    }
+
+    /// timer_delete() deletes the timer `timerid`, disarming it. A signal of
+    /// it already pending stays pending.
+    pub fn sys_timer_delete(&self, timerid: usize) -> SyscallResult {
+        self.task.delete_posix_timer(timerid)?;
         Ok(0)
     }
 }
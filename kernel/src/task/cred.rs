@@ -0,0 +1,249 @@
+//! Process credentials, see credentials(7).
+
+use alloc::vec::Vec;
+
+use vfs_core::{InodeMode, InodeType, Stat};
+
+pub type Uid = u32;
+pub type Gid = u32;
+
+pub const ROOT_UID: Uid = 0;
+
+/// Maximum number of supplementary groups.
+pub const NGROUPS_MAX: usize = 65536;
+
+bitflags! {
+    // Defined in <unistd.h>.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct AccessMode: u32 {
+        /// Test for read permission.
+        const R_OK = 4;
+        /// Test for write permission.
+        const W_OK = 2;
+        /// Test for execute permission.
+        const X_OK = 1;
+    }
+}
+
+bitflags! {
+    /// Capabilities, see capabilities(7). Each bit is the capability of that
+    /// number.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Capabilities: u64 {
+        /// Change the owner and group of files.
+        const CHOWN = 1 << 0;
+        /// Bypass permission checks of files.
+        const DAC_OVERRIDE = 1 << 1;
+        /// Bypass read and search permission checks of files.
+        const DAC_READ_SEARCH = 1 << 2;
+        /// Bypass the checks that the file is owned by the caller.
+        const FOWNER = 1 << 3;
+        /// Keep the set-user-ID and set-group-ID bits of changed files.
+        const FSETID = 1 << 4;
+        /// Send signals to processes of other users.
+        const KILL = 1 << 5;
+        /// Change group IDs and supplementary groups.
+        const SETGID = 1 << 6;
+        /// Change user IDs.
+        const SETUID = 1 << 7;
+        /// Add any capability of the bounding set to the inheritable set.
+        const SETPCAP = 1 << 8;
+        const LINUX_IMMUTABLE = 1 << 9;
+        /// Bind sockets to ports below 1024.
+        const NET_BIND_SERVICE = 1 << 10;
+        const NET_BROADCAST = 1 << 11;
+        const NET_ADMIN = 1 << 12;
+        const NET_RAW = 1 << 13;
+        const IPC_LOCK = 1 << 14;
+        const IPC_OWNER = 1 << 15;
+        const SYS_MODULE = 1 << 16;
+        const SYS_RAWIO = 1 << 17;
+        const SYS_CHROOT = 1 << 18;
+        const SYS_PTRACE = 1 << 19;
+        const SYS_PACCT = 1 << 20;
+        /// Mount file systems and a range of other administration.
+        const SYS_ADMIN = 1 << 21;
+        const SYS_BOOT = 1 << 22;
+        /// Lower nice values and change priorities of processes of other users.
+        const SYS_NICE = 1 << 23;
+        const SYS_RESOURCE = 1 << 24;
+        const SYS_TIME = 1 << 25;
+        const SYS_TTY_CONFIG = 1 << 26;
+        const MKNOD = 1 << 27;
+        const LEASE = 1 << 28;
+        const AUDIT_WRITE = 1 << 29;
+        const AUDIT_CONTROL = 1 << 30;
+        const SETFCAP = 1 << 31;
+        const MAC_OVERRIDE = 1 << 32;
+        const MAC_ADMIN = 1 << 33;
+        const SYSLOG = 1 << 34;
+        const WAKE_ALARM = 1 << 35;
+        const BLOCK_SUSPEND = 1 << 36;
+        const AUDIT_READ = 1 << 37;
+        const PERFMON = 1 << 38;
+        const BPF = 1 << 39;
+        const CHECKPOINT_RESTORE = 1 << 40;
+    }
+}
+
+impl Capabilities {
+    /// Capabilities which follow the filesystem user ID.
+    pub const FS_MASK: Self = Self::CHOWN
+        .union(Self::DAC_OVERRIDE)
+        .union(Self::DAC_READ_SEARCH)
+        .union(Self::FOWNER)
+        .union(Self::FSETID)
+        .union(Self::LINUX_IMMUTABLE)
+        .union(Self::MKNOD)
+        .union(Self::MAC_OVERRIDE);
+}
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    /// Real user ID.
+    pub uid: Uid,
+    /// Effective user ID, used for most permission checks.
+    pub euid: Uid,
+    /// Saved set-user-ID.
+    pub suid: Uid,
+    /// Filesystem user ID.
+    pub fsuid: Uid,
+    /// Real group ID.
+    pub gid: Gid,
+    /// Effective group ID.
+    pub egid: Gid,
+    /// Saved set-group-ID.
+    pub sgid: Gid,
+    /// Filesystem group ID.
+    pub fsgid: Gid,
+    /// Supplementary group IDs, sorted and without duplicates.
+    pub groups: Vec<Gid>,
+    /// Capabilities checked for privileged operations.
+    pub cap_effective: Capabilities,
+    /// Capabilities the process may make effective.
+    pub cap_permitted: Capabilities,
+    /// Capabilities kept as permitted across execve by a root process.
+    pub cap_inheritable: Capabilities,
+}
+
+impl Credentials {
+    pub const fn root() -> Self {
+        Self {
+            uid: ROOT_UID,
+            euid: ROOT_UID,
+            suid: ROOT_UID,
+            fsuid: ROOT_UID,
+            gid: 0,
+            egid: 0,
+            sgid: 0,
+            fsgid: 0,
+            groups: Vec::new(),
+            cap_effective: Capabilities::all(),
+            cap_permitted: Capabilities::all(),
+            cap_inheritable: Capabilities::empty(),
+        }
+    }
+
+    /// Whether `cap` is an effective capability.
+    pub fn has_cap(&self, cap: Capabilities) -> bool {
+        self.cap_effective.contains(cap)
+    }
+
+    /// Real, effective, saved and filesystem user IDs.
+    pub fn uids(&self) -> [Uid; 4] {
+        [self.uid, self.euid, self.suid, self.fsuid]
+    }
+
+    /// Adjust the capabilities after the user IDs changed from `old`, see
+    /// "Effect of user ID changes on capabilities" in capabilities(7).
+    pub fn update_caps(&mut self, old: [Uid; 4]) {
+        let [old_uid, old_euid, old_suid, old_fsuid] = old;
+        let had_root = [old_uid, old_euid, old_suid].contains(&ROOT_UID);
+        if had_root && ![self.uid, self.euid, self.suid].contains(&ROOT_UID) {
+            self.cap_permitted = Capabilities::empty();
+            self.cap_effective = Capabilities::empty();
+        }
+        if old_euid == ROOT_UID && self.euid != ROOT_UID {
+            self.cap_effective = Capabilities::empty();
+        } else if old_euid != ROOT_UID && self.euid == ROOT_UID {
+            self.cap_effective = self.cap_permitted;
+        }
+        if old_fsuid == ROOT_UID && self.fsuid != ROOT_UID {
+            self.cap_effective -= Capabilities::FS_MASK;
+        } else if old_fsuid != ROOT_UID && self.fsuid == ROOT_UID {
+            self.cap_effective |= self.cap_permitted & Capabilities::FS_MASK;
+        }
+    }
+
+    /// Compute the capabilities across execve. There are no file capabilities
+    /// nor bounding set, so a root process gets all capabilities permitted, and
+    /// effective if the effective user ID is root, while any other process
+    /// gets none.
+    pub fn exec_caps(&mut self) {
+        if self.uid == ROOT_UID || self.euid == ROOT_UID {
+            self.cap_permitted = Capabilities::all();
+        } else {
+            self.cap_permitted = Capabilities::empty();
+        }
+        self.cap_effective = if self.euid == ROOT_UID {
+            self.cap_permitted
+        } else {
+            Capabilities::empty()
+        };
+    }
+
+    /// Whether `gid` is the filesystem group ID or one of the supplementary
+    /// groups.
+    pub fn in_group(&self, gid: Gid) -> bool {
+        self.fsgid == gid || self.groups.binary_search(&gid).is_ok()
+    }
+
+    /// Whether the filesystem user ID owns the file of `stat`, or the process
+    /// has `CAP_FOWNER`.
+    pub fn owns(&self, stat: &Stat) -> bool {
+        self.has_cap(Capabilities::FOWNER) || self.fsuid == stat.st_uid
+    }
+
+    /// Check whether this process is allowed to access the file of `stat`
+    /// with `mode`, as the filesystem user and group IDs.
+    pub fn may_access(&self, stat: &Stat, mode: AccessMode) -> bool {
+        let dac_override = self.has_cap(Capabilities::DAC_OVERRIDE);
+        Self::check_access(
+            self.fsuid,
+            self.fsgid,
+            &self.groups,
+            dac_override,
+            stat,
+            mode,
+        )
+    }
+
+    /// Check whether a user of `uid`, `gid` and the supplementary `groups` is
+    /// allowed to access the file of `stat` with `mode`.
+    ///
+    /// With `dac_override`, it may read and write anything, and may execute a
+    /// file if any of its execute bits is set.
+    pub fn check_access(
+        uid: Uid,
+        gid: Gid,
+        groups: &[Gid],
+        dac_override: bool,
+        stat: &Stat,
+        mode: AccessMode,
+    ) -> bool {
+        if dac_override {
+            let itype = InodeMode::from_bits_truncate(stat.st_mode).to_type();
+            return !mode.contains(AccessMode::X_OK)
+                || itype == InodeType::Dir
+                || stat.st_mode & 0o111 != 0;
+        }
+        let perm = if uid == stat.st_uid {
+            stat.st_mode >> 6
+        } else if gid == stat.st_gid || groups.binary_search(&stat.st_gid).is_ok() {
+            stat.st_mode >> 3
+        } else {
+            stat.st_mode
+        };
+        AccessMode::from_bits_truncate(perm).contains(mode)
+    }
+}
@@ -3,14 +3,15 @@ use alloc::{
     sync::{Arc, Weak},
     vec::Vec,
 };
+use core::time::Duration;
 
 use config::process::INIT_PROC_PID;
 use hashbrown::HashMap;
 use spin::Lazy;
 use sync::mutex::SpinNoIrqLock;
-use systype::SysResult;
+use systype::{SysError, SysResult};
 
-use super::{task::Task, PGid, Tid};
+use super::{task::Task, PGid, Pid, Tid};
 
 pub static TASK_MANAGER: Lazy<TaskManager> = Lazy::new(TaskManager::new);
 
@@ -64,44 +65,276 @@ impl TaskManager {
     }
 }
 
+/// CPU time and peak memory of a process group or a session.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuAcct {
+    pub utime: Duration,
+    pub stime: Duration,
+    /// Peak number of resident pages of all members together.
+    pub peak_rss: usize,
+}
+
+impl CpuAcct {
+    fn add_time(&mut self, (utime, stime): (Duration, Duration)) {
+        self.utime += utime;
+        self.stime += stime;
+    }
+
+    fn add(&mut self, other: &CpuAcct) {
+        self.add_time((other.utime, other.stime));
+    }
+}
+
+/// Accounting of a process group or a session, as listed in procfs.
+#[derive(Clone, Copy, Debug)]
+pub struct AcctEntry {
+    /// Process group or session id.
+    pub id: Pid,
+    /// Session of a process group.
+    pub sid: Pid,
+    /// Processes in a group, or groups in a session.
+    pub members: usize,
+    pub acct: CpuAcct,
+}
+
+pub struct ProcessGroup {
+    sid: Pid,
+    members: Vec<Weak<Task>>,
+    /// Time of the processes that left this group, up to when they left.
+    acct: CpuAcct,
+}
+
+struct ProcessGroups {
+    groups: BTreeMap<PGid, ProcessGroup>,
+    /// Time of the process groups that ended, by session id.
+    sessions: BTreeMap<Pid, CpuAcct>,
+}
+
 /// PGid -> Process group
+///
+/// CPU time rolls up from threads into their process, and from processes
+/// into their group and session. A member counts its time since it joined,
+/// and when it leaves, by exiting or by moving to another group, that time
+/// is transferred into the group, so that nothing is counted twice. The live
+/// members are only summed up when the accounting is read.
+///
+/// Resident pages are sampled when a member exits and when the accounting is
+/// read, so the peak misses spikes in between.
 // TODO: process group should be created by shell forking, but how do we
 // recognize a shell? may be by sid, which will introduce session in extra.
-pub struct ProcessGroupManager(SpinNoIrqLock<BTreeMap<PGid, Vec<Weak<Task>>>>);
+pub struct ProcessGroupManager(SpinNoIrqLock<ProcessGroups>);
+
+/// Time of `process` since it joined its group.
+fn time_in_group(process: &Arc<Task>) -> (Duration, Duration) {
+    let (utime, stime) = process.get_process_ustime();
+    let (base_utime, base_stime) = process.with_thread_group(|tg| tg.group_base);
+    (
+        utime.saturating_sub(base_utime),
+        stime.saturating_sub(base_stime),
+    )
+}
+
+fn resident_pages(processes: &[Arc<Task>]) -> usize {
+    processes
+        .iter()
+        .map(|process| process.with_memory_space(|m| m.resident_pages()))
+        .sum()
+}
+
+impl ProcessGroups {
+    /// Take `process` out of its current group, transferring its time there.
+    /// Returns the session of the group, if it was a member of one.
+    fn detach(&mut self, process: &Arc<Task>) -> Option<Pid> {
+        let pgid = process.pgid();
+        let group = self.groups.get_mut(&pgid)?;
+        let len = group.members.len();
+        group
+            .members
+            .retain(|task| task.upgrade().map_or(false, |t| !Arc::ptr_eq(process, &t)));
+        if group.members.len() == len {
+            return None;
+        }
+        let sid = group.sid;
+        group.acct.add_time(time_in_group(process));
+        if group.members.is_empty() {
+            let group = self.groups.remove(&pgid).unwrap();
+            self.sessions.entry(sid).or_default().add(&group.acct);
+        }
+        Some(sid)
+    }
+
+    /// Put `process` into the group `pgid`, counting its time from now.
+    fn attach(&mut self, pgid: PGid, sid: Pid, process: &Arc<Task>) {
+        process.set_pgid(pgid);
+        let now = process.get_process_ustime();
+        process.with_mut_thread_group(|tg| tg.group_base = now);
+        self.groups
+            .entry(pgid)
+            .or_insert_with(|| ProcessGroup {
+                sid,
+                members: Vec::new(),
+                acct: CpuAcct::default(),
+            })
+            .members
+            .push(Arc::downgrade(process));
+    }
+
+    fn is_member(&self, pgid: PGid, process: &Arc<Task>) -> bool {
+        process.pgid() == pgid
+            && self.groups.get(&pgid).map_or(false, |group| {
+                group
+                    .members
+                    .iter()
+                    .any(|task| task.as_ptr() == Arc::as_ptr(process))
+            })
+    }
+
+    fn has_session(&self, sid: Pid) -> bool {
+        self.groups.values().any(|group| group.sid == sid)
+    }
+}
 
 impl ProcessGroupManager {
     pub const fn new() -> Self {
-        Self(SpinNoIrqLock::new(BTreeMap::new()))
+        Self(SpinNoIrqLock::new(ProcessGroups {
+            groups: BTreeMap::new(),
+            sessions: BTreeMap::new(),
+        }))
     }
 
+    /// Make `group_leader` the leader of a new group in its session, or of the
+    /// session of its own if it is in no group, as init is.
     pub fn add_group(&self, group_leader: &Arc<Task>) {
         let pgid = group_leader.tid();
-        group_leader.set_pgid(pgid);
-        let mut group = Vec::new();
-        group.push(Arc::downgrade(group_leader));
-        self.0.lock().insert(pgid, group);
+        let mut inner = self.0.lock();
+        if inner.is_member(pgid, group_leader) {
+            return;
+        }
+        let sid = inner.detach(group_leader).unwrap_or(pgid);
+        inner.attach(pgid, sid, group_leader);
     }
 
+    /// Put `process` into the existing group `pgid`.
     pub fn add_process(&self, pgid: PGid, process: &Arc<Task>) {
         if !process.is_leader() {
             log::warn!("[ProcessGroupManager::add_process] try adding task that is not a process");
             return;
         }
-        process.set_pgid(pgid);
         let mut inner = self.0.lock();
-        let vec = inner.get_mut(&pgid).unwrap();
-        vec.push(Arc::downgrade(process));
+        if inner.is_member(pgid, process) {
+            return;
+        }
+        inner.detach(process);
+        let sid = inner.groups.get(&pgid).unwrap().sid;
+        inner.attach(pgid, sid, process);
     }
 
-    pub fn get_group(&self, pgid: PGid) -> Option<Vec<Weak<Task>>> {
-        self.0.lock().get(&pgid).cloned()
+    /// Make `process` the leader of a new session and of a new group in it.
+    /// A group leader can not, since its group would be left in another
+    /// session.
+    pub fn add_session(&self, process: &Arc<Task>) -> SysResult<Pid> {
+        let sid = process.pid();
+        let mut inner = self.0.lock();
+        if inner.groups.contains_key(&sid) || inner.has_session(sid) {
+            return Err(SysError::EPERM);
+        }
+        inner.detach(process);
+        inner.attach(sid, sid, process);
+        Ok(sid)
     }
 
-    pub fn remove(&self, process: &Arc<Task>) {
+    pub fn get_group(&self, pgid: PGid) -> Option<Vec<Weak<Task>>> {
         self.0
             .lock()
-            .get_mut(&process.pgid())
-            .unwrap()
-            .retain(|task| task.upgrade().map_or(false, |t| !Arc::ptr_eq(process, &t)))
+            .groups
+            .get(&pgid)
+            .map(|group| group.members.clone())
+    }
+
+    /// Session of the group `pgid`.
+    pub fn sid(&self, pgid: PGid) -> Option<Pid> {
+        self.0.lock().groups.get(&pgid).map(|group| group.sid)
+    }
+
+    /// Remove a reaped process, whose time is final.
+    pub fn remove(&self, process: &Arc<Task>) {
+        let members = self.live_members(process.pgid());
+        let rss = resident_pages(&members);
+        let mut inner = self.0.lock();
+        if let Some(group) = inner.groups.get_mut(&process.pgid()) {
+            group.acct.peak_rss = group.acct.peak_rss.max(rss);
+        }
+        inner.detach(process);
+    }
+
+    fn live_members(&self, pgid: PGid) -> Vec<Arc<Task>> {
+        self.get_group(pgid)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    /// Accounting of the process groups and of the sessions, both by id.
+    pub fn accts(&self) -> (Vec<AcctEntry>, Vec<AcctEntry>) {
+        let pgids: Vec<PGid> = self.0.lock().groups.keys().copied().collect();
+        let mut groups = Vec::new();
+        for pgid in pgids {
+            let members = self.live_members(pgid);
+            let rss = resident_pages(&members);
+            let mut live = CpuAcct::default();
+            for process in members.iter() {
+                live.add_time(time_in_group(process));
+            }
+            let mut inner = self.0.lock();
+            let Some(group) = inner.groups.get_mut(&pgid) else {
+                continue;
+            };
+            group.acct.peak_rss = group.acct.peak_rss.max(rss);
+            live.add(&group.acct);
+            live.peak_rss = group.acct.peak_rss;
+            groups.push((
+                AcctEntry {
+                    id: pgid,
+                    sid: group.sid,
+                    members: members.len(),
+                    acct: live,
+                },
+                rss,
+            ));
+        }
+
+        let mut inner = self.0.lock();
+        let mut sessions: BTreeMap<Pid, (AcctEntry, usize)> = BTreeMap::new();
+        for (group, rss) in groups.iter() {
+            let (session, session_rss) = sessions.entry(group.sid).or_insert_with(|| {
+                let ended = inner.sessions.get(&group.sid).copied().unwrap_or_default();
+                let entry = AcctEntry {
+                    id: group.sid,
+                    sid: group.sid,
+                    members: 0,
+                    acct: ended,
+                };
+                (entry, 0)
+            });
+            session.members += 1;
+            session.acct.add(&group.acct);
+            *session_rss += rss;
+        }
+        // the time of a session without groups is no longer needed
+        inner.sessions.retain(|sid, _| sessions.contains_key(sid));
+        let sessions = sessions
+            .into_values()
+            .map(|(mut session, rss)| {
+                let ended = inner.sessions.entry(session.id).or_default();
+                ended.peak_rss = ended.peak_rss.max(rss);
+                session.acct.peak_rss = ended.peak_rss;
+                session
+            })
+            .collect();
+        (
+            groups.into_iter().map(|(group, _)| group).collect(),
+            sessions,
+        )
     }
 }
@@ -1,5 +1,7 @@
 pub mod aux;
+pub mod cred;
 mod manager;
+pub mod perf;
 pub mod resource;
 mod schedule;
 pub mod signal;
@@ -10,19 +12,37 @@ use alloc::{string::ToString, vec, vec::Vec};
 
 use async_utils::block_on;
 use config::process::USER_STACK_SIZE;
-pub use manager::{PROCESS_GROUP_MANAGER, TASK_MANAGER};
+pub use manager::{AcctEntry, PROCESS_GROUP_MANAGER, TASK_MANAGER};
 pub use schedule::{spawn_kernel_task, spawn_user_task};
+use systype::SysResult;
 pub use task::Task;
 pub use tid::{PGid, Pid, Tid, TID_ALLOCATOR};
 use vfs::sys_root_dentry;
 use vfs_core::{OpenFlags, Path};
 
 use crate::{
-    mm::memory_space::{init_stack, MemorySpace},
+    mm::memory_space::{init_stack, read_elf_headers, DenyWrite, MemorySpace},
     processor::env::within_sum,
     trap::TrapContext,
 };
 
+/// Spawn the init process, which runs once the boot hart starts to fetch
+/// tasks, after all initcalls.
+fn init_proc() -> SysResult<()> {
+    spawn_kernel_task(async move {
+        spawn_init_proc();
+    });
+    Ok(())
+}
+systype::late_initcall!(init_proc);
+
+/// Spawn the flusher, which writes back dirty pages in the background.
+fn spawn_flusher() -> SysResult<()> {
+    spawn_kernel_task(vfs::flusher());
+    Ok(())
+}
+systype::late_initcall!(spawn_flusher);
+
 pub fn spawn_init_proc() {
     #[cfg(not(feature = "final2"))]
     let init_proc_path = "/init_proc";
@@ -36,9 +56,10 @@ pub fn spawn_init_proc() {
         .unwrap()
         .open()
         .unwrap();
-    let elf_data = block_on(async { file.read_all().await }).unwrap();
+    let elf_data = block_on(read_elf_headers(&file)).unwrap();
 
     let mut memory_space = MemorySpace::new_user();
+    memory_space.set_exe(DenyWrite::new(file.inode()).unwrap());
     unsafe { memory_space.switch_page_table() };
     let (entry, auxv) = memory_space.parse_and_map_elf(file.clone(), &elf_data);
     let sp_init = memory_space.alloc_stack_lazily(USER_STACK_SIZE);
@@ -48,6 +69,18 @@ pub fn spawn_init_proc() {
     let trap_context = TrapContext::new(entry, sp);
 
     let task = Task::new_init(memory_space, trap_context, file, args);
+    // stdin, stdout and stderr of init, inherited by every other task
+    let tty = Path::new(sys_root_dentry(), sys_root_dentry(), "/dev/tty")
+        .walk(OpenFlags::empty())
+        .unwrap()
+        .open()
+        .unwrap();
+    tty.set_flags(OpenFlags::O_RDWR);
+    task.with_mut_fd_table(|table| {
+        for _ in 0..3 {
+            table.alloc(tty.clone(), OpenFlags::empty()).unwrap();
+        }
+    });
     schedule::spawn_user_task(task);
 }
 
@@ -0,0 +1,244 @@
+//! Hardware performance counters of tasks, opened by `perf_event_open`.
+//!
+//! The `cycle` and `instret` counters of a hart are sampled when a task is
+//! switched in and out, and the differences are accumulated in the
+//! [`PerfStat`] of the task, so that a task counts only what it executed
+//! itself, including the syscalls and traps it takes.
+
+use alloc::{boxed::Box, sync::Arc};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use arch::time::{get_cycle, get_instret};
+use async_trait::async_trait;
+use strum::FromRepr;
+use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{arc_zero, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, OpenFlags};
+
+use crate::processor::hart::current_task_ref;
+
+/// `type` of `struct perf_event_attr`.
+pub const PERF_TYPE_HARDWARE: u32 = 0;
+
+/// `disabled` bit of the flags of `struct perf_event_attr`, the event starts
+/// disabled.
+pub const PERF_ATTR_DISABLED: u64 = 1 << 0;
+
+pub const PERF_EVENT_IOC_ENABLE: usize = 0x2400;
+pub const PERF_EVENT_IOC_DISABLE: usize = 0x2401;
+pub const PERF_EVENT_IOC_RESET: usize = 0x2403;
+
+/// Head of `struct perf_event_attr`, up to the flags, which is all we look at.
+/// Later fields are ignored whatever `size` says.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct PerfEventAttr {
+    pub type_: u32,
+    /// Size of the whole structure as known by the user.
+    pub size: u32,
+    pub config: u64,
+    pub sample_period: u64,
+    pub sample_type: u64,
+    pub read_format: u64,
+    /// `disabled`, `inherit`, `pinned` and so on, as bits.
+    pub flags: u64,
+}
+
+/// Generalized hardware events, `config` of `PERF_TYPE_HARDWARE`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromRepr)]
+#[repr(u64)]
+pub enum PerfHwEvent {
+    CpuCycles = 0,
+    Instructions = 1,
+}
+
+impl PerfHwEvent {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Cycles and instructions executed by a task. It is shared with the perf
+/// event files counting the task, so that they still read after the task is
+/// reaped.
+#[derive(Default)]
+pub struct PerfStat {
+    counts: [AtomicU64; 2],
+    /// Counter values of the hart when the task was switched in.
+    start: [AtomicU64; 2],
+}
+
+impl PerfStat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn hart_counters() -> [u64; 2] {
+        [get_cycle() as u64, get_instret() as u64]
+    }
+
+    pub fn record_switch_in(&self) {
+        for (start, now) in self.start.iter().zip(Self::hart_counters()) {
+            start.store(now, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_switch_out(&self) {
+        let now = Self::hart_counters();
+        for i in 0..now.len() {
+            let start = self.start[i].load(Ordering::Relaxed);
+            self.counts[i].fetch_add(now[i].wrapping_sub(start), Ordering::Relaxed);
+        }
+    }
+
+    /// Accumulate the slice running since the last switch in, of the task
+    /// running on this hart, and start a new one.
+    pub fn record_running(&self) {
+        self.record_switch_out();
+        self.record_switch_in();
+    }
+
+    /// Count of `event` up to now. The slice running since the last switch in
+    /// is included only if the task is running on this hart, i.e. it is the
+    /// caller itself, for a task on another hart it is counted at its next
+    /// switch out.
+    pub fn count(&self, event: PerfHwEvent, running_here: bool) -> u64 {
+        let i = event.index();
+        let count = self.counts[i].load(Ordering::Relaxed);
+        if running_here {
+            let start = self.start[i].load(Ordering::Relaxed);
+            count + Self::hart_counters()[i].wrapping_sub(start)
+        } else {
+            count
+        }
+    }
+}
+
+/// Inode of perf event files, which have neither a path nor a file system,
+/// like an anonymous inode of linux.
+pub struct PerfEventInode {
+    meta: InodeMeta,
+}
+
+impl PerfEventInode {
+    fn new() -> Arc<Self> {
+        let mode = InodeMode::OWNER_READ | InodeMode::OWNER_WRITE;
+        Arc::new(Self {
+            meta: InodeMeta::new(mode, Arc::<usize>::new_uninit(), 0),
+        })
+    }
+}
+
+impl Inode for PerfEventInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+struct PerfEventInner {
+    enabled: bool,
+    /// Counted during the past enabled periods.
+    total: u64,
+    /// Count of the task when the current enabled period began.
+    base: u64,
+}
+
+/// A counting perf event of one task, whose `read` yields the count as a
+/// `u64`.
+pub struct PerfEventFile {
+    meta: FileMeta,
+    event: PerfHwEvent,
+    stat: Arc<PerfStat>,
+    inner: SpinNoIrqLock<PerfEventInner>,
+}
+
+impl PerfEventFile {
+    pub fn new(event: PerfHwEvent, stat: Arc<PerfStat>, enabled: bool) -> Arc<Self> {
+        let file = Arc::new(Self {
+            meta: FileMeta::new(arc_zero(), PerfEventInode::new()),
+            event,
+            stat,
+            inner: SpinNoIrqLock::new(PerfEventInner {
+                enabled: false,
+                total: 0,
+                base: 0,
+            }),
+        });
+        file.set_flags(OpenFlags::O_RDWR);
+        if enabled {
+            file.enable();
+        }
+        file
+    }
+
+    fn task_count(&self) -> u64 {
+        let running_here = Arc::ptr_eq(&self.stat, current_task_ref().perf_stat());
+        self.stat.count(self.event, running_here)
+    }
+
+    pub fn value(&self) -> u64 {
+        let inner = self.inner.lock();
+        if inner.enabled {
+            inner.total + (self.task_count() - inner.base)
+        } else {
+            inner.total
+        }
+    }
+
+    pub fn enable(&self) {
+        let mut inner = self.inner.lock();
+        if !inner.enabled {
+            inner.enabled = true;
+            inner.base = self.task_count();
+        }
+    }
+
+    pub fn disable(&self) {
+        let mut inner = self.inner.lock();
+        if inner.enabled {
+            inner.enabled = false;
+            inner.total += self.task_count() - inner.base;
+        }
+    }
+
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock();
+        inner.total = 0;
+        inner.base = self.task_count();
+    }
+}
+
+#[async_trait]
+impl File for PerfEventFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    /// Every read yields the current count, the offset does not matter.
+    async fn read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
+        let value = self.value().to_ne_bytes();
+        if buf.len() < value.len() {
+            return Err(SysError::ENOSPC);
+        }
+        buf[..value.len()].copy_from_slice(&value);
+        Ok(value.len())
+    }
+
+    async fn write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
+        Err(SysError::EINVAL)
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn ioctl(&self, cmd: usize, _arg: usize) -> SyscallResult {
+        match cmd {
+            PERF_EVENT_IOC_ENABLE => self.enable(),
+            PERF_EVENT_IOC_DISABLE => self.disable(),
+            PERF_EVENT_IOC_RESET => self.reset(),
+            _ => return Err(SysError::ENOTTY),
+        }
+        Ok(0)
+    }
+}
@@ -1,38 +1,80 @@
 use core::time::Duration;
 
+use config::{board, fs::MAX_FDS, process::USER_STACK_SIZE};
+use strum::FromRepr;
+use systype::{RLimit, RLIM_INFINITY, RLIM_NLIMITS};
+use vfs_core::IoBand;
+
 use super::Task;
+use crate::{processor::hotplug, syscall::Resource};
 
 impl Task {
+    /// User and system time of the process, the threads that exited included.
     pub fn get_process_ustime(&self) -> (Duration, Duration) {
         self.with_thread_group(|tg| -> (Duration, Duration) {
             tg.iter()
                 .map(|thread| thread.time_stat().user_system_time())
-                .reduce(|(acc_utime, acc_stime), (utime, stime)| {
-                    (acc_utime + utime, acc_stime + stime)
-                })
-                .unwrap()
+                .fold(
+                    tg.exited_time(),
+                    |(acc_utime, acc_stime), (utime, stime)| (acc_utime + utime, acc_stime + stime),
+                )
         })
     }
 
+    /// User and system time of the children of the process waited for, which
+    /// include those of their own children they waited for.
+    pub fn get_children_ustime(&self) -> (Duration, Duration) {
+        self.with_thread_group(|tg| tg.children_time())
+    }
+
+    /// Account the time of `child`, a zombie being reaped, to the process.
+    pub fn add_children_time(&self, child: &Task) {
+        let (utime, stime) = child.get_process_ustime();
+        let (cutime, cstime) = child.get_children_ustime();
+        self.with_mut_thread_group(|tg| tg.add_children_time((utime + cutime, stime + cstime)));
+    }
+
     pub fn get_process_utime(&self) -> Duration {
-        self.with_thread_group(|tg| -> Duration {
-            tg.iter()
-                .map(|thread| thread.time_stat().user_time())
-                .reduce(|acc_utime, utime| acc_utime + utime)
-                .unwrap()
-        })
+        self.get_process_ustime().0
     }
 
     pub fn get_process_cputime(&self) -> Duration {
-        self.with_thread_group(|tg| -> Duration {
-            tg.iter()
-                .map(|thread| thread.time_stat().cpu_time())
-                .reduce(|acc, cputime| acc + cputime)
-                .unwrap()
+        let (utime, stime) = self.get_process_ustime();
+        utime + stime
+    }
+
+    pub fn rlimit(&self, resource: Resource) -> RLimit {
+        self.with_rlimits(|rlimits| rlimits[resource as usize])
+    }
+
+    /// Limit the fds allocated by every thread of the process, following a new
+    /// soft `RLIMIT_NOFILE`.
+    pub fn set_max_fds(&self, max_fds: usize) {
+        self.with_thread_group(|tg| {
+            for thread in tg.iter() {
+                thread.with_mut_fd_table(|table| table.set_max_fds(max_fds));
+            }
         })
     }
 }
 
+/// Resource limits of the init process, inherited by all the others. Those
+/// not listed are unlimited.
+pub fn init_rlimits() -> [RLimit; RLIM_NLIMITS] {
+    let mut rlimits = [RLimit::new(RLIM_INFINITY); RLIM_NLIMITS];
+    let mut set = |resource: Resource, rlim_cur: usize, rlim_max: usize| {
+        rlimits[resource as usize] = RLimit { rlim_cur, rlim_max };
+    };
+    set(Resource::STACK, USER_STACK_SIZE, RLIM_INFINITY);
+    set(Resource::CORE, 0, RLIM_INFINITY);
+    set(Resource::NOFILE, MAX_FDS, MAX_FDS);
+    set(Resource::MEMLOCK, 8 * 1024 * 1024, 8 * 1024 * 1024);
+    set(Resource::MSGQUEUE, 819200, 819200);
+    set(Resource::NICE, 0, 0);
+    set(Resource::RTPRIO, 0, 0);
+    rlimits
+}
+
 bitflags! {
     #[derive(Clone, Copy)]
     #[repr(C)]
@@ -48,3 +90,116 @@ bitflags! {
         const CPU_ALL = 0b11111111;
     }
 }
+
+impl CpuMask {
+    /// Mask of all harts brought up on this board.
+    pub fn online() -> Self {
+        Self::from_bits_truncate((1 << board::harts()) - 1)
+    }
+
+    /// Mask of the harts running tasks now, i.e. not taken offline.
+    pub fn active() -> Self {
+        let bits = (0..board::harts())
+            .filter(|&hart_id| hotplug::is_hart_active(hart_id))
+            .fold(0, |bits, hart_id| bits | 1 << hart_id);
+        Self::from_bits_truncate(bits)
+    }
+
+    pub fn has_hart(&self, hart_id: usize) -> bool {
+        self.bits() & (1 << hart_id) != 0
+    }
+}
+
+/// Highest priority nice value.
+pub const NICE_MIN: i32 = -20;
+/// Lowest priority nice value.
+pub const NICE_MAX: i32 = 19;
+
+/// Scheduling weight of each nice value, taken from Linux. Each step is about
+/// 1.25 times the next, so one nice level makes a difference of about 10% in
+/// polls when two tasks compete.
+#[rustfmt::skip]
+const NICE_TO_WEIGHT: [usize; 40] = [
+    /* -20 */ 88761, 71755, 56483, 46273, 36291,
+    /* -15 */ 29154, 23254, 18705, 14949, 11916,
+    /* -10 */ 9548, 7620, 6100, 4904, 3906,
+    /*  -5 */ 3121, 2501, 1991, 1586, 1277,
+    /*   0 */ 1024, 820, 655, 526, 423,
+    /*   5 */ 335, 272, 215, 172, 137,
+    /*  10 */ 110, 87, 70, 56, 45,
+    /*  15 */ 36, 29, 23, 18, 15,
+];
+
+/// Scheduling weight of a task with the given nice value, nice 0 maps to
+/// `executor::DEFAULT_WEIGHT`.
+pub fn nice_to_weight(nice: i32) -> usize {
+    NICE_TO_WEIGHT[(nice.clamp(NICE_MIN, NICE_MAX) - NICE_MIN) as usize]
+}
+
+/// Scheduling class of I/O priority, see ioprio_set(2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
+#[repr(u16)]
+pub enum IoPrioClass {
+    /// No class was set, the priority is derived from the nice value.
+    None = 0,
+    /// Real-time, always served first.
+    Rt = 1,
+    /// Best-effort, the default class.
+    Be = 2,
+    /// Served only when no one else needs the disk.
+    Idle = 3,
+}
+
+/// I/O priority of a task, encoded for user space as `class << 13 | level`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoPrio {
+    pub class: IoPrioClass,
+    /// Level within the class, 0 is the highest and 7 the lowest.
+    pub level: u16,
+}
+
+impl IoPrio {
+    const CLASS_SHIFT: u16 = 13;
+    const LEVEL_MASK: u16 = (1 << Self::CLASS_SHIFT) - 1;
+    /// Number of levels of the real-time and best-effort classes.
+    pub const NR_LEVELS: u16 = 8;
+
+    pub const fn default() -> Self {
+        Self {
+            class: IoPrioClass::None,
+            level: 0,
+        }
+    }
+
+    /// Decode and validate a priority passed to ioprio_set(2).
+    pub fn from_raw(raw: u16) -> Option<Self> {
+        let class = IoPrioClass::from_repr(raw >> Self::CLASS_SHIFT)?;
+        let level = raw & Self::LEVEL_MASK;
+        match class {
+            IoPrioClass::None if level != 0 => None,
+            IoPrioClass::Rt | IoPrioClass::Be if level >= Self::NR_LEVELS => None,
+            // The level of idle class is ignored.
+            IoPrioClass::Idle => Some(Self { class, level: 0 }),
+            _ => Some(Self { class, level }),
+        }
+    }
+
+    pub fn to_raw(self) -> u16 {
+        (self.class as u16) << Self::CLASS_SHIFT | self.level
+    }
+
+    /// Band of the block request queue for this priority. Without a class,
+    /// the best-effort level follows the nice value as in Linux.
+    pub fn band(self, nice: i32) -> IoBand {
+        let (class, level) = match self.class {
+            IoPrioClass::None => (IoPrioClass::Be, ((nice + 20) / 5) as u16),
+            class => (class, self.level),
+        };
+        match class {
+            IoPrioClass::Rt => IoBand::Rt,
+            IoPrioClass::Idle => IoBand::Idle,
+            _ if level < Self::NR_LEVELS / 2 => IoBand::BeHigh,
+            _ => IoBand::BeLow,
+        }
+    }
+}
@@ -10,10 +10,13 @@ use arch::time::get_time_duration;
 use async_utils::{get_waker, suspend_now};
 use timer::{Timer, TIMER_MANAGER};
 
-use super::Task;
+use super::{
+    resource::{nice_to_weight, CpuMask},
+    Task,
+};
 use crate::{
     processor::{env::EnvContext, hart},
-    task::{signal::*, task::TaskState::*},
+    task::signal::*,
     trap,
 };
 
@@ -42,6 +45,14 @@ impl<F: Future + Send + 'static> Future for UserTaskFuture<F> {
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = unsafe { self.get_unchecked_mut() };
         let hart = hart::local_hart();
+        // Not allowed on this hart, e.g. the affinity changed while it was queued.
+        // Queued again, it goes to the queue of a hart it is allowed on, see
+        // `Task::pinned_hart`. If every allowed hart is offline, it runs anywhere.
+        let allowed = *this.task.cpus_allowed_ref();
+        if !allowed.has_hart(hart.hart_id()) && allowed.intersects(CpuMask::active()) {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
         hart.enter_user_task_switch(&mut this.task, &mut this.env);
         let ret = unsafe { Pin::new_unchecked(&mut this.future).poll(cx) };
         hart.leave_user_task_switch(&mut this.env);
@@ -79,28 +90,28 @@ impl<F: Future<Output = ()> + Send + 'static> Future for KernelTaskFuture<F> {
 pub async fn task_loop(task: Arc<Task>) {
     *task.waker() = Some(get_waker().await);
     loop {
-        match task.state() {
-            Terminated => break,
-            Stopped => suspend_now().await,
-            _ => {}
+        if task.is_terminated() {
+            break;
+        }
+        // the thread group is stopped, wait for `SIGCONT` or `SIGKILL`, which is
+        // handled before going back to user
+        if task.park_while_stopped().await {
+            do_signal(&task, false).expect("do signal error");
+            continue;
         }
 
         trap::user_trap::trap_return(&task);
 
         // task may be set to terminated by other task, e.g. execve will kill other
         // tasks in the same thread group
-        match task.state() {
-            Terminated => break,
-            Stopped => suspend_now().await,
-            _ => {}
+        if task.is_terminated() {
+            break;
         }
 
         let intr = trap::user_trap::trap_handler(&task).await;
 
-        match task.state() {
-            Terminated => break,
-            Stopped => suspend_now().await,
-            _ => {}
+        if task.is_terminated() {
+            break;
         }
         do_signal(&task, intr).expect("do signal error");
     }
@@ -111,8 +122,17 @@ pub async fn task_loop(task: Arc<Task>) {
 
 /// Spawn a new async user task
 pub fn spawn_user_task(user_task: Arc<Task>) {
+    // Weak, the schedule function lives as long as the waker held by the task
+    let weak = Arc::downgrade(&user_task);
+    let weight = move || {
+        weak.upgrade().map_or(executor::DEFAULT_WEIGHT, |task| {
+            nice_to_weight(*task.nice_ref())
+        })
+    };
+    let weak = Arc::downgrade(&user_task);
+    let hart = move || weak.upgrade().and_then(|task| task.pinned_hart());
     let future = UserTaskFuture::new(user_task.clone(), task_loop(user_task));
-    let (runnable, task) = executor::spawn(future);
+    let (runnable, task) = executor::spawn_pinned(future, weight, hart);
     runnable.schedule();
     task.detach();
 }
@@ -127,6 +147,24 @@ pub fn spawn_kernel_task<F: Future<Output = ()> + Send + 'static>(kernel_task: F
 }
 
 impl Task {
+    /// The hart to queue this task on if it may not run on every active hart:
+    /// the local hart if it is allowed, or else the first allowed one. `None`
+    /// if it runs anywhere, which it also does when every allowed hart is
+    /// offline.
+    pub fn pinned_hart(&self) -> Option<usize> {
+        let active = CpuMask::active();
+        let allowed = *self.cpus_allowed_ref() & active;
+        if allowed.is_empty() || allowed.bits() == active.bits() {
+            return None;
+        }
+        let hart_id = hart::local_hart().hart_id();
+        if allowed.has_hart(hart_id) {
+            Some(hart_id)
+        } else {
+            Some(allowed.bits().trailing_zeros() as usize)
+        }
+    }
+
     /// 返回值代表的是条件满足时，还剩余多少Duration。如果剩余的 Duration 为
     /// 0，说明就是超时了，大于 0 才是因事件唤醒
     pub async fn suspend_timeout(&self, limit: Duration) -> Duration {
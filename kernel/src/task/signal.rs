@@ -12,12 +12,17 @@ use core::{
 };
 
 use arch::time::get_time_duration;
+use async_utils::suspend_now;
 use signal::*;
-use systype::SysResult;
-use timer::{Timer, TimerEvent};
+use systype::{SysError, SysResult};
+use time::{
+    timespec::ITimerSpec, timeval::ITimerVal, CLOCK_DEVIATION, CLOCK_MONOTONIC, CLOCK_REALTIME,
+};
+use timer::{Timer, TimerEvent, TIMER_MANAGER};
+use vfs_core::FileOwner;
 
 use super::Task;
-use crate::mm::UserWritePtr;
+use crate::{mm::UserWritePtr, trap::TrapContext};
 
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
@@ -61,7 +66,24 @@ impl Task {
     /// not blocking the signal
     /// A thread-directed signal is targeted at
     /// (i.e., delivered to) a specific thread.
-    pub fn receive_siginfo(&self, si: SigInfo, thread_directed: bool) {
+    ///
+    /// Job control takes effect when the signal is sent, like linux: SIGCONT
+    /// continues the stopped process even if blocked or ignored, and discards
+    /// the stop signals pending, which a stop signal does to SIGCONT in turn.
+    pub fn receive_siginfo(self: &Arc<Self>, si: SigInfo, thread_directed: bool) {
+        // signal 0 only checks that the receiver exists
+        if si.sig.raw() == 0 {
+            return;
+        }
+        if si.sig == Sig::SIGCONT {
+            self.continue_group();
+        } else if SigSet::STOP_MASK.contain_signal(si.sig) {
+            self.with_thread_group(|tg| {
+                for t in tg.iter() {
+                    t.with_mut_sig_pending(|pending| pending.discard(SigSet::SIGCONT));
+                }
+            });
+        }
         match thread_directed {
             false => {
                 debug_assert!(self.is_leader());
@@ -95,7 +117,9 @@ impl Task {
         );
         self.with_mut_sig_pending(|pending| {
             pending.add(si);
-            if pending.should_wake.contain_signal(si.sig) && self.is_interruptable() {
+            if pending.should_wake.contain_signal(si.sig)
+                && (self.is_interruptable() || self.is_stopped())
+            {
                 log::info!("[Task::recv] tid {} has been woken", self.tid());
                 self.wake();
             } else {
@@ -109,15 +133,171 @@ impl Task {
         });
     }
 
+    /// Stop the thread group, whose threads park before going back to user,
+    /// and tell the parent.
+    fn stop_group(self: &Arc<Self>, sig: Sig) {
+        if self.with_mut_thread_group(|tg| tg.stop(sig)) {
+            log::info!("[Task::stop_group] pid {} stopped by {sig:?}", self.pid());
+            self.notify_parent(SigInfo::CLD_STOPPED, sig);
+        }
+    }
+
+    /// Continue the thread group if stopped, waking the threads parked, and
+    /// tell the parent. The stop signals pending are discarded.
+    fn continue_group(self: &Arc<Self>) {
+        let continued = self.with_mut_thread_group(|tg| {
+            for t in tg.iter() {
+                t.with_mut_sig_pending(|pending| pending.discard(SigSet::STOP_MASK));
+            }
+            let continued = tg.cont();
+            if continued {
+                for t in tg.iter().filter(|t| t.is_stopped()) {
+                    t.wake();
+                }
+            }
+            continued
+        });
+        if continued {
+            log::info!("[Task::continue_group] pid {} continued", self.pid());
+            self.notify_parent(SigInfo::CLD_CONTINUED, Sig::SIGCONT);
+        }
+    }
+
+    /// Terminate all the threads of the group with the wait status
+    /// `exit_code`, unless the group is exiting already. The other threads get
+    /// `SIGKILL`, which interrupts their syscalls, and those sleeping or
+    /// stopped are woken to exit.
+    pub fn exit_group(self: &Arc<Self>, exit_code: i32) {
+        self.with_mut_thread_group(|tg| {
+            if !tg.exit() {
+                self.set_terminated();
+                return;
+            }
+            for t in tg.iter() {
+                let sleeping = t.is_interruptable() || t.is_stopped();
+                t.set_terminated();
+                if t.tid() == self.tid() {
+                    continue;
+                }
+                t.with_mut_sig_pending(|pending| {
+                    pending.add(SigInfo {
+                        sig: Sig::SIGKILL,
+                        code: SigInfo::KERNEL,
+                        details: SigDetails::None,
+                    })
+                });
+                if sleeping {
+                    t.wake();
+                }
+            }
+            self.leader().set_exit_code(exit_code);
+        });
+    }
+
+    /// Park the task while its thread group is stopped, until the group is
+    /// continued or the task is to be killed. Returns whether the group was
+    /// stopped, in which case the signals pending are to be handled before
+    /// going back to user.
+    pub async fn park_while_stopped(self: &Arc<Self>) -> bool {
+        let mut parked = false;
+        loop {
+            // checked with the group locked, so that a continue either sees the task
+            // stopped and wakes it, or has happened before
+            let stopped = self.with_thread_group(|tg| {
+                let stopped = tg.is_stopped() && !self.is_terminated();
+                if stopped {
+                    self.set_stopped();
+                    self.set_wake_up_signal(SigSet::SIGCONT);
+                }
+                stopped
+            });
+            if !stopped {
+                break;
+            }
+            parked = true;
+            if self.with_sig_pending(|pending| pending.has_expect_signals(SigSet::SIGKILL)) {
+                break;
+            }
+            suspend_now().await;
+        }
+        if parked && self.is_stopped() {
+            self.set_running();
+        }
+        parked
+    }
+
     pub fn set_wake_up_signal(&self, except: SigSet) {
-        debug_assert!(self.is_interruptable());
+        debug_assert!(self.is_interruptable() || self.is_stopped());
         self.with_mut_sig_pending(|pending| {
             pending.should_wake = except | SigSet::SIGKILL | SigSet::SIGSTOP
         })
     }
 
-    fn notify_parent(self: &Arc<Self>, code: i32, _signum: Sig) {
-        let parent = self.parent().unwrap().upgrade().unwrap();
+    /// Whether the task runs on its alternate signal stack, given its user
+    /// stack pointer `sp`.
+    pub fn on_sig_stack(&self, sp: usize) -> bool {
+        self.sig_stack_ref().is_some_and(|stack| stack.contains(sp))
+    }
+
+    /// The alternate signal stack as reported by sigaltstack(2) and saved in
+    /// the ucontext of a handler, given the user stack pointer `sp`.
+    pub fn sig_stack_info(&self, sp: usize) -> SignalStack {
+        match *self.sig_stack_ref() {
+            Some(mut stack) => {
+                if stack.contains(sp) {
+                    stack.ss_flags |= SignalStack::SS_ONSTACK;
+                }
+                stack
+            }
+            None => SignalStack {
+                ss_flags: SignalStack::SS_DISABLE,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Set the alternate signal stack to `ss`, or disable it with
+    /// `SS_DISABLE`, given the user stack pointer `sp`. It can not be changed
+    /// while the task runs on it.
+    pub fn set_sig_stack(&self, ss: SignalStack, sp: usize) -> SysResult<()> {
+        if self.on_sig_stack(sp) {
+            return Err(SysError::EPERM);
+        }
+        match ss.ss_flags & !SignalStack::SS_AUTODISARM {
+            SignalStack::SS_DISABLE => *self.sig_stack() = None,
+            // `SS_ONSTACK` is accepted as 0 for old programs, like linux does
+            0 | SignalStack::SS_ONSTACK => {
+                if ss.ss_size < MINSIGSTKSZ {
+                    return Err(SysError::ENOMEM);
+                }
+                *self.sig_stack() = Some(SignalStack {
+                    ss_flags: ss.ss_flags & SignalStack::SS_AUTODISARM,
+                    ..ss
+                });
+            }
+            _ => return Err(SysError::EINVAL),
+        }
+        Ok(())
+    }
+
+    /// The owner process of a file with `O_ASYNC`, unless it has exited or
+    /// the owner is a process group.
+    pub fn sigio_owner(owner: &FileOwner) -> Option<Arc<Task>> {
+        owner
+            .process
+            .as_ref()?
+            .upgrade()?
+            .downcast::<Task>()
+            .ok()
+            .filter(|task| !task.is_zombie())
+    }
+
+    // NOTE: a parent with `SA_NOCLDSTOP` waiting in wait4 with `WUNTRACED` or
+    // `WCONTINUED` is not woken, since wait4 waits for SIGCHLD
+    fn notify_parent(self: &Arc<Self>, code: i32, signum: Sig) {
+        let Some(parent) = self.leader().parent().and_then(|p| p.upgrade()) else {
+            return;
+        };
         if !parent
             .with_sig_handlers(|handlers| handlers.get(Sig::SIGCHLD))
             .flags
@@ -127,7 +307,10 @@ impl Task {
                 SigInfo {
                     sig: Sig::SIGCHLD,
                     code,
-                    details: SigDetails::None,
+                    details: SigDetails::Child {
+                        pid: self.pid(),
+                        status: signum.raw() as i32,
+                    },
                 },
                 false,
             );
@@ -142,24 +325,48 @@ extern "C" {
 /// Signal dispositions and actions are process-wide: if an unhandled signal is
 /// delivered to a thread, then it will affect (terminate, stop, continue, be
 /// ignored in) all members of the thread group.
+///
+/// `intr` tells whether the task trapped for a syscall interrupted by a signal
+/// and can be restarted, which is restarted unless a handler without
+/// `SA_RESTART` runs. The signals ignored, stopping or continuing the task do
+/// not count, so that the syscall is transparently restarted across them.
 pub fn do_signal(task: &Arc<Task>, mut intr: bool) -> SysResult<()> {
     let old_mask = *task.sig_mask();
     let cx = task.trap_context_mut();
 
-    while let Some(si) = task.with_mut_sig_pending(|pending| pending.dequeue_signal(&old_mask)) {
+    // Signals are left pending while an execve tears the group down, it has
+    // terminated the task, which must not take the whole group, the execve
+    // included, with a fatal signal
+    let op = match task.with_sig_pending(|pending| pending.is_empty()) {
+        true => None,
+        false => task.begin_group_op(),
+    };
+    while let Some(si) = op
+        .as_ref()
+        .and_then(|_| task.with_mut_sig_pending(|pending| pending.dequeue_signal(&old_mask)))
+    {
         let action = task.with_sig_handlers(|handlers| handlers.get(si.sig));
         log::info!("[do signal] Handling signal: {:?} {:?}", si, action);
-        if intr && action.flags.contains(SigActionFlag::SA_RESTART) {
-            cx.sepc -= 4;
-            cx.restore_last_user_a0();
-            log::info!("[do_signal] restart syscall");
+        // restart before the context is saved for the handler, which returns to the
+        // syscall then
+        if intr && matches!(action.atype, ActionType::User { .. }) {
+            if action.flags.contains(SigActionFlag::SA_RESTART) {
+                restart_syscall(cx);
+            }
             intr = false;
         }
         match action.atype {
-            ActionType::Ignore => {}
-            ActionType::Kill => terminate(task, si.sig),
-            ActionType::Stop => stop(task, si.sig),
-            ActionType::Cont => cont(task, si.sig),
+            // continued when sent already, see `Task::receive_siginfo`
+            ActionType::Ignore | ActionType::Cont => {}
+            ActionType::Kill => {
+                terminate(task, si.sig);
+                break;
+            }
+            ActionType::Stop => {
+                // the signals left are handled once continued
+                task.stop_group(si.sig);
+                break;
+            }
             ActionType::User { entry } => {
                 // The signal being delivered is also added to the signal mask, unless
                 // SA_NODEFER was specified when registering the handler.
@@ -170,19 +377,26 @@ pub fn do_signal(task: &Arc<Task>, mut intr: bool) -> SysResult<()> {
                 // 这些信息定义在Action的mask字段
                 *task.sig_mask() |= action.mask;
                 cx.user_fx.encounter_signal();
-                let signal_stack = task.sig_stack().take();
-                let sp = match signal_stack {
-                    Some(s) => {
-                        log::error!("[sigstack] use user defined signal stack. Unimplemented");
-                        s.get_stack_top()
-                    }
-                    None => {
-                        // 如果进程未定义专门的信号栈，
-                        // 用户自定义的信号处理函数将使用进程的普通栈空间，
-                        // 即和其他普通函数相同的栈。这个栈通常就是进程的主栈，
-                        // 也就是在进程启动时由操作系统自动分配的栈。
-                        cx.user_x[2]
+                let user_sp = cx.user_x[2];
+                let uc_stack = task.sig_stack_info(user_sp);
+                // A handler with `SA_ONSTACK` runs on the alternate signal stack, unless it
+                // is running there already, when a nested one goes on below the frame of
+                // the outer handler.
+                let sp = match *task.sig_stack_ref() {
+                    Some(stack)
+                        if action.flags.contains(SigActionFlag::SA_ONSTACK)
+                            && !stack.contains(user_sp) =>
+                    {
+                        if stack.ss_flags & SignalStack::SS_AUTODISARM != 0 {
+                            *task.sig_stack() = None;
+                        }
+                        stack.get_stack_top() & !0xF
                     }
+                    // 如果进程未定义专门的信号栈，
+                    // 用户自定义的信号处理函数将使用进程的普通栈空间，
+                    // 即和其他普通函数相同的栈。这个栈通常就是进程的主栈，
+                    // 也就是在进程启动时由操作系统自动分配的栈。
+                    _ => user_sp,
                 };
                 // extend the signal_stack
                 // 在栈上压入一个UContext，存储trap frame里的寄存器信息
@@ -193,8 +407,10 @@ pub fn do_signal(task: &Arc<Task>, mut intr: bool) -> SysResult<()> {
                 // that
                 let mut ucontext = UContext {
                     uc_flags: 0,
-                    uc_link: 0,
-                    uc_stack: signal_stack.unwrap_or_default(),
+                    // the context of the handler interrupted, if any, to go back to once
+                    // this one returns, see `sys_rt_sigreturn`
+                    uc_link: task.sig_ucontext_ptr(),
+                    uc_stack,
                     uc_sigmask: old_mask,
                     uc_sig: [0; 16],
                     uc_mcontext: MContext {
@@ -218,21 +434,9 @@ pub fn do_signal(task: &Arc<Task>, mut intr: bool) -> SysResult<()> {
                     // log::error!("[SA_SIGINFO] set ucontext {ucontext:?}");
                     // a2
                     cx.user_x[12] = new_sp;
-                    #[derive(Default, Copy, Clone)]
-                    #[repr(C)]
-                    pub struct LinuxSigInfo {
-                        pub si_signo: i32,
-                        pub si_errno: i32,
-                        pub si_code: i32,
-                        pub _pad: [i32; 29],
-                        _align: [u64; 0],
-                    }
-                    let mut siginfo_v = LinuxSigInfo::default();
-                    siginfo_v.si_signo = si.sig.raw() as _;
-                    siginfo_v.si_code = si.code;
                     new_sp -= size_of::<LinuxSigInfo>();
                     let siginfo_ptr: UserWritePtr<LinuxSigInfo> = new_sp.into();
-                    siginfo_ptr.write(&task, siginfo_v)?;
+                    siginfo_ptr.write(&task, LinuxSigInfo::from(si))?;
                     cx.user_x[11] = new_sp;
                 }
                 cx.sepc = entry;
@@ -248,42 +452,24 @@ pub fn do_signal(task: &Arc<Task>, mut intr: bool) -> SysResult<()> {
             }
         }
     }
+    if intr {
+        restart_syscall(cx);
+    }
     Ok(())
 }
 
+/// Make the task execute the interrupted syscall again on return to user.
+fn restart_syscall(cx: &mut TrapContext) {
+    // back to the `ecall` instruction
+    cx.sepc -= 4;
+    cx.restore_last_user_a0();
+    log::info!("[do_signal] restart syscall");
+}
+
 /// terminate the process
 fn terminate(task: &Arc<Task>, sig: Sig) {
-    // exit all the memers of a thread group
-    task.with_thread_group(|tg| {
-        for t in tg.iter() {
-            t.set_terminated();
-        }
-    });
     // 将信号放入低7位 (第8位是core dump标志,在gdb调试崩溃程序中用到)
-    task.set_exit_code(sig.raw() as i32 & 0x7F);
-}
-
-fn stop(task: &Arc<Task>, sig: Sig) {
-    log::warn!("[do_signal] task stopped!");
-    task.with_mut_thread_group(|tg| {
-        for t in tg.iter() {
-            t.set_stopped();
-            t.set_wake_up_signal(SigSet::SIGCONT);
-        }
-    });
-    task.notify_parent(SigInfo::CLD_STOPPED, sig);
-}
-
-/// continue the process if it is currently stopped
-fn cont(task: &Arc<Task>, sig: Sig) {
-    log::warn!("[do_signal] task continue");
-    task.with_mut_thread_group(|tg| {
-        for t in tg.iter() {
-            t.set_running();
-            t.wake();
-        }
-    });
-    task.notify_parent(SigInfo::CLD_CONTINUED, sig);
+    task.exit_group(sig.raw() as i32 & 0x7F);
 }
 
 static TIMER_ID_ALLOCATOR: AtomicUsize = AtomicUsize::new(1);
@@ -292,9 +478,19 @@ pub fn alloc_timer_id() -> usize {
     TIMER_ID_ALLOCATOR.fetch_add(1, Ordering::Relaxed)
 }
 
+/// Interval timer counting wall time, delivers `SIGALRM`.
+pub const ITIMER_REAL: usize = 0;
+/// Interval timer counting user time of the process, delivers `SIGVTALRM`.
+pub const ITIMER_VIRTUAL: usize = 1;
+/// Interval timer counting user and system time of the process, delivers
+/// `SIGPROF`.
+pub const ITIMER_PROF: usize = 2;
+
 #[derive(Debug)]
 pub struct ITimer {
     pub interval: Duration,
+    /// Time the timer expires at, as measured by its clock, or zero if it is
+    /// disarmed.
     pub next_expire: Duration,
     pub id: usize,
 }
@@ -305,11 +501,110 @@ impl ITimer {
         next_expire: Duration::ZERO,
         id: 0,
     };
+
+    /// The timer as seen by user, `now` being the time of its clock.
+    pub fn get(&self, now: Duration) -> ITimerVal {
+        ITimerVal {
+            it_interval: self.interval.into(),
+            it_value: self.next_expire.saturating_sub(now).into(),
+        }
+    }
 }
 
+impl Task {
+    /// Current time of the clock the interval timer `which` counts.
+    fn itimer_clock(&self, which: usize) -> Duration {
+        match which {
+            ITIMER_REAL => get_time_duration(),
+            ITIMER_VIRTUAL => self.get_process_utime(),
+            _ => self.get_process_cputime(),
+        }
+    }
+
+    pub fn get_itimer(&self, which: usize) -> ITimerVal {
+        let now = self.itimer_clock(which);
+        self.with_itimers(|itimers| itimers[which].get(now))
+    }
+
+    /// Arm the interval timer `which` of the process, or disarm it if the value
+    /// of `new` is zero, and return its old value.
+    pub fn set_itimer(self: &Arc<Self>, which: usize, new: ITimerVal) -> ITimerVal {
+        let now = self.itimer_clock(which);
+        let id = alloc_timer_id();
+        let (old, next_expire) = self.with_mut_itimers(|itimers| {
+            let itimer = &mut itimers[which];
+            let old = itimer.get(now);
+            itimer.interval = new.it_interval.into();
+            itimer.next_expire = if new.it_value.is_zero() {
+                Duration::ZERO
+            } else {
+                now + new.it_value.into()
+            };
+            // NOTE: a real timer armed before is left in the timer manager, and
+            // does nothing when it fires with another id
+            itimer.id = id;
+            (old, itimer.next_expire)
+        });
+        if which == ITIMER_REAL && !next_expire.is_zero() {
+            let timer = Timer::new(
+                next_expire,
+                Box::new(RealITimer {
+                    task: Arc::downgrade(&self.leader()),
+                    id,
+                }),
+            );
+            TIMER_MANAGER.add_timer(timer);
+        }
+        old
+    }
+
+    /// Disarm all interval timers of the process, which is exiting.
+    pub fn cancel_itimers(&self) {
+        self.with_mut_itimers(|itimers| itimers.fill(ITimer::ZERO));
+    }
+
+    /// Fire the interval timers counting CPU time of the process which have
+    /// expired, called on timer ticks.
+    pub fn check_cpu_itimers(self: &Arc<Self>) {
+        if self.with_itimers(|itimers| {
+            itimers[ITIMER_VIRTUAL].next_expire.is_zero()
+                && itimers[ITIMER_PROF].next_expire.is_zero()
+        }) {
+            return;
+        }
+        let (utime, stime) = self.get_process_ustime();
+        let leader = self.leader();
+        self.with_mut_itimers(|itimers| {
+            for (which, now, sig) in [
+                (ITIMER_VIRTUAL, utime, Sig::SIGVTALRM),
+                (ITIMER_PROF, utime + stime, Sig::SIGPROF),
+            ] {
+                let itimer = &mut itimers[which];
+                if itimer.next_expire.is_zero() || now < itimer.next_expire {
+                    continue;
+                }
+                itimer.next_expire = if itimer.interval.is_zero() {
+                    Duration::ZERO
+                } else {
+                    now + itimer.interval
+                };
+                leader.receive_siginfo(
+                    SigInfo {
+                        sig,
+                        code: SigInfo::KERNEL,
+                        details: SigDetails::None,
+                    },
+                    false,
+                );
+            }
+        });
+    }
+}
+
+/// Expiration of `ITIMER_REAL` of a process.
 #[derive(Default, Debug)]
 pub struct RealITimer {
-    /// 设置Weak是因为可能task已经释放了但是RealITimer依然存在于TimerManager的情况
+    /// The thread group leader, weak since the timer may outlive the process.
     pub task: Weak<Task>,
     pub id: usize,
 }
@@ -348,6 +643,227 @@ impl TimerEvent for RealITimer {
     }
 }
 
+/// A POSIX timer of a process, see timer_create(2).
+#[derive(Debug)]
+pub struct PosixTimer {
+    clockid: usize,
+    /// Signal sent on expiration, none for `SIGEV_NONE`.
+    sig: Option<Sig>,
+    /// `sigev_value` sent with the signal.
+    value: usize,
+    interval: Duration,
+    /// Next expiration on the monotonic time since boot, zero if disarmed.
+    next_expire: Duration,
+    /// Expirations missed while the signal of an earlier one was pending.
+    overrun: i32,
+    /// Allocated on every arming, so that the timer of an earlier one is
+    /// ignored.
+    id: usize,
+}
+
+impl PosixTimer {
+    /// Time until the next expiration and the interval.
+    fn get(&self) -> ITimerSpec {
+        let value = match self.next_expire.is_zero() {
+            true => Duration::ZERO,
+            // a timer about to fire reads as 1ns left, 0 would mean disarmed
+            false => self
+                .next_expire
+                .saturating_sub(get_time_duration())
+                .max(Duration::from_nanos(1)),
+        };
+        ITimerSpec {
+            it_interval: self.interval.into(),
+            it_value: value.into(),
+        }
+    }
+}
+
+impl Task {
+    /// Create a POSIX timer of the process on `clockid`, notifying as `event`
+    /// tells, or by `SIGALRM` with the timer id as value if none, and return
+    /// its id. Only `CLOCK_REALTIME` and `CLOCK_MONOTONIC` are supported.
+    pub fn create_posix_timer(&self, clockid: usize, event: Option<SigEvent>) -> SysResult<usize> {
+        if clockid != CLOCK_REALTIME && clockid != CLOCK_MONOTONIC {
+            return Err(SysError::EINVAL);
+        }
+        self.with_mut_posix_timers(|timers| {
+            let timerid = (0..).find(|id| !timers.contains_key(id)).unwrap();
+            let (sig, value) = match event {
+                None => (Some(Sig::SIGALRM), timerid),
+                Some(event) => match event.sigev_notify {
+                    SigEvent::SIGEV_NONE => (None, event.sigev_value),
+                    SigEvent::SIGEV_SIGNAL => {
+                        let sig = Sig::from_i32(event.sigev_signo);
+                        if !sig.is_valid() || sig.raw() == 0 {
+                            return Err(SysError::EINVAL);
+                        }
+                        (Some(sig), event.sigev_value)
+                    }
+                    _ => return Err(SysError::EINVAL),
+                },
+            };
+            timers.insert(
+                timerid,
+                PosixTimer {
+                    clockid,
+                    sig,
+                    value,
+                    interval: Duration::ZERO,
+                    next_expire: Duration::ZERO,
+                    overrun: 0,
+                    id: 0,
+                },
+            );
+            Ok(timerid)
+        })
+    }
+
+    pub fn delete_posix_timer(&self, timerid: usize) -> SysResult<()> {
+        self.with_mut_posix_timers(|timers| timers.remove(&timerid))
+            .map(|_| ())
+            .ok_or(SysError::EINVAL)
+    }
+
+    /// Delete all POSIX timers of the process, on exec or exit.
+    pub fn clear_posix_timers(&self) {
+        self.with_mut_posix_timers(|timers| timers.clear());
+    }
+
+    pub fn get_posix_timer(&self, timerid: usize) -> SysResult<ITimerSpec> {
+        self.with_posix_timers(|timers| timers.get(&timerid).map(PosixTimer::get))
+            .ok_or(SysError::EINVAL)
+    }
+
+    /// The overrun count of the last expiration notified by the timer.
+    pub fn posix_timer_overrun(&self, timerid: usize) -> SysResult<i32> {
+        self.with_posix_timers(|timers| timers.get(&timerid).map(|timer| timer.overrun))
+            .ok_or(SysError::EINVAL)
+    }
+
+    /// Arm the POSIX timer `timerid`, or disarm it if `new` has a zero value,
+    /// returning the setting before. The value of `new` is a time of the clock
+    /// of the timer if `abstime`, or relative to now otherwise.
+    pub fn set_posix_timer(
+        self: &Arc<Self>,
+        timerid: usize,
+        abstime: bool,
+        new: ITimerSpec,
+    ) -> SysResult<ITimerSpec> {
+        if !new.is_valid() {
+            return Err(SysError::EINVAL);
+        }
+        let id = alloc_timer_id();
+        let (old, next_expire) = self.with_mut_posix_timers(|timers| {
+            let timer = timers.get_mut(&timerid).ok_or(SysError::EINVAL)?;
+            let old = timer.get();
+            let value: Duration = new.it_value.into();
+            timer.interval = new.it_interval.into();
+            timer.next_expire = if value.is_zero() {
+                Duration::ZERO
+            } else if abstime {
+                let deviation = unsafe { CLOCK_DEVIATION }[timer.clockid];
+                value.saturating_sub(deviation).max(Duration::from_nanos(1))
+            } else {
+                get_time_duration() + value
+            };
+            timer.overrun = 0;
+            // NOTE: the timer of an earlier arming is left in the timer manager,
+            // and does nothing when it fires with another id
+            timer.id = id;
+            Ok((old, timer.next_expire))
+        })?;
+        // NOTE: the timer manager calls back with its lock held, which then takes
+        // ours, so ours is released before adding the timer
+        if !next_expire.is_zero() {
+            let event = PosixTimerEvent {
+                task: Arc::downgrade(&self.leader()),
+                timerid,
+                id,
+            };
+            TIMER_MANAGER.add_timer(Timer::new(next_expire, Box::new(event)));
+        }
+        Ok(old)
+    }
+
+    /// Send the signal of an expiration of `timer`, unless the signal of an
+    /// earlier one is still pending, when this one and the `missed` ones before
+    /// are counted as overruns of it instead.
+    fn notify_posix_timer(self: &Arc<Self>, timerid: usize, timer: &mut PosixTimer, missed: i32) {
+        let Some(sig) = timer.sig else {
+            return;
+        };
+        let pending = self.with_thread_group(|tg| {
+            tg.iter().find_map(|t| {
+                t.with_mut_sig_pending(|pending| {
+                    pending
+                        .queue
+                        .iter_mut()
+                        .find_map(|si| match &mut si.details {
+                            SigDetails::Timer { tid, overrun, .. }
+                                if *tid == timerid && si.sig == sig =>
+                            {
+                                *overrun = overrun.saturating_add(missed).saturating_add(1);
+                                Some(*overrun)
+                            }
+                            _ => None,
+                        })
+                })
+            })
+        });
+        match pending {
+            Some(overrun) => timer.overrun = overrun,
+            None => {
+                timer.overrun = missed;
+                self.receive_siginfo(
+                    SigInfo {
+                        sig,
+                        code: SigInfo::TIMER,
+                        details: SigDetails::Timer {
+                            tid: timerid,
+                            overrun: missed,
+                            value: timer.value,
+                        },
+                    },
+                    false,
+                );
+            }
+        }
+    }
+}
+
+/// Expiration of an arming of a POSIX timer.
+struct PosixTimerEvent {
+    /// The thread group leader, weak since the timer may outlive the process.
+    task: Weak<Task>,
+    timerid: usize,
+    id: usize,
+}
+
+impl TimerEvent for PosixTimerEvent {
+    fn callback(self: Box<Self>) -> Option<Timer> {
+        let task = self.task.upgrade()?;
+        task.with_mut_posix_timers(|timers| {
+            let timer = timers
+                .get_mut(&self.timerid)
+                // deleted, disarmed or armed again since
+                .filter(|timer| timer.id == self.id)?;
+            let mut missed = 0;
+            if timer.interval.is_zero() {
+                timer.next_expire = Duration::ZERO;
+            } else {
+                // the expirations missed, e.g. while interrupts were off, are overruns
+                let late = get_time_duration().saturating_sub(timer.next_expire);
+                missed = (late.as_nanos() / timer.interval.as_nanos()) as u32;
+                timer.next_expire += timer.interval * (missed + 1);
+            }
+            task.notify_posix_timer(self.timerid, timer, missed.min(i32::MAX as u32) as i32);
+            let next_expire = timer.next_expire;
+            (!next_expire.is_zero()).then(|| Timer::new(next_expire, self))
+        })
+    }
+}
+
 pub struct IntrBySignalFuture {
     pub task: Arc<Task>,
     pub mask: SigSet,
@@ -361,7 +877,7 @@ impl Future for IntrBySignalFuture {
             .task
             .with_sig_pending(|pending| pending.has_expect_signals(!self.mask));
         if has_signal {
-            log::warn!("[IntrBySignalFuture] received interupt signal");
+            log::debug!("[IntrBySignalFuture] received interupt signal");
             Poll::Ready(())
         } else {
             Poll::Pending
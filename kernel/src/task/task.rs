@@ -11,32 +11,35 @@ use core::{
     ops::DerefMut,
     sync::atomic::{AtomicI32, AtomicUsize, Ordering},
     task::Waker,
+    time::Duration,
 };
 
-use arch::memory::sfence_vma_all;
-use async_utils::block_on;
+use async_utils::{block_on, yield_now};
 use config::{
     mm::DL_INTERP_OFFSET,
     process::{INIT_PROC_PID, USER_STACK_SIZE},
 };
 use memory::VirtAddr;
 use signal::{
-    action::{SigHandlers, SigPending},
+    action::{SigActionFlag, SigHandlers, SigPending},
     siginfo::{SigDetails, SigInfo},
     signal_stack::SignalStack,
     sigset::{Sig, SigSet},
 };
 use sync::mutex::SpinNoIrqLock;
-use systype::{SysError, SysResult};
+use systype::{RLimit, SysError, SysResult, RLIM_NLIMITS};
 use time::stat::TaskTimeStat;
 use vfs::{fd_table::FdTable, sys_root_dentry};
 use vfs_core::{
     is_absolute_path, split_path, AtFd, Dentry, File, InodeMode, InodeType, OpenFlags, Path,
+    ResolveFlags,
 };
 
 use super::{
-    resource::CpuMask,
-    signal::ITimer,
+    cred::Credentials,
+    perf::PerfStat,
+    resource::{init_rlimits, CpuMask, IoPrio},
+    signal::{ITimer, PosixTimer},
     tid::{Pid, Tid, TidHandle},
     PGid, PROCESS_GROUP_MANAGER,
 };
@@ -46,8 +49,14 @@ use crate::{
         futex::{futex_manager, FutexHashKey, RobustListHead},
         shm::SHARED_MEMORY_MANAGER,
     },
-    mm::{memory_space::init_stack, MemorySpace, UserWritePtr},
-    processor::env::within_sum,
+    mm::{
+        memory_space::{init_stack, DenyWrite},
+        MemorySpace, UserWritePtr,
+    },
+    processor::{
+        env::within_sum,
+        shutdown::{power_off, PowerOff},
+    },
     syscall::CloneFlags,
     task::{
         aux::{AuxHeader, AT_BASE},
@@ -80,8 +89,10 @@ pub struct Task {
     /// Indicates if the task is a zombie. Protected by a spin lock due to
     /// potential access by other tasks.
     state: SpinNoIrqLock<TaskState>,
-    /// The address space of the process.
-    memory_space: Shared<MemorySpace>,
+    /// The address space of the process. It is shared with other tasks cloned
+    /// with `CLONE_VM`, and is replaced on execve, so that those still running
+    /// keep the old one alive.
+    memory_space: SpinNoIrqLock<Shared<MemorySpace>>,
     /// Map of start address of shared memory areas to their keys in the shared
     /// memory manager.
     shm_ids: Shared<BTreeMap<VirtAddr, usize>>,
@@ -100,36 +111,52 @@ pub struct Task {
     waker: SyncUnsafeCell<Option<Waker>>,
     /// Thread group containing this task.
     thread_group: Shared<ThreadGroup>,
-    /// File descriptor table.
-    fd_table: Shared<FdTable>,
+    /// File descriptor table. It is shared with other tasks cloned with
+    /// `CLONE_FILES`, and is replaced on execve and exit to detach from them.
+    fd_table: SpinNoIrqLock<Shared<FdTable>>,
     /// Current working directory dentry.
     cwd: Shared<Arc<dyn Dentry>>,
+    /// Root directory dentry, where absolute paths start from.
+    root: Shared<Arc<dyn Dentry>>,
     /// Pending signals for the task.
     sig_pending: SpinNoIrqLock<SigPending>,
     /// Signal handlers.
     sig_handlers: Shared<SigHandlers>,
     /// Signal mask for the task.
     sig_mask: SyncUnsafeCell<SigSet>,
-    /// Optional signal stack for the task, settable via `sys_signalstack`.
+    /// Optional alternate signal stack for the task, settable via
+    /// `sys_sigaltstack`.
     sig_stack: SyncUnsafeCell<Option<SignalStack>>,
     /// Pointer to the user context for signal handling.
     sig_ucontext_ptr: AtomicUsize,
     /// Statistics for task execution times.
     time_stat: SyncUnsafeCell<TaskTimeStat>,
+    /// Hardware counters of the task, shared with perf events counting it.
+    perf_stat: Arc<PerfStat>,
     /// Interval timers for the task.
     itimers: Shared<[ITimer; 3]>,
+    /// POSIX timers of the process by id, see timer_create(2).
+    posix_timers: Shared<BTreeMap<usize, PosixTimer>>,
+    /// Resource limits of the process, indexed by `Resource`.
+    rlimits: Shared<[RLimit; RLIM_NLIMITS]>,
     /// Futexes used by the task.
     robust: Shared<RobustListHead>,
     /// Address of the task's thread ID.
     tid_address: SyncUnsafeCell<TidAddress>,
     /// Mask of CPUs allowed for the task.
     cpus_allowed: SyncUnsafeCell<CpuMask>,
+    /// I/O scheduling class and priority of the task.
+    ioprio: SyncUnsafeCell<IoPrio>,
+    /// Nice value of the task, from `NICE_MIN` to `NICE_MAX`.
+    nice: SyncUnsafeCell<i32>,
     /// Process group ID of the task.
     pgid: Shared<PGid>,
     /// ELF file the task executes.
     elf: SyncUnsafeCell<Arc<dyn File>>,
     /// Command-line arguments for the task.
     args: SyncUnsafeCell<Vec<String>>,
+    /// User and group identities of the task.
+    cred: SyncUnsafeCell<Credentials>,
 }
 
 impl core::fmt::Debug for Task {
@@ -192,21 +219,24 @@ impl Task {
         sig_stack: Option<SignalStack>,
         time_stat: TaskTimeStat,
         cpus_allowed: CpuMask,
+        ioprio: IoPrio,
+        nice: i32,
         elf: Arc<dyn File>,
-        args: Vec<String>
+        args: Vec<String>,
+        cred: Credentials
     );
     generate_atomic_accessors!(exit_code: i32, sig_ucontext_ptr: usize);
     generate_with_methods!(
-        fd_table: FdTable,
         children: BTreeMap<Tid, Arc<Task>>,
-        memory_space: MemorySpace,
         thread_group: ThreadGroup,
         sig_pending: SigPending,
         robust: RobustListHead,
         sig_handlers: SigHandlers,
         state: TaskState,
         shm_ids: BTreeMap<VirtAddr, usize>,
-        itimers: [ITimer;3]
+        itimers: [ITimer;3],
+        posix_timers: BTreeMap<usize, PosixTimer>,
+        rlimits: [RLimit; RLIM_NLIMITS]
     );
 
     pub fn new_init(
@@ -215,7 +245,7 @@ impl Task {
         elf_file: Arc<dyn File>,
         args: Vec<String>,
     ) -> Arc<Self> {
-        let tid = alloc_tid();
+        let tid = alloc_tid().expect("no tid for the init process");
         let pgid = tid.0;
         let task = Arc::new(Self {
             tid,
@@ -226,21 +256,28 @@ impl Task {
             children: new_shared(BTreeMap::new()),
             exit_code: AtomicI32::new(0),
             trap_context: SyncUnsafeCell::new(trap_context),
-            memory_space: new_shared(memory_space),
+            memory_space: SpinNoIrqLock::new(new_shared(memory_space)),
             waker: SyncUnsafeCell::new(None),
             thread_group: new_shared(ThreadGroup::new()),
-            fd_table: new_shared(FdTable::new()),
+            fd_table: SpinNoIrqLock::new(new_shared(FdTable::empty())),
             cwd: new_shared(sys_root_dentry()),
+            root: new_shared(sys_root_dentry()),
             sig_pending: SpinNoIrqLock::new(SigPending::new()),
             sig_mask: SyncUnsafeCell::new(SigSet::empty()),
             sig_handlers: new_shared(SigHandlers::new()),
             sig_stack: SyncUnsafeCell::new(None),
             time_stat: SyncUnsafeCell::new(TaskTimeStat::new()),
+            perf_stat: Arc::new(PerfStat::new()),
             sig_ucontext_ptr: AtomicUsize::new(0),
             itimers: new_shared([ITimer::ZERO; 3]),
+            posix_timers: new_shared(BTreeMap::new()),
+            rlimits: new_shared(init_rlimits()),
             robust: new_shared(RobustListHead::default()),
             tid_address: SyncUnsafeCell::new(TidAddress::new()),
             cpus_allowed: SyncUnsafeCell::new(CpuMask::CPU_ALL),
+            ioprio: SyncUnsafeCell::new(IoPrio::default()),
+            nice: SyncUnsafeCell::new(0),
+            cred: SyncUnsafeCell::new(Credentials::root()),
             shm_ids: new_shared(BTreeMap::new()),
             pgid: new_shared(pgid),
             elf: SyncUnsafeCell::new(elf_file),
@@ -262,6 +299,10 @@ impl Task {
         self.children.lock()
     }
 
+    pub fn perf_stat(&self) -> &Arc<PerfStat> {
+        &self.perf_stat
+    }
+
     pub fn state(&self) -> TaskState {
         *self.state.lock()
     }
@@ -339,16 +380,118 @@ impl Task {
         *self.cwd.lock() = dentry;
     }
 
+    pub fn root(&self) -> Arc<dyn Dentry> {
+        self.root.lock().clone()
+    }
+
+    pub fn set_root(&self, dentry: Arc<dyn Dentry>) {
+        *self.root.lock() = dentry;
+    }
+
+    pub fn fd_table(&self) -> Shared<FdTable> {
+        self.fd_table.lock().clone()
+    }
+
+    fn set_fd_table(&self, fd_table: Shared<FdTable>) {
+        *self.fd_table.lock() = fd_table;
+    }
+
+    /// Take a copy of the fd table if it is shared with other tasks.
+    pub fn unshare_fd_table(&self) {
+        let fd_table = self.fd_table();
+        // NOTE: one reference is held by `self` and the other by `fd_table` here
+        if Arc::strong_count(&fd_table) > 2 {
+            self.set_fd_table(new_shared(fd_table.lock().clone()));
+        }
+    }
+
+    pub fn with_fd_table<T>(&self, f: impl FnOnce(&FdTable) -> T) -> T {
+        log::trace!("with_fd_table");
+        f(&self.fd_table().lock())
+    }
+
+    pub fn with_mut_fd_table<T>(&self, f: impl FnOnce(&mut FdTable) -> T) -> T {
+        log::trace!("with_mut_fd_table");
+        f(&mut self.fd_table().lock())
+    }
+
+    pub fn memory_space(&self) -> Shared<MemorySpace> {
+        self.memory_space.lock().clone()
+    }
+
+    fn set_memory_space(&self, memory_space: Shared<MemorySpace>) {
+        *self.memory_space.lock() = memory_space;
+    }
+
+    pub fn with_memory_space<T>(&self, f: impl FnOnce(&MemorySpace) -> T) -> T {
+        f(&self.memory_space().lock())
+    }
+
+    pub fn with_mut_memory_space<T>(&self, f: impl FnOnce(&mut MemorySpace) -> T) -> T {
+        f(&mut self.memory_space().lock())
+    }
+
     pub unsafe fn switch_page_table(&self) {
-        self.memory_space.lock().switch_page_table()
+        self.memory_space().lock().switch_page_table()
     }
 
     pub fn raw_mm_pointer(&self) -> usize {
-        Arc::as_ptr(&self.memory_space) as usize
+        Arc::as_ptr(&self.memory_space()) as usize
     }
 
-    pub fn do_clone(self: &Arc<Self>, flags: CloneFlags) -> Arc<Self> {
-        let tid = alloc_tid();
+    /// Start an operation on the whole thread group, i.e. clone, exit_group or
+    /// signal delivery, which an execve will wait for. It fails while an
+    /// execve is tearing the group down, which terminates the caller anyway.
+    pub fn begin_group_op(&self) -> Option<GroupOpGuard> {
+        let mut tg = self.thread_group.lock();
+        if tg.exec {
+            return None;
+        }
+        tg.ops += 1;
+        Some(GroupOpGuard {
+            thread_group: self.thread_group.clone(),
+        })
+    }
+
+    /// Take the thread group for an execve: no other group operation starts
+    /// from now on, those in flight are waited for, and then all the other
+    /// threads are terminated. Fails with `EAGAIN` if another thread of the
+    /// group got there first.
+    ///
+    /// The threads terminated may still be inside a syscall or on another hart
+    /// for a while, they keep the old memory space alive until they exit.
+    pub async fn begin_exec(&self) -> SysResult<ExecGuard> {
+        {
+            let mut tg = self.thread_group.lock();
+            if tg.exec {
+                return Err(SysError::EAGAIN);
+            }
+            tg.exec = true;
+        }
+        // NOTE: no other lock is held while waiting, a clone in flight may be
+        // waiting for the memory space lock to copy it
+        while self.thread_group.lock().ops > 0 {
+            yield_now().await;
+        }
+        self.with_thread_group(|tg| {
+            for t in tg.iter() {
+                if t.tid() != self.tid() {
+                    t.set_terminated();
+                }
+            }
+        });
+        Ok(ExecGuard {
+            thread_group: self.thread_group.clone(),
+        })
+    }
+
+    /// Fails with `EAGAIN` if there is no free tid, or if an execve is tearing
+    /// the thread group down.
+    pub async fn do_clone(self: &Arc<Self>, flags: CloneFlags) -> SysResult<Arc<Self>> {
+        // NOTE: held until the new task is in its thread group, so that an execve
+        // terminates it along with the others
+        let _op = self.begin_group_op().ok_or(SysError::EAGAIN)?;
+        let tid = alloc_tid()?;
         let trap_context = SyncUnsafeCell::new(*self.trap_context_mut());
         let state = SpinNoIrqLock::new(self.state());
 
@@ -357,8 +500,9 @@ impl Task {
         let parent;
         let children;
         let thread_group;
-        let cwd;
         let itimers;
+        let posix_timers;
+        let rlimits;
         let robust;
         let shm_ids;
         let pgid;
@@ -374,7 +518,8 @@ impl Task {
             children = self.children.clone();
             thread_group = self.thread_group.clone();
             itimers = self.itimers.clone();
-            cwd = self.cwd.clone();
+            posix_timers = self.posix_timers.clone();
+            rlimits = self.rlimits.clone();
             robust = self.robust.clone();
             shm_ids = self.shm_ids.clone();
             pgid = self.pgid.clone();
@@ -385,7 +530,10 @@ impl Task {
             children = new_shared(BTreeMap::new());
             thread_group = new_shared(ThreadGroup::new());
             itimers = new_shared([ITimer::ZERO; 3]);
-            cwd = new_shared(self.cwd());
+            // A child created via fork(2) does not inherit the timers of its parent.
+            posix_timers = new_shared(BTreeMap::new());
+            // A child created via fork(2) inherits its parent's resource limits.
+            rlimits = new_shared(*self.rlimits.lock());
             robust = new_shared(RobustListHead::default());
             shm_ids = new_shared(BTreeMap::clone(&self.shm_ids.lock()));
             for (_, shm_id) in shm_ids.lock().iter() {
@@ -396,18 +544,21 @@ impl Task {
 
         let memory_space;
         if flags.contains(CloneFlags::VM) {
-            memory_space = self.memory_space.clone();
+            memory_space = self.memory_space();
         } else {
-            memory_space =
-                new_shared(self.with_mut_memory_space(|m| MemorySpace::from_user_lazily(m)));
-            // TODO: avoid flushing global entries like kernel mappings
-            unsafe { sfence_vma_all() };
+            memory_space = new_shared(MemorySpace::from_user_lazily(&self.memory_space()).await);
         }
 
+        let (cwd, root) = if flags.contains(CloneFlags::FS) {
+            (self.cwd.clone(), self.root.clone())
+        } else {
+            (new_shared(self.cwd()), new_shared(self.root()))
+        };
+
         let fd_table = if flags.contains(CloneFlags::FILES) {
-            self.fd_table.clone()
+            self.fd_table()
         } else {
-            new_shared(self.fd_table.lock().clone())
+            new_shared(self.with_fd_table(|table| table.clone()))
         };
 
         let new = Arc::new(Self {
@@ -415,31 +566,44 @@ impl Task {
             leader,
             is_leader,
             cwd,
+            root,
             state,
             parent,
             children,
             exit_code: AtomicI32::new(0),
             trap_context,
-            memory_space,
+            memory_space: SpinNoIrqLock::new(memory_space),
             waker: SyncUnsafeCell::new(None),
             thread_group,
-            fd_table,
+            fd_table: SpinNoIrqLock::new(fd_table),
             sig_pending: SpinNoIrqLock::new(SigPending::new()),
             // A child created via fork(2) inherits a copy of its parent's signal mask;
             sig_mask: SyncUnsafeCell::new(self.sig_mask_ref().clone()),
             sig_handlers,
-            sig_stack: SyncUnsafeCell::new(None),
+            // A child created via fork(2) inherits the alternate signal stack, but a
+            // thread does not, as it has a stack of its own
+            sig_stack: SyncUnsafeCell::new(if flags.contains(CloneFlags::VM) {
+                None
+            } else {
+                *self.sig_stack_ref()
+            }),
             time_stat: SyncUnsafeCell::new(TaskTimeStat::new()),
+            perf_stat: Arc::new(PerfStat::new()),
             sig_ucontext_ptr: AtomicUsize::new(0),
             itimers,
+            posix_timers,
+            rlimits,
             robust,
             tid_address: SyncUnsafeCell::new(TidAddress::new()),
-            cpus_allowed: SyncUnsafeCell::new(CpuMask::CPU_ALL),
+            cpus_allowed: SyncUnsafeCell::new(*self.cpus_allowed_ref()),
+            ioprio: SyncUnsafeCell::new(*self.ioprio_ref()),
+            nice: SyncUnsafeCell::new(*self.nice_ref()),
             // After a fork(2), the child inherits the attached shared memory segments.
             shm_ids,
             pgid,
             elf: SyncUnsafeCell::new(self.elf_ref().clone()),
             args: SyncUnsafeCell::new(self.args_ref().clone()),
+            cred: SyncUnsafeCell::new(self.cred_ref().clone()),
         });
 
         if !flags.contains(CloneFlags::THREAD) {
@@ -452,49 +616,39 @@ impl Task {
         }
 
         TASK_MANAGER.add(&new);
-        new
+        Ok(new)
     }
 
     pub fn do_execve(
         self: &Arc<Self>,
         elf_file: Arc<dyn File>,
+        exe: DenyWrite,
         elf_data: &[u8],
         argv: Vec<String>,
         envp: Vec<String>,
     ) {
         log::debug!("[Task::do_execve] parsing elf");
         let mut memory_space = MemorySpace::new_user();
+        memory_space.set_exe(exe);
         let (mut entry, mut auxv) = memory_space.parse_and_map_elf(elf_file.clone(), elf_data);
 
         let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
-        if let Some(interp_entry_point) = memory_space.load_dl_interp_if_needed(&elf) {
+        if let Some(interp_entry_point) = memory_space.load_dl_interp_if_needed(&elf_file, &elf) {
             auxv.push(AuxHeader::new(AT_BASE, DL_INTERP_OFFSET));
             entry = interp_entry_point;
         } else {
             auxv.push(AuxHeader::new(AT_BASE, 0));
         }
 
-        // NOTE: should do termination before switching page table, so that other
-        // threads will trap in by page fault and be handled by `do_exit`
-        log::debug!("[Task::do_execve] terminating all threads except the leader");
-        let pid = self.with_thread_group(|tg| {
-            let mut pid = 0;
-            for t in tg.iter() {
-                if !t.is_leader() {
-                    t.set_terminated();
-                } else {
-                    pid = t.tid();
-                }
-            }
-            pid
-        });
-
+        // NOTE: the other threads have been terminated by `begin_exec`. The old
+        // memory space is replaced rather than overwritten, those still running
+        // or sharing it by `CLONE_VM`, e.g. a vfork parent, keep using it
         log::debug!("[Task::do_execve] changing memory space");
         // NOTE: need to switch to new page table first before dropping old page table,
         // otherwise, there will be a vacuum period without page table which will cause
         // random errors in smp situation
         unsafe { memory_space.switch_page_table() };
-        self.with_mut_memory_space(|m| *m = memory_space);
+        self.set_memory_space(new_shared(memory_space));
 
         // alloc stack, and push argv, envp and auxv
         log::debug!("[Task::do_execve] allocing stack");
@@ -502,12 +656,16 @@ impl Task {
 
         *self.elf() = elf_file;
         *self.args() = argv.clone();
+        self.cred().exec_caps();
 
         let (sp, argc, argv, envp) = within_sum(|| init_stack(sp_init, argv, envp, auxv));
 
         // alloc heap
         self.with_mut_memory_space(|m| m.alloc_heap_lazily());
 
+        // The fd table shared by `CLONE_FILES` is unshared on exec, so that closing
+        // fds below never affects other tasks
+        self.unshare_fd_table();
         // close fd on exec
         self.with_mut_fd_table(|table| table.do_close_on_exec());
 
@@ -524,12 +682,17 @@ impl Task {
 
         // After an execve(2), all attached shared memory segments are detached from the
         // process.
+        let pid = self.pid();
         self.with_mut_shm_ids(|ids| {
             for (_, shm_id) in ids.iter() {
                 SHARED_MEMORY_MANAGER.detach(*shm_id, pid);
             }
             *ids = BTreeMap::new();
         });
+
+        // POSIX timers are deleted on execve(2), the other threads sharing them having
+        // been terminated
+        self.clear_posix_timers();
     }
 
     // NOTE: After all of the threads in a thread group is terminated, the parent
@@ -538,12 +701,17 @@ impl Task {
     // instead, call `set_terminated`
     pub fn do_exit(self: &Arc<Self>) {
         log::info!("thread {} do exit", self.tid());
-        assert_ne!(
-            self.tid(),
-            INIT_PROC_PID,
-            "initproc die!!!, sepc {:#x}",
-            self.trap_context_mut().sepc
-        );
+        if self.tid() == INIT_PROC_PID {
+            log::info!(
+                "[do_exit] initproc exit with code {}, sepc {:#x}, power off",
+                self.exit_code(),
+                self.trap_context_mut().sepc
+            );
+            power_off(PowerOff::Shutdown);
+        }
+
+        // the counts are final before the parent can see the exit
+        self.perf_stat().record_running();
 
         if let Some(address) = self.tid_address_ref().clear_child_tid {
             log::info!("[do_exit] clear_child_tid: {:x}", address);
@@ -561,6 +729,10 @@ impl Task {
             let _ = futex_manager().wake(&key, 1);
         }
 
+        // Drop the reference of fd table, files are closed when the last task
+        // sharing this table exits.
+        self.set_fd_table(new_shared(FdTable::empty()));
+
         let mut tg = self.thread_group.lock();
 
         if (!self.leader().is_terminated())
@@ -587,6 +759,14 @@ impl Task {
         // exit the process, e.g. reparent all children, and send SIGCHLD to parent
         log::info!("[Task::do_exit] exit the whole process");
 
+        // NOTE: the executable may be written once no process runs it, rather than
+        // after the parent reaps this one. A memory space shared by `CLONE_VM` is
+        // still running it.
+        // NOTE: one reference is held by `self` and the other by the getter here
+        if Arc::strong_count(&self.memory_space()) == 2 {
+            self.with_mut_memory_space(|m| m.release_exe());
+        }
+
         log::debug!("[Task::do_exit] reparent children to init");
         debug_assert_ne!(self.tid(), INIT_PROC_PID);
         self.with_mut_children(|children| {
@@ -617,22 +797,6 @@ impl Task {
             children.clear();
         });
 
-        // NOTE: leader will be removed by parent calling `sys_wait4`
-        if let Some(parent) = self.parent() {
-            if let Some(parent) = parent.upgrade() {
-                parent.receive_siginfo(
-                    SigInfo {
-                        sig: Sig::SIGCHLD,
-                        code: SigInfo::CLD_EXITED,
-                        details: SigDetails::None,
-                    },
-                    false,
-                )
-            } else {
-                log::error!("no arc parent");
-            }
-        }
-
         // Upon _exit(2), all attached shared memory segments are detached from the
         // process.
         self.with_shm_ids(|ids| {
@@ -641,15 +805,55 @@ impl Task {
             }
         });
 
-        // TODO: drop most resources here instead of wait4 function parent
-        // called
-        self.with_mut_fd_table(|table| table.clear());
+        let leader = self.leader();
+        drop(tg);
 
-        if self.is_leader() {
-            self.set_zombie();
-        } else {
-            self.leader().set_zombie();
+        // NOTE: after the thread group is unlocked, as an expiring timer locks it to
+        // deliver the signal
+        self.cancel_itimers();
+        self.clear_posix_timers();
+        let Some(parent) = leader.parent().and_then(|p| p.upgrade()) else {
+            log::error!("no arc parent");
+            leader.set_zombie();
+            return;
+        };
+
+        // NOTE: leader will be removed by parent calling `sys_wait4`, unless the
+        // parent does not wait for its children. Then it is reaped here before it
+        // is marked a zombie, so that a wait4 never reaps it as well.
+        let reaped = parent.with_sig_handlers(|handlers| {
+            handlers.is_ignored(Sig::SIGCHLD)
+                || handlers
+                    .get(Sig::SIGCHLD)
+                    .flags
+                    .contains(SigActionFlag::SA_NOCLDWAIT)
+        });
+        if reaped {
+            log::info!("[Task::do_exit] reaped by parent {}", parent.pid());
+            parent.remove_child(leader.pid());
+            TASK_MANAGER.remove(leader.pid());
+            PROCESS_GROUP_MANAGER.remove(&leader);
         }
+        leader.set_zombie();
+
+        // the wait status stores the signal that killed the process in the lowest 7
+        // bits, or the exit code in the next 8 bits
+        let wstatus = leader.exit_code();
+        let (code, status) = match wstatus & 0x7F {
+            0 => (SigInfo::CLD_EXITED, (wstatus >> 8) & 0xFF),
+            sig => (SigInfo::CLD_KILLED, sig),
+        };
+        parent.receive_siginfo(
+            SigInfo {
+                sig: Sig::SIGCHLD,
+                code,
+                details: SigDetails::Child {
+                    pid: leader.pid(),
+                    status,
+                },
+            },
+            false,
+        );
         // When the task is not leader, which means its is not a process, it
         // will get dropped when hart leaves this task.
     }
@@ -668,27 +872,42 @@ impl Task {
     ///   using the O_PATH flag.
     pub fn at_helper(&self, fd: AtFd, path: &str, flags: OpenFlags) -> SysResult<Arc<dyn Dentry>> {
         log::info!("[at_helper] fd: {fd}, path: {path}");
+        let path = self.at_path(fd, path)?;
+        path.resolve(flags, ResolveFlags::empty())
+    }
+
+    /// Resolve the directory that holds the last component of `path`, relative
+    /// to the directory `fd` in the way of `at_helper`, and return it with the
+    /// name of that component. It is for the syscalls that create or remove a
+    /// name in a directory, see `Path::resolve_parent`.
+    pub fn resolve_parent_and_name(
+        &self,
+        fd: AtFd,
+        path: &str,
+    ) -> SysResult<(Arc<dyn Dentry>, String)> {
+        log::info!("[resolve_parent_and_name] fd: {fd}, path: {path}");
+        let path = self.at_path(fd, path)?;
+        path.resolve_parent(ResolveFlags::empty())
+    }
+
+    /// Build the `Path` to walk for `path` relative to the directory `fd`.
+    pub fn at_path(&self, fd: AtFd, path: &str) -> SysResult<Path> {
+        let root = self.root();
         let path = if is_absolute_path(path) {
-            Path::new(sys_root_dentry(), sys_root_dentry(), path)
+            Path::new(root.clone(), root, path)
         } else {
             match fd {
                 AtFd::FdCwd => {
                     log::info!("[at_helper] cwd: {}", self.cwd().path());
-                    Path::new(sys_root_dentry(), self.cwd(), path)
+                    Path::new(root, self.cwd(), path)
                 }
                 AtFd::Normal(fd) => {
                     let file = self.with_fd_table(|table| table.get_file(fd))?;
-                    Path::new(sys_root_dentry(), file.dentry(), path)
+                    Path::new(root, file.dentry(), path)
                 }
             }
         };
-
-        let dentry = path.walk(OpenFlags::empty())?;
-        if flags.contains(OpenFlags::O_NOFOLLOW) {
-            Ok(dentry)
-        } else {
-            Path::resolve_dentry(dentry)
-        }
+        Ok(path)
     }
 
     /// Given a path, absolute or relative, will find.
@@ -704,14 +923,104 @@ impl Task {
 }
 
 /// Hold a group of threads which belongs to the same process.
+/// Lock ordering: `ThreadGroup` is locked only briefly to count group
+/// operations and is never held while taking the memory space or fd table
+/// locks, nor across an await. A group operation in flight may take them, and
+/// an execve waiting for it holds none of them.
 pub struct ThreadGroup {
     members: BTreeMap<Tid, Weak<Task>>,
+    /// Group operations in flight, see `Task::begin_group_op`.
+    ops: usize,
+    /// Whether an execve is tearing the group down, see `Task::begin_exec`.
+    exec: bool,
+    /// User and system time of the threads that exited.
+    exited_time: (Duration, Duration),
+    /// User and system time of the children waited for, theirs included.
+    children_time: (Duration, Duration),
+    /// User and system time of the process when it joined its process group,
+    /// which the group does not account.
+    pub group_base: (Duration, Duration),
+    /// Whether the group is stopped by a stop signal. The threads park before
+    /// going back to user, see `Task::park_while_stopped`.
+    stopped: bool,
+    /// A stop or continue of the group which wait4 has not reported yet.
+    job_event: Option<JobEvent>,
+    /// Whether the group is exiting, by exit_group or a fatal signal.
+    exiting: bool,
+}
+
+/// A change of the job control state of a process, reported by wait4 with
+/// `WUNTRACED` and `WCONTINUED` respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobEvent {
+    /// Stopped by the signal.
+    Stopped(Sig),
+    Continued,
+}
+
+impl JobEvent {
+    /// The wait status of the event, as `WIFSTOPPED` and `WIFCONTINUED` tell
+    /// it apart.
+    pub fn wstatus(&self) -> i32 {
+        match self {
+            JobEvent::Stopped(sig) => ((sig.raw() as i32) << 8) | 0x7f,
+            JobEvent::Continued => 0xffff,
+        }
+    }
 }
 
 impl ThreadGroup {
     pub fn new() -> Self {
         Self {
             members: BTreeMap::new(),
+            ops: 0,
+            exec: false,
+            exited_time: (Duration::ZERO, Duration::ZERO),
+            children_time: (Duration::ZERO, Duration::ZERO),
+            group_base: (Duration::ZERO, Duration::ZERO),
+            stopped: false,
+            job_event: None,
+            exiting: false,
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Stop the group, returning whether it was running.
+    pub fn stop(&mut self, sig: Sig) -> bool {
+        if self.stopped {
+            return false;
+        }
+        self.stopped = true;
+        self.job_event = Some(JobEvent::Stopped(sig));
+        true
+    }
+
+    /// Continue the group, returning whether it was stopped.
+    pub fn cont(&mut self) -> bool {
+        if !self.stopped {
+            return false;
+        }
+        self.stopped = false;
+        self.job_event = Some(JobEvent::Continued);
+        true
+    }
+
+    /// Mark the group exiting, returning whether it was not yet, so that the
+    /// exit status is the one of the first exit.
+    pub fn exit(&mut self) -> bool {
+        !core::mem::replace(&mut self.exiting, true)
+    }
+
+    /// Take the event not reported yet, if `WUNTRACED` or `WCONTINUED` ask for
+    /// it as `untraced` and `continued` tell.
+    pub fn take_job_event(&mut self, untraced: bool, continued: bool) -> Option<JobEvent> {
+        match self.job_event? {
+            JobEvent::Stopped(_) if !untraced => None,
+            JobEvent::Continued if !continued => None,
+            _ => self.job_event.take(),
         }
     }
 
@@ -723,11 +1032,53 @@ impl ThreadGroup {
         self.members.insert(task.tid(), Arc::downgrade(&task));
     }
 
+    /// Remove an exited thread, whose time stays with the process.
     pub fn remove(&mut self, task: &Task) {
         self.members.remove(&task.tid());
+        let (utime, stime) = task.time_stat_ref().user_system_time();
+        self.exited_time.0 += utime;
+        self.exited_time.1 += stime;
+    }
+
+    /// User and system time of the threads that exited.
+    pub fn exited_time(&self) -> (Duration, Duration) {
+        self.exited_time
+    }
+
+    /// User and system time of the children waited for, theirs included.
+    pub fn children_time(&self) -> (Duration, Duration) {
+        self.children_time
+    }
+
+    pub fn add_children_time(&mut self, (utime, stime): (Duration, Duration)) {
+        self.children_time.0 += utime;
+        self.children_time.1 += stime;
     }
 
     pub fn iter(&self) -> impl Iterator<Item = Arc<Task>> + '_ {
         self.members.values().map(|t| t.upgrade().unwrap())
     }
 }
+
+/// A group operation in flight, which an execve of the group waits for.
+pub struct GroupOpGuard {
+    thread_group: Shared<ThreadGroup>,
+}
+
+impl Drop for GroupOpGuard {
+    fn drop(&mut self) {
+        self.thread_group.lock().ops -= 1;
+    }
+}
+
+/// An execve of the group in progress, no group operation starts until it is
+/// dropped.
+pub struct ExecGuard {
+    thread_group: Shared<ThreadGroup>,
+}
+
+impl Drop for ExecGuard {
+    fn drop(&mut self) {
+        self.thread_group.lock().exec = false;
+    }
+}
@@ -1,14 +1,106 @@
+use alloc::vec::Vec;
+
 use config::process::INIT_PROC_PID;
-use recycle_allocator::RecycleAllocator;
 use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SysResult};
 
-pub static TID_ALLOCATOR: SpinNoIrqLock<RecycleAllocator> =
-    SpinNoIrqLock::new(RecycleAllocator::new(INIT_PROC_PID));
+pub static TID_ALLOCATOR: SpinNoIrqLock<TidAllocator> = SpinNoIrqLock::new(TidAllocator::new());
 
 pub type Tid = usize;
 pub type Pid = Tid;
 pub type PGid = Tid;
 
+/// Ids below are not handed out again once the ids wrap around, as in linux,
+/// since they are likely held by long running daemons.
+const RESERVED_TIDS: usize = 300;
+
+/// Default of `/proc/sys/kernel/pid_max`.
+pub const PID_MAX_DEFAULT: usize = 32768;
+/// Bounds of `/proc/sys/kernel/pid_max`, as in linux on 64-bit.
+pub const PID_MAX_MIN: usize = RESERVED_TIDS + 1;
+pub const PID_MAX_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Allocator of tids below `pid_max`, with a bitmap of those in use.
+///
+/// Ids are handed out in increasing order from a cursor and wrap around at
+/// `pid_max`, so that an id just freed is not reused at once by something
+/// that a stale holder of the old id would then hit.
+pub struct TidAllocator {
+    /// Bit `i % 64` of word `i / 64` is set if id `i` is in use.
+    used: Vec<u64>,
+    pid_max: usize,
+    /// Where the search for a free id starts.
+    cursor: usize,
+}
+
+impl TidAllocator {
+    pub const fn new() -> Self {
+        Self {
+            used: Vec::new(),
+            pid_max: PID_MAX_DEFAULT,
+            cursor: INIT_PROC_PID,
+        }
+    }
+
+    fn is_used(&self, id: usize) -> bool {
+        self.used
+            .get(id / 64)
+            .is_some_and(|word| word & (1 << (id % 64)) != 0)
+    }
+
+    /// Lowest free id in `from..to`.
+    fn find_free(&self, from: usize, to: usize) -> Option<usize> {
+        let mut id = from;
+        while id < to {
+            // ids below `id` in its word count as used
+            let word = self.used.get(id / 64).copied().unwrap_or(0) | ((1 << (id % 64)) - 1);
+            if word != u64::MAX {
+                let free = id / 64 * 64 + word.trailing_ones() as usize;
+                return (free < to).then_some(free);
+            }
+            id = (id / 64 + 1) * 64;
+        }
+        None
+    }
+
+    /// Allocate the lowest free id from the cursor, wrapping around at
+    /// `pid_max`, or fail with `EAGAIN` if all are in use.
+    pub fn alloc(&mut self) -> SysResult<Tid> {
+        let id = self
+            .find_free(self.cursor, self.pid_max)
+            .or_else(|| self.find_free(RESERVED_TIDS, self.cursor.min(self.pid_max)))
+            .ok_or(SysError::EAGAIN)?;
+        if self.used.len() <= id / 64 {
+            self.used.resize(id / 64 + 1, 0);
+        }
+        self.used[id / 64] |= 1 << (id % 64);
+        self.cursor = id + 1;
+        Ok(id)
+    }
+
+    pub fn dealloc(&mut self, id: Tid) {
+        debug_assert!(self.is_used(id), "tid {id} has been deallocated!");
+        if let Some(word) = self.used.get_mut(id / 64) {
+            *word &= !(1 << (id % 64));
+        }
+    }
+
+    pub fn pid_max(&self) -> usize {
+        self.pid_max
+    }
+
+    /// Ids in use at or above a lowered `pid_max` stay valid until freed.
+    pub fn set_pid_max(&mut self, pid_max: usize) -> SysResult<()> {
+        if !(PID_MAX_MIN..=PID_MAX_LIMIT).contains(&pid_max) {
+            return Err(SysError::EINVAL);
+        }
+        self.pid_max = pid_max;
+        Ok(())
+    }
+}
+
+/// A tid in use, freed once the task holding it is dropped, i.e. after it is
+/// reaped rather than when it becomes a zombie.
 #[derive(Debug)]
 pub struct TidHandle(pub Tid);
 
@@ -18,8 +110,8 @@ impl Drop for TidHandle {
     }
 }
 
-pub fn alloc_tid() -> TidHandle {
-    TidHandle(TID_ALLOCATOR.lock().alloc())
+pub fn alloc_tid() -> SysResult<TidHandle> {
+    Ok(TidHandle(TID_ALLOCATOR.lock().alloc()?))
 }
 
 /// Tid address which may be set by `set_tid_address` syscall.
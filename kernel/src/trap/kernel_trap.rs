@@ -1,5 +1,7 @@
 //! Trap from kernel.
 
+use core::time::Duration;
+
 use arch::{
     interrupts::set_trap_handler_vector,
     time::{get_time_duration, set_next_timer_irq, set_timer_irq},
@@ -10,13 +12,17 @@ use riscv::register::{
     sepc, sstatus, stval, stvec,
 };
 use signal::{Sig, SigDetails, SigInfo};
-use timer::TIMER_MANAGER;
+use systype::SysError;
 
 use crate::{
     mm::PageFaultAccessType,
-    processor::hart::{
-        current_task_ref, local_hart, local_hart_disable_preemptable,
-        local_hart_enable_preemptable, local_hart_preemptable,
+    processor::{
+        hart::{
+            current_task_ref, local_hart, local_hart_disable_preemptable,
+            local_hart_enable_preemptable, local_hart_preemptable,
+        },
+        shutdown,
+        softirq::{irq_exit, raise_softirq, Softirq},
     },
     when_debug,
 };
@@ -32,6 +38,16 @@ fn panic_on_unknown_trap() {
     );
 }
 
+/// Run the softirqs at the end of an interrupt from the kernel. The trap
+/// entry does not save `sepc` and `sstatus`, which a nested trap taken once
+/// interrupts are enabled overwrites, so they are restored here.
+unsafe fn kernel_irq_exit(entry: Duration, sepc: usize) {
+    let sstatus = arch::sstatus::read().bits();
+    irq_exit(entry);
+    arch::sstatus::write(sstatus);
+    sepc::write(sepc);
+}
+
 /// Kernel trap handler
 #[no_mangle]
 pub fn kernel_trap_handler() {
@@ -42,13 +58,21 @@ pub fn kernel_trap_handler() {
     match scause.cause() {
         Trap::Interrupt(i) => match i {
             Interrupt::SupervisorExternal => {
+                let entry = get_time_duration();
                 log::info!("[kernel] receive externel interrupt");
-                driver::get_device_manager_mut().handle_irq();
+                if driver::get_device_manager_mut().handle_irq() {
+                    raise_softirq(Softirq::Device);
+                }
+                unsafe { kernel_irq_exit(entry, sepc) };
             }
             Interrupt::SupervisorTimer => {
+                let entry = get_time_duration();
                 // log::error!("[kernel_trap] receive timer interrupt");
-                TIMER_MANAGER.check();
+                #[cfg(feature = "profile")]
+                super::profile::record_sample(sepc);
                 unsafe { set_next_timer_irq() };
+                raise_softirq(Softirq::Timer);
+                unsafe { kernel_irq_exit(entry, sepc) };
                 #[cfg(feature = "preempt")]
                 {
                     use crate::processor::hart::local_hart;
@@ -68,6 +92,9 @@ pub fn kernel_trap_handler() {
                     local_hart_enable_preemptable();
                 }
             }
+            Interrupt::SupervisorSoft => {
+                shutdown::handle_ipi();
+            }
             _ => panic_on_unknown_trap(),
         },
         Trap::Exception(e) => match e {
@@ -87,19 +114,25 @@ pub fn kernel_trap_handler() {
                 let result = current_task_ref().with_mut_memory_space(|m| {
                     m.handle_page_fault(VirtAddr::from(stval), access_type)
                 });
-                if let Err(_e) = result {
-                    log::warn!(
+                if let Err(e) = result {
+                    log::info!(
                         "[trap_handler] encounter page fault, addr {stval:#x}, instruction {sepc:#x} scause {cause:?}",
                     );
-                    log::warn!("{:x?}", current_task_ref().trap_context_mut());
-                    log::warn!("bad memory access, send SIGSEGV to task");
+                    log::info!("{:x?}", current_task_ref().trap_context_mut());
+                    // a file mapping accessed beyond the end of file
+                    let (sig, code) = if e == SysError::ENXIO {
+                        (Sig::SIGBUS, SigInfo::BUS_ADRERR)
+                    } else {
+                        (Sig::SIGSEGV, SigInfo::KERNEL)
+                    };
+                    log::info!("bad memory access, send {sig:?} to task");
                     current_task_ref().receive_siginfo(
                         SigInfo {
-                            sig: Sig::SIGSEGV,
-                            code: SigInfo::KERNEL,
-                            details: SigDetails::None,
+                            sig,
+                            code,
+                            details: SigDetails::Fault { addr: stval },
                         },
-                        false,
+                        true,
                     );
                 }
             }
@@ -2,12 +2,15 @@
 
 pub mod context;
 pub mod kernel_trap;
+#[cfg(feature = "profile")]
+pub mod profile;
 pub mod user_trap;
 
 use core::arch::global_asm;
 
 use arch::interrupts::set_trap_handler;
 pub use context::TrapContext;
+use systype::SysResult;
 
 global_asm!(include_str!("trap.asm"));
 
@@ -20,6 +23,14 @@ pub fn init() {
     unsafe { set_kernel_trap() };
 }
 
+/// Set the trap handler of the boot hart, the other harts set their own when
+/// they start.
+fn init_boot_hart() -> SysResult<()> {
+    init();
+    Ok(())
+}
+systype::early_initcall!(init_boot_hart);
+
 pub unsafe fn set_kernel_trap() {
     set_trap_handler(__trap_from_kernel as usize);
 }
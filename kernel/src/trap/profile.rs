@@ -0,0 +1,111 @@
+//! Sampling profiler driven by the timer interrupt.
+//!
+//! Each hart records the pc it was interrupted at into a histogram of its own.
+//! The histograms are fixed tables of atomics, so that recording neither
+//! allocates nor takes a lock in interrupt context. A pc that finds no free
+//! slot within a few probes is counted as dropped.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use config::board::MAX_HARTS;
+use vfs::procfs::KernelProfileIf;
+
+use crate::processor::hart::local_hart;
+
+/// Slots of the histogram of each hart, a power of two.
+const PROFILE_SLOTS: usize = 1024;
+/// Slots probed for a pc before the sample is dropped.
+const MAX_PROBES: usize = 8;
+
+struct Slot {
+    /// Sampled pc, zero if the slot is free.
+    pc: AtomicUsize,
+    count: AtomicUsize,
+}
+
+struct Histogram {
+    slots: [Slot; PROFILE_SLOTS],
+    dropped: AtomicUsize,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const SLOT: Slot = Slot {
+            pc: AtomicUsize::new(0),
+            count: AtomicUsize::new(0),
+        };
+        Self {
+            slots: [SLOT; PROFILE_SLOTS],
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    fn record(&self, pc: usize) {
+        // instructions are at least 2 bytes aligned
+        let hash = (pc >> 1).wrapping_mul(0x9e3779b97f4a7c15);
+        for i in 0..MAX_PROBES {
+            let slot = &self.slots[(hash.wrapping_add(i)) % PROFILE_SLOTS];
+            let old = match slot
+                .pc
+                .compare_exchange(0, pc, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => pc,
+                Err(old) => old,
+            };
+            if old == pc {
+                slot.count.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        for slot in self.slots.iter() {
+            slot.count.store(0, Ordering::Relaxed);
+            slot.pc.store(0, Ordering::Relaxed);
+        }
+        self.dropped.store(0, Ordering::Relaxed);
+    }
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const HISTOGRAM: Histogram = Histogram::new();
+static HISTOGRAMS: [Histogram; MAX_HARTS] = [HISTOGRAM; MAX_HARTS];
+
+/// Record a sample of `pc` on the local hart, called from the timer interrupt.
+pub fn record_sample(pc: usize) {
+    HISTOGRAMS[local_hart().hart_id()].record(pc);
+}
+
+struct KernelProfileIfImpl;
+
+#[crate_interface::impl_interface]
+impl KernelProfileIf for KernelProfileIfImpl {
+    fn samples() -> (Vec<(usize, usize)>, usize) {
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut dropped = 0;
+        for histogram in HISTOGRAMS.iter() {
+            for slot in histogram.slots.iter() {
+                let pc = slot.pc.load(Ordering::Relaxed);
+                let count = slot.count.load(Ordering::Relaxed);
+                if pc == 0 || count == 0 {
+                    continue;
+                }
+                *counts.entry(pc).or_default() += count;
+            }
+            dropped += histogram.dropped.load(Ordering::Relaxed);
+        }
+        let mut samples: Vec<(usize, usize)> = counts.into_iter().collect();
+        samples.sort_by(|a, b| b.1.cmp(&a.1));
+        (samples, dropped)
+    }
+
+    fn reset() {
+        for histogram in HISTOGRAMS.iter() {
+            histogram.reset();
+        }
+    }
+}
@@ -16,13 +16,23 @@ use riscv::register::{
 };
 use signal::{Sig, SigDetails, SigInfo};
 use systype::SysError;
-use timer::TIMER_MANAGER;
 
 use super::{set_kernel_trap, TrapContext};
-use crate::{mm::PageFaultAccessType, syscall::Syscall, task::Task, trap::set_user_trap};
+use crate::{
+    mm::PageFaultAccessType,
+    processor::{
+        hart::local_hart,
+        shutdown,
+        softirq::{irq_exit, raise_softirq, Softirq},
+    },
+    syscall::{RestartPolicy, Syscall, SyscallNo},
+    task::Task,
+    trap::set_user_trap,
+};
 
 /// handle an interrupt, exception, or system call from user space
-/// return if it is syscall and has been interrupted
+/// return if it is syscall and has been interrupted, and may be restarted
+/// according to [`RestartPolicy`]
 #[no_mangle]
 pub async fn trap_handler(task: &Arc<Task>) -> bool {
     unsafe { set_kernel_trap() };
@@ -36,7 +46,7 @@ pub async fn trap_handler(task: &Arc<Task>) -> bool {
     log::trace!("[trap_handler] sepc:{sepc:#x}, stval:{stval:#x}");
     unsafe { enable_interrupt() };
 
-    if task.time_stat_ref().need_schedule() && executor::has_task() {
+    if task.time_stat_ref().need_schedule() && executor::has_task(local_hart().hart_id()) {
         log::info!("time slice used up, yield now");
         yield_now().await;
     }
@@ -54,7 +64,8 @@ pub async fn trap_handler(task: &Arc<Task>) -> bool {
                     cx.save_last_user_a0();
                     cx.set_user_a0(ret);
                     if ret == -(SysError::EINTR as isize) as usize {
-                        return true;
+                        return SyscallNo::from_repr(syscall_no)
+                            .is_some_and(|no| no.restart_policy() == RestartPolicy::Restart);
                     }
                 }
                 Exception::StorePageFault
@@ -81,29 +92,43 @@ pub async fn trap_handler(task: &Arc<Task>) -> bool {
                     let result = task.with_mut_memory_space(|m| {
                         m.handle_page_fault(VirtAddr::from(stval), access_type)
                     });
-                    if let Err(_e) = result {
-                        log::warn!(
+                    if let Err(e) = result {
+                        log::info!(
                             "[trap_handler] encounter page fault, addr {stval:#x}, instruction {sepc:#x} scause {cause:?}",
                         );
                         // backtrace::backtrace();
-                        log::warn!("{:x?}", task.trap_context_mut());
+                        log::info!("{:x?}", task.trap_context_mut());
                         // task.with_memory_space(|m| m.print_all());
-                        log::warn!("bad memory access, send SIGSEGV to task");
+                        // a file mapping accessed beyond the end of file
+                        let (sig, code) = if e == SysError::ENXIO {
+                            (Sig::SIGBUS, SigInfo::BUS_ADRERR)
+                        } else {
+                            (Sig::SIGSEGV, SigInfo::KERNEL)
+                        };
+                        log::info!("bad memory access, send {sig:?} to task");
+                        // a fault is for the thread that caused it
                         task.receive_siginfo(
                             SigInfo {
-                                sig: Sig::SIGSEGV,
-                                code: SigInfo::KERNEL,
-                                details: SigDetails::None,
+                                sig,
+                                code,
+                                details: SigDetails::Fault { addr: stval },
                             },
-                            false,
+                            true,
                         );
                     }
                 }
                 Exception::IllegalInstruction => {
-                    log::warn!(
+                    log::info!(
                         "[trap_handler] detected illegal instruction, stval {stval:#x}, sepc {sepc:#x}",
                     );
-                    task.set_terminated();
+                    task.receive_siginfo(
+                        SigInfo {
+                            sig: Sig::SIGILL,
+                            code: SigInfo::ILL_ILLOPC,
+                            details: SigDetails::Fault { addr: sepc },
+                        },
+                        true,
+                    );
                 }
                 e => {
                     log::warn!("Unknown user exception: {:?}", e);
@@ -116,16 +141,32 @@ pub async fn trap_handler(task: &Arc<Task>) -> bool {
                     // NOTE: User may trap into kernel frequently. As a consequence, this timer are
                     // likely not triggered in user mode but rather be triggered in supervisor mode,
                     // which will cause user program running on the cpu for a quite long time.
+                    let entry = get_time_duration();
                     log::trace!("[trap_handler] timer interrupt, sepc {sepc:#x}");
-                    TIMER_MANAGER.check();
+                    #[cfg(feature = "profile")]
+                    super::profile::record_sample(sepc);
                     unsafe { set_next_timer_irq() };
-                    if executor::has_task() {
+                    raise_softirq(Softirq::Timer);
+                    irq_exit(entry);
+                    task.check_cpu_itimers();
+                    if executor::has_task(local_hart().hart_id()) {
                         yield_now().await;
                     }
                 }
                 Interrupt::SupervisorExternal => {
+                    let entry = get_time_duration();
                     log::info!("[kernel] receive externel interrupt");
-                    driver::get_device_manager_mut().handle_irq();
+                    if driver::get_device_manager_mut().handle_irq() {
+                        raise_softirq(Softirq::Device);
+                    }
+                    irq_exit(entry);
+                }
+                Interrupt::SupervisorSoft => {
+                    shutdown::handle_ipi();
+                    // Give the hart back to the executor loop so that it can go offline.
+                    if shutdown::quiesce_requested() {
+                        yield_now().await;
+                    }
                 }
                 _ => {
                     panic!(
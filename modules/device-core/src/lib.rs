@@ -25,9 +25,26 @@ pub enum DeviceType {
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(usize)]
 pub enum DeviceMajor {
+    /// Memory devices, e.g. /dev/null and /dev/zero.
+    Mem = 1,
     Serial = 4,
     Block = 8,
     Net = 9,
+    /// Miscellaneous character devices.
+    Misc = 10,
+}
+
+impl DeviceMajor {
+    pub fn from_raw(major: usize) -> Option<Self> {
+        match major {
+            1 => Some(Self::Mem),
+            4 => Some(Self::Serial),
+            8 => Some(Self::Block),
+            9 => Some(Self::Net),
+            10 => Some(Self::Misc),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -39,6 +56,27 @@ pub struct DevId {
     pub minor: usize,
 }
 
+impl DevId {
+    /// Encode as a `dev_t` the same way as Linux `new_encode_dev`, which is
+    /// what glibc `major()` and `minor()` expect.
+    pub fn encode(&self) -> u64 {
+        let major = self.major as u64;
+        let minor = self.minor as u64;
+        (minor & 0xff) | (major << 8) | ((minor & !0xff) << 12)
+    }
+
+    /// Decode a `dev_t` as Linux `new_decode_dev`, `None` if the major number
+    /// is not one of [`DeviceMajor`].
+    pub fn decode(dev: u64) -> Option<Self> {
+        let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+        let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+        Some(Self {
+            major: DeviceMajor::from_raw(major as usize)?,
+            minor: minor as usize,
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DeviceMeta {
     /// Device id.
@@ -60,8 +98,14 @@ pub trait Device: Sync + Send + DowncastSync {
 
     fn init(&self);
 
+    /// The hard half of an interrupt, run with interrupts disabled, which
+    /// only acknowledges the device and takes what must be taken at once.
     fn handle_irq(&self);
 
+    /// The deferred half of an interrupt, run in a softirq with interrupts
+    /// enabled after `handle_irq`, e.g. to wake the waiters.
+    fn handle_deferred(&self) {}
+
     fn dev_id(&self) -> DevId {
         self.meta().dev_id
     }
@@ -129,6 +173,11 @@ pub trait BlockDevice: Device {
 
     /// Write data from buffer to block
     fn write_block(&self, block_id: usize, buf: &[u8]);
+
+    /// Write back cached blocks and wait until every block written before
+    /// this call is on stable storage. No block written after it may reach the
+    /// device first, so it also serves as a write barrier.
+    fn flush(&self) {}
 }
 
 impl_downcast!(sync BlockDevice);
@@ -5,29 +5,159 @@
 
 extern crate alloc;
 
-use alloc::collections::VecDeque;
-use core::future::Future;
+use alloc::collections::{BTreeMap, VecDeque};
+use core::{
+    future::Future,
+    sync::atomic::{self, AtomicU64},
+};
 
 use async_task::{Runnable, ScheduleInfo, Task, WithInfo};
+use crate_interface::call_interface;
 use sync::mutex::SpinNoIrqLock;
 
 static TASK_QUEUE: TaskQueue = TaskQueue::new();
 
+#[crate_interface::def_interface]
+pub trait KernelExecutorIf {
+    /// Wake `hart_id` up to fetch a task queued for it alone, if it is not the
+    /// local hart and may be idle.
+    fn kick_hart(hart_id: usize);
+}
+
+/// Weight of a task with the default priority, see `spawn_weighted`.
+pub const DEFAULT_WEIGHT: usize = 1024;
+
+/// Virtual time a task of `DEFAULT_WEIGHT` is charged for each poll.
+const POLL_VRUNTIME: u64 = 1024;
+
+/// Queue of tasks that yielded. Each task owns a virtual clock that advances
+/// by `POLL_VRUNTIME * DEFAULT_WEIGHT / weight` every time it is queued, and
+/// the task with the smallest clock is picked first, so a task with twice the
+/// weight gets polled about twice as often.
+struct FairQueue {
+    /// Keyed by the virtual time a runnable was queued at, and a sequence
+    /// number to keep tasks with the same time in FIFO order.
+    queue: BTreeMap<(u64, u64), Runnable>,
+    min_vruntime: u64,
+    seq: u64,
+}
+
+impl FairQueue {
+    const fn new() -> Self {
+        Self {
+            queue: BTreeMap::new(),
+            min_vruntime: 0,
+            seq: 0,
+        }
+    }
+
+    fn push(&mut self, runnable: Runnable, vruntime: &AtomicU64, weight: usize) {
+        // A task that slept for long must not starve the others by catching
+        // up from its old clock.
+        let start = vruntime
+            .load(atomic::Ordering::Relaxed)
+            .max(self.min_vruntime);
+        let delta = POLL_VRUNTIME * DEFAULT_WEIGHT as u64 / weight.max(1) as u64;
+        vruntime.store(start + delta.max(1), atomic::Ordering::Relaxed);
+        self.seq += 1;
+        self.queue.insert((start, self.seq), runnable);
+    }
+
+    /// Queue a runnable at the current virtual time, without charging it.
+    fn push_now(&mut self, runnable: Runnable) {
+        self.seq += 1;
+        self.queue.insert((self.min_vruntime, self.seq), runnable);
+    }
+
+    /// Virtual time the next task to pop was queued at.
+    fn peek(&self) -> Option<(u64, u64)> {
+        self.queue.first_key_value().map(|(key, _)| *key)
+    }
+
+    fn pop(&mut self) -> Option<Runnable> {
+        let ((vruntime, _), runnable) = self.queue.pop_first()?;
+        self.min_vruntime = vruntime;
+        Some(runnable)
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+
 struct TaskQueue {
-    normal: SpinNoIrqLock<VecDeque<Runnable>>,
+    normal: SpinNoIrqLock<FairQueue>,
     prior: SpinNoIrqLock<VecDeque<Runnable>>,
+    /// Tasks that may not run on every hart, each queued on a hart it may run
+    /// on, keyed by hart id. Only that hart fetches them.
+    local: SpinNoIrqLock<BTreeMap<usize, FairQueue>>,
 }
 
 impl TaskQueue {
     pub const fn new() -> Self {
         Self {
-            normal: SpinNoIrqLock::new(VecDeque::new()),
+            normal: SpinNoIrqLock::new(FairQueue::new()),
             prior: SpinNoIrqLock::new(VecDeque::new()),
+            local: SpinNoIrqLock::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn push_local(
+        &self,
+        hart_id: usize,
+        runnable: Runnable,
+        vruntime: &AtomicU64,
+        weight: usize,
+    ) {
+        self.local
+            .lock()
+            .entry(hart_id)
+            .or_insert_with(FairQueue::new)
+            .push(runnable, vruntime, weight);
+        // no other hart fetches it, so the target must not sleep on it
+        call_interface!(KernelExecutorIf::kick_hart(hart_id));
+    }
+
+    /// Fetch a task for `hart_id`: a woken one first, then the one queued
+    /// earliest in virtual time of its local queue and the global one.
+    pub fn fetch_for(&self, hart_id: usize) -> Option<Runnable> {
+        if let Some(runnable) = self.prior.lock().pop_front() {
+            return Some(runnable);
+        }
+        let mut local = self.local.lock();
+        let mut normal = self.normal.lock();
+        match local.get_mut(&hart_id) {
+            Some(queue)
+                if queue
+                    .peek()
+                    .is_some_and(|key| normal.peek().map_or(true, |n| key <= n)) =>
+            {
+                queue.pop()
+            }
+            _ => normal.pop(),
+        }
+    }
+
+    /// Move the tasks queued on `hart_id` to the global queue.
+    pub fn release_local(&self, hart_id: usize) {
+        let Some(mut local) = self.local.lock().remove(&hart_id) else {
+            return;
+        };
+        let mut normal = self.normal.lock();
+        while let Some(runnable) = local.pop() {
+            normal.push_now(runnable);
         }
     }
 
-    pub fn push_normal(&self, runnable: Runnable) {
-        self.normal.lock().push_back(runnable);
+    pub fn local_len(&self, hart_id: usize) -> usize {
+        self.local
+            .lock()
+            .get(&hart_id)
+            .map_or(0, |local| local.len())
+    }
+
+    pub fn push_normal(&self, runnable: Runnable, vruntime: &AtomicU64, weight: usize) {
+        self.normal.lock().push(runnable, vruntime, weight);
     }
 
     pub fn push_prior(&self, runnable: Runnable) {
@@ -35,7 +165,7 @@ impl TaskQueue {
     }
 
     pub fn fetch_normal(&self) -> Option<Runnable> {
-        self.normal.lock().pop_front()
+        self.normal.lock().pop()
     }
 
     pub fn fetch_prior(&self) -> Option<Runnable> {
@@ -46,7 +176,7 @@ impl TaskQueue {
         self.prior
             .lock()
             .pop_front()
-            .or_else(|| self.normal.lock().pop_front())
+            .or_else(|| self.normal.lock().pop())
     }
 
     pub fn len(&self) -> usize {
@@ -68,10 +198,37 @@ where
     F: Future + Send + 'static,
     F::Output: Send + 'static,
 {
+    spawn_weighted(future, || DEFAULT_WEIGHT)
+}
+
+/// Add a task into task queue, `weight` is consulted every time the task
+/// yields to decide how soon it is polled again relative to other tasks.
+pub fn spawn_weighted<F, W>(future: F, weight: W) -> (Runnable, Task<F::Output>)
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+    W: Fn() -> usize + Send + Sync + 'static,
+{
+    spawn_pinned(future, weight, || None)
+}
+
+/// Add a task into task queue like `spawn_weighted`, `hart` is consulted
+/// every time the task is queued and gives the hart it must be polled on, if
+/// it may not run on every hart.
+pub fn spawn_pinned<F, W, H>(future: F, weight: W, hart: H) -> (Runnable, Task<F::Output>)
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+    W: Fn() -> usize + Send + Sync + 'static,
+    H: Fn() -> Option<usize> + Send + Sync + 'static,
+{
+    let vruntime = AtomicU64::new(0);
     let schedule = move |runnable: Runnable, info: ScheduleInfo| {
-        if info.woken_while_running {
+        if let Some(hart_id) = hart() {
+            TASK_QUEUE.push_local(hart_id, runnable, &vruntime, weight());
+        } else if info.woken_while_running {
             // i.e `yield_now()`
-            TASK_QUEUE.push_normal(runnable);
+            TASK_QUEUE.push_normal(runnable, &vruntime, weight());
         } else {
             // i.e. woken up by some signal
             TASK_QUEUE.push_prior(runnable);
@@ -89,8 +246,9 @@ pub fn run_until_idle() -> usize {
     len
 }
 
-pub fn run_one() {
-    if let Some(task) = TASK_QUEUE.fetch() {
+/// Run one task that may run on `hart_id`.
+pub fn run_one(hart_id: usize) {
+    if let Some(task) = TASK_QUEUE.fetch_for(hart_id) {
         task.run();
     }
 }
@@ -101,8 +259,15 @@ pub fn run_prior_until_idle() {
     }
 }
 
-pub fn has_task() -> bool {
-    TASK_QUEUE.len() >= 1
+/// Whether there is a task that may run on `hart_id`.
+pub fn has_task(hart_id: usize) -> bool {
+    TASK_QUEUE.len() + TASK_QUEUE.local_len(hart_id) >= 1
+}
+
+/// Hand the tasks queued on `hart_id` over to the other harts, as it is
+/// going offline.
+pub fn release_local(hart_id: usize) {
+    TASK_QUEUE.release_local(hart_id)
 }
 
 pub fn has_prior_task() -> bool {
@@ -1,4 +1,9 @@
-use alloc::{ffi::CString, sync::Arc, vec, vec::Vec};
+use alloc::{
+    ffi::CString,
+    sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
 use core::fmt::Error;
 
 use lwext4_rust::{
@@ -7,13 +12,14 @@ use lwext4_rust::{
 };
 use systype::{SysError, SysResult};
 use vfs_core::{
-    Dentry, DentryMeta, DentryState, File, FileSystemType, FileSystemTypeMeta, Inode, InodeMode,
-    InodeType, MountFlags, OpenFlags, Path, RenameFlags, StatFs, SuperBlock, SuperBlockMeta,
+    Dentry, DentryMeta, DentryState, DirFile, File, FileSystemType, FileSystemTypeMeta, Inode,
+    InodeMode, InodeType, MountFlags, OpenFlags, Path, RenameFlags, StatFs, SuperBlock,
+    SuperBlockMeta,
 };
 
 use crate::{
-    file::Ext4FileFile, inode::Ext4FileInode, readlink, Ext4DirFile, Ext4DirInode, Ext4LinkFile,
-    Ext4LinkInode, LwExt4Dir, LwExt4File,
+    file::Ext4FileFile, inode::Ext4FileInode, readlink, Ext4DirInode, Ext4LinkFile, Ext4LinkInode,
+    LwExt4Dir, LwExt4File,
 };
 
 pub struct Ext4Dentry {
@@ -50,7 +56,7 @@ impl Dentry for Ext4Dentry {
                     .inode()?
                     .downcast_arc::<Ext4DirInode>()
                     .unwrap_or_else(|_| unreachable!());
-                Ok(Ext4DirFile::new(self, inode))
+                Ok(DirFile::new(self, inode))
             }
             InodeType::File => {
                 let inode = self
@@ -77,7 +83,7 @@ impl Dentry for Ext4Dentry {
         let path = sub_dentry.path();
         if lwext4_check_inode_exist(&path, InodeTypes::EXT4_DE_DIR) {
             let new_file = LwExt4Dir::open(&path).map_err(SysError::from_i32)?;
-            sub_dentry.set_inode(Ext4DirInode::new(sb, new_file))
+            sub_dentry.set_inode(Ext4DirInode::new(sb, new_file, &path))
         } else if lwext4_check_inode_exist(&path, InodeTypes::EXT4_DE_REG_FILE) {
             let new_file =
                 LwExt4File::open(&path, OpenFlags::empty().bits()).map_err(SysError::from_i32)?;
@@ -103,17 +109,19 @@ impl Dentry for Ext4Dentry {
         let new_inode: Arc<dyn Inode> = match mode.to_type() {
             InodeType::Dir => {
                 let new_dir = LwExt4Dir::create(&path).map_err(SysError::from_i32)?;
-                Ext4DirInode::new(sb, new_dir)
+                Ext4DirInode::new(sb, new_dir, &path)
             }
             InodeType::File => {
-                let new_file = LwExt4File::open(
-                    &path,
-                    (OpenFlags::O_RDWR | OpenFlags::O_CREAT | OpenFlags::O_TRUNC).bits(),
-                )
-                .map_err(SysError::from_i32)?;
+                // NOTE: no O_TRUNC, a racing create of the same name may have made the
+                // file and written to it already
+                let new_file =
+                    LwExt4File::open(&path, (OpenFlags::O_RDWR | OpenFlags::O_CREAT).bits())
+                        .map_err(SysError::from_i32)?;
                 Ext4FileInode::new(sb, new_file)
             }
-            _ => todo!(),
+            // NOTE: special files such as FIFOs can only live in memory file
+            // systems for now
+            _ => return Err(SysError::EPERM),
         };
         sub_dentry.set_inode(new_inode);
         Ok(sub_dentry)
@@ -138,9 +146,17 @@ impl Dentry for Ext4Dentry {
     fn base_rename_to(self: Arc<Self>, new: Arc<dyn Dentry>, flags: RenameFlags) -> SysResult<()> {
         // TODO: lwext4_rust does not support RENAME_EXCHANGE, it remove old path when
         // renaming
-        let old_itype = self.inode()?.itype();
-        if !new.is_negetive() {
-            let new_itype = new.inode()?.itype();
+        if flags.contains(RenameFlags::RENAME_EXCHANGE) {
+            return Err(SysError::EINVAL);
+        }
+        let old = self.into_dyn();
+        if Arc::ptr_eq(&old, &new) {
+            return Ok(());
+        }
+        let old_inode = old.inode()?;
+        let old_itype = old_inode.itype();
+        if let Ok(new_inode) = new.inode() {
+            let new_itype = new_inode.itype();
             if new_itype != old_itype {
                 return match (old_itype, new_itype) {
                     (InodeType::File, InodeType::Dir) => Err(SysError::EISDIR),
@@ -154,23 +170,26 @@ impl Dentry for Ext4Dentry {
                 InodeType::SymLink => todo!(),
                 _ => todo!(),
             };
+            new.clear_inode();
         }
         match old_itype {
             InodeType::Dir => {
-                lwext4_mvdir(&self.path(), &new.path());
+                lwext4_mvdir(&old.path(), &new.path());
             }
             InodeType::File => {
-                lwext4_mvfile(&self.path(), &new.path());
+                lwext4_mvfile(&old.path(), &new.path());
             }
             InodeType::SymLink => todo!(),
             _ => unimplemented!(),
         }
-        new.set_inode(self.inode()?);
-        if flags.contains(RenameFlags::RENAME_EXCHANGE) {
-            self.set_inode(new.inode()?);
-        } else {
-            self.clear_inode();
-        }
+        // NOTE: the cached children still name the old path, they will be looked up
+        // again below `new` while the old name is left as a negative dentry
+        old.meta()
+            .children
+            .lock()
+            .retain(|_, child| !Weak::ptr_eq(&child.meta().super_block, &old.meta().super_block));
+        old.clear_inode();
+        new.set_inode(old_inode);
         Ok(())
     }
 
@@ -16,8 +16,8 @@ use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{DirEntry, File, FileMeta, Inode, InodeType, OpenFlags};
 
 use crate::{
-    dentry::Ext4Dentry, inode::Ext4FileInode, map_ext4_type, Ext4DirInode, Ext4LinkInode,
-    LwExt4Dir, LwExt4File, Shared,
+    dentry::Ext4Dentry, fs::lwext4_sync_metadata, inode::Ext4FileInode, map_ext4_type,
+    Ext4DirInode, Ext4LinkInode, LwExt4Dir, LwExt4File, Shared,
 };
 
 pub struct Ext4FileFile {
@@ -67,8 +67,17 @@ impl File for Ext4FileFile {
         }
     }
 
+    /// lwext4 writes file data straight to the device, nothing is left to
+    /// write back here.
     fn flush(&self) -> SysResult<usize> {
-        todo!()
+        Ok(0)
+    }
+
+    /// Write the metadata after the data it refers to has reached the device.
+    /// lwext4 can not write the inode alone, the size is written along with
+    /// the rest of metadata even if `datasync`.
+    fn base_fsync(&self, _datasync: bool) -> SysResult<()> {
+        lwext4_sync_metadata(self.super_block().as_ref())
     }
 
     fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
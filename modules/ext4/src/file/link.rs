@@ -58,10 +58,6 @@ impl File for Ext4LinkFile {
         Err(SysError::ENOTDIR)
     }
 
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
-
     async fn readlink(&self, buf: &mut [u8]) -> SysResult<usize> {
         lwext4_readlink(&self.dentry().path(), buf).map_err(SysError::from_i32)
     }
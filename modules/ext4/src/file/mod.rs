@@ -1,7 +1,5 @@
-mod dir;
 mod file;
 mod link;
 
-pub use dir::*;
 pub use file::*;
 pub use link::*;
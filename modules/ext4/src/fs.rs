@@ -1,11 +1,20 @@
-use alloc::sync::Arc;
+use alloc::{sync::Arc, vec};
+use core::ffi::CStr;
 
 use device_core::BlockDevice;
-use lwext4_rust::{Ext4BlockWrapper, InodeTypes};
+use lwext4_rust::{
+    bindings::{
+        ext4_atime_get, ext4_atime_set, ext4_cache_flush, ext4_ctime_get, ext4_inode,
+        ext4_mode_get, ext4_mode_set, ext4_mount_point_stats, ext4_mount_stats, ext4_mtime_get,
+        ext4_mtime_set, ext4_owner_get, ext4_owner_set, ext4_raw_inode_fill,
+    },
+    Ext4BlockWrapper, InodeTypes,
+};
 use systype::{SysError, SysResult};
+use time::timespec::TimeSpec;
 use vfs_core::{
-    Dentry, FileSystemType, FileSystemTypeMeta, InodeType, MountFlags, OpenFlags, StatFs,
-    SuperBlock, SuperBlockMeta,
+    Dentry, FileSystemFlags, FileSystemType, FileSystemTypeMeta, InodeMode, InodeType, MountFlags,
+    OpenFlags, StatFs, SuperBlock, SuperBlockMeta,
 };
 
 use crate::{disk::Disk, Ext4Dentry, Ext4DirInode, Ext4FileInode, LwExt4Dir, LwExt4File};
@@ -22,6 +31,8 @@ impl Ext4FsType {
     }
 }
 
+vfs_core::register_fs!(Ext4FsType::new);
+
 impl FileSystemType for Ext4FsType {
     fn meta(&self) -> &FileSystemTypeMeta {
         &self.meta
@@ -36,26 +47,32 @@ impl FileSystemType for Ext4FsType {
     ) -> SysResult<Arc<dyn Dentry>> {
         debug_assert!(dev.is_some());
         let sb = Ext4SuperBlock::new(SuperBlockMeta::new(dev, self.clone()));
-        let mut root_ext4_dir = LwExt4Dir::open("/").map_err(SysError::from_i32)?;
-        let root_inode = Ext4DirInode::new(sb.clone(), root_ext4_dir);
-        let root_dentry = Ext4Dentry::new(name, sb.clone(), parent.clone()).into_dyn();
-        root_dentry.set_inode(root_inode);
-        if let Some(parent) = parent {
-            parent.insert(root_dentry.clone());
-        }
-        sb.set_root_dentry(root_dentry.clone());
-        self.insert_sb(&root_dentry.path(), sb);
-        Ok(root_dentry)
+        let root_ext4_dir = LwExt4Dir::open("/").map_err(SysError::from_i32)?;
+        let root_inode = Ext4DirInode::new(sb.clone(), root_ext4_dir, "/");
+        let root_dentry = Ext4Dentry::new(name, sb.clone(), parent);
+        Ok(self.mount_root(sb, root_dentry, root_inode))
     }
 
-    fn kill_sb(&self, _sb: Arc<dyn SuperBlock>) -> SysResult<()> {
-        todo!()
+    fn fs_flags(&self) -> FileSystemFlags {
+        FileSystemFlags::REQUIRES_DEV
+    }
+
+    /// The super block is synced by the caller, and lwext4 is unmounted when
+    /// the last open file drops it.
+    fn kill_sb(&self, sb: Arc<dyn SuperBlock>) -> SysResult<()> {
+        lwext4_sync_metadata(sb.as_ref())?;
+        self.remove_sb(&sb);
+        Ok(())
     }
 }
 
+/// Magic number reported by statfs, as in linux.
+const EXT4_SUPER_MAGIC: i64 = 0xef53;
+
 pub struct Ext4SuperBlock {
     meta: SuperBlockMeta,
     inner: Ext4BlockWrapper<Disk>,
+    block_size: usize,
 }
 
 unsafe impl Send for Ext4SuperBlock {}
@@ -64,23 +81,170 @@ unsafe impl Sync for Ext4SuperBlock {}
 impl Ext4SuperBlock {
     pub fn new(meta: SuperBlockMeta) -> Arc<Self> {
         let blk_dev = meta.device.as_ref().unwrap().clone();
+        let block_size = read_block_size(blk_dev.as_ref());
         let disk = Disk::new(blk_dev);
         let inner =
             Ext4BlockWrapper::<Disk>::new(disk).expect("failed to initialize EXT4 filesystem");
-        Arc::new(Self { meta, inner })
+        Arc::new(Self {
+            meta,
+            inner,
+            block_size,
+        })
+    }
+}
+
+/// Mount point under which lwext4_rust registers the file system.
+const LWEXT4_MOUNT_POINT: &[u8] = b"/\0";
+
+/// Write back the block cache of lwext4, which holds inodes, bitmaps and
+/// directory blocks. File data does not go through it: lwext4 writes file
+/// blocks straight to the device.
+///
+/// lwext4_rust does not expose the journal of lwext4, so file data is ordered
+/// before the metadata referring to it by a device barrier on each side.
+pub(crate) fn lwext4_sync_metadata(sb: &dyn SuperBlock) -> SysResult<()> {
+    let device = sb.meta().device.as_ref();
+    if let Some(device) = device {
+        device.flush();
+    }
+    match unsafe { ext4_cache_flush(LWEXT4_MOUNT_POINT.as_ptr().cast()) } {
+        0 => {}
+        err => return Err(SysError::from_i32(err)),
+    }
+    if let Some(device) = device {
+        device.flush();
+    }
+    Ok(())
+}
+
+/// Read the access, modification and change times of the file at `path`.
+pub(crate) fn lwext4_get_times(path: &CStr) -> SysResult<[TimeSpec; 3]> {
+    let getters = [ext4_atime_get, ext4_mtime_get, ext4_ctime_get];
+    let mut times = [TimeSpec::default(); 3];
+    for (time, get) in times.iter_mut().zip(getters) {
+        let mut sec = 0;
+        match unsafe { get(path.as_ptr().cast(), &mut sec) } {
+            0 => time.tv_sec = sec as usize,
+            err => return Err(SysError::from_i32(err)),
+        }
+    }
+    Ok(times)
+}
+
+/// Write the access and modification times of the file at `path`. The on
+/// disk times have a precision of seconds.
+pub(crate) fn lwext4_set_times(path: &CStr, atime: TimeSpec, mtime: TimeSpec) -> SysResult<()> {
+    match unsafe { ext4_atime_set(path.as_ptr().cast(), atime.tv_sec as u32) } {
+        0 => {}
+        err => return Err(SysError::from_i32(err)),
+    }
+    match unsafe { ext4_mtime_set(path.as_ptr().cast(), mtime.tv_sec as u32) } {
+        0 => Ok(()),
+        err => Err(SysError::from_i32(err)),
     }
 }
 
+/// Read the mode of the file at `path`, type bits included.
+pub(crate) fn lwext4_get_mode(path: &CStr) -> SysResult<InodeMode> {
+    let mut mode = 0;
+    match unsafe { ext4_mode_get(path.as_ptr().cast(), &mut mode) } {
+        0 => Ok(InodeMode::from_bits_truncate(mode)),
+        err => Err(SysError::from_i32(err)),
+    }
+}
+
+/// Write the permission bits of `mode` to the file at `path`, lwext4 keeps the
+/// type bits on disk.
+pub(crate) fn lwext4_set_mode(path: &CStr, mode: InodeMode) -> SysResult<()> {
+    match unsafe { ext4_mode_set(path.as_ptr().cast(), mode.bits()) } {
+        0 => Ok(()),
+        err => Err(SysError::from_i32(err)),
+    }
+}
+
+/// Read the link count and the size of the inode at `path` as they are on disk.
+pub(crate) fn lwext4_get_nlink_size(path: &CStr) -> SysResult<(usize, usize)> {
+    let mut ino = 0;
+    let mut inode: ext4_inode = unsafe { core::mem::zeroed() };
+    match unsafe { ext4_raw_inode_fill(path.as_ptr().cast(), &mut ino, &mut inode) } {
+        0 => Ok((
+            inode.links_count as usize,
+            inode.size_lo as usize | (inode.size_hi as usize) << 32,
+        )),
+        err => Err(SysError::from_i32(err)),
+    }
+}
+
+/// Read the user and group IDs of the owner of the file at `path`.
+pub(crate) fn lwext4_get_owner(path: &CStr) -> SysResult<(u32, u32)> {
+    let (mut uid, mut gid) = (0, 0);
+    match unsafe { ext4_owner_get(path.as_ptr().cast(), &mut uid, &mut gid) } {
+        0 => Ok((uid, gid)),
+        err => Err(SysError::from_i32(err)),
+    }
+}
+
+/// Write the user and group IDs of the owner of the file at `path`.
+pub(crate) fn lwext4_set_owner(path: &CStr, uid: u32, gid: u32) -> SysResult<()> {
+    match unsafe { ext4_owner_set(path.as_ptr().cast(), uid, gid) } {
+        0 => Ok(()),
+        err => Err(SysError::from_i32(err)),
+    }
+}
+
+/// Byte offset of the on disk super block.
+const EXT4_SUPER_BLOCK_OFFSET: usize = 1024;
+/// Byte offset of `s_log_block_size` in the super block.
+const EXT4_LOG_BLOCK_SIZE_OFFSET: usize = 24;
+
+/// Read the block size from the on disk super block, which lwext4 does not
+/// expose.
+fn read_block_size(blk_dev: &dyn BlockDevice) -> usize {
+    let offset = EXT4_SUPER_BLOCK_OFFSET + EXT4_LOG_BLOCK_SIZE_OFFSET;
+    let dev_block_size = blk_dev.block_size();
+    let mut buf = vec![0; dev_block_size];
+    blk_dev.read_block(offset / dev_block_size, &mut buf);
+    let offset = offset % dev_block_size;
+    let log_block_size = u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap());
+    1024 << log_block_size
+}
+
 impl SuperBlock for Ext4SuperBlock {
     fn meta(&self) -> &SuperBlockMeta {
         &self.meta
     }
 
     fn stat_fs(&self) -> SysResult<StatFs> {
-        todo!()
+        let mut stats: ext4_mount_stats = unsafe { core::mem::zeroed() };
+        match unsafe { ext4_mount_point_stats(LWEXT4_MOUNT_POINT.as_ptr().cast(), &mut stats) } {
+            0 => {}
+            err => return Err(SysError::from_i32(err)),
+        }
+        Ok(StatFs {
+            f_type: EXT4_SUPER_MAGIC,
+            f_bsize: stats.block_size as i64,
+            f_blocks: stats.blocks_count,
+            f_bfree: stats.free_blocks_count,
+            f_bavail: stats.free_blocks_count,
+            f_files: stats.inodes_count as u64,
+            f_ffree: stats.free_inodes_count as u64,
+            f_fsid: [0, 0],
+            f_namelen: 255,
+            f_frsize: stats.block_size as isize,
+            f_flags: 0,
+            f_spare: [0; 4],
+        })
     }
 
+    /// Write back the page caches, then the block cache of lwext4 holding the
+    /// metadata. The device completes writes before returning, so `wait` makes
+    /// no difference.
     fn sync_fs(&self, _wait: isize) -> systype::SysResult<()> {
-        todo!()
+        (self as &dyn SuperBlock).sync_cached_files()?;
+        lwext4_sync_metadata(self)
+    }
+
+    fn block_size(&self) -> usize {
+        self.block_size
     }
 }
@@ -1,13 +1,20 @@
-use alloc::sync::Arc;
+use alloc::{ffi::CString, sync::Arc};
 
+use async_utils::block_on;
 use lwext4_rust::{
     bindings::{ext4_flink, O_RDONLY, SEEK_CUR, SEEK_SET},
     InodeTypes,
 };
 use systype::{SysError, SysResult};
-use vfs_core::{Inode, InodeMeta, InodeMode, InodeType, Stat, SuperBlock};
+use vfs_core::{
+    Dentry, DentryState, DirOps, Inode, InodeMeta, InodeMode, InodeType, OpenFlags, SuperBlock,
+};
 
-use crate::{map_ext4_err, map_ext4_type, LwExt4Dir, LwExt4File, Mutex, Shared};
+use crate::{
+    fs::{lwext4_get_nlink_size, lwext4_sync_metadata},
+    map_ext4_err, map_ext4_type, readlink, Ext4FileInode, Ext4LinkInode, LwExt4Dir, LwExt4File,
+    Mutex, Shared,
+};
 
 pub struct Ext4DirInode {
     meta: InodeMeta,
@@ -18,9 +25,27 @@ unsafe impl Send for Ext4DirInode {}
 unsafe impl Sync for Ext4DirInode {}
 
 impl Ext4DirInode {
-    pub fn new(super_block: Arc<dyn SuperBlock>, dir: LwExt4Dir) -> Arc<Self> {
+    /// An lwext4 directory does not know its path, so the link count and the
+    /// size are read from the inode at `path` here.
+    pub fn new(super_block: Arc<dyn SuperBlock>, dir: LwExt4Dir, path: &str) -> Arc<Self> {
+        let meta = InodeMeta::new(
+            InodeMode::from_type(InodeType::Dir),
+            super_block.clone(),
+            super_block.block_size(),
+        );
+        let info = CString::new(path)
+            .map_err(|_| SysError::EINVAL)
+            .and_then(|path| lwext4_get_nlink_size(&path));
+        match info {
+            Ok((nlink, size)) => {
+                let mut inner = meta.inner.lock();
+                inner.nlink = nlink;
+                inner.size = size;
+            }
+            Err(err) => log::warn!("[Ext4DirInode::new] failed to get nlink and size: {err:?}"),
+        }
         let inode = Arc::new(Self {
-            meta: InodeMeta::new(InodeMode::from_type(InodeType::Dir), super_block.clone(), 0),
+            meta,
             dir: Arc::new(Mutex::new(dir)),
         });
         inode
@@ -32,30 +57,6 @@ impl Inode for Ext4DirInode {
         &self.meta
     }
 
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: inner.nlink as _,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
-
     fn base_truncate(&self, len: usize) -> SysResult<()> {
         Err(SysError::EINVAL)
     }
@@ -63,4 +64,69 @@ impl Inode for Ext4DirInode {
     fn base_get_blk_idx(&self, offset: usize) -> SysResult<usize> {
         Err(SysError::EINVAL)
     }
+
+    // NOTE: an lwext4 directory does not know its path, so the mode is kept in
+    // memory only, as the times are
+    fn base_set_mode(&self, _mode: InodeMode) -> SysResult<()> {
+        Ok(())
+    }
+}
+
+impl DirOps for Ext4DirInode {
+    fn load_dir(&self, dentry: &Arc<dyn Dentry>) -> SysResult<()> {
+        let sb = dentry.super_block();
+        // NOTE: taken before the lwext4 directory, as `base_create` does
+        let _guard = block_on(dentry.meta().child_lock.lock());
+        let mut dir = self.dir.lock();
+        let iters = dir.lwext4_dir_entries(&dentry.path()).unwrap();
+
+        while let Some(dirent) = dir.next() {
+            let name = CString::new(dirent.name).map_err(|_| SysError::EINVAL)?;
+            let name = name.to_str().unwrap();
+            // "." and ".." are not cached as children, `DirFile` makes them up
+            if name == "." || name == ".." {
+                continue;
+            }
+            let itype = InodeTypes::from(dirent.type_ as usize);
+            if !matches!(
+                itype,
+                InodeTypes::EXT4_DE_REG_FILE
+                    | InodeTypes::EXT4_DE_DIR
+                    | InodeTypes::EXT4_DE_SYMLINK
+            ) {
+                log::warn!("[Ext4DirInode::load_dir] skip {name} of unsupported type {itype:?}");
+                continue;
+            }
+            let sub_dentry = dentry.get_child_or_create(name);
+            // already looked up or created
+            if !sub_dentry.is_negetive() {
+                continue;
+            }
+            let new_inode: Arc<dyn Inode> = match itype {
+                InodeTypes::EXT4_DE_REG_FILE => {
+                    let ext4_file =
+                        LwExt4File::open(&(sub_dentry.path()), OpenFlags::O_RDWR.bits())
+                            .map_err(SysError::from_i32)?;
+                    Ext4FileInode::new(sb.clone(), ext4_file)
+                }
+                InodeTypes::EXT4_DE_DIR => {
+                    let ext4_dir =
+                        LwExt4Dir::open(&(sub_dentry.path())).map_err(SysError::from_i32)?;
+                    Ext4DirInode::new(sb.clone(), ext4_dir, &sub_dentry.path())
+                }
+                _ => {
+                    let target = readlink(&sub_dentry.path())?;
+                    Ext4LinkInode::new(target.to_str().unwrap(), sb.clone())
+                }
+            };
+            sub_dentry.set_inode(new_inode);
+            sub_dentry.set_state(DentryState::Sync);
+        }
+
+        Ok(())
+    }
+
+    fn fsync_dir(&self) -> SysResult<()> {
+        lwext4_sync_metadata(self.meta().super_block.upgrade().unwrap().as_ref())
+    }
 }
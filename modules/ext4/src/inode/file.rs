@@ -1,13 +1,21 @@
 use alloc::sync::Arc;
 
+use config::mm::PAGE_SIZE;
 use lwext4_rust::{
     bindings::{ext4_flink, O_RDONLY, SEEK_CUR, SEEK_SET},
     InodeTypes,
 };
 use systype::{SysError, SysResult};
-use vfs_core::{Inode, InodeMeta, InodeMode, InodeType, Stat, SuperBlock};
+use time::timespec::TimeSpec;
+use vfs_core::{Inode, InodeMeta, InodeMode, InodeType, SuperBlock};
 
-use crate::{map_ext4_err, map_ext4_type, LwExt4Dir, LwExt4File, Mutex, Shared};
+use crate::{
+    fs::{
+        lwext4_get_mode, lwext4_get_nlink_size, lwext4_get_owner, lwext4_get_times,
+        lwext4_set_mode, lwext4_set_owner, lwext4_set_times,
+    },
+    map_ext4_err, map_ext4_type, LwExt4Dir, LwExt4File, Mutex, Shared,
+};
 
 pub struct Ext4FileInode {
     meta: InodeMeta,
@@ -22,12 +30,41 @@ impl Ext4FileInode {
         let mut file = file;
         let size = file.size();
         let size: usize = size.try_into().unwrap();
+        let meta = InodeMeta::new(
+            InodeMode::from_type(InodeType::File),
+            super_block.clone(),
+            size,
+        );
+        match lwext4_get_times(&file.get_path()) {
+            Ok([atime, mtime, ctime]) => {
+                let mut inner = meta.inner.lock();
+                inner.atime = atime;
+                inner.mtime = mtime;
+                inner.ctime = ctime;
+            }
+            Err(err) => log::warn!("[Ext4FileInode::new] failed to get times: {err:?}"),
+        }
+        match lwext4_get_mode(&file.get_path()) {
+            Ok(mode) => {
+                meta.inner.lock().mode = InodeMode::FILE | (mode - InodeMode::TYPE_MASK);
+            }
+            Err(err) => log::warn!("[Ext4FileInode::new] failed to get mode: {err:?}"),
+        }
+        match lwext4_get_owner(&file.get_path()) {
+            Ok((uid, gid)) => {
+                let mut inner = meta.inner.lock();
+                inner.uid = uid;
+                inner.gid = gid;
+            }
+            Err(err) => log::warn!("[Ext4FileInode::new] failed to get owner: {err:?}"),
+        }
+        // other hard links to the file may be on disk already
+        match lwext4_get_nlink_size(&file.get_path()) {
+            Ok((nlink, _)) => meta.inner.lock().nlink = nlink,
+            Err(err) => log::warn!("[Ext4FileInode::new] failed to get nlink: {err:?}"),
+        }
         let inode = Arc::new(Self {
-            meta: InodeMeta::new(
-                InodeMode::from_type(InodeType::File),
-                super_block.clone(),
-                size,
-            ),
+            meta,
             file: Arc::new(Mutex::new(file)),
         });
         inode
@@ -39,35 +76,46 @@ impl Inode for Ext4FileInode {
         &self.meta
     }
 
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: inner.nlink as _,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
-
     fn base_truncate(&self, len: usize) -> SysResult<()> {
-        self.file.lock().truncate(len as u64);
+        let mut file = self.file.lock();
+        let size = self.size();
+        if len > size {
+            // NOTE: lwext4 can only shrink a file, fill the gap with zero instead,
+            // a page at a time so that a large gap needs no large buffer
+            file.seek(size as i64, SEEK_SET)
+                .map_err(SysError::from_i32)?;
+            let zeros = [0; PAGE_SIZE];
+            let mut left = len - size;
+            while left > 0 {
+                let n = file
+                    .write(&zeros[..left.min(PAGE_SIZE)])
+                    .map_err(SysError::from_i32)?;
+                if n == 0 {
+                    return Err(SysError::ENOSPC);
+                }
+                left -= n;
+            }
+        } else {
+            file.truncate(len as u64).map_err(SysError::from_i32)?;
+        }
         Ok(())
     }
 
+    fn base_set_times(&self, atime: TimeSpec, mtime: TimeSpec) -> SysResult<()> {
+        let path = self.file.lock().get_path();
+        lwext4_set_times(&path, atime, mtime)
+    }
+
+    fn base_set_mode(&self, mode: InodeMode) -> SysResult<()> {
+        let path = self.file.lock().get_path();
+        lwext4_set_mode(&path, mode)
+    }
+
+    fn base_set_owner(&self, uid: u32, gid: u32) -> SysResult<()> {
+        let path = self.file.lock().get_path();
+        lwext4_set_owner(&path, uid, gid)
+    }
+
     fn base_get_blk_idx(&self, offset: usize) -> SysResult<usize> {
         let mut file = self.file.lock();
         let origin_offset = file.tell();
@@ -37,19 +37,19 @@ impl Inode for Ext4LinkInode {
 
     fn get_attr(&self) -> SysResult<Stat> {
         let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
+        let mode = inner.mode.bits();
         let len = inner.size;
         Ok(Stat {
             st_dev: 0,
             st_ino: self.meta.ino as u64,
             st_mode: mode,
             st_nlink: inner.nlink as _,
-            st_uid: 0,
-            st_gid: 0,
+            st_uid: inner.uid,
+            st_gid: inner.gid,
             st_rdev: 0,
             __pad: 0,
             st_size: len as u64,
-            st_blksize: 512,
+            st_blksize: self.meta.block_size() as _,
             __pad2: 0,
             st_blocks: ((len + BLOCK_MASK) / 512) as u64,
             st_atime: inner.atime,
@@ -0,0 +1,34 @@
+use fatfs::{Date, DateTime, Time};
+
+/// 1980-01-01 00:00:00, the earliest time FAT can hold.
+const FAT_EPOCH_SEC: usize = 315532800;
+/// 2107-12-31 23:59:58, the latest time FAT can hold.
+const FAT_MAX_SEC: usize = 4354819198;
+
+const SECS_PER_DAY: usize = 24 * 60 * 60;
+
+/// Convert seconds since the unix epoch to a FAT date and time, clamped to the
+/// years FAT can hold.
+pub fn to_fat_date_time(sec: usize) -> DateTime {
+    let sec = sec.clamp(FAT_EPOCH_SEC, FAT_MAX_SEC);
+    let (days, sec_of_day) = (sec / SECS_PER_DAY, sec % SECS_PER_DAY);
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as usize;
+    DateTime::new(
+        Date::new(year as u16, month as u16, day as u16),
+        Time::new(
+            (sec_of_day / 3600) as u16,
+            (sec_of_day / 60 % 60) as u16,
+            (sec_of_day % 60) as u16,
+            0,
+        ),
+    )
+}
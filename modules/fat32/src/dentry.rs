@@ -1,11 +1,11 @@
 use alloc::sync::Arc;
 
 use systype::{SysError, SysResult};
-use vfs_core::{Dentry, DentryMeta, Inode, InodeType, SuperBlock};
+use vfs_core::{Dentry, DentryMeta, DirFile, Inode, InodeType, SuperBlock};
 
 use crate::{
     as_sys_err,
-    file::{FatDirFile, FatFileFile},
+    file::FatFileFile,
     inode::{dir::FatDirInode, file::FatFileInode},
 };
 
@@ -48,7 +48,7 @@ impl Dentry for FatDentry {
                 let inode = inode
                     .downcast_arc::<FatDirInode>()
                     .map_err(|_| SysError::EIO)?;
-                Ok(FatDirFile::new(self.clone(), inode))
+                Ok(DirFile::new(self, inode))
             }
             _ => Err(SysError::EPERM),
         }
@@ -78,7 +78,7 @@ impl Dentry for FatDentry {
             };
             sub_dentry.set_inode(new_inode);
         } else {
-            log::warn!("[FatDentry::base_lookup] name {name} does not exist");
+            log::debug!("[FatDentry::base_lookup] name {name} does not exist");
         }
         Ok(sub_dentry)
     }
@@ -109,7 +109,7 @@ impl Dentry for FatDentry {
                 Ok(sub_dentry)
             }
             _ => {
-                log::warn!("[FatDentry::base_create] not supported mode {mode:?}");
+                log::info!("[FatDentry::base_create] not supported mode {mode:?}");
                 Err(SysError::EIO)
             }
         }
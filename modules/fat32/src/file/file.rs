@@ -5,7 +5,10 @@ use fatfs::{Read, Seek, Write};
 use systype::{SysError, SyscallResult};
 use vfs_core::{File, FileMeta, InodeType};
 
-use crate::{as_sys_err, dentry::FatDentry, inode::file::FatFileInode, FatFile, Shared};
+use crate::{
+    as_sys_err, dentry::FatDentry, flush_dir_entry_ordered, inode::file::FatFileInode, FatFile,
+    Shared,
+};
 
 pub struct FatFileFile {
     meta: FileMeta,
@@ -80,8 +83,16 @@ impl File for FatFileFile {
         }
     }
 
+    /// Data are written to the device at once, there is nothing buffered.
     fn flush(&self) -> systype::SysResult<usize> {
-        todo!()
+        Ok(0)
+    }
+
+    /// Update the directory entry, i.e. the size and the first cluster, after
+    /// the data clusters it refers to have reached the device. The timestamps
+    /// are in the same entry, so they are written even if `datasync`.
+    fn base_fsync(&self, _datasync: bool) -> systype::SysResult<()> {
+        flush_dir_entry_ordered(&mut self.file.lock(), self.super_block().device().as_ref())
     }
 
     fn base_read_dir(&self) -> systype::SysResult<Option<vfs_core::DirEntry>> {
@@ -1,5 +1,3 @@
-pub mod dir;
 pub mod file;
 
-pub use dir::*;
 pub use file::*;
@@ -1,7 +1,9 @@
 use alloc::sync::Arc;
 
 use device_core::BlockDevice;
-use vfs_core::{Dentry, FileSystemType, FileSystemTypeMeta, StatFs, SuperBlock, SuperBlockMeta};
+use vfs_core::{
+    Dentry, FileSystemFlags, FileSystemType, FileSystemTypeMeta, StatFs, SuperBlock, SuperBlockMeta,
+};
 
 use crate::{as_sys_err, dentry::FatDentry, inode::dir::FatDirInode, DiskCursor, FatFs};
 
@@ -17,6 +19,8 @@ impl FatFsType {
     }
 }
 
+vfs_core::register_fs!(FatFsType::new);
+
 impl FileSystemType for FatFsType {
     fn meta(&self) -> &FileSystemTypeMeta {
         &self.meta
@@ -32,42 +36,51 @@ impl FileSystemType for FatFsType {
         debug_assert!(dev.is_some());
         let sb = FatSuperBlock::new(SuperBlockMeta::new(dev, self.clone()));
         let root_inode = FatDirInode::new(sb.clone(), sb.fs.root_dir());
-        let root_dentry = FatDentry::new(name, sb.clone(), parent.clone()).into_dyn();
-        root_dentry.set_inode(root_inode);
-        if let Some(parent) = parent {
-            parent.insert(root_dentry.clone());
-        }
-        sb.set_root_dentry(root_dentry.clone());
-        self.insert_sb(&root_dentry.path(), sb);
-        Ok(root_dentry)
+        let root_dentry = FatDentry::new(name, sb.clone(), parent);
+        Ok(self.mount_root(sb, root_dentry, root_inode))
     }
 
-    fn kill_sb(&self, _sb: Arc<dyn vfs_core::SuperBlock>) -> systype::SysResult<()> {
-        todo!()
+    fn fs_flags(&self) -> FileSystemFlags {
+        FileSystemFlags::REQUIRES_DEV
+    }
+
+    /// The super block is synced by the caller, and the fatfs instance is
+    /// dropped with it once no open file holds it.
+    fn kill_sb(&self, sb: Arc<dyn vfs_core::SuperBlock>) -> systype::SysResult<()> {
+        if let Some(device) = sb.meta().device.as_ref() {
+            device.flush();
+        }
+        self.remove_sb(&sb);
+        Ok(())
     }
 }
 
+/// Magic number reported by statfs, as in linux.
+const MSDOS_SUPER_MAGIC: i64 = 0x4d44;
+
 pub struct FatSuperBlock {
     meta: SuperBlockMeta,
     fs: Arc<FatFs>,
+    cluster_size: usize,
 }
 
 impl FatSuperBlock {
     pub fn new(meta: SuperBlockMeta) -> Arc<Self> {
         let blk_dev = meta.device.as_ref().unwrap().clone();
+        let fs = FatFs::new(
+            DiskCursor {
+                sector: 0,
+                offset: 0,
+                blk_dev,
+            },
+            fatfs::FsOptions::new(),
+        )
+        .unwrap();
+        let cluster_size = fs.stats().unwrap().cluster_size() as usize;
         Arc::new(Self {
             meta,
-            fs: Arc::new(
-                FatFs::new(
-                    DiskCursor {
-                        sector: 0,
-                        offset: 0,
-                        blk_dev,
-                    },
-                    fatfs::FsOptions::new(),
-                )
-                .unwrap(),
-            ),
+            fs: Arc::new(fs),
+            cluster_size,
         })
     }
 }
@@ -79,15 +92,11 @@ impl SuperBlock for FatSuperBlock {
 
     fn stat_fs(&self) -> systype::SysResult<vfs_core::StatFs> {
         let stat_fs = self.fs.stats().map_err(as_sys_err)?;
-        let ft = self.fs.fat_type();
-        let f_type = match ft {
-            fatfs::FatType::Fat12 => 0x01,
-            fatfs::FatType::Fat16 => 0x04,
-            fatfs::FatType::Fat32 => 0x0c,
-        };
+        let cluster_size = stat_fs.cluster_size() as i64;
+        // FAT has no inodes, and like linux no count of them is reported
         Ok(StatFs {
-            f_type,
-            f_bsize: stat_fs.cluster_size() as i64,
+            f_type: MSDOS_SUPER_MAGIC,
+            f_bsize: cluster_size,
             f_blocks: stat_fs.total_clusters() as u64,
             f_bfree: stat_fs.free_clusters() as u64,
             f_bavail: stat_fs.free_clusters() as u64,
@@ -95,13 +104,24 @@ impl SuperBlock for FatSuperBlock {
             f_ffree: 0,
             f_fsid: [0, 0],
             f_namelen: 255,
-            f_frsize: 0,
+            f_frsize: cluster_size as isize,
             f_flags: 0,
             f_spare: [0; 4],
         })
     }
 
+    /// Write back the page caches along with the directory entries of the
+    /// files. The device completes writes before returning, so `wait` makes no
+    /// difference.
     fn sync_fs(&self, _wait: isize) -> systype::SysResult<()> {
-        todo!()
+        (self as &dyn SuperBlock).sync_cached_files()?;
+        if let Some(device) = self.meta.device.as_ref() {
+            device.flush();
+        }
+        Ok(())
+    }
+
+    fn block_size(&self) -> usize {
+        self.cluster_size
     }
 }
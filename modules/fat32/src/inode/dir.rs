@@ -1,8 +1,13 @@
 use alloc::sync::Arc;
 
-use vfs_core::{Inode, InodeMeta, InodeMode, InodeType, Stat, SuperBlock};
+use systype::{SysError, SysResult};
+use vfs_core::{Dentry, DirOps, Inode, InodeMeta, InodeMode, InodeType, Stat, SuperBlock};
 
-use crate::{new_shared, FatDir, Shared};
+use super::file::FatFileInode;
+use crate::{fat_mode_check, new_shared, FatDir, Shared};
+
+/// Size of an entry in a FAT directory.
+const DIR_ENTRY_SIZE: usize = 32;
 
 pub struct FatDirInode {
     meta: InodeMeta,
@@ -11,9 +16,13 @@ pub struct FatDirInode {
 
 impl FatDirInode {
     pub fn new(super_block: Arc<dyn SuperBlock>, dir: FatDir) -> Arc<Self> {
-        // TODO: Dir size is zero?
+        let size = super_block.block_size();
         let inode = Arc::new(Self {
-            meta: InodeMeta::new(InodeMode::from_type(InodeType::Dir), super_block.clone(), 0),
+            meta: InodeMeta::new(
+                InodeMode::from_type(InodeType::Dir),
+                super_block.clone(),
+                size,
+            ),
             dir: new_shared(dir),
         });
         inode
@@ -25,27 +34,62 @@ impl Inode for FatDirInode {
         &self.meta
     }
 
-    fn get_attr(&self) -> systype::SysResult<vfs_core::Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
+    fn base_set_mode(&self, mode: InodeMode) -> SysResult<()> {
+        fat_mode_check(mode)
+    }
+
+    /// FAT keeps neither a link count nor the size of a directory, they are
+    /// counted from its entries like Linux does: 2 plus the subdirectories, and
+    /// the clusters taken by the entries. A long name takes one more entry for
+    /// every 13 of its UCS-2 units.
+    fn get_attr(&self) -> SysResult<Stat> {
+        let mut stat = self.meta.stat();
+        let (mut subdirs, mut slots) = (0, 0);
+        for entry in self.dir.lock().iter() {
+            let Ok(entry) = entry else {
+                return Err(SysError::EIO);
+            };
+            let name = entry.file_name();
+            if entry.is_dir() && name != "." && name != ".." {
+                subdirs += 1;
+            }
+            let lfn_slots = entry
+                .long_file_name_as_ucs2_units()
+                .map_or(0, |units| units.len().div_ceil(13));
+            slots += 1 + lfn_slots;
+        }
+        let cluster_size = self.meta.block_size();
+        let size = (slots * DIR_ENTRY_SIZE)
+            .next_multiple_of(cluster_size)
+            .max(cluster_size);
+        stat.st_nlink = 2 + subdirs;
+        stat.st_size = size as u64;
+        stat.st_blocks = (size / 512) as u64;
+        Ok(stat)
+    }
+}
+
+impl DirOps for FatDirInode {
+    fn load_dir(&self, dentry: &Arc<dyn Dentry>) -> SysResult<()> {
+        let sb = dentry.super_block();
+        let mut iter = self.dir.lock().iter();
+        while let Some(entry) = iter.next() {
+            let Ok(entry) = entry else {
+                return Err(SysError::EIO);
+            };
+            let name = entry.file_name();
+            // "." and ".." are not cached as children, `DirFile` makes them up
+            if name == "." || name == ".." {
+                continue;
+            }
+            let sub_dentry = dentry.get_child_or_create(&name);
+            let new_inode: Arc<dyn Inode> = if entry.is_dir() {
+                FatDirInode::new(sb.clone(), entry.to_dir())
+            } else {
+                FatFileInode::new(sb.clone(), entry.to_file())
+            };
+            sub_dentry.set_inode(new_inode);
+        }
+        Ok(())
     }
 }
@@ -1,12 +1,21 @@
 use alloc::sync::Arc;
 
-use vfs_core::{Inode, InodeMeta, InodeMode, InodeType, Stat, SuperBlock};
+use config::mm::PAGE_SIZE;
+use device_core::BlockDevice;
+use fatfs::{Seek, SeekFrom, Write};
+use systype::SysResult;
+use time::timespec::TimeSpec;
+use vfs_core::{Inode, InodeMeta, InodeMode, InodeType, SuperBlock};
 
-use crate::{FatFile, Mutex, Shared};
+use crate::{
+    as_sys_err, datetime::to_fat_date_time, fat_mode_check, flush_dir_entry_ordered, FatFile,
+    Mutex, Shared,
+};
 
 pub struct FatFileInode {
     meta: InodeMeta,
     pub file: Shared<FatFile>,
+    device: Arc<dyn BlockDevice>,
 }
 
 impl FatFileInode {
@@ -19,37 +28,57 @@ impl FatFileInode {
                 size,
             ),
             file: Arc::new(Mutex::new(file)),
+            device: super_block.device(),
         });
         inode
     }
 }
 
+impl Drop for FatFileInode {
+    /// fatfs writes a dirty directory entry when the file is dropped, which is
+    /// done here after a barrier instead.
+    fn drop(&mut self) {
+        if let Err(e) = flush_dir_entry_ordered(&mut self.file.lock(), self.device.as_ref()) {
+            log::warn!("[FatFileInode::drop] failed to write directory entry: {e:?}");
+        }
+    }
+}
+
 impl Inode for FatFileInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
 
-    fn get_attr(&self) -> systype::SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
+    /// FAT keeps only the date of the last access.
+    fn base_set_times(&self, atime: TimeSpec, mtime: TimeSpec) -> SysResult<()> {
+        let mut file = self.file.lock();
+        file.set_accessed(to_fat_date_time(atime.tv_sec).date);
+        file.set_modified(to_fat_date_time(mtime.tv_sec));
+        file.flush().map_err(as_sys_err)
+    }
+
+    fn base_set_mode(&self, mode: InodeMode) -> SysResult<()> {
+        fat_mode_check(mode)
+    }
+
+    fn base_truncate(&self, len: usize) -> SysResult<()> {
+        let mut file = self.file.lock();
+        let size = self.size();
+        if len < size {
+            file.seek(SeekFrom::Start(len as u64)).map_err(as_sys_err)?;
+            file.truncate().map_err(as_sys_err)?;
+        } else if len > size {
+            file.seek(SeekFrom::Start(size as u64))
+                .map_err(as_sys_err)?;
+            // a page at a time, so that a large gap needs no large buffer
+            let zeros = [0; PAGE_SIZE];
+            let mut left = len - size;
+            while left > 0 {
+                let n = left.min(PAGE_SIZE);
+                file.write_all(&zeros[..n]).map_err(as_sys_err)?;
+                left -= n;
+            }
+        }
+        Ok(())
     }
 }
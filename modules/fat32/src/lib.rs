@@ -5,13 +5,17 @@
 use alloc::sync::Arc;
 
 use device_core::BlockDevice;
-use fatfs::{DefaultTimeProvider, Dir, DirIter, Error, File, FileSystem, LossyOemCpConverter};
+use fatfs::{
+    DefaultTimeProvider, Dir, DirIter, Error, File, FileSystem, LossyOemCpConverter, Write,
+};
 use sync::mutex::SpinNoIrqLock;
-use systype::SysError;
+use systype::{SysError, SysResult};
+use vfs_core::InodeMode;
 
 #[macro_use]
 extern crate alloc;
 
+mod datetime;
 mod dentry;
 mod file;
 mod fs;
@@ -34,10 +38,40 @@ type FatFs = FileSystem<DiskCursor, DefaultTimeProvider, LossyOemCpConverter>;
 pub const fn as_sys_err(err: fatfs::Error<()>) -> systype::SysError {
     match err {
         Error::NotFound => SysError::ENOENT,
+        Error::AlreadyExists => SysError::EEXIST,
+        Error::DirectoryIsNotEmpty => SysError::ENOTEMPTY,
         _ => SysError::EIO,
     }
 }
 
+/// Write the directory entry of `file`, i.e. its size, first cluster and
+/// timestamps, only after the data clusters written before have reached
+/// `device`. A write barrier separates them, so that after a crash the entry
+/// never refers to clusters whose data was not written.
+///
+/// NOTE: fatfs writes data clusters and the FAT at once but keeps the entry in
+/// memory until the file is flushed or dropped.
+fn flush_dir_entry_ordered(file: &mut FatFile, device: &dyn BlockDevice) -> SysResult<()> {
+    device.flush();
+    file.flush().map_err(as_sys_err)?;
+    device.flush();
+    Ok(())
+}
+
+/// Check that FAT can represent `mode`, i.e. it has no set-user-ID,
+/// set-group-ID or sticky bit.
+///
+/// FAT has only a read-only attribute for the permissions, so the other bits
+/// are accepted and kept in memory.
+// NOTE: fatfs has no setter for the attributes of an entry, the read-only
+// attribute is not written either
+fn fat_mode_check(mode: InodeMode) -> SysResult<()> {
+    if mode.intersects(InodeMode::SET_UID | InodeMode::SET_GID | InodeMode::STICKY) {
+        return Err(SysError::EPERM);
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct DiskCursor {
     sector: u64,
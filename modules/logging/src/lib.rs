@@ -1,27 +1,56 @@
 #![no_std]
 #![no_main]
 
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::{
+    cmp,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
 
 use crate_interface::call_interface;
 use log::{Level, LevelFilter, Log, Metadata, Record};
 
 pub static mut LOG_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
+/// Number of warnings logged since boot.
+static WARN_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// Number of errors logged since boot.
+static ERROR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the kernel panics on the first warning or error.
+pub const PANIC_ON_WARN: bool = cfg!(feature = "panic_on_warn");
+
+/// Max level of records to be printed, configured by `LOG` env when building.
+const PRINT_LEVEL: LevelFilter = match option_env!("LOG") {
+    Some(level) => match level.as_bytes() {
+        b"error" => LevelFilter::Error,
+        b"warn" => LevelFilter::Warn,
+        b"info" => LevelFilter::Info,
+        b"debug" => LevelFilter::Debug,
+        b"trace" => LevelFilter::Trace,
+        _ => LevelFilter::Off,
+    },
+    None => LevelFilter::Off,
+};
+
 pub fn init() {
     static LOGGER: SimpleLogger = SimpleLogger;
     log::set_logger(&LOGGER).unwrap();
-    log::set_max_level(match option_env!("LOG") {
-        Some("error") => LevelFilter::Error,
-        Some("warn") => LevelFilter::Warn,
-        Some("info") => LevelFilter::Info,
-        Some("debug") => LevelFilter::Debug,
-        Some("trace") => LevelFilter::Trace,
-        _ => LevelFilter::Off,
-    });
+    // NOTE: warnings and errors always reach the logger to be counted, even if
+    // they will not be printed
+    log::set_max_level(cmp::max(PRINT_LEVEL, LevelFilter::Warn));
     unsafe { LOG_INITIALIZED.store(true, Ordering::SeqCst) };
 }
 
+/// Get the number of records logged with `level` since boot. Only warnings
+/// and errors are counted.
+pub fn log_count(level: Level) -> usize {
+    match level {
+        Level::Warn => WARN_COUNT.load(Ordering::Relaxed),
+        Level::Error => ERROR_COUNT.load(Ordering::Relaxed),
+        _ => 0,
+    }
+}
+
 /// Add escape sequence to print with color in linux console
 // #[macro_export]
 // macro_rules! with_color {
@@ -52,7 +81,22 @@ impl Log for SimpleLogger {
         if !self.enabled(record.metadata()) {
             return;
         }
-        call_interface!(LogIf::print_log(record));
+        match record.level() {
+            Level::Warn => WARN_COUNT.fetch_add(1, Ordering::Relaxed),
+            Level::Error => ERROR_COUNT.fetch_add(1, Ordering::Relaxed),
+            _ => 0,
+        };
+        if record.level() <= PRINT_LEVEL {
+            call_interface!(LogIf::print_log(record));
+        }
+        #[cfg(feature = "panic_on_warn")]
+        if record.level() <= Level::Warn {
+            // NOTE: only panic on the first one, the panic handler will log errors too
+            static PANICKED: AtomicBool = AtomicBool::new(false);
+            if !PANICKED.swap(true, Ordering::SeqCst) {
+                panic!("panic on warn: {}", record.args());
+            }
+        }
     }
     fn flush(&self) {}
 }
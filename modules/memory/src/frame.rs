@@ -6,13 +6,17 @@ use core::{
     cell::SyncUnsafeCell,
     fmt::{self, Debug, Formatter},
     ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use bitmap_allocator::BitAlloc;
 use crate_interface::call_interface;
 use sync::mutex::SpinNoIrqLock;
 
-use crate::{PhysAddr, PhysPageNum};
+use crate::{
+    vmstat::{vm_stat_add, VmStat},
+    PhysAddr, PhysPageNum,
+};
 
 /// Manage a frame which has the same lifecycle as the tracker.
 pub struct FrameTracker {
@@ -49,6 +53,8 @@ impl Drop for FrameTracker {
 struct FrameAllocator {
     range_ppn: SyncUnsafeCell<Range<PhysPageNum>>,
     allocator: SpinNoIrqLock<bitmap_allocator::BitAlloc16M>,
+    /// Count of frames allocated now.
+    allocated: AtomicUsize,
 }
 
 impl FrameAllocator {
@@ -59,11 +65,16 @@ impl FrameAllocator {
     fn range_ppn(&self) -> Range<PhysPageNum> {
         unsafe { &*self.range_ppn.get() }.clone()
     }
+
+    fn record_alloc(&self, count: usize) {
+        self.allocated.fetch_add(count, Ordering::Relaxed);
+    }
 }
 
 static FRAME_ALLOCATOR: FrameAllocator = FrameAllocator {
     range_ppn: SyncUnsafeCell::new(PhysPageNum::ZERO..PhysPageNum::ZERO),
     allocator: SpinNoIrqLock::new(bitmap_allocator::BitAlloc16M::DEFAULT),
+    allocated: AtomicUsize::new(0),
 };
 
 /// Initiate the frame allocator, using `VPNRange`
@@ -81,8 +92,17 @@ pub fn init_frame_allocator(start: PhysPageNum, end: PhysPageNum) {
     );
 }
 
+/// Count of all frames and of the free ones.
+pub fn frame_stat() -> (usize, usize) {
+    let range = FRAME_ALLOCATOR.range_ppn();
+    let total = range.end.0 - range.start.0;
+    let allocated = FRAME_ALLOCATOR.allocated.load(Ordering::Relaxed);
+    (total, total.saturating_sub(allocated))
+}
+
 /// Allocate a frame
 pub fn alloc_frame_tracker() -> FrameTracker {
+    FRAME_ALLOCATOR.record_alloc(1);
     let ret = FRAME_ALLOCATOR
         .allocator
         .lock()
@@ -103,6 +123,7 @@ pub fn alloc_frame_tracker() -> FrameTracker {
 
 /// Allocate contiguous frames
 pub fn alloc_frame_trackers(size: usize) -> Vec<FrameTracker> {
+    FRAME_ALLOCATOR.record_alloc(size);
     if let Some(first_frame) = FRAME_ALLOCATOR.allocator.lock().alloc_contiguous(size, 0) {
         (first_frame..first_frame + size)
             .map(|u| FrameTracker::new(FRAME_ALLOCATOR.range_ppn().start + u))
@@ -122,6 +143,7 @@ pub fn alloc_frame_trackers(size: usize) -> Vec<FrameTracker> {
 
 /// Allocate contiguous frames
 pub fn alloc_frames(size: usize) -> PhysAddr {
+    FRAME_ALLOCATOR.record_alloc(size);
     if let Some(first_frame) = FRAME_ALLOCATOR.allocator.lock().alloc_contiguous(size, 0) {
         let ppn = FRAME_ALLOCATOR.range_ppn().start + first_frame;
         ppn.to_paddr()
@@ -139,6 +161,8 @@ pub fn alloc_frames(size: usize) -> PhysAddr {
 
 /// Deallocate a frame
 pub fn dealloc_frame(ppn: PhysPageNum) {
+    FRAME_ALLOCATOR.allocated.fetch_sub(1, Ordering::Relaxed);
+    vm_stat_add(VmStat::PgFree, 1);
     FRAME_ALLOCATOR
         .allocator
         .lock()
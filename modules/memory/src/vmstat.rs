@@ -0,0 +1,53 @@
+//! Global counters of memory events, shown in `/proc/vmstat`.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmStat {
+    /// Pages mapped in user address spaces now, counted once per mapping.
+    NrMapped,
+    /// Page faults of user address spaces.
+    PgFault,
+    /// Page faults that had to read a file.
+    PgMajFault,
+    /// Frames freed.
+    PgFree,
+}
+
+impl VmStat {
+    pub const ALL: [VmStat; 4] = [
+        VmStat::NrMapped,
+        VmStat::PgFault,
+        VmStat::PgMajFault,
+        VmStat::PgFree,
+    ];
+
+    /// Name in `/proc/vmstat`.
+    pub fn name(self) -> &'static str {
+        match self {
+            VmStat::NrMapped => "nr_mapped",
+            VmStat::PgFault => "pgfault",
+            VmStat::PgMajFault => "pgmajfault",
+            VmStat::PgFree => "pgfree",
+        }
+    }
+}
+
+static VM_STAT: [AtomicUsize; VmStat::ALL.len()] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+
+pub fn vm_stat_add(item: VmStat, count: usize) {
+    VM_STAT[item as usize].fetch_add(count, Ordering::Relaxed);
+}
+
+pub fn vm_stat_sub(item: VmStat, count: usize) {
+    VM_STAT[item as usize].fetch_sub(count, Ordering::Relaxed);
+}
+
+pub fn vm_stat(item: VmStat) -> usize {
+    VM_STAT[item as usize].load(Ordering::Relaxed)
+}
@@ -259,7 +259,7 @@ impl InterfaceWrapper {
         let mut sockets = sockets.lock();
         let timestamp = Self::current_time();
         let result = iface.poll(timestamp, dev.deref_mut(), &mut sockets);
-        log::warn!("[net::InterfaceWrapper::poll] does something have been changed? {result:?}");
+        log::debug!("[net::InterfaceWrapper::poll] does something have been changed? {result:?}");
         timestamp
     }
 
@@ -438,7 +438,7 @@ impl<'a> RxToken for NetRxToken<'a> {
         F: FnOnce(&mut [u8]) -> R,
     {
         let mut rx_buf = self.1;
-        warn!(
+        debug!(
             "[RxToken::consume] RECV {} bytes",
             rx_buf.packet_len(),
             // rx_buf.packet()
@@ -461,7 +461,7 @@ impl<'a> TxToken for NetTxToken<'a> {
         let mut dev = self.0.borrow_mut();
         let mut tx_buf = dev.alloc_tx_buffer(len).unwrap();
         let ret = f(tx_buf.packet_mut());
-        warn!(
+        debug!(
             "[TxToken::consume] SEND {} bytes",
             len,
             // tx_buf.packet()
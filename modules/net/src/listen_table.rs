@@ -120,7 +120,7 @@ impl ListenTable {
             *entry = Some(Box::new(ListenTableEntry::new(listen_endpoint, waker)));
             Ok(())
         } else {
-            warn!("socket listen() failed");
+            info!("socket listen() failed");
             Err(SysError::EADDRINUSE)
         }
     }
@@ -174,7 +174,7 @@ impl ListenTable {
             let handle = syn_queue.swap_remove_front(idx).unwrap();
             Ok((handle, addr_tuple))
         } else {
-            warn!("socket accept() failed: not listen");
+            info!("socket accept() failed: not listen");
             Err(SysError::EINVAL)
         }
     }
@@ -188,7 +188,7 @@ impl ListenTable {
         if let Some(entry) = self.tcp[dst.port as usize].lock().deref_mut() {
             if !entry.can_accept(dst.addr) {
                 // not listening on this address
-                warn!(
+                info!(
                     "[ListenTable::incoming_tcp_packet] not listening on address {}",
                     dst.addr
                 );
@@ -67,6 +67,10 @@ pub struct TcpSocket {
     /// Indicates whether the socket is in non-blocking mode, using an atomic
     /// boolean for thread-safe access.
     nonblock: AtomicBool,
+    /// Whether the Nagle algorithm is disabled, i.e. `TCP_NODELAY`.
+    nodelay: AtomicBool,
+    /// Error of a non-blocking connect, reported by `SO_ERROR`.
+    error: Mutex<Option<SysError>>,
 }
 
 unsafe impl Sync for TcpSocket {}
@@ -83,6 +87,8 @@ impl TcpSocket {
             local_addr: UnsafeCell::new(UNSPECIFIED_ENDPOINT_V4),
             peer_addr: UnsafeCell::new(UNSPECIFIED_ENDPOINT_V4),
             nonblock: AtomicBool::new(false),
+            nodelay: AtomicBool::new(false),
+            error: Mutex::new(None),
         }
     }
 
@@ -99,6 +105,8 @@ impl TcpSocket {
             local_addr: UnsafeCell::new(local_addr),
             peer_addr: UnsafeCell::new(peer_addr),
             nonblock: AtomicBool::new(false),
+            nodelay: AtomicBool::new(false),
+            error: Mutex::new(None),
         }
     }
 
@@ -143,6 +151,22 @@ impl TcpSocket {
         self.nonblock.store(nonblocking, Ordering::Release);
     }
 
+    /// Disables or enables the Nagle algorithm.
+    pub fn set_nodelay(&self, nodelay: bool) {
+        self.nodelay.store(nodelay, Ordering::Release);
+        // SAFETY: the handle is only written while the socket is busy
+        if let Some(handle) = unsafe { self.handle.get().read() } {
+            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+                socket.set_nagle_enabled(!nodelay)
+            });
+        }
+    }
+
+    /// Takes the error of a failed non-blocking connect, if any.
+    pub fn take_error(&self) -> Option<SysError> {
+        self.error.lock().take()
+    }
+
     /// Connects to the given address and port.
     ///
     /// The local port is generated automatically.
@@ -168,15 +192,16 @@ impl TcpSocket {
                             // again on an already connected socket, or performing
                             // the operation on a closed socket
                             ConnectError::InvalidState => {
-                                warn!("[TcpSocket::connect] failed: InvalidState");
+                                info!("[TcpSocket::connect] failed: InvalidState");
                                 Err(SysError::EBADF)
                             }
                             // The target address or port attempting to connect is unreachable
                             ConnectError::Unaddressable => {
-                                warn!("[TcpSocket::connect] failed: Unaddressable");
+                                info!("[TcpSocket::connect] failed: Unaddressable");
                                 Err(SysError::EADDRNOTAVAIL)
                             }
                         })?;
+                    socket.set_nagle_enabled(!self.nodelay.load(Ordering::Acquire));
                     Ok((
                         socket.local_endpoint().unwrap(),
                         socket.remote_endpoint().unwrap(),
@@ -192,7 +217,7 @@ impl TcpSocket {
             Ok(())
         })
         .unwrap_or_else(|_| {
-            warn!("[TcpSocket::connect] failed: already connected");
+            info!("[TcpSocket::connect] failed: already connected");
             Err(SysError::EEXIST)
         })?; // EISCONN
 
@@ -203,12 +228,12 @@ impl TcpSocket {
             self.block_on_async(|| async {
                 let NetPollState { writable, .. } = self.poll_connect().await;
                 if !writable {
-                    warn!("[TcpSocket::connect] failed: try again");
+                    info!("[TcpSocket::connect] failed: try again");
                     Err(SysError::EAGAIN)
                 } else if self.get_state() == STATE_CONNECTED {
                     Ok(())
                 } else {
-                    warn!("[TcpSocket::connect] failed, connection refused");
+                    info!("[TcpSocket::connect] failed, connection refused");
                     Err(SysError::ECONNREFUSED)
                 }
             })
@@ -235,13 +260,13 @@ impl TcpSocket {
             unsafe {
                 let old = self.local_addr.get().read();
                 if old != UNSPECIFIED_ENDPOINT_V4 {
-                    warn!("socket bind() failed: {:?} already bound", local_addr);
+                    info!("socket bind() failed: {:?} already bound", local_addr);
                     return Err(SysError::EINVAL);
                 }
                 // FIXME
                 if let IpAddress::Ipv6(v6) = local_addr.addr {
                     if v6.is_unspecified() {
-                        log::warn!("[TcpSocket::bind] Unstable: just use ipv4 instead of ipv6 when ipv6 is unspecified");
+                        log::info!("[TcpSocket::bind] Unstable: just use ipv4 instead of ipv6 when ipv6 is unspecified");
                         local_addr.addr = UNSPECIFIED_IPV4;
                     }
                 }
@@ -250,7 +275,7 @@ impl TcpSocket {
             Ok(())
         })
         .unwrap_or_else(|_| {
-            warn!("socket bind() failed: {:?} already bound", local_addr);
+            info!("socket bind() failed: {:?} already bound", local_addr);
             Err(SysError::EINVAL)
         })
     }
@@ -281,7 +306,7 @@ impl TcpSocket {
     /// [`listen`](Self::listen).
     pub async fn accept(&self) -> SysResult<TcpSocket> {
         if !self.is_listening() {
-            warn!("socket accept() failed: not listen");
+            info!("socket accept() failed: not listen");
             return Err(SysError::EINVAL);
         }
 
@@ -314,12 +339,12 @@ impl TcpSocket {
             // no other threads can read or write it.
             let handle = unsafe { self.handle.get().read().unwrap() };
             SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
-                warn!(
+                debug!(
                     "TCP handle {handle}: shutting down, before state is {:?}",
                     socket.state()
                 );
                 socket.close();
-                warn!(
+                debug!(
                     "TCP handle {handle}: shutting down, after state is {:?}",
                     socket.state()
                 );
@@ -352,14 +377,14 @@ impl TcpSocket {
     pub async fn recv(&self, buf: &mut [u8]) -> SysResult<usize> {
         let shutdown = unsafe { *self.shutdown.get() };
         if shutdown & RCV_SHUTDOWN != 0 {
-            log::warn!("[TcpSocket::recv] shutdown closed read, recv return 0");
+            log::debug!("[TcpSocket::recv] shutdown closed read, recv return 0");
             return Ok(0);
         }
         if self.is_connecting() {
             // TODO: 这里是否要加上 waker
             return Err(SysError::EAGAIN);
         } else if !self.is_connected() && shutdown == 0 {
-            warn!("socket recv() failed");
+            info!("socket recv() failed");
             return Err(SysError::ENOTCONN);
         }
 
@@ -371,7 +396,7 @@ impl TcpSocket {
                 log::info!("[TcpSocket::recv] handle{handle} state {} is trying to recv", socket.state());
                 if !socket.is_active() {
                     // not open
-                    warn!("[TcpSocket::recv] socket recv() failed because handle{handle} is not active");
+                    info!("[TcpSocket::recv] socket recv() failed because handle{handle} is not active");
                     Err(SysError::ECONNREFUSED)
                 } else if !socket.may_recv() {
                     // connection closed
@@ -380,7 +405,7 @@ impl TcpSocket {
                     // data available
                     // TODO: use socket.recv(|buf| {...})
                     let len = socket.recv_slice(buf).map_err(|_| {
-                        warn!("socket recv() failed, badstate");
+                        info!("socket recv() failed, badstate");
                         SysError::EBADF
                     })?;
                     Ok(len)
@@ -399,13 +424,13 @@ impl TcpSocket {
     pub async fn send(&self, buf: &[u8]) -> SysResult<usize> {
         let shutdown = unsafe { *self.shutdown.get() };
         if shutdown & SEND_SHUTDOWN != 0 {
-            log::warn!("[TcpSocket::send] shutdown closed write, send return 0");
+            log::debug!("[TcpSocket::send] shutdown closed write, send return 0");
             return Ok(0);
         }
         if self.is_connecting() {
             return Err(SysError::EAGAIN);
         } else if !self.is_connected() && shutdown == 0 {
-            warn!("socket send() failed");
+            info!("socket send() failed");
             return Err(SysError::ENOTCONN);
         }
 
@@ -416,7 +441,7 @@ impl TcpSocket {
             SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
                 if !socket.is_active() || !socket.may_send() {
                     // closed by remote
-                    warn!("socket send() failed, ECONNRESET");
+                    info!("socket send() failed, ECONNRESET");
                     Err(SysError::ECONNRESET)
                 } else if socket.can_send() {
                     // connected, and the tx buffer is not full
@@ -476,15 +501,15 @@ impl<'a> Future for TcpRecvFuture<'a> {
 
         let shutdown = unsafe { *self.socket.shutdown.get() };
         if shutdown & RCV_SHUTDOWN != 0 {
-            log::warn!("[TcpSocket::recv] shutdown closed read, recv return 0");
+            log::debug!("[TcpSocket::recv] shutdown closed read, recv return 0");
             return Poll::Ready(Ok(0));
         }
         if self.socket.is_connecting() {
             // TODO: 这里是否要加上 waker
-            log::warn!("[TcpRecvFuture] may loss waker");
+            log::debug!("[TcpRecvFuture] may loss waker");
             return Poll::Pending;
         } else if !self.socket.is_connected() && shutdown == 0 {
-            warn!("socket recv() failed");
+            info!("socket recv() failed");
             return Poll::Ready(Err(SysError::ENOTCONN));
         }
 
@@ -497,7 +522,7 @@ impl<'a> Future for TcpRecvFuture<'a> {
             );
             if !socket.is_active() {
                 // not open
-                warn!("[TcpSocket::recv] socket recv() failed because handle{handle} is not active");
+                info!("[TcpSocket::recv] socket recv() failed because handle{handle} is not active");
                 Poll::Ready(Err(SysError::ECONNREFUSED))
             } else if !socket.may_recv() {
                 // connection closed
@@ -639,6 +664,10 @@ impl TcpSocket {
                         self.peer_addr.get().write(UNSPECIFIED_ENDPOINT_V4);
                     }
                     self.set_state(STATE_CLOSED); // connection failed
+                    if self.is_nonblocking() {
+                        // nobody waits in connect to be told
+                        *self.error.lock() = Some(SysError::ECONNREFUSED);
+                    }
                     true
                 }
             }
@@ -691,7 +720,7 @@ impl TcpSocket {
         let handle = unsafe { self.handle.get().read() };
         if let Some(handle) = handle {
             SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
-                log::warn!(
+                log::debug!(
                     "[TcpSocket::poll_closed] handle {handle} state {}",
                     socket.state()
                 );
@@ -732,7 +761,7 @@ impl TcpSocket {
                     Err(SysError::EAGAIN) => {
                         suspend_now().await;
                         if has_signal() {
-                            warn!("[TcpSocket::block_on] has signal");
+                            info!("[TcpSocket::block_on] has signal");
                             return Err(SysError::EINTR);
                         }
                     }
@@ -759,7 +788,7 @@ impl TcpSocket {
                     Err(SysError::EAGAIN) => {
                         suspend_now().await;
                         if has_signal() {
-                            warn!("[TcpSocket::block_on_async] has signal");
+                            info!("[TcpSocket::block_on_async] has signal");
                             return Err(SysError::EINTR);
                         }
                     }
@@ -5,7 +5,7 @@ use core::{
 };
 
 use async_utils::{get_waker, suspend_now, yield_now};
-use log::{debug, error, info, warn};
+use log::{debug, info};
 use smoltcp::{
     iface::SocketHandle,
     socket::udp::{self, BindError, SendError},
@@ -94,7 +94,7 @@ impl UdpSocket {
         // 但它们需要绑定到不同的地址
         if let Some((fd, prev_bound_addr)) = PORT_MAP.get(bound_addr.port) {
             if bound_addr == prev_bound_addr {
-                warn!("[UdpSocket::bind] The port is already used by another socket. Reuse the Arc of {fd}");
+                info!("[UdpSocket::bind] The port is already used by another socket. Reuse the Arc of {fd}");
                 // SOCKET_SET.remove(self.handle);
                 // self.overridden.store(true, Ordering::SeqCst);
                 // 这个check_bind函数到这里执行之后，该Udp复用原来的Socket
@@ -128,7 +128,7 @@ impl UdpSocket {
             );
         }
         if self_local_addr.is_some() {
-            warn!("socket bind() failed: The socket is already bound to an address.");
+            info!("socket bind() failed: The socket is already bound to an address.");
             return Err(SysError::EINVAL);
         }
 
@@ -144,7 +144,7 @@ impl UdpSocket {
         // };
         SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
             socket.bind(bound_addr).map_err(|e| {
-                warn!("socket bind() failed");
+                info!("socket bind() failed");
                 match e {
                     BindError::InvalidState => SysError::EEXIST,
                     BindError::Unaddressable => SysError::EINVAL,
@@ -164,7 +164,7 @@ impl UdpSocket {
     /// number of bytes written.
     pub async fn send_to(&self, buf: &[u8], remote_addr: IpEndpoint) -> SysResult<usize> {
         if remote_addr.port == 0 || remote_addr.addr.is_unspecified() {
-            warn!("socket send_to() failed: invalid remote address");
+            info!("socket send_to() failed: invalid remote address");
             return Err(SysError::EINVAL);
         }
         self.send_impl(buf, remote_addr).await
@@ -176,7 +176,7 @@ impl UdpSocket {
         self.recv_impl(|socket| match socket.recv_slice(buf) {
             Ok((len, meta)) => Ok((len, meta.endpoint)),
             Err(e) => {
-                warn!("[UdpSocket::recv_from] socket {} failed {e:?}", self.handle);
+                info!("[UdpSocket::recv_from] socket {} failed {e:?}", self.handle);
                 Err(SysError::EAGAIN)
             }
         })
@@ -190,7 +190,7 @@ impl UdpSocket {
         self.recv_impl(|socket| match socket.peek_slice(buf) {
             Ok((len, meta)) => Ok((len, meta.endpoint)),
             Err(_) => {
-                warn!("socket recv_from() failed");
+                info!("socket recv_from() failed");
                 Err(SysError::EAGAIN)
             }
         })
@@ -234,7 +234,7 @@ impl UdpSocket {
         let remote_endpoint = self.remote_endpoint()?;
         self.recv_impl(|socket| {
             let (len, meta) = socket.recv_slice(buf).map_err(|_| {
-                warn!("socket recv()  failed");
+                info!("socket recv()  failed");
                 SysError::EAGAIN
             })?;
             if !is_unspecified(remote_endpoint.addr) && remote_endpoint.addr != meta.endpoint.addr {
@@ -251,7 +251,7 @@ impl UdpSocket {
     /// Close the socket.
     pub fn shutdown(&self) -> SysResult<()> {
         SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-            warn!(
+            info!(
                 "UDP socket {}: shutting down, remote {:?}",
                 self.handle,
                 self.peer_addr()
@@ -304,7 +304,7 @@ impl UdpSocket {
 
     async fn send_impl(&self, buf: &[u8], remote_endpoint: IpEndpoint) -> SysResult<usize> {
         if self.local_addr.read().is_none() {
-            warn!(
+            info!(
                 "[send_impl] UDP socket {}: not bound. Use 127.0.0.1",
                 self.handle
             );
@@ -319,12 +319,12 @@ impl UdpSocket {
                             .send_slice(buf, remote_endpoint)
                             .map_err(|e| match e {
                                 SendError::BufferFull => {
-                                    warn!("socket send() failed, {e:?}");
+                                    debug!("socket send() failed, {e:?}");
                                     socket.register_send_waker(&waker);
                                     SysError::EAGAIN
                                 }
                                 SendError::Unaddressable => {
-                                    warn!("socket send() failed, {e:?}");
+                                    info!("socket send() failed, {e:?}");
                                     SysError::ECONNREFUSED
                                 }
                             })?;
@@ -351,7 +351,7 @@ impl UdpSocket {
         F: FnMut(&mut udp::Socket) -> SysResult<T>,
     {
         if self.local_addr.read().is_none() {
-            warn!("socket send() failed");
+            info!("socket send() failed");
             return Err(SysError::ENOTCONN);
         }
         let waker = get_waker().await;
@@ -363,7 +363,7 @@ impl UdpSocket {
                         op(socket)
                     } else if !socket.is_open() {
                         // TODO: I suppose that this would't happen
-                        warn!("UDP socket {}: recv() failed: not connected", self.handle);
+                        info!("UDP socket {}: recv() failed: not connected", self.handle);
                         Err(SysError::ENOTCONN)
                     } else {
                         // no more data
@@ -394,7 +394,7 @@ impl UdpSocket {
                     Err(SysError::EAGAIN) => {
                         suspend_now().await;
                         if has_signal() {
-                            warn!("[UdpSocket::block_on] has signal");
+                            info!("[UdpSocket::block_on] has signal");
                             return Err(SysError::EINTR);
                         }
                     }
@@ -1,4 +1,7 @@
-use alloc::sync::{Arc, Weak};
+use alloc::{
+    sync::{Arc, Weak},
+    vec::Vec,
+};
 use core::num::NonZeroUsize;
 
 use config::{
@@ -67,6 +70,19 @@ impl BufferCache {
         }
     }
 
+    /// Write all dirty cached blocks back to the device in block order.
+    pub fn flush(&mut self) {
+        let mut pages: Vec<_> = self
+            .pages
+            .iter()
+            .map(|(&id, page)| (id, page.clone()))
+            .collect();
+        pages.sort_unstable_by_key(|&(id, _)| id);
+        for (_, page) in pages {
+            page.flush()
+        }
+    }
+
     pub fn get_buffer_head_from_disk(&mut self, block_id: usize) -> Arc<BufferHead> {
         let device = self.device();
         if let Some(buffer_head) = self.buffer_heads.get_mut(&block_id).cloned() {
@@ -70,7 +70,7 @@ impl Drop for Page {
             }
             PageKind::BlockCache(inner) => {
                 let mut inner = inner.lock();
-                log::warn!("[Page::drop] sync block buffer back to disk");
+                log::debug!("[Page::drop] sync block buffer back to disk");
                 let device = inner.device.upgrade().unwrap();
                 while let Some(buffer_head) = inner.buffer_heads.pop_front() {
                     if buffer_head.bstate() == BufferState::Dirty {
@@ -182,13 +182,38 @@ impl Page {
             PageKind::FileCache(inner) => inner.lock(),
             PageKind::BlockCache(inner) => inner.lock(),
         };
-        log::warn!("[Page::flush] sync buffer back to disk");
+        log::debug!("[Page::flush] sync buffer back to disk");
         let device = inner.device.upgrade().unwrap();
+        // Adjacent dirty blocks are written with one request.
+        let mut run: Option<(usize, usize, usize)> = None;
         for buffer_head in inner.buffer_heads.iter() {
-            if buffer_head.bstate() == BufferState::Dirty {
-                let block_id = buffer_head.block_id();
-                device.base_write_blocks(buffer_head.block_id(), &buffer_head.bytes_array());
+            if buffer_head.bstate() != BufferState::Dirty {
+                continue;
             }
+            let (block_id, offset) = (buffer_head.block_id(), buffer_head.offset());
+            buffer_head.set_bstate(BufferState::Sync);
+            match run {
+                Some((start, start_offset, cnt))
+                    if start + cnt == block_id && start_offset + cnt * BLOCK_SIZE == offset =>
+                {
+                    run = Some((start, start_offset, cnt + 1))
+                }
+                _ => {
+                    if let Some((start, start_offset, cnt)) = run {
+                        device.base_write_blocks(
+                            start,
+                            self.bytes_array_range(start_offset..start_offset + cnt * BLOCK_SIZE),
+                        );
+                    }
+                    run = Some((block_id, offset, 1))
+                }
+            }
+        }
+        if let Some((start, start_offset, cnt)) = run {
+            device.base_write_blocks(
+                start,
+                self.bytes_array_range(start_offset..start_offset + cnt * BLOCK_SIZE),
+            );
         }
     }
 }
@@ -1,26 +1,61 @@
-use alloc::sync::Arc;
+use alloc::{collections::BTreeSet, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicUsize, Ordering};
 
-use config::mm::is_aligned_to_page;
+use config::mm::{align_offset_to_page, is_aligned_to_page, round_up_to_page};
 use hashbrown::HashMap;
 use sync::mutex::SpinNoIrqLock;
 
 use crate::Page;
 
+/// Lookups in all page caches that found the page.
+static HITS: AtomicUsize = AtomicUsize::new(0);
+/// Lookups in all page caches that did not, after which the page is usually
+/// read from the device.
+static MISSES: AtomicUsize = AtomicUsize::new(0);
+
+/// Hits and misses of page lookups in all page caches so far.
+pub fn page_cache_stat() -> (usize, usize) {
+    (HITS.load(Ordering::Relaxed), MISSES.load(Ordering::Relaxed))
+}
+
 pub struct PageCache {
     /// Map from aligned file offset to page cache.
     pages: SpinNoIrqLock<HashMap<usize, Arc<Page>>>,
+    /// Aligned file offsets of pages written since the last writeback.
+    dirty: SpinNoIrqLock<BTreeSet<usize>>,
+    /// Sequence number bumped whenever pages are dropped. A mapping that saw
+    /// an older one may still hold pages no longer in the cache.
+    seq: AtomicUsize,
 }
 
 impl PageCache {
     pub fn new() -> Self {
         Self {
             pages: SpinNoIrqLock::new(HashMap::new()),
+            dirty: SpinNoIrqLock::new(BTreeSet::new()),
+            seq: AtomicUsize::new(0),
         }
     }
 
+    pub fn seq(&self) -> usize {
+        self.seq.load(Ordering::Acquire)
+    }
+
     pub fn get_page(&self, offset_aligned: usize) -> Option<Arc<Page>> {
         debug_assert!(is_aligned_to_page(offset_aligned));
-        self.pages.lock().get(&offset_aligned).cloned()
+        let page = self.pages.lock().get(&offset_aligned).cloned();
+        match page {
+            Some(_) => HITS.fetch_add(1, Ordering::Relaxed),
+            None => MISSES.fetch_add(1, Ordering::Relaxed),
+        };
+        page
+    }
+
+    /// Whether the page is cached, which unlike `get_page` is not counted as
+    /// a lookup.
+    pub fn contains(&self, offset_aligned: usize) -> bool {
+        debug_assert!(is_aligned_to_page(offset_aligned));
+        self.pages.lock().contains_key(&offset_aligned)
     }
 
     pub fn insert_page(&self, offset_aligned: usize, page: Arc<Page>) {
@@ -28,8 +63,51 @@ impl PageCache {
         self.pages.lock().insert(offset_aligned, page);
     }
 
+    pub fn mark_dirty(&self, offset_aligned: usize) {
+        debug_assert!(is_aligned_to_page(offset_aligned));
+        self.dirty.lock().insert(offset_aligned);
+    }
+
+    pub fn has_dirty(&self) -> bool {
+        !self.dirty.lock().is_empty()
+    }
+
+    /// Take all dirty pages ordered by file offset, marking them clean.
+    pub fn take_dirty(&self) -> Vec<(usize, Arc<Page>)> {
+        let dirty = core::mem::take(&mut *self.dirty.lock());
+        let pages = self.pages.lock();
+        dirty
+            .into_iter()
+            .filter_map(|offset_aligned| {
+                pages
+                    .get(&offset_aligned)
+                    .map(|page| (offset_aligned, page.clone()))
+            })
+            .collect()
+    }
+
+    /// Drop pages beyond `len` and zero the tail of the last page, so that the
+    /// range will read as zero if the file grows again.
+    pub fn truncate(&self, len: usize) {
+        let mut pages = self.pages.lock();
+        let end = round_up_to_page(len);
+        pages.retain(|&offset_aligned, _| offset_aligned < end);
+        self.seq.fetch_add(1, Ordering::Release);
+        self.dirty
+            .lock()
+            .retain(|&offset_aligned| offset_aligned < end);
+        let (offset_aligned, offset_in_page) = align_offset_to_page(len);
+        if offset_in_page != 0 {
+            if let Some(page) = pages.get(&offset_aligned) {
+                page.bytes_array()[offset_in_page..].fill(0);
+            }
+        }
+    }
+
     pub fn clear(&self) {
-        self.pages.lock().clear()
+        self.pages.lock().clear();
+        self.seq.fetch_add(1, Ordering::Release);
+        self.dirty.lock().clear()
     }
 
     pub fn flush(&self) {
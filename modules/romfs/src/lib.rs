@@ -0,0 +1,183 @@
+//! A read-only file system whose files are built into the kernel image.
+//!
+//! It is also the smallest example of a file system on top of the helpers of
+//! vfs-core, and registers itself with `register_fs` once linked into the
+//! kernel.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, sync::Arc};
+use core::cmp;
+
+use async_trait::async_trait;
+use device_core::BlockDevice;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    CachedDentry, Dentry, DentryOps, DirFile, DirOps, File, FileMeta, FileSystemType,
+    FileSystemTypeMeta, Inode, InodeMeta, InodeMode, InodeType, MemSuperBlock, MountFlags,
+    SuperBlock,
+};
+
+/// Files in the image as `(path, content)`, directories are implied by the
+/// paths.
+static IMAGE: &[(&str, &[u8])] = &[
+    (
+        "README",
+        b"romfs: a read-only file system built into the kernel\n",
+    ),
+    ("etc/os-release", b"NAME=Phoenix\nID=phoenix\n"),
+];
+
+/// Magic number reported by statfs, as in linux.
+const ROMFS_MAGIC: i64 = 0x7275;
+
+pub struct RomFsType {
+    meta: FileSystemTypeMeta,
+}
+
+impl RomFsType {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            meta: FileSystemTypeMeta::new("romfs"),
+        })
+    }
+}
+
+vfs_core::register_fs!(RomFsType::new);
+
+impl FileSystemType for RomFsType {
+    fn meta(&self) -> &FileSystemTypeMeta {
+        &self.meta
+    }
+
+    fn base_mount(
+        self: Arc<Self>,
+        name: &str,
+        parent: Option<Arc<dyn Dentry>>,
+        _flags: MountFlags,
+        dev: Option<Arc<dyn BlockDevice>>,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        let sb = MemSuperBlock::new(dev, self.clone(), ROMFS_MAGIC);
+        let root_dentry = RomDentry::new(name, sb.clone(), parent);
+        let root_inode = RomInode::new(InodeMode::DIR, &[], sb.clone());
+        let root_dentry = self.mount_root(sb.clone(), root_dentry, root_inode);
+        for (path, data) in IMAGE {
+            let mut dentry = root_dentry.clone();
+            let mut names = path.split('/').peekable();
+            while let Some(name) = names.next() {
+                dentry = dentry.get_child_or_create(name);
+                if !dentry.is_negetive() {
+                    continue;
+                }
+                let inode = match names.peek() {
+                    Some(_) => RomInode::new(InodeMode::DIR, &[], sb.clone()),
+                    None => RomInode::new(InodeMode::FILE, *data, sb.clone()),
+                };
+                dentry.set_inode(inode);
+            }
+        }
+        Ok(root_dentry)
+    }
+
+    fn kill_sb(&self, sb: Arc<dyn SuperBlock>) -> SysResult<()> {
+        self.remove_sb(&sb);
+        Ok(())
+    }
+}
+
+pub type RomDentry = CachedDentry<RomOps>;
+
+pub struct RomOps;
+
+impl DentryOps for RomOps {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+        let inode = inode
+            .downcast_arc::<RomInode>()
+            .map_err(|_| SysError::EIO)?;
+        match inode.meta().inner.lock().mode.to_type() {
+            InodeType::Dir => Ok(DirFile::new(dentry, inode)),
+            _ => Ok(Arc::new(RomFile {
+                meta: FileMeta::new(dentry, inode.clone()),
+                data: inode.data,
+            })),
+        }
+    }
+
+    fn new_inode(_sb: Arc<dyn SuperBlock>, _mode: InodeMode) -> SysResult<Arc<dyn Inode>> {
+        Err(SysError::EROFS)
+    }
+
+    fn new_symlink(_sb: Arc<dyn SuperBlock>, _target: &str) -> SysResult<Arc<dyn Inode>> {
+        Err(SysError::EROFS)
+    }
+
+    fn unlink(_child: &Arc<dyn Dentry>) -> SysResult<()> {
+        Err(SysError::EROFS)
+    }
+
+    fn rename(_old: &Arc<dyn Dentry>, _new: &Arc<dyn Dentry>) -> SysResult<()> {
+        Err(SysError::EROFS)
+    }
+
+    fn link(_old: &Arc<dyn Dentry>, _new: &Arc<dyn Dentry>) -> SysResult<()> {
+        Err(SysError::EROFS)
+    }
+}
+
+pub struct RomInode {
+    meta: InodeMeta,
+    data: &'static [u8],
+}
+
+impl RomInode {
+    pub fn new(
+        mode: InodeMode,
+        data: &'static [u8],
+        super_block: Arc<dyn SuperBlock>,
+    ) -> Arc<Self> {
+        let mode = mode | InodeMode::OWNER_READ | InodeMode::GROUP_READ | InodeMode::OTHER_READ;
+        Arc::new(Self {
+            meta: InodeMeta::new(mode, super_block, data.len()),
+            data,
+        })
+    }
+}
+
+impl Inode for RomInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+
+    fn base_truncate(&self, _len: usize) -> SysResult<()> {
+        Err(SysError::EROFS)
+    }
+}
+
+impl DirOps for RomInode {}
+
+pub struct RomFile {
+    meta: FileMeta,
+    data: &'static [u8],
+}
+
+#[async_trait]
+impl File for RomFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        if offset >= self.data.len() {
+            return Ok(0);
+        }
+        let len = cmp::min(self.data.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&self.data[offset..offset + len]);
+        Ok(len)
+    }
+
+    async fn base_write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
+        Err(SysError::EROFS)
+    }
+}
@@ -74,6 +74,9 @@ pub struct SigPending {
     /// 如果在receive_siginfo的时候收到的信号位于should_wake信号集合中，
     /// 且task的wake存在，那么唤醒task
     pub should_wake: SigSet,
+    /// Readers and pollers of signal files waiting for a signal to arrive,
+    /// woken on every signal added
+    pub waiters: VecDeque<Waker>,
 }
 
 impl SigPending {
@@ -82,6 +85,7 @@ impl SigPending {
             queue: VecDeque::new(),
             bitmap: SigSet::empty(),
             should_wake: SigSet::empty(),
+            waiters: VecDeque::new(),
         }
     }
 
@@ -94,6 +98,16 @@ impl SigPending {
         if !self.bitmap.contain_signal(si.sig) {
             self.bitmap.add_signal(si.sig);
             self.queue.push_back(si);
+            while let Some(waker) = self.waiters.pop_front() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Wake `waker` when the next signal is added.
+    pub fn wait(&mut self, waker: &Waker) {
+        if !self.waiters.iter().any(|w| w.will_wake(waker)) {
+            self.waiters.push_back(waker.clone());
         }
     }
 
@@ -157,6 +171,14 @@ impl SigPending {
         !(expect & self.bitmap).is_empty()
     }
 
+    /// Discard the pending signals in `set`.
+    pub fn discard(&mut self, set: SigSet) {
+        if self.bitmap.intersects(set) {
+            self.queue.retain(|si| !set.contain_signal(si.sig));
+            self.bitmap.remove(set);
+        }
+    }
+
     // #[inline]
     // pub fn has_expect_sigset(&self, expect: SigSet) -> Option<SigInfo> {
     //     let x = self.bitmap & expect;
@@ -181,6 +203,9 @@ pub struct SigHandlers {
     /// 一个位掩码，如果为1表示该信号是用户定义的，如果为0表示默认。
     /// (实际上可以由actions间接得出来，这里只是存了一个快速路径)
     bitmap: SigSet,
+    /// Signals set to SIG_IGN by the user, told apart from those ignored by
+    /// default, which matters for SIGCHLD.
+    ignored: SigSet,
 }
 
 impl SigHandlers {
@@ -188,6 +213,7 @@ impl SigHandlers {
         Self {
             actions: core::array::from_fn(|signo| Action::new((signo + 1).into())),
             bitmap: SigSet::empty(),
+            ignored: SigSet::empty(),
         }
     }
 
@@ -203,6 +229,19 @@ impl SigHandlers {
             ActionType::User { .. } | ActionType::Kill => self.bitmap.add_signal(sig),
             _ => self.bitmap.remove_signal(sig),
         }
+        self.ignored.remove_signal(sig);
+    }
+
+    /// Like `update` with an action of `ActionType::Ignore`, for SIG_IGN.
+    pub fn ignore(&mut self, sig: Sig, new: Action) {
+        debug_assert!(new.atype == ActionType::Ignore);
+        self.update(sig, new);
+        self.ignored.add_signal(sig);
+    }
+
+    /// Whether the user set `sig` to SIG_IGN.
+    pub fn is_ignored(&self, sig: Sig) -> bool {
+        self.ignored.contain_signal(sig)
     }
 
     /// it is used in execve because it changed the memory
@@ -16,6 +16,104 @@ pub enum SigDetails {
         /// sender's pid
         pid: usize,
     },
+    /// SIGCHLD sent when a child changes state
+    Child {
+        /// child's pid
+        pid: usize,
+        /// exit code, or the signal that killed or stopped the child
+        status: i32,
+    },
+    /// SIGSEGV, SIGBUS raised by a memory access
+    Fault {
+        /// faulting address
+        addr: usize,
+    },
+    /// SIGIO, or the signal set by `F_SETSIG`, sent when a file gets ready
+    Poll {
+        /// events the file is ready for
+        band: usize,
+        /// fd of the file
+        fd: usize,
+    },
+    /// Signal of a POSIX timer, see timer_create(2)
+    Timer {
+        /// id of the timer
+        tid: usize,
+        /// expirations missed while the signal was pending
+        overrun: i32,
+        /// `sigev_value` the timer was created with
+        value: usize,
+    },
+}
+
+/// `siginfo_t` as seen by the user.
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+pub struct LinuxSigInfo {
+    pub si_signo: i32,
+    pub si_errno: i32,
+    pub si_code: i32,
+    _pad0: i32,
+    /// union of the signal specific fields, where `si_pid` and `si_addr` are
+    /// both the first field
+    pub si_fields: [u64; 14],
+}
+
+impl From<SigInfo> for LinuxSigInfo {
+    fn from(si: SigInfo) -> Self {
+        let mut siginfo_v = LinuxSigInfo::default();
+        siginfo_v.si_signo = si.sig.raw() as _;
+        siginfo_v.si_code = si.code;
+        match si.details {
+            SigDetails::Kill { pid } => siginfo_v.si_fields[0] = pid as u64,
+            // `si_status` follows `si_pid` and `si_uid`
+            SigDetails::Child { pid, status } => {
+                siginfo_v.si_fields[0] = pid as u64;
+                siginfo_v.si_fields[1] = status as u32 as u64;
+            }
+            SigDetails::Fault { addr } => siginfo_v.si_fields[0] = addr as u64,
+            // `si_fd` follows `si_band`
+            SigDetails::Poll { band, fd } => {
+                siginfo_v.si_fields[0] = band as u64;
+                siginfo_v.si_fields[1] = fd as u32 as u64;
+            }
+            // `si_overrun` follows `si_tid`, and `si_value` the two of them
+            SigDetails::Timer {
+                tid,
+                overrun,
+                value,
+            } => {
+                siginfo_v.si_fields[0] = tid as u32 as u64 | (overrun as u32 as u64) << 32;
+                siginfo_v.si_fields[1] = value as u64;
+            }
+            SigDetails::None => {}
+        }
+        siginfo_v
+    }
+}
+
+/// `struct sigevent` of timer_create(2), telling how to notify an expiration.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct SigEvent {
+    /// Data passed with the notification, as `si_value` of a signal
+    pub sigev_value: usize,
+    pub sigev_signo: i32,
+    pub sigev_notify: i32,
+    /// union of `sigev_notify_thread_id` and the function and attributes of
+    /// `SIGEV_THREAD`, which libc implements
+    pub _sigev_un: [i32; 12],
+}
+
+impl SigEvent {
+    /// Notify by sending `sigev_signo` to the process.
+    pub const SIGEV_SIGNAL: i32 = 0;
+    /// Do not notify.
+    pub const SIGEV_NONE: i32 = 1;
+    /// Notify by running a function in a new thread, done by libc.
+    pub const SIGEV_THREAD: i32 = 2;
+    /// Notify by sending `sigev_signo` to the thread `sigev_notify_thread_id`.
+    pub const SIGEV_THREAD_ID: i32 = 4;
 }
 
 #[allow(unused)]
@@ -55,4 +153,30 @@ impl SigInfo {
     /// stopped child has continued
     pub const CLD_CONTINUED: i32 = 6;
     pub const NSIGCHLD: i32 = 6;
+
+    // SIGILL si_codes
+    /// illegal opcode
+    pub const ILL_ILLOPC: i32 = 1;
+
+    // SIGSEGV si_codes
+    /// address not mapped to object
+    pub const SEGV_MAPERR: i32 = 1;
+    /// invalid permissions for mapped object
+    pub const SEGV_ACCERR: i32 = 2;
+
+    // SIGBUS si_codes
+    /// invalid address alignment
+    pub const BUS_ADRALN: i32 = 1;
+    /// nonexistent physical address
+    pub const BUS_ADRERR: i32 = 2;
+    /// object specific hardware error
+    pub const BUS_OBJERR: i32 = 3;
+
+    // SIGPOLL si_codes
+    /// data input available
+    pub const POLL_IN: i32 = 1;
+    /// output buffers available
+    pub const POLL_OUT: i32 = 2;
+    /// device disconnected
+    pub const POLL_HUP: i32 = 6;
 }
@@ -30,10 +30,28 @@ impl Default for SignalStack {
     }
 }
 
+/// Minimum size of an alternate signal stack, defined in <asm/sigcontext.h>
+/// of riscv.
+pub const MINSIGSTKSZ: usize = 2048;
+
 impl SignalStack {
+    /// The task is running on the stack, only reported by sigaltstack(2).
+    pub const SS_ONSTACK: i32 = 1;
+    /// The stack is disabled.
+    pub const SS_DISABLE: i32 = 2;
+    /// The stack is disabled while a handler runs on it, and set again when
+    /// the handler returns.
+    pub const SS_AUTODISARM: i32 = i32::MIN;
+
     pub fn get_stack_top(&self) -> usize {
         self.ss_sp + self.ss_size
     }
+
+    /// Whether the stack pointer `sp` is within the stack. The stack grows
+    /// down, so its top is included but its base is not.
+    pub fn contains(&self, sp: usize) -> bool {
+        sp > self.ss_sp && sp - self.ss_sp <= self.ss_size
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -158,6 +158,9 @@ bitflags! {
         const SYNCHRONOUS_MASK = SigSet::SIGSEGV.bits() | SigSet::SIGBUS.bits()
         | SigSet::SIGILL.bits() | SigSet::SIGTRAP.bits() | SigSet::SIGFPE.bits() | SigSet::SIGSYS.bits();
         // const SYNCHRONOUS_MASK = (1<<3) | (1<<4) | (1<<6) | (1<<7) | (1<<10) | (1<<30) ;
+        /// Signals that stop the process by default.
+        const STOP_MASK = SigSet::SIGSTOP.bits() | SigSet::SIGTSTP.bits()
+        | SigSet::SIGTTIN.bits() | SigSet::SIGTTOU.bits();
     }
 }
 
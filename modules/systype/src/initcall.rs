@@ -0,0 +1,106 @@
+//! Initcalls, the init functions of subsystems and drivers run at boot.
+//!
+//! An initcall is registered where it is defined with one of the
+//! [`early_initcall`], [`core_initcall`], [`driver_initcall`],
+//! [`fs_initcall`] and [`late_initcall`] macros, and the kernel runs all of
+//! them level by level. Initcalls of the same level run in link order, so one
+//! that depends on another must be registered at a later level.
+
+use crate::SysResult;
+
+/// Level of an initcall, the kernel runs all initcalls of a level before the
+/// next one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum InitcallLevel {
+    /// Memory and trap setup, nothing may allocate before.
+    Early,
+    /// Core facilities drivers depend on, like the clock.
+    Core,
+    /// Device probing and drivers, with the interrupt controller.
+    Driver,
+    /// File systems, which may use block devices.
+    Fs,
+    /// Everything else, run with the whole kernel up.
+    Late,
+}
+
+impl InitcallLevel {
+    pub const ALL: [Self; 5] = [Self::Early, Self::Core, Self::Driver, Self::Fs, Self::Late];
+}
+
+/// An initcall record, placed in the `.rodata.initcalls` section, see the
+/// kernel linker script.
+pub struct Initcall {
+    pub name: &'static str,
+    pub level: InitcallLevel,
+    pub func: fn() -> SysResult<()>,
+}
+
+/// Register `func` as an initcall of `level`.
+///
+/// ```ignore
+/// systype::initcall!(Driver, init_devices);
+/// ```
+#[macro_export]
+macro_rules! initcall {
+    ($level:ident, $func:path) => {
+        const _: () = {
+            #[used]
+            #[link_section = ".rodata.initcalls"]
+            static INITCALL: $crate::initcall::Initcall = $crate::initcall::Initcall {
+                name: stringify!($func),
+                level: $crate::initcall::InitcallLevel::$level,
+                func: $func,
+            };
+        };
+    };
+}
+
+#[macro_export]
+macro_rules! early_initcall {
+    ($func:path) => {
+        $crate::initcall!(Early, $func);
+    };
+}
+
+#[macro_export]
+macro_rules! core_initcall {
+    ($func:path) => {
+        $crate::initcall!(Core, $func);
+    };
+}
+
+#[macro_export]
+macro_rules! driver_initcall {
+    ($func:path) => {
+        $crate::initcall!(Driver, $func);
+    };
+}
+
+#[macro_export]
+macro_rules! fs_initcall {
+    ($func:path) => {
+        $crate::initcall!(Fs, $func);
+    };
+}
+
+#[macro_export]
+macro_rules! late_initcall {
+    ($func:path) => {
+        $crate::initcall!(Late, $func);
+    };
+}
+
+/// All initcalls registered, in link order.
+pub fn registered_initcalls() -> &'static [Initcall] {
+    extern "C" {
+        fn _sinitcalls();
+        fn _einitcalls();
+    }
+    let start = _sinitcalls as usize as *const Initcall;
+    let end = _einitcalls as usize as *const Initcall;
+    // SAFETY: the linker script places only initcall records between the two
+    // symbols
+    unsafe { core::slice::from_raw_parts(start, end.offset_from(start) as usize) }
+}
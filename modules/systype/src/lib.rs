@@ -2,6 +2,9 @@
 #![no_main]
 
 extern crate alloc;
+
+pub mod initcall;
+
 use alloc::boxed::Box;
 use core::{future::Future, pin::Pin};
 
@@ -102,10 +105,20 @@ pub enum SysError {
     ENOTEMPTY = 39,
     /// Too many symbolic links encountered
     ELOOP = 40,
+    /// Value too large for defined data type
+    EOVERFLOW = 75,
     /// Socket operation on non-socket
     ENOTSOCK = 88,
+    /// Destination address required
+    EDESTADDRREQ = 89,
+    /// Protocol not available
+    ENOPROTOOPT = 92,
+    /// Protocol not supported
+    EPROTONOSUPPORT = 93,
     /// Unsupported
     EOPNOTSUPP = 95,
+    /// Address family not supported by protocol
+    EAFNOSUPPORT = 97,
     /// Socket address is already in use
     EADDRINUSE = 98,
     /// Address not available
@@ -168,9 +181,14 @@ impl SysError {
             ENOSYS => "Invalid system call number",
             ENOTEMPTY => "Directory not empty",
             ELOOP => "Too many symbolic links encountered",
+            EOVERFLOW => "Value too large for defined data type",
             ENOTSOCK => "Socket operation on non-socket",
+            EDESTADDRREQ => "Destination address required",
+            ENOPROTOOPT => "Protocol not available",
+            EPROTONOSUPPORT => "Protocol not supported",
             ENOTCONN => "Transport endpoint is not connected",
             EOPNOTSUPP => "Unsupported Error",
+            EAFNOSUPPORT => "Address family not supported by protocol",
             EADDRNOTAVAIL => "Address not available",
             EADDRINUSE => "Address already in use",
             EISCONN => "Transport endpoint is already connected",
@@ -212,9 +230,11 @@ pub struct Rusage {
 }
 
 pub const RLIM_INFINITY: usize = usize::MAX;
+/// Number of resources a limit can be set on.
+pub const RLIM_NLIMITS: usize = 16;
 
 /// Resource Limit
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(C)]
 pub struct RLimit {
     /// Soft limit: the kernel enforces for the corresponding resource
@@ -18,5 +18,13 @@ pub const CLOCK_MONOTONIC: usize = 1;
 pub const CLOCK_PROCESS_CPUTIME_ID: usize = 2;
 /// 用于测量调用线程消耗的CPU时间
 pub const CLOCK_THREAD_CPUTIME_ID: usize = 3;
+/// 与 CLOCK_MONOTONIC 相同，但不受 NTP 调整影响
+pub const CLOCK_MONOTONIC_RAW: usize = 4;
+/// 精度较低但读取更快的 CLOCK_REALTIME
+pub const CLOCK_REALTIME_COARSE: usize = 5;
+/// 精度较低但读取更快的 CLOCK_MONOTONIC，取自上一次时钟中断的时间
+pub const CLOCK_MONOTONIC_COARSE: usize = 6;
+/// 与 CLOCK_MONOTONIC 相同，但包含系统挂起的时间
+pub const CLOCK_BOOTTIME: usize = 7;
 
 pub static mut CLOCK_DEVIATION: [Duration; SUPPORT_CLOCK] = [Duration::ZERO; SUPPORT_CLOCK];
@@ -15,9 +15,6 @@ pub struct TaskTimeStat {
     system_time_start: Duration,
     user_time_start: Duration,
     schedule_time_start: Duration,
-
-    child_user_time: Duration,
-    child_system_stime: Duration,
 }
 
 impl TaskTimeStat {
@@ -27,23 +24,17 @@ impl TaskTimeStat {
             // task_start: start,
             user_time: Duration::ZERO,
             system_time: Duration::ZERO,
-            child_user_time: Duration::ZERO,
-            child_system_stime: Duration::ZERO,
             system_time_start: Duration::ZERO,
             user_time_start: Duration::ZERO,
             schedule_time_start: Duration::ZERO,
         }
     }
 
-    /// return the cutime and cstime
+    /// return the utime and stime
     pub fn user_system_time(&self) -> (Duration, Duration) {
         (self.user_time, self.system_time)
     }
 
-    pub fn child_user_system_time(&self) -> (Duration, Duration) {
-        (self.child_user_time, self.child_system_stime)
-    }
-
     #[inline]
     pub fn user_time(&self) -> Duration {
         self.user_time
@@ -58,11 +49,6 @@ impl TaskTimeStat {
         self.user_time + self.system_time
     }
 
-    pub fn update_child_time(&mut self, (utime, stime): (Duration, Duration)) {
-        self.child_user_time += utime;
-        self.child_system_stime += stime;
-    }
-
     pub fn record_switch_in(&mut self) {
         let current_time = get_time_duration();
 
@@ -87,7 +73,7 @@ impl TaskTimeStat {
     pub fn record_trap_return(&mut self) {
         let current_time = get_time_duration();
 
-        let stime_slice = current_time - self.user_time_start;
+        let stime_slice = current_time - self.system_time_start;
         self.system_time += stime_slice;
 
         self.user_time_start = current_time;
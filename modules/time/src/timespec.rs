@@ -41,3 +41,19 @@ impl From<TimeSpec> for Duration {
         Duration::new(time_spec.tv_sec as u64, time_spec.tv_nsec as u32)
     }
 }
+
+/// An interval timer of timerfd_settime(2) and timer_settime(2).
+#[derive(Default, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ITimerSpec {
+    /// Interval for periodic timer
+    pub it_interval: TimeSpec,
+    /// Time until next expiration, or the time of it if absolute
+    pub it_value: TimeSpec,
+}
+
+impl ITimerSpec {
+    pub fn is_valid(&self) -> bool {
+        self.it_interval.is_valid() && self.it_value.is_valid()
+    }
+}
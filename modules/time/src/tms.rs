@@ -1,4 +1,6 @@
-use crate::stat::TaskTimeStat;
+use core::time::Duration;
+
+use config::time::USER_HZ;
 
 #[derive(Clone, Copy)]
 #[repr(C)]
@@ -16,14 +18,22 @@ pub struct TMS {
 }
 
 impl TMS {
-    pub fn from_task_time_stat(tts: &TaskTimeStat) -> Self {
-        let (utime, stime) = tts.user_system_time();
-        let (cutime, cstime) = tts.child_user_system_time();
+    /// Times of a process from its user and system time and those of its
+    /// waited for children, all in clock ticks.
+    pub fn new(
+        (utime, stime): (Duration, Duration),
+        (cutime, cstime): (Duration, Duration),
+    ) -> Self {
         Self {
-            tms_utime: utime.as_micros() as usize,
-            tms_stime: stime.as_micros() as usize,
-            tms_cutime: cutime.as_micros() as usize,
-            tms_cstime: cstime.as_micros() as usize,
+            tms_utime: Self::ticks(utime),
+            tms_stime: Self::ticks(stime),
+            tms_cutime: Self::ticks(cutime),
+            tms_cstime: Self::ticks(cstime),
         }
     }
+
+    /// `time` in clock ticks of `USER_HZ`.
+    pub fn ticks(time: Duration) -> usize {
+        (time.as_micros() * USER_HZ as u128 / 1_000_000) as usize
+    }
 }
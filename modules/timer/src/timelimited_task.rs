@@ -14,13 +14,13 @@ pub enum TimeLimitedTaskOutput<T> {
     Ok(T),
 }
 
-pub struct TimeLimitedTaskFuture<F: Future + Send + 'static> {
+pub struct TimeLimitedTaskFuture<F: Future + Send> {
     expire: Duration,
     future: F,
     in_timermanager: bool,
 }
 
-impl<F: Future + Send + 'static> TimeLimitedTaskFuture<F> {
+impl<F: Future + Send> TimeLimitedTaskFuture<F> {
     pub fn new(limit: Duration, future: F) -> Self {
         Self {
             expire: get_time_duration() + limit,
@@ -30,7 +30,7 @@ impl<F: Future + Send + 'static> TimeLimitedTaskFuture<F> {
     }
 }
 
-impl<F: Future + Send + 'static> Future for TimeLimitedTaskFuture<F> {
+impl<F: Future + Send> Future for TimeLimitedTaskFuture<F> {
     type Output = TimeLimitedTaskOutput<F::Output>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
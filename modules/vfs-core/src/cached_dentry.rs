@@ -0,0 +1,227 @@
+use alloc::{
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::marker::PhantomData;
+
+use device_core::DevId;
+use systype::{SysError, SysResult};
+
+use crate::{Dentry, DentryMeta, File, Inode, InodeMode, InodeState, RenameFlags, SuperBlock};
+
+/// How files of a [`CachedDentry`] are opened and created.
+pub trait DentryOps: Send + Sync + 'static {
+    /// Open a new file on `dentry`, which points to `inode`, see
+    /// `Dentry::base_open`.
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>>;
+
+    /// Create the inode of a new file with `mode`.
+    fn new_inode(_sb: Arc<dyn SuperBlock>, _mode: InodeMode) -> SysResult<Arc<dyn Inode>> {
+        Err(SysError::EPERM)
+    }
+
+    /// Create the inode of a new device node for `dev_id`, whose type is in
+    /// `mode`.
+    fn new_device(
+        _sb: Arc<dyn SuperBlock>,
+        _mode: InodeMode,
+        _dev_id: DevId,
+    ) -> SysResult<Arc<dyn Inode>> {
+        Err(SysError::EPERM)
+    }
+
+    /// Create the inode of a new symlink to `target`.
+    fn new_symlink(_sb: Arc<dyn SuperBlock>, _target: &str) -> SysResult<Arc<dyn Inode>> {
+        Err(SysError::EPERM)
+    }
+
+    /// Called before `child` is removed from the dentry cache, or replaced by
+    /// a rename.
+    fn unlink(_child: &Arc<dyn Dentry>) -> SysResult<()> {
+        Ok(())
+    }
+
+    /// Called before the inode of `old` is moved to `new`, which may replace
+    /// the file at `new`. Files are not renamed unless allowed here.
+    fn rename(_old: &Arc<dyn Dentry>, _new: &Arc<dyn Dentry>) -> SysResult<()> {
+        Err(SysError::EPERM)
+    }
+
+    /// Called before the inode of `old` is linked at `new`. Files are not
+    /// linked unless allowed here.
+    fn link(_old: &Arc<dyn Dentry>, _new: &Arc<dyn Dentry>) -> SysResult<()> {
+        Err(SysError::EPERM)
+    }
+}
+
+/// A dentry whose children are all kept in the dentry cache, i.e. the cache is
+/// the only record of the directory tree, as in tmpfs and devfs.
+///
+/// A name not in the cache does not exist, so lookup never goes further.
+pub struct CachedDentry<O: DentryOps> {
+    meta: DentryMeta,
+    _ops: PhantomData<O>,
+}
+
+impl<O: DentryOps> CachedDentry<O> {
+    pub fn new(
+        name: &str,
+        super_block: Arc<dyn SuperBlock>,
+        parent: Option<Arc<dyn Dentry>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            meta: DentryMeta::new(name, super_block, parent),
+            _ops: PhantomData,
+        })
+    }
+
+    pub fn into_dyn(self: Arc<Self>) -> Arc<dyn Dentry> {
+        self
+    }
+}
+
+impl<O: DentryOps> Dentry for CachedDentry<O> {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        let inode = self.inode()?;
+        O::open(self, inode)
+    }
+
+    fn base_lookup(self: Arc<Self>, name: &str) -> SysResult<Arc<dyn Dentry>> {
+        Ok(self.into_dyn().get_child_or_create(name))
+    }
+
+    fn base_create(self: Arc<Self>, name: &str, mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        let sub_inode = O::new_inode(self.super_block(), mode)?;
+        let sub_dentry = self.into_dyn().get_child_or_create(name);
+        sub_dentry.set_inode(sub_inode);
+        Ok(sub_dentry)
+    }
+
+    fn base_mknod(
+        self: Arc<Self>,
+        name: &str,
+        mode: InodeMode,
+        dev_id: DevId,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        let sub_inode = O::new_device(self.super_block(), mode, dev_id)?;
+        let sub_dentry = self.into_dyn().get_child_or_create(name);
+        sub_dentry.set_inode(sub_inode);
+        Ok(sub_dentry)
+    }
+
+    fn base_unlink(self: Arc<Self>, name: &str) -> SysResult<()> {
+        let child = self.get_child(name).ok_or(SysError::ENOENT)?;
+        O::unlink(&child)?;
+        self.remove_child(name);
+        Ok(())
+    }
+
+    /// Move the inode to `new`, along with the cached subtree of a directory.
+    /// A file replaced at `new` is unlinked.
+    fn base_rename_to(self: Arc<Self>, new: Arc<dyn Dentry>, flags: RenameFlags) -> SysResult<()> {
+        if !Weak::ptr_eq(&self.meta.super_block, &new.meta().super_block) {
+            return Err(SysError::EXDEV);
+        }
+        let old = self.into_dyn();
+        if Arc::ptr_eq(&old, &new) {
+            return Ok(());
+        }
+        let old_inode = old.inode()?;
+        O::rename(&old, &new)?;
+        if flags.contains(RenameFlags::RENAME_EXCHANGE) {
+            let new_inode = new.inode()?;
+            let (old_children, new_children) = (take_children(&old), take_children(&new));
+            old.clear_inode();
+            new.clear_inode();
+            old.set_inode(new_inode);
+            new.set_inode(old_inode);
+            adopt_children(&old, new_children);
+            adopt_children(&new, old_children);
+            return Ok(());
+        }
+        if let Ok(new_inode) = new.inode() {
+            // both are links to the same file
+            if core::ptr::addr_eq(Arc::as_ptr(&old_inode), Arc::as_ptr(&new_inode)) {
+                return Ok(());
+            }
+            match (old_inode.itype().is_dir(), new_inode.itype().is_dir()) {
+                (true, false) => return Err(SysError::ENOTDIR),
+                (false, true) => return Err(SysError::EISDIR),
+                _ => {}
+            }
+            O::unlink(&new)?;
+            let removed = new_inode.itype().is_dir() || {
+                let mut inner = new_inode.meta().inner.lock();
+                inner.nlink = inner.nlink.saturating_sub(1);
+                inner.nlink == 0
+            };
+            if removed {
+                new_inode.set_state(InodeState::Removed);
+            }
+            new.clear_inode();
+        }
+        let children = take_children(&old);
+        // NOTE: the old name is left as a negative dentry
+        old.clear_inode();
+        new.set_inode(old_inode);
+        adopt_children(&new, children);
+        Ok(())
+    }
+
+    fn base_link(self: Arc<Self>, new: &Arc<dyn Dentry>) -> SysResult<()> {
+        if !Weak::ptr_eq(&self.meta.super_block, &new.meta().super_block) {
+            return Err(SysError::EXDEV);
+        }
+        let inode = self.inode()?;
+        if inode.itype().is_dir() {
+            return Err(SysError::EPERM);
+        }
+        O::link(&self.clone().into_dyn(), new)?;
+        new.set_inode(inode);
+        Ok(())
+    }
+
+    fn base_symlink(self: Arc<Self>, name: &str, target: &str) -> SysResult<()> {
+        let sub_inode = O::new_symlink(self.super_block(), target)?;
+        let sub_dentry = self.into_dyn().get_child_or_create(name);
+        sub_dentry.set_inode(sub_inode);
+        Ok(())
+    }
+
+    fn base_new_child(self: Arc<Self>, name: &str) -> Arc<dyn Dentry> {
+        Self::new(name, self.super_block(), Some(self))
+    }
+}
+
+/// Take the children of `dentry` in the same file system out of the cache.
+/// Mount points stay where they are.
+fn take_children(dentry: &Arc<dyn Dentry>) -> Vec<Arc<dyn Dentry>> {
+    let mut children = dentry.meta().children.lock();
+    let names: Vec<_> = children
+        .iter()
+        .filter(|(_, child)| Weak::ptr_eq(&child.meta().super_block, &dentry.meta().super_block))
+        .map(|(name, _)| name.clone())
+        .collect();
+    names
+        .iter()
+        .filter_map(|name| children.remove(name))
+        .collect()
+}
+
+/// Recreate `children` of another dentry below `dentry`, since the parent of
+/// a dentry never changes. Their inodes are moved as is, so the link counts
+/// stay the same.
+fn adopt_children(dentry: &Arc<dyn Dentry>, children: Vec<Arc<dyn Dentry>>) {
+    for child in children {
+        let Some(inode) = child.meta().inode.lock().take() else {
+            continue;
+        };
+        let new_child = dentry.get_child_or_create(child.name());
+        *new_child.meta().inode.lock() = Some(inode);
+        adopt_children(&new_child, take_children(&child));
+    }
+}
@@ -2,13 +2,25 @@ use alloc::{
     collections::BTreeMap,
     string::{String, ToString},
     sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::{
+    default,
+    fmt::Error,
+    mem::MaybeUninit,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
 };
-use core::{default, fmt::Error, mem::MaybeUninit, str::FromStr};
 
-use sync::mutex::spin_mutex::SpinMutex;
+use async_utils::block_on;
+use device_core::DevId;
+use sync::mutex::{spin_mutex::SpinMutex, SleepLock};
 use systype::{SysError, SysResult, SyscallResult};
 
-use crate::{inode::Inode, File, InodeMode, InodeState, InodeType, Mutex, RenameFlags, SuperBlock};
+use crate::{
+    find_root_mount, inode::Inode, File, InodeMode, InodeState, InodeType, Mount, Mutex,
+    RenameFlags, SuperBlock,
+};
 
 pub struct DentryMeta {
     /// Name of this file or directory.
@@ -22,6 +34,10 @@ pub struct DentryMeta {
     /// Children dentries. Key value pair is <name, dentry>.
     // PERF: may be no need to be BTreeMap, since we will look up in hash table
     pub children: Mutex<BTreeMap<String, Arc<dyn Dentry>>>,
+    /// Held while children are looked up, created or loaded through the file
+    /// system, which may sleep on disk I/O. A name is then resolved once, and
+    /// an exclusive create sees any creation that came before.
+    pub child_lock: SleepLock<()>,
     pub state: Mutex<DentryState>,
 }
 
@@ -32,6 +48,7 @@ impl DentryMeta {
         parent: Option<Arc<dyn Dentry>>,
     ) -> Self {
         log::debug!("[Dentry::new] new dentry with name {name}");
+        DENTRY_COUNT.fetch_add(1, Ordering::Relaxed);
         let super_block = Arc::downgrade(&super_block);
         let inode = Mutex::new(None);
         Self {
@@ -40,11 +57,25 @@ impl DentryMeta {
             inode,
             parent: parent.map(|p| Arc::downgrade(&p)),
             children: Mutex::new(BTreeMap::new()),
+            child_lock: SleepLock::new(()),
             state: Mutex::new(DentryState::UnInit),
         }
     }
 }
 
+impl Drop for DentryMeta {
+    fn drop(&mut self) {
+        DENTRY_COUNT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+static DENTRY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of dentries alive, cached or not.
+pub fn dentry_count() -> usize {
+    DENTRY_COUNT.load(Ordering::Relaxed)
+}
+
 #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum DentryState {
     /// Either not read from disk or write in memory.
@@ -58,6 +89,11 @@ pub trait Dentry: Send + Sync {
     fn meta(&self) -> &DentryMeta;
 
     /// Open a file associated with the inode that this dentry points to.
+    ///
+    /// Every call returns a new `File`, i.e. a new open file description, which
+    /// keeps the state private to this open, e.g. the offset, the status flags
+    /// and the position in a directory. State shared by all opens, such as the
+    /// data of a FIFO or the termios of a tty, lives in the inode.
     fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>>;
 
     /// Look up in a directory inode and find file with `name`.
@@ -74,6 +110,17 @@ pub trait Dentry: Send + Sync {
     /// inode for the negative child and return the child.
     fn base_create(self: Arc<Self>, name: &str, mode: InodeMode) -> SysResult<Arc<dyn Dentry>>;
 
+    /// Called by the mknod(2) system call. Create a character or block device
+    /// node for `dev_id` as the child with `name`, like `base_create`.
+    fn base_mknod(
+        self: Arc<Self>,
+        name: &str,
+        mode: InodeMode,
+        dev_id: DevId,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::EPERM)
+    }
+
     /// Called by the unlink(2) system call. Reduce an inode ref count in a
     /// directory inode. Delete the inode when inode ref count is one.
     fn base_unlink(self: Arc<Self>, name: &str) -> SysResult<()>;
@@ -90,6 +137,12 @@ pub trait Dentry: Send + Sync {
         Err(SysError::EINVAL)
     }
 
+    /// Follow a magic link such as /proc/self/fd/N, which refers to an open
+    /// file rather than to a path. Returns `None` for ordinary symlinks.
+    fn base_magic_link(self: Arc<Self>) -> SysResult<Option<Arc<dyn Dentry>>> {
+        Ok(None)
+    }
+
     /// Create a negetive child dentry with `name`.
     fn base_new_child(self: Arc<Self>, _name: &str) -> Arc<dyn Dentry> {
         todo!()
@@ -132,23 +185,44 @@ pub trait Dentry: Send + Sync {
         self.meta().children.lock().remove(name)
     }
 
+    /// Set the inode of a negative dentry. Lookups and creations of a name are
+    /// serialized by the `child_lock` of the parent, should the inode be set
+    /// twice all the same, the one set first is kept and the others are
+    /// dropped, so that one file never has two inodes.
     fn set_inode(&self, inode: Arc<dyn Inode>) {
-        if self.meta().inode.lock().is_some() {
-            log::warn!("[Dentry::set_inode] replace inode in {:?}", self.name());
+        let mut slot = self.meta().inode.lock();
+        if slot.is_some() {
+            log::debug!("[Dentry::set_inode] keep the inode of {:?}", self.name());
+            return;
         }
-        *self.meta().inode.lock() = Some(inode);
+        *slot = Some(inode);
     }
 
     fn clear_inode(&self) {
-        *self.meta().inode.lock() = None;
+        self.meta().inode.lock().take();
     }
 
-    /// Insert a child dentry to this dentry.
+    /// Insert a child dentry to this dentry, return the old child with the
+    /// same name if any.
+    ///
+    /// A child of another file system, i.e. a mount point, will replace the old
+    /// one. However, a child of the same file system will never be replaced,
+    /// since two dentries for one file make their inodes diverge.
     fn insert(&self, child: Arc<dyn Dentry>) -> Option<Arc<dyn Dentry>> {
-        self.meta()
-            .children
-            .lock()
-            .insert(child.name_string(), child)
+        let mut children = self.meta().children.lock();
+        if let Some(old) = children.get(child.name()) {
+            if Weak::ptr_eq(&old.meta().super_block, &child.meta().super_block) {
+                if !core::ptr::addr_eq(Arc::as_ptr(old), Arc::as_ptr(&child)) {
+                    log::error!(
+                        "[Dentry::insert] reject duplicate dentry {} in {}",
+                        child.name(),
+                        self.name()
+                    );
+                }
+                return Some(old.clone());
+            }
+        }
+        children.insert(child.name_string(), child)
     }
 
     fn set_state(&self, state: DentryState) {
@@ -157,6 +231,12 @@ pub trait Dentry: Send + Sync {
 
     /// Get the path of this dentry.
     fn path(&self) -> String {
+        if let Some(mount) = root_mount_of(self) {
+            return mount
+                .covered
+                .as_ref()
+                .map_or(String::from("/"), |covered| covered.path());
+        }
         if let Some(p) = self.parent() {
             let p_path = p.path();
             if p_path == "/" {
@@ -170,6 +250,35 @@ pub trait Dentry: Send + Sync {
     }
 }
 
+/// The mount of the file system `dentry` is the root of, which tells where the
+/// root stands, see `mount_table`. `None` if it is no root.
+fn root_mount_of<D: Dentry + ?Sized>(dentry: &D) -> Option<Arc<Mount>> {
+    let is_root = dentry.meta().super_block.upgrade().is_some_and(|sb| {
+        sb.meta()
+            .root_dentry
+            .get()
+            .is_some_and(|root| core::ptr::addr_eq(Arc::as_ptr(root), dentry as *const D))
+    });
+    is_root
+        .then(|| find_root_mount(dentry as *const D))
+        .flatten()
+}
+
+/// A subdirectory links back to its parent through "..", so the link count of a
+/// directory is 2 plus the number of its subdirectories. It is read from disk
+/// with the inode, and kept up to date here when a subdirectory is made,
+/// removed or moved. A count of 1 means the file system does not count
+/// subdirectories, so it is left as it is.
+fn update_dir_nlink(dir: Option<&Arc<dyn Dentry>>, delta: isize) {
+    let Some(Ok(inode)) = dir.map(|dir| dir.inode()) else {
+        return;
+    };
+    let mut inner = inode.meta().inner.lock();
+    if delta != 0 && inner.nlink != 1 {
+        inner.nlink = inner.nlink.saturating_add_signed(delta).max(2);
+    }
+}
+
 impl dyn Dentry {
     pub fn state(&self) -> DentryState {
         *self.meta().state.lock()
@@ -187,6 +296,7 @@ impl dyn Dentry {
         if !self.inode()?.itype().is_dir() {
             return Err(SysError::ENOTDIR);
         }
+        let _guard = block_on(self.meta().child_lock.lock());
         let child = self.get_child_or_create(name);
         if child.state() == DentryState::UnInit {
             log::trace!(
@@ -200,14 +310,62 @@ impl dyn Dentry {
         Ok(child)
     }
 
+    /// Create a file named `name`, or get the one that already exists.
     pub fn create(self: &Arc<Self>, name: &str, mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        self.do_create(name, mode, false)
+    }
+
+    /// Create a file named `name`, failing with `EEXIST` if it exists, even if
+    /// it was created by a racing task since it was looked up.
+    pub fn create_exclusive(
+        self: &Arc<Self>,
+        name: &str,
+        mode: InodeMode,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        self.do_create(name, mode, true)
+    }
+
+    fn do_create(
+        self: &Arc<Self>,
+        name: &str,
+        mode: InodeMode,
+        exclusive: bool,
+    ) -> SysResult<Arc<dyn Dentry>> {
         if !self.inode()?.itype().is_dir() {
             return Err(SysError::ENOTDIR);
         }
+        let _guard = block_on(self.meta().child_lock.lock());
+        // NOTE: a negative child turns positive in place
         let child = self.get_child_or_create(name);
+        if exclusive && !child.is_negetive() {
+            return Err(SysError::EEXIST);
+        }
         if child.is_negetive() {
             self.clone().base_create(name, mode)?;
+            child.set_state(DentryState::Sync);
+            if mode.to_type().is_dir() {
+                update_dir_nlink(Some(self), 1);
+            }
+        }
+        Ok(child)
+    }
+
+    pub fn mknod(
+        self: &Arc<Self>,
+        name: &str,
+        mode: InodeMode,
+        dev_id: DevId,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        if !self.inode()?.itype().is_dir() {
+            return Err(SysError::ENOTDIR);
+        }
+        let _guard = block_on(self.meta().child_lock.lock());
+        let child = self.get_child_or_create(name);
+        if !child.is_negetive() {
+            return Err(SysError::EEXIST);
         }
+        self.clone().base_mknod(name, mode, dev_id)?;
+        child.set_state(DentryState::Sync);
         Ok(child)
     }
 
@@ -216,12 +374,42 @@ impl dyn Dentry {
             return Err(SysError::ENOTDIR);
         }
         let sub_dentry = self.get_child(name).ok_or(SysError::ENOENT)?;
-        sub_dentry.inode()?.set_state(InodeState::Removed);
+        let sub_inode = sub_dentry.inode()?;
+        let is_dir = sub_inode.itype().is_dir();
+        // NOTE: some file systems, e.g. lwext4, remove a directory with all it
+        // holds, so it is checked here for all of them
+        if is_dir && !sub_dentry.is_empty_dir()? {
+            return Err(SysError::ENOTEMPTY);
+        }
         self.clone().base_unlink(name)?;
+        // NOTE: the inode lives on while other hard links to it remain
+        let removed = is_dir || {
+            let mut inner = sub_inode.meta().inner.lock();
+            inner.nlink = inner.nlink.saturating_sub(1);
+            inner.nlink == 0
+        };
+        if removed {
+            sub_inode.set_state(InodeState::Removed);
+        }
         sub_dentry.clear_inode();
+        if is_dir {
+            update_dir_nlink(Some(self), -1);
+            // A removed directory is detached from the tree for good, with its cached
+            // children, so that a task still inside it does not get back in when the
+            // name is reused, as a new dentry is looked up for it then.
+            sub_dentry.meta().children.lock().clear();
+            self.remove_child(name);
+        }
         Ok(())
     }
 
+    /// Whether the directory of this dentry has no entries but "." and "..".
+    /// The entries not in the dentry cache yet are loaded first.
+    pub fn is_empty_dir(self: &Arc<Self>) -> SysResult<bool> {
+        self.open()?.load_dir()?;
+        Ok(self.children().values().all(|child| child.is_negetive()))
+    }
+
     pub fn rename_to(self: &Arc<Self>, new: &Arc<Self>, flags: RenameFlags) -> SysResult<()> {
         if flags.contains(RenameFlags::RENAME_EXCHANGE)
             && (flags.contains(RenameFlags::RENAME_NOREPLACE)
@@ -235,16 +423,44 @@ impl dyn Dentry {
 
         if new.is_negetive() && flags.contains(RenameFlags::RENAME_EXCHANGE) {
             return Err(SysError::ENOENT);
-        } else if flags.contains(RenameFlags::RENAME_NOREPLACE) {
+        } else if !new.is_negetive() && flags.contains(RenameFlags::RENAME_NOREPLACE) {
             return Err(SysError::EEXIST);
         }
-        self.clone().base_rename_to(new.clone(), flags)
+        // a directory replaced must be empty, like one removed
+        if !new.is_negetive()
+            && !Arc::ptr_eq(self, new)
+            && new.inode()?.itype().is_dir()
+            && !new.is_empty_dir()?
+        {
+            return Err(SysError::ENOTEMPTY);
+        }
+        // NOTE: `new` may be a cached negative dentry, it takes the inode in place so
+        // that no lookup will find the name missing afterwards
+        let is_dir = |dentry: &Arc<Self>| dentry.inode().is_ok_and(|i| i.itype().is_dir());
+        let (old_is_dir, new_is_dir) = (is_dir(self), is_dir(new));
+        self.clone().base_rename_to(new.clone(), flags)?;
+        new.set_state(DentryState::Sync);
+        if !Arc::ptr_eq(self, new) {
+            // a directory moved out of or into a parent, or one replaced at `new`
+            let exchanged = flags.contains(RenameFlags::RENAME_EXCHANGE) && new_is_dir;
+            let (old_parent, new_parent) = (self.parent(), new.parent());
+            update_dir_nlink(
+                old_parent.as_ref(),
+                exchanged as isize - old_is_dir as isize,
+            );
+            update_dir_nlink(
+                new_parent.as_ref(),
+                old_is_dir as isize - new_is_dir as isize,
+            );
+        }
+        Ok(())
     }
 
     pub fn symlink(self: &Arc<Self>, name: &str, target: &str) -> SysResult<()> {
         if !self.inode()?.itype().is_dir() {
             return Err(SysError::ENOTDIR);
         }
+        let _guard = block_on(self.meta().child_lock.lock());
         let child = self.get_child_or_create(name);
         if child.is_negetive() {
             self.clone().base_symlink(name, target)
@@ -259,9 +475,10 @@ impl dyn Dentry {
         } else if !new.is_negetive() {
             Err(SysError::EEXIST)
         } else {
-            let ret = self.clone().base_link(new);
+            self.clone().base_link(new)?;
+            new.set_state(DentryState::Sync);
             self.inode()?.meta().inner.lock().nlink += 1;
-            ret
+            Ok(())
         }
     }
 
@@ -271,21 +488,71 @@ impl dyn Dentry {
         child
     }
 
+    /// Get the child dentry with `name`, or create a negetive one if not
+    /// exists. The check and the insertion are done under the lock of children,
+    /// so that only one child dentry will be created for the same name.
     pub fn get_child_or_create(self: &Arc<Self>, name: &str) -> Arc<dyn Dentry> {
-        self.get_child(name).unwrap_or_else(|| {
-            let new_dentry = self.new_child(name);
-            self.insert(new_dentry.clone());
-            new_dentry
-        })
+        self.meta()
+            .children
+            .lock()
+            .entry(name.to_string())
+            .or_insert_with(|| self.new_child(name))
+            .clone()
+    }
+
+    /// Whether a file system is mounted at this dentry, i.e. it is the root
+    /// dentry of its super block.
+    pub fn is_mount_root(self: &Arc<Self>) -> bool {
+        self.super_block()
+            .meta()
+            .root_dentry
+            .get()
+            .is_some_and(|root| Arc::ptr_eq(root, self))
+    }
+
+    /// The dentry this one stands for in the tree of its parent: the mount
+    /// point covered for the root of a mount, which may have been moved since
+    /// it was mounted, and the dentry itself otherwise.
+    pub fn mount_place(self: &Arc<Self>) -> Arc<dyn Dentry> {
+        root_mount_of(self.as_ref())
+            .and_then(|mount| mount.covered.clone())
+            .unwrap_or_else(|| self.clone())
+    }
+
+    /// Get the path of this dentry as seen from `root`, e.g. by a task whose
+    /// root directory is not the system root. A dentry outside of `root` gets
+    /// its full path prefixed with "(unreachable)" as Linux does.
+    pub fn path_from(self: &Arc<Self>, root: &Arc<Self>) -> String {
+        let mut names = Vec::new();
+        let mut dentry = self.clone();
+        while !Arc::ptr_eq(&dentry, root) {
+            let place = dentry.mount_place();
+            match place.parent() {
+                Some(parent) => {
+                    names.push(place.name_string());
+                    dentry = parent;
+                }
+                None => return String::from("(unreachable)") + &self.path(),
+            }
+        }
+        let mut path = String::new();
+        for name in names.iter().rev() {
+            path.push('/');
+            path.push_str(name);
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
     }
 
     pub fn is_descendant_of(self: &Arc<Self>, dir: &Arc<Self>) -> bool {
-        let mut parent_opt = self.parent();
+        let mut parent_opt = self.mount_place().parent();
         while let Some(parent) = parent_opt {
-            if Arc::ptr_eq(self, dir) {
+            if Arc::ptr_eq(&parent, dir) {
                 return true;
             }
-            parent_opt = parent.parent();
+            parent_opt = parent.mount_place().parent();
         }
         false
     }
@@ -0,0 +1,104 @@
+use alloc::{boxed::Box, string::String, sync::Arc};
+
+use async_trait::async_trait;
+use systype::{SysError, SysResult, SyscallResult};
+
+use crate::{Dentry, DirEntry, File, FileMeta, Inode};
+
+/// A directory inode, which knows how to load its entries into the dentry
+/// cache.
+pub trait DirOps: Inode {
+    /// Load all children of `dentry`, which points to this inode, into the
+    /// dentry cache. Nothing to do for a file system whose dentry cache is the
+    /// only record of the directory tree.
+    fn load_dir(&self, _dentry: &Arc<dyn Dentry>) -> SysResult<()> {
+        Ok(())
+    }
+
+    /// Write back the entries of this directory.
+    fn fsync_dir(&self) -> SysResult<()> {
+        Ok(())
+    }
+}
+
+/// An open directory. Its entries are read from the dentry cache once loaded
+/// by the inode.
+pub struct DirFile<I: DirOps> {
+    meta: FileMeta,
+    dir: Arc<I>,
+}
+
+impl<I: DirOps> DirFile<I> {
+    pub fn new(dentry: Arc<dyn Dentry>, dir: Arc<I>) -> Arc<Self> {
+        Arc::new(Self {
+            meta: FileMeta::new(dentry, dir.clone()),
+            dir,
+        })
+    }
+}
+
+#[async_trait]
+impl<I: DirOps> File for DirFile<I> {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, _offset: usize, _buf: &mut [u8]) -> SyscallResult {
+        Err(SysError::EISDIR)
+    }
+
+    async fn base_write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
+        Err(SysError::EISDIR)
+    }
+
+    /// Read the next entry from the dentry cache, which should be loaded
+    /// first.
+    ///
+    /// Offset 0 is ".", 1 is "..", and `i + 2` is the `i`th child in the
+    /// dentry cache, which is ordered by name. The offset of an entry is the
+    /// one of the entry after it, like `d_off` of linux, so that reading can be
+    /// resumed from the offset of the last entry read.
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        let pos = self.pos();
+        let (next_pos, name, inode) = match pos {
+            0 => (1, String::from("."), self.inode()),
+            1 => {
+                // the root is its own parent
+                let parent = self
+                    .dentry()
+                    .parent()
+                    .and_then(|parent| parent.inode().ok())
+                    .unwrap_or_else(|| self.inode());
+                (2, String::from(".."), parent)
+            }
+            _ => {
+                let entry = self
+                    .dentry()
+                    .children()
+                    .into_iter()
+                    .enumerate()
+                    .skip(pos - 2)
+                    .find_map(|(i, (name, child))| Some((i + 3, name, child.inode().ok()?)));
+                let Some(entry) = entry else {
+                    return Ok(None);
+                };
+                entry
+            }
+        };
+        self.set_pos(next_pos);
+        Ok(Some(DirEntry {
+            ino: inode.ino() as u64,
+            off: next_pos as u64,
+            itype: inode.itype(),
+            name,
+        }))
+    }
+
+    fn base_load_dir(&self) -> SysResult<()> {
+        self.dir.load_dir(&self.dentry())
+    }
+
+    fn base_fsync(&self, _datasync: bool) -> SysResult<()> {
+        self.dir.fsync_dir()
+    }
+}
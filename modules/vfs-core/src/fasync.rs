@@ -0,0 +1,74 @@
+use alloc::{
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::any::Any;
+
+use crate_interface::call_interface;
+
+use crate::{File, OpenFlags, PollEvents};
+
+#[crate_interface::def_interface]
+pub trait KernelFasyncIf {
+    /// Send `SIGIO`, or the signal set by `F_SETSIG`, to `owner`, telling
+    /// that the file at `fd` got ready for `band`.
+    fn send_sigio(owner: FileOwner, fd: usize, band: PollEvents);
+}
+
+/// Receiver of the signal of a file with `O_ASYNC`, set by `fcntl(F_SETOWN)`.
+#[derive(Clone, Default)]
+pub struct FileOwner {
+    /// Process, or process group if negative, to signal. 0 for no one.
+    pub pid: isize,
+    /// The owner process, so that the file is no longer owned once it has
+    /// exited, even if its pid is used again. `None` for a process group.
+    pub process: Option<Weak<dyn Any + Send + Sync>>,
+    /// Signal sent instead of `SIGIO` if not 0, set by `fcntl(F_SETSIG)`.
+    pub sig: usize,
+}
+
+impl FileOwner {
+    /// Whether there is an owner to signal.
+    pub fn is_alive(&self) -> bool {
+        match &self.process {
+            Some(process) => process.strong_count() > 0,
+            None => self.pid != 0,
+        }
+    }
+}
+
+/// Open files with `O_ASYNC` set on an object that can get ready, such as a
+/// pipe, along with the fd it was set at.
+#[derive(Clone, Default)]
+pub struct FasyncList {
+    files: Vec<(Weak<dyn File>, usize)>,
+}
+
+impl FasyncList {
+    /// Add `file` at `fd`, or remove it if not `on`.
+    pub fn update(&mut self, file: &Arc<dyn File>, fd: usize, on: bool) {
+        self.files.retain(|(f, _)| {
+            f.strong_count() > 0 && !core::ptr::addr_eq(f.as_ptr(), Arc::as_ptr(file))
+        });
+        if on {
+            self.files.push((Arc::downgrade(file), fd));
+        }
+    }
+
+    /// Signal the owners of the files. To be called once every time the object
+    /// turns ready for `band`, without any lock of the object held.
+    pub fn notify(&self, band: PollEvents) {
+        for (file, fd) in self.files.iter() {
+            let Some(file) = file.upgrade() else {
+                continue;
+            };
+            if !file.flags().contains(OpenFlags::O_ASYNC) {
+                continue;
+            }
+            let owner = file.meta().owner.lock().clone();
+            if owner.is_alive() {
+                call_interface!(KernelFasyncIf::send_sigio(owner, *fd, band));
+            }
+        }
+    }
+}
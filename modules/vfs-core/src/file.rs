@@ -1,11 +1,13 @@
 use alloc::{boxed::Box, ffi::CString, string::String, sync::Arc, vec, vec::Vec};
 use core::{
     cmp,
-    sync::atomic::{AtomicUsize, Ordering},
+    ops::DerefMut,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
     usize,
 };
 
 use async_trait::async_trait;
+use async_utils::yield_now;
 use config::{
     board::BLOCK_SIZE,
     mm::{
@@ -13,15 +15,17 @@ use config::{
         MAX_BUFFERS_PER_PAGE, PAGE_MASK, PAGE_SIZE,
     },
 };
+use device_core::BlockDevice;
 use downcast_rs::{impl_downcast, DowncastSync};
 use memory::address;
 use page::Page;
 use spin::Mutex;
+use sync::mutex::SleepLock;
 use systype::{SysError, SysResult, SyscallResult};
 
 use crate::{
-    inode, Dentry, DirEntry, Inode, InodeState, InodeType, OpenFlags, PollEvents, SeekFrom,
-    SuperBlock,
+    inode, request_queue, Dentry, DirEntry, FileOwner, Inode, InodeState, InodeType, IoBand,
+    OpenFlags, PollEvents, SeekFrom, SuperBlock, MAX_REQUEST_SIZE,
 };
 
 pub struct FileMeta {
@@ -30,18 +34,51 @@ pub struct FileMeta {
     pub inode: Arc<dyn Inode>,
 
     /// Offset position of this file.
-    /// WARN: may cause trouble if this is not locked with other things.
+    ///
+    /// A `File` is an open file description, all fds dup'd from it and
+    /// inherited by fork hold the same `Arc<dyn File>`, so they share this
+    /// offset.
     pub pos: AtomicUsize,
+    /// Serialize `read` and `write` that advance `pos`, so that concurrent
+    /// users of the same open file never get overlapping ranges.
+    pub pos_lock: SleepLock<()>,
     pub flags: Mutex<OpenFlags>,
+    /// File system of the inode, kept busy while this file is open. `None`
+    /// for files of no file system, such as pipes and sockets.
+    pub super_block: Option<Arc<dyn SuperBlock>>,
+    /// Whether this file holds the write access of the inode, see
+    /// `File::get_write_access`.
+    pub write_access: AtomicBool,
+    /// Receiver of the signal when the file gets ready, if `O_ASYNC` is set.
+    pub owner: Mutex<FileOwner>,
 }
 
 impl FileMeta {
     pub fn new(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> Self {
+        let super_block = inode.meta().super_block.upgrade();
+        if let Some(sb) = super_block.as_ref() {
+            sb.meta().open_files.fetch_add(1, Ordering::SeqCst);
+        }
         Self {
             dentry,
             inode,
             pos: 0.into(),
+            pos_lock: SleepLock::new(()),
             flags: Mutex::new(OpenFlags::empty()),
+            super_block,
+            write_access: AtomicBool::new(false),
+            owner: Mutex::new(FileOwner::default()),
+        }
+    }
+}
+
+impl Drop for FileMeta {
+    fn drop(&mut self) {
+        if *self.write_access.get_mut() {
+            self.inode.put_write_access();
+        }
+        if let Some(sb) = self.super_block.take() {
+            crate::put_open_file(sb);
         }
     }
 }
@@ -58,10 +95,12 @@ pub trait File: Send + Sync + DowncastSync {
         todo!()
     }
 
-    /// Read directory entries. This is called by the getdents(2) system call.
+    /// Read the directory entry at the offset of this file and advance the
+    /// offset past it. This is called by the getdents(2) system call.
     ///
     /// For every call, this function will return an valid entry, or an error.
-    /// If it read to the end of directory, it will return an empty entry.
+    /// If it read to the end of directory, it will return an empty entry. The
+    /// `off` of an entry is the offset to seek to for the entry after it.
     fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
         todo!()
     }
@@ -76,7 +115,7 @@ pub trait File: Send + Sync + DowncastSync {
         log::trace!("[File::read_page] read offset {offset_aligned}");
 
         if offset_aligned >= self.size() {
-            log::warn!("[File::read_page] reach end of file");
+            log::debug!("[File::read_page] reach end of file");
             return Ok(None);
         }
 
@@ -89,6 +128,9 @@ pub trait File: Send + Sync + DowncastSync {
         let len = self
             .base_read_at(offset_aligned, page.bytes_array())
             .await?;
+        // NOTE: the page may be mapped to user as a whole, so nothing beyond the end
+        // of file should be left from the last user of the frame
+        page.bytes_array()[len..].fill(0);
 
         // let virtio_blk = device
         //     .downcast_arc::<VirtIoBlkDev>()
@@ -218,12 +260,13 @@ pub trait File: Send + Sync + DowncastSync {
             let len = (buf_it.len()).min(PAGE_SIZE - offset_in_page);
             page.bytes_array_range(offset_in_page..offset_in_page + len)
                 .copy_from_slice(&buf_it[0..len]);
+            page_cache.mark_dirty(offset_aligned);
             log::trace!("[File::write] write count {len}, buf len {}", buf_it.len());
             offset_it += len;
             buf_it = &buf_it[len..];
         }
         if offset_it > self.size() {
-            log::warn!(
+            log::debug!(
                 "[File::write_at] write beyond file, offset_it:{offset_it}, size:{}",
                 self.size()
             );
@@ -263,14 +306,30 @@ pub trait File: Send + Sync + DowncastSync {
         Ok(buf.len())
     }
 
+    /// Push the data the file system buffers for this file, below the page
+    /// cache, to the device.
     fn flush(&self) -> SysResult<usize> {
-        todo!()
+        Ok(0)
+    }
+
+    /// Write back the metadata of this file, e.g. its size and timestamps. If
+    /// `datasync`, only the metadata needed to read the data back, e.g. the
+    /// size, has to be written.
+    ///
+    /// Called by `fsync` after the file data has reached the device.
+    fn base_fsync(&self, _datasync: bool) -> SysResult<()> {
+        Ok(())
     }
 
     fn ioctl(&self, _cmd: usize, _arg: usize) -> SyscallResult {
         Err(SysError::ENOTTY)
     }
 
+    /// Called when `O_ASYNC` is set on `file`, which is this file, at `fd`, or
+    /// cleared if not `on`. A file that can get ready adds `file` to the
+    /// `FasyncList` it notifies.
+    fn base_fasync(&self, _file: &Arc<dyn File>, _fd: usize, _on: bool) {}
+
     async fn readlink(&self, buf: &mut [u8]) -> SyscallResult {
         todo!()
     }
@@ -296,6 +355,16 @@ pub trait File: Send + Sync + DowncastSync {
         self.meta().inode.itype()
     }
 
+    /// Block device the data of this file is on, whose request queue its reads
+    /// and writes wait in.
+    fn block_device(&self) -> Option<Arc<dyn BlockDevice>> {
+        if self.itype().is_file() {
+            self.super_block().meta().device.clone()
+        } else {
+            None
+        }
+    }
+
     /// Called when the VFS needs to move the file position index.
     ///
     /// Return the result offset.
@@ -368,22 +437,242 @@ impl dyn File {
     /// Read from offset in self, and will fill `buf` until `buf` is full or eof
     /// is reached. Will advance offset.
     pub async fn read(&self, buf: &mut [u8]) -> SyscallResult {
+        let _guard = self.lock_pos().await;
         let pos = self.pos();
-        let ret = self.read_at(pos, buf).await?;
+        let ret = self.read_at_queued(pos, buf).await?;
         self.set_pos(pos + ret);
         Ok(ret)
     }
 
+    /// Write `buf` at offset of self, or at the end of file if self is opened
+    /// with `O_APPEND`. Will advance offset.
     pub async fn write(&self, buf: &[u8]) -> SyscallResult {
+        let _guard = self.lock_pos().await;
         if self.flags().contains(OpenFlags::O_APPEND) {
-            self.set_pos(self.size());
+            return self.append(buf).await;
         }
         let pos = self.pos();
-        let ret = self.write_at(pos, buf).await?;
+        let ret = self.write_at_queued(pos, buf).await?;
+        self.set_pos(pos + ret);
+        Ok(ret)
+    }
+
+    /// Read at `offset` like `read_at`, as block requests of the current task
+    /// of at most `MAX_REQUEST_SIZE` bytes, each waiting for its turn in the
+    /// request queue of the device. Files not on a block device are read at
+    /// once.
+    ///
+    /// NOTE: only for system calls, kernel reads under `block_on` must not wait
+    /// in the queue.
+    pub async fn read_at_queued(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        let Some(device) = self.block_device() else {
+            return self.read_at(offset, buf).await;
+        };
+        let queue = request_queue(&device);
+        let band = IoBand::current();
+        let mut total_len = 0;
+        for chunk in buf.chunks_mut(MAX_REQUEST_SIZE) {
+            if total_len > 0 {
+                // let the tasks woken by the last request send theirs in between
+                yield_now().await;
+            }
+            let ret = {
+                let _request = queue.submit(band, chunk.len()).await;
+                self.read_at(offset + total_len, chunk).await
+            };
+            match ret {
+                Ok(len) => {
+                    total_len += len;
+                    if len < chunk.len() {
+                        break;
+                    }
+                }
+                Err(_) if total_len > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(total_len)
+    }
+
+    /// Write at `offset` like `write_at`, as block requests of the current
+    /// task, see `read_at_queued`.
+    pub async fn write_at_queued(&self, offset: usize, buf: &[u8]) -> SyscallResult {
+        let Some(device) = self.block_device() else {
+            return self.write_at(offset, buf).await;
+        };
+        let queue = request_queue(&device);
+        let band = IoBand::current();
+        let mut total_len = 0;
+        for chunk in buf.chunks(MAX_REQUEST_SIZE) {
+            if total_len > 0 {
+                yield_now().await;
+            }
+            let ret = {
+                let _request = queue.submit(band, chunk.len()).await;
+                self.write_at(offset + total_len, chunk).await
+            };
+            match ret {
+                Ok(len) => {
+                    total_len += len;
+                    if len < chunk.len() {
+                        break;
+                    }
+                }
+                Err(_) if total_len > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(total_len)
+    }
+
+    /// Write `bufs` one after another at offset of self, or at the end of file
+    /// if self is opened with `O_APPEND`, stopping at the first short write.
+    /// Will advance offset.
+    ///
+    /// The offset lock, and the inode append lock for `O_APPEND`, are held
+    /// across all buffers, so the bytes of one call are never interleaved
+    /// with another write.
+    pub async fn write_vectored(&self, bufs: &[&[u8]]) -> SyscallResult {
+        let _guard = self.lock_pos().await;
+        let inode = self.inode();
+        let (_append_guard, mut pos) = if self.flags().contains(OpenFlags::O_APPEND) {
+            (Some(inode.meta().append_lock.lock().await), self.size())
+        } else {
+            (None, self.pos())
+        };
+        let mut total_len = 0;
+        for buf in bufs.iter().filter(|buf| !buf.is_empty()) {
+            let ret = match self.write_at_queued(pos, buf).await {
+                Ok(ret) => ret,
+                Err(_) if total_len > 0 => break,
+                Err(e) => return Err(e),
+            };
+            pos += ret;
+            total_len += ret;
+            if ret < buf.len() {
+                break;
+            }
+        }
+        self.set_pos(pos);
+        Ok(total_len)
+    }
+
+    /// Write `buf` at the end of file and move offset after it. The end of file
+    /// is looked up under the inode append lock, so appends through different
+    /// open files never overwrite each other.
+    async fn append(&self, buf: &[u8]) -> SyscallResult {
+        let inode = self.inode();
+        let _guard = inode.meta().append_lock.lock().await;
+        let pos = self.size();
+        let ret = self.write_at_queued(pos, buf).await?;
         self.set_pos(pos + ret);
         Ok(ret)
     }
 
+    /// Take the write access of the inode for this file opened for writing,
+    /// released when the file is dropped.
+    pub fn get_write_access(&self) -> SysResult<()> {
+        self.inode().get_write_access()?;
+        if self.meta().write_access.swap(true, Ordering::AcqRel) {
+            self.inode().put_write_access();
+        }
+        Ok(())
+    }
+
+    /// Lock the offset of regular files and directories. Other files like
+    /// pipes and ttys may block for a long time and do not care about offset.
+    async fn lock_pos(&self) -> Option<impl DerefMut<Target = ()> + Send + Sync + '_> {
+        if self.itype().is_file() || self.itype().is_dir() {
+            Some(self.meta().pos_lock.lock().await)
+        } else {
+            None
+        }
+    }
+
+    /// Flush dirty data of this file to the device, then its metadata, or only
+    /// the metadata needed to read the data back if `datasync`.
+    ///
+    /// Data and metadata are separated by a write barrier, so that after a
+    /// crash the metadata never refers to data that has not been written.
+    pub async fn fsync(&self, datasync: bool) -> SysResult<()> {
+        self.write_back_dirty(Some(IoBand::current())).await?;
+        self.sync_metadata(datasync)
+    }
+
+    /// Write the dirty pages of this file back to the file system, as block
+    /// requests in `band`, or at once if `None`, as needed under `block_on`.
+    pub async fn write_back_dirty(&self, band: Option<IoBand>) -> SysResult<()> {
+        let inode = self.inode();
+        let Some(page_cache) = inode.page_cache() else {
+            return Ok(());
+        };
+        let size = inode.size();
+        let mut dirty = page_cache.take_dirty();
+        dirty.retain(|&(offset_aligned, _)| offset_aligned < size);
+        if let Err(e) = self.write_back(&dirty, size, band).await {
+            for &(offset_aligned, _) in dirty.iter() {
+                page_cache.mark_dirty(offset_aligned);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Write the metadata of this file after the data written back before,
+    /// with a write barrier on each side, see `fsync`.
+    pub fn sync_metadata(&self, datasync: bool) -> SysResult<()> {
+        self.flush()?;
+        let device = self.super_block().meta().device.clone();
+        if let Some(device) = device.as_ref() {
+            device.flush();
+        }
+        self.base_fsync(datasync)?;
+        if let Some(device) = device.as_ref() {
+            device.flush();
+        }
+        Ok(())
+    }
+
+    /// Write `pages` ordered by offset back to the file system, batching pages
+    /// of contiguous offsets into one write, sent as block requests in `band`
+    /// if any.
+    async fn write_back(
+        &self,
+        pages: &[(usize, Arc<Page>)],
+        size: usize,
+        band: Option<IoBand>,
+    ) -> SysResult<()> {
+        let queue = band
+            .and(self.block_device())
+            .map(|device| request_queue(&device));
+        let mut pages = pages.iter().peekable();
+        while let Some((start, page)) = pages.next() {
+            let mut buf = Vec::new();
+            buf.extend_from_slice(page.bytes_array_range(0..PAGE_SIZE.min(size - start)));
+            while let Some((offset_aligned, page)) =
+                pages.next_if(|(offset_aligned, _)| *offset_aligned == start + buf.len())
+            {
+                buf.extend_from_slice(
+                    page.bytes_array_range(0..PAGE_SIZE.min(size - offset_aligned)),
+                );
+            }
+            log::debug!("[File::write_back] offset {start}, len {}", buf.len());
+            let Some(queue) = queue.as_ref() else {
+                self.base_write_at(*start, &buf).await?;
+                continue;
+            };
+            for (i, chunk) in buf.chunks(MAX_REQUEST_SIZE).enumerate() {
+                if i > 0 {
+                    yield_now().await;
+                }
+                let _request = queue.submit(band.unwrap(), chunk.len()).await;
+                self.base_write_at(start + i * MAX_REQUEST_SIZE, chunk)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
     /// Given interested events, keep track of these events and return events
     /// that is ready.
     // NOTE: async function but always returns `Ready`. Why async, to take the
@@ -402,57 +691,10 @@ impl dyn File {
         Ok(())
     }
 
-    pub fn read_dir(&self, buf: &mut [u8]) -> SyscallResult {
+    /// Read the next directory entry, `None` at the end of the directory.
+    pub fn read_dir(&self) -> SysResult<Option<DirEntry>> {
         self.load_dir()?;
-
-        #[derive(Debug, Clone, Copy)]
-        #[repr(C)]
-        struct LinuxDirent64 {
-            d_ino: u64,
-            d_off: u64,
-            d_reclen: u16,
-            d_type: u8,
-            // d_name follows here, which will be written later
-        }
-        let buf_len = buf.len();
-        // NOTE: Considering C struct align, we can not use `size_of` directly, because
-        // `size_of::<LinuxDirent64>` equals 24, which is not what we want.
-        const LEN_BEFORE_NAME: usize = 19;
-        let mut writen_len = 0;
-        let mut buf_it = buf;
-        for dentry in self.dentry().children().values().skip(self.pos()) {
-            if dentry.is_negetive() {
-                self.seek(SeekFrom::Current(1))?;
-                continue;
-            }
-            // align to 8 bytes
-            let c_name_len = dentry.name().len() + 1;
-            let rec_len = (LEN_BEFORE_NAME + c_name_len + 7) & !0x7;
-            let inode = dentry.inode()?;
-            let linux_dirent = LinuxDirent64 {
-                d_ino: inode.ino() as u64,
-                d_off: self.pos() as u64,
-                d_type: inode.itype() as u8,
-                d_reclen: rec_len as u16,
-            };
-
-            log::debug!("[sys_getdents64] linux dirent {linux_dirent:?}");
-            if writen_len + rec_len > buf_len {
-                break;
-            }
-
-            self.seek(SeekFrom::Current(1))?;
-            let ptr = buf_it.as_mut_ptr() as *mut LinuxDirent64;
-            unsafe {
-                ptr.copy_from_nonoverlapping(&linux_dirent, 1);
-            }
-            buf_it[LEN_BEFORE_NAME..LEN_BEFORE_NAME + c_name_len - 1]
-                .copy_from_slice(dentry.name().as_bytes());
-            buf_it[LEN_BEFORE_NAME + c_name_len - 1] = b'\0';
-            buf_it = &mut buf_it[rec_len..];
-            writen_len += rec_len;
-        }
-        Ok(writen_len)
+        self.base_read_dir()
     }
 
     /// Read all data from this file synchronously.
@@ -7,7 +7,7 @@ use alloc::{
 use device_core::BlockDevice;
 use systype::{SysError, SysResult};
 
-use crate::{Dentry, MountFlags, Mutex, SuperBlock};
+use crate::{add_mount, Dentry, DentryState, Inode, MountFlags, Mutex, SuperBlock};
 
 pub struct FileSystemTypeMeta {
     /// Name of this file system type.
@@ -49,6 +49,43 @@ pub trait FileSystemType: Send + Sync {
             .insert(abs_mount_path.to_string(), super_block);
     }
 
+    /// Forget `sb`, which is no longer mounted. Called by `kill_sb`.
+    fn remove_sb(&self, sb: &Arc<dyn SuperBlock>) {
+        self.meta()
+            .supers
+            .lock()
+            .retain(|_, super_block| !Arc::ptr_eq(super_block, sb));
+    }
+
+    /// Make `root_dentry` pointing to `root_inode` the root of the mount of
+    /// `sb`, and attach it below its parent if any, in place of the mount
+    /// point. Called at the end of `base_mount`.
+    fn mount_root(
+        &self,
+        sb: Arc<dyn SuperBlock>,
+        root_dentry: Arc<dyn Dentry>,
+        root_inode: Arc<dyn Inode>,
+    ) -> Arc<dyn Dentry> {
+        root_dentry.set_inode(root_inode);
+        // the root is complete as it is, a lookup of the mount point must not
+        // fill it from the file system below
+        root_dentry.set_state(DentryState::Sync);
+        let covered = root_dentry
+            .parent()
+            .and_then(|parent| parent.insert(root_dentry.clone()));
+        sb.set_root_dentry(root_dentry.clone());
+        let path = root_dentry.path();
+        self.insert_sb(&path, sb);
+        add_mount(path, root_dentry.clone(), covered);
+        root_dentry
+    }
+
+    /// Flags of this file system type, e.g. whether it is mounted from a
+    /// device.
+    fn fs_flags(&self) -> FileSystemFlags {
+        FileSystemFlags::empty()
+    }
+
     fn name(&self) -> &str {
         &self.meta().name
     }
@@ -79,6 +116,42 @@ impl dyn FileSystemType {
     }
 }
 
+/// Constructor of a file system type, see [`register_fs`].
+pub type FileSystemTypeCtor = fn() -> Arc<dyn FileSystemType>;
+
+/// Register a file system type with its constructor, so that the type is
+/// available once the crate defining it is linked into the kernel.
+///
+/// The constructors are collected in the `.rodata.fs_types` section, see the
+/// kernel linker script.
+///
+/// ```ignore
+/// vfs_core::register_fs!(TmpFsType::new);
+/// ```
+#[macro_export]
+macro_rules! register_fs {
+    ($ctor:path) => {
+        const _: () = {
+            #[used]
+            #[link_section = ".rodata.fs_types"]
+            static FS_TYPE_CTOR: $crate::FileSystemTypeCtor = || $ctor();
+        };
+    };
+}
+
+/// Construct all file system types registered with [`register_fs`].
+pub fn registered_fs_types() -> impl Iterator<Item = Arc<dyn FileSystemType>> {
+    extern "C" {
+        fn _sfs_types();
+        fn _efs_types();
+    }
+    let start = _sfs_types as usize as *const FileSystemTypeCtor;
+    let end = _efs_types as usize as *const FileSystemTypeCtor;
+    // SAFETY: the linker script places only constructors between the two symbols
+    let ctors = unsafe { core::slice::from_raw_parts(start, end.offset_from(start) as usize) };
+    ctors.iter().map(|ctor| ctor())
+}
+
 bitflags::bitflags! {
     pub struct FileSystemFlags:u32{
         /// The file system requires a device.
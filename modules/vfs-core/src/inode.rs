@@ -1,10 +1,15 @@
 use alloc::sync::{Arc, Weak};
-use core::mem::MaybeUninit;
+use core::{
+    mem::MaybeUninit,
+    sync::atomic::{AtomicIsize, AtomicUsize, Ordering},
+};
 
+use config::mm::PAGE_SIZE;
 use device_core::DevId;
 use downcast_rs::{impl_downcast, DowncastSync};
 use page::PageCache;
-use systype::{SysResult, SyscallResult};
+use sync::mutex::SleepLock;
+use systype::{SysError, SysResult, SyscallResult};
 use time::timespec::TimeSpec;
 
 use crate::{alloc_ino, Mutex, Stat, SuperBlock};
@@ -12,20 +17,30 @@ use crate::{alloc_ino, Mutex, Stat, SuperBlock};
 pub struct InodeMeta {
     /// Inode number.
     pub ino: usize,
-    /// Mode of inode.
-    pub mode: InodeMode,
     pub dev_id: Option<DevId>,
     pub super_block: Weak<dyn SuperBlock>,
 
     pub page_cache: Option<PageCache>,
+    /// Serialize appends from all open files of this inode, so that looking up
+    /// the end of file and writing there is atomic.
+    pub append_lock: SleepLock<()>,
+    /// Open files that may write this inode if positive, or negated count of
+    /// the executables mapping it if negative, see `Inode::get_write_access`.
+    pub write_count: AtomicIsize,
     pub inner: Mutex<InodeMetaInner>,
 }
 
 pub struct InodeMetaInner {
+    /// Mode of inode, whose type bits never change.
+    pub mode: InodeMode,
     /// Size of a file in bytes.
     pub size: usize,
     /// Link count.
     pub nlink: usize,
+    /// User ID of the owner.
+    pub uid: u32,
+    /// Group ID of the owner.
+    pub gid: u32,
     /// Last access time.
     pub atime: TimeSpec,
     /// Last modification time.
@@ -36,14 +51,22 @@ pub struct InodeMetaInner {
     pub state: InodeState,
 }
 
+static INODE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of inodes alive, cached or not.
+pub fn inode_count() -> usize {
+    INODE_COUNT.load(Ordering::Relaxed)
+}
+
 impl Drop for InodeMeta {
     fn drop(&mut self) {
+        INODE_COUNT.fetch_sub(1, Ordering::Relaxed);
         match self.inner.lock().state {
             InodeState::UnInit => {}
             InodeState::Sync => {}
             InodeState::Dirty => {
                 self.page_cache.as_ref().map(|page_cache| {
-                    log::warn!("[InodeMeta::drop] flush page cache to disk");
+                    log::debug!("[InodeMeta::drop] flush page cache to disk");
                     page_cache.flush()
                 });
             }
@@ -62,28 +85,76 @@ impl InodeMeta {
         } else {
             None
         };
+        INODE_COUNT.fetch_add(1, Ordering::Relaxed);
         Self {
             ino: alloc_ino(),
-            mode,
             super_block: Arc::downgrade(&super_block),
             dev_id: None,
             page_cache: address_space,
+            append_lock: SleepLock::new(()),
+            write_count: AtomicIsize::new(0),
             inner: Mutex::new(InodeMetaInner {
+                mode,
                 size,
+                uid: 0,
+                gid: 0,
                 atime: TimeSpec::default(),
                 mtime: TimeSpec::default(),
                 ctime: TimeSpec::default(),
                 state: InodeState::UnInit,
-                nlink: 1,
+                // "." of a directory is a link to itself
+                nlink: if itype.is_dir() { 2 } else { 1 },
             }),
         }
     }
 }
 
+impl InodeMeta {
+    /// Preferred I/O block size of the file system holding this inode.
+    pub fn block_size(&self) -> usize {
+        self.super_block
+            .upgrade()
+            .map_or(PAGE_SIZE, |sb| sb.block_size())
+    }
+
+    /// Device number of the device this inode represents, 0 if it is not a
+    /// device file.
+    pub fn rdev(&self) -> u64 {
+        self.dev_id.map_or(0, |dev_id| dev_id.encode())
+    }
+
+    /// Build the attributes of this inode from its metadata, which is all most
+    /// file systems keep.
+    pub fn stat(&self) -> Stat {
+        let inner = self.inner.lock();
+        let len = inner.size;
+        Stat {
+            st_dev: 0,
+            st_ino: self.ino as u64,
+            st_mode: inner.mode.bits(),
+            st_nlink: inner.nlink as _,
+            st_uid: inner.uid,
+            st_gid: inner.gid,
+            st_rdev: self.rdev(),
+            __pad: 0,
+            st_size: len as u64,
+            st_blksize: self.block_size() as _,
+            __pad2: 0,
+            st_blocks: (len / 512) as u64,
+            st_atime: inner.atime,
+            st_mtime: inner.mtime,
+            st_ctime: inner.ctime,
+            unused: 0,
+        }
+    }
+}
+
 pub trait Inode: Send + Sync + DowncastSync {
     fn meta(&self) -> &InodeMeta;
 
-    fn get_attr(&self) -> SysResult<Stat>;
+    fn get_attr(&self) -> SysResult<Stat> {
+        Ok(self.meta().stat())
+    }
 
     fn base_truncate(&self, len: usize) -> SysResult<()> {
         todo!()
@@ -95,6 +166,25 @@ pub trait Inode: Send + Sync + DowncastSync {
         todo!()
     }
 
+    /// Write the access and modification times to the file system, called by
+    /// `Inode::set_times`.
+    fn base_set_times(&self, _atime: TimeSpec, _mtime: TimeSpec) -> SysResult<()> {
+        Ok(())
+    }
+
+    /// Write the mode to the file system, called by `Inode::set_mode`. A file
+    /// system that can not keep the mode at all returns `EPERM`, which is the
+    /// default.
+    fn base_set_mode(&self, _mode: InodeMode) -> SysResult<()> {
+        Err(SysError::EPERM)
+    }
+
+    /// Write the owner to the file system, called by `Inode::set_owner`. By
+    /// default the owner is only kept in memory.
+    fn base_set_owner(&self, _uid: u32, _gid: u32) -> SysResult<()> {
+        Ok(())
+    }
+
     fn size(&self) -> usize {
         self.meta().inner.lock().size
     }
@@ -113,8 +203,12 @@ impl dyn Inode {
         self.meta().dev_id.expect("should own a dev id")
     }
 
+    pub fn mode(&self) -> InodeMode {
+        self.meta().inner.lock().mode
+    }
+
     pub fn itype(&self) -> InodeType {
-        self.meta().mode.to_type()
+        self.mode().to_type()
     }
 
     pub fn state(&self) -> InodeState {
@@ -134,12 +228,100 @@ impl dyn Inode {
         }
     }
 
+    /// Shrink or grow a regular file to `len` bytes. The gap will read as
+    /// zero when growing.
     pub fn truncate(&self, len: usize) -> SyscallResult {
         log::info!(
             "[Inode::truncate] len:{len:#x}, origin size:{:#x}",
             self.size()
         );
-        self.base_truncate(len).map(|_| 0)
+        match self.itype() {
+            InodeType::Dir => return Err(SysError::EISDIR),
+            InodeType::File => {}
+            _ => return Err(SysError::EINVAL),
+        }
+        self.base_truncate(len)?;
+        if let Some(page_cache) = self.page_cache() {
+            page_cache.truncate(len);
+        }
+        self.set_size(len);
+        self.set_state(InodeState::Dirty);
+        Ok(0)
+    }
+
+    /// Set the access and modification times, a `None` one is left
+    /// unchanged. The change time is set to `now`.
+    pub fn set_times(
+        &self,
+        atime: Option<TimeSpec>,
+        mtime: Option<TimeSpec>,
+        now: TimeSpec,
+    ) -> SysResult<()> {
+        let (atime, mtime) = {
+            let inner = self.meta().inner.lock();
+            (atime.unwrap_or(inner.atime), mtime.unwrap_or(inner.mtime))
+        };
+        self.base_set_times(atime, mtime)?;
+        let mut inner = self.meta().inner.lock();
+        inner.atime = atime;
+        inner.mtime = mtime;
+        inner.ctime = now;
+        Ok(())
+    }
+
+    /// Change the permission bits, and the set-user-ID, set-group-ID and
+    /// sticky bits, to those of `mode`. The type is kept.
+    pub fn set_mode(&self, mode: InodeMode) -> SysResult<()> {
+        let mode = (self.mode() & InodeMode::TYPE_MASK) | (mode - InodeMode::TYPE_MASK);
+        self.base_set_mode(mode)?;
+        self.meta().inner.lock().mode = mode;
+        Ok(())
+    }
+
+    /// Change the owner and group, a `None` one is left unchanged.
+    pub fn set_owner(&self, uid: Option<u32>, gid: Option<u32>) -> SysResult<()> {
+        let (uid, gid) = {
+            let inner = self.meta().inner.lock();
+            (uid.unwrap_or(inner.uid), gid.unwrap_or(inner.gid))
+        };
+        self.base_set_owner(uid, gid)?;
+        let mut inner = self.meta().inner.lock();
+        inner.uid = uid;
+        inner.gid = gid;
+        Ok(())
+    }
+
+    /// Take the right to write this inode, which fails with `ETXTBSY` while it
+    /// is mapped by a running executable. Released by `put_write_access`.
+    pub fn get_write_access(&self) -> SysResult<()> {
+        self.meta()
+            .write_count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count >= 0).then_some(count + 1)
+            })
+            .map(|_| ())
+            .map_err(|_| SysError::ETXTBSY)
+    }
+
+    pub fn put_write_access(&self) {
+        self.meta().write_count.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// Keep this inode from being written while it is executed, which fails
+    /// with `ETXTBSY` if it is open for writing. Released by
+    /// `allow_write_access`.
+    pub fn deny_write_access(&self) -> SysResult<()> {
+        self.meta()
+            .write_count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count <= 0).then_some(count - 1)
+            })
+            .map(|_| ())
+            .map_err(|_| SysError::ETXTBSY)
+    }
+
+    pub fn allow_write_access(&self) {
+        self.meta().write_count.fetch_add(1, Ordering::AcqRel);
     }
 
     pub fn get_blk_idx(&self, offset: usize) -> SysResult<usize> {
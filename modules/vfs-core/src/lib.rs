@@ -2,11 +2,16 @@
 #![no_main]
 #![feature(new_uninit)]
 
+mod cached_dentry;
 mod dentry;
+mod dir_file;
+mod fasync;
 mod file;
 mod file_system_type;
 mod inode;
+mod mount_table;
 mod path;
+mod request_queue;
 mod super_block;
 mod utils;
 
@@ -30,10 +35,15 @@ pub fn arc_zero() -> Arc<core::mem::MaybeUninit<usize>> {
     Arc::<usize>::new_zeroed()
 }
 
+pub use cached_dentry::*;
 pub use dentry::*;
+pub use dir_file::*;
+pub use fasync::*;
 pub use file::*;
 pub use file_system_type::*;
 pub use inode::*;
+pub use mount_table::*;
 pub use path::*;
+pub use request_queue::*;
 pub use super_block::*;
 pub use utils::*;
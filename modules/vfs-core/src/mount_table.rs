@@ -0,0 +1,257 @@
+//! Table of the mounts.
+//!
+//! A mount takes the place of the dentry it is mounted on in the children of
+//! its parent, so that a walk crossing the mount point lands in the mounted
+//! file system. The covered dentry is kept here to be restored on unmount.
+//!
+//! The parent of a dentry never changes, so a mount moved by `pivot_root`
+//! keeps the parent of its first mount point. The mount table tells where the
+//! root of a mount stands instead, see `Dentry::path`.
+//!
+//! Once its last mount is gone, a file system is synced and shut down with
+//! `kill_sb`. A lazy unmount may leave files open on it, then the shutdown
+//! waits for the last of them to be closed.
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::Ordering;
+
+use systype::{SysError, SysResult};
+
+use crate::{Dentry, Mutex, SuperBlock};
+
+pub struct Mount {
+    /// Absolute path of the mount point.
+    pub path: String,
+    /// Dentry in place of the mount point, the root of the file system or the
+    /// dentry bound for a bind mount.
+    pub root: Arc<dyn Dentry>,
+    /// Dentry hidden by this mount, `None` for the root mount.
+    pub covered: Option<Arc<dyn Dentry>>,
+    pub sb: Arc<dyn SuperBlock>,
+}
+
+/// Mounts in the order of mounting.
+static MOUNT_TABLE: Mutex<Vec<Arc<Mount>>> = Mutex::new(Vec::new());
+
+/// Record a mount of `root` at `path` hiding `covered`.
+pub fn add_mount(path: String, root: Arc<dyn Dentry>, covered: Option<Arc<dyn Dentry>>) {
+    let sb = root.super_block();
+    MOUNT_TABLE.lock().push(Arc::new(Mount {
+        path,
+        root,
+        covered,
+        sb,
+    }));
+}
+
+/// Get all mounts in the order of mounting.
+pub fn mount_table() -> Vec<Arc<Mount>> {
+    MOUNT_TABLE.lock().clone()
+}
+
+/// Find the mount of the file system `root` is the root dentry of, rather than
+/// a bind mount of it, which comes later.
+pub(crate) fn find_root_mount<T: ?Sized>(root: *const T) -> Option<Arc<Mount>> {
+    MOUNT_TABLE
+        .lock()
+        .iter()
+        .find(|mount| core::ptr::addr_eq(Arc::as_ptr(&mount.root), root))
+        .cloned()
+}
+
+/// Make the mount of `new_root` the root mount, and move the old root mount
+/// onto `put_old`, which is `new_root` or a directory below it.
+///
+/// The mount of `new_root` is detached from its mount point, so that the old
+/// tree does not hold the new one, which holds the old one below `put_old`.
+/// The paths of the other mounts are rewritten as seen from the new root.
+pub fn pivot_root(new_root: &Arc<dyn Dentry>, put_old: &Arc<dyn Dentry>) -> SysResult<()> {
+    let old_mount = MOUNT_TABLE
+        .lock()
+        .iter()
+        .find(|mount| mount.covered.is_none())
+        .cloned()
+        .ok_or(SysError::EINVAL)?;
+    let new_mount = find_root_mount(Arc::as_ptr(new_root)).ok_or(SysError::EINVAL)?;
+    let new_covered = new_mount.covered.clone().ok_or(SysError::EBUSY)?;
+    let new_root_path = new_mount.path.clone();
+    // the path seen from the new root of a path below it, `None` for another one
+    let rebase = |path: &str| match path.strip_prefix(new_root_path.as_str()) {
+        Some("") => Some(String::from("/")),
+        Some(rest) if rest.starts_with('/') => Some(String::from(rest)),
+        _ => None,
+    };
+    let put_old_path = rebase(&put_old.path()).ok_or(SysError::EINVAL)?;
+
+    new_covered
+        .parent()
+        .ok_or(SysError::EBUSY)?
+        .meta()
+        .children
+        .lock()
+        .insert(new_covered.name_string(), new_covered.clone());
+    if !Arc::ptr_eq(put_old, new_root) {
+        let parent = put_old.parent().ok_or(SysError::EINVAL)?;
+        // NOTE: `Dentry::insert` would keep a dentry of the same file system
+        parent
+            .meta()
+            .children
+            .lock()
+            .insert(put_old.name_string(), old_mount.root.clone());
+    }
+
+    for mount in MOUNT_TABLE.lock().iter_mut() {
+        let (path, covered) = if Arc::ptr_eq(mount, &old_mount) {
+            (put_old_path.clone(), Some(put_old.clone()))
+        } else if Arc::ptr_eq(mount, &new_mount) {
+            (String::from("/"), None)
+        } else {
+            // a mount left in the old tree is moved along with it
+            let path = rebase(&mount.path).unwrap_or_else(|| match put_old_path.as_str() {
+                "/" => mount.path.clone(),
+                _ => put_old_path.clone() + &mount.path,
+            });
+            (path, mount.covered.clone())
+        };
+        *mount = Arc::new(Mount {
+            path,
+            root: mount.root.clone(),
+            covered,
+            sb: mount.sb.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Find the topmost mount on the child `name` of `parent`.
+///
+/// A bound dentry is also reachable at its own place, so a mount is not
+/// told by its root alone.
+pub fn find_mount(parent: &Arc<dyn Dentry>, name: &str) -> Option<Arc<Mount>> {
+    MOUNT_TABLE
+        .lock()
+        .iter()
+        .rev()
+        .find(|mount| {
+            mount.covered.as_ref().is_some_and(|covered| {
+                covered.name() == name
+                    && covered
+                        .parent()
+                        .is_some_and(|covered_parent| Arc::ptr_eq(&covered_parent, parent))
+            })
+        })
+        .cloned()
+}
+
+/// Graft `source` in place of `mount_point`, so that the subtree of `source`
+/// is also reachable below `mount_point`.
+///
+/// The dentries below keep their parent, so the paths of the dentries below
+/// lead to `source`. So does `..` of the bound dentry, unless the walk
+/// crossed this mount itself, see `Path::walk`.
+pub fn bind_mount(source: Arc<dyn Dentry>, mount_point: Arc<dyn Dentry>) -> SysResult<()> {
+    let parent = mount_point.parent().ok_or(SysError::EBUSY)?;
+    let path = mount_point.path();
+    // NOTE: `Dentry::insert` would key the child by its own name, and keep a
+    // dentry of the same file system in place
+    parent
+        .meta()
+        .children
+        .lock()
+        .insert(mount_point.name_string(), source.clone());
+    add_mount(path, source, Some(mount_point));
+    Ok(())
+}
+
+/// Detach `mount`, restoring the dentry it covered.
+fn detach_mount(mount: &Arc<Mount>) -> SysResult<()> {
+    let covered = mount.covered.as_ref().ok_or(SysError::EBUSY)?;
+    let parent = covered.parent().ok_or(SysError::EBUSY)?;
+    parent
+        .meta()
+        .children
+        .lock()
+        .insert(covered.name_string(), covered.clone());
+    MOUNT_TABLE.lock().retain(|m| !Arc::ptr_eq(m, mount));
+    Ok(())
+}
+
+/// Unmount `mount`, and shut its file system down if this was the last mount
+/// of it.
+///
+/// The mount is busy if another one is mounted on its dentries, or if files
+/// are open on its file system and no other mount is left. A `lazy` unmount
+/// detaches these submounts as well, and leaves the shutdown to the close of
+/// the last open file.
+pub fn umount(mount: &Arc<Mount>, lazy: bool) -> SysResult<()> {
+    if mount.covered.is_none() {
+        return Err(SysError::EBUSY);
+    }
+    let submounts = submounts(mount);
+    if !lazy {
+        let last = mount_table()
+            .iter()
+            .all(|m| Arc::ptr_eq(m, mount) || !Arc::ptr_eq(&m.sb, &mount.sb));
+        if !submounts.is_empty() || last && mount.sb.meta().open_files.load(Ordering::SeqCst) > 0 {
+            return Err(SysError::EBUSY);
+        }
+    }
+    // the latest first, as they may be mounted on one another
+    for submount in submounts.iter().rev() {
+        umount(submount, true)?;
+    }
+    detach_mount(mount)?;
+    // a bind mount shares the super block of the file system bound
+    if find_sb_mount(&mount.sb).is_none() {
+        let meta = mount.sb.meta();
+        meta.detached.store(true, Ordering::SeqCst);
+        if meta.open_files.load(Ordering::SeqCst) == 0 {
+            kill_detached_sb(&mount.sb)?;
+        }
+    }
+    Ok(())
+}
+
+/// Mounts on dentries of the file system of `mount`, in the order of
+/// mounting.
+fn submounts(mount: &Arc<Mount>) -> Vec<Arc<Mount>> {
+    MOUNT_TABLE
+        .lock()
+        .iter()
+        .filter(|m| {
+            !Arc::ptr_eq(m, mount)
+                && m.covered
+                    .as_ref()
+                    .is_some_and(|covered| Arc::ptr_eq(&covered.super_block(), &mount.sb))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Sync and shut down a detached file system, unless it was already. Both the
+/// unmount and the close of the last open file may get here.
+fn kill_detached_sb(sb: &Arc<dyn SuperBlock>) -> SysResult<()> {
+    if !sb.meta().detached.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    sb.sync_fs(1)?;
+    sb.fs_type().kill_sb(sb.clone())
+}
+
+/// Called when a file open on `sb` is closed.
+pub(crate) fn put_open_file(sb: Arc<dyn SuperBlock>) {
+    let meta = sb.meta();
+    if meta.open_files.fetch_sub(1, Ordering::SeqCst) == 1 && meta.detached.load(Ordering::SeqCst) {
+        if let Err(err) = kill_detached_sb(&sb) {
+            log::error!("[put_open_file] failed to shut down a detached file system: {err:?}");
+        }
+    }
+}
+/// Find a mount of the file system of `sb`.
+fn find_sb_mount(sb: &Arc<dyn SuperBlock>) -> Option<Arc<Mount>> {
+    MOUNT_TABLE
+        .lock()
+        .iter()
+        .find(|mount| Arc::ptr_eq(&mount.sb, sb))
+        .cloned()
+}
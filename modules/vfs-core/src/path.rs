@@ -1,6 +1,6 @@
 use alloc::{
     string::{String, ToString},
-    sync::Arc,
+    sync::{Arc, Weak},
     vec::Vec,
 };
 
@@ -8,7 +8,42 @@ use async_utils::block_on;
 use crate_interface::call_interface;
 use systype::{SysError, SysResult};
 
-use crate::{dentry, Dentry, InodeMode, InodeType, OpenFlags};
+use crate::{dentry, Dentry, InodeMode, InodeType, OpenFlags, ResolveFlags};
+
+/// Symlinks a single lookup may follow, those met while following others
+/// included.
+pub const MAX_SYMLINK_FOLLOWS: usize = 40;
+
+/// Components a single lookup may resolve, those of the symlinks it follows
+/// included.
+pub const MAX_PATH_COMPONENTS: usize = 4096;
+
+/// What a single lookup has used up. It is shared with the lookups of the
+/// symlinks it follows, so that nested or cyclic symlinks can not recurse
+/// without end.
+#[derive(Default)]
+struct WalkBudget {
+    links: usize,
+    components: usize,
+}
+
+impl WalkBudget {
+    fn component(&mut self) -> SysResult<()> {
+        self.components += 1;
+        if self.components > MAX_PATH_COMPONENTS {
+            return Err(SysError::ENAMETOOLONG);
+        }
+        Ok(())
+    }
+
+    fn link(&mut self) -> SysResult<()> {
+        self.links += 1;
+        if self.links > MAX_SYMLINK_FOLLOWS {
+            return Err(SysError::ELOOP);
+        }
+        Ok(())
+    }
+}
 
 #[derive(Clone)]
 pub struct Path {
@@ -39,52 +74,225 @@ impl Path {
 
     /// Walk until path has been resolved.
     pub fn walk(&self, flags: OpenFlags) -> SysResult<Arc<dyn Dentry>> {
+        self.walk_with(flags, ResolveFlags::empty())
+    }
+
+    /// Walk until path has been resolved, under the constraints of `resolve`.
+    ///
+    /// The trailing component is never followed, see `Path::resolve`. An empty
+    /// path fails with `ENOENT`, one of slashes only is the root. Mount points
+    /// are crossed both ways, `..` of a mount leads to the parent of its mount
+    /// point.
+    pub fn walk_with(&self, flags: OpenFlags, resolve: ResolveFlags) -> SysResult<Arc<dyn Dentry>> {
+        self.walk_budget(flags, resolve, &mut WalkBudget::default())
+    }
+
+    fn walk_budget(
+        &self,
+        flags: OpenFlags,
+        resolve: ResolveFlags,
+        budget: &mut WalkBudget,
+    ) -> SysResult<Arc<dyn Dentry>> {
         let path = self.path.as_str();
+        if path.is_empty() {
+            return Err(SysError::ENOENT);
+        }
+        if resolve.contains(ResolveFlags::RESOLVE_BENEATH) && is_absolute_path(path) {
+            return Err(SysError::EXDEV);
+        }
         let mut dentry = if is_absolute_path(path) {
             self.root.clone()
         } else {
             self.start.clone()
         };
         log::debug!("[Path::walk] {:?}", split_path(path));
+        // bind mounts crossed so far, as the bound dentry and the directory it was
+        // reached from, which ".." of it goes back to
+        let mut crossed: Vec<(Arc<dyn Dentry>, Arc<dyn Dentry>)> = Vec::new();
         for p in split_path(path) {
+            budget.component()?;
             match p {
                 ".." => {
-                    dentry = dentry.parent().ok_or(SysError::ENOENT)?;
+                    if resolve.contains(ResolveFlags::RESOLVE_BENEATH)
+                        && Arc::ptr_eq(&dentry, &self.start)
+                    {
+                        return Err(SysError::EXDEV);
+                    }
+                    // ".." of the root is the root itself, so that a task can
+                    // not escape its root directory
+                    if Arc::ptr_eq(&dentry, &self.root) {
+                        continue;
+                    }
+                    dentry = match crossed.last() {
+                        Some((bound, _)) if Arc::ptr_eq(bound, &dentry) => crossed.pop().unwrap().1,
+                        _ => dentry.mount_place().parent().ok_or(SysError::ENOENT)?,
+                    };
+                    self.check_resolved(&dentry, resolve)?;
                 }
                 // NOTE: lookup will only create negative dentry in non-negetive dir dentry
                 name => {
                     dentry = if !flags.contains(OpenFlags::O_NOFOLLOW)
                         && dentry.inode()?.itype().is_symlink()
                     {
-                        Path::resolve_dentry(dentry)?
+                        self.follow_link(dentry, resolve, budget)?
                     } else {
                         dentry
                     };
                     match dentry.lookup(name) {
                         Ok(sub_dentry) => {
                             log::debug!("[Path::walk] sub dentry {}", sub_dentry.name());
+                            // A mount takes the place of the mount point among the
+                            // children, so the lookup lands in the mounted file system.
+                            // The root of a mount has the parent of the mount point, but a
+                            // bound dentry keeps its own.
+                            if !sub_dentry
+                                .parent()
+                                .is_some_and(|parent| Arc::ptr_eq(&parent, &dentry))
+                            {
+                                crossed.push((sub_dentry.clone(), dentry.clone()));
+                            }
                             dentry = sub_dentry
                         }
                         Err(e) => {
-                            log::warn!("[Path::walk] {e:?} when walking in path {path}");
+                            log::debug!("[Path::walk] {e:?} when walking in path {path}");
                             return Err(e);
                         }
                     }
+                    self.check_resolved(&dentry, resolve)?;
                 }
             }
         }
         Ok(dentry)
     }
 
+    /// Walk until path has been resolved under the constraints of `resolve`,
+    /// and follow the trailing symlink unless `O_NOFOLLOW` is given.
+    ///
+    /// A trailing slash names a directory, so the trailing symlink is followed
+    /// anyway, and anything else found fails with `ENOTDIR`. A negative dentry
+    /// is returned as usual, e.g. for `mkdir("a/b/")`.
+    pub fn resolve(&self, flags: OpenFlags, resolve: ResolveFlags) -> SysResult<Arc<dyn Dentry>> {
+        let mut budget = WalkBudget::default();
+        let dentry = self.walk_budget(OpenFlags::empty(), resolve, &mut budget)?;
+        let must_be_dir = has_trailing_slash(&self.path);
+        let dentry = if (flags.contains(OpenFlags::O_NOFOLLOW) && !must_be_dir)
+            || dentry.is_negetive()
+            || !dentry.inode()?.itype().is_symlink()
+        {
+            dentry
+        } else {
+            self.follow_link(dentry, resolve, &mut budget)?
+        };
+        if must_be_dir && !dentry.is_negetive() && !dentry.inode()?.itype().is_dir() {
+            return Err(SysError::ENOTDIR);
+        }
+        Ok(dentry)
+    }
+
+    /// Resolve all but the last component of the path under the constraints
+    /// of `resolve`, and return the directory reached with the last component.
+    ///
+    /// The directory is followed if it is a symlink, and fails with `ENOENT`
+    /// if it does not exist or with `ENOTDIR` if it is no directory. Trailing
+    /// slashes are dropped from the name, see `get_name`. A last component of
+    /// "." or ".." is returned as is, and a path of slashes only gives the root
+    /// with "/", which the caller is to refuse in the way of its syscall.
+    ///
+    /// # Example
+    ///
+    /// "a/b/c" -> (a/b, "c")
+    /// "a/b/" -> (a, "b")
+    /// "c" -> (start, "c")
+    /// "a/.." -> (a, "..")
+    /// "/" -> (root, "/")
+    pub fn resolve_parent(&self, resolve: ResolveFlags) -> SysResult<(Arc<dyn Dentry>, String)> {
+        let name = get_name(&self.path);
+        if name.is_empty() {
+            return Err(SysError::ENOENT);
+        }
+        if name == "/" {
+            if resolve.contains(ResolveFlags::RESOLVE_BENEATH) {
+                return Err(SysError::EXDEV);
+            }
+            return Ok((self.root.clone(), name.to_string()));
+        }
+        let trimmed = self.path.trim_end_matches('/');
+        // NOTE: the directory part keeps its trailing slash, so that it is followed
+        // if it is a symlink and must be a directory
+        let dir = &trimmed[..trimmed.len() - name.len()];
+        let parent = if dir.is_empty() {
+            self.start.clone()
+        } else {
+            Path::new(self.root.clone(), self.start.clone(), dir)
+                .resolve(OpenFlags::empty(), resolve)?
+        };
+        if parent.is_negetive() {
+            return Err(SysError::ENOENT);
+        }
+        if !parent.inode()?.itype().is_dir() {
+            return Err(SysError::ENOTDIR);
+        }
+        Ok((parent, name.to_string()))
+    }
+
+    fn follow_link(
+        &self,
+        dentry: Arc<dyn Dentry>,
+        resolve: ResolveFlags,
+        budget: &mut WalkBudget,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        if resolve.contains(ResolveFlags::RESOLVE_NO_SYMLINKS) {
+            return Err(SysError::ELOOP);
+        }
+        if resolve.contains(ResolveFlags::RESOLVE_NO_MAGICLINKS)
+            && dentry.clone().base_magic_link()?.is_some()
+        {
+            return Err(SysError::ELOOP);
+        }
+        let dentry = Path::resolve_dentry_budget(dentry, budget)?;
+        if resolve.contains(ResolveFlags::RESOLVE_BENEATH)
+            && !Arc::ptr_eq(&dentry, &self.start)
+            && !dentry.is_descendant_of(&self.start)
+        {
+            return Err(SysError::EXDEV);
+        }
+        self.check_resolved(&dentry, resolve)?;
+        Ok(dentry)
+    }
+
+    /// Check that a dentry reached in the walk does not cross a mount point
+    /// when `RESOLVE_NO_XDEV` is given.
+    fn check_resolved(&self, dentry: &Arc<dyn Dentry>, resolve: ResolveFlags) -> SysResult<()> {
+        if resolve.contains(ResolveFlags::RESOLVE_NO_XDEV)
+            && !Weak::ptr_eq(&dentry.meta().super_block, &self.start.meta().super_block)
+        {
+            return Err(SysError::EXDEV);
+        }
+        Ok(())
+    }
+
+    /// Follow `dentry` until it is no symlink, failing with `ELOOP` after
+    /// `MAX_SYMLINK_FOLLOWS` symlinks.
     pub fn resolve_dentry(dentry: Arc<dyn Dentry>) -> SysResult<Arc<dyn Dentry>> {
-        const MAX_RESOLVE_LINK_DEPTH: usize = 40;
+        Path::resolve_dentry_budget(dentry, &mut WalkBudget::default())
+    }
+
+    fn resolve_dentry_budget(
+        dentry: Arc<dyn Dentry>,
+        budget: &mut WalkBudget,
+    ) -> SysResult<Arc<dyn Dentry>> {
         let mut dentry_it = dentry;
-        for _ in 0..MAX_RESOLVE_LINK_DEPTH {
+        loop {
             if dentry_it.is_negetive() {
                 return Ok(dentry_it);
             }
             match dentry_it.inode()?.itype() {
                 InodeType::SymLink => {
+                    budget.link()?;
+                    if let Some(target) = dentry_it.clone().base_magic_link()? {
+                        dentry_it = target;
+                        continue;
+                    }
                     let path = block_on(async { dentry_it.open()?.readlink_string().await })?;
                     let path = if is_absolute_path(&path) {
                         Path::new(
@@ -99,13 +307,13 @@ impl Path {
                             &path,
                         )
                     };
-                    let new_dentry = path.walk(OpenFlags::empty())?;
+                    let new_dentry =
+                        path.walk_budget(OpenFlags::empty(), ResolveFlags::empty(), budget)?;
                     dentry_it = new_dentry;
                 }
                 _ => return Ok(dentry_it),
             }
         }
-        Err(SysError::ELOOP)
     }
 }
 
@@ -117,26 +325,64 @@ pub fn is_relative_path(path: &str) -> bool {
     !path.starts_with('/')
 }
 
+/// Whether `path` ends with a slash, which asks for a directory.
+///
+/// "/" is a directory itself, and so are "a/" and "a//", but not "a".
+pub fn has_trailing_slash(path: &str) -> bool {
+    path.ends_with('/')
+}
+
+/// Split `path` into the components to walk, dropping empty ones and ".".
+/// ".." is kept, as it is resolved against the dentries walked.
+///
+/// # Example
+///
+/// "" -> []
+/// "/" -> []
+/// "a/" -> ["a"]
+/// "a//b" -> ["a", "b"]
+/// "./a/../b" -> ["a", "..", "b"]
 pub fn split_path(path: &str) -> Vec<&str> {
     path.split('/')
         .filter(|name| !name.is_empty() && *name != ".")
         .collect()
 }
 
+/// Split `path` into its first component and the rest, if any, in the way
+/// of `split_path`.
+///
+/// # Example
+///
+/// "/" -> ("", None)
+/// "a/" -> ("a", None)
+/// "a//b/" -> ("a", Some("b/"))
 pub fn split_parent_and_name(path: &str) -> (&str, Option<&str>) {
     let trimmed_path = path.trim_start_matches('/');
     trimmed_path.find('/').map_or((trimmed_path, None), |n| {
-        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
+        let rest = trimmed_path[n..].trim_start_matches('/');
+        (&trimmed_path[..n], (!rest.is_empty()).then_some(rest))
     })
 }
 
+/// Get the last component of `path`, ignoring trailing slashes.
+///
 /// # Example
 ///
+/// "" -> ""
 /// "/" -> "/"
+/// "a/" -> "a"
+/// "a//b" -> "b"
+/// "./a/../b" -> "b"
 /// "/dir/" -> "dir"
 /// "/dir/file" -> "file"
 pub fn get_name(path: &str) -> &str {
-    path.split('/').last().unwrap_or("/")
+    if path.is_empty() {
+        return "";
+    }
+    match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed.rsplit('/').next().unwrap(),
+    }
 }
 
 #[crate_interface::def_interface]
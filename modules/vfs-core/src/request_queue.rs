@@ -0,0 +1,231 @@
+//! Queue of the block requests to a device.
+//!
+//! Requests are served one at a time. While the device is busy, new requests
+//! wait in the band of the I/O priority of their task, and the bands take
+//! turns with deficit round robin: at its turn, a band gets its quantum of
+//! bytes as credit and sends requests while the credit covers them. A higher
+//! band has a larger quantum, so its requests are rarely stuck behind those
+//! of a lower band, while an idle band still makes progress.
+
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use crate_interface::call_interface;
+use device_core::BlockDevice;
+
+use crate::Mutex;
+
+/// Largest request in bytes. Larger transfers are split, so that requests of
+/// other tasks can be served in between.
+pub const MAX_REQUEST_SIZE: usize = 64 * 1024;
+
+/// Priority band of a block request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoBand {
+    /// Real-time class.
+    Rt = 0,
+    /// Best-effort class, levels 0 to 3.
+    BeHigh = 1,
+    /// Best-effort class, levels 4 to 7, the default.
+    BeLow = 2,
+    /// Idle class, also used by the writeback of dirty pages.
+    Idle = 3,
+}
+
+impl IoBand {
+    const NR_BANDS: usize = 4;
+
+    /// Credit in bytes a band gets at its turn.
+    const QUANTUM: [usize; Self::NR_BANDS] = [
+        16 * MAX_REQUEST_SIZE,
+        4 * MAX_REQUEST_SIZE,
+        2 * MAX_REQUEST_SIZE,
+        MAX_REQUEST_SIZE / 4,
+    ];
+
+    /// Band of the current task.
+    pub fn current() -> Self {
+        call_interface!(KernelIoPrioIf::current_io_band())
+    }
+}
+
+#[crate_interface::def_interface]
+pub trait KernelIoPrioIf {
+    /// Band of the block requests issued by the current task.
+    fn current_io_band() -> IoBand;
+}
+
+struct Waiter {
+    band: IoBand,
+    len: usize,
+    /// Set when it is the turn of this request, under the lock of the queue.
+    granted: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+struct QueueInner {
+    /// Whether a request is being served.
+    busy: bool,
+    bands: [VecDeque<Arc<Waiter>>; IoBand::NR_BANDS],
+    /// Credit of each band in bytes.
+    deficits: [usize; IoBand::NR_BANDS],
+    /// Band whose turn it is.
+    turn: usize,
+}
+
+impl QueueInner {
+    /// Pick the next request to serve by deficit round robin.
+    fn pick(&mut self) -> Option<Arc<Waiter>> {
+        if self.bands.iter().all(|band| band.is_empty()) {
+            return None;
+        }
+        loop {
+            let turn = self.turn;
+            match self.bands[turn].front() {
+                Some(waiter) if waiter.len <= self.deficits[turn] => {
+                    self.deficits[turn] -= waiter.len;
+                    return self.bands[turn].pop_front();
+                }
+                Some(_) => {}
+                // a band keeps no credit while it has nothing to send
+                None => self.deficits[turn] = 0,
+            }
+            self.turn = (turn + 1) % IoBand::NR_BANDS;
+            if !self.bands[self.turn].is_empty() {
+                self.deficits[self.turn] += IoBand::QUANTUM[self.turn];
+            }
+        }
+    }
+}
+
+pub struct RequestQueue {
+    inner: Mutex<QueueInner>,
+}
+
+impl RequestQueue {
+    fn new() -> Self {
+        Self {
+            inner: Mutex::new(QueueInner {
+                busy: false,
+                bands: Default::default(),
+                deficits: [0; IoBand::NR_BANDS],
+                turn: 0,
+            }),
+        }
+    }
+
+    /// Wait for the turn of a request of `len` bytes in `band`. The request
+    /// is served until the returned guard is dropped, which must not sleep in
+    /// between.
+    ///
+    /// NOTE: never call it under `block_on`, which could spin forever on a
+    /// turn given to a task of the same hart.
+    pub async fn submit(&self, band: IoBand, len: usize) -> Request<'_> {
+        let waiter = {
+            let mut inner = self.inner.lock();
+            if !inner.busy {
+                inner.busy = true;
+                return Request { queue: self };
+            }
+            let waiter = Arc::new(Waiter {
+                band,
+                len: len.min(MAX_REQUEST_SIZE),
+                granted: AtomicBool::new(false),
+                waker: Mutex::new(None),
+            });
+            inner.bands[band as usize].push_back(waiter.clone());
+            waiter
+        };
+        WaitTurn {
+            queue: self,
+            waiter,
+            done: false,
+        }
+        .await;
+        Request { queue: self }
+    }
+
+    /// Finish the request being served and give the turn to the next one.
+    fn complete(&self) {
+        let mut inner = self.inner.lock();
+        let Some(next) = inner.pick() else {
+            inner.busy = false;
+            return;
+        };
+        next.granted.store(true, Ordering::Release);
+        let waker = next.waker.lock().take();
+        drop(inner);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// A block request being served.
+pub struct Request<'a> {
+    queue: &'a RequestQueue,
+}
+
+impl Drop for Request<'_> {
+    fn drop(&mut self) {
+        self.queue.complete();
+    }
+}
+
+struct WaitTurn<'a> {
+    queue: &'a RequestQueue,
+    waiter: Arc<Waiter>,
+    done: bool,
+}
+
+impl Future for WaitTurn<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        *self.waiter.waker.lock() = Some(cx.waker().clone());
+        if self.waiter.granted.load(Ordering::Acquire) {
+            self.done = true;
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for WaitTurn<'_> {
+    /// A task killed while waiting leaves the queue, or passes on the turn it
+    /// was given.
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let mut inner = self.queue.inner.lock();
+        if self.waiter.granted.load(Ordering::Acquire) {
+            drop(inner);
+            self.queue.complete();
+        } else {
+            inner.bands[self.waiter.band as usize].retain(|w| !Arc::ptr_eq(w, &self.waiter));
+        }
+    }
+}
+
+/// Queues by the address of their device.
+static REQUEST_QUEUES: Mutex<BTreeMap<usize, Arc<RequestQueue>>> = Mutex::new(BTreeMap::new());
+
+/// The request queue of `device`, shared by all its users.
+pub fn request_queue(device: &Arc<dyn BlockDevice>) -> Arc<RequestQueue> {
+    let key = Arc::as_ptr(device) as *const () as usize;
+    REQUEST_QUEUES
+        .lock()
+        .entry(key)
+        .or_insert_with(|| Arc::new(RequestQueue::new()))
+        .clone()
+}
@@ -1,16 +1,26 @@
 use alloc::{
     sync::{Arc, Weak},
+    vec,
     vec::Vec,
 };
-use core::mem::MaybeUninit;
+use core::{
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
 
+use async_utils::block_on;
+use config::mm::PAGE_SIZE;
 use device_core::BlockDevice;
 use spin::Once;
 use systype::SysResult;
 
-use crate::{Dentry, FileSystemType, Inode, Mutex, StatFs};
+use crate::{Dentry, File, FileSystemType, Inode, IoBand, Mutex, StatFs};
+
+static MNT_ID_ALLOCATOR: AtomicUsize = AtomicUsize::new(1);
 
 pub struct SuperBlockMeta {
+    /// Unique id of the mount this super block belongs to.
+    pub mnt_id: usize,
     /// Block device that hold this file system.
     // TODO: dyn file for device?
     pub device: Option<Arc<dyn BlockDevice>>,
@@ -18,14 +28,24 @@ pub struct SuperBlockMeta {
     pub fs_type: Weak<dyn FileSystemType>,
     /// Root dentry points to the mount point.
     pub root_dentry: Once<Arc<dyn Dentry>>,
+    /// Number of open files on this file system, see [`FileMeta`].
+    ///
+    /// [`FileMeta`]: crate::FileMeta
+    pub open_files: AtomicUsize,
+    /// Set when the last mount is detached while files are still open, so
+    /// that closing the last one shuts the file system down.
+    pub detached: AtomicBool,
 }
 
 impl SuperBlockMeta {
     pub fn new(device: Option<Arc<dyn BlockDevice>>, fs_type: Arc<dyn FileSystemType>) -> Self {
         Self {
+            mnt_id: MNT_ID_ALLOCATOR.fetch_add(1, Ordering::Relaxed),
             device,
             root_dentry: Once::new(),
             fs_type: Arc::downgrade(&fs_type),
+            open_files: AtomicUsize::new(0),
+            detached: AtomicBool::new(false),
         }
     }
 }
@@ -41,6 +61,11 @@ pub trait SuperBlock: Send + Sync {
     /// superblock.
     fn sync_fs(&self, wait: isize) -> SysResult<()>;
 
+    /// Preferred block size for file system I/O, reported as `st_blksize`.
+    fn block_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
     fn set_root_dentry(&self, root_dentry: Arc<dyn Dentry>) {
         self.meta().root_dentry.call_once(|| root_dentry);
     }
@@ -57,9 +82,95 @@ impl dyn SuperBlock {
         self.meta().root_dentry.get().unwrap().clone()
     }
 
+    /// Get the mount id.
+    pub fn mnt_id(&self) -> usize {
+        self.meta().mnt_id
+    }
+
     pub fn device(&self) -> Arc<dyn BlockDevice> {
         self.meta().device.as_ref().cloned().unwrap()
     }
+
+    /// Write back the dirty pages of the files of this file system in the
+    /// dentry cache, and their metadata. File systems call it in `sync_fs`
+    /// before writing back their own metadata.
+    ///
+    /// NOTE: the pages are written at once rather than through the request
+    /// queue, as this may run under `block_on`.
+    pub fn sync_cached_files(&self) -> SysResult<()> {
+        for file in self.dirty_cached_files()? {
+            block_on(file.write_back_dirty(None))?;
+            file.sync_metadata(false)?;
+        }
+        Ok(())
+    }
+
+    /// Write back the dirty pages of the files of this file system in the
+    /// dentry cache as block requests in `band`, leaving their metadata.
+    pub async fn write_back_cached_files(&self, band: IoBand) -> SysResult<()> {
+        for file in self.dirty_cached_files()? {
+            file.write_back_dirty(Some(band)).await?;
+        }
+        Ok(())
+    }
+
+    /// Open the files of this file system in the dentry cache that have dirty
+    /// pages.
+    fn dirty_cached_files(&self) -> SysResult<Vec<Arc<dyn File>>> {
+        let mnt_id = self.mnt_id();
+        let mut files = Vec::new();
+        let mut dentries = vec![self.root_dentry()];
+        while let Some(dentry) = dentries.pop() {
+            for child in dentry.children().into_values() {
+                // skip the file systems mounted below
+                if child.is_negetive() || child.super_block().mnt_id() != mnt_id {
+                    continue;
+                }
+                let inode = child.inode()?;
+                if inode.itype().is_dir() {
+                    dentries.push(child);
+                } else if inode.page_cache().is_some_and(|p| p.has_dirty()) {
+                    files.push(child.open()?);
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Super block of a file system living only in memory, e.g. tmpfs and devfs.
+pub struct MemSuperBlock {
+    meta: SuperBlockMeta,
+    /// Magic number of the file system, reported as `f_type` by statfs.
+    magic: i64,
+}
+
+impl MemSuperBlock {
+    pub fn new(
+        device: Option<Arc<dyn BlockDevice>>,
+        fs_type: Arc<dyn FileSystemType>,
+        magic: i64,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            meta: SuperBlockMeta::new(device, fs_type),
+            magic,
+        })
+    }
+}
+
+impl SuperBlock for MemSuperBlock {
+    fn meta(&self) -> &SuperBlockMeta {
+        &self.meta
+    }
+
+    fn stat_fs(&self) -> SysResult<StatFs> {
+        Ok(StatFs::in_memory(self.magic))
+    }
+
+    fn sync_fs(&self, _wait: isize) -> SysResult<()> {
+        // Nothing to write back for a memory only file system.
+        Ok(())
+    }
 }
 
 impl<T: Send + Sync + 'static> SuperBlock for MaybeUninit<T> {
@@ -2,6 +2,7 @@ use alloc::string::String;
 use core::fmt::Display;
 
 use bitflags::Flags;
+use config::mm::PAGE_SIZE;
 use time::timespec::TimeSpec;
 
 use crate::InodeType;
@@ -100,6 +101,20 @@ pub struct StatFs {
     pub f_spare: [isize; 4],
 }
 
+impl StatFs {
+    /// Statistics of a file system living only in memory, which like linux has
+    /// no blocks or inodes to count.
+    pub fn in_memory(magic: i64) -> Self {
+        Self {
+            f_type: magic,
+            f_bsize: PAGE_SIZE as i64,
+            f_namelen: 255,
+            f_frsize: PAGE_SIZE as isize,
+            ..Default::default()
+        }
+    }
+}
+
 /// Directory entry.
 #[derive(Debug, Clone)]
 #[repr(C)]
@@ -217,6 +232,21 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// Flags of umount2(2).
+    #[derive(Debug)]
+    pub struct UmountFlags: u32 {
+        /// Unmount even if busy, only takes effect on network file systems.
+        const MNT_FORCE = 1;
+        /// Detach the mount now and clean up when it is no longer busy.
+        const MNT_DETACH = 1 << 1;
+        /// Mark the mount expired, to unmount it on a second call if unused.
+        const MNT_EXPIRE = 1 << 2;
+        /// Do not dereference the target if it is a symbolic link.
+        const UMOUNT_NOFOLLOW = 1 << 3;
+    }
+}
+
 /// Enumeration of possible methods to seek within an I/O object.
 ///
 /// Copied from `std`.
@@ -283,6 +313,8 @@ pub const AT_SYMLINK_NOFOLLOW: i32 = 0x100;
 pub const AT_REMOVEDIR: i32 = 0x200;
 /// Follow symbolic links.
 pub const AT_SYMLINK_FOLLOW: i32 = 0x400;
+/// Operate on the file `dirfd` refers to when the path is empty.
+pub const AT_EMPTY_PATH: i32 = 0x1000;
 
 bitflags::bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -296,3 +328,19 @@ bitflags::bitflags! {
         const RENAME_WHITEOUT = 1 << 2;
     }
 }
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    // Defined in <linux/openat2.h>.
+    pub struct ResolveFlags: u64 {
+        /// Block mount-point crossings, including bind mounts.
+        const RESOLVE_NO_XDEV = 0x01;
+        /// Block traversal through procfs-style "magic links".
+        const RESOLVE_NO_MAGICLINKS = 0x02;
+        /// Block traversal through all symlinks, implies RESOLVE_NO_MAGICLINKS.
+        const RESOLVE_NO_SYMLINKS = 0x04;
+        /// Block "lexical" trickery like "..", symlinks and absolute paths
+        /// which escape the dirfd.
+        const RESOLVE_BENEATH = 0x08;
+    }
+}
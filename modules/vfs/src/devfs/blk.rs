@@ -0,0 +1,136 @@
+use alloc::{boxed::Box, sync::Arc};
+
+use async_trait::async_trait;
+use device_core::{BlockDevice, DevId, DeviceMajor};
+use driver::RamDisk;
+use strum::FromRepr;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, SuperBlock,
+};
+
+impl DentryOps for BlkFile {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+        Ok(Arc::new(BlkFile {
+            meta: FileMeta::new(dentry, inode),
+        }))
+    }
+}
+
+/// A block device node, which names the device as the source of a mount.
+pub struct BlkInode {
+    meta: InodeMeta,
+    device: Arc<dyn BlockDevice>,
+}
+
+impl BlkInode {
+    pub fn new(
+        super_block: Arc<dyn SuperBlock>,
+        device: Arc<dyn BlockDevice>,
+        minor: usize,
+    ) -> Arc<Self> {
+        let mut meta = InodeMeta::new(InodeMode::BLOCK, super_block, device.size() as usize);
+        meta.dev_id = Some(DevId {
+            major: DeviceMajor::Block,
+            minor,
+        });
+        Arc::new(Self { meta, device })
+    }
+}
+
+impl Inode for BlkInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+/// Get the device of the block device node `inode`.
+pub fn block_device_of(inode: Arc<dyn Inode>) -> SysResult<Arc<dyn BlockDevice>> {
+    inode
+        .downcast_arc::<BlkInode>()
+        .map(|inode| inode.device.clone())
+        .map_err(|_| SysError::ENOTBLK)
+}
+
+pub struct BlkFile {
+    meta: FileMeta,
+}
+
+/// Ioctls of a `RamDisk` to simulate a power loss. Linux has no such ioctls,
+/// they are numbered after the `_IO(0x12, ...)` ones of block devices.
+#[derive(Debug, Clone, Copy, FromRepr)]
+#[repr(usize)]
+enum RamDiskIoctlCmd {
+    /// Let only `arg` more block writes reach the disk.
+    SetCrashPoint = 0x12f0,
+    /// Power the disk on again, keeping what reached it.
+    Recover = 0x12f1,
+    /// Return 1 if the crash point has been reached, else 0.
+    HasCrashed = 0x12f2,
+}
+
+/// Length of a raw access of `len` bytes at `offset` of `device`, which must be
+/// in whole blocks and stops at the end of the device.
+fn raw_len(device: &dyn BlockDevice, offset: usize, len: usize) -> SysResult<usize> {
+    let block_size = device.block_size();
+    if offset % block_size != 0 || len % block_size != 0 {
+        return Err(SysError::EINVAL);
+    }
+    Ok(len.min((device.size() as usize).saturating_sub(offset)))
+}
+
+#[async_trait]
+impl File for BlkFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    /// Raw access to the device in whole blocks, as with `O_DIRECT`.
+    async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        let device = block_device_of(self.inode())?;
+        let len = raw_len(device.as_ref(), offset, buf.len())?;
+        let block_size = device.block_size();
+        for (i, block) in buf[..len].chunks_mut(block_size).enumerate() {
+            device.read_block(offset / block_size + i, block);
+        }
+        Ok(len)
+    }
+
+    async fn base_write_at(&self, offset: usize, buf: &[u8]) -> SyscallResult {
+        let device = block_device_of(self.inode())?;
+        let len = raw_len(device.as_ref(), offset, buf.len())?;
+        if len == 0 && !buf.is_empty() {
+            return Err(SysError::ENOSPC);
+        }
+        let block_size = device.block_size();
+        for (i, block) in buf[..len].chunks(block_size).enumerate() {
+            device.write_block(offset / block_size + i, block);
+        }
+        Ok(len)
+    }
+
+    fn block_device(&self) -> Option<Arc<dyn BlockDevice>> {
+        block_device_of(self.inode()).ok()
+    }
+
+    fn ioctl(&self, cmd: usize, arg: usize) -> SyscallResult {
+        let device = block_device_of(self.inode())?;
+        let Some(cmd) = RamDiskIoctlCmd::from_repr(cmd) else {
+            return Err(SysError::ENOTTY);
+        };
+        let ram_disk = device
+            .downcast_arc::<RamDisk>()
+            .map_err(|_| SysError::ENOTTY)?;
+        log::info!("[BlkFile::ioctl] {cmd:?}, arg {arg}");
+        match cmd {
+            RamDiskIoctlCmd::SetCrashPoint => ram_disk.set_crash_point(arg),
+            RamDiskIoctlCmd::Recover => ram_disk.recover(),
+            RamDiskIoctlCmd::HasCrashed => return Ok(ram_disk.has_crashed() as usize),
+        }
+        Ok(0)
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+}
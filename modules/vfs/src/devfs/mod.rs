@@ -1,72 +1,121 @@
-use alloc::sync::Arc;
-
-use device_core::BlockDevice;
-use systype::SysResult;
-use vfs_core::{Dentry, FileSystemType, FileSystemTypeMeta, InodeMode, SuperBlock, SuperBlockMeta};
+use alloc::{collections::BTreeMap, sync::Arc};
+
+use device_core::{BlockDevice, DevId};
+use driver::{RamDisk, BLOCK_DEVICE};
+use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SysResult};
+use vfs_core::{
+    CachedDentry, Dentry, DentryOps, File, FileSystemType, FileSystemTypeMeta, Inode, InodeMode,
+    MemSuperBlock, MountFlags, SuperBlock,
+};
 
+pub use self::{blk::block_device_of, pty::init_devpts};
 use self::{
-    cpu_dma_latency::{CpuDmaLatencyDentry, CpuDmaLatencyInode},
-    null::{NullDentry, NullInode},
-    rtc::{RtcDentry, RtcInode},
-    tty::{TtyDentry, TtyFile, TtyInode, TTY},
-    urandom::{UrandomDentry, UrandomInode},
-    zero::{ZeroDentry, ZeroInode},
+    blk::{BlkFile, BlkInode},
+    cpu_dma_latency::{CpuDmaLatencyFile, CpuDmaLatencyInode},
+    null::{NullFile, NullInode},
+    pty::{PtyInode, PtyMasterFile},
+    rtc::{RtcFile, RtcInode},
+    tty::{TtyFile, TtyInode},
+    urandom::{UrandomFile, UrandomInode},
+    zero::{ZeroFile, ZeroInode},
 };
 use crate::simplefs::{dentry::SimpleDentry, inode::SimpleDirInode};
 
+mod blk;
 mod cpu_dma_latency;
 mod null;
+mod pty;
 mod rtc;
 pub mod tty;
 pub mod urandom;
 mod zero;
 
+/// A device of devfs, which a device node made by mknod opens too.
+struct DeviceEntry {
+    inode: Arc<dyn Inode>,
+    open: fn(Arc<dyn Dentry>, Arc<dyn Inode>) -> SysResult<Arc<dyn File>>,
+}
+
+/// Size of the ram disk `/dev/ram0`.
+const RAMDISK_SIZE: usize = 1024 * 1024;
+
+/// Devices by their numbers.
+static DEVICES: SpinNoIrqLock<BTreeMap<DevId, DeviceEntry>> = SpinNoIrqLock::new(BTreeMap::new());
+
+/// Add the device node `name` to devfs, and register the device if it has a
+/// number.
+fn add_device<O: DentryOps>(root_dentry: &Arc<dyn Dentry>, name: &str, inode: Arc<dyn Inode>) {
+    let dentry = CachedDentry::<O>::new(name, root_dentry.super_block(), Some(root_dentry.clone()));
+    root_dentry.insert(dentry.clone());
+    dentry.set_inode(inode.clone());
+    if let Some(dev_id) = inode.meta().dev_id {
+        DEVICES.lock().insert(
+            dev_id,
+            DeviceEntry {
+                inode,
+                open: O::open,
+            },
+        );
+    }
+}
+
+/// Open the device of the node `dentry` points to, which may live in any file
+/// system. The file opened is the one of the devfs node with the same type and
+/// number, or `ENXIO` if there is no such device.
+pub fn open_device(dentry: Arc<dyn Dentry>, node: &Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+    let dev_id = node.meta().dev_id.ok_or(SysError::ENXIO)?;
+    let devices = DEVICES.lock();
+    let device = devices
+        .get(&dev_id)
+        .filter(|device| device.inode.itype() == node.itype())
+        .ok_or(SysError::ENXIO)?;
+    let (inode, open) = (device.inode.clone(), device.open);
+    drop(devices);
+    open(dentry, inode)
+}
+
 pub fn init_devfs(root_dentry: Arc<dyn Dentry>) -> SysResult<()> {
     let sb = root_dentry.super_block();
 
-    let zero_dentry = ZeroDentry::new("zero", sb.clone(), Some(root_dentry.clone()));
-    root_dentry.insert(zero_dentry.clone());
-    let zero_inode = ZeroInode::new(sb.clone());
-    zero_dentry.set_inode(zero_inode);
-
-    let null_dentry = NullDentry::new("null", sb.clone(), Some(root_dentry.clone()));
-    root_dentry.insert(null_dentry.clone());
-    let null_inode = NullInode::new(sb.clone());
-    null_dentry.set_inode(null_inode);
-
-    let rtc_dentry = RtcDentry::new("rtc", sb.clone(), Some(root_dentry.clone()));
-    root_dentry.insert(rtc_dentry.clone());
-    let rtc_inode = RtcInode::new(sb.clone());
-    rtc_dentry.set_inode(rtc_inode);
-
-    let cpu_dma_latency_dentry =
-        CpuDmaLatencyDentry::new("cpu_dma_latency", sb.clone(), Some(root_dentry.clone()));
-    root_dentry.insert(cpu_dma_latency_dentry.clone());
-    let cpu_dma_latency_inode = CpuDmaLatencyInode::new(sb.clone());
-    cpu_dma_latency_dentry.set_inode(cpu_dma_latency_inode);
-
-    let urandom_dentry = UrandomDentry::new("urandom", sb.clone(), Some(root_dentry.clone()));
-    root_dentry.insert(urandom_dentry.clone());
-    let urandom_inode = UrandomInode::new(sb.clone());
-    urandom_dentry.set_inode(urandom_inode);
-
-    let tty_dentry = TtyDentry::new("tty", sb.clone(), Some(root_dentry.clone()));
-    root_dentry.insert(tty_dentry.clone());
-    let tty_inode = TtyInode::new(sb.clone());
-    tty_dentry.set_inode(tty_inode);
-    let tty_file = TtyFile::new(tty_dentry.clone(), tty_dentry.inode()?);
-    TTY.call_once(|| tty_file);
-
-    // TODO: POSIX shm operations are not implemented yet. The code below is work
-    // around to pass libc test pthread_cancel_points.
-    let shm_dentry = SimpleDentry::new("shm", sb.clone(), Some(root_dentry.clone()));
-    root_dentry.insert(shm_dentry.clone());
-    let shm_inode = SimpleDirInode::new(InodeMode::DIR, sb.clone(), 0);
-    shm_dentry.set_inode(shm_inode);
+    add_device::<ZeroFile>(&root_dentry, "zero", ZeroInode::new(sb.clone()));
+    add_device::<NullFile>(&root_dentry, "null", NullInode::new(sb.clone()));
+    add_device::<RtcFile>(&root_dentry, "rtc", RtcInode::new(sb.clone()));
+    add_device::<CpuDmaLatencyFile>(
+        &root_dentry,
+        "cpu_dma_latency",
+        CpuDmaLatencyInode::new(sb.clone()),
+    );
+    add_device::<UrandomFile>(&root_dentry, "urandom", UrandomInode::new(sb.clone()));
+    add_device::<TtyFile>(&root_dentry, "tty", TtyInode::new(sb.clone()));
+    if let Some(device) = BLOCK_DEVICE.get() {
+        add_device::<BlkFile>(
+            &root_dentry,
+            "vda",
+            BlkInode::new(sb.clone(), device.clone(), 0),
+        );
+    }
+    // A disk in memory which can simulate a power loss, for crash tests.
+    add_device::<BlkFile>(
+        &root_dentry,
+        "ram0",
+        BlkInode::new(sb.clone(), RamDisk::new(RAMDISK_SIZE), 1),
+    );
+    // Slaves are created in devpts, which is mounted at /dev/pts.
+    add_device::<PtyMasterFile>(&root_dentry, "ptmx", PtyInode::new_ptmx(sb.clone()));
+
+    // Opening these reopens the files of the current task, see procfs fd.
+    root_dentry.symlink("fd", "/proc/self/fd")?;
+    for (fd, name) in ["stdin", "stdout", "stderr"].into_iter().enumerate() {
+        root_dentry.symlink(name, &alloc::format!("/proc/self/fd/{fd}"))?;
+    }
 
     Ok(())
 }
 
+/// Magic number reported by statfs, as in linux.
+const DEVFS_MAGIC: i64 = 0x1373;
+
 pub struct DevFsType {
     meta: FileSystemTypeMeta,
 }
@@ -79,59 +128,28 @@ impl DevFsType {
     }
 }
 
+vfs_core::register_fs!(DevFsType::new);
+
 impl FileSystemType for DevFsType {
     fn meta(&self) -> &FileSystemTypeMeta {
         &self.meta
     }
 
     fn base_mount(
-        self: alloc::sync::Arc<Self>,
+        self: Arc<Self>,
         name: &str,
         parent: Option<Arc<dyn Dentry>>,
-        _flags: vfs_core::MountFlags,
-        dev: Option<alloc::sync::Arc<dyn BlockDevice>>,
-    ) -> systype::SysResult<alloc::sync::Arc<dyn vfs_core::Dentry>> {
-        let sb = DevSuperBlock::new(dev, self.clone());
-        let mount_dentry = SimpleDentry::new(name, sb.clone(), parent.clone());
+        _flags: MountFlags,
+        dev: Option<Arc<dyn BlockDevice>>,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        let sb = MemSuperBlock::new(dev, self.clone(), DEVFS_MAGIC);
+        let mount_dentry = SimpleDentry::new(name, sb.clone(), parent);
         let mount_inode = SimpleDirInode::new(InodeMode::DIR, sb.clone(), 0);
-        mount_dentry.set_inode(mount_inode.clone());
-        if let Some(parent) = parent {
-            parent.insert(mount_dentry.clone());
-        }
-        self.insert_sb(&mount_dentry.path(), sb);
-        Ok(mount_dentry)
-    }
-
-    fn kill_sb(&self, _sb: alloc::sync::Arc<dyn vfs_core::SuperBlock>) -> systype::SysResult<()> {
-        todo!()
-    }
-}
-
-struct DevSuperBlock {
-    meta: SuperBlockMeta,
-}
-
-impl DevSuperBlock {
-    pub fn new(
-        device: Option<Arc<dyn BlockDevice>>,
-        fs_type: Arc<dyn FileSystemType>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: SuperBlockMeta::new(device, fs_type),
-        })
-    }
-}
-
-impl SuperBlock for DevSuperBlock {
-    fn meta(&self) -> &SuperBlockMeta {
-        &self.meta
-    }
-
-    fn stat_fs(&self) -> systype::SysResult<vfs_core::StatFs> {
-        todo!()
+        Ok(self.mount_root(sb, mount_dentry, mount_inode))
     }
 
-    fn sync_fs(&self, _wait: isize) -> systype::SysResult<()> {
-        todo!()
+    fn kill_sb(&self, sb: Arc<dyn SuperBlock>) -> SysResult<()> {
+        self.remove_sb(&sb);
+        Ok(())
     }
 }
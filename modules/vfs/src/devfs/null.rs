@@ -2,49 +2,18 @@ use alloc::{boxed::Box, sync::Arc};
 
 use async_trait::async_trait;
 use config::board::BLOCK_SIZE;
+use device_core::{DevId, DeviceMajor};
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{
-    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+    Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, SuperBlock,
 };
 
-pub struct NullDentry {
-    meta: DentryMeta,
-}
-
-impl NullDentry {
-    pub fn new(
-        name: &str,
-        super_block: Arc<dyn SuperBlock>,
-        parent: Option<Arc<dyn Dentry>>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new(name, super_block, parent),
-        })
-    }
-}
-
-impl Dentry for NullDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
-
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+impl DentryOps for NullFile {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
         Ok(Arc::new(NullFile {
-            meta: FileMeta::new(self.clone(), self.inode()?),
+            meta: FileMeta::new(dentry, inode),
         }))
     }
-
-    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
-        Err(SysError::ENOTDIR)
-    }
 }
 
 pub struct NullInode {
@@ -54,9 +23,12 @@ pub struct NullInode {
 impl NullInode {
     pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
         let size = BLOCK_SIZE;
-        Arc::new(Self {
-            meta: InodeMeta::new(InodeMode::CHAR, super_block, size),
-        })
+        let mut meta = InodeMeta::new(InodeMode::CHAR, super_block, size);
+        meta.dev_id = Some(DevId {
+            major: DeviceMajor::Mem,
+            minor: 3,
+        });
+        Arc::new(Self { meta })
     }
 }
 
@@ -64,30 +36,6 @@ impl Inode for NullInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
-
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
 }
 
 pub struct NullFile {
@@ -111,8 +59,4 @@ impl File for NullFile {
     fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
         Err(SysError::ENOTDIR)
     }
-
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
 }
@@ -0,0 +1,473 @@
+//! Pseudo terminals. Opening `/dev/ptmx` creates a pair, whose master is the
+//! opened file and whose slave shows up as `/dev/pts/N` until the master is
+//! closed.
+//!
+//! There is no line discipline, bytes pass between the ends as they are, the
+//! terminal state is only kept for the ioctls.
+
+use alloc::{boxed::Box, collections::VecDeque, format, sync::Arc};
+use core::{
+    future::poll_fn,
+    sync::atomic::{AtomicU32, Ordering},
+    task::{Poll, Waker},
+};
+
+use async_trait::async_trait;
+use async_utils::get_waker;
+use crate_interface::call_interface;
+use device_core::BlockDevice;
+use ring_buffer::RingBuffer;
+use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    Dentry, DentryMeta, DentryOps, DirEntry, File, FileMeta, FileSystemType, FileSystemTypeMeta,
+    Inode, InodeMeta, InodeMode, MemSuperBlock, MountFlags, OpenFlags, PollEvents, SuperBlock,
+};
+
+use super::tty::{signal_resize, KernelTtyIf, Pid, Termios, TtyIoctlCmd, WinSize};
+use crate::simplefs::{dentry::SimpleDentry, inode::SimpleDirInode};
+
+/// Capacity of the buffer of each direction.
+const PTY_BUF_LEN: usize = 4096;
+
+/// Magic number reported by statfs, as in linux.
+const DEVPTS_MAGIC: i64 = 0x1cd1;
+
+/// Root of the mounted devpts, where the slaves are created.
+static DEVPTS_ROOT: SpinNoIrqLock<Option<Arc<dyn Dentry>>> = SpinNoIrqLock::new(None);
+
+/// Index of the next pty.
+static NEXT_INDEX: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum End {
+    Master,
+    Slave,
+}
+
+struct PtyInner {
+    /// Written by the master, read by the slave.
+    to_slave: RingBuffer,
+    /// Written by the slave, read by the master.
+    to_master: RingBuffer,
+    /// Count of open masters, the pty is hung up when it drops to zero.
+    masters: usize,
+    /// Count of open slaves.
+    slaves: usize,
+    /// Whether a slave was ever opened, before that the master waits for one
+    /// instead of seeing a hang up.
+    slave_opened: bool,
+    /// Whether the slave can not be opened, until unlocked by `TIOCSPTLCK`.
+    locked: bool,
+    win_size: WinSize,
+    termios: Termios,
+    /// Foreground process group, 0 if none.
+    fg_pgid: Pid,
+    /// Tasks waiting on either end. All of them are woken on every event.
+    wakers: VecDeque<Waker>,
+}
+
+impl PtyInner {
+    fn wait(&mut self, waker: &Waker) {
+        if !self.wakers.iter().any(|w| w.will_wake(waker)) {
+            self.wakers.push_back(waker.clone());
+        }
+    }
+
+    fn wake_all(&mut self) {
+        while let Some(waker) = self.wakers.pop_front() {
+            waker.wake();
+        }
+    }
+
+    /// Buffers read and written by `end`.
+    fn buffers(&mut self, end: End) -> (&mut RingBuffer, &mut RingBuffer) {
+        match end {
+            End::Master => (&mut self.to_master, &mut self.to_slave),
+            End::Slave => (&mut self.to_slave, &mut self.to_master),
+        }
+    }
+
+    /// Whether the other end of `end` is gone.
+    fn hung_up(&self, end: End) -> bool {
+        match end {
+            End::Master => self.slave_opened && self.slaves == 0,
+            End::Slave => self.masters == 0,
+        }
+    }
+}
+
+pub struct Pty {
+    index: u32,
+    inner: SpinNoIrqLock<PtyInner>,
+}
+
+impl Pty {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            index: NEXT_INDEX.fetch_add(1, Ordering::Relaxed),
+            inner: SpinNoIrqLock::new(PtyInner {
+                to_slave: RingBuffer::new(PTY_BUF_LEN),
+                to_master: RingBuffer::new(PTY_BUF_LEN),
+                masters: 1,
+                slaves: 0,
+                slave_opened: false,
+                locked: true,
+                win_size: WinSize::new(),
+                termios: Termios::new(),
+                fg_pgid: 0,
+                wakers: VecDeque::new(),
+            }),
+        })
+    }
+
+    /// Read from `end`. A slave reads 0 once the master is closed, while a
+    /// master gets `EIO` once every slave is closed, as in linux.
+    async fn read(&self, end: End, nonblock: bool, buf: &mut [u8]) -> SyscallResult {
+        poll_fn(|cx| {
+            let mut inner = self.inner.lock();
+            let (input, _) = inner.buffers(end);
+            if !input.is_empty() {
+                let len = input.read(buf);
+                inner.wake_all();
+                return Poll::Ready(Ok(len));
+            }
+            if inner.hung_up(end) {
+                return Poll::Ready(match end {
+                    End::Master => Err(SysError::EIO),
+                    End::Slave => Ok(0),
+                });
+            }
+            if nonblock {
+                return Poll::Ready(Err(SysError::EAGAIN));
+            }
+            inner.wait(cx.waker());
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Write to `end`. A slave gets `EIO` once the master is closed.
+    async fn write(&self, end: End, nonblock: bool, buf: &[u8]) -> SyscallResult {
+        poll_fn(|cx| {
+            let mut inner = self.inner.lock();
+            if end == End::Slave && inner.hung_up(end) {
+                return Poll::Ready(Err(SysError::EIO));
+            }
+            let (_, output) = inner.buffers(end);
+            if !output.is_full() {
+                let len = output.write(buf);
+                inner.wake_all();
+                return Poll::Ready(Ok(len));
+            }
+            if nonblock {
+                return Poll::Ready(Err(SysError::EAGAIN));
+            }
+            inner.wait(cx.waker());
+            Poll::Pending
+        })
+        .await
+    }
+
+    async fn poll(&self, end: End, events: PollEvents) -> PollEvents {
+        let waker = get_waker().await;
+        let mut inner = self.inner.lock();
+        let mut res = PollEvents::empty();
+        if inner.hung_up(end) {
+            res |= PollEvents::HUP;
+        }
+        let (input, output) = inner.buffers(end);
+        if events.contains(PollEvents::IN) && !input.is_empty() {
+            res |= PollEvents::IN;
+        }
+        if events.contains(PollEvents::OUT) && !output.is_full() {
+            res |= PollEvents::OUT;
+        }
+        if res.is_empty() {
+            inner.wait(&waker);
+        }
+        res
+    }
+
+    /// See `ioctl_tty` manual page. The terminal state is shared by both
+    /// ends, so that e.g. a terminal emulator resizes the window through the
+    /// master and the shell sees it through the slave.
+    fn ioctl(&self, end: End, cmd: usize, arg: usize) -> SyscallResult {
+        use TtyIoctlCmd::*;
+        let Some(cmd) = TtyIoctlCmd::from_repr(cmd) else {
+            log::info!("[Pty::ioctl] cmd {cmd:#x} not supported");
+            return Err(SysError::ENOTTY);
+        };
+        log::info!("[Pty::ioctl] pty {}, cmd {cmd:?}, arg {arg:#x}", self.index);
+        let mut inner = self.inner.lock();
+        match cmd {
+            TCGETS | TCGETA => unsafe {
+                *(arg as *mut Termios) = inner.termios;
+            },
+            TCSETS | TCSETSW | TCSETSF => unsafe {
+                inner.termios = *(arg as *const Termios);
+            },
+            TIOCGPGRP => unsafe {
+                *(arg as *mut Pid) = inner.fg_pgid;
+            },
+            TIOCSPGRP => unsafe {
+                inner.fg_pgid = *(arg as *const Pid);
+            },
+            TIOCSCTTY if end == End::Slave => {
+                inner.fg_pgid = call_interface!(KernelTtyIf::pgid()) as Pid;
+            }
+            TIOCGWINSZ => unsafe {
+                *(arg as *mut WinSize) = inner.win_size;
+            },
+            TIOCSWINSZ => {
+                let win_size = unsafe { *(arg as *const WinSize) };
+                if inner.win_size != win_size {
+                    inner.win_size = win_size;
+                    let fg_pgid = inner.fg_pgid;
+                    drop(inner);
+                    signal_resize(fg_pgid);
+                }
+            }
+            TIOCGPTN if end == End::Master => unsafe {
+                *(arg as *mut u32) = self.index;
+            },
+            TIOCSPTLCK if end == End::Master => {
+                inner.locked = unsafe { *(arg as *const i32) } != 0;
+            }
+            TCSBRK => {}
+            _ => return Err(SysError::ENOTTY),
+        }
+        Ok(0)
+    }
+}
+
+pub struct PtyInode {
+    meta: InodeMeta,
+}
+
+impl PtyInode {
+    fn new(super_block: Arc<dyn SuperBlock>, perm: InodeMode) -> Arc<Self> {
+        Arc::new(Self {
+            meta: InodeMeta::new(InodeMode::CHAR | perm, super_block, 0),
+        })
+    }
+
+    /// Inode of `/dev/ptmx`, which anyone can open.
+    pub fn new_ptmx(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        let perm = InodeMode::OWNER_READ
+            | InodeMode::OWNER_WRITE
+            | InodeMode::GROUP_READ
+            | InodeMode::GROUP_WRITE
+            | InodeMode::OTHER_READ
+            | InodeMode::OTHER_WRITE;
+        Self::new(super_block, perm)
+    }
+}
+
+impl Inode for PtyInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+impl DentryOps for PtyMasterFile {
+    /// Create a pty, and its slave in devpts.
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+        let root = DEVPTS_ROOT.lock().clone().ok_or(SysError::ENODEV)?;
+        let pty = Pty::new();
+        PtsDentry::create(&root, pty.clone());
+        Ok(Arc::new(PtyMasterFile {
+            meta: FileMeta::new(dentry, inode),
+            pty,
+        }))
+    }
+}
+
+pub struct PtyMasterFile {
+    meta: FileMeta,
+    pty: Arc<Pty>,
+}
+
+impl Drop for PtyMasterFile {
+    fn drop(&mut self) {
+        let mut inner = self.pty.inner.lock();
+        inner.masters -= 1;
+        if inner.masters != 0 {
+            return;
+        }
+        inner.wake_all();
+        drop(inner);
+        log::info!("[PtyMasterFile::drop] pty {} is hung up", self.pty.index);
+        if let Some(root) = DEVPTS_ROOT.lock().clone() {
+            root.remove_child(&format!("{}", self.pty.index));
+        }
+    }
+}
+
+#[async_trait]
+impl File for PtyMasterFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
+        let nonblock = self.flags().contains(OpenFlags::O_NONBLOCK);
+        self.pty.read(End::Master, nonblock, buf).await
+    }
+
+    async fn base_write_at(&self, _offset: usize, buf: &[u8]) -> SyscallResult {
+        let nonblock = self.flags().contains(OpenFlags::O_NONBLOCK);
+        self.pty.write(End::Master, nonblock, buf).await
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    async fn base_poll(&self, events: PollEvents) -> PollEvents {
+        self.pty.poll(End::Master, events).await
+    }
+
+    fn ioctl(&self, cmd: usize, arg: usize) -> SyscallResult {
+        self.pty.ioctl(End::Master, cmd, arg)
+    }
+}
+
+/// `/dev/pts/N`, the slave of the pty `N`.
+pub struct PtsDentry {
+    meta: DentryMeta,
+    pty: Arc<Pty>,
+}
+
+impl PtsDentry {
+    fn create(parent: &Arc<dyn Dentry>, pty: Arc<Pty>) {
+        let sb = parent.super_block();
+        let name = format!("{}", pty.index);
+        let dentry = Arc::new(Self {
+            meta: DentryMeta::new(&name, sb.clone(), Some(parent.clone())),
+            pty,
+        });
+        let perm = InodeMode::OWNER_READ | InodeMode::OWNER_WRITE | InodeMode::GROUP_WRITE;
+        dentry.set_inode(PtyInode::new(sb, perm));
+        parent.insert(dentry);
+    }
+}
+
+impl Dentry for PtsDentry {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        let mut inner = self.pty.inner.lock();
+        if inner.locked || inner.masters == 0 {
+            return Err(SysError::EIO);
+        }
+        inner.slaves += 1;
+        inner.slave_opened = true;
+        drop(inner);
+        Ok(Arc::new(PtySlaveFile {
+            meta: FileMeta::new(self.clone(), self.inode()?),
+            pty: self.pty.clone(),
+        }))
+    }
+
+    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
+        Err(SysError::ENOTDIR)
+    }
+}
+
+pub struct PtySlaveFile {
+    meta: FileMeta,
+    pty: Arc<Pty>,
+}
+
+impl Drop for PtySlaveFile {
+    fn drop(&mut self) {
+        let mut inner = self.pty.inner.lock();
+        inner.slaves -= 1;
+        if inner.slaves == 0 {
+            inner.wake_all();
+        }
+    }
+}
+
+#[async_trait]
+impl File for PtySlaveFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
+        let nonblock = self.flags().contains(OpenFlags::O_NONBLOCK);
+        self.pty.read(End::Slave, nonblock, buf).await
+    }
+
+    async fn base_write_at(&self, _offset: usize, buf: &[u8]) -> SyscallResult {
+        let nonblock = self.flags().contains(OpenFlags::O_NONBLOCK);
+        self.pty.write(End::Slave, nonblock, buf).await
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    async fn base_poll(&self, events: PollEvents) -> PollEvents {
+        self.pty.poll(End::Slave, events).await
+    }
+
+    fn ioctl(&self, cmd: usize, arg: usize) -> SyscallResult {
+        self.pty.ioctl(End::Slave, cmd, arg)
+    }
+}
+
+pub fn init_devpts(root_dentry: Arc<dyn Dentry>) -> SysResult<()> {
+    *DEVPTS_ROOT.lock() = Some(root_dentry);
+    Ok(())
+}
+
+pub struct DevPtsType {
+    meta: FileSystemTypeMeta,
+}
+
+impl DevPtsType {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            meta: FileSystemTypeMeta::new("devpts"),
+        })
+    }
+}
+
+vfs_core::register_fs!(DevPtsType::new);
+
+impl FileSystemType for DevPtsType {
+    fn meta(&self) -> &FileSystemTypeMeta {
+        &self.meta
+    }
+
+    fn base_mount(
+        self: Arc<Self>,
+        name: &str,
+        parent: Option<Arc<dyn Dentry>>,
+        _flags: MountFlags,
+        dev: Option<Arc<dyn BlockDevice>>,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        let sb = MemSuperBlock::new(dev, self.clone(), DEVPTS_MAGIC);
+        let mount_dentry = SimpleDentry::new(name, sb.clone(), parent);
+        let mount_inode = SimpleDirInode::new(InodeMode::DIR, sb.clone(), 0);
+        Ok(self.mount_root(sb, mount_dentry, mount_inode))
+    }
+
+    fn kill_sb(&self, sb: Arc<dyn SuperBlock>) -> SysResult<()> {
+        self.remove_sb(&sb);
+        Ok(())
+    }
+}
@@ -4,47 +4,15 @@ use async_trait::async_trait;
 use config::board::BLOCK_SIZE;
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{
-    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+    Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, SuperBlock,
 };
 
-pub struct RtcDentry {
-    meta: DentryMeta,
-}
-
-impl RtcDentry {
-    pub fn new(
-        name: &str,
-        super_block: Arc<dyn SuperBlock>,
-        parent: Option<Arc<dyn Dentry>>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new(name, super_block, parent),
-        })
-    }
-}
-
-impl Dentry for RtcDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
-
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+impl DentryOps for RtcFile {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
         Ok(Arc::new(RtcFile {
-            meta: FileMeta::new(self.clone(), self.inode()?),
+            meta: FileMeta::new(dentry, inode),
         }))
     }
-
-    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
-        Err(SysError::ENOTDIR)
-    }
 }
 
 pub struct RtcInode {
@@ -64,30 +32,6 @@ impl Inode for RtcInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
-
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
 }
 
 pub struct RtcFile {
@@ -101,13 +45,13 @@ impl File for RtcFile {
     }
 
     async fn base_read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
-        log::warn!("[RtcFile::base_read_at] fill buf with zero");
+        log::debug!("[RtcFile::base_read_at] fill buf with zero");
         buf.fill(0);
         Ok(buf.len())
     }
 
     async fn base_write_at(&self, _offset: usize, buf: &[u8]) -> SyscallResult {
-        log::warn!("[RtcFile::base_write_at] does nothing");
+        log::debug!("[RtcFile::base_write_at] does nothing");
         Ok(buf.len())
     }
 
@@ -115,10 +59,6 @@ impl File for RtcFile {
         Err(SysError::ENOTDIR)
     }
 
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
-
     fn ioctl(&self, _cmd: usize, arg: usize) -> SyscallResult {
         unsafe {
             *(arg as *mut RtcTime) = RtcTime::default();
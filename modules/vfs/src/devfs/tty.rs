@@ -1,58 +1,48 @@
 use alloc::{boxed::Box, sync::Arc};
 
 use async_trait::async_trait;
+use crate_interface::call_interface;
 use device_core::{CharDevice, DeviceMajor};
 use driver::{get_device_manager, serial::Serial};
-use spin::Once;
 use strum::FromRepr;
 use sync::mutex::{SleepLock, SpinNoIrqLock};
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{
-    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, PollEvents, Stat,
-    SuperBlock,
+    Dentry, DentryOps, DirEntry, FasyncList, File, FileMeta, Inode, InodeMeta, InodeMode,
+    OpenFlags, PollEvents, Stat, SuperBlock,
 };
 
-pub struct TtyDentry {
-    meta: DentryMeta,
-}
+#[crate_interface::def_interface]
+pub trait KernelTtyIf {
+    /// Send the signal `sig` to every process in the process group `pgid`.
+    fn kill_pgrp(pgid: usize, sig: usize);
 
-impl TtyDentry {
-    pub fn new(
-        name: &str,
-        super_block: Arc<dyn SuperBlock>,
-        parent: Option<Arc<dyn Dentry>>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new(name, super_block, parent),
-        })
-    }
+    /// Process group of the current task.
+    fn pgid() -> usize;
 }
 
-impl Dentry for TtyDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
-
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
-        Ok(TtyFile::new(self.clone(), self.inode()?))
-    }
-
-    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
+/// Window resize signal.
+const SIGWINCH: usize = 28;
 
-    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
+/// Tell the foreground process group of a terminal that its window size
+/// changed.
+pub(super) fn signal_resize(fg_pgid: Pid) {
+    if fg_pgid != 0 {
+        call_interface!(KernelTtyIf::kill_pgrp(fg_pgid as usize, SIGWINCH));
     }
+}
 
-    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
-        Err(SysError::ENOTDIR)
+impl DentryOps for TtyFile {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+        Ok(TtyFile::new(dentry, inode))
     }
 }
 
 pub struct TtyInode {
     meta: InodeMeta,
     char_dev: Arc<dyn CharDevice>,
+    /// Terminal state, shared by every open file of the tty.
+    inner: SpinNoIrqLock<TtyInner>,
 }
 
 impl TtyInode {
@@ -69,7 +59,37 @@ impl TtyInode {
             .clone()
             .downcast_arc::<Serial>()
             .unwrap_or_else(|_| unreachable!());
-        Arc::new(Self { meta, char_dev })
+        let tty = Arc::new(Self {
+            meta,
+            char_dev,
+            inner: SpinNoIrqLock::new(TtyInner {
+                fg_pgid: 1 as u32,
+                win_size: WinSize::new(),
+                termios: Termios::new(),
+                fasync: FasyncList::default(),
+            }),
+        });
+        let weak = Arc::downgrade(&tty);
+        tty.char_dev.set_input_hook(Arc::new(move || {
+            if let Some(tty) = weak.upgrade() {
+                let fasync = tty.inner.lock().fasync.clone();
+                fasync.notify(PollEvents::IN);
+            }
+        }));
+        tty
+    }
+
+    /// Set the window size, e.g. once the driver learns the size of the host
+    /// terminal. The foreground process group gets `SIGWINCH` if it changed.
+    pub fn resize(&self, win_size: WinSize) {
+        let mut inner = self.inner.lock();
+        if inner.win_size == win_size {
+            return;
+        }
+        inner.win_size = win_size;
+        let fg_pgid = inner.fg_pgid;
+        drop(inner);
+        signal_resize(fg_pgid);
     }
 }
 
@@ -83,14 +103,14 @@ impl Inode for TtyInode {
         Ok(Stat {
             st_dev: 0,
             st_ino: self.meta.ino as u64,
-            st_mode: self.meta.mode.bits(),
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
+            st_mode: inner.mode.bits(),
+            st_nlink: inner.nlink as _,
+            st_uid: inner.uid,
+            st_gid: inner.gid,
+            st_rdev: self.meta.rdev(),
             __pad: 0,
             st_size: inner.size as u64,
-            st_blksize: 0,
+            st_blksize: self.meta.block_size() as _,
             __pad2: 0,
             st_blocks: 0 as u64,
             st_atime: inner.atime,
@@ -101,12 +121,12 @@ impl Inode for TtyInode {
     }
 }
 
-type Pid = u32;
+pub(super) type Pid = u32;
 
 /// Defined in <asm-generic/ioctls.h>
 #[derive(FromRepr, Debug)]
 #[repr(usize)]
-enum TtyIoctlCmd {
+pub(super) enum TtyIoctlCmd {
     // For struct termios
     /// Gets the current serial port settings.
     TCGETS = 0x5401,
@@ -136,6 +156,8 @@ enum TtyIoctlCmd {
     /// is zero, then send a break (a stream of zero bits) for between 0.25
     /// and 0.5 seconds.
     TCSBRK = 0x5409,
+    /// Make the terminal the controlling terminal of the calling process.
+    TIOCSCTTY = 0x540E,
     /// Get the process group ID of the foreground process group on this
     /// terminal.
     TIOCGPGRP = 0x540F,
@@ -145,19 +167,23 @@ enum TtyIoctlCmd {
     TIOCGWINSZ = 0x5413,
     /// Set window size.
     TIOCSWINSZ = 0x5414,
+    /// Get the index of the slave of a pty master.
+    TIOCGPTN = 0x80045430,
+    /// Lock or unlock the slave of a pty master.
+    TIOCSPTLCK = 0x40045431,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(C)]
-struct WinSize {
-    ws_row: u16,
-    ws_col: u16,
-    ws_xpixel: u16, // Unused
-    ws_ypixel: u16, // Unused
+pub struct WinSize {
+    pub ws_row: u16,
+    pub ws_col: u16,
+    pub ws_xpixel: u16, // Unused
+    pub ws_ypixel: u16, // Unused
 }
 
 impl WinSize {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             ws_row: 67,
             ws_col: 120,
@@ -167,30 +193,30 @@ impl WinSize {
     }
 }
 
-pub static TTY: Once<Arc<TtyFile>> = Once::new();
-
 pub struct TtyFile {
     meta: FileMeta,
-    pub(crate) inner: SpinNoIrqLock<TtyInner>,
 }
 
 struct TtyInner {
     fg_pgid: Pid,
     win_size: WinSize,
     termios: Termios,
+    /// Files signaled when input arrives.
+    fasync: FasyncList,
 }
 
 impl TtyFile {
     pub fn new(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> Arc<Self> {
         Arc::new(Self {
             meta: FileMeta::new(dentry, inode),
-            inner: SpinNoIrqLock::new(TtyInner {
-                fg_pgid: 1 as u32,
-                win_size: WinSize::new(),
-                termios: Termios::new(),
-            }),
         })
     }
+
+    fn tty_inode(&self) -> Arc<TtyInode> {
+        self.inode()
+            .downcast_arc::<TtyInode>()
+            .unwrap_or_else(|_| unreachable!())
+    }
 }
 
 #[async_trait]
@@ -201,13 +227,12 @@ impl File for TtyFile {
 
     async fn base_read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
         log::debug!("[TtyFile::base_read_at] buf len {}", buf.len());
-        let char_dev = &self
-            .inode()
-            .downcast_arc::<TtyInode>()
-            .unwrap_or_else(|_| unreachable!())
-            .char_dev;
+        let char_dev = &self.tty_inode().char_dev;
+        if self.flags().contains(OpenFlags::O_NONBLOCK) && !char_dev.poll_in().await {
+            return Err(SysError::EAGAIN);
+        }
         let len = char_dev.read(buf).await;
-        let termios = self.inner.lock().termios;
+        let termios = self.tty_inode().inner.lock().termios;
         if termios.is_icrnl() {
             for i in 0..len {
                 if buf[i] == '\r' as u8 {
@@ -222,22 +247,14 @@ impl File for TtyFile {
     }
 
     async fn base_write_at(&self, _offset: usize, buf: &[u8]) -> SyscallResult {
-        let char_dev = &self
-            .inode()
-            .downcast_arc::<TtyInode>()
-            .unwrap_or_else(|_| unreachable!())
-            .char_dev;
+        let char_dev = &self.tty_inode().char_dev;
         let len = char_dev.write(buf).await;
         Ok(len)
     }
 
     async fn base_poll(&self, events: PollEvents) -> PollEvents {
         let mut res = PollEvents::empty();
-        let char_dev = &self
-            .inode()
-            .downcast_arc::<TtyInode>()
-            .unwrap_or_else(|_| unreachable!())
-            .char_dev;
+        let char_dev = &self.tty_inode().char_dev;
         if events.contains(PollEvents::IN) {
             if char_dev.poll_in().await {
                 res |= PollEvents::IN;
@@ -252,30 +269,35 @@ impl File for TtyFile {
         res
     }
 
+    fn base_fasync(&self, file: &Arc<dyn File>, fd: usize, on: bool) {
+        self.tty_inode().inner.lock().fasync.update(file, fd, on);
+    }
+
     /// See `ioctl_tty` manual page.
     fn ioctl(&self, cmd: usize, arg: usize) -> SyscallResult {
         use TtyIoctlCmd::*;
         let Some(cmd) = TtyIoctlCmd::from_repr(cmd) else {
             log::error!("[TtyFile::ioctl] cmd {cmd} not included");
-            unimplemented!()
+            return Err(SysError::ENOTTY);
         };
         log::info!("[TtyFile::ioctl] cmd {:?}, value {:#x}", cmd, arg);
+        let inode = self.tty_inode();
         match cmd {
             TCGETS | TCGETA => {
                 unsafe {
-                    *(arg as *mut Termios) = self.inner.lock().termios;
+                    *(arg as *mut Termios) = inode.inner.lock().termios;
                 }
                 Ok(0)
             }
             TCSETS | TCSETSW | TCSETSF => {
                 unsafe {
-                    self.inner.lock().termios = *(arg as *const Termios);
-                    log::info!("termios {:#x?}", self.inner.lock().termios);
+                    inode.inner.lock().termios = *(arg as *const Termios);
+                    log::info!("termios {:#x?}", inode.inner.lock().termios);
                 }
                 Ok(0)
             }
             TIOCGPGRP => {
-                let fg_pgid = self.inner.lock().fg_pgid;
+                let fg_pgid = inode.inner.lock().fg_pgid;
                 log::info!("[TtyFile::ioctl] get fg pgid {fg_pgid}");
                 unsafe {
                     *(arg as *mut Pid) = fg_pgid;
@@ -284,14 +306,14 @@ impl File for TtyFile {
             }
             TIOCSPGRP => {
                 unsafe {
-                    self.inner.lock().fg_pgid = *(arg as *const Pid);
+                    inode.inner.lock().fg_pgid = *(arg as *const Pid);
                 }
-                let fg_pgid = self.inner.lock().fg_pgid;
+                let fg_pgid = inode.inner.lock().fg_pgid;
                 log::info!("[TtyFile::ioctl] set fg pgid {fg_pgid}");
                 Ok(0)
             }
             TIOCGWINSZ => {
-                let win_size = self.inner.lock().win_size;
+                let win_size = inode.inner.lock().win_size;
                 log::info!("[TtyFile::ioctl] get window size {win_size:?}",);
                 unsafe {
                     *(arg as *mut WinSize) = win_size;
@@ -299,29 +321,23 @@ impl File for TtyFile {
                 Ok(0)
             }
             TIOCSWINSZ => {
-                unsafe {
-                    self.inner.lock().win_size = *(arg as *const WinSize);
-                }
+                inode.resize(unsafe { *(arg as *const WinSize) });
                 Ok(0)
             }
-            TCSBRK => Ok(0),
-            _ => todo!(),
+            TCSBRK | TIOCSCTTY => Ok(0),
+            _ => Err(SysError::ENOTTY),
         }
     }
 
     fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
         Err(SysError::ENOTDIR)
     }
-
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
 }
 
 /// Defined in <asm-generic/termbits.h>
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
-struct Termios {
+pub(super) struct Termios {
     /// Input mode flags.
     pub iflag: u32,
     /// Output mode flags.
@@ -337,7 +353,7 @@ struct Termios {
 }
 
 impl Termios {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             // IMAXBEL | IUTF8 | IXON | IXANY | ICRNL | BRKINT
             iflag: 0o66402,
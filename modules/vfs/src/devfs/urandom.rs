@@ -1,112 +1,119 @@
-//! /Dev/random is a pseudo-random number generator device file
+//! /dev/urandom is a random number generator device file
 
 use alloc::{boxed::Box, sync::Arc};
 
+use arch::time::{get_cycle, get_time};
 use async_trait::async_trait;
 use config::board::BLOCK_SIZE;
+use device_core::{DevId, DeviceMajor};
+use sync::mutex::SpinNoIrqLock;
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{
-    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+    Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, SuperBlock,
 };
 
-/// Linear congruence generator (LCG)
-pub struct SimpleRng {
-    state: u64,
+/// Words of the ChaCha20 key.
+const KEY_WORDS: usize = 8;
+/// "expand 32-byte k"
+const SIGMA: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// A ChaCha20 based CSPRNG.
+///
+/// It is seeded lazily from the cycle counter and the timer of the hart, and
+/// fresh readings of both are mixed in before every request. After serving a
+/// request the key is replaced with the next keystream block, so that output
+/// already handed out cannot be recovered from the state.
+pub struct ChaChaRng {
+    key: [u32; KEY_WORDS],
+    counter: u64,
+    seeded: bool,
 }
 
-impl SimpleRng {
-    // 使用时间初始化种子
+impl ChaChaRng {
     pub const fn new() -> Self {
-        // let seed = get_time_duration();
-        let seed = 42;
-        Self { state: seed }
-    }
-
-    // 生成下一个随机数
-    pub fn next_u32(&mut self) -> u32 {
-        const A: u64 = 6364136223846793005;
-        const C: u64 = 1;
-        self.state = self.state.wrapping_mul(A).wrapping_add(C);
-        (self.state >> 32) as u32
+        Self {
+            key: [0; KEY_WORDS],
+            counter: 0,
+            seeded: false,
+        }
     }
 
-    #[allow(dead_code)]
-    pub fn next_u8(&mut self) -> u8 {
-        // LCG 参数：乘数、增量和模数
-        const A: u64 = 1664525;
-        const C: u64 = 1013904223;
-
-        // 更新内部状态
-        self.state = self.state.wrapping_mul(A).wrapping_add(C);
+    fn seed(&mut self) {
+        for word in self.key.iter_mut() {
+            // the low bits of the cycle counter jitter with cache and tlb misses
+            // between readings
+            let cycle = get_cycle();
+            let time = get_time();
+            *word ^= (cycle as u32) ^ (time as u32).rotate_left(16) ^ ((cycle >> 32) as u32);
+            self.counter = self.counter.wrapping_add(cycle as u64);
+        }
+        self.seeded = true;
+    }
+
+    fn block(&mut self) -> [u8; 64] {
+        let mut state = [0u32; 16];
+        state[..4].copy_from_slice(&SIGMA);
+        state[4..12].copy_from_slice(&self.key);
+        state[12] = self.counter as u32;
+        state[13] = (self.counter >> 32) as u32;
+        self.counter = self.counter.wrapping_add(1);
+
+        let mut x = state;
+        for _ in 0..10 {
+            quarter_round(&mut x, 0, 4, 8, 12);
+            quarter_round(&mut x, 1, 5, 9, 13);
+            quarter_round(&mut x, 2, 6, 10, 14);
+            quarter_round(&mut x, 3, 7, 11, 15);
+            quarter_round(&mut x, 0, 5, 10, 15);
+            quarter_round(&mut x, 1, 6, 11, 12);
+            quarter_round(&mut x, 2, 7, 8, 13);
+            quarter_round(&mut x, 3, 4, 9, 14);
+        }
 
-        // 返回最低 8 位
-        (self.state >> 24) as u8
+        let mut out = [0u8; 64];
+        for (i, chunk) in out.chunks_exact_mut(4).enumerate() {
+            chunk.copy_from_slice(&x[i].wrapping_add(state[i]).to_le_bytes());
+        }
+        out
     }
 
-    /// Generate a random number of u32 (4 bytes) at a time, and then split it
-    /// into bytes to fill in the buf
+    /// Fill `buf` with random bytes.
     pub fn fill_buf(&mut self, buf: &mut [u8]) {
-        let mut remaining = buf.len();
-        let mut offset = 0;
-
-        while remaining > 0 {
-            // 生成一个随机的 u32 值
-            let rand = self.next_u32();
-            let rand_bytes = rand.to_le_bytes();
-
-            // 计算要复制的字节数
-            let chunk_size = remaining.min(4);
-
-            // 将 rand_bytes 中的字节填充到 buf 中
-            buf[offset..offset + chunk_size].copy_from_slice(&rand_bytes[..chunk_size]);
-
-            // 更新剩余字节数和偏移量
-            remaining -= chunk_size;
-            offset += chunk_size;
+        if !self.seeded {
+            self.seed();
+        }
+        self.key[0] ^= get_cycle() as u32;
+        self.key[1] ^= get_time() as u32;
+        for chunk in buf.chunks_mut(64) {
+            let block = self.block();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        let block = self.block();
+        for (word, bytes) in self.key.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
         }
     }
 }
 
-pub struct UrandomDentry {
-    meta: DentryMeta,
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
 }
 
-pub static mut RNG: SimpleRng = SimpleRng::new();
-
-impl UrandomDentry {
-    pub fn new(
-        name: &str,
-        super_block: Arc<dyn SuperBlock>,
-        parent: Option<Arc<dyn Dentry>>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new(name, super_block, parent),
-        })
-    }
-}
+pub static RNG: SpinNoIrqLock<ChaChaRng> = SpinNoIrqLock::new(ChaChaRng::new());
 
-impl Dentry for UrandomDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
-
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+impl DentryOps for UrandomFile {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
         Ok(Arc::new(UrandomFile {
-            meta: FileMeta::new(self.clone(), self.inode()?),
+            meta: FileMeta::new(dentry, inode),
         }))
     }
-
-    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
-        Err(SysError::ENOTDIR)
-    }
 }
 
 pub struct UrandomInode {
@@ -116,10 +123,13 @@ pub struct UrandomInode {
 impl UrandomInode {
     pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
         let size = BLOCK_SIZE;
-        Arc::new(Self {
-            // accroding to linux, it should be S_IFCHR
-            meta: InodeMeta::new(InodeMode::CHAR, super_block, size),
-        })
+        // accroding to linux, it should be S_IFCHR
+        let mut meta = InodeMeta::new(InodeMode::CHAR, super_block, size);
+        meta.dev_id = Some(DevId {
+            major: DeviceMajor::Mem,
+            minor: 9,
+        });
+        Arc::new(Self { meta })
     }
 }
 
@@ -127,30 +137,6 @@ impl Inode for UrandomInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
-
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
 }
 
 pub struct UrandomFile {
@@ -172,7 +158,7 @@ impl File for UrandomFile {
     }
 
     async fn read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
-        unsafe { RNG.fill_buf(buf) };
+        RNG.lock().fill_buf(buf);
         Ok(buf.len())
     }
 
@@ -184,8 +170,4 @@ impl File for UrandomFile {
     fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
         Err(SysError::ENOTDIR)
     }
-
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
 }
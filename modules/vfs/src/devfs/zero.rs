@@ -2,50 +2,19 @@ use alloc::{boxed::Box, sync::Arc};
 
 use async_trait::async_trait;
 use config::board::BLOCK_SIZE;
+use device_core::{DevId, DeviceMajor};
 use page::Page;
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{
-    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+    Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, SuperBlock,
 };
 
-pub struct ZeroDentry {
-    meta: DentryMeta,
-}
-
-impl ZeroDentry {
-    pub fn new(
-        name: &str,
-        super_block: Arc<dyn SuperBlock>,
-        parent: Option<Arc<dyn Dentry>>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new(name, super_block, parent),
-        })
-    }
-}
-
-impl Dentry for ZeroDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
-
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+impl DentryOps for ZeroFile {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
         Ok(Arc::new(ZeroFile {
-            meta: FileMeta::new(self.clone(), self.inode()?),
+            meta: FileMeta::new(dentry, inode),
         }))
     }
-
-    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
-        Err(SysError::ENOTDIR)
-    }
 }
 
 pub struct ZeroInode {
@@ -55,9 +24,12 @@ pub struct ZeroInode {
 impl ZeroInode {
     pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
         let size = BLOCK_SIZE;
-        Arc::new(Self {
-            meta: InodeMeta::new(InodeMode::CHAR, super_block, size),
-        })
+        let mut meta = InodeMeta::new(InodeMode::CHAR, super_block, size);
+        meta.dev_id = Some(DevId {
+            major: DeviceMajor::Mem,
+            minor: 5,
+        });
+        Arc::new(Self { meta })
     }
 }
 
@@ -65,30 +37,6 @@ impl Inode for ZeroInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
-
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
 }
 
 pub struct ZeroFile {
@@ -114,10 +62,6 @@ impl File for ZeroFile {
         Err(SysError::ENOTDIR)
     }
 
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
-
     async fn get_page_at(&self, offset_aligned: usize) -> SysResult<Option<Arc<Page>>> {
         let page = Page::new();
         page.fill_zero();
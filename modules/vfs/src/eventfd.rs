@@ -0,0 +1,171 @@
+//! Event files, see eventfd(2). An event file holds a 64-bit counter, a write
+//! adds to it and a read takes it, so that tasks can wake each other up.
+
+use alloc::{boxed::Box, collections::VecDeque, sync::Arc};
+use core::{
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+
+use async_trait::async_trait;
+use async_utils::get_waker;
+use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SyscallResult};
+use vfs_core::{arc_zero, File, FileMeta, Inode, InodeMeta, InodeMode, OpenFlags, PollEvents};
+
+/// Largest value of the counter, a write going beyond it blocks.
+const EVENTFD_MAX: u64 = u64::MAX - 1;
+
+bitflags::bitflags! {
+    /// Flags of eventfd2(2).
+    #[derive(Clone, Copy, Debug)]
+    pub struct EventFdFlags: u32 {
+        /// A read takes one from the counter instead of all of it.
+        const EFD_SEMAPHORE = 1;
+        const EFD_NONBLOCK = OpenFlags::O_NONBLOCK.bits();
+        const EFD_CLOEXEC = OpenFlags::O_CLOEXEC.bits();
+    }
+}
+
+pub struct EventFdInode {
+    meta: InodeMeta,
+}
+
+impl EventFdInode {
+    fn new() -> Arc<Self> {
+        let mode = InodeMode::OWNER_READ | InodeMode::OWNER_WRITE;
+        Arc::new(Self {
+            meta: InodeMeta::new(mode, Arc::<usize>::new_uninit(), 0),
+        })
+    }
+}
+
+impl Inode for EventFdInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+struct EventFdInner {
+    count: u64,
+    // NOTE: all wakers are woken on every change, see `PipeInodeInner`
+    /// Tasks waiting for the counter to become nonzero, or to have room.
+    wakers: VecDeque<Waker>,
+}
+
+impl EventFdInner {
+    fn wait(&mut self, waker: &Waker) {
+        if !self.wakers.iter().any(|w| w.will_wake(waker)) {
+            self.wakers.push_back(waker.clone());
+        }
+    }
+
+    fn wake_all(&mut self) {
+        while let Some(waker) = self.wakers.pop_front() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct EventFdFile {
+    meta: FileMeta,
+    semaphore: bool,
+    inner: SpinNoIrqLock<EventFdInner>,
+}
+
+impl EventFdFile {
+    pub fn new(initval: u32, flags: EventFdFlags) -> Arc<Self> {
+        let file = Arc::new(Self {
+            meta: FileMeta::new(arc_zero(), EventFdInode::new()),
+            semaphore: flags.contains(EventFdFlags::EFD_SEMAPHORE),
+            inner: SpinNoIrqLock::new(EventFdInner {
+                count: initval as u64,
+                wakers: VecDeque::new(),
+            }),
+        });
+        let mut open_flags = OpenFlags::O_RDWR;
+        if flags.contains(EventFdFlags::EFD_NONBLOCK) {
+            open_flags |= OpenFlags::O_NONBLOCK;
+        }
+        file.set_flags(open_flags);
+        file
+    }
+}
+
+#[async_trait]
+impl File for EventFdFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    /// Take the counter, or one of it for a semaphore, waiting while it is
+    /// zero.
+    async fn base_read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
+        if buf.len() < 8 {
+            return Err(SysError::EINVAL);
+        }
+        let nonblock = self.flags().contains(OpenFlags::O_NONBLOCK);
+        let value = poll_fn(|cx| {
+            let mut inner = self.inner.lock();
+            if inner.count == 0 {
+                if nonblock {
+                    return Poll::Ready(Err(SysError::EAGAIN));
+                }
+                inner.wait(cx.waker());
+                return Poll::Pending;
+            }
+            let value = if self.semaphore { 1 } else { inner.count };
+            inner.count -= value;
+            inner.wake_all();
+            Poll::Ready(Ok(value))
+        })
+        .await?;
+        buf[..8].copy_from_slice(&value.to_ne_bytes());
+        Ok(8)
+    }
+
+    /// Add to the counter, waiting until it has room for the value.
+    async fn base_write_at(&self, _offset: usize, buf: &[u8]) -> SyscallResult {
+        if buf.len() < 8 {
+            return Err(SysError::EINVAL);
+        }
+        let value = u64::from_ne_bytes(buf[..8].try_into().unwrap());
+        if value == u64::MAX {
+            return Err(SysError::EINVAL);
+        }
+        let nonblock = self.flags().contains(OpenFlags::O_NONBLOCK);
+        poll_fn(|cx| {
+            let mut inner = self.inner.lock();
+            if EVENTFD_MAX - inner.count < value {
+                if nonblock {
+                    return Poll::Ready(Err(SysError::EAGAIN));
+                }
+                inner.wait(cx.waker());
+                return Poll::Pending;
+            }
+            inner.count += value;
+            if value > 0 {
+                inner.wake_all();
+            }
+            Poll::Ready(Ok(()))
+        })
+        .await?;
+        Ok(8)
+    }
+
+    async fn base_poll(&self, events: PollEvents) -> PollEvents {
+        let waker = get_waker().await;
+        let mut inner = self.inner.lock();
+        let mut res = PollEvents::empty();
+        if events.contains(PollEvents::IN) && inner.count > 0 {
+            res |= PollEvents::IN;
+        }
+        if events.contains(PollEvents::OUT) && inner.count < EVENTFD_MAX {
+            res |= PollEvents::OUT;
+        }
+        if res.is_empty() {
+            inner.wait(&waker);
+        }
+        res
+    }
+}
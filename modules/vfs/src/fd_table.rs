@@ -2,17 +2,17 @@ use alloc::{sync::Arc, vec::Vec};
 use core::fmt;
 
 use config::fs::MAX_FDS;
-use systype::{RLimit, SysError, SysResult};
+use systype::{SysError, SysResult};
 use vfs_core::{File, OpenFlags};
 
-use crate::devfs::tty::TTY;
-
 pub type Fd = usize;
 
 #[derive(Clone)]
 pub struct FdTable {
     table: Vec<Option<FdInfo>>,
-    rlimit: RLimit,
+    /// Soft `RLIMIT_NOFILE` of the processes using the table, no fd may be
+    /// allocated at or above it.
+    max_fds: usize,
 }
 
 bitflags::bitflags! {
@@ -73,27 +73,12 @@ impl FdInfo {
 }
 
 impl FdTable {
-    pub fn new() -> Self {
-        let mut table: Vec<Option<FdInfo>> = Vec::with_capacity(MAX_FDS);
-
-        let tty_file = TTY.get().unwrap().clone();
-        let stdin = tty_file.clone();
-        stdin.set_flags(OpenFlags::empty());
-        let stdout = tty_file.clone();
-        stdout.set_flags(OpenFlags::O_WRONLY);
-        let stderr = tty_file.clone();
-        stderr.set_flags(OpenFlags::O_WRONLY);
-
-        table.push(Some(FdInfo::new(stdin, FdFlags::empty())));
-        table.push(Some(FdInfo::new(stdout, FdFlags::empty())));
-        table.push(Some(FdInfo::new(stderr, FdFlags::empty())));
-
+    /// Create a table without any fd, e.g. for the init task before it opens
+    /// the tty or for a task that has exited.
+    pub fn empty() -> Self {
         Self {
-            table,
-            rlimit: RLimit {
-                rlim_cur: MAX_FDS,
-                rlim_max: MAX_FDS,
-            },
+            table: Vec::new(),
+            max_fds: MAX_FDS,
         }
     }
 
@@ -106,11 +91,12 @@ impl FdTable {
             .table
             .iter()
             .enumerate()
+            .take(self.max_fds)
             .find(|(_i, e)| e.is_none())
             .map(|(i, _)| i);
         if inner_slot.is_some() {
             return inner_slot;
-        } else if inner_slot.is_none() && self.table.len() < self.rlimit.rlim_max {
+        } else if inner_slot.is_none() && self.table.len() < self.max_fds {
             self.table.push(None);
             return Some(self.table.len() - 1);
         } else {
@@ -123,12 +109,13 @@ impl FdTable {
             .table
             .iter()
             .enumerate()
+            .take(self.max_fds)
             .skip(start)
             .find(|(_i, e)| e.is_none())
             .map(|(i, _)| i);
         if inner_slot.is_some() {
             return inner_slot;
-        } else if inner_slot.is_none() && start < self.rlimit.rlim_max {
+        } else if inner_slot.is_none() && start.max(self.table.len()) < self.max_fds {
             // if table len not enough, push enough empty slots
             for _ in self.table.len()..start {
                 self.table.push(None);
@@ -142,7 +129,7 @@ impl FdTable {
     }
 
     fn extend_to(&mut self, len: usize) -> SysResult<()> {
-        if len > self.rlimit.rlim_max {
+        if len > self.max_fds {
             return Err(SysError::EBADF);
         } else if self.table.len() >= len {
             return Ok(());
@@ -238,6 +225,18 @@ impl FdTable {
         Ok(new_fd)
     }
 
+    /// Close the fds from `first` to `last`, or only mark them close-on-exec
+    /// with `cloexec`.
+    pub fn close_range(&mut self, first: Fd, last: Fd, cloexec: bool) {
+        let end = last.saturating_add(1).min(self.table.len());
+        for slot in self.table.iter_mut().take(end).skip(first) {
+            match slot {
+                Some(fd_info) if cloexec => fd_info.set_close_on_exec(),
+                _ => *slot = None,
+            }
+        }
+    }
+
     pub fn do_close_on_exec(&mut self) {
         for slot in self.table.iter_mut() {
             if let Some(fd_info) = slot {
@@ -248,14 +247,8 @@ impl FdTable {
         }
     }
 
-    pub fn rlimit(&self) -> RLimit {
-        self.rlimit
-    }
-
-    pub fn set_rlimit(&mut self, rlimit: RLimit) {
-        self.rlimit = rlimit;
-        if rlimit.rlim_max <= self.table.len() {
-            self.table.truncate(self.rlimit.rlim_max)
-        }
+    /// Set the limit of fds to allocate. Open fds at or above it are kept.
+    pub fn set_max_fds(&mut self, max_fds: usize) {
+        self.max_fds = max_fds;
     }
 }
@@ -4,29 +4,32 @@
 #![feature(new_uninit)]
 
 pub mod devfs;
+pub mod eventfd;
 pub mod fd_table;
+pub mod mount;
 pub mod pipefs;
 pub mod procfs;
+pub mod signalfd;
 pub mod simplefs;
 pub mod sockfs;
+pub mod sysfs;
+pub mod timerfd;
 mod tmpfs;
 
 extern crate alloc;
+// Disk file systems are separate crates, which register themselves once linked.
+extern crate ext4;
+extern crate fat32;
 
-use alloc::{collections::BTreeMap, string::String, sync::Arc};
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
 
-use driver::BLOCK_DEVICE;
 use memory::FrameReleaseIf;
-use procfs::init_procfs;
-use sockfs::SockFsType;
-use spin::Once;
+use mount::mount_boot_fs;
 use sync::mutex::SpinNoIrqLock;
-use vfs_core::{Dentry, DentryState, FileSystemType, InodeMode, MountFlags, OpenFlags, Path};
-
-use crate::{
-    devfs::{init_devfs, DevFsType},
-    procfs::ProcFsType,
-    tmpfs::TmpFsType,
+use systype::SysResult;
+use timer::timelimited_task::ksleep_s;
+use vfs_core::{
+    mount_table, registered_fs_types, Dentry, FileSystemType, IoBand, OpenFlags, Path, SuperBlock,
 };
 
 type Mutex<T> = SpinNoIrqLock<T>;
@@ -34,95 +37,82 @@ type Mutex<T> = SpinNoIrqLock<T>;
 pub static FS_MANAGER: Mutex<BTreeMap<String, Arc<dyn FileSystemType>>> =
     Mutex::new(BTreeMap::new());
 
-static SYS_ROOT_DENTRY: Once<Arc<dyn Dentry>> = Once::new();
-
-// type DiskFsType = fat32::FatFsType;
-type DiskFsType = ext4::Ext4FsType;
+/// Root of the mount namespace, changed by `pivot_root`.
+static SYS_ROOT_DENTRY: Mutex<Option<Arc<dyn Dentry>>> = Mutex::new(None);
 
 // pub const DISK_FS_NAME: &str = "fat32";
 pub const DISK_FS_NAME: &str = "ext4";
 
+/// Register all file system types linked into the kernel, see
+/// `vfs_core::register_fs`.
 fn register_all_fs() {
-    let diskfs = DiskFsType::new();
-    FS_MANAGER.lock().insert(diskfs.name_string(), diskfs);
-
-    let devfs = DevFsType::new();
-    FS_MANAGER.lock().insert(devfs.name_string(), devfs);
+    for fs_type in registered_fs_types() {
+        log::info!("[vfs] register fs {}", fs_type.name());
+        // keep the type registered before, which holds its mounts
+        FS_MANAGER
+            .lock()
+            .entry(fs_type.name_string())
+            .or_insert(fs_type);
+    }
+    log::info!("[vfs] register fs success");
+}
 
-    let procfs = ProcFsType::new();
-    FS_MANAGER.lock().insert(procfs.name_string(), procfs);
+/// Init the filesystem, on the block device found by the driver initcalls.
+fn init() -> SysResult<()> {
+    register_all_fs();
+    mount_boot_fs();
+    Ok(())
+}
+systype::fs_initcall!(init);
 
-    let tmpfs = TmpFsType::new();
-    FS_MANAGER.lock().insert(tmpfs.name_string(), tmpfs);
+pub fn sys_root_dentry() -> Arc<dyn Dentry> {
+    SYS_ROOT_DENTRY.lock().clone().unwrap()
+}
 
-    let sockfs = SockFsType::new();
-    FS_MANAGER.lock().insert(sockfs.name_string(), sockfs);
+pub fn set_sys_root_dentry(root: Arc<dyn Dentry>) {
+    *SYS_ROOT_DENTRY.lock() = Some(root);
+}
 
-    log::info!("[vfs] register fs success");
+/// Collect all mounts as `(mount path, super block)`, ordered by mount id. A
+/// bind mount comes after the mount of the file system it binds.
+pub fn mounts() -> Vec<(String, Arc<dyn SuperBlock>)> {
+    let mut mounts: Vec<_> = mount_table()
+        .into_iter()
+        .map(|mount| (mount.path.clone(), mount.sb.clone()))
+        .collect();
+    mounts.sort_by_key(|(_, sb)| sb.mnt_id());
+    mounts
 }
 
-/// Init the filesystem.
-pub fn init() {
-    register_all_fs();
-    let diskfs = FS_MANAGER.lock().get(DISK_FS_NAME).unwrap().clone();
-    log::info!("[vfs] mounting disk fs");
-    let diskfs_root = diskfs
-        .mount(
-            "/",
-            None,
-            MountFlags::empty(),
-            Some(BLOCK_DEVICE.get().unwrap().clone()),
-        )
-        .unwrap();
-    // WARN: for "lmbench_all lat_sig -P 1 prot lat_sig" test
-    diskfs_root
-        .create(
-            "lat_sig",
-            InodeMode::FILE | InodeMode::OTHER_MASK | InodeMode::GROUP_MASK | InodeMode::OWNER_MASK,
-        )
-        .unwrap();
-    // // WARN: for "lmbench_all lat_sig -P 1 prot lat_sig" test
-    // diskfs_root
-    //     .create(
-    //         "sort.src",
-    //         InodeMode::FILE | InodeMode::OTHER_MASK | InodeMode::GROUP_MASK |
-    // InodeMode::OWNER_MASK,     )
-    //     .unwrap();
-
-    log::info!("[vfs] mounting dev fs");
-    let devfs = FS_MANAGER.lock().get("devfs").unwrap().clone();
-    let devfs_dentry = devfs
-        .mount("dev", Some(diskfs_root.clone()), MountFlags::empty(), None)
-        .unwrap();
-    devfs_dentry.set_state(DentryState::Sync);
-    init_devfs(devfs_dentry).unwrap();
-
-    let procfs = FS_MANAGER.lock().get("procfs").unwrap().clone();
-    let procfs_dentry = procfs
-        .mount("proc", Some(diskfs_root.clone()), MountFlags::empty(), None)
-        .unwrap();
-    procfs_dentry.set_state(DentryState::Sync);
-    init_procfs(procfs_dentry).unwrap();
-
-    let tmpfs = FS_MANAGER.lock().get("tmpfs").unwrap().clone();
-    let tmpfs_dentry = tmpfs
-        .mount("tmp", Some(diskfs_root.clone()), MountFlags::empty(), None)
-        .unwrap();
-    tmpfs_dentry.set_state(DentryState::Sync);
-
-    let sockfs = FS_MANAGER.lock().get("sockfs").unwrap().clone();
-    let sockfs_dentry = sockfs
-        .mount("sock", Some(diskfs_root.clone()), MountFlags::empty(), None)
-        .unwrap();
-    sockfs_dentry.set_state(DentryState::Sync);
-
-    SYS_ROOT_DENTRY.call_once(|| diskfs_root);
-
-    sys_root_dentry().open().unwrap().load_dir().unwrap();
+/// Write back all mounted file systems. A failing one is logged and the
+/// others are still synced.
+pub fn sync_all() {
+    for (path, sb) in mounts() {
+        if let Err(e) = sb.sync_fs(1) {
+            log::error!("[vfs] sync {path} failed: {e:?}");
+        }
+    }
 }
 
-pub fn sys_root_dentry() -> Arc<dyn Dentry> {
-    SYS_ROOT_DENTRY.get().unwrap().clone()
+/// Seconds between two writebacks of the flusher, as the default
+/// dirty_writeback_centisecs of Linux.
+const WRITEBACK_INTERVAL_SECS: usize = 5;
+
+/// Body of the flusher, the kernel task writing back the dirty pages of the
+/// mounted disk file systems every few seconds. Its requests are idle class,
+/// so that they do not hold up the I/O of tasks.
+pub async fn flusher() {
+    loop {
+        ksleep_s(WRITEBACK_INTERVAL_SECS).await;
+        for (path, sb) in mounts() {
+            if sb.meta().device.is_none() {
+                continue;
+            }
+            if let Err(e) = sb.write_back_cached_files(IoBand::Idle).await {
+                log::info!("[flusher] write back {path} failed: {e:?}");
+            }
+        }
+    }
 }
 
 struct FrameReleaseIfImpl;
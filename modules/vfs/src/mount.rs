@@ -0,0 +1,179 @@
+//! Mounts of the file systems at boot.
+//!
+//! The root is mounted first, the disk or a tmpfs if the disk is not usable,
+//! then the synthetic file systems below it in the order of [`BOOT_MOUNTS`],
+//! where a mount comes after the one it is nested in. Anything already
+//! mounted is kept, so that the sequence can be run again to repair the tree.
+
+use alloc::sync::Arc;
+
+use driver::BLOCK_DEVICE;
+use systype::{SysError, SysResult};
+use vfs_core::{Dentry, DentryState, FileSystemType, InodeMode, MountFlags, OpenFlags, Path};
+
+use crate::{
+    devfs::{init_devfs, init_devpts},
+    procfs::init_procfs,
+    set_sys_root_dentry,
+    sysfs::init_sysfs,
+    DISK_FS_NAME, FS_MANAGER, SYS_ROOT_DENTRY,
+};
+
+/// File system mounted as root if the disk can not be.
+const FALLBACK_ROOT_FS_NAME: &str = "tmpfs";
+
+struct BootMount {
+    /// Absolute path of the mount point.
+    path: &'static str,
+    fs_type: &'static str,
+    /// Populates the file system once mounted.
+    init: Option<fn(Arc<dyn Dentry>) -> SysResult<()>>,
+    /// Whether the system can not boot without this mount, other mounts
+    /// failing are skipped.
+    essential: bool,
+}
+
+/// Mounts below the root, the console is in devfs, so that it is populated
+/// before the init process starts.
+static BOOT_MOUNTS: &[BootMount] = &[
+    BootMount {
+        path: "/dev",
+        fs_type: "devfs",
+        init: Some(init_devfs),
+        essential: true,
+    },
+    BootMount {
+        path: "/dev/shm",
+        fs_type: "tmpfs",
+        init: None,
+        essential: false,
+    },
+    BootMount {
+        path: "/dev/pts",
+        fs_type: "devpts",
+        init: Some(init_devpts),
+        essential: false,
+    },
+    BootMount {
+        path: "/proc",
+        fs_type: "procfs",
+        init: Some(init_procfs),
+        essential: true,
+    },
+    BootMount {
+        path: "/sys",
+        fs_type: "sysfs",
+        init: Some(init_sysfs),
+        essential: false,
+    },
+    BootMount {
+        path: "/tmp",
+        fs_type: "tmpfs",
+        init: None,
+        essential: false,
+    },
+    BootMount {
+        path: "/sock",
+        fs_type: "sockfs",
+        init: None,
+        essential: false,
+    },
+];
+
+/// Get the registered file system type called `name`.
+pub fn fs_type(name: &str) -> SysResult<Arc<dyn FileSystemType>> {
+    FS_MANAGER.lock().get(name).cloned().ok_or(SysError::ENODEV)
+}
+
+/// Mount the root, or get it if already mounted.
+fn mount_root() -> Arc<dyn Dentry> {
+    let root = SYS_ROOT_DENTRY.lock().clone();
+    if let Some(root) = root {
+        return root;
+    }
+    log::info!("[vfs] mounting disk fs");
+    let root = BLOCK_DEVICE
+        .get()
+        .ok_or(SysError::ENODEV)
+        .and_then(|dev| {
+            fs_type(DISK_FS_NAME)?.mount("/", None, MountFlags::empty(), Some(dev.clone()))
+        })
+        .or_else(|err| {
+            log::error!(
+                "[vfs] failed to mount disk fs: {err:?}, fall back to {FALLBACK_ROOT_FS_NAME}"
+            );
+            fs_type(FALLBACK_ROOT_FS_NAME)?.mount("/", None, MountFlags::empty(), None)
+        })
+        .expect("no root file system");
+    set_sys_root_dentry(root.clone());
+    root
+}
+
+fn boot_mount(root: &Arc<dyn Dentry>, mount: &BootMount) -> SysResult<()> {
+    let (parent_path, name) = mount.path.rsplit_once('/').unwrap();
+    let parent_path = if parent_path.is_empty() {
+        "/"
+    } else {
+        parent_path
+    };
+    let parent = Path::new(root.clone(), root.clone(), parent_path).walk(OpenFlags::empty())?;
+    let mount_point = parent.lookup(name)?;
+    if mount_point.is_mount_root() && mount_point.super_block().fs_type().name() == mount.fs_type {
+        log::info!(
+            "[vfs] {} is already mounted at {}",
+            mount.fs_type,
+            mount.path
+        );
+        return Ok(());
+    }
+    if mount_point.is_negetive() {
+        let mode =
+            InodeMode::DIR | InodeMode::OWNER_MASK | InodeMode::GROUP_MASK | InodeMode::OTHER_MASK;
+        if let Err(err) = parent.create(name, mode) {
+            // A mount takes the place of the mount point in the dentry tree, so
+            // on a read-only root it mounts over a skeleton dentry instead.
+            log::info!("[vfs] failed to create mount point {}: {err:?}", mount.path);
+        }
+    }
+    let fs_root = fs_type(mount.fs_type)?.mount(name, Some(parent), MountFlags::empty(), None)?;
+    fs_root.set_state(DentryState::Sync);
+    if let Some(init) = mount.init {
+        init(fs_root)?;
+    }
+    Ok(())
+}
+
+/// Mount the root and the file systems of [`BOOT_MOUNTS`].
+///
+/// It panics only if an essential mount fails, and it can be called again to
+/// mount what is missing.
+pub fn mount_boot_fs() {
+    let root = mount_root();
+    // WARN: for "lmbench_all lat_sig -P 1 prot lat_sig" test
+    let mode =
+        InodeMode::FILE | InodeMode::OTHER_MASK | InodeMode::GROUP_MASK | InodeMode::OWNER_MASK;
+    if let Err(err) = root.create("lat_sig", mode) {
+        log::info!("[vfs] failed to create lat_sig: {err:?}");
+    }
+
+    for mount in BOOT_MOUNTS {
+        match boot_mount(&root, mount) {
+            Ok(()) => log::info!("[vfs] mounted {} at {}", mount.fs_type, mount.path),
+            Err(err) if mount.essential => {
+                panic!(
+                    "[vfs] failed to mount {} at {}: {err:?}",
+                    mount.fs_type, mount.path
+                )
+            }
+            Err(err) => log::warn!(
+                "[vfs] skip mounting {} at {}: {err:?}",
+                mount.fs_type,
+                mount.path
+            ),
+        }
+    }
+
+    if let Err(err) = root.open().and_then(|dir| dir.load_dir()) {
+        log::error!("[vfs] failed to load the root directory: {err:?}");
+    }
+}
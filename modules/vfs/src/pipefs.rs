@@ -11,7 +11,10 @@ use config::fs::PIPE_BUF_LEN;
 use ring_buffer::RingBuffer;
 use sync::mutex::SpinNoIrqLock;
 use systype::{SysError, SysResult};
-use vfs_core::{arc_zero, File, FileMeta, Inode, InodeMeta, InodeMode, PollEvents, Stat};
+use vfs_core::{
+    arc_zero, Dentry, FasyncList, File, FileMeta, Inode, InodeMeta, InodeMode, OpenFlags,
+    PollEvents, Stat, SuperBlock,
+};
 
 type Mutex<T> = SpinNoIrqLock<T>;
 
@@ -21,27 +24,102 @@ pub struct PipeInode {
 }
 
 pub struct PipeInodeInner {
+    /// Count of open read ends, the read end is closed when it drops to zero.
+    readers: usize,
+    /// Count of open write ends.
+    writers: usize,
+    /// Count of read ends ever opened, so that an open of a FIFO waiting for
+    /// a reader sees one that came and went.
+    read_opens: usize,
+    /// Count of write ends ever opened.
+    write_opens: usize,
     is_write_closed: bool,
     is_read_closed: bool,
     ring_buffer: RingBuffer,
-    // WARN: `Waker` may not wake the task exactly, it may be abandoned.
-    // Rust only guarentees that waker will wake the task from the last poll where the waker is
-    // passed in.
-    // FIXME: `sys_ppoll` and `sys_pselect6` may return because of other wake ups
-    // while the waker registered here is not removed.
+    // NOTE: a waker registered here may be abandoned, e.g. `sys_ppoll` returned
+    // because of another fd. All wakers are woken on every event, so that a
+    // stale one at the front does not leave the others sleeping, and the
+    // abandoned ones only cost a spurious wake up.
+    /// Tasks waiting for data or for the write end to be closed.
     read_waker: VecDeque<Waker>,
+    /// Tasks waiting for free space or for the read end to be closed.
     write_waker: VecDeque<Waker>,
+    /// Tasks opening a FIFO and waiting for the other end to be opened.
+    open_waker: VecDeque<Waker>,
+    /// Files signaled when data comes into the empty pipe.
+    read_fasync: FasyncList,
+    /// Files signaled when space is freed in the full pipe.
+    write_fasync: FasyncList,
+}
+
+impl PipeInodeInner {
+    fn wait_readable(&mut self, waker: &Waker) {
+        if !self.read_waker.iter().any(|w| w.will_wake(waker)) {
+            self.read_waker.push_back(waker.clone());
+        }
+    }
+
+    fn wait_writable(&mut self, waker: &Waker) {
+        if !self.write_waker.iter().any(|w| w.will_wake(waker)) {
+            self.write_waker.push_back(waker.clone());
+        }
+    }
+
+    fn wake_readers(&mut self) {
+        while let Some(waker) = self.read_waker.pop_front() {
+            waker.wake();
+        }
+    }
+
+    fn wake_writers(&mut self) {
+        while let Some(waker) = self.write_waker.pop_front() {
+            waker.wake();
+        }
+    }
+
+    /// Data left in a FIFO is discarded once no end of it is open.
+    fn discard_if_unused(&mut self) {
+        if self.readers == 0 && self.writers == 0 {
+            while self.ring_buffer.dequeue().is_some() {}
+        }
+    }
+
+    fn wake_openers(&mut self) {
+        while let Some(waker) = self.open_waker.pop_front() {
+            waker.wake();
+        }
+    }
 }
 
 impl PipeInode {
+    /// Create the inode of an anonymous pipe.
     pub fn new(len: usize) -> Arc<Self> {
         let meta = InodeMeta::new(InodeMode::FIFO, Arc::<usize>::new_uninit(), PIPE_BUF_LEN);
+        Self::with_meta(meta, len)
+    }
+
+    /// Create the inode of a FIFO, i.e. a named pipe, living in `super_block`.
+    pub fn new_fifo(mode: InodeMode, super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        debug_assert!(mode.to_type().is_fifo());
+        let meta = InodeMeta::new(mode, super_block, 0);
+        Self::with_meta(meta, PIPE_BUF_LEN)
+    }
+
+    fn with_meta(meta: InodeMeta, len: usize) -> Arc<Self> {
+        // both ends are closed until opened
         let inner = Mutex::new(PipeInodeInner {
-            is_write_closed: false,
-            is_read_closed: false,
+            readers: 0,
+            writers: 0,
+            read_opens: 0,
+            write_opens: 0,
+            is_write_closed: true,
+            is_read_closed: true,
             ring_buffer: RingBuffer::new(len),
             read_waker: VecDeque::new(),
             write_waker: VecDeque::new(),
+            open_waker: VecDeque::new(),
+            read_fasync: FasyncList::default(),
+            write_fasync: FasyncList::default(),
         });
         Arc::new(Self { meta, inner })
     }
@@ -57,14 +135,14 @@ impl Inode for PipeInode {
         Ok(Stat {
             st_dev: 0,
             st_ino: self.meta.ino as u64,
-            st_mode: self.meta.mode.bits(),
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
+            st_mode: inner.mode.bits(),
+            st_nlink: inner.nlink as _,
+            st_uid: inner.uid,
+            st_gid: inner.gid,
             st_rdev: 0,
             __pad: 0,
             st_size: inner.size as u64,
-            st_blksize: 0,
+            st_blksize: self.meta.block_size() as _,
             __pad2: 0,
             st_blocks: 0 as u64,
             st_atime: inner.atime,
@@ -100,7 +178,7 @@ impl Future for PipeWritePollFuture {
             res |= PollEvents::OUT;
             Poll::Ready(res)
         } else {
-            inner.write_waker.push_back(cx.waker().clone());
+            inner.wait_writable(cx.waker());
             Poll::Pending
         }
     }
@@ -111,8 +189,14 @@ pub struct PipeWriteFile {
 }
 
 impl PipeWriteFile {
-    pub fn new(inode: Arc<PipeInode>) -> Arc<Self> {
-        let meta = FileMeta::new(arc_zero(), inode);
+    pub fn new(dentry: Arc<dyn Dentry>, inode: Arc<PipeInode>) -> Arc<Self> {
+        let mut inner = inode.inner.lock();
+        inner.writers += 1;
+        inner.write_opens += 1;
+        inner.is_write_closed = false;
+        inner.wake_openers();
+        drop(inner);
+        let meta = FileMeta::new(dentry, inode);
         Arc::new(Self { meta })
     }
 }
@@ -125,14 +209,16 @@ impl Drop for PipeWriteFile {
             .downcast_arc::<PipeInode>()
             .unwrap_or_else(|_| unreachable!());
         log::info!(
-            "[PipeWriteFile::drop] pipe ino {} write end is closed",
+            "[PipeWriteFile::drop] pipe ino {} write end is dropped",
             pipe.meta().ino
         );
         let mut inner = pipe.inner.lock();
-        inner.is_write_closed = true;
-        while let Some(waker) = inner.read_waker.pop_front() {
-            waker.wake();
+        inner.writers -= 1;
+        if inner.writers == 0 {
+            inner.is_write_closed = true;
+            inner.wake_readers();
         }
+        inner.discard_if_unused();
     }
 }
 
@@ -141,8 +227,14 @@ pub struct PipeReadFile {
 }
 
 impl PipeReadFile {
-    pub fn new(inode: Arc<PipeInode>) -> Arc<Self> {
-        let meta = FileMeta::new(arc_zero(), inode);
+    pub fn new(dentry: Arc<dyn Dentry>, inode: Arc<PipeInode>) -> Arc<Self> {
+        let mut inner = inode.inner.lock();
+        inner.readers += 1;
+        inner.read_opens += 1;
+        inner.is_read_closed = false;
+        inner.wake_openers();
+        drop(inner);
+        let meta = FileMeta::new(dentry, inode);
         Arc::new(Self { meta })
     }
 }
@@ -154,14 +246,16 @@ impl Drop for PipeReadFile {
             .downcast_arc::<PipeInode>()
             .unwrap_or_else(|_| unreachable!());
         log::info!(
-            "[PipeReadFile::drop] pipe ino {} read end is closed",
+            "[PipeReadFile::drop] pipe ino {} read end is dropped",
             pipe.meta().ino
         );
         let mut inner = pipe.inner.lock();
-        inner.is_read_closed = true;
-        while let Some(waker) = inner.write_waker.pop_front() {
-            waker.wake();
+        inner.readers -= 1;
+        if inner.readers == 0 {
+            inner.is_read_closed = true;
+            inner.wake_writers();
         }
+        inner.discard_if_unused();
     }
 }
 
@@ -184,20 +278,38 @@ impl File for PipeWriteFile {
             "[PipeWriteFile::base_write_at] read pipe ino {}",
             pipe.meta().ino
         );
+        if self.flags().contains(OpenFlags::O_NONBLOCK) {
+            let inner = pipe.inner.lock();
+            if !inner.is_read_closed && inner.ring_buffer.is_full() {
+                return Err(SysError::EAGAIN);
+            }
+        }
         let revents = PipeWritePollFuture::new(pipe.clone(), PollEvents::OUT).await;
         if revents.contains(PollEvents::ERR) {
             return Err(SysError::EPIPE);
         }
         assert!(revents.contains(PollEvents::OUT));
         let mut inner = pipe.inner.lock();
+        let was_empty = inner.ring_buffer.is_empty();
         let len = inner.ring_buffer.write(buf);
-        if let Some(waker) = inner.read_waker.pop_front() {
-            waker.wake();
-        }
+        inner.wake_readers();
         log::trace!("[Pipe::write] already write buf {buf:?} with data len {len:?}");
+        if was_empty && len > 0 {
+            let fasync = inner.read_fasync.clone();
+            drop(inner);
+            fasync.notify(PollEvents::IN);
+        }
         return Ok(len);
     }
 
+    fn base_fasync(&self, file: &Arc<dyn File>, fd: usize, on: bool) {
+        let pipe = self
+            .inode()
+            .downcast_arc::<PipeInode>()
+            .unwrap_or_else(|_| unreachable!());
+        pipe.inner.lock().write_fasync.update(file, fd, on);
+    }
+
     async fn base_poll(&self, events: PollEvents) -> PollEvents {
         let waker = get_waker().await;
         let pipe = self
@@ -211,8 +323,9 @@ impl File for PipeWriteFile {
         }
         if events.contains(PollEvents::OUT) && !inner.ring_buffer.is_full() {
             res |= PollEvents::OUT;
-        } else {
-            inner.write_waker.push_back(waker);
+        }
+        if res.is_empty() {
+            inner.wait_writable(&waker);
         }
         res
     }
@@ -243,7 +356,7 @@ impl Future for PipeReadPollFuture {
                 res |= PollEvents::HUP;
                 return Poll::Ready(res);
             }
-            inner.read_waker.push_back(cx.waker().clone());
+            inner.wait_readable(cx.waker());
             Poll::Pending
         }
     }
@@ -264,6 +377,12 @@ impl File for PipeReadFile {
             "[PipeReadFile::base_read_at] read pipe ino {}",
             pipe.meta().ino
         );
+        if self.flags().contains(OpenFlags::O_NONBLOCK) {
+            let inner = pipe.inner.lock();
+            if !inner.is_write_closed && inner.ring_buffer.is_empty() {
+                return Err(SysError::EAGAIN);
+            }
+        }
         let events = PollEvents::IN;
         let revents = PipeReadPollFuture::new(pipe.clone(), events).await;
         if revents.contains(PollEvents::HUP) {
@@ -272,9 +391,13 @@ impl File for PipeReadFile {
         assert!(revents.contains(PollEvents::IN));
         let mut inner = pipe.inner.lock();
 
+        let was_full = inner.ring_buffer.is_full();
         let len = inner.ring_buffer.read(buf);
-        if let Some(waker) = inner.write_waker.pop_front() {
-            waker.wake();
+        inner.wake_writers();
+        if was_full && len > 0 {
+            let fasync = inner.write_fasync.clone();
+            drop(inner);
+            fasync.notify(PollEvents::OUT);
         }
         return Ok(len);
     }
@@ -283,6 +406,14 @@ impl File for PipeReadFile {
         Err(SysError::EBADF)
     }
 
+    fn base_fasync(&self, file: &Arc<dyn File>, fd: usize, on: bool) {
+        let pipe = self
+            .inode()
+            .downcast_arc::<PipeInode>()
+            .unwrap_or_else(|_| unreachable!());
+        pipe.inner.lock().read_fasync.update(file, fd, on);
+    }
+
     async fn base_poll(&self, events: PollEvents) -> PollEvents {
         let pipe = self
             .inode()
@@ -296,16 +427,105 @@ impl File for PipeReadFile {
         }
         if events.contains(PollEvents::IN) && !inner.ring_buffer.is_empty() {
             res |= PollEvents::IN;
-        } else {
-            inner.read_waker.push_back(waker);
+        }
+        if res.is_empty() {
+            inner.wait_readable(&waker);
         }
         res
     }
 }
 
-pub fn new_pipe(len: usize) -> (Arc<dyn File>, Arc<dyn File>) {
+/// Create a pipe, `flags` are the file status flags of both ends, e.g.
+/// `O_NONBLOCK`.
+pub fn new_pipe(len: usize, flags: OpenFlags) -> (Arc<dyn File>, Arc<dyn File>) {
     let pipe_inode = PipeInode::new(len);
-    let read_end = PipeReadFile::new(pipe_inode.clone());
-    let write_end = PipeWriteFile::new(pipe_inode);
+    let read_end = PipeReadFile::new(arc_zero(), pipe_inode.clone());
+    read_end.set_flags(OpenFlags::O_RDONLY | flags.status());
+    let write_end = PipeWriteFile::new(arc_zero(), pipe_inode);
+    write_end.set_flags(OpenFlags::O_WRONLY | flags.status());
     (read_end, write_end)
 }
+
+/// Open the pipe which `file` is an end of once more, with the same access
+/// mode as `file`. Returns `None` if `file` is not a pipe.
+pub fn reopen_pipe(file: &Arc<dyn File>) -> Option<Arc<dyn File>> {
+    let pipe = file.inode().downcast_arc::<PipeInode>().ok()?;
+    if file.is::<PipeReadFile>() {
+        Some(PipeReadFile::new(file.dentry(), pipe))
+    } else {
+        Some(PipeWriteFile::new(file.dentry(), pipe))
+    }
+}
+
+/// Waits in an open of a FIFO until the other end has been opened since.
+struct FifoOpenFuture {
+    pipe: Arc<PipeInode>,
+    /// Waiting for a writer, or else for a reader.
+    for_writer: bool,
+    /// Opens of the other end seen when the open started.
+    opens: usize,
+}
+
+impl Future for FifoOpenFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.pipe.inner.lock();
+        let opens = if self.for_writer {
+            inner.write_opens
+        } else {
+            inner.read_opens
+        };
+        if opens != self.opens {
+            Poll::Ready(())
+        } else {
+            if !inner.open_waker.iter().any(|w| w.will_wake(cx.waker())) {
+                inner.open_waker.push_back(cx.waker().clone());
+            }
+            Poll::Pending
+        }
+    }
+}
+
+/// Open the FIFO at `dentry`.
+///
+/// An open for reading blocks until a writer opens it and vice versa, unless
+/// `O_NONBLOCK` is given. Then an open for reading returns at once, and an
+/// open for writing fails with `ENXIO` when there is no reader.
+pub async fn open_fifo(dentry: Arc<dyn Dentry>, flags: OpenFlags) -> SysResult<Arc<dyn File>> {
+    let pipe = dentry
+        .inode()?
+        .downcast_arc::<PipeInode>()
+        .map_err(|_| SysError::ENXIO)?;
+    let nonblock = flags.contains(OpenFlags::O_NONBLOCK);
+    let (file, wait) = if flags.readable() && flags.writable() {
+        // NOTE: a FIFO opened for both reading and writing is undefined by
+        // POSIX, and not supported here
+        return Err(SysError::EINVAL);
+    } else if flags.writable() {
+        let inner = pipe.inner.lock();
+        if nonblock && inner.readers == 0 {
+            return Err(SysError::ENXIO);
+        }
+        let wait = (inner.readers == 0).then_some(FifoOpenFuture {
+            pipe: pipe.clone(),
+            for_writer: false,
+            opens: inner.read_opens,
+        });
+        drop(inner);
+        (PipeWriteFile::new(dentry, pipe) as Arc<dyn File>, wait)
+    } else {
+        let inner = pipe.inner.lock();
+        let wait = (!nonblock && inner.writers == 0).then_some(FifoOpenFuture {
+            pipe: pipe.clone(),
+            for_writer: true,
+            opens: inner.write_opens,
+        });
+        drop(inner);
+        (PipeReadFile::new(dentry, pipe) as Arc<dyn File>, wait)
+    };
+    if let Some(wait) = wait {
+        wait.await;
+    }
+    Ok(file)
+}
@@ -0,0 +1,239 @@
+//! /proc/self/fd, whose entries are magic links to the open files of the
+//! current task.
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::mem::MaybeUninit;
+
+use async_trait::async_trait;
+use crate_interface::call_interface;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    Dentry, DentryMeta, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode,
+    InodeType, SuperBlock,
+};
+
+use super::KernelProcIf;
+use crate::{pipefs::reopen_pipe, simplefs::dentry::SimpleOps};
+
+pub struct FdDirDentry {
+    meta: DentryMeta,
+}
+
+impl FdDirDentry {
+    pub fn new(super_block: Arc<dyn SuperBlock>, parent: Option<Arc<dyn Dentry>>) -> Arc<Self> {
+        Arc::new(Self {
+            meta: DentryMeta::new("fd", super_block, parent),
+        })
+    }
+
+    fn into_dyn(self: Arc<Self>) -> Arc<dyn Dentry> {
+        self
+    }
+}
+
+impl Dentry for FdDirDentry {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        let inode = self.inode()?;
+        SimpleOps::open(self, inode)
+    }
+
+    /// Every fd number looks up to a link, whether the fd is open is only
+    /// checked when the link is followed, since the dentry is cached and
+    /// shared by all tasks.
+    fn base_lookup(self: Arc<Self>, name: &str) -> SysResult<Arc<dyn Dentry>> {
+        let sb = self.super_block();
+        let sub_dentry = self.into_dyn().get_child_or_create(name);
+        if name.parse::<usize>().is_ok() {
+            sub_dentry.set_inode(FdInode::new(sb));
+        }
+        Ok(sub_dentry)
+    }
+
+    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::EPERM)
+    }
+
+    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
+        Err(SysError::EPERM)
+    }
+
+    fn base_new_child(self: Arc<Self>, name: &str) -> Arc<dyn Dentry> {
+        FdDentry::new(name, self.super_block(), Some(self))
+    }
+}
+
+pub struct FdDentry {
+    meta: DentryMeta,
+}
+
+impl FdDentry {
+    pub fn new(
+        name: &str,
+        super_block: Arc<dyn SuperBlock>,
+        parent: Option<Arc<dyn Dentry>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            meta: DentryMeta::new(name, super_block, parent),
+        })
+    }
+
+    fn fd(&self) -> SysResult<usize> {
+        self.name().parse().map_err(|_| SysError::ENOENT)
+    }
+}
+
+/// The open file of `fd` in the current task.
+fn fd_file(fd: usize) -> SysResult<Arc<dyn File>> {
+    call_interface!(KernelProcIf::file(fd)).ok_or(SysError::ENOENT)
+}
+
+impl Dentry for FdDentry {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        Ok(Arc::new(FdLinkFile {
+            meta: FileMeta::new(self.clone(), self.inode()?),
+            fd: self.fd()?,
+        }))
+    }
+
+    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
+        Err(SysError::ENOTDIR)
+    }
+
+    /// Files with a path are opened again through their dentry, so that a new
+    /// open file is created with the requested flags. A pipe has no path, the
+    /// same pipe is opened again with the access mode of the fd instead.
+    fn base_magic_link(self: Arc<Self>) -> SysResult<Option<Arc<dyn Dentry>>> {
+        let file = fd_file(self.fd()?)?;
+        if file.inode().is::<MaybeUninit<usize>>() {
+            // sockets can not be opened
+            return Err(SysError::ENXIO);
+        }
+        if let Some(pipe) = reopen_pipe(&file) {
+            let dentry = PipeEndDentry::new(self.name(), pipe, self.super_block(), self.parent());
+            return Ok(Some(dentry));
+        }
+        Ok(Some(file.dentry()))
+    }
+}
+
+pub struct FdInode {
+    meta: InodeMeta,
+}
+
+impl FdInode {
+    pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        Arc::new(Self {
+            meta: InodeMeta::new(InodeMode::LINK, super_block, 0),
+        })
+    }
+}
+
+impl Inode for FdInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+pub struct FdLinkFile {
+    meta: FileMeta,
+    fd: usize,
+}
+
+#[async_trait]
+impl File for FdLinkFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, _offset: usize, _buf: &mut [u8]) -> SyscallResult {
+        Err(SysError::EINVAL)
+    }
+
+    async fn base_write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
+        Err(SysError::EINVAL)
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    async fn readlink(&self, buf: &mut [u8]) -> SyscallResult {
+        let file = fd_file(self.fd)?;
+        let target: String = if file.inode().is::<MaybeUninit<usize>>() {
+            "socket:[0]".to_string()
+        } else if file.itype() == InodeType::Fifo {
+            format!("pipe:[{}]", file.inode().meta().ino)
+        } else {
+            file.dentry().path()
+        };
+        let len = target.len().min(buf.len());
+        buf[..len].copy_from_slice(&target.as_bytes()[..len]);
+        Ok(len)
+    }
+}
+
+/// A dentry without name in any directory, standing for a pipe reopened
+/// through /proc/self/fd.
+pub struct PipeEndDentry {
+    meta: DentryMeta,
+    file: Arc<dyn File>,
+}
+
+impl PipeEndDentry {
+    pub fn new(
+        name: &str,
+        file: Arc<dyn File>,
+        super_block: Arc<dyn SuperBlock>,
+        parent: Option<Arc<dyn Dentry>>,
+    ) -> Arc<Self> {
+        let dentry = Arc::new(Self {
+            meta: DentryMeta::new(name, super_block, parent),
+            file: file.clone(),
+        });
+        dentry.set_inode(file.inode());
+        dentry
+    }
+}
+
+impl Dentry for PipeEndDentry {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        Ok(self.file.clone())
+    }
+
+    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
+        Err(SysError::ENOTDIR)
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::cmp;
+
+use async_trait::async_trait;
+use log::Level;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+};
+
+/// Number of records logged with `level` since boot, one line in decimal.
+fn log_count_string(level: Level) -> String {
+    logging::log_count(level).to_string() + "\n"
+}
+
+pub struct LogCountDentry {
+    meta: DentryMeta,
+    level: Level,
+}
+
+impl LogCountDentry {
+    pub fn new(
+        name: &str,
+        level: Level,
+        super_block: Arc<dyn SuperBlock>,
+        parent: Option<Arc<dyn Dentry>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            meta: DentryMeta::new(name, super_block, parent),
+            level,
+        })
+    }
+}
+
+impl Dentry for LogCountDentry {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        Ok(Arc::new(LogCountFile {
+            meta: FileMeta::new(self.clone(), self.inode()?),
+            level: self.level,
+        }))
+    }
+
+    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
+        Err(SysError::ENOTDIR)
+    }
+}
+
+pub struct LogCountInode {
+    meta: InodeMeta,
+    level: Level,
+}
+
+impl LogCountInode {
+    pub fn new(level: Level, super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        Arc::new(Self {
+            meta: InodeMeta::new(InodeMode::FILE, super_block, 0),
+            level,
+        })
+    }
+}
+
+impl Inode for LogCountInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+
+    fn get_attr(&self) -> SysResult<Stat> {
+        let inner = self.meta.inner.lock();
+        let mode = inner.mode.bits();
+        let len = log_count_string(self.level).len();
+        Ok(Stat {
+            st_dev: 0,
+            st_ino: self.meta.ino as u64,
+            st_mode: mode,
+            st_nlink: inner.nlink as _,
+            st_uid: inner.uid,
+            st_gid: inner.gid,
+            st_rdev: 0,
+            __pad: 0,
+            st_size: len as u64,
+            st_blksize: self.meta.block_size() as _,
+            __pad2: 0,
+            st_blocks: (len / 512) as u64,
+            st_atime: inner.atime,
+            st_mtime: inner.mtime,
+            st_ctime: inner.ctime,
+            unused: 0,
+        })
+    }
+}
+
+pub struct LogCountFile {
+    meta: FileMeta,
+    level: Level,
+}
+
+#[async_trait]
+impl File for LogCountFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        let info = log_count_string(self.level);
+        if offset >= info.len() {
+            return Ok(0);
+        }
+        let len = cmp::min(info.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&info.as_bytes()[offset..offset + len]);
+        Ok(len)
+    }
+
+    async fn base_write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
+        Err(SysError::EACCES)
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+}
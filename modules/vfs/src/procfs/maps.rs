@@ -0,0 +1,129 @@
+use alloc::{boxed::Box, string::String, sync::Arc};
+use core::cmp;
+
+use async_trait::async_trait;
+use crate_interface::call_interface;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+};
+
+use super::KernelProcIf;
+
+/// `/proc/[pid]/maps`, the memory areas of task `pid`, or of the calling task
+/// when `pid` is `None`, i.e. `/proc/self/maps`.
+pub struct MapsDentry {
+    meta: DentryMeta,
+    pid: Option<usize>,
+}
+
+impl MapsDentry {
+    pub fn new(
+        pid: Option<usize>,
+        super_block: Arc<dyn SuperBlock>,
+        parent: Option<Arc<dyn Dentry>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            meta: DentryMeta::new("maps", super_block, parent),
+            pid,
+        })
+    }
+}
+
+impl Dentry for MapsDentry {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    /// The areas are listed once on open, so that reads in pieces see one
+    /// consistent snapshot of the live mappings.
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        let maps = call_interface!(KernelProcIf::maps(self.pid)).ok_or(SysError::ENOENT)?;
+        Ok(Arc::new(MapsFile {
+            meta: FileMeta::new(self.clone(), self.inode()?),
+            maps,
+        }))
+    }
+
+    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
+        Err(SysError::ENOTDIR)
+    }
+}
+
+pub struct MapsInode {
+    meta: InodeMeta,
+}
+
+impl MapsInode {
+    pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        Arc::new(Self {
+            meta: InodeMeta::new(InodeMode::FILE, super_block, 0),
+        })
+    }
+}
+
+impl Inode for MapsInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+
+    fn get_attr(&self) -> SysResult<Stat> {
+        let inner = self.meta.inner.lock();
+        Ok(Stat {
+            st_dev: 0,
+            st_ino: self.meta.ino as u64,
+            st_mode: inner.mode.bits(),
+            st_nlink: inner.nlink as _,
+            st_uid: inner.uid,
+            st_gid: inner.gid,
+            st_rdev: 0,
+            __pad: 0,
+            // like linux, the size of a generated file is zero
+            st_size: 0,
+            st_blksize: self.meta.block_size() as _,
+            __pad2: 0,
+            st_blocks: 0,
+            st_atime: inner.atime,
+            st_mtime: inner.mtime,
+            st_ctime: inner.ctime,
+            unused: 0,
+        })
+    }
+}
+
+pub struct MapsFile {
+    meta: FileMeta,
+    maps: String,
+}
+
+#[async_trait]
+impl File for MapsFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        if offset >= self.maps.len() {
+            return Ok(0);
+        }
+        let len = cmp::min(self.maps.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&self.maps.as_bytes()[offset..offset + len]);
+        Ok(len)
+    }
+
+    async fn base_write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
+        Err(SysError::EACCES)
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+}
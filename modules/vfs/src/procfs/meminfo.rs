@@ -6,21 +6,13 @@ use alloc::{
 use core::cmp;
 
 use async_trait::async_trait;
+use config::mm::PAGE_SIZE;
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{
-    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+    CachedDentry, Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode,
+    SuperBlock,
 };
 
-use crate::Mutex;
-
-pub static MEM_INFO: Mutex<MemInfo> = Mutex::new(MemInfo::new());
-
-const TOTAL_MEM: usize = 16251136;
-const FREE_MEM: usize = 327680;
-const BUFFER: usize = 373336;
-const CACHED: usize = 10391984;
-const TOTAL_SWAP: usize = 4194300;
-
 /// Mapping to free output: https://access.redhat.com/solutions/406773.
 pub struct MemInfo {
     /// General memory
@@ -39,19 +31,23 @@ pub struct MemInfo {
 }
 
 impl MemInfo {
-    pub const fn new() -> Self {
+    /// Memory in use now, as counted by the frame allocator, in KB.
+    pub fn now() -> Self {
+        let (total, free) = memory::frame_stat();
+        let kb = |frames: usize| frames * PAGE_SIZE / 1024;
         Self {
-            total_mem: TOTAL_MEM,
-            free_mem: FREE_MEM,
-            avail_mem: TOTAL_MEM - FREE_MEM,
-            buffers: BUFFER,
-            cached: CACHED,
-            total_swap: TOTAL_SWAP,
-            free_swap: TOTAL_SWAP,
+            total_mem: kb(total),
+            free_mem: kb(free),
+            avail_mem: kb(free),
+            buffers: 0,
+            cached: 0,
+            total_swap: 0,
+            free_swap: 0,
             shmem: 0,
             slab: 0,
         }
     }
+
     pub fn serialize(&self) -> String {
         let mut res = "".to_string();
         let end = " KB\n";
@@ -79,44 +75,17 @@ impl MemInfo {
     }
 }
 
-pub struct MemInfoDentry {
-    meta: DentryMeta,
-}
-
-impl MemInfoDentry {
-    pub fn new(
-        name: &str,
-        super_block: Arc<dyn SuperBlock>,
-        parent: Option<Arc<dyn Dentry>>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new(name, super_block, parent),
-        })
-    }
-}
-
-impl Dentry for MemInfoDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
+pub type MemInfoDentry = CachedDentry<MemInfoFile>;
 
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+impl DentryOps for MemInfoFile {
+    /// The content is generated on open, so that reads in pieces see one
+    /// snapshot.
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
         Ok(Arc::new(MemInfoFile {
-            meta: FileMeta::new(self.clone(), self.inode()?),
+            meta: FileMeta::new(dentry, inode),
+            info: MemInfo::now().serialize(),
         }))
     }
-
-    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
-        Err(SysError::ENOTDIR)
-    }
 }
 
 pub struct MemInfoInode {
@@ -124,10 +93,10 @@ pub struct MemInfoInode {
 }
 
 impl MemInfoInode {
-    pub fn new(super_block: Arc<dyn SuperBlock>, _size: usize) -> Arc<Self> {
-        let size = MEM_INFO.lock().serialize().len();
+    pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        // like linux, the size of a generated file is zero
         Arc::new(Self {
-            meta: InodeMeta::new(InodeMode::FILE, super_block, size),
+            meta: InodeMeta::new(InodeMode::FILE, super_block, 0),
         })
     }
 }
@@ -136,34 +105,11 @@ impl Inode for MemInfoInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
-
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
 }
 
 pub struct MemInfoFile {
     meta: FileMeta,
+    info: String,
 }
 
 #[async_trait]
@@ -173,10 +119,11 @@ impl File for MemInfoFile {
     }
 
     async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
-        let meminfo = MEM_INFO.lock();
-        let info = meminfo.serialize();
-        let len = cmp::min(info.len() - offset, buf.len());
-        buf[..len].copy_from_slice(&info.as_bytes()[offset..offset + len]);
+        if offset >= self.info.len() {
+            return Ok(0);
+        }
+        let len = cmp::min(self.info.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&self.info.as_bytes()[offset..offset + len]);
         Ok(len)
     }
 
@@ -187,8 +134,4 @@ impl File for MemInfoFile {
     fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
         Err(SysError::ENOTDIR)
     }
-
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
 }
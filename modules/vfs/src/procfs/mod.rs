@@ -1,23 +1,100 @@
+mod fd;
+mod log_count;
+mod maps;
 mod meminfo;
 mod mounts;
+mod pid;
+#[cfg(feature = "profile")]
+mod profile;
 mod self_;
+mod stat;
 
-use alloc::sync::Arc;
+use alloc::{boxed::Box, format, string::String, sync::Arc, vec::Vec};
+use core::time::Duration;
 
-use async_utils::block_on;
+use config::mm::PAGE_SIZE;
+use crate_interface::call_interface;
 use device_core::BlockDevice;
+use log::Level;
+use memory::vmstat::{vm_stat, VmStat};
+#[cfg(feature = "profile")]
+pub use profile::KernelProfileIf;
 pub use self_::KernelProcIf;
-use systype::SysResult;
+use systype::{SysError, SysResult};
 use vfs_core::{
-    Dentry, FileSystemType, FileSystemTypeMeta, InodeMode, MountFlags, SuperBlock, SuperBlockMeta,
+    dentry_count, inode_count, Dentry, FileSystemType, FileSystemTypeMeta, InodeMode,
+    MemSuperBlock, MountFlags, SuperBlock,
 };
 
 use self::{
+    fd::FdDirDentry,
+    log_count::{LogCountDentry, LogCountInode},
+    maps::{MapsDentry, MapsInode},
     meminfo::{MemInfoDentry, MemInfoInode},
     mounts::{MountsDentry, MountsInode},
+    pid::ProcRootDentry,
     self_::{ExeDentry, ExeFile, ExeInode},
+    stat::{StatDentry, StatInode},
 };
-use crate::simplefs::{dentry::SimpleDentry, inode::SimpleDirInode};
+use crate::{
+    simplefs::{dentry::SimpleDentry, inode::SimpleDirInode},
+    sysfs::attr::AttrDentry,
+};
+
+/// Lines of `/proc/pgid_stat` or `/proc/sid_stat`, with times in microseconds.
+fn acct_table(id: &str, accts: Vec<(usize, usize, usize, [Duration; 2], usize)>) -> String {
+    let mut res = format!("{id} sid nr utime_us stime_us peak_rss_kb\n");
+    for (id, sid, nr, [utime, stime], peak_rss) in accts {
+        res += &format!(
+            "{id} {sid} {nr} {} {} {peak_rss}\n",
+            utime.as_micros(),
+            stime.as_micros()
+        );
+    }
+    res
+}
+
+/// Content of `/proc/[pid]/statm` in pages: size, resident, shared, text,
+/// lib, data and dt, or nothing once the task is gone.
+fn statm(pid: Option<usize>) -> String {
+    let Some([size, anon, file, shmem, text, data]) = call_interface!(KernelProcIf::mem_pages(pid))
+    else {
+        return String::new();
+    };
+    format!(
+        "{size} {} {} {text} 0 {data} 0\n",
+        anon + file + shmem,
+        file + shmem
+    )
+}
+
+/// Memory lines of `/proc/[pid]/status`, or nothing once the task is gone.
+fn status(pid: Option<usize>) -> String {
+    let Some([size, anon, file, shmem, text, data]) = call_interface!(KernelProcIf::mem_pages(pid))
+    else {
+        return String::new();
+    };
+    let mut res = String::new();
+    for (name, pages) in [
+        ("VmSize", size),
+        ("VmRSS", anon + file + shmem),
+        ("RssAnon", anon),
+        ("RssFile", file),
+        ("RssShmem", shmem),
+        ("VmData", data),
+        ("VmExe", text),
+    ] {
+        res += &format!("{name}:\t{:>8} kB\n", pages * PAGE_SIZE / 1024);
+    }
+    res
+}
+
+/// Create the files of `/proc/[pid]` generated from the memory of task `pid`,
+/// or of the current task if `pid` is `None`, in `dir`.
+fn create_mem_files(dir: &Arc<dyn Dentry>, pid: Option<usize>) {
+    AttrDentry::create(dir, "statm", Box::new(move || statm(pid)));
+    AttrDentry::create(dir, "status", Box::new(move || status(pid)));
+}
 
 pub fn init_procfs(root_dentry: Arc<dyn Dentry>) -> SysResult<()> {
     let mem_info_dentry = MemInfoDentry::new(
@@ -25,19 +102,103 @@ pub fn init_procfs(root_dentry: Arc<dyn Dentry>) -> SysResult<()> {
         root_dentry.super_block(),
         Some(root_dentry.clone()),
     );
-    let mem_info_inode = MemInfoInode::new(root_dentry.super_block(), 0);
+    let mem_info_inode = MemInfoInode::new(root_dentry.super_block());
     mem_info_dentry.set_inode(mem_info_inode);
     root_dentry.insert(mem_info_dentry);
 
+    let stat_dentry = StatDentry::new("stat", root_dentry.super_block(), Some(root_dentry.clone()));
+    stat_dentry.set_inode(StatInode::new(root_dentry.super_block()));
+    root_dentry.insert(stat_dentry);
+
+    AttrDentry::create(
+        &root_dentry,
+        "interrupts",
+        Box::new(|| {
+            let acks = call_interface!(KernelProcIf::membarrier_acks());
+            let mut res = String::from("     ");
+            for (hart_id, _) in acks.iter() {
+                res += &format!(" {:>10}", format!("CPU{hart_id}"));
+            }
+            res += "\nMBR: ";
+            for (_, count) in acks.iter() {
+                res += &format!(" {count:>10}");
+            }
+            res += "  Membarrier acknowledgments\n";
+            res
+        }),
+    );
+
+    AttrDentry::create(
+        &root_dentry,
+        "softirqs",
+        Box::new(|| {
+            let (hart_ids, rows) = call_interface!(KernelProcIf::softirqs());
+            let mut res = format!("{:>16}", "");
+            for hart_id in hart_ids {
+                res += &format!(" {:>10}", format!("CPU{hart_id}"));
+            }
+            res += "\n";
+            for (name, values) in rows {
+                res += &format!("{:>16}", format!("{name}:"));
+                for value in values {
+                    res += &format!(" {value:>10}");
+                }
+                res += "\n";
+            }
+            res
+        }),
+    );
+    AttrDentry::create(
+        &root_dentry,
+        "pgid_stat",
+        Box::new(|| acct_table("pgid", call_interface!(KernelProcIf::pgid_accts()))),
+    );
+    AttrDentry::create(
+        &root_dentry,
+        "sid_stat",
+        Box::new(|| acct_table("sid", call_interface!(KernelProcIf::sid_accts()))),
+    );
+    AttrDentry::create(
+        &root_dentry,
+        "vmstat",
+        Box::new(|| {
+            VmStat::ALL
+                .iter()
+                .map(|&item| format!("{} {}\n", item.name(), vm_stat(item)))
+                .collect()
+        }),
+    );
+    AttrDentry::create(
+        &root_dentry,
+        "page_cache_stat",
+        Box::new(|| {
+            let (hits, misses) = page::page_cache_stat();
+            format!("hits {hits}\nmisses {misses}\n")
+        }),
+    );
+
     let mounts_dentry = MountsDentry::new(
         "mounts",
         root_dentry.super_block(),
         Some(root_dentry.clone()),
     );
-    let mounts_inode = MountsInode::new(root_dentry.super_block(), 0);
+    let mounts_inode = MountsInode::new(root_dentry.super_block());
     mounts_dentry.set_inode(mounts_inode);
     root_dentry.insert(mounts_dentry);
 
+    #[cfg(feature = "profile")]
+    {
+        use self::profile::{ProfileDentry, ProfileInode};
+
+        let profile_dentry = ProfileDentry::new(
+            "profile",
+            root_dentry.super_block(),
+            Some(root_dentry.clone()),
+        );
+        profile_dentry.set_inode(ProfileInode::new(root_dentry.super_block()));
+        root_dentry.insert(profile_dentry);
+    }
+
     let sys_dentry: Arc<dyn Dentry> =
         SimpleDentry::new("sys", root_dentry.super_block(), Some(root_dentry.clone()));
     let sys_inode = SimpleDirInode::new(InodeMode::DIR, root_dentry.super_block(), 0);
@@ -45,31 +206,85 @@ pub fn init_procfs(root_dentry: Arc<dyn Dentry>) -> SysResult<()> {
     root_dentry.insert(sys_dentry.clone());
 
     let kernel_dentry = sys_dentry.create("kernel", InodeMode::DIR)?;
-    let pid_max_dentry = kernel_dentry.create("pid_max", InodeMode::FILE)?;
-    let pid_max_file = pid_max_dentry.open()?;
-    block_on(async { pid_max_file.write("32768\0".as_bytes()).await });
+    AttrDentry::create_with_store(
+        &kernel_dentry,
+        "pid_max",
+        Box::new(|| format!("{}\n", call_interface!(KernelProcIf::pid_max()))),
+        Some(Box::new(|value| {
+            let pid_max = value.trim().parse().map_err(|_| SysError::EINVAL)?;
+            call_interface!(KernelProcIf::set_pid_max(pid_max))
+        })),
+    );
+    for (name, level) in [("warn_count", Level::Warn), ("error_count", Level::Error)] {
+        let log_count_dentry = LogCountDentry::new(
+            name,
+            level,
+            kernel_dentry.super_block(),
+            Some(kernel_dentry.clone()),
+        );
+        log_count_dentry.set_inode(LogCountInode::new(level, kernel_dentry.super_block()));
+        kernel_dentry.insert(log_count_dentry);
+    }
+    // NOTE: unlike linux, it only tells whether the kernel is built with
+    // `panic_on_warn` and can not be changed at runtime
+    AttrDentry::create(
+        &kernel_dentry,
+        "panic_on_warn",
+        Box::new(|| format!("{}\n", logging::PANIC_ON_WARN as u8)),
+    );
+    // A deliberate and stable source of warnings for tests, like the `WARNING`
+    // crash type of lkdtm in linux.
+    AttrDentry::create_with_store(
+        &kernel_dentry,
+        "provoke_warn",
+        Box::new(String::new),
+        Some(Box::new(|value| {
+            log::warn!("[procfs] warning provoked by user: {}", value.trim());
+            Ok(())
+        })),
+    );
 
-    let self_dentry: Arc<dyn Dentry> =
-        SimpleDentry::new("self", root_dentry.super_block(), Some(root_dentry.clone()));
-    let self_inode = SimpleDirInode::new(InodeMode::DIR, root_dentry.super_block(), 0);
-    self_dentry.set_inode(self_inode);
-    root_dentry.insert(self_dentry.clone());
+    // only the number of dentries and inodes alive is kept, the other fields are
+    // zero or the defaults of linux
+    let fs_dentry = sys_dentry.create("fs", InodeMode::DIR)?;
+    AttrDentry::create(
+        &fs_dentry,
+        "dentry-state",
+        Box::new(|| format!("{} 0 45 0 0 0\n", dentry_count())),
+    );
+    AttrDentry::create(
+        &fs_dentry,
+        "inode-nr",
+        Box::new(|| format!("{} 0\n", inode_count())),
+    );
 
     let self_dentry: Arc<dyn Dentry> =
         SimpleDentry::new("self", root_dentry.super_block(), Some(root_dentry.clone()));
     let self_inode = SimpleDirInode::new(InodeMode::DIR, root_dentry.super_block(), 0);
     self_dentry.set_inode(self_inode);
     let exe_dentry: Arc<dyn Dentry> =
-        ExeDentry::new(root_dentry.super_block(), Some(root_dentry.clone()));
+        ExeDentry::new("exe", root_dentry.super_block(), Some(root_dentry.clone()));
     let exe_inode = ExeInode::new(root_dentry.super_block(), 0);
     exe_dentry.set_inode(exe_inode);
     self_dentry.insert(exe_dentry);
+    let fd_dentry: Arc<dyn Dentry> =
+        FdDirDentry::new(root_dentry.super_block(), Some(self_dentry.clone()));
+    let fd_inode = SimpleDirInode::new(InodeMode::DIR, root_dentry.super_block(), 0);
+    fd_dentry.set_inode(fd_inode);
+    self_dentry.insert(fd_dentry);
+    let maps_dentry = MapsDentry::new(None, root_dentry.super_block(), Some(self_dentry.clone()));
+    maps_dentry.set_inode(MapsInode::new(root_dentry.super_block()));
+    self_dentry.insert(maps_dentry);
+    create_mem_files(&self_dentry, None);
 
     root_dentry.insert(self_dentry.clone());
 
     Ok(())
 }
 
+/// Magic number reported by statfs, as in linux.
+const PROC_SUPER_MAGIC: i64 = 0x9fa0;
+
 pub struct ProcFsType {
     meta: FileSystemTypeMeta,
 }
@@ -82,6 +297,8 @@ impl ProcFsType {
     }
 }
 
+vfs_core::register_fs!(ProcFsType::new);
+
 impl FileSystemType for ProcFsType {
     fn meta(&self) -> &FileSystemTypeMeta {
         &self.meta
@@ -94,47 +311,14 @@ impl FileSystemType for ProcFsType {
         _flags: MountFlags,
         dev: Option<Arc<dyn BlockDevice>>,
     ) -> SysResult<Arc<dyn Dentry>> {
-        let sb = ProcSuperBlock::new(dev, self.clone());
-        let mount_dentry = SimpleDentry::new(name, sb.clone(), parent.clone());
+        let sb = MemSuperBlock::new(dev, self.clone(), PROC_SUPER_MAGIC);
+        let mount_dentry = ProcRootDentry::new(name, sb.clone(), parent);
         let mount_inode = SimpleDirInode::new(InodeMode::DIR, sb.clone(), 0);
-        mount_dentry.set_inode(mount_inode.clone());
-        if let Some(parent) = parent {
-            parent.insert(mount_dentry.clone());
-        }
-        self.insert_sb(&mount_dentry.path(), sb);
-        Ok(mount_dentry)
-    }
-
-    fn kill_sb(&self, _sb: Arc<dyn SuperBlock>) -> SysResult<()> {
-        todo!()
-    }
-}
-
-pub struct ProcSuperBlock {
-    meta: SuperBlockMeta,
-}
-
-impl ProcSuperBlock {
-    pub fn new(
-        device: Option<Arc<dyn BlockDevice>>,
-        fs_type: Arc<dyn FileSystemType>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: SuperBlockMeta::new(device, fs_type),
-        })
-    }
-}
-
-impl SuperBlock for ProcSuperBlock {
-    fn meta(&self) -> &SuperBlockMeta {
-        &self.meta
-    }
-
-    fn stat_fs(&self) -> SysResult<vfs_core::StatFs> {
-        todo!()
+        Ok(self.mount_root(sb, mount_dentry, mount_inode))
     }
 
-    fn sync_fs(&self, _wait: isize) -> SysResult<()> {
-        todo!()
+    fn kill_sb(&self, sb: Arc<dyn SuperBlock>) -> SysResult<()> {
+        self.remove_sb(&sb);
+        Ok(())
     }
 }
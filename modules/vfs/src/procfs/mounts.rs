@@ -1,56 +1,24 @@
-use alloc::{
-    boxed::Box,
-    string::{String, ToString},
-    sync::Arc,
-};
+use alloc::{boxed::Box, string::String, sync::Arc};
+use core::{cmp, fmt::Write};
 
 use async_trait::async_trait;
-use config::board::BLOCK_SIZE;
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{
-    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+    CachedDentry, Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode,
+    SuperBlock,
 };
 
-use crate::FS_MANAGER;
-
-pub struct MountsDentry {
-    meta: DentryMeta,
-}
+pub type MountsDentry = CachedDentry<MountsFile>;
 
-impl MountsDentry {
-    pub fn new(
-        name: &str,
-        super_block: Arc<dyn SuperBlock>,
-        parent: Option<Arc<dyn Dentry>>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new(name, super_block, parent),
-        })
-    }
-}
-
-impl Dentry for MountsDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
-
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+impl DentryOps for MountsFile {
+    /// The content is generated on open, so that reads in pieces see one
+    /// snapshot.
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
         Ok(Arc::new(MountsFile {
-            meta: FileMeta::new(self.clone(), self.inode()?),
+            meta: FileMeta::new(dentry, inode),
+            mounts: list_mounts(),
         }))
     }
-
-    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
-
-    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
-        Err(SysError::ENOTDIR)
-    }
 }
 
 pub struct MountsInode {
@@ -58,10 +26,10 @@ pub struct MountsInode {
 }
 
 impl MountsInode {
-    pub fn new(super_block: Arc<dyn SuperBlock>, _size: usize) -> Arc<Self> {
-        let size = BLOCK_SIZE;
+    pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        // like linux, the size of a generated file is zero
         Arc::new(Self {
-            meta: InodeMeta::new(InodeMode::FILE, super_block, size),
+            meta: InodeMeta::new(InodeMode::FILE, super_block, 0),
         })
     }
 }
@@ -70,47 +38,27 @@ impl Inode for MountsInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
-
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
 }
 
-// TODO:
+/// Lines of the mount table in the order of mounting, as
+/// `source mount_point fs_type options 0 0`. The source of a file system
+/// without a device is the name of its type.
 pub fn list_mounts() -> String {
-    let mut res = "".to_string();
-    let fs_mgr = FS_MANAGER.lock();
-    for (_fstype, fs) in fs_mgr.iter() {
-        let supers = fs.meta().supers.lock();
-        for (_mount_path, _sb) in supers.iter() {
-            res += "proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0\n"
-        }
+    let mut res = String::new();
+    for (path, sb) in crate::mounts() {
+        let fs_type = sb.fs_type();
+        let source = match sb.meta().device {
+            Some(_) => "/dev/root",
+            None => fs_type.name(),
+        };
+        let _ = writeln!(res, "{source} {path} {} rw 0 0", fs_type.name());
     }
     res
 }
 
 pub struct MountsFile {
     meta: FileMeta,
+    mounts: String,
 }
 
 #[async_trait]
@@ -119,13 +67,12 @@ impl File for MountsFile {
         &self.meta
     }
 
-    async fn base_read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
-        let info = list_mounts();
-        let len = info.len();
-        if self.pos() >= len {
+    async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        if offset >= self.mounts.len() {
             return Ok(0);
         }
-        buf[..len].copy_from_slice(info.as_bytes());
+        let len = cmp::min(self.mounts.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&self.mounts.as_bytes()[offset..offset + len]);
         Ok(len)
     }
 
@@ -136,8 +83,4 @@ impl File for MountsFile {
     fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
         Err(SysError::ENOTDIR)
     }
-
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
 }
@@ -0,0 +1,84 @@
+use alloc::sync::Arc;
+
+use crate_interface::call_interface;
+use systype::{SysError, SysResult};
+use vfs_core::{Dentry, DentryMeta, DentryOps, File, InodeMode, SuperBlock};
+
+use super::{
+    create_mem_files,
+    maps::{MapsDentry, MapsInode},
+    KernelProcIf,
+};
+use crate::simplefs::{
+    dentry::{SimpleDentry, SimpleOps},
+    inode::SimpleDirInode,
+};
+
+/// Root of procfs, where a numeric name is looked up to the directory of the
+/// task with that pid.
+pub struct ProcRootDentry {
+    meta: DentryMeta,
+}
+
+impl ProcRootDentry {
+    pub fn new(
+        name: &str,
+        super_block: Arc<dyn SuperBlock>,
+        parent: Option<Arc<dyn Dentry>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            meta: DentryMeta::new(name, super_block, parent),
+        })
+    }
+
+    fn into_dyn(self: Arc<Self>) -> Arc<dyn Dentry> {
+        self
+    }
+}
+
+impl Dentry for ProcRootDentry {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        let inode = self.inode()?;
+        SimpleOps::open(self, inode)
+    }
+
+    /// NOTE: the directory of a task is cached after it exits, files in it
+    /// check that the task is still alive when opened.
+    fn base_lookup(self: Arc<Self>, name: &str) -> SysResult<Arc<dyn Dentry>> {
+        let sb = self.super_block();
+        let sub_dentry = self.clone().into_dyn().get_child_or_create(name);
+        let Ok(pid) = name.parse::<usize>() else {
+            return Ok(sub_dentry);
+        };
+        if !call_interface!(KernelProcIf::has_task(pid)) {
+            // do not cache the negative dentry, the pid may be used later
+            self.remove_child(name);
+            return Ok(sub_dentry);
+        }
+        sub_dentry.set_inode(SimpleDirInode::new(InodeMode::DIR, sb.clone(), 0));
+        let maps_dentry = MapsDentry::new(Some(pid), sb.clone(), Some(sub_dentry.clone()));
+        maps_dentry.set_inode(MapsInode::new(sb));
+        sub_dentry.insert(maps_dentry);
+        create_mem_files(&sub_dentry, Some(pid));
+        Ok(sub_dentry)
+    }
+
+    fn base_create(self: Arc<Self>, name: &str, mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        let sub_inode = SimpleOps::new_inode(self.super_block(), mode)?;
+        let sub_dentry = self.into_dyn().get_child_or_create(name);
+        sub_dentry.set_inode(sub_inode);
+        Ok(sub_dentry)
+    }
+
+    fn base_unlink(self: Arc<Self>, name: &str) -> SysResult<()> {
+        self.remove_child(name).ok_or(SysError::ENOENT).map(|_| ())
+    }
+
+    fn base_new_child(self: Arc<Self>, name: &str) -> Arc<dyn Dentry> {
+        SimpleDentry::new(name, self.super_block(), Some(self))
+    }
+}
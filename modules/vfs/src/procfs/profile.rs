@@ -0,0 +1,99 @@
+//! `/proc/profile`, the histogram of the sampling profiler.
+//!
+//! The first line is `samples <total> dropped <dropped>`, followed by a line
+//! `<pc> <count>` for each sampled pc, the hottest first. Writing anything to
+//! the file resets the histogram.
+
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use core::{cmp, fmt::Write};
+
+use async_trait::async_trait;
+use crate_interface::call_interface;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    CachedDentry, Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode,
+    SuperBlock,
+};
+
+#[crate_interface::def_interface]
+pub trait KernelProfileIf {
+    /// Samples of all harts as `(pc, count)` sorted by count in descending
+    /// order, and the number of samples dropped.
+    fn samples() -> (Vec<(usize, usize)>, usize);
+
+    fn reset();
+}
+
+pub fn serialize_profile() -> String {
+    let (samples, dropped) = call_interface!(KernelProfileIf::samples());
+    let total: usize = samples.iter().map(|(_, count)| count).sum::<usize>() + dropped;
+    let mut res = String::new();
+    let _ = writeln!(res, "samples {total} dropped {dropped}");
+    for (pc, count) in samples {
+        let _ = writeln!(res, "{pc:#x} {count}");
+    }
+    res
+}
+
+pub type ProfileDentry = CachedDentry<ProfileFile>;
+
+impl DentryOps for ProfileFile {
+    /// The content is generated on open, so that reads in pieces see one
+    /// snapshot.
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+        Ok(Arc::new(ProfileFile {
+            meta: FileMeta::new(dentry, inode),
+            profile: serialize_profile(),
+        }))
+    }
+}
+
+pub struct ProfileInode {
+    meta: InodeMeta,
+}
+
+impl ProfileInode {
+    pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        let mode = InodeMode::FILE | InodeMode::OWNER_READ | InodeMode::OWNER_WRITE;
+        // like linux, the size of a generated file is zero
+        Arc::new(Self {
+            meta: InodeMeta::new(mode, super_block, 0),
+        })
+    }
+}
+
+impl Inode for ProfileInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+pub struct ProfileFile {
+    meta: FileMeta,
+    profile: String,
+}
+
+#[async_trait]
+impl File for ProfileFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        if offset >= self.profile.len() {
+            return Ok(0);
+        }
+        let len = cmp::min(self.profile.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&self.profile.as_bytes()[offset..offset + len]);
+        Ok(len)
+    }
+
+    async fn base_write_at(&self, _offset: usize, buf: &[u8]) -> SyscallResult {
+        call_interface!(KernelProfileIf::reset());
+        Ok(buf.len())
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+}
@@ -5,47 +5,61 @@ use config::board::BLOCK_SIZE;
 use crate_interface::call_interface;
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{
-    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, Stat, SuperBlock,
+    CachedDentry, Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode,
+    SuperBlock,
 };
 
 #[crate_interface::def_interface]
 pub trait KernelProcIf {
     fn exe() -> alloc::string::String;
-}
 
-pub struct ExeDentry {
-    meta: DentryMeta,
-}
+    /// The open file of `fd` in the current task.
+    fn file(fd: usize) -> Option<Arc<dyn File>>;
 
-impl ExeDentry {
-    pub fn new(super_block: Arc<dyn SuperBlock>, parent: Option<Arc<dyn Dentry>>) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new("exe", super_block, parent),
-        })
-    }
-}
+    fn has_task(pid: usize) -> bool;
 
-impl Dentry for ExeDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
+    /// Lines of `/proc/[pid]/maps` of task `pid`, or of the current task if
+    /// `pid` is `None`.
+    fn maps(pid: Option<usize>) -> Option<alloc::string::String>;
 
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
-        Ok(Arc::new(ExeFile {
-            meta: FileMeta::new(self.clone(), self.inode()?),
-        }))
-    }
+    /// Pages of the address space of task `pid`, or of the current task if
+    /// `pid` is `None`: the size, the resident anonymous, file and shared
+    /// memory pages, the text and the data.
+    fn mem_pages(pid: Option<usize>) -> Option<[usize; 6]>;
 
-    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
+    /// User, system and idle time of each online hart, by hart id.
+    fn cpu_times() -> alloc::vec::Vec<(usize, [core::time::Duration; 3])>;
 
-    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        Err(SysError::ENOTDIR)
-    }
+    /// Membarrier requests acknowledged by each online hart, by hart id.
+    fn membarrier_acks() -> alloc::vec::Vec<(usize, usize)>;
 
-    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
-        Err(SysError::ENOTDIR)
+    /// Ids of the online harts, and rows of softirq counters with a value for
+    /// each of them, see `/proc/softirqs`.
+    fn softirqs() -> (
+        alloc::vec::Vec<usize>,
+        alloc::vec::Vec<(&'static str, alloc::vec::Vec<u64>)>,
+    );
+
+    /// Id, session id, member count, user and system time, and peak resident
+    /// size in kB of each process group, by pgid.
+    fn pgid_accts() -> alloc::vec::Vec<(usize, usize, usize, [core::time::Duration; 2], usize)>;
+
+    /// Same as `pgid_accts`, for each session with a group count, by sid.
+    fn sid_accts() -> alloc::vec::Vec<(usize, usize, usize, [core::time::Duration; 2], usize)>;
+
+    /// Ids of tasks are below this.
+    fn pid_max() -> usize;
+
+    fn set_pid_max(pid_max: usize) -> SysResult<()>;
+}
+
+pub type ExeDentry = CachedDentry<ExeFile>;
+
+impl DentryOps for ExeFile {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+        Ok(Arc::new(ExeFile {
+            meta: FileMeta::new(dentry, inode),
+        }))
     }
 }
 
@@ -66,30 +80,6 @@ impl Inode for ExeInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
-
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
 }
 
 pub struct ExeFile {
@@ -114,14 +104,10 @@ impl File for ExeFile {
         Err(SysError::ENOTDIR)
     }
 
-    fn flush(&self) -> SysResult<usize> {
-        todo!()
-    }
-
     async fn readlink(&self, buf: &mut [u8]) -> SyscallResult {
         let exe = call_interface!(KernelProcIf::exe());
         if buf.len() < exe.len() + 1 {
-            log::warn!("readlink buf not big enough");
+            log::info!("readlink buf not big enough");
             return Err(SysError::EINVAL);
         }
         buf[0..exe.len()].copy_from_slice(exe.as_bytes());
@@ -0,0 +1,108 @@
+use alloc::{boxed::Box, string::String, sync::Arc};
+use core::{cmp, fmt::Write, time::Duration};
+
+use async_trait::async_trait;
+use config::time::USER_HZ;
+use crate_interface::call_interface;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    CachedDentry, Dentry, DentryOps, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode,
+    SuperBlock,
+};
+
+use super::KernelProcIf;
+
+fn ticks(time: Duration) -> u128 {
+    time.as_micros() * USER_HZ as u128 / 1_000_000
+}
+
+/// Write a line of `/proc/stat` for cpu `name`, the fields are user, nice,
+/// system, idle, iowait, irq, softirq, steal, guest and guest_nice.
+fn write_cpu_line(res: &mut String, name: &str, [user, system, idle]: [Duration; 3]) {
+    let _ = writeln!(
+        res,
+        "{name} {} 0 {} {} 0 0 0 0 0 0",
+        ticks(user),
+        ticks(system),
+        ticks(idle)
+    );
+}
+
+/// Lines of `/proc/stat`, the first of which sums up all harts.
+pub fn serialize_stat() -> String {
+    let cpu_times = call_interface!(KernelProcIf::cpu_times());
+    let mut total = [Duration::ZERO; 3];
+    for (_, times) in cpu_times.iter() {
+        for (sum, time) in total.iter_mut().zip(times) {
+            *sum += *time;
+        }
+    }
+    let mut res = String::new();
+    write_cpu_line(&mut res, "cpu ", total);
+    for (hart_id, times) in cpu_times {
+        write_cpu_line(&mut res, &alloc::format!("cpu{hart_id}"), times);
+    }
+    res += "intr 0\nctxt 0\nbtime 0\nprocesses 0\nprocs_running 1\nprocs_blocked 0\n";
+    res
+}
+
+pub type StatDentry = CachedDentry<StatFile>;
+
+impl DentryOps for StatFile {
+    /// The content is generated on open, so that reads in pieces see one
+    /// snapshot.
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+        Ok(Arc::new(StatFile {
+            meta: FileMeta::new(dentry, inode),
+            stat: serialize_stat(),
+        }))
+    }
+}
+
+pub struct StatInode {
+    meta: InodeMeta,
+}
+
+impl StatInode {
+    pub fn new(super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        // like linux, the size of a generated file is zero
+        Arc::new(Self {
+            meta: InodeMeta::new(InodeMode::FILE, super_block, 0),
+        })
+    }
+}
+
+impl Inode for StatInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+pub struct StatFile {
+    meta: FileMeta,
+    stat: String,
+}
+
+#[async_trait]
+impl File for StatFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        if offset >= self.stat.len() {
+            return Ok(0);
+        }
+        let len = cmp::min(self.stat.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&self.stat.as_bytes()[offset..offset + len]);
+        Ok(len)
+    }
+
+    async fn base_write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
+        Err(SysError::EACCES)
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+}
@@ -0,0 +1,206 @@
+//! Signal files, see signalfd(2). A read takes the pending signals of the
+//! reading task that are in the mask of the file, which are then no longer
+//! delivered to it.
+
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    future::poll_fn,
+    mem::size_of,
+    task::{Poll, Waker},
+};
+
+use async_trait::async_trait;
+use async_utils::get_waker;
+use crate_interface::call_interface;
+use signal::{
+    siginfo::{SigDetails, SigInfo},
+    sigset::SigSet,
+};
+use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SyscallResult};
+use vfs_core::{arc_zero, File, FileMeta, Inode, InodeMeta, InodeMode, OpenFlags, PollEvents};
+
+#[crate_interface::def_interface]
+pub trait KernelSignalIf {
+    /// Take a pending signal in `mask` of the current task, or make `waker`
+    /// wait for the next signal if there is none.
+    fn take_signal(mask: SigSet, waker: Option<&Waker>) -> Option<SigInfo>;
+
+    /// Whether a signal in `mask` is pending for the current task, making
+    /// `waker` wait for the next signal if not.
+    fn has_signal(mask: SigSet, waker: &Waker) -> bool;
+}
+
+bitflags::bitflags! {
+    /// Flags of signalfd4(2).
+    #[derive(Clone, Copy, Debug)]
+    pub struct SignalFdFlags: u32 {
+        const SFD_NONBLOCK = OpenFlags::O_NONBLOCK.bits();
+        const SFD_CLOEXEC = OpenFlags::O_CLOEXEC.bits();
+    }
+}
+
+/// What a read returns for each signal, `struct signalfd_siginfo`.
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct SignalFdSigInfo {
+    pub ssi_signo: u32,
+    pub ssi_errno: i32,
+    pub ssi_code: i32,
+    pub ssi_pid: u32,
+    pub ssi_uid: u32,
+    pub ssi_fd: i32,
+    pub ssi_tid: u32,
+    pub ssi_band: u32,
+    pub ssi_overrun: u32,
+    pub ssi_trapno: u32,
+    pub ssi_status: i32,
+    pub ssi_int: i32,
+    pub ssi_ptr: u64,
+    pub ssi_utime: u64,
+    pub ssi_stime: u64,
+    pub ssi_addr: u64,
+    pub ssi_addr_lsb: u16,
+    pub __pad2: u16,
+    pub ssi_syscall: i32,
+    pub ssi_call_addr: u64,
+    pub ssi_arch: u32,
+    pub __pad: [u8; 28],
+}
+
+impl From<SigInfo> for SignalFdSigInfo {
+    fn from(si: SigInfo) -> Self {
+        let mut info = Self {
+            ssi_signo: si.sig.raw() as u32,
+            ssi_code: si.code,
+            ..Default::default()
+        };
+        match si.details {
+            SigDetails::None => {}
+            SigDetails::Kill { pid } => info.ssi_pid = pid as u32,
+            SigDetails::Child { pid, status } => {
+                info.ssi_pid = pid as u32;
+                info.ssi_status = status;
+            }
+            SigDetails::Fault { addr } => info.ssi_addr = addr as u64,
+            SigDetails::Poll { band, fd } => {
+                info.ssi_band = band as u32;
+                info.ssi_fd = fd as i32;
+            }
+            SigDetails::Timer {
+                tid,
+                overrun,
+                value,
+            } => {
+                info.ssi_tid = tid as u32;
+                info.ssi_overrun = overrun as u32;
+                info.ssi_int = value as i32;
+                info.ssi_ptr = value as u64;
+            }
+        }
+        info
+    }
+}
+
+impl SignalFdSigInfo {
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self as *const Self as *const u8, size_of::<Self>()) }
+    }
+}
+
+pub struct SignalFdInode {
+    meta: InodeMeta,
+}
+
+impl SignalFdInode {
+    fn new() -> Arc<Self> {
+        let mode = InodeMode::OWNER_READ | InodeMode::OWNER_WRITE;
+        Arc::new(Self {
+            meta: InodeMeta::new(mode, Arc::<usize>::new_uninit(), 0),
+        })
+    }
+}
+
+impl Inode for SignalFdInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+pub struct SignalFdFile {
+    meta: FileMeta,
+    /// Signals the file takes.
+    mask: SpinNoIrqLock<SigSet>,
+}
+
+impl SignalFdFile {
+    pub fn new(mask: SigSet, flags: SignalFdFlags) -> Arc<Self> {
+        let file = Arc::new(Self {
+            meta: FileMeta::new(arc_zero(), SignalFdInode::new()),
+            mask: SpinNoIrqLock::new(SigSet::empty()),
+        });
+        file.set_mask(mask);
+        let mut open_flags = OpenFlags::O_RDONLY;
+        if flags.contains(SignalFdFlags::SFD_NONBLOCK) {
+            open_flags |= OpenFlags::O_NONBLOCK;
+        }
+        file.set_flags(open_flags);
+        file
+    }
+
+    /// Replace the signals the file takes. SIGKILL and SIGSTOP can not be
+    /// taken and are silently ignored.
+    pub fn set_mask(&self, mut mask: SigSet) {
+        mask.remove(SigSet::SIGKILL | SigSet::SIGSTOP);
+        *self.mask.lock() = mask;
+    }
+}
+
+#[async_trait]
+impl File for SignalFdFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    /// Take as many pending signals as fit in `buf`, waiting for the first one
+    /// if none is pending.
+    async fn base_read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
+        const INFO_SIZE: usize = size_of::<SignalFdSigInfo>();
+        if buf.len() < INFO_SIZE {
+            return Err(SysError::EINVAL);
+        }
+        let mask = *self.mask.lock();
+        let nonblock = self.flags().contains(OpenFlags::O_NONBLOCK);
+        let si = poll_fn(|cx| {
+            let waker = (!nonblock).then(|| cx.waker());
+            match call_interface!(KernelSignalIf::take_signal(mask, waker)) {
+                Some(si) => Poll::Ready(Ok(si)),
+                None if nonblock => Poll::Ready(Err(SysError::EAGAIN)),
+                None => Poll::Pending,
+            }
+        })
+        .await?;
+        buf[..INFO_SIZE].copy_from_slice(SignalFdSigInfo::from(si).as_bytes());
+        let mut len = INFO_SIZE;
+        while buf.len() - len >= INFO_SIZE {
+            let Some(si) = call_interface!(KernelSignalIf::take_signal(mask, None)) else {
+                break;
+            };
+            buf[len..len + INFO_SIZE].copy_from_slice(SignalFdSigInfo::from(si).as_bytes());
+            len += INFO_SIZE;
+        }
+        Ok(len)
+    }
+
+    async fn base_poll(&self, events: PollEvents) -> PollEvents {
+        let waker = get_waker().await;
+        let mask = *self.mask.lock();
+        let mut res = PollEvents::empty();
+        if events.contains(PollEvents::IN)
+            && call_interface!(KernelSignalIf::has_signal(mask, &waker))
+        {
+            res |= PollEvents::IN;
+        }
+        res
+    }
+}
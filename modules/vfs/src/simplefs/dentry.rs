@@ -1,70 +1,60 @@
 use alloc::sync::Arc;
 
+use device_core::DevId;
 use systype::{SysError, SysResult};
-use vfs_core::{Dentry, DentryMeta, File, Inode, InodeMode, InodeType, SuperBlock};
+use vfs_core::{
+    CachedDentry, Dentry, DentryOps, DirFile, File, Inode, InodeMode, InodeType, SuperBlock,
+};
 
 use super::{
-    file::{SimpleDirFile, SimpleFileFile},
-    inode::{SimpleDirInode, SimpleFileInode},
+    file::{SimpleFileFile, SimpleLinkFile},
+    inode::{SimpleDevInode, SimpleDirInode, SimpleFileInode, SimpleLinkInode},
 };
+use crate::{devfs::open_device, pipefs::PipeInode};
 
-pub struct SimpleDentry {
-    meta: DentryMeta,
-}
-
-impl SimpleDentry {
-    pub fn new(
-        name: &str,
-        super_block: Arc<dyn SuperBlock>,
-        parent: Option<Arc<dyn Dentry>>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: DentryMeta::new(name, super_block, parent),
-        })
-    }
+/// Files of a memory only file system, with simple inodes.
+pub struct SimpleOps;
 
-    pub fn into_dyn(self: Arc<Self>) -> Arc<dyn Dentry> {
-        self.clone()
-    }
-}
+pub type SimpleDentry = CachedDentry<SimpleOps>;
 
-impl Dentry for SimpleDentry {
-    fn meta(&self) -> &DentryMeta {
-        &self.meta
-    }
-
-    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
-        let inode = self.inode()?;
+impl DentryOps for SimpleOps {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
         match inode.itype() {
-            InodeType::Dir => Ok(SimpleDirFile::new(self.clone(), inode)),
-            InodeType::File => Ok(SimpleFileFile::new(self.clone(), inode)),
-            InodeType::Socket => Ok(SimpleFileFile::new(self.clone(), inode)),
+            InodeType::Dir => {
+                let inode = inode
+                    .downcast_arc::<SimpleDirInode>()
+                    .map_err(|_| SysError::EIO)?;
+                Ok(DirFile::new(dentry, inode))
+            }
+            InodeType::File => Ok(SimpleFileFile::new(dentry, inode)),
+            InodeType::Socket => Ok(SimpleFileFile::new(dentry, inode)),
+            InodeType::SymLink => Ok(SimpleLinkFile::new(dentry, inode)),
+            // a FIFO is opened through `open_fifo`, which needs the open flags
+            InodeType::Fifo => Err(SysError::ENXIO),
+            InodeType::CharDevice | InodeType::BlockDevice => open_device(dentry, &inode),
             _ => unreachable!(),
         }
     }
 
-    fn base_lookup(self: Arc<Self>, name: &str) -> SysResult<Arc<dyn Dentry>> {
-        let sub_dentry = self.into_dyn().get_child_or_create(name);
-        Ok(sub_dentry)
-    }
-
-    fn base_create(self: Arc<Self>, name: &str, mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
-        let sb = self.super_block();
-        let sub_dentry = self.into_dyn().get_child_or_create(name);
-        let sub_inode: Arc<dyn Inode> = match mode.to_type() {
-            InodeType::Dir => SimpleDirInode::new(mode, sb, 0),
-            InodeType::File => SimpleFileInode::new(mode, sb, 0),
-            _ => return Err(SysError::EPERM),
-        };
-        sub_dentry.set_inode(sub_inode);
-        Ok(sub_dentry)
+    fn new_inode(sb: Arc<dyn SuperBlock>, mode: InodeMode) -> SysResult<Arc<dyn Inode>> {
+        match mode.to_type() {
+            InodeType::Dir => Ok(SimpleDirInode::new(mode, sb, 0)),
+            // a socket inode only marks the path a unix socket is bound to
+            InodeType::File | InodeType::Socket => Ok(SimpleFileInode::new(mode, sb, 0)),
+            InodeType::Fifo => Ok(PipeInode::new_fifo(mode, sb)),
+            _ => Err(SysError::EPERM),
+        }
     }
 
-    fn base_unlink(self: Arc<Self>, name: &str) -> SysResult<()> {
-        self.remove_child(name).ok_or(SysError::ENOENT).map(|_| ())
+    fn new_device(
+        sb: Arc<dyn SuperBlock>,
+        mode: InodeMode,
+        dev_id: DevId,
+    ) -> SysResult<Arc<dyn Inode>> {
+        Ok(SimpleDevInode::new(mode, sb, dev_id))
     }
 
-    fn base_new_child(self: Arc<Self>, name: &str) -> Arc<dyn Dentry> {
-        Self::new(name, self.super_block(), Some(self))
+    fn new_symlink(sb: Arc<dyn SuperBlock>, target: &str) -> SysResult<Arc<dyn Inode>> {
+        Ok(SimpleLinkInode::new(target, sb))
     }
 }
@@ -6,44 +6,7 @@ use page::Page;
 use systype::{SysError, SysResult, SyscallResult};
 use vfs_core::{Dentry, DirEntry, File, FileMeta, Inode};
 
-pub struct SimpleDirFile {
-    meta: FileMeta,
-}
-
-impl SimpleDirFile {
-    pub fn new(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> Arc<Self> {
-        Arc::new(Self {
-            meta: FileMeta::new(dentry, inode),
-        })
-    }
-}
-
-#[async_trait]
-impl File for SimpleDirFile {
-    fn meta(&self) -> &FileMeta {
-        &self.meta
-    }
-
-    async fn base_read_at(&self, _offset: usize, _buf: &mut [u8]) -> SyscallResult {
-        Err(SysError::EISDIR)
-    }
-
-    async fn base_write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
-        Err(SysError::EISDIR)
-    }
-
-    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
-        todo!()
-    }
-
-    fn base_load_dir(&self) -> SysResult<()> {
-        Ok(())
-    }
-
-    fn flush(&self) -> systype::SysResult<usize> {
-        todo!()
-    }
-}
+use super::inode::SimpleLinkInode;
 
 pub struct SimpleFileFile {
     meta: FileMeta,
@@ -79,10 +42,6 @@ impl File for SimpleFileFile {
         Err(SysError::ENOTDIR)
     }
 
-    fn flush(&self) -> systype::SysResult<usize> {
-        todo!()
-    }
-
     async fn read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
         log::info!(
             "[File::read] file {}, offset {offset}, buf len {}",
@@ -154,7 +113,7 @@ impl File for SimpleFileFile {
             buf_it = &buf_it[len..];
         }
         if offset_it > self.size() {
-            log::warn!(
+            log::debug!(
                 "[File::write_at] write beyond file, offset_it:{offset_it}, size:{}",
                 self.size()
             );
@@ -210,10 +169,6 @@ impl File for SimpleLinkFile {
         Err(SysError::ENOTDIR)
     }
 
-    fn flush(&self) -> systype::SysResult<usize> {
-        todo!()
-    }
-
     async fn read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
         todo!()
     }
@@ -227,6 +182,13 @@ impl File for SimpleLinkFile {
     }
 
     async fn readlink(&self, buf: &mut [u8]) -> SyscallResult {
-        todo!()
+        let inode = self
+            .inode()
+            .downcast_arc::<SimpleLinkInode>()
+            .unwrap_or_else(|_| unreachable!());
+        let target = inode.target().as_bytes();
+        let len = target.len().min(buf.len());
+        buf[..len].copy_from_slice(&target[..len]);
+        Ok(len)
     }
 }
@@ -1,9 +1,13 @@
-use alloc::sync::Arc;
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+};
 
 use config::mm::{round_up_to_page, PAGE_SIZE};
+use device_core::DevId;
 use page::{Page, PageCache};
 use systype::SysResult;
-use vfs_core::{Inode, InodeMeta, InodeMode, InodeState, Stat, SuperBlock};
+use vfs_core::{DirOps, Inode, InodeMeta, InodeMode, InodeState, InodeType, SuperBlock};
 
 pub struct SimpleFileInode {
     meta: InodeMeta,
@@ -24,35 +28,10 @@ impl Inode for SimpleFileInode {
         &self.meta
     }
 
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
-
     fn base_truncate(&self, len: usize) -> SysResult<()> {
-        if len == self.size() {
+        if len <= self.size() {
+            // NOTE: pages beyond `len` are dropped by `Inode::truncate`
             return Ok(());
-        } else if len < self.size() {
-            todo!()
         } else {
             let page_cache = self.meta().page_cache.as_ref().unwrap();
             let offset_aligned_start = round_up_to_page(self.size());
@@ -84,71 +63,54 @@ impl Inode for SimpleDirInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
-
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
-    }
 }
 
+impl DirOps for SimpleDirInode {}
+
 pub struct SimpleLinkInode {
     meta: InodeMeta,
+    target: String,
 }
 
 impl SimpleLinkInode {
-    pub fn new(mode: InodeMode, super_block: Arc<dyn SuperBlock>, size: usize) -> Arc<Self> {
-        debug_assert!(mode.to_type().is_symlink());
+    pub fn new(target: &str, super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
         Arc::new(Self {
-            meta: InodeMeta::new(mode, super_block, size),
+            meta: InodeMeta::new(InodeMode::LINK, super_block, target.len()),
+            target: target.to_string(),
         })
     }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
 }
 
 impl Inode for SimpleLinkInode {
     fn meta(&self) -> &InodeMeta {
         &self.meta
     }
+}
 
-    fn get_attr(&self) -> SysResult<Stat> {
-        let inner = self.meta.inner.lock();
-        let mode = self.meta.mode.bits();
-        let len = inner.size;
-        Ok(Stat {
-            st_dev: 0,
-            st_ino: self.meta.ino as u64,
-            st_mode: mode,
-            st_nlink: 1,
-            st_uid: 0,
-            st_gid: 0,
-            st_rdev: 0,
-            __pad: 0,
-            st_size: len as u64,
-            st_blksize: 512,
-            __pad2: 0,
-            st_blocks: (len / 512) as u64,
-            st_atime: inner.atime,
-            st_mtime: inner.mtime,
-            st_ctime: inner.ctime,
-            unused: 0,
-        })
+/// A device node made by mknod, which only holds the device number. Opening it
+/// opens the device, see `devfs::open_device`.
+pub struct SimpleDevInode {
+    meta: InodeMeta,
+}
+
+impl SimpleDevInode {
+    pub fn new(mode: InodeMode, super_block: Arc<dyn SuperBlock>, dev_id: DevId) -> Arc<Self> {
+        debug_assert!(matches!(
+            mode.to_type(),
+            InodeType::CharDevice | InodeType::BlockDevice
+        ));
+        let mut meta = InodeMeta::new(mode, super_block, 0);
+        meta.dev_id = Some(dev_id);
+        Arc::new(Self { meta })
+    }
+}
+
+impl Inode for SimpleDevInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
     }
 }
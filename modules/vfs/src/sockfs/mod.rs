@@ -7,6 +7,9 @@ use vfs_core::*;
 use crate::simplefs::{dentry::SimpleDentry, inode::SimpleDirInode};
 
 /// 参考https://zhuanlan.zhihu.com/p/497849394 【Linux内核 | socket底层的来龙去脉】
+/// Magic number reported by statfs, as in linux.
+const SOCKFS_MAGIC: i64 = 0x534f434b;
+
 pub struct SockFsType {
     meta: FileSystemTypeMeta,
 }
@@ -19,6 +22,8 @@ impl SockFsType {
     }
 }
 
+vfs_core::register_fs!(SockFsType::new);
+
 impl FileSystemType for SockFsType {
     fn meta(&self) -> &FileSystemTypeMeta {
         &self.meta
@@ -31,50 +36,16 @@ impl FileSystemType for SockFsType {
         _flags: MountFlags,
         dev: Option<Arc<dyn BlockDevice>>,
     ) -> SysResult<Arc<dyn Dentry>> {
-        let sb = SockSuperBlock::new(dev, self.clone());
-        let mount_dentry = SimpleDentry::new(name, sb.clone(), parent.clone());
+        let sb = MemSuperBlock::new(dev, self.clone(), SOCKFS_MAGIC);
+        let mount_dentry = SimpleDentry::new(name, sb.clone(), parent);
         // SockFs的第一个Inode是DIR类型的
         let mount_inode = SimpleDirInode::new(InodeMode::DIR, sb.clone(), 0);
-        mount_dentry.set_inode(mount_inode.clone());
-        if let Some(parent) = parent {
-            parent.insert(mount_dentry.clone());
-        }
-        self.insert_sb(&mount_dentry.path(), sb);
-        Ok(mount_dentry)
-    }
-
-    fn kill_sb(&self, _sb: Arc<dyn SuperBlock>) -> SysResult<()> {
-        todo!()
-    }
-}
-
-pub struct SockSuperBlock {
-    meta: SuperBlockMeta,
-}
-
-impl SockSuperBlock {
-    pub fn new(
-        device: Option<Arc<dyn BlockDevice>>,
-        fs_type: Arc<dyn FileSystemType>,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            meta: SuperBlockMeta::new(device, fs_type),
-        })
-    }
-}
-
-impl SuperBlock for SockSuperBlock {
-    fn meta(&self) -> &SuperBlockMeta {
-        &self.meta
-    }
-
-    fn stat_fs(&self) -> SysResult<StatFs> {
-        // 应该是没有这个方法的？因为不涉及磁盘存储？
-        todo!()
+        Ok(self.mount_root(sb, mount_dentry, mount_inode))
     }
 
-    fn sync_fs(&self, _wait: isize) -> SysResult<()> {
-        todo!()
+    fn kill_sb(&self, sb: Arc<dyn SuperBlock>) -> SysResult<()> {
+        self.remove_sb(&sb);
+        Ok(())
     }
 }
 
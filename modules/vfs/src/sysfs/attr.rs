@@ -0,0 +1,139 @@
+//! Attribute files of sysfs, whose content is generated on open. A writable
+//! attribute passes what is written to its store. The knobs of procfs are
+//! attributes too.
+
+use alloc::{boxed::Box, string::String, sync::Arc};
+use core::cmp;
+
+use async_trait::async_trait;
+use async_utils::yield_now;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{
+    Dentry, DentryMeta, DirEntry, File, FileMeta, Inode, InodeMeta, InodeMode, SuperBlock,
+};
+
+/// Generates the content of an attribute.
+pub type AttrShow = Box<dyn Fn() -> String + Send + Sync>;
+
+/// Applies what is written to an attribute, with the trailing newline removed.
+pub type AttrStore = Box<dyn Fn(&str) -> SysResult<()> + Send + Sync>;
+
+pub struct AttrDentry {
+    meta: DentryMeta,
+    show: AttrShow,
+    store: Option<AttrStore>,
+}
+
+impl AttrDentry {
+    /// Create the read-only attribute `name` in the directory `parent`.
+    pub fn create(parent: &Arc<dyn Dentry>, name: &str, show: AttrShow) {
+        Self::create_with_store(parent, name, show, None)
+    }
+
+    /// Create the attribute `name` in the directory `parent`, writable if it
+    /// has a `store`.
+    pub fn create_with_store(
+        parent: &Arc<dyn Dentry>,
+        name: &str,
+        show: AttrShow,
+        store: Option<AttrStore>,
+    ) {
+        let writable = store.is_some();
+        let dentry = Arc::new(Self {
+            meta: DentryMeta::new(name, parent.super_block(), Some(parent.clone())),
+            show,
+            store,
+        });
+        dentry.set_inode(AttrInode::new(parent.super_block(), writable));
+        parent.insert(dentry);
+    }
+}
+
+impl Dentry for AttrDentry {
+    fn meta(&self) -> &DentryMeta {
+        &self.meta
+    }
+
+    /// The content is generated on open, so that reads in pieces see one
+    /// snapshot.
+    fn base_open(self: Arc<Self>) -> SysResult<Arc<dyn File>> {
+        Ok(Arc::new(AttrFile {
+            meta: FileMeta::new(self.clone(), self.inode()?),
+            content: (self.show)(),
+            attr: self.clone(),
+        }))
+    }
+
+    fn base_lookup(self: Arc<Self>, _name: &str) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_create(self: Arc<Self>, _name: &str, _mode: InodeMode) -> SysResult<Arc<dyn Dentry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_unlink(self: Arc<Self>, _name: &str) -> SysResult<()> {
+        Err(SysError::ENOTDIR)
+    }
+}
+
+pub struct AttrInode {
+    meta: InodeMeta,
+}
+
+impl AttrInode {
+    pub fn new(super_block: Arc<dyn SuperBlock>, writable: bool) -> Arc<Self> {
+        let mut mode =
+            InodeMode::FILE | InodeMode::OWNER_READ | InodeMode::GROUP_READ | InodeMode::OTHER_READ;
+        if writable {
+            mode |= InodeMode::OWNER_WRITE;
+        }
+        // like linux, the size of an attribute is reported as a page
+        Arc::new(Self {
+            meta: InodeMeta::new(mode, super_block, 4096),
+        })
+    }
+}
+
+impl Inode for AttrInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+pub struct AttrFile {
+    meta: FileMeta,
+    content: String,
+    attr: Arc<AttrDentry>,
+}
+
+#[async_trait]
+impl File for AttrFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    async fn base_read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        if offset >= self.content.len() {
+            return Ok(0);
+        }
+        let len = cmp::min(self.content.len() - offset, buf.len());
+        buf[..len].copy_from_slice(&self.content.as_bytes()[offset..offset + len]);
+        Ok(len)
+    }
+
+    /// The whole write is stored at once, whatever the offset.
+    async fn base_write_at(&self, _offset: usize, buf: &[u8]) -> SyscallResult {
+        let store = self.attr.store.as_ref().ok_or(SysError::EACCES)?;
+        let value = core::str::from_utf8(buf).map_err(|_| SysError::EINVAL)?;
+        store(value.strip_suffix('\n').unwrap_or(value))?;
+        // A store may take effect once the caller is back in the executor, e.g.
+        // taking the local hart offline.
+        yield_now().await;
+        Ok(buf.len())
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+}
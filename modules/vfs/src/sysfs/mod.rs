@@ -0,0 +1,143 @@
+//! A minimal sysfs, holding the topology of the harts that runtimes like the
+//! ones of OpenMP read at `/sys/devices/system/cpu`. Harts are taken offline
+//! and back online by writing 0 or 1 to their `online` attribute.
+
+pub(crate) mod attr;
+
+use alloc::{boxed::Box, format, string::String, sync::Arc, vec::Vec};
+
+use config::board;
+use crate_interface::call_interface;
+use device_core::BlockDevice;
+use systype::{SysError, SysResult};
+use vfs_core::{
+    Dentry, FileSystemType, FileSystemTypeMeta, InodeMode, MemSuperBlock, MountFlags, SuperBlock,
+};
+
+use self::attr::AttrDentry;
+use crate::simplefs::{dentry::SimpleDentry, inode::SimpleDirInode};
+
+#[crate_interface::def_interface]
+pub trait KernelHartIf {
+    /// Whether the hart runs tasks, i.e. it is not offline.
+    fn is_hart_online(hart_id: usize) -> bool;
+
+    /// Take the hart offline or bring it back online.
+    fn set_hart_online(hart_id: usize, online: bool) -> SysResult<()>;
+}
+
+/// Magic number reported by statfs, as in linux.
+const SYSFS_MAGIC: i64 = 0x62656572;
+
+/// Harts as a cpu list of linux, e.g. `0-1,3`.
+fn cpu_list(harts: impl Iterator<Item = usize>) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for hart in harts {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == hart => *last = hart,
+            _ => ranges.push((hart, hart)),
+        }
+    }
+    let ranges: Vec<String> = ranges
+        .into_iter()
+        .map(|(first, last)| {
+            if first == last {
+                format!("{first}")
+            } else {
+                format!("{first}-{last}")
+            }
+        })
+        .collect();
+    format!("{}\n", ranges.join(","))
+}
+
+fn is_hart_online(hart_id: usize) -> bool {
+    call_interface!(KernelHartIf::is_hart_online(hart_id))
+}
+
+/// Create `/devices/system/cpu`, with a `cpuN` directory for each hart. All
+/// harts are in the same package and each is a core of its own.
+fn init_cpu(root_dentry: &Arc<dyn Dentry>) -> SysResult<()> {
+    let cpu_dentry = root_dentry
+        .create("devices", InodeMode::DIR)?
+        .create("system", InodeMode::DIR)?
+        .create("cpu", InodeMode::DIR)?;
+    for name in ["possible", "present"] {
+        AttrDentry::create(&cpu_dentry, name, Box::new(|| cpu_list(0..board::harts())));
+    }
+    AttrDentry::create(
+        &cpu_dentry,
+        "online",
+        Box::new(|| cpu_list((0..board::harts()).filter(|&hart| is_hart_online(hart)))),
+    );
+    for hart in 0..board::harts() {
+        let hart_dentry = cpu_dentry.create(&format!("cpu{hart}"), InodeMode::DIR)?;
+        AttrDentry::create_with_store(
+            &hart_dentry,
+            "online",
+            Box::new(move || format!("{}\n", is_hart_online(hart) as u8)),
+            Some(Box::new(move |value| {
+                let online = match value {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(SysError::EINVAL),
+                };
+                call_interface!(KernelHartIf::set_hart_online(hart, online))
+            })),
+        );
+        let topology_dentry = hart_dentry.create("topology", InodeMode::DIR)?;
+        AttrDentry::create(
+            &topology_dentry,
+            "core_id",
+            Box::new(move || format!("{hart}\n")),
+        );
+        AttrDentry::create(
+            &topology_dentry,
+            "physical_package_id",
+            Box::new(|| String::from("0\n")),
+        );
+    }
+    Ok(())
+}
+
+pub fn init_sysfs(root_dentry: Arc<dyn Dentry>) -> SysResult<()> {
+    init_cpu(&root_dentry)
+}
+
+pub struct SysFsType {
+    meta: FileSystemTypeMeta,
+}
+
+impl SysFsType {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            meta: FileSystemTypeMeta::new("sysfs"),
+        })
+    }
+}
+
+vfs_core::register_fs!(SysFsType::new);
+
+impl FileSystemType for SysFsType {
+    fn meta(&self) -> &FileSystemTypeMeta {
+        &self.meta
+    }
+
+    fn base_mount(
+        self: Arc<Self>,
+        name: &str,
+        parent: Option<Arc<dyn Dentry>>,
+        _flags: MountFlags,
+        dev: Option<Arc<dyn BlockDevice>>,
+    ) -> SysResult<Arc<dyn Dentry>> {
+        let sb = MemSuperBlock::new(dev, self.clone(), SYSFS_MAGIC);
+        let mount_dentry = SimpleDentry::new(name, sb.clone(), parent);
+        let mount_inode = SimpleDirInode::new(InodeMode::DIR, sb.clone(), 0);
+        Ok(self.mount_root(sb, mount_dentry, mount_inode))
+    }
+
+    fn kill_sb(&self, sb: Arc<dyn SuperBlock>) -> SysResult<()> {
+        self.remove_sb(&sb);
+        Ok(())
+    }
+}
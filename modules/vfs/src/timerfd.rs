@@ -0,0 +1,250 @@
+//! Timer files, see timerfd_create(2). A timer file counts the expirations of
+//! a timer, a read takes the count, so that event loops can wait for timers
+//! like for other files.
+
+use alloc::{
+    boxed::Box,
+    collections::VecDeque,
+    sync::{Arc, Weak},
+};
+use core::{
+    future::poll_fn,
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use arch::time::get_time_duration;
+use async_trait::async_trait;
+use async_utils::get_waker;
+use sync::mutex::SpinNoIrqLock;
+use systype::{SysError, SysResult, SyscallResult};
+use time::{timespec::ITimerSpec, CLOCK_DEVIATION, CLOCK_MONOTONIC, CLOCK_REALTIME};
+use timer::{Timer, TimerEvent, TIMER_MANAGER};
+use vfs_core::{arc_zero, File, FileMeta, Inode, InodeMeta, InodeMode, OpenFlags, PollEvents};
+
+bitflags::bitflags! {
+    /// Flags of timerfd_create(2).
+    #[derive(Clone, Copy, Debug)]
+    pub struct TimerFdFlags: u32 {
+        const TFD_NONBLOCK = OpenFlags::O_NONBLOCK.bits();
+        const TFD_CLOEXEC = OpenFlags::O_CLOEXEC.bits();
+    }
+
+    /// Flags of timerfd_settime(2).
+    #[derive(Clone, Copy, Debug)]
+    pub struct TimerFdSetFlags: u32 {
+        /// The initial expiration is an absolute time of the clock.
+        const TFD_TIMER_ABSTIME = 1 << 0;
+        /// Accepted but not acted on, the realtime clock is never set back.
+        const TFD_TIMER_CANCEL_ON_SET = 1 << 1;
+    }
+}
+
+pub struct TimerFdInode {
+    meta: InodeMeta,
+}
+
+impl TimerFdInode {
+    fn new() -> Arc<Self> {
+        let mode = InodeMode::OWNER_READ | InodeMode::OWNER_WRITE;
+        Arc::new(Self {
+            meta: InodeMeta::new(mode, Arc::<usize>::new_uninit(), 0),
+        })
+    }
+}
+
+impl Inode for TimerFdInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+}
+
+struct TimerFdInner {
+    /// Next expiration on the monotonic time since boot, zero if disarmed.
+    expire: Duration,
+    interval: Duration,
+    /// Expirations since the last read.
+    count: u64,
+    /// Bumped on every arming, so that the timer of an earlier one is ignored.
+    id: usize,
+    // NOTE: all wakers are woken on every expiration, see `PipeInodeInner`
+    /// Tasks waiting for an expiration.
+    wakers: VecDeque<Waker>,
+}
+
+impl TimerFdInner {
+    fn wait(&mut self, waker: &Waker) {
+        if !self.wakers.iter().any(|w| w.will_wake(waker)) {
+            self.wakers.push_back(waker.clone());
+        }
+    }
+
+    fn wake_all(&mut self) {
+        while let Some(waker) = self.wakers.pop_front() {
+            waker.wake();
+        }
+    }
+}
+
+/// The timer of an arming of a timer file.
+struct TimerFdEvent {
+    inner: Weak<SpinNoIrqLock<TimerFdInner>>,
+    id: usize,
+}
+
+impl TimerEvent for TimerFdEvent {
+    fn callback(self: Box<Self>) -> Option<Timer> {
+        let inner = self.inner.upgrade()?;
+        let mut inner = inner.lock();
+        if inner.id != self.id {
+            // disarmed or armed again since
+            return None;
+        }
+        let now = get_time_duration();
+        let mut count = 1;
+        if inner.interval.is_zero() {
+            inner.expire = Duration::ZERO;
+        } else {
+            // the expirations missed, e.g. while interrupts were off, are counted
+            let late = now.saturating_sub(inner.expire);
+            let missed = (late.as_nanos() / inner.interval.as_nanos()) as u32;
+            count += missed as u64;
+            inner.expire += inner.interval * (missed + 1);
+        }
+        inner.count += count;
+        inner.wake_all();
+        let expire = inner.expire;
+        drop(inner);
+        (!expire.is_zero()).then(|| Timer::new(expire, self))
+    }
+}
+
+pub struct TimerFdFile {
+    meta: FileMeta,
+    clockid: usize,
+    inner: Arc<SpinNoIrqLock<TimerFdInner>>,
+}
+
+impl TimerFdFile {
+    /// Fails with `EINVAL` for a clock other than `CLOCK_REALTIME` and
+    /// `CLOCK_MONOTONIC`.
+    pub fn new(clockid: usize, flags: TimerFdFlags) -> SysResult<Arc<Self>> {
+        if clockid != CLOCK_REALTIME && clockid != CLOCK_MONOTONIC {
+            return Err(SysError::EINVAL);
+        }
+        let file = Arc::new(Self {
+            meta: FileMeta::new(arc_zero(), TimerFdInode::new()),
+            clockid,
+            inner: Arc::new(SpinNoIrqLock::new(TimerFdInner {
+                expire: Duration::ZERO,
+                interval: Duration::ZERO,
+                count: 0,
+                id: 0,
+                wakers: VecDeque::new(),
+            })),
+        });
+        let mut open_flags = OpenFlags::O_RDWR;
+        if flags.contains(TimerFdFlags::TFD_NONBLOCK) {
+            open_flags |= OpenFlags::O_NONBLOCK;
+        }
+        file.set_flags(open_flags);
+        Ok(file)
+    }
+
+    /// Time until the next expiration and the interval.
+    pub fn get_time(&self) -> ITimerSpec {
+        let inner = self.inner.lock();
+        let value = match inner.expire.is_zero() {
+            true => Duration::ZERO,
+            // a timer about to fire reads as 1ns left, 0 would mean disarmed
+            false => inner
+                .expire
+                .saturating_sub(get_time_duration())
+                .max(Duration::from_nanos(1)),
+        };
+        ITimerSpec {
+            it_interval: inner.interval.into(),
+            it_value: value.into(),
+        }
+    }
+
+    /// Arm the timer, or disarm it if `new` has a zero value, returning the
+    /// setting before. Pending expirations are dropped.
+    pub fn set_time(&self, flags: TimerFdSetFlags, new: ITimerSpec) -> SysResult<ITimerSpec> {
+        if !new.is_valid() {
+            return Err(SysError::EINVAL);
+        }
+        let old = self.get_time();
+        let value: Duration = new.it_value.into();
+        let mut inner = self.inner.lock();
+        inner.id += 1;
+        inner.count = 0;
+        inner.interval = new.it_interval.into();
+        inner.expire = if value.is_zero() {
+            Duration::ZERO
+        } else if flags.contains(TimerFdSetFlags::TFD_TIMER_ABSTIME) {
+            let deviation = unsafe { CLOCK_DEVIATION }[self.clockid];
+            value.saturating_sub(deviation).max(Duration::from_nanos(1))
+        } else {
+            get_time_duration() + value
+        };
+        let (expire, id) = (inner.expire, inner.id);
+        // NOTE: the timer manager calls back with its lock held, which then
+        // takes ours, so ours is released before adding the timer
+        drop(inner);
+        if !expire.is_zero() {
+            let event = TimerFdEvent {
+                inner: Arc::downgrade(&self.inner),
+                id,
+            };
+            TIMER_MANAGER.add_timer(Timer::new(expire, Box::new(event)));
+        }
+        Ok(old)
+    }
+}
+
+#[async_trait]
+impl File for TimerFdFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    /// Take the count of expirations, waiting while it is zero.
+    async fn base_read_at(&self, _offset: usize, buf: &mut [u8]) -> SyscallResult {
+        if buf.len() < 8 {
+            return Err(SysError::EINVAL);
+        }
+        let nonblock = self.flags().contains(OpenFlags::O_NONBLOCK);
+        let count = poll_fn(|cx| {
+            let mut inner = self.inner.lock();
+            if inner.count == 0 {
+                if nonblock {
+                    return Poll::Ready(Err(SysError::EAGAIN));
+                }
+                inner.wait(cx.waker());
+                return Poll::Pending;
+            }
+            Poll::Ready(Ok(core::mem::take(&mut inner.count)))
+        })
+        .await?;
+        buf[..8].copy_from_slice(&count.to_ne_bytes());
+        Ok(8)
+    }
+
+    async fn base_write_at(&self, _offset: usize, _buf: &[u8]) -> SyscallResult {
+        Err(SysError::EINVAL)
+    }
+
+    async fn base_poll(&self, events: PollEvents) -> PollEvents {
+        let waker = get_waker().await;
+        let mut inner = self.inner.lock();
+        let mut res = PollEvents::empty();
+        if events.contains(PollEvents::IN) && inner.count > 0 {
+            res |= PollEvents::IN;
+        }
+        if res.is_empty() {
+            inner.wait(&waker);
+        }
+        res
+    }
+}
@@ -0,0 +1,97 @@
+use alloc::sync::Arc;
+
+use device_core::DevId;
+use systype::{SysError, SysResult};
+use vfs_core::{
+    CachedDentry, Dentry, DentryOps, DirFile, File, Inode, InodeMode, InodeType, SuperBlock,
+};
+
+use super::{
+    file::TmpFile,
+    inode::{touch_mtime, TmpDirInode, TmpFileInode},
+};
+use crate::{
+    devfs::open_device,
+    pipefs::PipeInode,
+    simplefs::{
+        file::SimpleLinkFile,
+        inode::{SimpleDevInode, SimpleLinkInode},
+    },
+};
+
+pub struct TmpOps;
+
+pub type TmpDentry = CachedDentry<TmpOps>;
+
+/// The directory holding `dentry` changed.
+fn touch_parent(dentry: &Arc<dyn Dentry>) {
+    if let Some(inode) = dentry.parent().and_then(|parent| parent.inode().ok()) {
+        touch_mtime(inode.as_ref());
+    }
+}
+
+impl DentryOps for TmpOps {
+    fn open(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> SysResult<Arc<dyn File>> {
+        match inode.itype() {
+            InodeType::Dir => {
+                let inode = inode
+                    .downcast_arc::<TmpDirInode>()
+                    .map_err(|_| SysError::EIO)?;
+                Ok(DirFile::new(dentry, inode))
+            }
+            InodeType::File | InodeType::Socket => Ok(TmpFile::new(dentry, inode)),
+            InodeType::SymLink => Ok(SimpleLinkFile::new(dentry, inode)),
+            // a FIFO is opened through `open_fifo`, which needs the open flags
+            InodeType::Fifo => Err(SysError::ENXIO),
+            InodeType::CharDevice | InodeType::BlockDevice => open_device(dentry, &inode),
+            _ => unreachable!(),
+        }
+    }
+
+    fn new_inode(sb: Arc<dyn SuperBlock>, mode: InodeMode) -> SysResult<Arc<dyn Inode>> {
+        match mode.to_type() {
+            InodeType::Dir => Ok(TmpDirInode::new(mode, sb)),
+            // a socket inode only marks the path a unix socket is bound to
+            InodeType::File | InodeType::Socket => Ok(TmpFileInode::new(mode, sb)),
+            InodeType::Fifo => Ok(PipeInode::new_fifo(mode, sb)),
+            _ => Err(SysError::EPERM),
+        }
+    }
+
+    fn new_device(
+        sb: Arc<dyn SuperBlock>,
+        mode: InodeMode,
+        dev_id: DevId,
+    ) -> SysResult<Arc<dyn Inode>> {
+        Ok(SimpleDevInode::new(mode, sb, dev_id))
+    }
+
+    fn new_symlink(sb: Arc<dyn SuperBlock>, target: &str) -> SysResult<Arc<dyn Inode>> {
+        Ok(SimpleLinkInode::new(target, sb))
+    }
+
+    /// A directory must be empty to be removed.
+    fn unlink(child: &Arc<dyn Dentry>) -> SysResult<()> {
+        if child.inode()?.itype().is_dir()
+            && child
+                .children()
+                .values()
+                .any(|grandchild| !grandchild.is_negetive())
+        {
+            return Err(SysError::ENOTEMPTY);
+        }
+        touch_parent(child);
+        Ok(())
+    }
+
+    fn rename(old: &Arc<dyn Dentry>, new: &Arc<dyn Dentry>) -> SysResult<()> {
+        touch_parent(old);
+        touch_parent(new);
+        Ok(())
+    }
+
+    fn link(_old: &Arc<dyn Dentry>, new: &Arc<dyn Dentry>) -> SysResult<()> {
+        touch_parent(new);
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use alloc::{boxed::Box, sync::Arc};
+
+use async_trait::async_trait;
+use config::mm::{align_offset_to_page, PAGE_SIZE};
+use page::Page;
+use systype::{SysError, SysResult, SyscallResult};
+use vfs_core::{Dentry, DirEntry, File, FileMeta, Inode};
+
+use super::inode::{touch_atime, touch_mtime};
+
+pub struct TmpFile {
+    meta: FileMeta,
+}
+
+impl TmpFile {
+    pub fn new(dentry: Arc<dyn Dentry>, inode: Arc<dyn Inode>) -> Arc<Self> {
+        Arc::new(Self {
+            meta: FileMeta::new(dentry, inode),
+        })
+    }
+
+    /// Get the page at `offset_aligned`, filling a hole with a zeroed page.
+    fn page_or_zero(&self, offset_aligned: usize) -> Arc<Page> {
+        let inode = self.inode();
+        let page_cache = inode.page_cache().unwrap();
+        if let Some(page) = page_cache.get_page(offset_aligned) {
+            return page;
+        }
+        let page = Page::new();
+        page.fill_zero();
+        page_cache.insert_page(offset_aligned, page.clone());
+        page
+    }
+}
+
+#[async_trait]
+impl File for TmpFile {
+    fn meta(&self) -> &FileMeta {
+        &self.meta
+    }
+
+    fn base_read_dir(&self) -> SysResult<Option<DirEntry>> {
+        Err(SysError::ENOTDIR)
+    }
+
+    fn base_load_dir(&self) -> SysResult<()> {
+        Err(SysError::ENOTDIR)
+    }
+
+    /// A hole reads as zero without taking memory.
+    async fn read_at(&self, offset: usize, buf: &mut [u8]) -> SyscallResult {
+        let inode = self.inode();
+        let page_cache = inode.page_cache().unwrap();
+        let size = self.size();
+        let end = size.min(offset.saturating_add(buf.len()));
+        let mut offset_it = offset;
+        while offset_it < end {
+            let (offset_aligned, offset_in_page) = align_offset_to_page(offset_it);
+            let len = (PAGE_SIZE - offset_in_page).min(end - offset_it);
+            let dst = &mut buf[offset_it - offset..offset_it - offset + len];
+            match page_cache.get_page(offset_aligned) {
+                Some(page) => dst
+                    .copy_from_slice(page.bytes_array_range(offset_in_page..offset_in_page + len)),
+                None => dst.fill(0),
+            }
+            offset_it += len;
+        }
+        touch_atime(inode.as_ref());
+        Ok(end.saturating_sub(offset))
+    }
+
+    /// Writing beyond the end of file leaves a hole between.
+    async fn write_at(&self, offset: usize, buf: &[u8]) -> SyscallResult {
+        let inode = self.inode();
+        let mut offset_it = offset;
+        let mut buf_it = buf;
+        while !buf_it.is_empty() {
+            let (offset_aligned, offset_in_page) = align_offset_to_page(offset_it);
+            let len = buf_it.len().min(PAGE_SIZE - offset_in_page);
+            self.page_or_zero(offset_aligned)
+                .bytes_array_range(offset_in_page..offset_in_page + len)
+                .copy_from_slice(&buf_it[..len]);
+            offset_it += len;
+            buf_it = &buf_it[len..];
+        }
+        if offset_it > self.size() {
+            inode.set_size(offset_it);
+        }
+        touch_mtime(inode.as_ref());
+        Ok(buf.len())
+    }
+
+    /// Pages of memory maps are shared with the file, a hole below the size
+    /// gets a zeroed page.
+    async fn get_page_at(&self, offset_aligned: usize) -> SysResult<Option<Arc<Page>>> {
+        if offset_aligned >= self.size() {
+            return Ok(None);
+        }
+        Ok(Some(self.page_or_zero(offset_aligned)))
+    }
+}
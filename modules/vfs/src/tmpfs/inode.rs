@@ -0,0 +1,98 @@
+use alloc::sync::Arc;
+
+use arch::time::get_time_duration;
+use page::PageCache;
+use systype::SysResult;
+use time::timespec::TimeSpec;
+use vfs_core::{DirOps, Inode, InodeMeta, InodeMode, InodeState, SuperBlock};
+
+fn now() -> TimeSpec {
+    TimeSpec::from(get_time_duration())
+}
+
+/// Set all timestamps of a new inode to now.
+fn new_meta(mode: InodeMode, super_block: Arc<dyn SuperBlock>) -> InodeMeta {
+    let meta = InodeMeta::new(mode, super_block, 0);
+    let now = now();
+    let mut inner = meta.inner.lock();
+    inner.atime = now;
+    inner.mtime = now;
+    inner.ctime = now;
+    drop(inner);
+    meta
+}
+
+/// Update the access time of `inode`.
+pub fn touch_atime(inode: &dyn Inode) {
+    inode.meta().inner.lock().atime = now();
+}
+
+/// Update the modification and change times of `inode`, after its content
+/// changed.
+pub fn touch_mtime(inode: &dyn Inode) {
+    let now = now();
+    let mut inner = inode.meta().inner.lock();
+    inner.mtime = now;
+    inner.ctime = now;
+}
+
+/// A regular file, whose data lives only in its page cache. The pages are
+/// freed with the inode, i.e. once it is unlinked and no longer open.
+///
+/// A page missing below the size is a hole, which reads as zero.
+pub struct TmpFileInode {
+    meta: InodeMeta,
+}
+
+impl TmpFileInode {
+    pub fn new(mode: InodeMode, super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        debug_assert!(mode.to_type().is_file() || mode.to_type().is_socket());
+        let mut meta = new_meta(mode, super_block);
+        meta.page_cache = Some(PageCache::new());
+        // NOTE: there is nothing to write back, see `InodeMeta::drop`
+        meta.inner.lock().state = InodeState::Removed;
+        Arc::new(Self { meta })
+    }
+}
+
+impl Inode for TmpFileInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+
+    /// Pages beyond `len` are dropped by `Inode::truncate`, and growing leaves
+    /// a hole.
+    fn base_truncate(&self, _len: usize) -> SysResult<()> {
+        touch_mtime(self);
+        Ok(())
+    }
+
+    fn base_set_mode(&self, _mode: InodeMode) -> SysResult<()> {
+        Ok(())
+    }
+}
+
+pub struct TmpDirInode {
+    meta: InodeMeta,
+}
+
+impl TmpDirInode {
+    pub fn new(mode: InodeMode, super_block: Arc<dyn SuperBlock>) -> Arc<Self> {
+        debug_assert!(mode.to_type().is_dir());
+        Arc::new(Self {
+            meta: new_meta(mode, super_block),
+        })
+    }
+}
+
+impl Inode for TmpDirInode {
+    fn meta(&self) -> &InodeMeta {
+        &self.meta
+    }
+
+    fn base_set_mode(&self, _mode: InodeMode) -> SysResult<()> {
+        Ok(())
+    }
+}
+
+impl DirOps for TmpDirInode {}
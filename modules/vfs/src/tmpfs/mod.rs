@@ -1,3 +1,10 @@
+//! A file system in memory, whose dentry cache is the directory tree and
+//! whose files keep their data in the page cache.
+
+mod dentry;
+mod file;
+mod inode;
+
 use alloc::sync::Arc;
 
 use device_core::BlockDevice;
@@ -7,7 +14,10 @@ use vfs_core::{
     SuperBlockMeta,
 };
 
-use crate::simplefs::{dentry::SimpleDentry, inode::SimpleDirInode};
+use self::{dentry::TmpDentry, inode::TmpDirInode};
+
+/// Magic number reported by statfs, as in linux.
+const TMPFS_MAGIC: i64 = 0x01021994;
 
 pub struct TmpFsType {
     meta: FileSystemTypeMeta,
@@ -21,6 +31,8 @@ impl TmpFsType {
     }
 }
 
+vfs_core::register_fs!(TmpFsType::new);
+
 impl FileSystemType for TmpFsType {
     fn meta(&self) -> &FileSystemTypeMeta {
         &self.meta
@@ -34,18 +46,14 @@ impl FileSystemType for TmpFsType {
         dev: Option<Arc<dyn BlockDevice>>,
     ) -> SysResult<Arc<dyn Dentry>> {
         let sb = TmpSuperBlock::new(dev, self.clone());
-        let mount_dentry = SimpleDentry::new(name, sb.clone(), parent.clone());
-        let mount_inode = SimpleDirInode::new(InodeMode::DIR, sb.clone(), 0);
-        mount_dentry.set_inode(mount_inode.clone());
-        if let Some(parent) = parent {
-            parent.insert(mount_dentry.clone());
-        }
-        self.insert_sb(&mount_dentry.path(), sb);
-        Ok(mount_dentry)
+        let mount_dentry = TmpDentry::new(name, sb.clone(), parent);
+        let mount_inode = TmpDirInode::new(InodeMode::DIR, sb.clone());
+        Ok(self.mount_root(sb, mount_dentry, mount_inode))
     }
 
-    fn kill_sb(&self, _sb: Arc<dyn SuperBlock>) -> SysResult<()> {
-        todo!()
+    fn kill_sb(&self, sb: Arc<dyn SuperBlock>) -> SysResult<()> {
+        self.remove_sb(&sb);
+        Ok(())
     }
 }
 
@@ -69,11 +77,21 @@ impl SuperBlock for TmpSuperBlock {
         &self.meta
     }
 
+    /// Like the default size of tmpfs in linux, the file system may take half
+    /// of the memory, of which what is not allocated yet is free.
     fn stat_fs(&self) -> SysResult<StatFs> {
-        todo!()
+        let (total, free) = memory::frame_stat();
+        let blocks = total / 2;
+        let free = free.min(blocks);
+        Ok(StatFs {
+            f_blocks: blocks as u64,
+            f_bfree: free as u64,
+            f_bavail: free as u64,
+            ..StatFs::in_memory(TMPFS_MAGIC)
+        })
     }
 
     fn sync_fs(&self, _wait: isize) -> SysResult<()> {
-        todo!()
+        Ok(())
     }
 }
@@ -0,0 +1,15 @@
+#!/bin/sh
+# Make $1/crash_test_fat.img, an empty FAT image the size of the ramdisk
+# /dev/ram0, whose free clusters are filled with 0xee. crash_consistency_test
+# copies it to the ramdisk, so a file referring to a cluster never written
+# shows up as 0xee bytes.
+set -e
+root=$1
+img=$root/crash_test_fat.img
+mnt=$(mktemp -d)
+mkfs.vfat -C $img 1024 > /dev/null
+mount -o loop $img $mnt
+dd if=/dev/zero bs=1K 2>/dev/null | tr '\0' '\356' > $mnt/fill || true
+rm $mnt/fill
+umount $mnt
+rmdir $mnt
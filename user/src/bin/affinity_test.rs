@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EINVAL: isize = 22;
+const ESRCH: isize = 3;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin affinity test");
+    let mut online = 0;
+    assert!(sched_getaffinity(0, &mut online) == core::mem::size_of::<usize>() as isize);
+    assert!(online != 0);
+    let nharts = online.count_ones();
+    println!("{} harts online", nharts);
+
+    // empty mask or a hart beyond the hart count
+    assert!(sched_setaffinity(0, 0) == -EINVAL);
+    assert!(sched_setaffinity(0, 1 << nharts) == -EINVAL);
+    assert!(sched_setaffinity(99999, online) == -ESRCH);
+
+    // pin to the last hart, and keep running there after yielding
+    let last = 1 << (nharts - 1);
+    assert!(sched_setaffinity(0, last) == 0);
+    let mut mask = 0;
+    sched_getaffinity(getpid() as usize, &mut mask);
+    assert!(mask == last);
+    for _ in 0..10 {
+        yield_();
+        assert!(getcpu() == nharts as isize - 1);
+    }
+
+    // inherited by the child
+    let pid = fork();
+    if pid == 0 {
+        let mut mask = 0;
+        sched_getaffinity(0, &mut mask);
+        assert!(mask == last);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+
+    assert!(sched_setaffinity(0, online) == 0);
+    println!("affinity test pass.");
+    0
+}
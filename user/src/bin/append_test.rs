@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+/// The last writer writes each record in two iovecs with one `writev`.
+const WRITERS: usize = 3;
+const RECORDS: usize = 200;
+const RECORD_LEN: usize = 16;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin append test");
+    let path = "/tmp/append_test\0";
+    let fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    for w in 0..WRITERS {
+        if fork() == 0 {
+            // every writer opens its own file description
+            let fd = openat(path, OpenFlags::O_WRONLY | OpenFlags::O_APPEND);
+            assert!(fd >= 0);
+            let record = [b'a' + w as u8; RECORD_LEN];
+            let (head, tail) = record.split_at(RECORD_LEN / 2);
+            let iov = [
+                IoVec {
+                    base: head.as_ptr() as usize,
+                    len: head.len(),
+                },
+                IoVec {
+                    base: tail.as_ptr() as usize,
+                    len: tail.len(),
+                },
+            ];
+            for _ in 0..RECORDS {
+                let len = if w == WRITERS - 1 {
+                    writev(fd as usize, &iov)
+                } else {
+                    write(fd as usize, &record)
+                };
+                assert!(len == RECORD_LEN as isize);
+            }
+            exit(0);
+        }
+    }
+    for _ in 0..WRITERS {
+        let mut exit_code: i32 = 0;
+        assert!(wait(&mut exit_code) > 0 && exit_code == 0);
+    }
+
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    assert!(st.st_size as usize == WRITERS * RECORDS * RECORD_LEN);
+
+    // no record is torn by another writer
+    let fd = openat(path, OpenFlags::O_RDONLY) as usize;
+    let mut record = [0u8; RECORD_LEN];
+    let mut count = [0usize; WRITERS];
+    while read(fd, &mut record) == RECORD_LEN as isize {
+        assert!(record.iter().all(|&b| b == record[0]));
+        count[(record[0] - b'a') as usize] += 1;
+    }
+    assert!(count.iter().all(|&c| c == RECORDS));
+    println!("append test pass.");
+    0
+}
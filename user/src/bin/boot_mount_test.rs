@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{string::String, vec::Vec};
+
+use user_lib::*;
+
+fn read_all(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+/// Mount point and file system type of each line of the mount table.
+fn mount_table() -> Vec<(String, String)> {
+    read_all("/proc/mounts\0")
+        .lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            assert!(fields.len() == 6);
+            (String::from(fields[1]), String::from(fields[2]))
+        })
+        .collect()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin boot mount test");
+    let mounts = mount_table();
+    // the root comes first, and a mount after the one it is nested in
+    assert!(mounts[0].0 == "/");
+    let position = |path: &str, fs_type: &str| {
+        mounts
+            .iter()
+            .position(|(p, t)| p == path && t == fs_type)
+            .expect("mount is missing")
+    };
+    let dev = position("/dev", "devfs");
+    assert!(position("/dev/shm", "tmpfs") > dev);
+    position("/proc", "procfs");
+    position("/tmp", "tmpfs");
+
+    // the mount point of devpts is made even though it is not mounted
+    let mut st = Kstat::default();
+    assert!(stat("/dev/pts\0", &mut st) == 0);
+    assert!(st.st_mode & 0o170000 == 0o040000);
+    let fd = openat("/dev/tty\0", OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    // files can be made in the tmpfs at /dev/shm
+    let fd = openat(
+        "/dev/shm/boot_mount_test\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    close(fd as usize);
+    assert!(unlink("/dev/shm/boot_mount_test\0") == 0);
+
+    println!("boot mount test pass.");
+    0
+}
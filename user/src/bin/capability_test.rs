@@ -0,0 +1,112 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+
+fn caps() -> [CapUserData; 2] {
+    let mut hdr = CapUserHeader {
+        version: LINUX_CAPABILITY_VERSION_3,
+        pid: 0,
+    };
+    let mut data = [CapUserData::default(); 2];
+    assert!(capget(&mut hdr, Some(&mut data)) == 0);
+    data
+}
+
+fn set_caps(data: &[CapUserData; 2]) -> isize {
+    let mut hdr = CapUserHeader {
+        version: LINUX_CAPABILITY_VERSION_3,
+        pid: 0,
+    };
+    capset(&mut hdr, data)
+}
+
+fn drop_chown() {
+    let fd = openat(
+        "/tmp/capability_test\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    // root may give the file away while it has CAP_CHOWN
+    assert!(fchown(fd, 1000, u32::MAX) == 0);
+    assert!(fchown(fd, 0, u32::MAX) == 0);
+
+    let mut data = caps();
+    let chown = 1 << CAP_CHOWN;
+    data[0].effective &= !chown;
+    data[0].permitted &= !chown;
+    assert!(set_caps(&data) == 0);
+    let data = caps();
+    assert!(data[0].effective & chown == 0);
+    assert!(data[0].permitted & chown == 0);
+    assert!(data[0].effective & (1 << CAP_DAC_OVERRIDE) != 0);
+
+    // without it even root can not
+    assert!(getuid() == 0);
+    assert!(fchown(fd, 1000, u32::MAX) == -EPERM);
+    // keeping the owner needs nothing
+    assert!(fchown(fd, 0, u32::MAX) == 0);
+    assert!(fchown(fd, u32::MAX, u32::MAX) == 0);
+
+    // a dropped capability can not be raised again
+    let mut raised = data;
+    raised[0].effective |= chown;
+    raised[0].permitted |= chown;
+    assert!(set_caps(&raised) == -EPERM);
+    // nor can an effective one go beyond the permitted set
+    let mut raised = data;
+    raised[0].effective |= chown;
+    assert!(set_caps(&raised) == -EPERM);
+    close(fd);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin capability test");
+
+    // probing the version
+    let mut hdr = CapUserHeader::default();
+    assert!(capget(&mut hdr, None) == 0);
+    assert!(hdr.version == LINUX_CAPABILITY_VERSION_3);
+    let mut hdr = CapUserHeader::default();
+    let mut data = [CapUserData::default(); 2];
+    assert!(capget(&mut hdr, Some(&mut data)) == -EINVAL);
+    assert!(hdr.version == LINUX_CAPABILITY_VERSION_3);
+
+    // root starts with everything
+    let data = caps();
+    assert!(data[0].effective == u32::MAX && data[0].permitted == u32::MAX);
+    assert!(data[1].permitted != 0);
+
+    // capabilities are per process, the parent keeps its own
+    let pid = fork();
+    if pid == 0 {
+        drop_chown();
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+    let data = caps();
+    assert!(data[0].effective & (1 << CAP_CHOWN) != 0);
+    assert!(unlink("/tmp/capability_test\0") == 0);
+
+    // dropping root drops the capabilities
+    let pid = fork();
+    if pid == 0 {
+        assert!(setresuid(1000, 1000, 1000) == 0);
+        let data = caps();
+        assert!(data[0].effective == 0 && data[0].permitted == 0);
+        exit(0);
+    }
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+    println!("capability test passed");
+    0
+}
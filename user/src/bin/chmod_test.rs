@@ -0,0 +1,86 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+const EOPNOTSUPP: isize = 95;
+
+const AT_SYMLINK_NOFOLLOW: i32 = 0x100;
+const S_IFMT: u32 = 0o170000;
+const S_IFREG: u32 = 0o100000;
+const S_IFDIR: u32 = 0o040000;
+
+fn mode_of(path: &str) -> u32 {
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    st.st_mode
+}
+
+fn chmod_in(dir: &str) {
+    println!("chmod in {dir}");
+    let path = alloc::format!("{dir}/chmod_test\0");
+    let path = path.as_str();
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+
+    // the type is kept
+    assert!(chmod(path, 0o755) == 0);
+    assert!(mode_of(path) == S_IFREG | 0o755);
+    // seen through the open file as well
+    assert!(fchmod(fd, 0o640) == 0);
+    let mut st = Kstat::default();
+    assert!(fstat(fd, &mut st) == 0);
+    assert!(st.st_mode == S_IFREG | 0o640);
+    assert!(mode_of(path) == S_IFREG | 0o640);
+    // type bits passed in are ignored
+    assert!(chmod(path, S_IFDIR | 0o4711) == 0);
+    assert!(mode_of(path) == S_IFREG | 0o4711);
+
+    let pid = fork();
+    if pid == 0 {
+        // only the owner may change the mode
+        assert!(setuid(1000) == 0);
+        assert!(chmod(path, 0o777) == -EPERM);
+        assert!(fchmod(fd, 0o777) == -EPERM);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    assert!(mode_of(path) & !S_IFMT == 0o4711);
+
+    close(fd);
+    assert!(unlink(path) == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin chmod test");
+    chmod_in("/tmp");
+    chmod_in("");
+
+    // a directory
+    assert!(mkdir("/tmp/chmod_dir\0") == 0);
+    assert!(chmod("/tmp/chmod_dir\0", 0o700) == 0);
+    assert!(mode_of("/tmp/chmod_dir\0") == S_IFDIR | 0o700);
+
+    // a symbolic link is followed, and has no mode of its own
+    assert!(symlink("/tmp/chmod_dir\0", "/tmp/chmod_link\0") == 0);
+    assert!(chmod("/tmp/chmod_link\0", 0o750) == 0);
+    assert!(mode_of("/tmp/chmod_dir\0") == S_IFDIR | 0o750);
+    assert!(fchmodat2("/tmp/chmod_link\0", 0o700, AT_SYMLINK_NOFOLLOW) == -EOPNOTSUPP);
+    assert!(fchmodat2("/tmp/chmod_link\0", 0o700, 0x1) == -EINVAL);
+    assert!(unlink("/tmp/chmod_link\0") == 0);
+    assert!(rmdir("/tmp/chmod_dir\0") == 0);
+
+    // a generated file can not keep a mode
+    assert!(chmod("/proc/meminfo\0", 0o600) == -EPERM);
+
+    println!("chmod test pass.");
+    0
+}
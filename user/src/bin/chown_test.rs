@@ -0,0 +1,95 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const ENOTDIR: isize = 20;
+const EINVAL: isize = 22;
+
+fn owner_of(dirfd: isize, path: &str, flags: i32) -> (u32, u32) {
+    let mut st = Kstat::default();
+    assert!(fstatat(dirfd, path, &mut st, flags) == 0);
+    (st.st_uid, st.st_gid)
+}
+
+fn chown_in(dir: &str) {
+    println!("chown in {dir}");
+    let path = alloc::format!("{dir}/chown_test\0");
+    let path = path.as_str();
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+
+    assert!(owner_of(AT_FDCWD, path, 0) == (0, 0));
+    assert!(fchownat(AT_FDCWD, path, 1000, 100, 0) == 0);
+    assert!(owner_of(AT_FDCWD, path, 0) == (1000, 100));
+    // -1 leaves an id unchanged
+    assert!(fchown(fd, u32::MAX, 200) == 0);
+    assert!(owner_of(AT_FDCWD, path, 0) == (1000, 200));
+    assert!(fchownat(fd as isize, "\0", 2000, u32::MAX, AT_EMPTY_PATH) == 0);
+    let mut st = Kstat::default();
+    assert!(fstat(fd, &mut st) == 0);
+    assert!((st.st_uid, st.st_gid) == (2000, 200));
+    assert!(owner_of(fd as isize, "\0", AT_EMPTY_PATH) == (2000, 200));
+
+    close(fd);
+    assert!(unlink(path) == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin chown test");
+    chown_in("/tmp");
+    chown_in("");
+
+    // a symbolic link has an owner of its own
+    assert!(mkdir("/tmp/chown_dir\0") == 0);
+    assert!(symlink("/tmp/chown_dir\0", "/tmp/chown_link\0") == 0);
+    assert!(fchownat(AT_FDCWD, "/tmp/chown_link\0", 1000, 1000, 0) == 0);
+    assert!(owner_of(AT_FDCWD, "/tmp/chown_dir\0", 0) == (1000, 1000));
+    assert!(owner_of(AT_FDCWD, "/tmp/chown_link\0", AT_SYMLINK_NOFOLLOW) == (0, 0));
+    assert!(
+        fchownat(
+            AT_FDCWD,
+            "/tmp/chown_link\0",
+            3000,
+            3000,
+            AT_SYMLINK_NOFOLLOW
+        ) == 0
+    );
+    assert!(owner_of(AT_FDCWD, "/tmp/chown_link\0", AT_SYMLINK_NOFOLLOW) == (3000, 3000));
+    assert!(owner_of(AT_FDCWD, "/tmp/chown_dir\0", 0) == (1000, 1000));
+
+    // an empty path needs AT_EMPTY_PATH
+    assert!(fchownat(AT_FDCWD, "\0", 0, 0, 0) == -ENOENT);
+    assert!(fchownat(AT_FDCWD, "/tmp/chown_dir\0", 0, 0, 0x1) == -EINVAL);
+
+    // fchdir to an open directory
+    let mut cwd = [0u8; 64];
+    assert!(getcwd(&mut cwd) > 0);
+    let old = openat(".\0", OpenFlags::O_RDONLY);
+    let dir = openat("/tmp/chown_dir\0", OpenFlags::O_RDONLY);
+    assert!(old >= 0 && dir >= 0);
+    assert!(fchdir(dir as usize) == 0);
+    let fd = openat("file\0", OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    assert!(fchdir(fd as usize) == -ENOTDIR);
+    close(fd as usize);
+    assert!(owner_of(AT_FDCWD, "\0", AT_EMPTY_PATH) == (1000, 1000));
+    assert!(fchdir(old as usize) == 0);
+    let mut now = [0u8; 64];
+    assert!(getcwd(&mut now) > 0);
+    assert!(now == cwd);
+    close(old as usize);
+    close(dir as usize);
+
+    assert!(unlink("/tmp/chown_dir/file\0") == 0);
+    assert!(unlink("/tmp/chown_link\0") == 0);
+    assert!(rmdir("/tmp/chown_dir\0") == 0);
+    println!("chown test pass.");
+    0
+}
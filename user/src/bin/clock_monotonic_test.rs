@@ -0,0 +1,78 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use user_lib::*;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_SHARED: i32 = 0x01;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const SAMPLES: usize = 20_000;
+
+fn now_ns() -> u64 {
+    let mut tp = TimeSpec::default();
+    assert!(clock_gettime(CLOCK_MONOTONIC, &mut tp) == 0);
+    core::time::Duration::from(tp).as_nanos() as u64
+}
+
+/// Sample the clock in a tight loop on the harts of `mask`. No sample may be
+/// behind the one before, nor behind the latest of any hart seen before it.
+/// Returns how many samples are not whole microseconds.
+fn sample(mask: usize, latest: &AtomicU64) -> usize {
+    assert!(sched_setaffinity(0, mask) == 0);
+    let mut last = 0;
+    let mut fine = 0;
+    for _ in 0..SAMPLES {
+        let seen = latest.load(Ordering::SeqCst);
+        let ns = now_ns();
+        assert!(ns >= last, "went back from {last} to {ns} ns");
+        assert!(ns >= seen, "{ns} ns behind {seen} ns of another hart");
+        if ns % 1_000 != 0 {
+            fine += 1;
+        }
+        last = ns;
+        latest.fetch_max(ns, Ordering::SeqCst);
+    }
+    fine
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin clock monotonic test");
+    let mut online = 0;
+    assert!(sched_getaffinity(0, &mut online) > 0);
+    let nharts = online.count_ones();
+    let addr = mmap(
+        core::ptr::null(),
+        4096,
+        PROT_READ | PROT_WRITE,
+        MAP_SHARED | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    );
+    assert!(addr > 0);
+    let latest = unsafe { &*(addr as usize as *const AtomicU64) };
+
+    // one process on the last hart and this one on the first, both at once
+    let pid = fork();
+    if pid == 0 {
+        sample(1 << (nharts - 1), latest);
+        exit(0);
+    }
+    let fine = sample(1, latest);
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    println!("[clock_monotonic] {nharts} harts, {fine} of {SAMPLES} samples below 1us");
+    // the time is finer than microseconds
+    assert!(fine > 0);
+
+    assert!(sched_setaffinity(0, online) == 0);
+    munmap(addr as usize as *mut u8, 4096);
+    println!("clock monotonic test passed");
+    0
+}
@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::*;
+
+const EINVAL: isize = 22;
+
+fn now(clockid: usize) -> Duration {
+    let mut tp = TimeSpec::default();
+    assert!(clock_gettime(clockid, &mut tp) == 0);
+    tp.into()
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("begin clock test");
+    let mut last_boot = Duration::ZERO;
+    let mut last_raw = Duration::ZERO;
+    let mut last_coarse = Duration::ZERO;
+    for _ in 0..10 {
+        let coarse = now(CLOCK_MONOTONIC_COARSE);
+        let before = now(CLOCK_MONOTONIC);
+        let boot = now(CLOCK_BOOTTIME);
+        let raw = now(CLOCK_MONOTONIC_RAW);
+        let after = now(CLOCK_MONOTONIC);
+        println!("boottime {:?}, raw {:?}, coarse {:?}", boot, raw, coarse);
+
+        // all three count from boot
+        assert!(before <= boot && boot <= raw && raw <= after);
+        // the coarse clock lags by at most a tick
+        assert!(coarse <= before);
+        assert!(before - coarse <= Duration::from_millis(50));
+        assert!(boot > Duration::ZERO);
+
+        assert!(last_boot <= boot);
+        assert!(last_raw <= raw);
+        assert!(last_coarse <= coarse);
+        last_boot = boot;
+        last_raw = raw;
+        last_coarse = coarse;
+        sleep(5);
+    }
+
+    let mut res = TimeSpec::default();
+    assert!(clock_getres(CLOCK_BOOTTIME, &mut res) == 0);
+    assert!(Duration::from(res) <= Duration::from_micros(1));
+    assert!(clock_getres(CLOCK_MONOTONIC_COARSE, &mut res) == 0);
+    assert!(Duration::from(res) >= Duration::from_millis(1));
+
+    let mut tp = TimeSpec::default();
+    assert!(clock_gettime(100, &mut tp) == -EINVAL);
+    println!("clock test passed");
+    0
+}
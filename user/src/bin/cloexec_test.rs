@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String, vec::Vec};
+
+use user_lib::*;
+
+const EBADF: isize = 9;
+
+/// Runs after exec, `argv` holds fds that should be closed, then fds that
+/// should be kept, separated by "keep".
+fn check_after_exec(argv: &[&str]) -> i32 {
+    let mut keep = false;
+    for arg in argv {
+        if *arg == "keep" {
+            keep = true;
+            continue;
+        }
+        let fd = arg.parse::<usize>().unwrap();
+        let ret = write(fd, b"x");
+        if keep {
+            assert!(ret == 1, "fd {fd} should be kept after exec");
+        } else {
+            assert!(ret == -EBADF, "fd {fd} should be closed after exec");
+        }
+    }
+    println!("cloexec test pass.");
+    0
+}
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc > 1 {
+        return check_after_exec(&argv[1..]);
+    }
+    println!("begin cloexec test");
+    let path = "/tmp/cloexec_test\0";
+    let cloexec_fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_CLOEXEC,
+    );
+    let plain_fd = openat(path, OpenFlags::O_RDWR);
+    assert!(cloexec_fd >= 0 && plain_fd >= 0);
+    let (cloexec_fd, plain_fd) = (cloexec_fd as usize, plain_fd as usize);
+    assert!(fcntl(cloexec_fd, F_GETFD, 0) == FD_CLOEXEC as isize);
+    assert!(fcntl(plain_fd, F_GETFD, 0) == 0);
+
+    // F_SETFD toggles the flag of one fd only
+    let setfd_fd = dup(plain_fd) as usize;
+    assert!(fcntl(setfd_fd, F_SETFD, FD_CLOEXEC) == 0);
+    assert!(fcntl(setfd_fd, F_GETFD, 0) == FD_CLOEXEC as isize);
+    assert!(fcntl(plain_fd, F_GETFD, 0) == 0);
+
+    let dupfd_fd = fcntl(plain_fd, F_DUPFD_CLOEXEC, 0) as usize;
+    let dup3_fd = 20;
+    assert!(dup3(plain_fd, dup3_fd, OpenFlags::O_CLOEXEC) == dup3_fd as isize);
+    assert!(dup3(plain_fd, 21, OpenFlags::O_TRUNC) < 0);
+
+    let mut pipe_fds = [0i32; 2];
+    assert!(pipe2(&mut pipe_fds, OpenFlags::O_CLOEXEC) == 0);
+
+    let closed = [
+        cloexec_fd,
+        setfd_fd,
+        dupfd_fd,
+        dup3_fd,
+        pipe_fds[1] as usize,
+    ];
+    let mut args: Vec<String> = Vec::new();
+    args.push(String::from(argv[0]));
+    args.extend(closed.iter().map(|fd| format!("{fd}")));
+    args.push(String::from("keep"));
+    args.push(format!("{plain_fd}"));
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    execve(argv[0], &args, &[]);
+    panic!("execve failed");
+}
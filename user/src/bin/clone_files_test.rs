@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin clone files test");
+    let path = "/tmp/clone_files_test\0";
+    let fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+
+    // fds opened and closed by the child are visible to the parent
+    let pid = clone(CloneFlags::FILES);
+    if pid == 0 {
+        let new_fd = openat(path, OpenFlags::O_WRONLY);
+        assert!(new_fd >= 0);
+        assert!(close(fd) == 0);
+        exit(new_fd as i32);
+    }
+    let mut new_fd: i32 = 0;
+    assert!(waitpid(pid as usize, &mut new_fd) == pid);
+    // exit code is stored in the second byte of wstatus
+    let new_fd = ((new_fd >> 8) & 0xff) as usize;
+    assert!(write(fd, b"x") < 0);
+    assert!(write(new_fd, b"shared") == 6);
+
+    // a forked child has its own copy
+    let pid = fork();
+    if pid == 0 {
+        assert!(close(new_fd) == 0);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    assert!(write(new_fd, b"copied") == 6);
+    println!("clone files test pass.");
+    0
+}
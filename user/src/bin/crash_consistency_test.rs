@@ -0,0 +1,135 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const SEEK_SET: usize = 0;
+
+/// ioctl commands of the ramdisk /dev/ram0.
+const RAMDISK_SET_CRASH_POINT: usize = 0x12f0;
+const RAMDISK_RECOVER: usize = 0x12f1;
+const RAMDISK_HAS_CRASHED: usize = 0x12f2;
+
+/// Empty FAT image whose free clusters hold 0xee, made by
+/// crash_test_prepare.sh.
+const IMAGE: &str = "/crash_test_fat.img\0";
+const RAMDISK: &str = "/dev/ram0\0";
+const MNT: &str = "/tmp/crash_mnt\0";
+const FILE: &str = "/tmp/crash_mnt/file\0";
+
+/// Several clusters of data, so that the file needs a chain of them.
+const FILE_SIZE: usize = 16 * 1024;
+/// Block writes are far fewer than this for one small file.
+const MAX_CRASH_POINT: usize = 512;
+
+fn pattern(i: usize) -> u8 {
+    b'a' + (i % 26) as u8
+}
+
+fn load_image(disk: usize) {
+    let img = openat(IMAGE, OpenFlags::O_RDONLY);
+    assert!(img >= 0);
+    let img = img as usize;
+    assert!(lseek(disk, 0, SEEK_SET) == 0);
+    let mut buf = [0u8; 4096];
+    loop {
+        let len = read(img, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        assert!(write(disk, &buf[..len as usize]) == len);
+    }
+    close(img);
+}
+
+fn write_file() {
+    let fd = openat(FILE, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let mut buf = [0u8; 4096];
+    for off in (0..FILE_SIZE).step_by(buf.len()) {
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = pattern(off + i);
+        }
+        // writes fail once the disk crashed, which is fine
+        write(fd, &buf);
+    }
+    fsync(fd);
+    close(fd);
+}
+
+/// The file may be missing or shorter than written, but every byte it holds
+/// must be what was written, not a cluster that never reached the disk.
+/// Returns the size found, or `None` if the file does not exist.
+fn check_file() -> Option<usize> {
+    let fd = openat(FILE, OpenFlags::O_RDONLY);
+    if fd < 0 {
+        return None;
+    }
+    let fd = fd as usize;
+    let mut st = Kstat::default();
+    assert!(fstat(fd, &mut st) == 0);
+    let size = st.st_size as usize;
+    assert!(size <= FILE_SIZE);
+    let mut buf = [0u8; 4096];
+    let mut off = 0;
+    while off < size {
+        let len = read(fd, &mut buf);
+        assert!(len > 0);
+        for (i, &b) in buf[..len as usize].iter().enumerate() {
+            if b != pattern(off + i) {
+                println!(
+                    "crash consistency test fail: byte {} of {} is {:#x}",
+                    off + i,
+                    size,
+                    b
+                );
+                panic!();
+            }
+        }
+        off += len as usize;
+    }
+    close(fd);
+    Some(size)
+}
+
+/// Crash the ramdisk after each possible number of block writes while a file
+/// is created, written and fsynced on FAT, then mount what reached the disk.
+///
+/// NOTE: ext4 is not covered, lwext4 only supports the root mount.
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin crash consistency test");
+    let disk = openat(RAMDISK, OpenFlags::O_RDWR);
+    assert!(disk >= 0);
+    let disk = disk as usize;
+    assert!(mkdir(MNT) == 0);
+
+    for crash_point in 1..MAX_CRASH_POINT {
+        load_image(disk);
+        assert!(mount(RAMDISK, MNT, "vfat\0", 0) == 0);
+        assert!(ioctl(disk, RAMDISK_SET_CRASH_POINT, crash_point) == 0);
+        write_file();
+        let crashed = ioctl(disk, RAMDISK_HAS_CRASHED, 0);
+        assert!(crashed >= 0);
+        assert!(umount2(MNT, 0) == 0);
+        assert!(ioctl(disk, RAMDISK_RECOVER, 0) == 0);
+
+        assert!(mount(RAMDISK, MNT, "vfat\0", 0) == 0);
+        let size = check_file();
+        assert!(umount2(MNT, 0) == 0);
+        if crashed == 0 {
+            // every write reached the disk, fsync must have made all durable
+            assert!(size == Some(FILE_SIZE));
+            println!("all block writes done before crash point {}", crash_point);
+            close(disk);
+            println!("crash consistency test pass.");
+            return 0;
+        }
+    }
+    println!("crash consistency test fail: the disk always crashed");
+    1
+}
@@ -0,0 +1,82 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const ERANGE: isize = 34;
+
+const DIR_A: &str = "/tmp/cwd_test_a";
+const DIR_B: &str = "/tmp/cwd_test_b";
+
+/// Check the cwd of the calling task is `expected`.
+fn assert_cwd(expected: &str) {
+    let mut buf = [0u8; 64];
+    let ret = getcwd(&mut buf);
+    assert!(ret > 0);
+    let len = buf.iter().position(|&b| b == 0).unwrap();
+    let cwd = core::str::from_utf8(&buf[..len]).unwrap();
+    println!("[cwd] {cwd}");
+    assert!(cwd == expected);
+}
+
+fn wait(pid: isize) {
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin cwd test");
+    assert!(mkdir("/tmp/cwd_test_a\0") == 0);
+    assert!(mkdir("/tmp/cwd_test_b\0") == 0);
+    assert!(chdir("/tmp/cwd_test_a\0") == 0);
+    assert_cwd(DIR_A);
+
+    // a forked child has its own cwd
+    let pid = fork();
+    if pid == 0 {
+        assert!(chdir("../cwd_test_b\0") == 0);
+        assert_cwd(DIR_B);
+        let fd = openat("in_b\0", OpenFlags::O_CREATE | OpenFlags::O_WRONLY);
+        assert!(fd >= 0);
+        close(fd as usize);
+        exit(0);
+    }
+    wait(pid);
+    assert_cwd(DIR_A);
+    assert!(openat("in_b\0", OpenFlags::O_RDONLY) == -ENOENT);
+    let fd = openat("../cwd_test_b/in_b\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    // one cloned with CLONE_FS shares it
+    let pid = clone(CloneFlags::FS);
+    if pid == 0 {
+        assert!(chdir("/tmp/cwd_test_b\0") == 0);
+        exit(0);
+    }
+    wait(pid);
+    assert_cwd(DIR_B);
+    let fd = openat("in_b\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    // the buffer must hold the path and its nul
+    let mut buf = [0u8; DIR_B.len() + 1];
+    assert!(getcwd(&mut buf[..DIR_B.len()]) == -ERANGE);
+    assert!(getcwd(&mut buf[..4]) == -ERANGE);
+    assert!(getcwd(&mut buf) > 0);
+    assert!(&buf[..DIR_B.len()] == DIR_B.as_bytes() && buf[DIR_B.len()] == 0);
+
+    assert!(chdir("/\0") == 0);
+    assert_cwd("/");
+    assert!(unlink("/tmp/cwd_test_b/in_b\0") == 0);
+    assert!(rmdir("/tmp/cwd_test_a\0") == 0);
+    assert!(rmdir("/tmp/cwd_test_b\0") == 0);
+    println!("cwd test passed");
+    0
+}
@@ -0,0 +1,158 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use user_lib::*;
+
+/// First number in a file of /proc/sys/fs, the count of dentries or inodes
+/// alive.
+fn fs_count(path: &str) -> usize {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; 64];
+    let len = read(fd as usize, &mut buf);
+    close(fd as usize);
+    assert!(len > 0);
+    let content = core::str::from_utf8(&buf[..len as usize]).unwrap();
+    content.split_whitespace().next().unwrap().parse().unwrap()
+}
+
+fn counts() -> (usize, usize) {
+    (
+        fs_count("/proc/sys/fs/dentry-state\0"),
+        fs_count("/proc/sys/fs/inode-nr\0"),
+    )
+}
+
+const NTHREAD: usize = 8;
+const ROUNDS: usize = 32;
+const DEEP_PATH: &str = "/dcache_test/a/b/c/d/file\0";
+/// Directories all threads race to make, in order, then the file they race
+/// to create exclusively.
+const RACE_DIRS: [&str; 4] = [
+    "/dcache_test/race\0",
+    "/dcache_test/race/a\0",
+    "/dcache_test/race/a/b\0",
+    "/dcache_test/race/a/b/c\0",
+];
+const RACE_FILE: &str = "/dcache_test/race/a/b/c/file\0";
+
+const EEXIST: isize = 17;
+const AT_REMOVEDIR: i32 = 0x200;
+
+/// Inode of `DEEP_PATH`, set before the threads are made.
+static INO: AtomicU64 = AtomicU64::new(0);
+
+/// Run `f` in `NTHREAD` threads at once, each sending the byte it returns
+/// through the pipe `fds`, and collect the bytes.
+fn run_threads(fds: &[i32; 2], f: fn() -> u8) -> [u8; NTHREAD] {
+    for _ in 0..NTHREAD {
+        if create_thread(CloneFlags::THREAD) == 0 {
+            let ret = f();
+            assert!(write(fds[1] as usize, &[ret]) == 1);
+            exit(0);
+        }
+    }
+    let mut rets = [0u8; NTHREAD];
+    let mut len = 0;
+    while len < NTHREAD {
+        let n = read(fds[0] as usize, &mut rets[len..]);
+        assert!(n > 0);
+        len += n as usize;
+    }
+    rets
+}
+
+/// Every thread must see the same inode for the same path.
+fn stat_deep_path() -> u8 {
+    let mut st = Kstat::default();
+    for _ in 0..ROUNDS {
+        assert!(stat(DEEP_PATH, &mut st) == 0);
+        if st.st_ino != INO.load(Ordering::Relaxed) {
+            return b'x';
+        }
+        yield_();
+    }
+    b'0'
+}
+
+/// Make the race directories and file, returning how many of them this thread
+/// made, the others must fail with `EEXIST`.
+fn race_create() -> u8 {
+    let mut made = 0;
+    for dir in RACE_DIRS {
+        match mkdir(dir) {
+            0 => made += 1,
+            ret if ret == -EEXIST => {}
+            _ => return b'x',
+        }
+    }
+    let fd = openat(
+        RACE_FILE,
+        OpenFlags::O_CREATE | OpenFlags::O_EXCL | OpenFlags::O_RDWR,
+    );
+    if fd >= 0 {
+        close(fd as usize);
+        made += 1;
+    } else if fd != -EEXIST {
+        return b'x';
+    }
+    b'0' + made
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin dcache test");
+    for dir in [
+        "/dcache_test\0",
+        "/dcache_test/a\0",
+        "/dcache_test/a/b\0",
+        "/dcache_test/a/b/c\0",
+        "/dcache_test/a/b/c/d\0",
+    ] {
+        mkdir(dir);
+    }
+    let fd = openat(DEEP_PATH, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    let mut st = Kstat::default();
+    assert!(stat(DEEP_PATH, &mut st) == 0);
+    INO.store(st.st_ino, Ordering::Relaxed);
+    let mut fds = [0i32; 2];
+    assert!(pipe(&mut fds) == 0);
+    // cache the dentries of the counters themselves first
+    counts();
+    let before = counts();
+    assert!(before.0 > 0 && before.1 > 0);
+
+    // the path was cached once, no lookup makes another dentry or inode for it
+    let rets = run_threads(&fds, stat_deep_path);
+    assert!(rets.iter().all(|&ret| ret == b'0'));
+    assert!(counts() == before);
+
+    // each name is made by exactly one thread, and looked up or made once
+    let rets = run_threads(&fds, race_create);
+    assert!(rets.iter().all(|&ret| ret != b'x'));
+    let made: usize = rets.iter().map(|&ret| (ret - b'0') as usize).sum();
+    let components = RACE_DIRS.len() + 1;
+    assert!(made == components);
+    let after = counts();
+    println!(
+        "dentries {} -> {}, inodes {} -> {}",
+        before.0, after.0, before.1, after.1
+    );
+    assert!(after == (before.0 + components, before.1 + components));
+
+    close(fds[0] as usize);
+    close(fds[1] as usize);
+    assert!(unlink(RACE_FILE) == 0);
+    for dir in RACE_DIRS.iter().rev() {
+        assert!(unlinkat(AT_FDCWD, dir, AT_REMOVEDIR) == 0);
+    }
+    println!("dcache test pass.");
+    0
+}
@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EBADF: isize = 9;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin dev fd test");
+    let mut buf = [0u8; 64];
+
+    // the standard streams are links into /proc/self/fd
+    let len = readlink("/dev/stdin\0", &mut buf);
+    assert!(len > 0);
+    assert!(&buf[..len as usize] == b"/proc/self/fd/0");
+    let len = readlink("/proc/self/fd/1\0", &mut buf);
+    assert!(&buf[..len as usize] == b"/dev/tty");
+
+    // echo hi | cat /dev/stdin
+    let mut fds = [0i32; 2];
+    assert!(pipe(&mut fds) == 0);
+    let (rfd, wfd) = (fds[0] as usize, fds[1] as usize);
+    let pid = fork();
+    if pid == 0 {
+        close(wfd);
+        assert!(dup3(rfd, 0, OpenFlags::empty()) == 0);
+        close(rfd);
+        let fd = openat("/dev/stdin\0", OpenFlags::O_RDONLY);
+        assert!(fd >= 0);
+        assert!(read(fd as usize, &mut buf) == 3);
+        assert!(&buf[..3] == b"hi\n");
+        // the write end is gone, only the reopened read end is left
+        assert!(read(fd as usize, &mut buf) == 0);
+        exit(0);
+    }
+    close(rfd);
+    assert!(write(wfd, b"hi\n") == 3);
+    close(wfd);
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+
+    // a reopened pipe end keeps its direction
+    assert!(pipe(&mut fds) == 0);
+    let (rfd, wfd) = (fds[0] as usize, fds[1] as usize);
+    assert!(dup3(rfd, 9, OpenFlags::empty()) == 9);
+    let fd = openat("/proc/self/fd/9\0", OpenFlags::O_RDONLY) as usize;
+    assert!(write(fd, b"x") == -EBADF);
+    assert!(write(wfd, b"x") == 1);
+    assert!(read(fd, &mut buf) == 1);
+    close(fd);
+    close(9);
+    close(rfd);
+    close(wfd);
+
+    // diff /dev/fd/10 10<file file
+    let file = "/tmp/dev_fd_test\0";
+    let fd = openat(
+        file,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    assert!(write(fd as usize, b"same content") == 12);
+    assert!(dup3(fd as usize, 10, OpenFlags::empty()) == 10);
+    let reopened = openat("/dev/fd/10\0", OpenFlags::O_RDONLY);
+    assert!(reopened >= 0);
+    // a new open file, its offset does not follow fd 10
+    assert!(read(reopened as usize, &mut buf) == 12);
+    assert!(&buf[..12] == b"same content");
+    assert!(read(10, &mut buf) == 0);
+    close(reopened as usize);
+    close(10);
+    close(fd as usize);
+    // gone once fd 10 is closed
+    assert!(openat("/dev/fd/10\0", OpenFlags::O_RDONLY) < 0);
+    unlink(file);
+
+    println!("dev fd test pass.");
+    0
+}
@@ -0,0 +1,93 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const EINVAL: isize = 22;
+
+const EFD_SEMAPHORE: i32 = 1;
+const EFD_NONBLOCK: i32 = 0o4000;
+
+fn read_count(fd: usize) -> isize {
+    let mut buf = [0u8; 8];
+    let ret = read(fd, &mut buf);
+    if ret < 0 {
+        return ret;
+    }
+    assert!(ret == 8);
+    u64::from_ne_bytes(buf) as isize
+}
+
+fn write_count(fd: usize, value: u64) -> isize {
+    write(fd, &value.to_ne_bytes())
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin eventfd test");
+
+    // a read takes the whole counter
+    let fd = eventfd(3, 0);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(read_count(fd) == 3);
+    assert!(write_count(fd, 2) == 8);
+    assert!(write_count(fd, 5) == 8);
+    assert!(read_count(fd) == 7);
+
+    // a blocked read is woken up by a write of another process
+    let pid = fork();
+    if pid == 0 {
+        sleep(50);
+        assert!(write_count(fd, 1) == 8);
+        exit(0);
+    }
+    assert!(read_count(fd) == 1);
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+
+    // and so is a poll for it
+    let mut fds = [PollFd::new(fd, POLLIN | POLLOUT)];
+    assert!(ppoll(&mut fds, Some(&TimeSpec::from_ms(0))) == 1);
+    assert!(fds[0].revents == POLLOUT);
+    let pid = fork();
+    if pid == 0 {
+        sleep(50);
+        assert!(write_count(fd, 4) == 8);
+        exit(0);
+    }
+    let mut fds = [PollFd::new(fd, POLLIN)];
+    assert!(ppoll(&mut fds, Some(&TimeSpec::from_ms(5000))) == 1);
+    assert!(fds[0].revents == POLLIN);
+    assert!(read_count(fd) == 4);
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    close(fd);
+
+    // nonblocking and the limits of the counter
+    let fd = eventfd(0, EFD_NONBLOCK) as usize;
+    assert!(read_count(fd) == -EAGAIN);
+    let mut short = [0u8; 4];
+    assert!(read(fd, &mut short) == -EINVAL);
+    assert!(write(fd, &short) == -EINVAL);
+    assert!(write_count(fd, u64::MAX) == -EINVAL);
+    assert!(write_count(fd, u64::MAX - 1) == 8);
+    assert!(write_count(fd, 1) == -EAGAIN);
+    let mut fds = [PollFd::new(fd, POLLOUT)];
+    assert!(ppoll(&mut fds, Some(&TimeSpec::from_ms(0))) == 0);
+    assert!(read_count(fd) == (u64::MAX - 1) as isize);
+    close(fd);
+
+    // a semaphore is taken one by one
+    let fd = eventfd(2, EFD_SEMAPHORE | EFD_NONBLOCK) as usize;
+    assert!(read_count(fd) == 1);
+    assert!(read_count(fd) == 1);
+    assert!(read_count(fd) == -EAGAIN);
+    close(fd);
+
+    assert!(eventfd(0, 1 << 4) == -EINVAL);
+    println!("eventfd test passed");
+    0
+}
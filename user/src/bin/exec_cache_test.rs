@@ -0,0 +1,163 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String, vec::Vec};
+use core::time::Duration;
+
+use user_lib::*;
+
+const ETXTBSY: isize = 26;
+const SEEK_SET: usize = 0;
+
+const EXECS: usize = 20;
+const COPY: &str = "/exec_cache_copy";
+
+/// Changed in a copy of this program, to check that the copy is not run from
+/// stale pages afterwards.
+static MARKER: [u8; 16] = *b"exec cache mark0";
+
+fn now() -> Duration {
+    let mut tv = TimeVal::default();
+    assert!(gettimeofday(&mut tv) == 0);
+    tv.into()
+}
+
+fn read_file(path: &str) -> Vec<u8> {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    content
+}
+
+/// Hits and misses of the page cache.
+fn page_cache_stat() -> (usize, usize) {
+    let content = String::from_utf8(read_file("/proc/page_cache_stat\0")).unwrap();
+    let mut values = content
+        .lines()
+        .map(|line| line.split_whitespace().nth(1).unwrap().parse().unwrap());
+    (values.next().unwrap(), values.next().unwrap())
+}
+
+/// Run `path` in `mode`, returning its exit code, or the error of execve.
+fn run(path: &str, mode: &str) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        let ret = execve(path, &[path, mode], &[]);
+        exit(-ret as i32);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    (exit_code >> 8) & 0xff
+}
+
+fn bench(path: &str) {
+    // the first run caches every page a run touches
+    assert!(run(path, "exit") == 0);
+    let (hits, misses) = page_cache_stat();
+    let start = now();
+    for _ in 0..EXECS {
+        assert!(run(path, "exit") == 0);
+    }
+    let elapsed = now() - start;
+    let (hits, misses) = {
+        let (h, m) = page_cache_stat();
+        (h - hits, m - misses)
+    };
+    println!(
+        "[exec_cache] {EXECS} execs in {:?}, {} execs/s, page cache hits {}, misses {}",
+        elapsed,
+        EXECS as u128 * 1_000_000 / elapsed.as_micros().max(1),
+        hits,
+        misses
+    );
+    // nothing is read from the device once cached
+    assert!(hits > 0);
+    assert!(misses == 0);
+}
+
+fn check_busy(path: &str, copy: &str) {
+    let data = read_file(path);
+    let fd = openat(
+        copy,
+        OpenFlags::O_CREATE | OpenFlags::O_WRONLY | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    assert!(chmod(copy, 0o755) == 0);
+    assert!(write(fd as usize, &data) == data.len() as isize);
+    // an executable open for writing can not run
+    assert!(run(COPY, "exit") == ETXTBSY as i32);
+    close(fd as usize);
+    assert!(run(COPY, "marker") == MARKER[15] as i32);
+
+    // and a running one can not be written
+    let pid = fork();
+    if pid == 0 {
+        execve(COPY, &[COPY, "sleep"], &[]);
+        exit(1);
+    }
+    let mut busy = false;
+    for _ in 0..100 {
+        let fd = openat(copy, OpenFlags::O_WRONLY);
+        if fd == -ETXTBSY {
+            busy = true;
+            break;
+        }
+        assert!(fd >= 0);
+        close(fd as usize);
+        sleep(5);
+    }
+    assert!(busy);
+    assert!(openat(copy, OpenFlags::O_RDWR | OpenFlags::O_TRUNC) == -ETXTBSY);
+    assert!(truncate(copy, 0) == -ETXTBSY);
+    let fd = openat(copy, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+
+    // a change after it exits is seen by the next run
+    let pos = data
+        .windows(MARKER.len())
+        .position(|window| window == MARKER)
+        .unwrap();
+    let fd = openat(copy, OpenFlags::O_WRONLY);
+    assert!(fd >= 0);
+    assert!(lseek(fd as usize, (pos + 15) as isize, SEEK_SET) == (pos + 15) as isize);
+    assert!(write(fd as usize, b"7") == 1);
+    close(fd as usize);
+    assert!(run(COPY, "marker") == b'7' as i32);
+    assert!(unlink(copy) == 0);
+}
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc > 1 {
+        return match argv[1] {
+            "sleep" => {
+                sleep(200);
+                0
+            }
+            "marker" => unsafe { core::ptr::read_volatile(&MARKER[15]) as i32 },
+            _ => 0,
+        };
+    }
+    println!("begin exec cache test");
+    bench(argv[0]);
+    check_busy(&format!("{}\0", argv[0]), &format!("{COPY}\0"));
+    println!("exec cache test passed");
+    0
+}
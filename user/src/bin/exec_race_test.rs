@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const ROUNDS: usize = 10;
+const THREADS: usize = 4;
+const EXEC_CODE: i32 = 42;
+
+/// Create threads that exit at once, forever.
+fn spawn_forever() -> ! {
+    loop {
+        let tid = create_thread(CloneFlags::THREAD);
+        if tid == 0 {
+            exit(0);
+        }
+        yield_();
+    }
+}
+
+/// A process whose threads keep creating threads while one of them runs
+/// `path`.
+fn race(path: &str) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        for _ in 0..THREADS {
+            if create_thread(CloneFlags::THREAD) == 0 {
+                spawn_forever();
+            }
+        }
+        sleep(20);
+        let ret = execve(path, &[path, "exec"], &[]);
+        exit(-ret as i32);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    (exit_code >> 8) & 0xff
+}
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc > 1 {
+        // only the thread that called execve runs the new program
+        sleep(20);
+        return EXEC_CODE;
+    }
+    println!("begin exec race test");
+    for round in 0..ROUNDS {
+        let code = race(argv[0]);
+        println!("[exec_race] round {round}, exit code {code}");
+        assert!(code == EXEC_CODE);
+    }
+    println!("exec race test passed");
+    0
+}
@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const EPERM: isize = 1;
+const EACCES: isize = 13;
+const EINVAL: isize = 22;
+const UNCHANGED: u32 = u32::MAX;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin faccessat test");
+    // created with mode 0, only root can access it
+    let path = "/tmp/faccessat_test\0";
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    assert!(faccessat(path, F_OK) == 0);
+    assert!(faccessat(path, R_OK | W_OK) == 0);
+    assert!(faccessat(path, X_OK) == -EACCES);
+    assert!(faccessat("/tmp/faccessat_none\0", F_OK) == -ENOENT);
+    // a directory is searchable by root
+    assert!(access("/tmp\0", X_OK) == 0);
+    assert!(access("/tmp/faccessat_none\0", X_OK) == -ENOENT);
+    assert!(faccessat2(path, R_OK, 0x1) == -EINVAL);
+
+    let pid = fork();
+    if pid == 0 {
+        // real uid is dropped while effective uid is still root
+        assert!(setresuid(1000, UNCHANGED, UNCHANGED) == 0);
+        assert!(getuid() == 1000 && geteuid() == 0);
+        assert!(faccessat(path, R_OK) == -EACCES);
+        assert!(faccessat2(path, R_OK, 0) == -EACCES);
+        assert!(faccessat2(path, R_OK, AT_EACCESS) == 0);
+
+        // a privileged setuid drops all ids
+        assert!(setuid(1000) == 0);
+        assert!(geteuid() == 1000);
+        assert!(faccessat2(path, R_OK, AT_EACCESS) == -EACCES);
+        assert!(setuid(0) == -EPERM);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    assert!(getuid() == 0);
+    println!("faccessat test pass.");
+    0
+}
@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const S_IFIFO: usize = 0o010000;
+const ENXIO: isize = 6;
+const EEXIST: isize = 17;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin fifo test");
+    let path = "/tmp/fifo_test\0";
+    assert!(mknod(path, S_IFIFO | 0o644, 0) == 0);
+    assert!(mknod(path, S_IFIFO | 0o644, 0) == -EEXIST);
+
+    // no reader yet, a nonblocking writer is refused
+    assert!(openat(path, OpenFlags::O_WRONLY | OpenFlags::O_NONBLOCK) == -ENXIO);
+    // while a nonblocking reader does not wait for a writer
+    let fd = openat(path, OpenFlags::O_RDONLY | OpenFlags::O_NONBLOCK);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    let pid = fork();
+    if pid == 0 {
+        // blocks until the parent opens the read end
+        let fd = openat(path, OpenFlags::O_WRONLY);
+        assert!(fd >= 0);
+        assert!(write(fd as usize, b"hello fifo") == 10);
+        close(fd as usize);
+        exit(0);
+    }
+    // blocks until the child opens the write end
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let mut buf = [0u8; 32];
+    let mut len = 0;
+    loop {
+        let n = read(fd, &mut buf[len..]);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        len += n as usize;
+    }
+    // EOF once the writer has closed
+    assert!(&buf[..len] == b"hello fifo");
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+    close(fd);
+
+    assert!(unlink(path) == 0);
+    println!("fifo test pass.");
+    0
+}
@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::*;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const PAGE_SIZE: usize = 4096;
+const MAP_SIZE: usize = 512 * 1024 * 1024;
+/// Only touch one page in this many, the board may not have 512MiB of memory.
+const TOUCH_STRIDE: usize = 64;
+
+fn now_us() -> usize {
+    let mut tv = TimeVal::default();
+    assert!(gettimeofday(&mut tv) == 0);
+    let time: Duration = tv.into();
+    time.as_micros() as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin fork large test");
+    let addr = mmap(
+        core::ptr::null(),
+        MAP_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    );
+    assert!(addr > 0);
+    let base = addr as usize as *mut usize;
+    let slot = |i: usize| unsafe { base.add(i * TOUCH_STRIDE * PAGE_SIZE / 8) };
+    let nr_slots = MAP_SIZE / PAGE_SIZE / TOUCH_STRIDE;
+    for i in 0..nr_slots {
+        unsafe { slot(i).write_volatile(i) };
+    }
+
+    let start = now_us();
+    let pid = fork();
+    if pid == 0 {
+        // the child sees the memory at fork time, and its writes stay private
+        for i in 0..nr_slots {
+            assert!(unsafe { slot(i).read_volatile() } == i);
+            unsafe { slot(i).write_volatile(usize::MAX) };
+        }
+        exit(0);
+    }
+    println!(
+        "fork of a {}MiB mapping with {} pages touched took {}us",
+        MAP_SIZE >> 20,
+        nr_slots,
+        now_us() - start
+    );
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    for i in 0..nr_slots {
+        assert!(unsafe { slot(i).read_volatile() } == i);
+    }
+    println!("fork large test pass.");
+    0
+}
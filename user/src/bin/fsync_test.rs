@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const SEEK_SET: usize = 0;
+const EBADF: isize = 9;
+const EINVAL: isize = 22;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin fsync test");
+    let path = "/fsync_test\0";
+    let fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+
+    // three pages of data, then overwrite inside the cached pages
+    let mut data = [0u8; 3 * 4096];
+    for (i, b) in data.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+    assert!(write(fd, &data) == data.len() as isize);
+    assert!(fsync(fd) == 0);
+    assert!(lseek(fd, 100, SEEK_SET) == 100);
+    assert!(write(fd, b"overwritten") == 11);
+    assert!(lseek(fd, 2 * 4096, SEEK_SET) == 2 * 4096);
+    assert!(write(fd, b"tail") == 4);
+    assert!(fsync(fd) == 0);
+    // nothing dirty left
+    assert!(fsync(fd) == 0);
+    // growing the file changes the size, which fdatasync writes too
+    assert!(lseek(fd, 3 * 4096, SEEK_SET) == 3 * 4096);
+    assert!(write(fd, b"grown") == 5);
+    assert!(fdatasync(fd) == 0);
+    close(fd);
+
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let mut buf = [0u8; 3 * 4096 + 5];
+    assert!(read(fd, &mut buf) == buf.len() as isize);
+    assert!(&buf[3 * 4096..] == b"grown");
+    assert!(&buf[100..111] == b"overwritten");
+    assert!(&buf[2 * 4096..2 * 4096 + 4] == b"tail");
+    assert!(buf[111..2 * 4096] == data[111..2 * 4096]);
+    // fsync through a read only fd is allowed
+    assert!(fsync(fd) == 0);
+    close(fd);
+
+    assert!(fsync(1000) == -EBADF);
+    assert!(fdatasync(1000) == -EBADF);
+
+    // pipes and sockets have nothing to synchronize
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::empty()) == 0);
+    for fd in fds {
+        assert!(fsync(fd as usize) == -EINVAL);
+        assert!(fdatasync(fd as usize) == -EINVAL);
+        close(fd as usize);
+    }
+    let sock = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(fsync(sock) == -EINVAL);
+    assert!(fdatasync(sock) == -EINVAL);
+    close(sock);
+
+    // devices and synthetic files have no data of their own to write back
+    assert!(fsync(1) == 0);
+    assert!(fdatasync(1) == 0);
+    for path in ["/dev/null\0", "/proc/meminfo\0"] {
+        let fd = openat(path, OpenFlags::O_RDONLY);
+        assert!(fd >= 0);
+        assert!(fsync(fd as usize) == 0);
+        assert!(fdatasync(fd as usize) == 0);
+        close(fd as usize);
+    }
+    assert!(unlink(path) == 0);
+
+    // sync writes back files never synced on their own, whose blocks are only
+    // allocated then
+    let path = "/sync_test\0";
+    let fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let mut before = StatFs::default();
+    assert!(fstatfs(fd as usize, &mut before) == 0);
+    for _ in 0..16 {
+        assert!(write(fd as usize, &data) == data.len() as isize);
+    }
+    assert!(sync() == 0);
+    let mut after = StatFs::default();
+    assert!(fstatfs(fd as usize, &mut after) == 0);
+    assert!(after.f_bfree < before.f_bfree);
+    close(fd as usize);
+    assert!(unlink(path) == 0);
+    println!("fsync test pass.");
+    0
+}
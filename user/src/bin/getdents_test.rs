@@ -0,0 +1,120 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::TryInto;
+
+use user_lib::*;
+
+const EINVAL: isize = 22;
+const SEEK_SET: usize = 0;
+
+const DT_DIR: u8 = 4;
+const DT_REG: u8 = 8;
+
+const DIR: &str = "/getdents_test";
+const FILES: [&str; 3] = ["a", "bb", "a_rather_long_file_name_0"];
+
+struct Entry {
+    off: u64,
+    itype: u8,
+    name: String,
+}
+
+/// Parse the `struct linux_dirent64` records in `buf`.
+fn parse(buf: &[u8], entries: &mut Vec<Entry>) {
+    let mut pos = 0;
+    while pos < buf.len() {
+        let rec = &buf[pos..];
+        let off = u64::from_ne_bytes(rec[8..16].try_into().unwrap());
+        let reclen = u16::from_ne_bytes(rec[16..18].try_into().unwrap()) as usize;
+        assert!(reclen % 8 == 0);
+        let itype = rec[18];
+        let name_len = rec[19..reclen].iter().position(|&b| b == 0).unwrap();
+        let name = core::str::from_utf8(&rec[19..19 + name_len]).unwrap();
+        entries.push(Entry {
+            off,
+            itype,
+            name: name.to_string(),
+        });
+        pos += reclen;
+    }
+}
+
+/// List `fd` from its current offset with a buffer of `len` bytes.
+fn list(fd: usize, len: usize) -> Vec<Entry> {
+    let mut buf = alloc::vec![0u8; len];
+    let mut entries = Vec::new();
+    loop {
+        let ret = getdents64(fd, &mut buf);
+        assert!(ret >= 0);
+        if ret == 0 {
+            return entries;
+        }
+        parse(&buf[..ret as usize], &mut entries);
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin getdents test");
+    assert!(mkdir(&format!("{DIR}\0")) == 0);
+    for name in FILES {
+        let fd = openat(
+            &format!("{DIR}/{name}\0"),
+            OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+        );
+        assert!(fd >= 0);
+        close(fd as usize);
+    }
+    assert!(mkdir(&format!("{DIR}/sub\0")) == 0);
+
+    let fd = openat(&format!("{DIR}\0"), OpenFlags::O_DIRECTORY);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    // too small for any entry
+    let mut tiny = [0u8; 8];
+    assert!(getdents64(fd, &mut tiny) == -EINVAL);
+
+    // a small buffer takes several calls, and sees every entry once
+    let entries = list(fd, 64);
+    let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+    println!("entries: {names:?}");
+    assert!(entries.len() == FILES.len() + 3);
+    for entry in &entries {
+        let expected = match entry.name.as_str() {
+            "." | ".." | "sub" => DT_DIR,
+            name if FILES.contains(&name) => DT_REG,
+            name => panic!("unexpected entry {name}"),
+        };
+        assert!(entry.itype == expected);
+        assert!(names.iter().filter(|&&n| n == entry.name).count() == 1);
+    }
+    assert!(names[0] == "." && names[1] == "..");
+
+    // resume from the offset of an entry, it goes on with the entry after it
+    for i in 0..entries.len() {
+        assert!(lseek(fd, entries[i].off as isize, SEEK_SET) == entries[i].off as isize);
+        let rest = list(fd, 4096);
+        assert!(rest.len() == entries.len() - i - 1);
+        for (a, b) in rest.iter().zip(&entries[i + 1..]) {
+            assert!(a.name == b.name && a.off == b.off);
+        }
+    }
+    close(fd);
+
+    for name in FILES {
+        assert!(unlink(&format!("{DIR}/{name}\0")) == 0);
+    }
+    assert!(rmdir(&format!("{DIR}/sub\0")) == 0);
+    assert!(rmdir(&format!("{DIR}\0")) == 0);
+    println!("getdents test pass.");
+    0
+}
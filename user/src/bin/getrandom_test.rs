@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const GRND_NONBLOCK: usize = 1;
+const GRND_RANDOM: usize = 2;
+const GRND_INSECURE: usize = 4;
+const EINVAL: isize = 22;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin getrandom test");
+    let mut a = [0u8; 256];
+    let mut b = [0u8; 256];
+    assert!(getrandom(&mut a, 0) == 256);
+    assert!(getrandom(&mut b, GRND_NONBLOCK) == 256);
+    assert!(a != b);
+    assert!(a.iter().any(|&x| x != 0));
+
+    // every byte value should show up in a large enough sample
+    let mut big = [0u8; 8192];
+    assert!(getrandom(&mut big, GRND_RANDOM) == 8192);
+    let mut seen = [false; 256];
+    for &x in big.iter() {
+        seen[x as usize] = true;
+    }
+    assert!(seen.iter().all(|&s| s));
+
+    let mut odd = [0u8; 7];
+    assert!(getrandom(&mut odd, GRND_INSECURE) == 7);
+    assert!(getrandom(&mut odd, GRND_INSECURE | GRND_RANDOM) == -EINVAL);
+    assert!(getrandom(&mut odd, 1 << 5) == -EINVAL);
+    assert!(getrandom(&mut [], 0) == 0);
+    println!("getrandom test pass.");
+    0
+}
@@ -0,0 +1,146 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+
+use user_lib::*;
+
+const EINVAL: isize = 22;
+const ESRCH: isize = 3;
+
+const THREADS: usize = 3;
+const EXIT_CODE: i32 = 42;
+
+const FAULT: usize = 0;
+const ILLEGAL: usize = 1;
+const EXIT_GROUP: usize = 2;
+
+/// What the crashing thread does.
+static FATAL: AtomicUsize = AtomicUsize::new(FAULT);
+/// Thread that ran the handler of SIGUSR1 last.
+static HANDLED_BY: AtomicIsize = AtomicIsize::new(0);
+static THREAD_TID: AtomicIsize = AtomicIsize::new(0);
+
+fn sleep_forever() -> ! {
+    loop {
+        sleep(10);
+    }
+}
+
+fn raise_fatal() -> ! {
+    sleep(50);
+    match FATAL.load(Ordering::SeqCst) {
+        FAULT => unsafe { core::ptr::null_mut::<u8>().write_volatile(1) },
+        ILLEGAL => unsafe { core::arch::asm!("unimp") },
+        _ => exit_group(EXIT_CODE),
+    }
+    loop {}
+}
+
+/// A process with a few threads sleeping, and its main thread blocked in a
+/// read that never completes, until one more thread does `fatal`. Returns the
+/// wait status.
+fn crash_child(fatal: usize) -> i32 {
+    FATAL.store(fatal, Ordering::SeqCst);
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::empty()) == 0);
+    let pid = fork();
+    if pid == 0 {
+        for _ in 0..THREADS {
+            if create_thread(CloneFlags::THREAD) == 0 {
+                sleep_forever();
+            }
+        }
+        if create_thread(CloneFlags::THREAD) == 0 {
+            raise_fatal();
+        }
+        let mut buf = [0u8; 1];
+        read(fds[0] as usize, &mut buf);
+        exit(100);
+    }
+    let mut status = 0;
+    assert!(wait4(pid, &mut status, 0, None) == pid);
+    close(fds[0] as usize);
+    close(fds[1] as usize);
+    status
+}
+
+fn on_sigusr1(_signal: usize) {
+    HANDLED_BY.store(gettid(), Ordering::SeqCst);
+}
+
+fn signal_thread() -> ! {
+    THREAD_TID.store(gettid(), Ordering::SeqCst);
+    sleep_forever();
+}
+
+/// Wait a while for the handler to run in some thread, returning which.
+fn handled_by() -> isize {
+    for _ in 0..100 {
+        let tid = HANDLED_BY.swap(0, Ordering::SeqCst);
+        if tid != 0 {
+            return tid;
+        }
+        sleep(5);
+    }
+    0
+}
+
+fn test_targets() {
+    let pid = fork();
+    if pid == 0 {
+        let mut act = SigAction::default();
+        act.sa_handler = on_sigusr1 as usize;
+        act.sa_flags = SigActionFlag::SA_RESTART;
+        let mut old = SigAction::default();
+        assert!(sigaction(Sig::SIGUSR1, &act, &mut old) == 0);
+        if create_thread(CloneFlags::THREAD) == 0 {
+            signal_thread();
+        }
+        while THREAD_TID.load(Ordering::SeqCst) == 0 {
+            sleep(5);
+        }
+        let thread = THREAD_TID.load(Ordering::SeqCst);
+        let me = gettid();
+        assert!(me == getpid() && thread != me);
+
+        // tkill and tgkill hit exactly the thread
+        assert!(tkill(thread, Sig::SIGUSR1) == 0);
+        assert!(handled_by() == thread);
+        assert!(tkill(me, Sig::SIGUSR1) == 0);
+        assert!(handled_by() == me);
+        assert!(tgkill(getpid(), thread, Sig::SIGUSR1) == 0);
+        assert!(handled_by() == thread);
+        assert!(tgkill(1, thread, Sig::SIGUSR1) == -ESRCH);
+        assert!(tkill(0, Sig::SIGUSR1) == -EINVAL);
+
+        // kill takes a thread not blocking the signal, also by the tid of a thread
+        let set = SigSet::SIGUSR1;
+        assert!(sigprocmask(SIG_BLOCK, Some(&set), None) == 0);
+        assert!(kill(getpid(), Sig::SIGUSR1) == 0);
+        assert!(handled_by() == thread);
+        assert!(kill(thread, Sig::SIGUSR1) == 0);
+        assert!(handled_by() == thread);
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin group kill test");
+    // a fatal signal of one thread takes the whole process, all the threads
+    // sleeping or blocked included
+    assert!(crash_child(FAULT) & 0x7f == Sig::SIGSEGV.raw() as i32);
+    assert!(crash_child(ILLEGAL) & 0x7f == Sig::SIGILL.raw() as i32);
+    // so does exit_group of a thread other than the main one
+    let status = crash_child(EXIT_GROUP);
+    assert!(status & 0x7f == 0 && (status >> 8) & 0xff == EXIT_CODE);
+
+    test_targets();
+    println!("group kill test passed");
+    0
+}
@@ -0,0 +1,94 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const EACCES: isize = 13;
+const EINVAL: isize = 22;
+const UNCHANGED: u32 = u32::MAX;
+
+/// Run `f` in a child, which may drop its privilege.
+fn in_child(f: impl FnOnce()) {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin groups test");
+    // owned by root, only the group may read it
+    let path = "/tmp/groups_test\0";
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+    assert!(chmod(path, 0o040) == 0);
+
+    // the saved ids follow the rules of setresuid
+    in_child(|| {
+        assert!(setresgid(10, 20, 30) == 0);
+        assert!(getresgid() == (10, 20, 30));
+        assert!(getegid() == 20);
+        assert!(setresuid(1000, 1000, 1000) == 0);
+        assert!(setresgid(30, UNCHANGED, 10) == 0);
+        assert!(getresgid() == (30, 20, 10));
+        assert!(setresgid(UNCHANGED, 99, UNCHANGED) == -EPERM);
+        assert!(getresgid() == (30, 20, 10));
+    });
+
+    // no group of the file
+    in_child(|| {
+        assert!(setgroups(&[]) == 0);
+        assert!(getgroups(&mut []) == 0);
+        assert!(setresgid(1000, 1000, 1000) == 0);
+        assert!(setresuid(1000, 1000, 1000) == 0);
+        assert!(faccessat(path, R_OK) == -EACCES);
+        assert!(faccessat2(path, R_OK, AT_EACCESS) == -EACCES);
+    });
+
+    // the group of the file is a supplementary one
+    in_child(|| {
+        assert!(setgroups(&[5, 0, 5]) == 0);
+        assert!(getgroups(&mut []) == 2);
+        let mut groups = [0; 2];
+        assert!(getgroups(&mut groups) == 2 && groups == [0, 5]);
+        assert!(getgroups(&mut [0; 1]) == -EINVAL);
+        assert!(setresgid(1000, 1000, 1000) == 0);
+        assert!(setresuid(1000, 1000, 1000) == 0);
+        assert!(faccessat(path, R_OK) == 0);
+        assert!(faccessat2(path, R_OK, AT_EACCESS) == 0);
+        assert!(faccessat2(path, W_OK, AT_EACCESS) == -EACCES);
+        // only root may change the groups
+        assert!(setgroups(&[]) == -EPERM);
+    });
+
+    // the filesystem ids are used for the checks, the effective ids are not
+    in_child(|| {
+        assert!(setgroups(&[]) == 0);
+        assert!(setfsuid(1000) == 0);
+        assert!(setfsuid(UNCHANGED) == 1000);
+        assert!(geteuid() == 0);
+        // the filesystem group is still root
+        assert!(faccessat2(path, R_OK, AT_EACCESS) == 0);
+        assert!(setfsgid(1000) == 0);
+        assert!(faccessat2(path, R_OK, AT_EACCESS) == -EACCES);
+        assert!(faccessat(path, R_OK) == 0);
+
+        // an unprivileged process is limited to its own ids
+        assert!(setresuid(1000, 1000, 1000) == 0);
+        assert!(setfsuid(0) == 1000);
+        assert!(setfsuid(UNCHANGED) == 1000);
+        assert!(setresgid(1000, 1000, 1000) == -EPERM);
+    });
+
+    assert!(unlink(path) == 0);
+    println!("groups test pass.");
+    0
+}
@@ -0,0 +1,93 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{string::String, vec::Vec};
+
+use user_lib::*;
+
+const EINVAL: isize = 22;
+
+const CPU1_ONLINE: &str = "/sys/devices/system/cpu/cpu1/online\0";
+const ONLINE: &str = "/sys/devices/system/cpu/online\0";
+
+fn read_all(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+fn write_attr(path: &str, value: &[u8]) -> isize {
+    let fd = openat(path, OpenFlags::O_WRONLY);
+    assert!(fd >= 0);
+    let ret = write(fd as usize, value);
+    close(fd as usize);
+    ret
+}
+
+/// Fork children that yield a lot and check the hart they run on with
+/// `check`.
+fn run_children(check: fn(isize) -> bool) {
+    let mut pids = Vec::new();
+    for _ in 0..4 {
+        let pid = fork();
+        if pid == 0 {
+            for _ in 0..50 {
+                let hart = getcpu();
+                assert!(hart >= 0 && check(hart));
+                yield_();
+            }
+            exit(0);
+        }
+        pids.push(pid);
+    }
+    for pid in pids {
+        let mut exit_code: i32 = 0;
+        assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin hotplug test");
+    let mut online = 0;
+    assert!(sched_getaffinity(0, &mut online) == core::mem::size_of::<usize>() as isize);
+    if online.count_ones() < 2 {
+        println!("only one hart, skipped");
+        println!("hotplug test pass.");
+        return 0;
+    }
+
+    assert!(write_attr(CPU1_ONLINE, b"0\n") == 2);
+    assert!(read_all(CPU1_ONLINE) == "0\n");
+    let list = read_all(ONLINE);
+    println!("online after offlining hart 1: {}", list.trim_end());
+    assert!(list.starts_with("0") && !list.contains('1'));
+    // nothing runs on an offline hart, even when asked to
+    run_children(|hart| hart != 1);
+    assert!(sched_setaffinity(0, 1 << 1) == -EINVAL);
+
+    assert!(write_attr(CPU1_ONLINE, b"1\n") == 2);
+    assert!(read_all(CPU1_ONLINE) == "1\n");
+    assert!(sched_setaffinity(0, 1 << 1) == 0);
+    run_children(|hart| hart == 1);
+    assert!(sched_setaffinity(0, online) == 0);
+
+    assert!(write_attr(CPU1_ONLINE, b"2") == -EINVAL);
+    assert!(read_all(CPU1_ONLINE) == "1\n");
+    println!("hotplug test pass.");
+    0
+}
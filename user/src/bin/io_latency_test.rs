@@ -0,0 +1,102 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::*;
+
+const SEEK_SET: usize = 0;
+
+const DD_FILE: &str = "/io_latency_test_dd\0";
+const DISK: &str = "/dev/vda\0";
+
+/// The dd part writes this many chunks of `DD_CHUNK` bytes, each synced.
+const DD_COUNT: usize = 16;
+const DD_CHUNK: usize = 1024 * 1024;
+const READ_SIZE: usize = 4096;
+/// Small reads cycle over the first blocks of the disk.
+const READ_SPAN: usize = 4096;
+
+fn now() -> Duration {
+    let mut tp = TimeSpec::default();
+    assert!(clock_gettime(CLOCK_MONOTONIC, &mut tp) == 0);
+    tp.into()
+}
+
+/// Write `DD_FILE` in large synced chunks as `dd oflag=dsync` does, and
+/// return the average time of one chunk in microseconds.
+fn dd() -> u64 {
+    let fd = openat(DD_FILE, OpenFlags::O_CREATE | OpenFlags::O_WRONLY);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let buf = [0x5au8; DD_CHUNK];
+    let mut total = Duration::ZERO;
+    for _ in 0..DD_COUNT {
+        let start = now();
+        assert!(write(fd, &buf) == DD_CHUNK as isize);
+        assert!(fsync(fd) == 0);
+        total += now() - start;
+    }
+    close(fd);
+    total.as_micros() as u64 / DD_COUNT as u64
+}
+
+/// A task of best-effort level 0 reads small blocks while a child of the
+/// default level writes large ones. The bands of the block request queue
+/// must serve the reads in between the chunks of the writer, so a read waits
+/// far less than one large write takes.
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin io latency test");
+    assert!(ioprio_set(0, ioprio_value(IOPRIO_CLASS_BE, 0)) == 0);
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::O_NONBLOCK) == 0);
+
+    let pid = fork();
+    if pid == 0 {
+        // the default priority, derived from nice 0
+        assert!(ioprio_set(0, 0) == 0);
+        close(fds[0] as usize);
+        let avg = dd();
+        assert!(write(fds[1] as usize, &avg.to_ne_bytes()) == 8);
+        exit(0);
+    }
+    assert!(pid > 0);
+    close(fds[1] as usize);
+
+    let disk = openat(DISK, OpenFlags::O_RDONLY);
+    assert!(disk >= 0);
+    let disk = disk as usize;
+    let mut buf = [0u8; READ_SIZE];
+    let mut avg = [0u8; 8];
+    let mut reads = 0;
+    let mut max_read = Duration::ZERO;
+    while read(fds[0] as usize, &mut avg) != 8 {
+        let off = (reads % READ_SPAN) * READ_SIZE;
+        let start = now();
+        assert!(lseek(disk, off as isize, SEEK_SET) == off as isize);
+        assert!(read(disk, &mut buf) == READ_SIZE as isize);
+        max_read = max_read.max(now() - start);
+        reads += 1;
+    }
+    close(disk);
+    close(fds[0] as usize);
+
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+    assert!(unlink(DD_FILE) == 0);
+
+    let avg_write = u64::from_ne_bytes(avg);
+    let max_read = max_read.as_micros() as u64;
+    println!(
+        "{} reads, max read {} us, average dd write {} us",
+        reads, max_read, avg_write
+    );
+    assert!(reads >= DD_COUNT);
+    assert!(max_read < avg_write / 2);
+    println!("io latency test pass.");
+    0
+}
@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+const ESRCH: isize = 3;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin ioprio test");
+    assert!(ioprio_get(0) == 0);
+
+    let be4 = ioprio_value(IOPRIO_CLASS_BE, 4);
+    assert!(ioprio_set(0, be4) == 0);
+    assert!(ioprio_get(0) == be4 as isize);
+    assert!(ioprio_get(getpid() as usize) == be4 as isize);
+
+    // invalid class or level
+    assert!(ioprio_set(0, ioprio_value(4, 0)) == -EINVAL);
+    assert!(ioprio_set(0, ioprio_value(IOPRIO_CLASS_BE, 8)) == -EINVAL);
+    assert!(ioprio_get(99999) == -ESRCH);
+
+    let pid = fork();
+    if pid == 0 {
+        // inherited from the parent
+        assert!(ioprio_get(0) == be4 as isize);
+        assert!(setuid(1000) == 0);
+        // lowering the priority is always allowed, raising it is not
+        let be6 = ioprio_value(IOPRIO_CLASS_BE, 6);
+        assert!(ioprio_set(0, be6) == 0);
+        assert!(ioprio_set(0, be4) == -EPERM);
+        assert!(ioprio_set(0, ioprio_value(IOPRIO_CLASS_RT, 0)) == -EPERM);
+        assert!(ioprio_set(0, ioprio_value(IOPRIO_CLASS_IDLE, 0)) == 0);
+        // the parent belongs to root
+        assert!(ioprio_set(getppid() as usize, be6) == -EPERM);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+
+    let rt0 = ioprio_value(IOPRIO_CLASS_RT, 0);
+    assert!(ioprio_set(0, rt0) == 0);
+    assert!(ioprio_get(0) == rt0 as isize);
+    println!("ioprio test pass.");
+    0
+}
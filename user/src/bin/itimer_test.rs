@@ -0,0 +1,143 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use user_lib::*;
+
+const EINTR: isize = 4;
+
+static ALARMS: AtomicUsize = AtomicUsize::new(0);
+static VTALARMS: AtomicUsize = AtomicUsize::new(0);
+static PROFS: AtomicUsize = AtomicUsize::new(0);
+
+fn on_signal(signal: usize) {
+    let counter = if signal == Sig::SIGALRM.raw() {
+        &ALARMS
+    } else if signal == Sig::SIGVTALRM.raw() {
+        &VTALARMS
+    } else {
+        &PROFS
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+fn set_handler(sig: Sig) {
+    let mut new = SigAction::default();
+    let mut old = SigAction::default();
+    new.sa_handler = on_signal as usize;
+    assert!(sigaction(sig, &new, &mut old) == 0);
+}
+
+fn itimerval(value_ms: usize, interval_ms: usize) -> ITimerVal {
+    ITimerVal {
+        it_interval: TimeVal::from_usec(interval_ms * 1000),
+        it_value: TimeVal::from_usec(value_ms * 1000),
+    }
+}
+
+fn now_ms() -> usize {
+    let mut ts = TimeSpec::default();
+    assert!(clock_gettime(CLOCK_MONOTONIC, &mut ts) == 0);
+    ts.into_ms()
+}
+
+/// An alarm interrupts a read blocked on an empty pipe.
+fn test_alarm() {
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::empty()) == 0);
+    let mut buf = [0u8; 1];
+    assert!(alarm(1) == 0);
+    assert!(read(fds[0] as usize, &mut buf) == -EINTR);
+    assert!(ALARMS.load(Ordering::Relaxed) == 1);
+    close(fds[0] as usize);
+    close(fds[1] as usize);
+
+    // the seconds left of a pending alarm, which is cancelled by 0
+    assert!(alarm(5) == 0);
+    assert!(alarm(0) == 5);
+    let mut curr = ITimerVal::ZERO;
+    assert!(getitimer(ITIMER_REAL, &mut curr) == 0);
+    assert!(!curr.is_enabled());
+}
+
+fn test_getitimer() {
+    assert!(setitimer(ITIMER_REAL, &itimerval(2000, 500), None) == 0);
+    let mut curr = ITimerVal::ZERO;
+    assert!(getitimer(ITIMER_REAL, &mut curr) == 0);
+    let left = curr.it_value.into_usec();
+    assert!(left > 1_000_000 && left <= 2_000_000);
+    assert!(curr.it_interval.into_usec() == 500_000);
+
+    let mut old = ITimerVal::ZERO;
+    assert!(setitimer(ITIMER_REAL, &ITimerVal::ZERO, Some(&mut old)) == 0);
+    assert!(old.it_value.into_usec() <= left);
+    assert!(getitimer(3, &mut curr) < 0);
+}
+
+/// A periodic timer fires until it is disarmed.
+fn test_periodic() {
+    let start = ALARMS.load(Ordering::Relaxed);
+    assert!(setitimer(ITIMER_REAL, &itimerval(20, 20), None) == 0);
+    let deadline = now_ms() + 2000;
+    while ALARMS.load(Ordering::Relaxed) < start + 3 {
+        assert!(now_ms() < deadline);
+        sleep(10);
+    }
+    assert!(setitimer(ITIMER_REAL, &ITimerVal::ZERO, None) == 0);
+    let fired = ALARMS.load(Ordering::Relaxed);
+    sleep(100);
+    assert!(ALARMS.load(Ordering::Relaxed) == fired);
+}
+
+/// Timers counting CPU time fire while the process spins.
+fn test_cpu_timers() {
+    for (which, counter) in [(ITIMER_VIRTUAL, &VTALARMS), (ITIMER_PROF, &PROFS)] {
+        assert!(setitimer(which, &itimerval(30, 0), None) == 0);
+        let deadline = now_ms() + 5000;
+        while counter.load(Ordering::Relaxed) == 0 {
+            assert!(now_ms() < deadline);
+        }
+        let mut curr = ITimerVal::ZERO;
+        assert!(getitimer(which, &mut curr) == 0);
+        assert!(!curr.is_enabled());
+    }
+
+    // sleeping takes no CPU time
+    assert!(setitimer(ITIMER_VIRTUAL, &itimerval(50, 0), None) == 0);
+    sleep(200);
+    assert!(VTALARMS.load(Ordering::Relaxed) == 1);
+    let mut curr = ITimerVal::ZERO;
+    assert!(getitimer(ITIMER_VIRTUAL, &mut curr) == 0);
+    assert!(curr.it_value.into_usec() > 0);
+    assert!(setitimer(ITIMER_VIRTUAL, &ITimerVal::ZERO, None) == 0);
+}
+
+/// A timer armed by a process which exits does not fire at it.
+fn test_exit() {
+    let pid = fork();
+    if pid == 0 {
+        assert!(setitimer(ITIMER_REAL, &itimerval(10, 10), None) == 0);
+        exit(0);
+    }
+    sleep(100);
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin itimer test");
+    set_handler(Sig::SIGALRM);
+    set_handler(Sig::SIGVTALRM);
+    set_handler(Sig::SIGPROF);
+    test_alarm();
+    test_getitimer();
+    test_periodic();
+    test_cpu_timers();
+    test_exit();
+    println!("itimer test pass.");
+    0
+}
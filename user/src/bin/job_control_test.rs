@@ -0,0 +1,138 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+
+const WNOHANG: i32 = 1;
+const WUNTRACED: i32 = 2;
+const WCONTINUED: i32 = 8;
+
+const SIG_IGN: usize = 1;
+
+/// The wait status of a continued child.
+const CONTINUED: i32 = 0xffff;
+
+const THREADS: usize = 3;
+
+fn stopped_by(sig: Sig) -> i32 {
+    ((sig.raw() as i32) << 8) | 0x7f
+}
+
+/// A child writing to the pipe every few milliseconds, with `threads` more
+/// threads sleeping meanwhile.
+fn fork_writer(wfd: usize, threads: usize, ignore_cont: bool) -> isize {
+    let pid = fork();
+    if pid == 0 {
+        if ignore_cont {
+            let mut act = SigAction::default();
+            act.sa_handler = SIG_IGN;
+            let mut old = SigAction::default();
+            assert!(sigaction(Sig::SIGCONT, &act, &mut old) == 0);
+        }
+        for _ in 0..threads {
+            if create_thread(CloneFlags::THREAD) == 0 {
+                loop {
+                    sleep(10);
+                }
+            }
+        }
+        loop {
+            write(wfd, b"x");
+            sleep(10);
+        }
+    }
+    pid
+}
+
+/// Read all there is in the pipe, returning how much it was.
+fn drain(rfd: usize) -> usize {
+    let mut buf = [0u8; 64];
+    let mut total = 0;
+    loop {
+        let ret = read(rfd, &mut buf);
+        if ret == -EAGAIN {
+            return total;
+        }
+        assert!(ret > 0);
+        total += ret as usize;
+    }
+}
+
+fn wait_status(pid: isize, options: i32) -> i32 {
+    let mut status = 0;
+    assert!(wait4(pid, &mut status, options, None) == pid);
+    status
+}
+
+/// Stop the child by `sig` and check that it no longer runs.
+fn stop(pid: isize, rfd: usize, sig: Sig) {
+    assert!(kill(pid, sig) == 0);
+    assert!(wait_status(pid, WUNTRACED) == stopped_by(sig));
+    // a write may be on the way while stopping
+    sleep(20);
+    drain(rfd);
+    sleep(100);
+    assert!(drain(rfd) == 0);
+    // reported once, and never without WUNTRACED
+    let mut status = 0;
+    assert!(wait4(pid, &mut status, WNOHANG | WUNTRACED, None) == 0);
+}
+
+/// Continue the child and check that it runs again.
+fn cont(pid: isize, rfd: usize) {
+    assert!(kill(pid, Sig::SIGCONT) == 0);
+    assert!(wait_status(pid, WCONTINUED) == CONTINUED);
+    sleep(100);
+    assert!(drain(rfd) > 0);
+    let mut status = 0;
+    assert!(wait4(pid, &mut status, WNOHANG | WCONTINUED, None) == 0);
+}
+
+fn kill_and_reap(pid: isize) {
+    assert!(kill(pid, Sig::SIGKILL) == 0);
+    assert!(wait_status(pid, 0) & 0x7f == Sig::SIGKILL.raw() as i32);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin job control test");
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::O_NONBLOCK) == 0);
+    let (rfd, wfd) = (fds[0] as usize, fds[1] as usize);
+
+    // stopped and continued, without being reaped
+    let pid = fork_writer(wfd, 0, false);
+    sleep(50);
+    assert!(drain(rfd) > 0);
+    stop(pid, rfd, Sig::SIGSTOP);
+    let mut status = 0;
+    assert!(wait4(pid, &mut status, WNOHANG, None) == 0);
+    cont(pid, rfd);
+    stop(pid, rfd, Sig::SIGTSTP);
+    // a stopped child is killed
+    kill_and_reap(pid);
+
+    // all the threads of a process stop, and exit when killed
+    let pid = fork_writer(wfd, THREADS, false);
+    sleep(50);
+    stop(pid, rfd, Sig::SIGSTOP);
+    cont(pid, rfd);
+    stop(pid, rfd, Sig::SIGSTOP);
+    kill_and_reap(pid);
+
+    // SIGCONT continues even if ignored
+    let pid = fork_writer(wfd, 0, true);
+    sleep(50);
+    stop(pid, rfd, Sig::SIGSTOP);
+    cont(pid, rfd);
+    kill_and_reap(pid);
+
+    close(rfd);
+    close(wfd);
+    println!("job control test passed");
+    0
+}
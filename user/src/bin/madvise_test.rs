@@ -0,0 +1,151 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EINVAL: isize = 22;
+const ENOMEM: isize = 12;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_SHARED: i32 = 0x01;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const MADV_NORMAL: i32 = 0;
+const MADV_SEQUENTIAL: i32 = 2;
+const MADV_WILLNEED: i32 = 3;
+const MADV_DONTNEED: i32 = 4;
+
+const PAGE_SIZE: usize = 4096;
+const PATH: &str = "/tmp/madvise_test\0";
+
+fn map_pages(pages: usize, flags: i32, fd: usize) -> *mut u8 {
+    let addr = mmap(
+        core::ptr::null(),
+        pages * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        flags,
+        fd,
+        0,
+    );
+    assert!(addr > 0);
+    addr as usize as *mut u8
+}
+
+fn page(map: *mut u8, i: usize) -> *mut u8 {
+    unsafe { map.add(i * PAGE_SIZE) }
+}
+
+fn load(map: *mut u8, i: usize) -> u8 {
+    unsafe { page(map, i).read_volatile() }
+}
+
+fn store(map: *mut u8, i: usize, val: u8) {
+    unsafe { page(map, i).write_volatile(val) }
+}
+
+fn advise(map: *mut u8, first: usize, pages: usize, advice: i32) -> isize {
+    madvise(page(map, first), pages * PAGE_SIZE, advice)
+}
+
+fn test_anonymous() {
+    let map = map_pages(3, MAP_PRIVATE | MAP_ANONYMOUS, usize::MAX);
+    for i in 0..3 {
+        store(map, i, b'a' + i as u8);
+    }
+    // the middle page is zero filled again, the mapping stays
+    assert!(advise(map, 1, 1, MADV_DONTNEED) == 0);
+    assert!(load(map, 0) == b'a');
+    assert!(load(map, 1) == 0);
+    assert!(load(map, 2) == b'c');
+    store(map, 1, b'x');
+    assert!(load(map, 1) == b'x');
+    // pages never touched are fine too
+    assert!(advise(map, 0, 3, MADV_DONTNEED) == 0);
+    for i in 0..3 {
+        assert!(load(map, i) == 0);
+    }
+    munmap(map, 3 * PAGE_SIZE);
+
+    // shared anonymous memory keeps its data, also for the other process
+    let map = map_pages(1, MAP_SHARED | MAP_ANONYMOUS, usize::MAX);
+    store(map, 0, b's');
+    let pid = fork();
+    if pid == 0 {
+        assert!(advise(map, 0, 1, MADV_DONTNEED) == 0);
+        assert!(load(map, 0) == b's');
+        store(map, 0, b't');
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    assert!(load(map, 0) == b't');
+    munmap(map, PAGE_SIZE);
+}
+
+fn test_file() {
+    let fd = openat(
+        PATH,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(write(fd, &[b'f'; PAGE_SIZE]) == PAGE_SIZE as isize);
+
+    // the private copy is dropped, the file shows through again
+    let private = map_pages(1, MAP_PRIVATE, fd);
+    store(private, 0, b'p');
+    assert!(load(private, 0) == b'p');
+    assert!(advise(private, 0, 1, MADV_DONTNEED) == 0);
+    assert!(load(private, 0) == b'f');
+
+    // what is stored through a shared mapping stays in the file
+    let shared = map_pages(1, MAP_SHARED, fd);
+    store(shared, 0, b's');
+    assert!(advise(shared, 0, 1, MADV_DONTNEED) == 0);
+    assert!(load(shared, 0) == b's');
+    let mut buf = [0u8; 1];
+    assert!(lseek(fd, 0, 0) == 0);
+    assert!(read(fd, &mut buf) == 1 && buf[0] == b's');
+    assert!(load(private, 0) == b's');
+
+    // read ahead, the page beyond the end of file is left alone
+    munmap(private, PAGE_SIZE);
+    let private = map_pages(2, MAP_PRIVATE, fd);
+    assert!(advise(private, 0, 2, MADV_WILLNEED) == 0);
+    assert!(load(private, 0) == b's');
+    munmap(private, 2 * PAGE_SIZE);
+    munmap(shared, PAGE_SIZE);
+    close(fd);
+    assert!(unlink(PATH) == 0);
+}
+
+fn test_errors() {
+    let map = map_pages(3, MAP_PRIVATE | MAP_ANONYMOUS, usize::MAX);
+    store(map, 0, b'a');
+    // the other advices are accepted
+    assert!(advise(map, 0, 3, MADV_NORMAL) == 0);
+    assert!(advise(map, 0, 3, MADV_SEQUENTIAL) == 0);
+    assert!(advise(map, 0, 0, MADV_DONTNEED) == 0);
+    assert!(advise(map, 0, 1, 100) == -EINVAL);
+    assert!(madvise(unsafe { map.add(1) }, PAGE_SIZE, MADV_DONTNEED) == -EINVAL);
+    // a hole fails the whole call, nothing is dropped
+    assert!(munmap(page(map, 1), PAGE_SIZE) == 0);
+    assert!(advise(map, 0, 3, MADV_DONTNEED) == -ENOMEM);
+    assert!(advise(map, 0, 3, MADV_NORMAL) == -ENOMEM);
+    assert!(load(map, 0) == b'a');
+    munmap(map, 3 * PAGE_SIZE);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin madvise test");
+    test_anonymous();
+    test_file();
+    test_errors();
+    println!("madvise test passed");
+    0
+}
@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String, vec::Vec};
+
+use user_lib::*;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const MAP_SIZE: usize = 4 * 4096;
+
+fn read_maps(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut maps = Vec::new();
+    // read in small pieces to go through the offset handling
+    let mut buf = [0u8; 100];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        maps.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(maps).unwrap()
+}
+
+fn find_area(maps: &str, start: usize) -> Option<&str> {
+    let prefix = format!("{:08x}-{:08x} ", start, start + MAP_SIZE);
+    maps.lines().find(|line| line.starts_with(&prefix))
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin maps test");
+    let maps = read_maps("/proc/self/maps\0");
+    assert!(maps.lines().any(|line| line.ends_with("[stack]")));
+    assert!(maps.lines().any(|line| line.contains("maps_test")));
+
+    let addr = mmap(
+        core::ptr::null(),
+        MAP_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    );
+    assert!(addr > 0);
+    let addr = addr as usize;
+
+    // the content is generated on open, so the new area shows up
+    let maps = read_maps("/proc/self/maps\0");
+    let area = find_area(&maps, addr).expect("mmapped area is not listed");
+    let fields: Vec<&str> = area.split_whitespace().collect();
+    assert!(fields[1] == "rw-p");
+    assert!(fields[2] == "00000000");
+    assert!(fields[4] == "0");
+
+    // the numeric pid names the same task
+    let path = format!("/proc/{}/maps\0", getpid());
+    let maps = read_maps(&path);
+    assert!(find_area(&maps, addr).is_some());
+    assert!(openat("/proc/99999/maps\0", OpenFlags::O_RDONLY) < 0);
+
+    println!("maps test pass.");
+    0
+}
@@ -0,0 +1,153 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{string::String, vec::Vec};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use user_lib::*;
+
+const EINVAL: isize = 22;
+const EPERM: isize = 1;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_SHARED: i32 = 0x01;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const MEMBARRIER_CMD_QUERY: i32 = 0;
+const MEMBARRIER_CMD_GLOBAL: i32 = 1 << 0;
+const MEMBARRIER_CMD_PRIVATE_EXPEDITED: i32 = 1 << 3;
+const MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED: i32 = 1 << 4;
+
+const ROUNDS: usize = 2000;
+
+/// Shared between the two sides of the store buffering test.
+#[repr(C)]
+struct Dekker {
+    round: AtomicUsize,
+    done: AtomicUsize,
+    x: AtomicUsize,
+    y: AtomicUsize,
+    r1: AtomicUsize,
+}
+
+fn read_all(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+/// Acknowledgments of each online hart from `/proc/interrupts`, in the order
+/// of the header.
+fn membarrier_acks() -> Vec<(String, usize)> {
+    let interrupts = read_all("/proc/interrupts\0");
+    let mut lines = interrupts.lines();
+    let harts: Vec<String> = lines
+        .next()
+        .unwrap()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    let line = lines.find(|line| line.starts_with("MBR:")).unwrap();
+    let counts = line
+        .split_whitespace()
+        .skip(1)
+        .take(harts.len())
+        .map(|count| count.parse().unwrap());
+    harts.into_iter().zip(counts).collect()
+}
+
+fn wait_for(counter: &AtomicUsize, value: usize) {
+    while counter.load(Ordering::Acquire) != value {
+        yield_();
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin membarrier test");
+    let supported = membarrier(MEMBARRIER_CMD_QUERY, 0);
+    assert!(supported & MEMBARRIER_CMD_GLOBAL as isize != 0);
+    assert!(membarrier(MEMBARRIER_CMD_QUERY, 1) == -EINVAL);
+    assert!(membarrier(1 << 20, 0) == -EINVAL);
+
+    assert!(membarrier(MEMBARRIER_CMD_PRIVATE_EXPEDITED, 0) == -EPERM);
+    assert!(membarrier(MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED, 0) == 0);
+    assert!(membarrier(MEMBARRIER_CMD_PRIVATE_EXPEDITED, 0) == 0);
+
+    // Run on the first online hart, every other one has to acknowledge before
+    // the call returns.
+    let mut online = 0;
+    sched_getaffinity(0, &mut online);
+    let first = online.trailing_zeros();
+    assert!(sched_setaffinity(0, 1 << first) == 0);
+    let local = alloc::format!("CPU{first}");
+    let before = membarrier_acks();
+    assert!(membarrier(MEMBARRIER_CMD_GLOBAL, 0) == 0);
+    let after = membarrier_acks();
+    assert!(before.len() == after.len());
+    for ((hart, old), (_, new)) in before.iter().zip(after.iter()) {
+        assert!(*hart == local || new > old, "{hart} did not acknowledge");
+    }
+    println!("{} harts acknowledged", after.len() - 1);
+
+    // Store buffering: with a membarrier on the slow side, the fast side needs
+    // no fence, and the two loads can not both miss the stores.
+    let addr = mmap(
+        core::ptr::null(),
+        4096,
+        PROT_READ | PROT_WRITE,
+        MAP_SHARED | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    );
+    assert!(addr > 0);
+    let dekker = unsafe { &*(addr as usize as *const Dekker) };
+    let pid = fork();
+    if pid == 0 {
+        if online.count_ones() > 1 {
+            let second = (online & !(1 << first)).trailing_zeros();
+            sched_setaffinity(0, 1 << second);
+        }
+        for round in 1..=ROUNDS {
+            wait_for(&dekker.round, round);
+            dekker.x.store(1, Ordering::Relaxed);
+            dekker
+                .r1
+                .store(dekker.y.load(Ordering::Relaxed), Ordering::Relaxed);
+            dekker.done.store(round, Ordering::Release);
+        }
+        exit(0);
+    }
+    for round in 1..=ROUNDS {
+        dekker.x.store(0, Ordering::Relaxed);
+        dekker.y.store(0, Ordering::Relaxed);
+        dekker.round.store(round, Ordering::Release);
+        dekker.y.store(1, Ordering::Relaxed);
+        assert!(membarrier(MEMBARRIER_CMD_GLOBAL, 0) == 0);
+        let r2 = dekker.x.load(Ordering::Relaxed);
+        wait_for(&dekker.done, round);
+        let r1 = dekker.r1.load(Ordering::Relaxed);
+        assert!(r1 == 1 || r2 == 1, "both loads missed in round {round}");
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+
+    assert!(sched_setaffinity(0, online) == 0);
+    println!("membarrier test pass.");
+    0
+}
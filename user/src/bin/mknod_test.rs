@@ -0,0 +1,123 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFSOCK: usize = 0o140000;
+const S_IFREG: usize = 0o100000;
+const S_IFCHR: usize = 0o020000;
+
+const EPERM: isize = 1;
+const ENXIO: isize = 6;
+const EEXIST: isize = 17;
+const EADDRINUSE: isize = 98;
+
+/// `makedev(major, minor)` of glibc.
+fn makedev(major: usize, minor: usize) -> usize {
+    (minor & 0xff) | ((major & 0xfff) << 8) | ((minor & !0xff) << 12)
+}
+
+/// A node for /dev/null opens the same device.
+fn test_char_device() {
+    let path = "/tmp/mknod_null\0";
+    let dev = makedev(1, 3);
+    assert!(mknod(path, S_IFCHR | 0o666, dev) == 0);
+    assert!(mknod(path, S_IFCHR | 0o666, dev) == -EEXIST);
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    assert!(st.st_mode & S_IFMT == S_IFCHR as u32);
+    assert!(st.st_rdev == dev as u64);
+
+    let fd = openat(path, OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(write(fd, b"gone") == 4);
+    let mut buf = [0u8; 4];
+    assert!(read(fd, &mut buf) == 0);
+    close(fd);
+    assert!(unlink(path) == 0);
+
+    // a number no device has
+    assert!(mknod(path, S_IFCHR | 0o666, makedev(1, 200)) == 0);
+    assert!(openat(path, OpenFlags::O_RDWR) == -ENXIO);
+    assert!(unlink(path) == 0);
+}
+
+fn test_regular_file() {
+    let path = "/tmp/mknod_reg\0";
+    assert!(mknod(path, S_IFREG | 0o644, 0) == 0);
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    assert!(st.st_mode & S_IFMT == S_IFREG as u32);
+    assert!(st.st_size == 0);
+    assert!(unlink(path) == 0);
+}
+
+/// A socket node is what a unix socket binds to, once.
+fn test_socket_node() {
+    let path = "/tmp/mknod_sock";
+    let addr = SockAddrUn::new(path);
+    assert!(mknod("/tmp/mknod_sock\0", S_IFSOCK | 0o777, 0) == 0);
+    let mut st = Kstat::default();
+    assert!(stat("/tmp/mknod_sock\0", &mut st) == 0);
+    assert!(st.st_mode & S_IFMT == S_IFSOCK as u32);
+
+    let server = socket(AF_UNIX, SOCK_STREAM, 0);
+    assert!(server >= 0);
+    let server = server as usize;
+    assert!(bind(server, &addr) == 0);
+    let other = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(bind(other, &addr) == -EADDRINUSE);
+    close(other);
+
+    assert!(listen(server, 1) == 0);
+    let pid = fork();
+    if pid == 0 {
+        let client = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+        assert!(connect(client, &addr) == 0);
+        assert!(send(client, b"ping") == 4);
+        close(client);
+        exit(0);
+    }
+    let mut peer = SockAddrUn::new("");
+    let conn = accept(server, &mut peer);
+    assert!(conn >= 0);
+    let conn = conn as usize;
+    let mut buf = [0u8; 16];
+    assert!(read(conn, &mut buf) == 4);
+    assert!(&buf[..4] == b"ping");
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    close(conn);
+    close(server);
+    assert!(unlink("/tmp/mknod_sock\0") == 0);
+}
+
+/// Device nodes need CAP_MKNOD, other nodes do not.
+fn test_unprivileged() {
+    let pid = fork();
+    if pid == 0 {
+        assert!(setuid(1000) == 0);
+        let dev = makedev(1, 3);
+        assert!(mknod("/tmp/mknod_denied\0", S_IFCHR | 0o666, dev) == -EPERM);
+        assert!(mknod("/tmp/mknod_denied\0", S_IFSOCK | 0o777, 0) == 0);
+        assert!(unlink("/tmp/mknod_denied\0") == 0);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin mknod test");
+    test_char_device();
+    test_regular_file();
+    test_socket_node();
+    test_unprivileged();
+    println!("mknod test pass.");
+    0
+}
@@ -0,0 +1,151 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_SHARED: i32 = 0x01;
+const MAP_PRIVATE: i32 = 0x02;
+const MS_ASYNC: i32 = 1;
+const MS_SYNC: i32 = 4;
+
+const EINVAL: isize = 22;
+const ENOMEM: isize = 12;
+
+const PAGE_SIZE: usize = 4096;
+const SIGBUS: i32 = 7;
+
+fn wait_child(pid: isize) -> i32 {
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    exit_code
+}
+
+/// Check read() and write() against shared and private maps of the file at
+/// `path`.
+fn coherence(path: &str) {
+    println!("mmap coherence on {path}");
+    let fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let page = [b'a'; PAGE_SIZE];
+    assert!(write(fd, &page) == PAGE_SIZE as isize);
+    assert!(write(fd, &page) == PAGE_SIZE as isize);
+
+    let addr = mmap(
+        core::ptr::null(),
+        2 * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_SHARED,
+        fd,
+        0,
+    );
+    assert!(addr > 0);
+    let map = addr as usize as *mut u8;
+    let at = |offset: usize| unsafe { map.add(offset).read_volatile() };
+    assert!(at(0) == b'a' && at(PAGE_SIZE) == b'a');
+
+    // another process writes the file, the mapping sees it without remapping
+    let pid = fork();
+    if pid == 0 {
+        let fd = openat(path, OpenFlags::O_WRONLY);
+        assert!(fd >= 0);
+        assert!(write(fd as usize, b"bb") == 2);
+        exit(0);
+    }
+    assert!(wait_child(pid) == 0);
+    assert!(at(0) == b'b' && at(1) == b'b' && at(2) == b'a');
+
+    // and the file sees stores through the mapping
+    unsafe { map.add(PAGE_SIZE).write_volatile(b'c') };
+    let mut buf = [0u8; 1];
+    assert!(lseek(fd, PAGE_SIZE as isize, 0) == PAGE_SIZE as isize);
+    assert!(read(fd, &mut buf) == 1 && buf[0] == b'c');
+
+    // which msync writes back, whether the pages were faulted in or not
+    assert!(msync(map, 2 * PAGE_SIZE, MS_SYNC) == 0);
+    assert!(msync(map, PAGE_SIZE, MS_ASYNC) == 0);
+    assert!(msync(map, PAGE_SIZE, MS_ASYNC | MS_SYNC) == -EINVAL);
+    assert!(msync(unsafe { map.add(1) }, PAGE_SIZE, MS_SYNC) == -EINVAL);
+
+    // a child stores through the inherited shared mapping, the parent sees it
+    // through both read() and its own mapping
+    let pid = fork();
+    if pid == 0 {
+        unsafe { map.add(PAGE_SIZE + 1).write_volatile(b'x') };
+        exit(0);
+    }
+    assert!(wait_child(pid) == 0);
+    assert!(lseek(fd, PAGE_SIZE as isize + 1, 0) == PAGE_SIZE as isize + 1);
+    assert!(read(fd, &mut buf) == 1 && buf[0] == b'x');
+    assert!(at(PAGE_SIZE) == b'c' && at(PAGE_SIZE + 1) == b'x');
+
+    // a private map sees write() until it stores to the page, and its stores
+    // never reach the file
+    let addr = mmap(
+        core::ptr::null(),
+        2 * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE,
+        fd,
+        0,
+    );
+    assert!(addr > 0);
+    let private = addr as usize as *mut u8;
+    let private_at = |offset: usize| unsafe { private.add(offset).read_volatile() };
+    assert!(private_at(0) == b'b' && private_at(PAGE_SIZE) == b'c');
+    assert!(lseek(fd, 0, 0) == 0);
+    assert!(write(fd, b"e") == 1);
+    assert!(private_at(0) == b'e');
+    unsafe { private.write_volatile(b'p') };
+    assert!(private_at(0) == b'p' && at(0) == b'e');
+    assert!(lseek(fd, 0, 0) == 0);
+    assert!(read(fd, &mut buf) == 1 && buf[0] == b'e');
+    assert!(lseek(fd, 0, 0) == 0);
+    assert!(write(fd, b"b") == 1);
+    assert!(private_at(0) == b'p' && at(0) == b'b');
+
+    // another process truncates the file below the second page
+    let pid = fork();
+    if pid == 0 {
+        assert!(truncate(path, 10) == 0);
+        exit(0);
+    }
+    assert!(wait_child(pid) == 0);
+    // the rest of the last page reads as zero
+    assert!(at(0) == b'b' && at(10) == 0);
+    let pid = fork();
+    if pid == 0 {
+        at(PAGE_SIZE);
+        exit(0);
+    }
+    assert!(wait_child(pid) & 0x7f == SIGBUS);
+
+    // growing the file again shows new zeroed pages, not the old ones
+    assert!(ftruncate(fd, 2 * PAGE_SIZE as isize) == 0);
+    assert!(at(PAGE_SIZE) == 0);
+    assert!(lseek(fd, PAGE_SIZE as isize, 0) == PAGE_SIZE as isize);
+    assert!(write(fd, b"d") == 1);
+    assert!(at(PAGE_SIZE) == b'd');
+
+    close(fd);
+    unlink(path);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin mmap coherence test");
+    // a file only in memory, and one written back to the disk
+    coherence("/tmp/mmap_coherence_test\0");
+    coherence("/mmap_coherence_test\0");
+    // msync fails on a range that is not mapped
+    assert!(msync(PAGE_SIZE as *const u8, PAGE_SIZE, MS_SYNC) == -ENOMEM);
+    println!("mmap coherence test pass.");
+    0
+}
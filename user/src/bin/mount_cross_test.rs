@@ -0,0 +1,101 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EEXIST: isize = 17;
+
+const MS_BIND: usize = 1 << 12;
+const TMPFS_MAGIC: i64 = 0x01021994;
+
+fn create(path: &str, data: &[u8]) {
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    assert!(write(fd as usize, data) == data.len() as isize);
+    close(fd as usize);
+}
+
+fn read_all(path: &str, buf: &mut [u8]) -> isize {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    if fd < 0 {
+        return fd;
+    }
+    let len = read(fd as usize, buf);
+    close(fd as usize);
+    len
+}
+
+fn ino_of(path: &str) -> u64 {
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    st.st_ino
+}
+
+fn fs_type(path: &str) -> i64 {
+    let mut fs = StatFs::default();
+    assert!(statfs(path, &mut fs) == 0);
+    fs.f_type
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin mount cross test");
+    let mut buf = [0u8; 16];
+    let made = match mkdir("/mnt\0") {
+        0 => true,
+        ret => {
+            assert!(ret == -EEXIST);
+            false
+        }
+    };
+    // a file of the root file system, hidden by the mount
+    create("/mnt/under\0", b"under");
+    let root_type = fs_type("/\0");
+    assert!(root_type != TMPFS_MAGIC);
+    let mnt_ino = ino_of("/mnt\0");
+
+    assert!(mount("none\0", "/mnt\0", "tmpfs\0", 0) == 0);
+    assert!(fs_type("/mnt\0") == TMPFS_MAGIC);
+    assert!(read_all("/mnt/under\0", &mut buf) < 0);
+
+    // a file of the mounted file system, opened through the root
+    create("/mnt/foo\0", b"foo");
+    assert!(read_all("/mnt/foo\0", &mut buf) == 3 && &buf[..3] == b"foo");
+    assert!(fs_type("/mnt/foo\0") == TMPFS_MAGIC);
+    assert!(read_all("/mnt/../mnt/./foo\0", &mut buf) == 3);
+
+    // ".." of the mount root is the parent of the mount point
+    assert!(ino_of("/mnt/..\0") == ino_of("/\0"));
+    assert!(fs_type("/mnt/..\0") == root_type);
+    assert!(chdir("/mnt\0") == 0);
+    assert!(read_all("foo\0", &mut buf) == 3);
+    assert!(read_all("../mnt/foo\0", &mut buf) == 3);
+    assert!(fs_type("..\0") == root_type);
+    assert!(chdir("/\0") == 0);
+
+    // so it is of a bound dentry, back to where the walk came from
+    assert!(mkdir("/mnt/dir\0") == 0);
+    create("/mnt/dir/bar\0", b"bar");
+    assert!(mkdir("/tmp/mount_cross_bind\0") == 0);
+    assert!(mount("/mnt/dir\0", "/tmp/mount_cross_bind\0", "\0", MS_BIND) == 0);
+    assert!(read_all("/tmp/mount_cross_bind/bar\0", &mut buf) == 3 && &buf[..3] == b"bar");
+    assert!(read_all("/tmp/mount_cross_bind/../mount_cross_bind/bar\0", &mut buf) == 3);
+    assert!(read_all("/tmp/mount_cross_bind/../foo\0", &mut buf) < 0);
+    assert!(umount2("/tmp/mount_cross_bind\0", 0) == 0);
+    assert!(rmdir("/tmp/mount_cross_bind\0") == 0);
+
+    // the mount point is back once unmounted
+    assert!(umount2("/mnt\0", 0) == 0);
+    assert!(ino_of("/mnt\0") == mnt_ino);
+    assert!(fs_type("/mnt\0") == root_type);
+    assert!(read_all("/mnt/foo\0", &mut buf) < 0);
+    assert!(read_all("/mnt/under\0", &mut buf) == 5 && &buf[..5] == b"under");
+    assert!(unlink("/mnt/under\0") == 0);
+    if made {
+        assert!(rmdir("/mnt\0") == 0);
+    }
+    println!("mount cross test passed");
+    0
+}
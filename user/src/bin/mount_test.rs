@@ -0,0 +1,92 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const ENOTBLK: isize = 15;
+const ENODEV: isize = 19;
+const ENOTDIR: isize = 20;
+const EINVAL: isize = 22;
+
+const MS_BIND: usize = 1 << 12;
+const S_IFMT: u32 = 0o170000;
+const S_IFBLK: u32 = 0o060000;
+
+fn create(path: &str, data: &[u8]) {
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    assert!(write(fd as usize, data) == data.len() as isize);
+    close(fd as usize);
+}
+
+fn read_all(path: &str, buf: &mut [u8]) -> isize {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    if fd < 0 {
+        return fd;
+    }
+    let len = read(fd as usize, buf);
+    close(fd as usize);
+    len
+}
+
+fn exists(path: &str) -> bool {
+    let mut st = Kstat::default();
+    stat(path, &mut st) == 0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin mount test");
+    let mut buf = [0u8; 16];
+
+    // the file system type is looked up by name
+    assert!(mkdir("/tmp/mount_test\0") == 0);
+    assert!(mount("none\0", "/tmp/mount_test\0", "nofs\0", 0) == -ENODEV);
+    // a disk file system needs a block device as the source
+    assert!(mount("/dev/null\0", "/tmp/mount_test\0", "ext4\0", 0) == -ENOTBLK);
+    assert!(mount("/dev/nodev\0", "/tmp/mount_test\0", "ext4\0", 0) == -ENOENT);
+    let mut st = Kstat::default();
+    assert!(stat("/dev/vda\0", &mut st) == 0 && st.st_mode & S_IFMT == S_IFBLK);
+
+    // a mount hides what is below the mount point until it is unmounted
+    create("/tmp/mount_test/under\0", b"under");
+    assert!(mount("none\0", "/tmp/mount_test\0", "tmpfs\0", 0) == 0);
+    assert!(!exists("/tmp/mount_test/under\0"));
+    create("/tmp/mount_test/over\0", b"over");
+    assert!(read_all("/tmp/mount_test/over\0", &mut buf) == 4 && &buf[..4] == b"over");
+
+    // a bind mount shows the same files at another place
+    assert!(mkdir("/tmp/mount_test_bind\0") == 0);
+    assert!(mount("/tmp/mount_test\0", "/tmp/mount_test_bind\0", "\0", MS_BIND) == 0);
+    assert!(read_all("/tmp/mount_test_bind/over\0", &mut buf) == 4 && &buf[..4] == b"over");
+    create("/tmp/mount_test_bind/both\0", b"both");
+    assert!(read_all("/tmp/mount_test/both\0", &mut buf) == 4 && &buf[..4] == b"both");
+    // a directory is bound on a directory only
+    assert!(
+        mount(
+            "/tmp/mount_test\0",
+            "/tmp/mount_test_bind/over\0",
+            "\0",
+            MS_BIND
+        ) == -ENOTDIR
+    );
+
+    // the source of a bind mount is not a mount point itself
+    assert!(umount2("/tmp/mount_test/over\0", 0) == -EINVAL);
+    assert!(umount2("/tmp/mount_test_bind\0", 0) == 0);
+    assert!(!exists("/tmp/mount_test_bind/both\0"));
+    assert!(exists("/tmp/mount_test/both\0"));
+    assert!(rmdir("/tmp/mount_test_bind\0") == 0);
+
+    assert!(umount2("/tmp/mount_test/\0", 0) == 0);
+    assert!(!exists("/tmp/mount_test/over\0"));
+    assert!(read_all("/tmp/mount_test/under\0", &mut buf) == 5 && &buf[..5] == b"under");
+    assert!(umount2("/tmp/mount_test\0", 0) == -EINVAL);
+    assert!(unlink("/tmp/mount_test/under\0") == 0);
+    assert!(rmdir("/tmp/mount_test\0") == 0);
+    println!("mount test pass.");
+    0
+}
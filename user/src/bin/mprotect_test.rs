@@ -0,0 +1,149 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EFAULT: isize = 14;
+const ENOMEM: isize = 12;
+const EINVAL: isize = 22;
+
+const PROT_NONE: i32 = 0x0;
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 4;
+
+fn page(map: *mut u8, i: usize) -> *mut u8 {
+    unsafe { map.add(i * PAGE_SIZE) }
+}
+
+fn load(map: *mut u8, i: usize) -> u8 {
+    unsafe { page(map, i).read_volatile() }
+}
+
+fn store(map: *mut u8, i: usize, val: u8) {
+    unsafe { page(map, i).write_volatile(val) }
+}
+
+fn protect(map: *mut u8, first: usize, pages: usize, prot: i32) -> isize {
+    mprotect(page(map, first), pages * PAGE_SIZE, prot)
+}
+
+/// Run `f` in a child, returning whether it was killed by SIGSEGV.
+fn segfaults(f: impl FnOnce()) -> bool {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    match status & 0x7f {
+        0 => false,
+        sig => {
+            assert!(sig == Sig::SIGSEGV.raw() as i32);
+            true
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin mprotect test");
+    let addr = mmap(
+        core::ptr::null(),
+        PAGES * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    );
+    assert!(addr > 0);
+    let map = addr as usize as *mut u8;
+    for i in 0..PAGES {
+        store(map, i, b'a' + i as u8);
+    }
+
+    // a read only page in the middle of the mapping
+    assert!(protect(map, 1, 1, PROT_READ) == 0);
+    assert!(load(map, 1) == b'b');
+    assert!(segfaults(|| store(map, 1, 0)));
+    assert!(!segfaults(|| {
+        store(map, 0, 0);
+        store(map, 2, 0);
+    }));
+    // the kernel does not write to it either
+    let fd = openat(
+        "/tmp/mprotect_test\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(write(fd, &[b'x'; 8]) == 8);
+    assert!(lseek(fd, 0, 0) == 0);
+    let ro = unsafe { core::slice::from_raw_parts_mut(page(map, 1), 8) };
+    assert!(read(fd, ro) == -EFAULT);
+    assert!(load(map, 1) == b'b');
+
+    // write access is dropped and given back across the areas split before
+    assert!(protect(map, 0, PAGES, PROT_READ) == 0);
+    assert!(segfaults(|| store(map, 0, 0)));
+    assert!(segfaults(|| store(map, PAGES - 1, 0)));
+    assert!(protect(map, 0, PAGES, PROT_READ | PROT_WRITE) == 0);
+    for i in 0..PAGES {
+        store(map, i, b'A' + i as u8);
+        assert!(load(map, i) == b'A' + i as u8);
+    }
+
+    // no access at all, then the data is back
+    assert!(protect(map, 2, 1, PROT_NONE) == 0);
+    assert!(segfaults(|| {
+        load(map, 2);
+    }));
+    assert!(segfaults(|| store(map, 2, 0)));
+    let none = unsafe { core::slice::from_raw_parts(page(map, 2), 8) };
+    assert!(write(fd, none) == -EFAULT);
+    assert!(!segfaults(|| {
+        load(map, 1);
+        load(map, 3);
+    }));
+    assert!(protect(map, 2, 1, PROT_READ) == 0);
+    assert!(load(map, 2) == b'C');
+
+    // the pages shared with a child are still copied on write
+    assert!(protect(map, 0, PAGES, PROT_READ | PROT_WRITE) == 0);
+    assert!(!segfaults(|| {
+        assert!(protect(map, 0, PAGES, PROT_READ) == 0);
+        assert!(protect(map, 0, PAGES, PROT_READ | PROT_WRITE) == 0);
+        store(map, 0, b'z');
+        assert!(load(map, 0) == b'z');
+    }));
+    assert!(load(map, 0) == b'A');
+    assert!(protect(map, 0, 1, PROT_READ) == 0);
+    assert!(protect(map, 0, 1, PROT_READ | PROT_WRITE) == 0);
+    store(map, 0, b'y');
+    assert!(load(map, 0) == b'y');
+
+    // a hole fails the whole call, nothing is changed
+    assert!(munmap(page(map, 1), PAGE_SIZE) == 0);
+    assert!(protect(map, 0, PAGES, PROT_READ) == -ENOMEM);
+    store(map, 0, b'x');
+    store(map, 2, b'x');
+    assert!(protect(map, 1, 1, PROT_READ) == -ENOMEM);
+
+    // errors of the arguments
+    assert!(mprotect(unsafe { map.add(1) }, PAGE_SIZE, PROT_READ) == -EINVAL);
+    assert!(protect(map, 0, 1, 0x8) == -EINVAL);
+    assert!(protect(map, 0, 0, PROT_READ) == 0);
+
+    close(fd);
+    assert!(unlink("/tmp/mprotect_test\0") == 0);
+    munmap(map, PAGES * PAGE_SIZE);
+    println!("mprotect test passed");
+    0
+}
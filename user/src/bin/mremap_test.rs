@@ -0,0 +1,192 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EFAULT: isize = 14;
+const ENOMEM: isize = 12;
+const EINVAL: isize = 22;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_SHARED: i32 = 0x01;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_FIXED: i32 = 0x10;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const MREMAP_MAYMOVE: i32 = 0x1;
+const MREMAP_FIXED: i32 = 0x2;
+
+const PAGE_SIZE: usize = 4096;
+
+fn map_pages(addr: *mut u8, pages: usize, flags: i32) -> *mut u8 {
+    let ret = mmap(
+        addr,
+        pages * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        flags | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    );
+    assert!(ret > 0);
+    ret as usize as *mut u8
+}
+
+fn page(map: *mut u8, i: usize) -> *mut u8 {
+    unsafe { map.add(i * PAGE_SIZE) }
+}
+
+fn load(map: *mut u8, i: usize) -> u8 {
+    unsafe { page(map, i).read_volatile() }
+}
+
+fn store(map: *mut u8, i: usize, val: u8) {
+    unsafe { page(map, i).write_volatile(val) }
+}
+
+fn remap(map: *mut u8, old_pages: usize, new_pages: usize, flags: i32, new: *mut u8) -> isize {
+    mremap(
+        map,
+        old_pages * PAGE_SIZE,
+        new_pages * PAGE_SIZE,
+        flags,
+        new,
+    )
+}
+
+/// Run `f` in a child, returning whether it was killed by SIGSEGV.
+fn segfaults(f: impl FnOnce()) -> bool {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    match status & 0x7f {
+        0 => false,
+        sig => {
+            assert!(sig == Sig::SIGSEGV.raw() as i32);
+            true
+        }
+    }
+}
+
+fn test_in_place() {
+    let map = map_pages(core::ptr::null_mut(), 4, MAP_PRIVATE);
+    assert!(munmap(page(map, 2), 2 * PAGE_SIZE) == 0);
+    store(map, 0, b'a');
+    store(map, 1, b'b');
+
+    // the range after the mapping is free, so it grows where it is
+    assert!(remap(map, 2, 4, 0, core::ptr::null_mut()) == map as isize);
+    assert!(load(map, 0) == b'a' && load(map, 1) == b'b');
+    assert!(load(map, 3) == 0);
+    store(map, 3, b'd');
+
+    // and shrinks where it is, the tail is gone
+    assert!(remap(map, 4, 1, 0, core::ptr::null_mut()) == map as isize);
+    assert!(load(map, 0) == b'a');
+    assert!(segfaults(|| {
+        load(map, 1);
+    }));
+    assert!(munmap(map, PAGE_SIZE) == 0);
+}
+
+fn test_move() {
+    let map = map_pages(core::ptr::null_mut(), 2, MAP_PRIVATE);
+    let guard = map_pages(page(map, 2), 1, MAP_PRIVATE | MAP_FIXED);
+    store(map, 0, b'a');
+    store(map, 1, b'b');
+    assert!(mprotect(map, 2 * PAGE_SIZE, PROT_READ) == 0);
+
+    // a range covering more than one mapping can not be remapped
+    assert!(remap(map, 3, 3, MREMAP_MAYMOVE, core::ptr::null_mut()) == -EFAULT);
+    // no room to grow without moving
+    assert!(remap(map, 2, 4, 0, core::ptr::null_mut()) == -ENOMEM);
+    assert!(load(map, 1) == b'b');
+
+    let new = remap(map, 2, 4, MREMAP_MAYMOVE, core::ptr::null_mut());
+    assert!(new > 0 && new != map as isize);
+    let new = new as usize as *mut u8;
+    assert!(load(new, 0) == b'a' && load(new, 1) == b'b');
+    // the permissions move along, and cover the grown part
+    assert!(segfaults(|| store(new, 1, 0)));
+    assert!(segfaults(|| store(new, 3, 0)));
+    assert!(load(new, 3) == 0);
+    // the old range is unmapped, the guard is untouched
+    assert!(segfaults(|| {
+        load(map, 0);
+    }));
+    store(guard, 0, b'g');
+    assert!(munmap(new, 4 * PAGE_SIZE) == 0);
+    assert!(munmap(guard, PAGE_SIZE) == 0);
+}
+
+fn test_fixed() {
+    let map = map_pages(core::ptr::null_mut(), 2, MAP_PRIVATE);
+    let target = map_pages(core::ptr::null_mut(), 3, MAP_PRIVATE);
+    store(map, 0, b'a');
+    store(map, 1, b'b');
+    store(target, 0, b't');
+
+    // the old and the new range may not overlap
+    assert!(remap(map, 2, 2, MREMAP_MAYMOVE | MREMAP_FIXED, page(map, 1)) == -EINVAL);
+    // what is mapped at the new address is replaced
+    assert!(remap(map, 2, 3, MREMAP_MAYMOVE | MREMAP_FIXED, target) == target as isize);
+    assert!(load(target, 0) == b'a' && load(target, 1) == b'b');
+    assert!(load(target, 2) == 0);
+    assert!(munmap(target, 3 * PAGE_SIZE) == 0);
+}
+
+fn test_shared() {
+    let map = map_pages(core::ptr::null_mut(), 1, MAP_SHARED);
+    store(map, 0, b's');
+    let new = remap(map, 1, 3, MREMAP_MAYMOVE, core::ptr::null_mut());
+    assert!(new > 0);
+    let new = new as usize as *mut u8;
+    assert!(load(new, 0) == b's');
+
+    // still shared with a child after moving
+    let pid = fork();
+    if pid == 0 {
+        store(new, 2, b'c');
+        exit(0);
+    }
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid && status == 0);
+    assert!(load(new, 2) == b'c');
+    assert!(munmap(new, 3 * PAGE_SIZE) == 0);
+}
+
+fn test_errors() {
+    let map = map_pages(core::ptr::null_mut(), 2, MAP_PRIVATE);
+    let null = core::ptr::null_mut();
+    // misaligned or empty
+    assert!(mremap(page(map, 0).wrapping_add(1), PAGE_SIZE, PAGE_SIZE, 0, null) == -EINVAL);
+    assert!(remap(map, 0, 1, 0, null) == -EINVAL);
+    assert!(remap(map, 1, 0, 0, null) == -EINVAL);
+    // unknown flags, or a fixed address that may not be moved to
+    assert!(remap(map, 1, 1, 0x100, null) == -EINVAL);
+    assert!(remap(map, 1, 1, MREMAP_FIXED, page(map, 4)) == -EINVAL);
+    // not mapped, or beyond the mapping
+    assert!(munmap(map, 2 * PAGE_SIZE) == 0);
+    assert!(remap(map, 1, 2, MREMAP_MAYMOVE, null) == -EFAULT);
+    let map = map_pages(core::ptr::null_mut(), 2, MAP_PRIVATE);
+    assert!(remap(map, 3, 4, MREMAP_MAYMOVE, null) == -EFAULT);
+    assert!(munmap(map, 2 * PAGE_SIZE) == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin mremap test");
+    test_in_place();
+    test_move();
+    test_fixed();
+    test_shared();
+    test_errors();
+    println!("mremap test passed");
+    0
+}
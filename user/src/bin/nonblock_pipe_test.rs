@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const F_GETFL: isize = 3;
+const F_SETFL: isize = 4;
+const EAGAIN: isize = 11;
+const EPIPE: isize = 32;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin nonblock pipe test");
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::O_NONBLOCK) == 0);
+    let (rfd, wfd) = (fds[0] as usize, fds[1] as usize);
+    assert!(fcntl(rfd, F_GETFL, 0) as usize & OpenFlags::O_NONBLOCK.bits() as usize != 0);
+
+    // empty pipe
+    let mut buf = [0u8; 64];
+    assert!(read(rfd, &mut buf) == -EAGAIN);
+
+    // fill the pipe until it would block
+    let chunk = [0x5au8; 512];
+    let mut total = 0;
+    loop {
+        let ret = write(wfd, &chunk);
+        if ret == -EAGAIN {
+            break;
+        }
+        assert!(ret > 0);
+        total += ret as usize;
+    }
+    assert!(total > 0);
+    assert!(read(rfd, &mut buf) == 64);
+
+    // clear O_NONBLOCK on the read end, the access mode is kept
+    assert!(fcntl(rfd, F_SETFL, 0) == 0);
+    assert!(fcntl(rfd, F_GETFL, 0) as usize & OpenFlags::O_NONBLOCK.bits() as usize == 0);
+    assert!(read(rfd, &mut buf) == 64);
+
+    // set it through F_SETFL on a blocking pipe
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::empty()) == 0);
+    let (rfd2, wfd2) = (fds[0] as usize, fds[1] as usize);
+    assert!(fcntl(rfd2, F_SETFL, OpenFlags::O_NONBLOCK.bits() as usize) == 0);
+    assert!(read(rfd2, &mut buf) == -EAGAIN);
+    assert!(write(wfd2, b"hi") == 2);
+    assert!(read(rfd2, &mut buf) == 2);
+
+    // end of file and broken pipe are reported instead of EAGAIN
+    close(wfd2);
+    assert!(read(rfd2, &mut buf) == 0);
+    close(rfd);
+    assert!(write(wfd, b"x") == -EPIPE);
+    println!("nonblock pipe test pass.");
+    0
+}
@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const SEEK_SET: usize = 0;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin offset test");
+    let fd = openat(
+        "/tmp/offset_test\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(write(fd, b"0123456789abcdef") == 16);
+    assert!(lseek(fd, 0, SEEK_SET) == 0);
+
+    // dup'd fds share one offset
+    let dup_fd = dup(fd) as usize;
+    let mut buf = [0u8; 4];
+    assert!(read(fd, &mut buf) == 4 && &buf == b"0123");
+    assert!(read(dup_fd, &mut buf) == 4 && &buf == b"4567");
+
+    // parent and child share the offset after fork
+    let pid = fork();
+    if pid == 0 {
+        assert!(read(fd, &mut buf) == 4 && &buf == b"89ab");
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    assert!(read(dup_fd, &mut buf) == 4 && &buf == b"cdef");
+    assert!(lseek(fd, 0, 1) == 16);
+    println!("offset test pass.");
+    0
+}
@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+
+const S_IFIFO: usize = 0o010000;
+const F_GETFL: isize = 3;
+const F_SETFL: isize = 4;
+const SEEK_CUR: usize = 1;
+
+const TCGETS: usize = 0x5401;
+const TCSETS: usize = 0x5402;
+/// `c_lflag` of `struct termios`, after the input, output and control flags.
+const LFLAG: usize = 3;
+const ECHO: u32 = 0o10;
+
+fn is_nonblock(fd: usize) -> bool {
+    let flags = fcntl(fd, F_GETFL, 0);
+    assert!(flags >= 0);
+    flags as usize & OpenFlags::O_NONBLOCK.bits() as usize != 0
+}
+
+/// Two opens of a FIFO have their own status flags, but read the same data.
+fn test_fifo() {
+    let path = "/tmp/open_file_fifo\0";
+    assert!(mknod(path, S_IFIFO | 0o644, 0) == 0);
+    let r1 = openat(path, OpenFlags::O_RDONLY | OpenFlags::O_NONBLOCK) as usize;
+    let r2 = openat(path, OpenFlags::O_RDONLY | OpenFlags::O_NONBLOCK) as usize;
+    let w = openat(path, OpenFlags::O_WRONLY);
+    assert!(w >= 0);
+    let w = w as usize;
+
+    assert!(fcntl(r2, F_SETFL, 0) == 0);
+    assert!(is_nonblock(r1) && !is_nonblock(r2));
+    let mut buf = [0u8; 4];
+    assert!(read(r1, &mut buf) == -EAGAIN);
+
+    assert!(write(w, b"ab") == 2);
+    assert!(read(r2, &mut buf[..1]) == 1 && buf[0] == b'a');
+    assert!(read(r1, &mut buf) == 1 && buf[0] == b'b');
+
+    // a dup is the same open file, and shares the flags
+    let dup_fd = dup(r2) as usize;
+    assert!(fcntl(dup_fd, F_SETFL, OpenFlags::O_NONBLOCK.bits() as usize) == 0);
+    assert!(is_nonblock(r2));
+    for fd in [dup_fd, r1, r2, w] {
+        close(fd);
+    }
+    assert!(unlink(path) == 0);
+}
+
+/// Two opens of the tty have their own status flags, while the terminal
+/// settings are shared.
+fn test_tty() {
+    let t1 = openat("/dev/tty\0", OpenFlags::O_RDWR);
+    let t2 = openat("/dev/tty\0", OpenFlags::O_RDWR | OpenFlags::O_NONBLOCK);
+    assert!(t1 >= 0 && t2 >= 0);
+    let (t1, t2) = (t1 as usize, t2 as usize);
+    assert!(!is_nonblock(t1) && is_nonblock(t2));
+    assert!(fcntl(t1, F_SETFL, OpenFlags::O_NONBLOCK.bits() as usize) == 0);
+    assert!(fcntl(t2, F_SETFL, 0) == 0);
+    assert!(is_nonblock(t1) && !is_nonblock(t2));
+
+    let mut termios = [0u32; 9];
+    assert!(ioctl(t1, TCGETS, termios.as_mut_ptr() as usize) == 0);
+    let saved = termios;
+    termios[LFLAG] ^= ECHO;
+    assert!(ioctl(t2, TCSETS, termios.as_ptr() as usize) == 0);
+    let mut seen = [0u32; 9];
+    assert!(ioctl(t1, TCGETS, seen.as_mut_ptr() as usize) == 0);
+    assert!(seen[LFLAG] == termios[LFLAG]);
+    assert!(ioctl(t1, TCSETS, saved.as_ptr() as usize) == 0);
+    close(t1);
+    close(t2);
+}
+
+/// Two opens of a directory read its entries from their own positions.
+fn test_dir() {
+    let d1 = openat("/dev\0", OpenFlags::O_RDONLY) as usize;
+    let d2 = openat("/dev\0", OpenFlags::O_RDONLY) as usize;
+    let mut buf1 = [0u8; 512];
+    let mut buf2 = [0u8; 512];
+    let len = getdents64(d1, &mut buf1);
+    assert!(len > 0);
+    assert!(lseek(d1, 0, SEEK_CUR) > 0);
+    assert!(lseek(d2, 0, SEEK_CUR) == 0);
+    assert!(getdents64(d2, &mut buf2) == len);
+    assert!(buf1[..len as usize] == buf2[..len as usize]);
+    close(d1);
+    close(d2);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin open file test");
+    test_fifo();
+    test_tty();
+    test_dir();
+    println!("open file test pass.");
+    0
+}
@@ -0,0 +1,98 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::mem::size_of;
+
+use user_lib::*;
+
+const EXDEV: isize = 18;
+const E2BIG: isize = 7;
+const EINVAL: isize = 22;
+
+fn how(resolve: u64) -> OpenHow {
+    OpenHow {
+        flags: OpenFlags::O_RDONLY.bits() as u64,
+        mode: 0,
+        resolve,
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin openat2 test");
+    assert!(mkdir("/tmp/openat2_test\0") == 0);
+    let fd = openat(
+        "/tmp/openat2_test/file\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    close(fd as usize);
+    let dirfd = openat("/tmp/openat2_test\0", OpenFlags::O_DIRECTORY);
+    assert!(dirfd >= 0);
+    let size = size_of::<OpenHow>();
+
+    // RESOLVE_BENEATH keeps the walk under dirfd
+    let fd = openat2(dirfd, "file\0", &how(RESOLVE_BENEATH), size);
+    assert!(fd >= 0);
+    close(fd as usize);
+    let fd = openat2(dirfd, "../openat2_test/file\0", &how(0), size);
+    assert!(fd >= 0);
+    close(fd as usize);
+    let ret = openat2(dirfd, "../openat2_test/file\0", &how(RESOLVE_BENEATH), size);
+    assert!(ret == -EXDEV);
+    let ret = openat2(
+        dirfd,
+        "/tmp/openat2_test/file\0",
+        &how(RESOLVE_BENEATH),
+        size,
+    );
+    assert!(ret == -EXDEV);
+
+    // RESOLVE_NO_XDEV refuses to walk from the root into the /tmp mount
+    let rootfd = openat("/\0", OpenFlags::O_DIRECTORY);
+    assert!(rootfd >= 0);
+    let ret = openat2(
+        rootfd,
+        "tmp/openat2_test/file\0",
+        &how(RESOLVE_NO_XDEV),
+        size,
+    );
+    assert!(ret == -EXDEV);
+    let fd = openat2(dirfd, "file\0", &how(RESOLVE_NO_XDEV), size);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    // size and flags validation
+    assert!(openat2(dirfd, "file\0", &how(0), size - 1) == -EINVAL);
+    assert!(openat2(dirfd, "file\0", &how(0x80), size) == -EINVAL);
+    let mut bad_mode = how(0);
+    bad_mode.mode = 0o644;
+    assert!(openat2(dirfd, "file\0", &bad_mode, size) == -EINVAL);
+    #[repr(C)]
+    struct OpenHowExt {
+        how: OpenHow,
+        ext: u64,
+    }
+    let ext = OpenHowExt {
+        how: how(0),
+        ext: 1,
+    };
+    let ret = openat2(dirfd, "file\0", &ext.how, size_of::<OpenHowExt>());
+    assert!(ret == -E2BIG);
+    let ext = OpenHowExt {
+        how: how(0),
+        ext: 0,
+    };
+    let fd = openat2(dirfd, "file\0", &ext.how, size_of::<OpenHowExt>());
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    close(rootfd as usize);
+    close(dirfd as usize);
+    assert!(unlink("/tmp/openat2_test/file\0") == 0);
+    assert!(rmdir("/tmp/openat2_test\0") == 0);
+    println!("openat2 test pass.");
+    0
+}
@@ -0,0 +1,113 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String};
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const EEXIST: isize = 17;
+const ENOTDIR: isize = 20;
+const EISDIR: isize = 21;
+
+const S_IFREG: usize = 0o100000;
+
+fn exists(path: &str) -> bool {
+    let mut st = Kstat::default();
+    stat(path, &mut st) == 0
+}
+
+/// Create a name at the end of nested paths, written in all the ways a path
+/// may name its parent.
+fn test_nested(base: &str) {
+    let mut dir = String::from(base);
+    for level in 0..4 {
+        assert!(mkdir(&format!("{dir}\0")) == 0);
+        assert!(mkdir(&format!("{dir}\0")) == -EEXIST);
+        assert!(mknod(&format!("{dir}/f{level}\0"), S_IFREG | 0o644, 0) == 0);
+        assert!(exists(&format!("{dir}/f{level}\0")));
+        dir = format!("{dir}/d{level}");
+    }
+    // redundant slashes, "." and ".." on the way to the parent
+    assert!(mkdir(&format!("{base}//d0/./d1/../d1/new/\0")) == 0);
+    assert!(exists(&format!("{base}/d0/d1/new\0")));
+    assert!(symlink("f0\0", &format!("{base}/d0/../link\0")) == 0);
+    assert!(exists(&format!("{base}/link\0")));
+    // a parent reached through a symlink
+    assert!(symlink("d0/d1\0", &format!("{base}/to_d1\0")) == 0);
+    assert!(mkdir(&format!("{base}/to_d1/via_link\0")) == 0);
+    assert!(exists(&format!("{base}/d0/d1/via_link\0")));
+    assert!(rmdir(&format!("{base}/to_d1/via_link/\0")) == 0);
+    assert!(!exists(&format!("{base}/d0/d1/via_link\0")));
+
+    // relative to the cwd and to a directory fd
+    assert!(chdir(&format!("{base}/d0\0")) == 0);
+    assert!(mkdir("d1/rel\0") == 0);
+    assert!(exists(&format!("{base}/d0/d1/rel\0")));
+    let fd = openat(&format!("{base}/d0/d1\0"), OpenFlags::O_DIRECTORY);
+    assert!(fd >= 0);
+    assert!(unlinkat(fd, "rel\0", AT_REMOVEDIR) == 0);
+    assert!(!exists(&format!("{base}/d0/d1/rel\0")));
+    close(fd as usize);
+    assert!(chdir("/\0") == 0);
+}
+
+fn test_bad_parent(base: &str) {
+    // a missing parent, or one that is no directory
+    assert!(mkdir(&format!("{base}/missing/new\0")) == -ENOENT);
+    assert!(mknod(&format!("{base}/missing/new\0"), S_IFREG, 0) == -ENOENT);
+    assert!(symlink("x\0", &format!("{base}/missing/new\0")) == -ENOENT);
+    assert!(unlink(&format!("{base}/missing/new\0")) == -ENOENT);
+    assert!(mkdir(&format!("{base}/f0/new\0")) == -ENOTDIR);
+    assert!(mknod(&format!("{base}/link/new\0"), S_IFREG, 0) == -ENOTDIR);
+    assert!(unlink(&format!("{base}/f0/new\0")) == -ENOTDIR);
+
+    // the last component is ".", "..", or the root
+    assert!(mkdir(&format!("{base}/d0/.\0")) == -EEXIST);
+    assert!(mkdir(&format!("{base}/d0/..\0")) == -EEXIST);
+    assert!(mkdir("/\0") == -EEXIST);
+    assert!(symlink("x\0", &format!("{base}/.\0")) == -EEXIST);
+    assert!(unlink(&format!("{base}/d0/.\0")) == -EISDIR);
+    assert!(unlink("/\0") == -EISDIR);
+
+    // a trailing slash names a directory
+    assert!(unlink(&format!("{base}/f0/\0")) == -ENOTDIR);
+    assert!(mknod(&format!("{base}/node/\0"), S_IFREG, 0) == -ENOENT);
+    assert!(!exists(&format!("{base}/node\0")));
+    assert!(mkdir(&format!("{base}/f0/\0")) == -EEXIST);
+}
+
+fn remove(base: &str) {
+    for path in [
+        "d0/d1/d2/f3",
+        "d0/d1/d2",
+        "d0/d1/f2",
+        "d0/d1/new",
+        "d0/d1",
+        "d0/f1",
+        "d0",
+        "f0",
+        "link",
+        "to_d1",
+    ] {
+        let path = format!("{base}/{path}\0");
+        if unlink(&path) != 0 {
+            assert!(rmdir(&path) == 0);
+        }
+    }
+    assert!(rmdir(&format!("{base}\0")) == 0);
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    for base in ["/tmp/parent_name_test", "/parent_name_test"] {
+        test_nested(base);
+        test_bad_parent(base);
+        remove(base);
+    }
+    println!("parent_name_test passed");
+    0
+}
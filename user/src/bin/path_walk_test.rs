@@ -0,0 +1,104 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String};
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const ENAMETOOLONG: isize = 36;
+const ELOOP: isize = 40;
+
+const AT_FDCWD: isize = -100;
+const AT_EMPTY_PATH: i32 = 0x1000;
+
+/// Symlinks a single lookup may follow.
+const MAX_SYMLINK_FOLLOWS: usize = 40;
+/// Components a single lookup may resolve.
+const MAX_PATH_COMPONENTS: usize = 4096;
+
+const DIR: &str = "/tmp/path_walk_test";
+
+fn ino_of(path: &str) -> isize {
+    let mut st = Kstat::default();
+    let ret = stat(path, &mut st);
+    if ret < 0 {
+        return ret;
+    }
+    st.st_ino as isize
+}
+
+/// A path of `n` ".." components from the root.
+fn dotdots(n: usize) -> String {
+    let mut path = String::from("/");
+    for _ in 0..n {
+        path.push_str("../");
+    }
+    path.push('\0');
+    path
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin path walk test");
+    assert!(mkdir(&format!("{DIR}\0")) == 0);
+    let file = format!("{DIR}/file\0");
+    let fd = openat(&file, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+    let root = ino_of("/\0");
+
+    // an empty path names nothing, unless AT_EMPTY_PATH is given
+    let mut st = Kstat::default();
+    assert!(stat("\0", &mut st) == -ENOENT);
+    assert!(openat("\0", OpenFlags::O_RDONLY) == -ENOENT);
+    assert!(chdir("\0") == -ENOENT);
+    assert!(fstatat(AT_FDCWD, "\0", &mut st, AT_EMPTY_PATH) == 0);
+
+    // a path of slashes only is the root
+    assert!(ino_of("////\0") == root);
+    assert!(ino_of(&format!("//{DIR}//.///\0")) == ino_of(&format!("{DIR}\0")));
+    let fd = openat("////\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    // ".." of the root stays there, up to the component limit
+    assert!(ino_of(&dotdots(MAX_PATH_COMPONENTS)) == root);
+    assert!(ino_of(&dotdots(MAX_PATH_COMPONENTS + 1)) == -ENAMETOOLONG);
+
+    // a chain of symlinks is followed up to the limit
+    for i in 0..MAX_SYMLINK_FOLLOWS + 1 {
+        let target = match i {
+            MAX_SYMLINK_FOLLOWS => String::from("file\0"),
+            _ => format!("l{}\0", i + 1),
+        };
+        assert!(symlink(&target, &format!("{DIR}/l{i}\0")) == 0);
+    }
+    assert!(ino_of(&format!("{DIR}/l1\0")) == ino_of(&file));
+    assert!(ino_of(&format!("{DIR}/l0\0")) == -ELOOP);
+
+    // cycles fail, also through the components before the last one
+    assert!(symlink("a\0", &format!("{DIR}/b\0")) == 0);
+    assert!(symlink("b\0", &format!("{DIR}/a\0")) == 0);
+    assert!(ino_of(&format!("{DIR}/a\0")) == -ELOOP);
+    assert!(ino_of(&format!("{DIR}/a/file\0")) == -ELOOP);
+    assert!(openat(&format!("{DIR}/b\0"), OpenFlags::O_RDONLY) == -ELOOP);
+    // the symlinks of a symlink count against the same limit
+    assert!(symlink("y/x\0", &format!("{DIR}/x\0")) == 0);
+    assert!(symlink("x/y\0", &format!("{DIR}/y\0")) == 0);
+    assert!(ino_of(&format!("{DIR}/x\0")) == -ELOOP);
+    assert!(chdir(&format!("{DIR}/y\0")) == -ELOOP);
+
+    for name in ["a", "b", "x", "y", "file"] {
+        assert!(unlink(&format!("{DIR}/{name}\0")) == 0);
+    }
+    for i in 0..MAX_SYMLINK_FOLLOWS + 1 {
+        assert!(unlink(&format!("{DIR}/l{i}\0")) == 0);
+    }
+    assert!(rmdir(&format!("{DIR}\0")) == 0);
+    println!("path walk test passed");
+    0
+}
@@ -0,0 +1,191 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::mem::size_of;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+
+const PATH: &str = "/tmp/peercred_test";
+
+fn peercred(fd: usize) -> UCred {
+    let mut cred = UCred::default();
+    let buf = unsafe {
+        core::slice::from_raw_parts_mut(&mut cred as *mut UCred as *mut u8, size_of::<UCred>())
+    };
+    let mut len = buf.len() as u32;
+    assert!(getsockopt(fd, SOL_SOCKET, SO_PEERCRED, buf, &mut len) == 0);
+    assert!(len as usize == size_of::<UCred>());
+    cred
+}
+
+fn passcred(fd: usize) {
+    assert!(setsockopt(fd, SOL_SOCKET, SO_PASSCRED, &1i32.to_ne_bytes()) == 0);
+}
+
+fn current() -> UCred {
+    UCred {
+        pid: getpid() as u32,
+        uid: geteuid() as u32,
+        gid: getegid() as u32,
+    }
+}
+
+/// Send `data` with the credentials `cred` if some.
+fn send_cred(fd: usize, data: &[u8], cred: Option<UCred>) -> isize {
+    let iov = [IoVec {
+        base: data.as_ptr() as usize,
+        len: data.len(),
+    }];
+    let mut msg = MsgHdr::new(&iov);
+    let mut cmsg = CMsgCred::default();
+    if let Some(cred) = cred {
+        cmsg = CMsgCred::new(cred);
+        msg.control = &cmsg as *const CMsgCred as usize;
+        msg.controllen = size_of::<CMsgCred>();
+    }
+    sendmsg(fd, &msg, 0)
+}
+
+/// Receive into `buf`, returning the length, the credentials received and the
+/// flags of the message. `controllen` is the room given for control data.
+fn recv_cred(fd: usize, buf: &mut [u8], controllen: usize) -> (isize, Option<UCred>, i32) {
+    let iov = [IoVec {
+        base: buf.as_mut_ptr() as usize,
+        len: buf.len(),
+    }];
+    let mut msg = MsgHdr::new(&iov);
+    let mut cmsg = CMsgCred::default();
+    msg.control = &mut cmsg as *mut CMsgCred as usize;
+    msg.controllen = controllen;
+    let ret = recvmsg(fd, &mut msg, 0);
+    if msg.controllen == 0 {
+        return (ret, None, msg.flags);
+    }
+    assert!(msg.controllen == size_of::<CMsgCred>());
+    assert!(cmsg.len == CMsgCred::LEN);
+    assert!(cmsg.level == SOL_SOCKET as i32 && cmsg.type_ == SCM_CREDENTIALS);
+    (ret, Some(cmsg.cred), msg.flags)
+}
+
+fn test_pair() {
+    let mut sv = [0i32; 2];
+    assert!(socketpair(AF_UNIX, SOCK_STREAM, 0, &mut sv) == 0);
+    let (fd0, fd1) = (sv[0] as usize, sv[1] as usize);
+    let me = current();
+    // both ends were made by this process
+    assert!(peercred(fd0) == me);
+    assert!(peercred(fd1) == me);
+
+    // nothing is passed without SO_PASSCRED
+    let mut buf = [0u8; 16];
+    assert!(send_cred(fd0, b"none", Some(me)) == 4);
+    assert!(recv_cred(fd1, &mut buf, size_of::<CMsgCred>()) == (4, None, 0));
+
+    // a privileged process may send any pid, each send is received apart
+    // from the others with other credentials
+    passcred(fd1);
+    let forged = UCred { pid: 1, ..me };
+    assert!(send_cred(fd0, b"aaaa", Some(forged)) == 4);
+    assert!(send_cred(fd0, b"bbbb", None) == 4);
+    assert!(send_cred(fd0, b"cccc", Some(me)) == 4);
+    assert!(recv_cred(fd1, &mut buf, size_of::<CMsgCred>()) == (4, Some(forged), 0));
+    assert!(&buf[..4] == b"aaaa");
+    // the same credentials are received at once
+    assert!(recv_cred(fd1, &mut buf, size_of::<CMsgCred>()) == (8, Some(me), 0));
+    assert!(&buf[..8] == b"bbbbcccc");
+
+    // without room for the control message it is cut off
+    assert!(write(fd0, b"x") == 1);
+    assert!(recv_cred(fd1, &mut buf, 8) == (1, None, MSG_CTRUNC));
+
+    // a malformed control message
+    let mut cmsg = CMsgCred::new(me);
+    cmsg.len = CMsgCred::LEN - 4;
+    let iov = [IoVec {
+        base: buf.as_ptr() as usize,
+        len: 1,
+    }];
+    let mut msg = MsgHdr::new(&iov);
+    msg.control = &cmsg as *const CMsgCred as usize;
+    msg.controllen = size_of::<CMsgCred>();
+    assert!(sendmsg(fd0, &msg, 0) == -EINVAL);
+
+    // an unprivileged process may only send its own credentials
+    let pid = fork();
+    if pid == 0 {
+        assert!(setuid(1000) == 0);
+        let me = current();
+        assert!(me.uid == 1000);
+        assert!(send_cred(fd0, b"x", Some(UCred { pid: 1, ..me })) == -EPERM);
+        assert!(send_cred(fd0, b"x", Some(UCred { uid: 0, ..me })) == -EPERM);
+        assert!(send_cred(fd0, b"y", Some(me)) == 1);
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    let child = UCred {
+        pid: pid as u32,
+        uid: 1000,
+        gid: me.gid,
+    };
+    assert!(recv_cred(fd1, &mut buf, size_of::<CMsgCred>()) == (1, Some(child), 0));
+    assert!(buf[0] == b'y');
+    close(fd0);
+    close(fd1);
+}
+
+fn test_path() {
+    let addr = SockAddrUn::new(PATH);
+    let server = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(bind(server, &addr) == 0);
+    assert!(listen(server, 4) == 0);
+    // a listening socket reports its own credentials
+    assert!(peercred(server) == current());
+    let parent = getpid() as u32;
+
+    let pid = fork();
+    if pid == 0 {
+        let client = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+        // no peer before the connection
+        assert!(peercred(client).pid == 0);
+        assert!(connect(client, &addr) == 0);
+        // the peer is the process that made the server listen
+        assert!(peercred(client).pid == parent);
+        assert!(send(client, b"hello") == 5);
+        exit(0);
+    }
+    let mut peer = SockAddrUn::new("");
+    let conn = accept(server, &mut peer);
+    assert!(conn >= 0);
+    let conn = conn as usize;
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    // the credentials outlive the client
+    let child = UCred {
+        pid: pid as u32,
+        ..current()
+    };
+    assert!(peercred(conn) == child);
+    // the sender attached none, but they are known anyway
+    passcred(conn);
+    let mut buf = [0u8; 16];
+    assert!(recv_cred(conn, &mut buf, size_of::<CMsgCred>()) == (5, Some(child), 0));
+    assert!(&buf[..5] == b"hello");
+    close(conn);
+    close(server);
+    assert!(unlink("/tmp/peercred_test\0") == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin peercred test");
+    test_pair();
+    test_path();
+    println!("peercred test passed");
+    0
+}
@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::hint::black_box;
+
+use user_lib::*;
+
+const ENOSPC: isize = 28;
+const ENOSYS: isize = 38;
+
+const ITERATIONS: usize = 1_000_000;
+
+#[inline(never)]
+fn spin(n: usize) -> usize {
+    let mut acc = 0usize;
+    for i in 0..n {
+        acc = black_box(acc.wrapping_mul(31).wrapping_add(i));
+    }
+    acc
+}
+
+fn open_counter(config: u64, pid: isize, flags: u64) -> usize {
+    let attr = PerfEventAttr {
+        type_: PERF_TYPE_HARDWARE,
+        size: core::mem::size_of::<PerfEventAttr>() as u32,
+        config,
+        flags,
+        ..Default::default()
+    };
+    let fd = perf_event_open(&attr, pid, -1, -1, PERF_FLAG_FD_CLOEXEC);
+    assert!(fd >= 0);
+    fd as usize
+}
+
+fn read_counter(fd: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    assert!(read(fd, &mut buf) == 8);
+    u64::from_ne_bytes(buf)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin perf test");
+    let mut attr = PerfEventAttr {
+        type_: PERF_TYPE_SOFTWARE,
+        size: core::mem::size_of::<PerfEventAttr>() as u32,
+        ..Default::default()
+    };
+    assert!(perf_event_open(&attr, 0, -1, -1, 0) == -ENOSYS);
+    attr.type_ = PERF_TYPE_HARDWARE;
+    attr.config = 99;
+    assert!(perf_event_open(&attr, 0, -1, -1, 0) == -ENOSYS);
+
+    // cycles over a busy loop, every iteration takes at least one
+    let cycles = open_counter(PERF_COUNT_HW_CPU_CYCLES, 0, 0);
+    assert!(fcntl(cycles, F_GETFD, 0) == FD_CLOEXEC as isize);
+    let before = read_counter(cycles);
+    black_box(spin(ITERATIONS));
+    let after = read_counter(cycles);
+    println!("{} cycles in spin", after - before);
+    assert!(after - before >= ITERATIONS as u64);
+    let mut small = [0u8; 4];
+    assert!(read(cycles, &mut small) == -ENOSPC);
+
+    // a disabled counter stands still, and a reset one starts from zero
+    assert!(ioctl(cycles, PERF_EVENT_IOC_DISABLE, 0) == 0);
+    let stopped = read_counter(cycles);
+    black_box(spin(ITERATIONS));
+    assert!(read_counter(cycles) == stopped);
+    assert!(ioctl(cycles, PERF_EVENT_IOC_RESET, 0) == 0);
+    assert!(read_counter(cycles) == 0);
+    assert!(ioctl(cycles, PERF_EVENT_IOC_ENABLE, 0) == 0);
+    black_box(spin(ITERATIONS));
+    let again = read_counter(cycles);
+    assert!(again >= ITERATIONS as u64);
+    close(cycles);
+
+    // instructions of a child, counted only from when it is enabled
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::empty()) == 0);
+    let pid = fork();
+    if pid == 0 {
+        close(fds[1] as usize);
+        let mut buf = [0u8; 1];
+        assert!(read(fds[0] as usize, &mut buf) == 1);
+        black_box(spin(ITERATIONS));
+        exit(0);
+    }
+    close(fds[0] as usize);
+    let instructions = open_counter(PERF_COUNT_HW_INSTRUCTIONS, pid, PERF_ATTR_DISABLED);
+    assert!(read_counter(instructions) == 0);
+    assert!(ioctl(instructions, PERF_EVENT_IOC_ENABLE, 0) == 0);
+    assert!(write(fds[1] as usize, b"x") == 1);
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    let count = read_counter(instructions);
+    println!("{count} instructions in the child");
+    assert!(count >= ITERATIONS as u64);
+    close(instructions);
+    close(fds[1] as usize);
+    println!("perf test pass.");
+    0
+}
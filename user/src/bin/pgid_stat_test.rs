@@ -0,0 +1,202 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_SHARED: i32 = 0x01;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const STAGES: usize = 3;
+const BURN: Duration = Duration::from_millis(50);
+/// Time a child may spend after reporting its usage, in exit and reaping.
+const SLACK: Duration = Duration::from_millis(20);
+
+fn read_all(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+/// Columns of the line of `id` in `/proc/pgid_stat` or `/proc/sid_stat`.
+fn acct_line(path: &str, id: usize) -> Option<Vec<usize>> {
+    read_all(path).lines().skip(1).find_map(|line| {
+        let columns: Vec<usize> = line
+            .split_whitespace()
+            .map(|column| column.parse().unwrap())
+            .collect();
+        (columns[0] == id).then_some(columns)
+    })
+}
+
+fn self_time() -> Duration {
+    let mut usage = Rusage::default();
+    assert!(getrusage(RUSAGE_SELF, &mut usage) == 0);
+    let utime: Duration = usage.utime.into();
+    let stime: Duration = usage.stime.into();
+    utime + stime
+}
+
+fn burn() {
+    let start = self_time();
+    let mut x = 1u64;
+    while self_time() - start < BURN {
+        for _ in 0..10000 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+        }
+    }
+    core::hint::black_box(x);
+}
+
+/// One stage of the pipeline: pass `input` on to `output` after burning some
+/// time, then report the time of the process in `report`.
+fn stage(input: Option<usize>, output: Option<usize>, report: &AtomicU64) -> ! {
+    let mut data = Vec::new();
+    if let Some(fd) = input {
+        let mut buf = [0u8; 64];
+        loop {
+            let len = read(fd, &mut buf);
+            assert!(len >= 0);
+            if len == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..len as usize]);
+        }
+    } else {
+        data.extend_from_slice(b"pipeline");
+    }
+    burn();
+    if let Some(fd) = output {
+        assert!(write(fd, &data) == data.len() as isize);
+    }
+    report.store(self_time().as_micros() as u64, Ordering::Release);
+    exit(0);
+}
+
+fn run_pipeline() -> i32 {
+    assert!(setpgid(0, 0) == 0);
+    let pgid = getpid() as usize;
+    assert!(getpgid(0) == pgid as isize);
+    // a group leader can not start a session
+    assert!(setsid() == -EPERM);
+
+    let reports = mmap(
+        core::ptr::null(),
+        4096,
+        PROT_READ | PROT_WRITE,
+        MAP_SHARED | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    );
+    assert!(reports > 0);
+    let reports = unsafe { core::slice::from_raw_parts(reports as *const AtomicU64, STAGES) };
+
+    let mut pipes = [[0i32; 2]; STAGES - 1];
+    for pipe in pipes.iter_mut() {
+        assert!(pipe2(pipe, OpenFlags::empty()) == 0);
+    }
+    for i in 0..STAGES {
+        if fork() == 0 {
+            let input = (i > 0).then(|| pipes[i - 1][0] as usize);
+            let output = (i < STAGES - 1).then(|| pipes[i][1] as usize);
+            for pipe in pipes.iter() {
+                for &fd in pipe.iter() {
+                    if Some(fd as usize) != input && Some(fd as usize) != output {
+                        close(fd as usize);
+                    }
+                }
+            }
+            stage(input, output, &reports[i]);
+        }
+    }
+    for pipe in pipes.iter() {
+        close(pipe[0] as usize);
+        close(pipe[1] as usize);
+    }
+    for _ in 0..STAGES {
+        let mut exit_code = 0;
+        assert!(wait(&mut exit_code) > 0);
+        assert!(exit_code == 0);
+    }
+
+    let children: Duration = reports
+        .iter()
+        .map(|report| Duration::from_micros(report.load(Ordering::Acquire)))
+        .sum();
+    assert!(children >= BURN * STAGES as u32);
+
+    let before = self_time();
+    let line = acct_line("/proc/pgid_stat\0", pgid).unwrap();
+    let after = self_time();
+    let sid = getsid(0) as usize;
+    assert!(line[1] == sid);
+    // only the leader is left, the reaped stages are accounted in the group
+    assert!(line[2] == 1);
+    let aggregate = Duration::from_micros((line[3] + line[4]) as u64);
+    println!(
+        "[pgid_stat] children {:?}, leader {:?}, aggregate {:?}",
+        children, after, aggregate
+    );
+    assert!(aggregate >= children + before);
+    assert!(aggregate <= children + after + SLACK * STAGES as u32);
+    assert!(line[5] > 0);
+
+    let session = acct_line("/proc/sid_stat\0", sid).unwrap();
+    assert!(session[2] >= 1);
+    assert!(Duration::from_micros((session[3] + session[4]) as u64) >= aggregate);
+
+    // a new session, whose processes can not join groups of the old one
+    let pid = fork();
+    if pid == 0 {
+        let pid = getpid();
+        assert!(setsid() == pid);
+        assert!(getsid(0) == pid);
+        assert!(getpgid(0) == pid);
+        assert!(setpgid(0, pgid) == -EPERM);
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin pgid_stat test");
+    let sid = getsid(0);
+    assert!(sid > 0);
+    let pid = fork();
+    if pid == 0 {
+        assert!(getsid(0) == sid);
+        exit(run_pipeline());
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+    println!("pgid_stat test passed");
+    0
+}
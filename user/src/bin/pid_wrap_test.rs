@@ -0,0 +1,136 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{string::String, vec::Vec};
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const EINVAL: isize = 22;
+
+const PID_MAX: &str = "/proc/sys/kernel/pid_max\0";
+/// Small enough to wrap around quickly.
+const TEST_PID_MAX: isize = 400;
+
+fn read_pid_max() -> String {
+    let fd = openat(PID_MAX, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; 32];
+    let len = read(fd as usize, &mut buf);
+    assert!(len > 0);
+    close(fd as usize);
+    String::from_utf8(buf[..len as usize].to_vec()).unwrap()
+}
+
+fn write_pid_max(value: &str) -> isize {
+    let fd = openat(PID_MAX, OpenFlags::O_WRONLY);
+    assert!(fd >= 0);
+    let ret = write(fd as usize, value.as_bytes());
+    close(fd as usize);
+    ret
+}
+
+/// Fork a child blocked until the write end of `pipe_fd` is closed.
+fn fork_blocked(pipe_fd: &[i32; 2]) -> isize {
+    let pid = fork();
+    if pid == 0 {
+        close(pipe_fd[1] as usize);
+        let mut buf = [0u8; 1];
+        read(pipe_fd[0] as usize, &mut buf);
+        exit(0);
+    }
+    pid
+}
+
+fn reap(pid: isize, code: i32) {
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == code);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin pid wrap test");
+    let default = read_pid_max();
+    assert!(default == "32768\n");
+    assert!(write_pid_max("100\n") == -EINVAL);
+    assert!(write_pid_max("400\n") == 4);
+    assert!(read_pid_max() == "400\n");
+
+    // a live child and a zombie keep their pids through the wraparounds
+    let mut pipe_fd = [0i32; 2];
+    assert!(pipe2(&mut pipe_fd, OpenFlags::empty()) == 0);
+    let live = fork_blocked(&pipe_fd);
+    assert!(live > 0);
+    let zombie = fork();
+    if zombie == 0 {
+        exit(7);
+    }
+    assert!(zombie > 0);
+
+    let mut last = zombie;
+    let mut wraps = 0;
+    for _ in 0..TEST_PID_MAX * 2 {
+        let pid = fork();
+        if pid == 0 {
+            exit(0);
+        }
+        assert!(pid > 0 && pid < TEST_PID_MAX);
+        assert!(pid != live && pid != zombie && pid != getpid());
+        // a pid just freed is not handed out again at once
+        assert!(pid != last);
+        if pid < last {
+            wraps += 1;
+        }
+        last = pid;
+        reap(pid, 0);
+
+        // children alive at the same time never share a pid
+        if pid % 64 == 0 {
+            let mut batch = [0i32; 2];
+            assert!(pipe2(&mut batch, OpenFlags::empty()) == 0);
+            let pids: Vec<isize> = (0..8).map(|_| fork_blocked(&batch)).collect();
+            for (i, &pid) in pids.iter().enumerate() {
+                assert!(pid > 0 && pid != live && !pids[..i].contains(&pid));
+            }
+            close(batch[1] as usize);
+            for pid in pids {
+                reap(pid, 0);
+            }
+            close(batch[0] as usize);
+        }
+    }
+    println!("pids wrapped around {wraps} times");
+    assert!(wraps >= 2);
+    // the stale pids still refer to the same children
+    reap(zombie, 7);
+    close(pipe_fd[1] as usize);
+    reap(live, 0);
+    close(pipe_fd[0] as usize);
+
+    // clone fails once every pid is taken
+    assert!(write_pid_max("301\n") == 4);
+    let mut pipe_fd = [0i32; 2];
+    assert!(pipe2(&mut pipe_fd, OpenFlags::empty()) == 0);
+    let mut pids = Vec::new();
+    let ret = loop {
+        let pid = fork_blocked(&pipe_fd);
+        if pid < 0 {
+            break pid;
+        }
+        pids.push(pid);
+        assert!(pids.len() <= 1);
+    };
+    assert!(ret == -EAGAIN);
+    close(pipe_fd[1] as usize);
+    for pid in pids {
+        reap(pid, 0);
+    }
+    close(pipe_fd[0] as usize);
+
+    assert!(write_pid_max(&default) == default.len() as isize);
+    println!("pid wrap test pass.");
+    0
+}
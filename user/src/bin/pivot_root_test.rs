@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const ENOENT: isize = 2;
+const EBUSY: isize = 16;
+const EINVAL: isize = 22;
+
+fn cwd_is(expected: &str) -> bool {
+    let mut buf = [0u8; 64];
+    assert!(getcwd(&mut buf) > 0);
+    let len = buf.iter().position(|&b| b == 0).unwrap();
+    &buf[..len] == expected.as_bytes()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin pivot_root test");
+    // /tmp is a tmpfs mount, use it as the new root
+    assert!(mkdir("/tmp/pivot_old\0") == 0);
+    assert!(mkdir("/tmp/pivot_dir\0") == 0);
+    let fd = openat(
+        "/tmp/pivot_marker\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    close(fd as usize);
+    assert!(chdir("/\0") == 0);
+
+    // new_root must be a mount point other than the current root, and put_old
+    // must be under it
+    assert!(pivot_root("/tmp/pivot_dir\0", "/tmp/pivot_dir\0") == -EINVAL);
+    assert!(pivot_root("/tmp\0", "/proc\0") == -EINVAL);
+    assert!(pivot_root("/\0", "/tmp\0") == -EBUSY);
+
+    let pid = fork();
+    if pid == 0 {
+        assert!(setuid(1000) == 0);
+        assert!(pivot_root("/tmp\0", "/tmp/pivot_old\0") == -EPERM);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+
+    assert!(pivot_root("/tmp\0", "/tmp/pivot_old\0") == 0);
+    // "/" is the old /tmp now, and the old root is under /pivot_old
+    assert!(cwd_is("/"));
+    let fd = openat("/pivot_marker\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+    let fd = openat("/../pivot_marker\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+    // the mount of /tmp was moved off its mount point, which is empty now
+    assert!(openat("/pivot_old/tmp/pivot_marker\0", OpenFlags::O_RDONLY) == -ENOENT);
+    assert!(chdir("/pivot_dir\0") == 0);
+    assert!(cwd_is("/pivot_dir"));
+    // the old root reads as /pivot_old, and its ".." is the new root
+    assert!(chdir("/pivot_old/tmp\0") == 0);
+    assert!(cwd_is("/pivot_old/tmp"));
+    assert!(chdir("../..\0") == 0);
+    assert!(cwd_is("/"));
+
+    // switch back, /tmp is mounted on its mount point again
+    assert!(pivot_root("/pivot_old\0", "/pivot_old/tmp\0") == 0);
+    assert!(cwd_is("/"));
+    let fd = openat("/tmp/pivot_marker\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+    assert!(chdir("/tmp/pivot_old\0") == 0);
+    assert!(cwd_is("/tmp/pivot_old"));
+    assert!(chdir("/\0") == 0);
+    println!("pivot_root test pass.");
+    0
+}
@@ -0,0 +1,185 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const EINVAL: isize = 22;
+
+const SI_TIMER: i32 = -2;
+
+fn spec(value_ms: usize, interval_ms: usize) -> ITimerSpec {
+    ITimerSpec {
+        it_interval: TimeSpec::from_ms(interval_ms),
+        it_value: TimeSpec::from_ms(value_ms),
+    }
+}
+
+fn sigevent(sig: Sig, value: usize) -> SigEvent {
+    SigEvent {
+        sigev_value: value,
+        sigev_signo: sig.raw() as i32,
+        sigev_notify: SigEvent::SIGEV_SIGNAL,
+        _sigev_un: [0; 12],
+    }
+}
+
+fn now_ms(clockid: usize) -> usize {
+    let mut ts = TimeSpec::from_ms(0);
+    assert!(clock_gettime(clockid, &mut ts) == 0);
+    ts.into_ms()
+}
+
+/// Take the signal `sig` of a timer, waiting up to `timeout_ms`.
+fn take(sig: Sig, timeout_ms: usize) -> Option<LinuxSigInfo> {
+    let set = SigSet::from(sig);
+    let mut info = LinuxSigInfo::default();
+    let ret = sigtimedwait(&set, Some(&mut info), Some(&TimeSpec::from_ms(timeout_ms)));
+    if ret == -EAGAIN {
+        return None;
+    }
+    assert!(ret == sig.raw() as isize);
+    assert!(info.si_code == SI_TIMER);
+    Some(info)
+}
+
+// `si_overrun` follows `si_tid`, and `si_value` the two of them
+fn timer_id(info: &LinuxSigInfo) -> i32 {
+    info.si_fields[0] as u32 as i32
+}
+
+fn overrun(info: &LinuxSigInfo) -> i32 {
+    (info.si_fields[0] >> 32) as u32 as i32
+}
+
+fn test_relative() {
+    let mut id = -1;
+    let event = sigevent(Sig::SIGUSR1, 0xdead);
+    assert!(timer_create(CLOCK_MONOTONIC, Some(&event), &mut id) == 0);
+    assert!(id >= 0);
+
+    let start = now_ms(CLOCK_MONOTONIC);
+    assert!(timer_settime(id, 0, &spec(50, 0), None) == 0);
+    let mut curr = ITimerSpec::default();
+    assert!(timer_gettime(id, &mut curr) == 0);
+    let left = curr.it_value.into_ms();
+    assert!(left <= 50 && curr.it_value != TimeSpec::default());
+
+    let info = take(Sig::SIGUSR1, 2000).unwrap();
+    assert!(now_ms(CLOCK_MONOTONIC) - start >= 50);
+    assert!(timer_id(&info) == id && overrun(&info) == 0);
+    assert!(info.si_fields[1] == 0xdead);
+
+    // a one-shot timer is disarmed once it fires
+    assert!(timer_gettime(id, &mut curr) == 0);
+    assert!(curr.it_value == TimeSpec::default());
+    assert!(timer_delete(id) == 0);
+    assert!(timer_gettime(id, &mut curr) == -EINVAL);
+    assert!(timer_delete(id) == -EINVAL);
+}
+
+/// Without a sigevent, a timer sends `SIGALRM` with its id as value.
+fn test_absolute() {
+    let mut id = -1;
+    assert!(timer_create(CLOCK_REALTIME, None, &mut id) == 0);
+    let deadline = now_ms(CLOCK_REALTIME) + 50;
+    assert!(timer_settime(id, TIMER_ABSTIME, &spec(deadline, 0), None) == 0);
+    let info = take(Sig::SIGALRM, 2000).unwrap();
+    assert!(now_ms(CLOCK_REALTIME) >= deadline);
+    assert!(info.si_fields[1] == id as u64);
+
+    // a time already past fires at once
+    let past = now_ms(CLOCK_REALTIME) - 1000;
+    assert!(timer_settime(id, TIMER_ABSTIME, &spec(past, 0), None) == 0);
+    assert!(take(Sig::SIGALRM, 100).is_some());
+    assert!(timer_delete(id) == 0);
+}
+
+/// Expirations while the signal is pending count as overruns of it.
+fn test_overrun() {
+    let mut id = -1;
+    let event = sigevent(Sig::SIGUSR2, 7);
+    assert!(timer_create(CLOCK_MONOTONIC, Some(&event), &mut id) == 0);
+    assert!(timer_settime(id, 0, &spec(10, 10), None) == 0);
+    sleep(100);
+    let info = take(Sig::SIGUSR2, 0).unwrap();
+    assert!(overrun(&info) >= 5);
+    assert!(timer_getoverrun(id) == overrun(&info) as isize);
+
+    let mut old = ITimerSpec::default();
+    assert!(timer_settime(id, 0, &spec(0, 0), Some(&mut old)) == 0);
+    assert!(old.it_interval.into_ms() == 10);
+    // the signal of an expiration before the disarming may be pending
+    take(Sig::SIGUSR2, 0);
+    sleep(50);
+    assert!(take(Sig::SIGUSR2, 0).is_none());
+    assert!(timer_delete(id) == 0);
+}
+
+/// A timer disarmed before it fires never does.
+fn test_disarm() {
+    let mut id = -1;
+    let event = sigevent(Sig::SIGUSR1, 0);
+    assert!(timer_create(CLOCK_MONOTONIC, Some(&event), &mut id) == 0);
+    assert!(timer_settime(id, 0, &spec(50, 50), None) == 0);
+    assert!(timer_settime(id, 0, &spec(0, 0), None) == 0);
+    assert!(take(Sig::SIGUSR1, 150).is_none());
+
+    // a deleted timer never fires either
+    assert!(timer_settime(id, 0, &spec(20, 0), None) == 0);
+    assert!(timer_delete(id) == 0);
+    assert!(take(Sig::SIGUSR1, 100).is_none());
+}
+
+fn test_invalid() {
+    let mut id = -1;
+    assert!(timer_create(CLOCK_PROCESS_CPUTIME_ID, None, &mut id) == -EINVAL);
+    let mut event = sigevent(Sig::SIGUSR1, 0);
+    event.sigev_signo = 0;
+    assert!(timer_create(CLOCK_MONOTONIC, Some(&event), &mut id) == -EINVAL);
+    event.sigev_notify = 3;
+    assert!(timer_create(CLOCK_MONOTONIC, Some(&event), &mut id) == -EINVAL);
+
+    // a timer notifying nothing still counts down
+    event.sigev_notify = SigEvent::SIGEV_NONE;
+    assert!(timer_create(CLOCK_MONOTONIC, Some(&event), &mut id) == 0);
+    assert!(timer_settime(id, 0, &spec(1000, 0), None) == 0);
+    let bad = ITimerSpec {
+        it_interval: TimeSpec::default(),
+        it_value: TimeSpec {
+            tv_sec: 0,
+            tv_nsec: 1_000_000_000,
+        },
+    };
+    assert!(timer_settime(id, 0, &bad, None) == -EINVAL);
+    assert!(timer_settime(id, 2, &spec(10, 0), None) == -EINVAL);
+    let mut curr = ITimerSpec::default();
+    assert!(timer_gettime(id, &mut curr) == 0);
+    assert!(curr.it_value.into_ms() <= 1000 && curr.it_value != TimeSpec::default());
+
+    // a child created by fork has no timers
+    let pid = fork();
+    if pid == 0 {
+        exit((timer_gettime(id, &mut curr) == -EINVAL) as i32);
+    }
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    assert!((status >> 8) & 0xff == 1);
+    assert!(timer_delete(id) == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin posix timer test");
+    let blocked = SigSet::SIGUSR1 | SigSet::SIGUSR2 | SigSet::SIGALRM;
+    assert!(sigprocmask(SIG_BLOCK, Some(&blocked), None) == 0);
+    test_relative();
+    test_absolute();
+    test_overrun();
+    test_disarm();
+    test_invalid();
+    println!("posix timer test pass.");
+    0
+}
@@ -0,0 +1,101 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::*;
+
+fn now_ms() -> usize {
+    let mut tv = TimeVal::default();
+    assert!(gettimeofday(&mut tv) == 0);
+    let time: Duration = tv.into();
+    time.as_millis() as usize
+}
+
+fn new_pipe() -> (usize, usize) {
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::empty()) == 0);
+    (fds[0] as usize, fds[1] as usize)
+}
+
+/// Poll with a long timeout, which must not be what ends the wait.
+fn poll_promptly(fds: &mut [PollFd]) -> isize {
+    let start = now_ms();
+    let ret = ppoll(fds, Some(&TimeSpec::from_ms(5000)));
+    assert!(now_ms() - start < 1000);
+    ret
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin ppoll pipe test");
+    let (ar, aw) = new_pipe();
+    let (br, bw) = new_pipe();
+
+    // the second pipe gets data first, then the first one, then both hang up
+    let pid = fork();
+    if pid == 0 {
+        close(ar);
+        close(br);
+        sleep(100);
+        assert!(write(bw, b"b") == 1);
+        sleep(100);
+        assert!(write(aw, b"a") == 1);
+        sleep(100);
+        exit(0);
+    }
+    close(aw);
+    close(bw);
+
+    let mut buf = [0u8; 4];
+    let mut fds = [PollFd::new(ar, POLLIN), PollFd::new(br, POLLIN)];
+    assert!(poll_promptly(&mut fds) == 1);
+    assert!(fds[0].revents == 0 && fds[1].revents == POLLIN);
+    assert!(read(br, &mut buf) == 1 && buf[0] == b'b');
+
+    let mut fds = [PollFd::new(ar, POLLIN), PollFd::new(br, POLLIN)];
+    assert!(poll_promptly(&mut fds) == 1);
+    assert!(fds[0].revents == POLLIN && fds[1].revents == 0);
+    assert!(read(ar, &mut buf) == 1 && buf[0] == b'a');
+
+    let mut fds = [PollFd::new(ar, POLLIN), PollFd::new(br, POLLIN)];
+    assert!(poll_promptly(&mut fds) == 2);
+    assert!(fds[0].revents & POLLHUP != 0 && fds[1].revents & POLLHUP != 0);
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    close(ar);
+    close(br);
+
+    // a full pipe becomes writable once the reader takes some data
+    let (cr, cw) = new_pipe();
+    let chunk = [0u8; 4096];
+    loop {
+        let mut fds = [PollFd::new(cw, POLLOUT)];
+        if ppoll(&mut fds, Some(&TimeSpec::from_ms(0))) == 0 {
+            break;
+        }
+        assert!(write(cw, &chunk) > 0);
+    }
+    let pid = fork();
+    if pid == 0 {
+        close(cw);
+        sleep(100);
+        let mut buf = [0u8; 4096];
+        assert!(read(cr, &mut buf) > 0);
+        exit(0);
+    }
+    let mut fds = [PollFd::new(cw, POLLOUT)];
+    assert!(poll_promptly(&mut fds) == 1);
+    assert!(fds[0].revents == POLLOUT);
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+
+    // and reports an error once the reader is gone
+    close(cr);
+    let mut fds = [PollFd::new(cw, POLLOUT)];
+    assert!(poll_promptly(&mut fds) == 1);
+    assert!(fds[0].revents & POLLERR != 0);
+    println!("ppoll pipe test pass.");
+    0
+}
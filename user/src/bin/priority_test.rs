@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+const ESRCH: isize = 3;
+
+/// Nice value of the calling process.
+fn nice() -> isize {
+    20 - getpriority(PRIO_PROCESS, 0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin priority test");
+    assert!(nice() == 0);
+
+    assert!(setpriority(PRIO_PROCESS, 0, 5) == 0);
+    assert!(nice() == 5);
+    assert!(20 - getpriority(PRIO_PROCESS, getpid() as usize) == 5);
+    let mut prio = 0;
+    assert!(sched_getparam(0, &mut prio) == 0 && prio == 15);
+
+    // out of range values are clamped
+    assert!(setpriority(PRIO_PROCESS, 0, 100) == 0);
+    assert!(nice() == 19);
+    assert!(setpriority(PRIO_PROCESS, 0, -100) == 0);
+    assert!(nice() == -20);
+
+    assert!(setpriority(3, 0, 0) == -EINVAL);
+    assert!(setpriority(PRIO_PROCESS, 99999, 0) == -ESRCH);
+    assert!(getpriority(PRIO_PROCESS, 99999) == -ESRCH);
+
+    assert!(setpriority(PRIO_PROCESS, 0, -3) == 0);
+    let pid = fork();
+    if pid == 0 {
+        // inherited from the parent
+        assert!(nice() == -3);
+        assert!(setuid(1000) == 0);
+        assert!(setpriority(PRIO_PROCESS, 0, 10) == 0);
+        assert!(setpriority(PRIO_PROCESS, 0, 2) == 0);
+        assert!(setpriority(PRIO_PROCESS, 0, -1) == -EPERM);
+        // the parent belongs to root
+        assert!(setpriority(PRIO_PROCESS, getppid() as usize, 10) == -EPERM);
+        // the group also holds the parent, so no one is changed
+        assert!(setpriority(PRIO_PGRP, 0, 12) == -EPERM);
+        assert!(nice() == 2);
+        assert!(20 - getpriority(PRIO_PROCESS, getppid() as usize) == -3);
+        assert!(setpriority(PRIO_USER, 0, 7) == 0);
+        assert!(nice() == 7);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+
+    // the group holds the parent only, the child has been reaped
+    assert!(setpriority(PRIO_PGRP, 0, 0) == 0);
+    assert!(20 - getpriority(PRIO_PGRP, 0) == 0);
+    println!("priority test pass.");
+    0
+}
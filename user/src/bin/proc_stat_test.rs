@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{string::String, vec::Vec};
+
+use user_lib::*;
+
+fn read_all(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    // stays at EOF
+    assert!(read(fd as usize, &mut buf) == 0);
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+fn field_kb(meminfo: &str, name: &str) -> usize {
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with(name))
+        .expect("field is missing");
+    line.split_whitespace().nth(1).unwrap().parse().unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin proc stat test");
+    let meminfo = read_all("/proc/meminfo\0");
+    let total = field_kb(&meminfo, "MemTotal:");
+    let free = field_kb(&meminfo, "MemFree:");
+    assert!(total > 0 && free <= total);
+
+    let stat = read_all("/proc/stat\0");
+    let mut lines = stat.lines();
+    let cpu: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+    assert!(cpu[0] == "cpu" && cpu.len() == 11);
+    assert!(lines.next().unwrap().starts_with("cpu0 "));
+
+    println!("proc stat test pass.");
+    0
+}
@@ -0,0 +1,90 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{string::String, vec::Vec};
+use core::hint::black_box;
+
+use user_lib::*;
+
+/// Upper bound of the size of the code of [`spin`].
+const SPIN_CODE_SIZE: usize = 0x100;
+/// Time spent in [`spin`] in microseconds.
+const BUSY_USEC: usize = 1_000_000;
+
+#[inline(never)]
+fn spin(n: usize) -> usize {
+    let mut acc = 0usize;
+    for i in 0..n {
+        acc = black_box(acc.wrapping_mul(31).wrapping_add(i));
+    }
+    acc
+}
+
+fn now_usec() -> usize {
+    let mut tv = TimeVal::from_usec(0);
+    assert!(gettimeofday(&mut tv) == 0);
+    tv.into_usec()
+}
+
+fn read_all(fd: usize) -> String {
+    let mut content = Vec::new();
+    let mut buf = [0u8; 256];
+    loop {
+        let len = read(fd, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    String::from_utf8(content).unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin profile test");
+    let fd = openat("/proc/profile\0", OpenFlags::O_RDWR);
+    if fd < 0 {
+        println!("kernel built without profile, skip profile test.");
+        return 0;
+    }
+    let fd = fd as usize;
+    // start from an empty histogram
+    assert!(write(fd, b"0") == 1);
+    close(fd);
+
+    let start = now_usec();
+    while now_usec() - start < BUSY_USEC {
+        black_box(spin(1_000_000));
+    }
+
+    let fd = openat("/proc/profile\0", OpenFlags::O_RDONLY) as usize;
+    let profile = read_all(fd);
+    close(fd);
+    let mut lines = profile.lines();
+    let header: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+    assert!(header[0] == "samples" && header[2] == "dropped");
+
+    let spin_start = spin as usize;
+    let spin_end = spin_start + SPIN_CODE_SIZE;
+    // kernel addresses are in the upper half
+    let (mut in_spin, mut in_user) = (0, 0);
+    for line in lines {
+        let (pc, count) = line.split_once(' ').unwrap();
+        let pc = usize::from_str_radix(pc.trim_start_matches("0x"), 16).unwrap();
+        let count: usize = count.parse().unwrap();
+        if pc as isize >= 0 {
+            in_user += count;
+        }
+        if (spin_start..spin_end).contains(&pc) {
+            in_spin += count;
+        }
+    }
+    println!("{in_spin} of {in_user} user samples in spin");
+    assert!(in_user > 0 && in_spin * 2 > in_user);
+    println!("profile test pass.");
+    0
+}
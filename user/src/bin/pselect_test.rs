@@ -0,0 +1,87 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::*;
+
+fn now_ms() -> usize {
+    let mut tv = TimeVal::default();
+    assert!(gettimeofday(&mut tv) == 0);
+    let time: Duration = tv.into();
+    time.as_millis() as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin pselect test");
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::empty()) == 0);
+    let (rfd, wfd) = (fds[0] as usize, fds[1] as usize);
+    let nfds = rfd.max(wfd) + 1;
+
+    // nothing to read, times out and clears the set
+    let mut readfds = FdSet::zero();
+    readfds.set(rfd);
+    let start = now_ms();
+    let ret = pselect6(
+        nfds,
+        Some(&mut readfds),
+        None,
+        None,
+        Some(&TimeSpec::from_ms(50)),
+    );
+    assert!(ret == 0);
+    assert!(now_ms() - start >= 40);
+    assert!(!readfds.is_set(rfd));
+
+    // only the ready bits are kept
+    let mut readfds = FdSet::zero();
+    readfds.set(rfd);
+    let mut writefds = FdSet::zero();
+    writefds.set(wfd);
+    let ret = pselect6(
+        nfds,
+        Some(&mut readfds),
+        Some(&mut writefds),
+        None,
+        Some(&TimeSpec::from_ms(0)),
+    );
+    assert!(ret == 1);
+    assert!(!readfds.is_set(rfd));
+    assert!(writefds.is_set(wfd));
+
+    // both ends ready, every ready bit counts
+    assert!(write(wfd, b"x") == 1);
+    let mut readfds = FdSet::zero();
+    readfds.set(rfd);
+    let mut writefds = FdSet::zero();
+    writefds.set(wfd);
+    let ret = pselect6(nfds, Some(&mut readfds), Some(&mut writefds), None, None);
+    assert!(ret == 2);
+    assert!(readfds.is_set(rfd) && writefds.is_set(wfd));
+
+    // invalid arguments
+    let mut readfds = FdSet::zero();
+    readfds.set(rfd);
+    assert!(pselect6(FD_SETSIZE + 1, Some(&mut readfds), None, None, None) < 0);
+    let mut readfds = FdSet::zero();
+    readfds.set(100);
+    assert!(pselect6(101, Some(&mut readfds), None, None, None) < 0);
+
+    // a broken pipe is ready with an error, reported only in the set it was
+    // asked for
+    close(rfd);
+    let mut readfds = FdSet::zero();
+    let mut writefds = FdSet::zero();
+    writefds.set(wfd);
+    let ret = pselect6(nfds, Some(&mut readfds), Some(&mut writefds), None, None);
+    assert!(ret == 1);
+    assert!(writefds.is_set(wfd) && !readfds.is_set(wfd));
+
+    close(wfd);
+    println!("pselect test pass.");
+    0
+}
@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const EINTR: isize = 4;
+const EIO: isize = 5;
+
+static RESIZED: AtomicUsize = AtomicUsize::new(0);
+
+fn on_sigwinch(_signal: usize) {
+    RESIZED.fetch_add(1, Ordering::Relaxed);
+}
+
+const NEW_SIZE: WinSize = WinSize {
+    ws_row: 50,
+    ws_col: 132,
+    ws_xpixel: 0,
+    ws_ypixel: 0,
+};
+
+/// Like a shell on the slave, in a process group of its own which is the
+/// foreground one, waiting for input.
+fn slave(path: &str) -> i32 {
+    let fd = openat(path, OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(setpgid(0, 0) == 0);
+    let pgid = getpid() as u32;
+    assert!(getpgid(0) == pgid as isize);
+    assert!(ioctl(fd, TIOCSPGRP, &pgid as *const u32 as usize) == 0);
+
+    let mut new = SigAction::default();
+    let mut old = SigAction::default();
+    new.sa_handler = on_sigwinch as usize;
+    new.sa_flags = SigActionFlag::empty();
+    assert!(sigaction(Sig::SIGWINCH, &new, &mut old) == 0);
+
+    assert!(write(fd, b"r") == 1);
+    // the resize interrupts the read, unless it came before the read began
+    let mut buf = [0u8; 1];
+    loop {
+        let len = read(fd, &mut buf);
+        if len == -EINTR {
+            assert!(RESIZED.load(Ordering::Relaxed) == 1);
+            continue;
+        }
+        assert!(len == 1 && buf[0] == b'q');
+        break;
+    }
+    // resizing to the same size again is not signalled
+    assert!(RESIZED.load(Ordering::Relaxed) == 1);
+    let mut size = WinSize::default();
+    assert!(ioctl(fd, TIOCGWINSZ, &mut size as *mut WinSize as usize) == 0);
+    assert!(size == NEW_SIZE);
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin pty test");
+    let master = openat("/dev/ptmx\0", OpenFlags::O_RDWR);
+    assert!(master >= 0);
+    let master = master as usize;
+    let mut index: u32 = 0;
+    assert!(ioctl(master, TIOCGPTN, &mut index as *mut u32 as usize) == 0);
+    let path: String = format!("/dev/pts/{index}\0");
+
+    // the slave can not be opened until unlocked
+    assert!(openat(&path, OpenFlags::O_RDWR) == -EIO);
+    let unlock: i32 = 0;
+    assert!(ioctl(master, TIOCSPTLCK, &unlock as *const i32 as usize) == 0);
+
+    let pid = fork();
+    if pid == 0 {
+        close(master);
+        exit(slave(&path));
+    }
+    let mut buf = [0u8; 1];
+    assert!(read(master, &mut buf) == 1 && buf[0] == b'r');
+    for _ in 0..2 {
+        assert!(ioctl(master, TIOCSWINSZ, &NEW_SIZE as *const WinSize as usize) == 0);
+        sleep(50);
+    }
+    let mut pgid: u32 = 0;
+    assert!(ioctl(master, TIOCGPGRP, &mut pgid as *mut u32 as usize) == 0);
+    assert!(pgid as isize == pid);
+    assert!(write(master, b"q") == 1);
+
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    // every slave is closed
+    assert!(read(master, &mut buf) == -EIO);
+    close(master);
+    assert!(openat(&path, OpenFlags::O_RDWR) == -ENOENT);
+    println!("pty test pass.");
+    0
+}
@@ -0,0 +1,81 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::format;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+
+fn exists(path: &str) -> bool {
+    let mut st = Kstat::default();
+    match stat(path, &mut st) {
+        0 => true,
+        ret => {
+            assert!(ret == -ENOENT);
+            false
+        }
+    }
+}
+
+fn create(path: &str) {
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+}
+
+/// Each name is looked up while missing first, so that it is cached as a
+/// negative dentry before it comes to exist.
+fn test_in(dir: &str) {
+    let path = |name: &str| format!("{dir}/{name}\0");
+    assert!(mkdir(&format!("{dir}\0")) == 0);
+
+    // rename into a missing name, the old one goes missing
+    create(&path("a"));
+    assert!(!exists(&path("b")));
+    assert!(rename(&path("a"), &path("b")) == 0);
+    assert!(exists(&path("b")));
+    assert!(!exists(&path("a")));
+    // and back again
+    assert!(rename(&path("b"), &path("a")) == 0);
+    assert!(exists(&path("a")));
+    assert!(!exists(&path("b")));
+
+    // link and create
+    assert!(!exists(&path("c")));
+    assert!(link(&path("a"), &path("c")) == 0);
+    assert!(exists(&path("c")));
+    assert!(!exists(&path("e")));
+    create(&path("e"));
+    assert!(exists(&path("e")));
+
+    // a renamed directory takes its children along
+    assert!(mkdir(&path("d")) == 0);
+    create(&path("d/f"));
+    assert!(!exists(&path("g/f")));
+    assert!(rename(&path("d"), &path("g")) == 0);
+    assert!(exists(&path("g/f")));
+    assert!(!exists(&path("d/f")));
+    assert!(mkdir(&path("d")) == 0);
+    assert!(!exists(&path("d/f")));
+
+    assert!(rmdir(&path("d")) == 0);
+    assert!(unlink(&path("g/f")) == 0);
+    assert!(rmdir(&path("g")) == 0);
+    for name in ["a", "c", "e"] {
+        assert!(unlink(&path(name)) == 0);
+    }
+    assert!(rmdir(&format!("{dir}\0")) == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin rename negative test");
+    test_in("/tmp/rename_negative");
+    test_in("/rename_negative");
+    println!("rename negative test passed");
+    0
+}
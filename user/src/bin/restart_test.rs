@@ -0,0 +1,94 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use user_lib::*;
+
+const EINTR: isize = 4;
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+fn on_sigusr1(_signal: usize) {
+    HANDLED.fetch_add(1, Ordering::Relaxed);
+}
+
+fn set_sigusr1_handler(flags: SigActionFlag) {
+    let mut new = SigAction::default();
+    let mut old = SigAction::default();
+    new.sa_handler = on_sigusr1 as usize;
+    new.sa_flags = flags;
+    assert!(sigaction(Sig::SIGUSR1, &new, &mut old) == 0);
+}
+
+/// Like a shell waiting for a job, the wait goes on across the parent being
+/// stopped and continued.
+fn wait_across_stop() {
+    let parent = getpid();
+    let job = fork();
+    if job == 0 {
+        sleep(300);
+        exit(7);
+    }
+    let helper = fork();
+    if helper == 0 {
+        sleep(50);
+        kill(parent, Sig::SIGSTOP);
+        sleep(50);
+        kill(parent, Sig::SIGCONT);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(job as usize, &mut exit_code) == job);
+    assert!((exit_code >> 8) & 0xff == 7);
+    assert!(waitpid(helper as usize, &mut exit_code) == helper && exit_code == 0);
+}
+
+/// A client signals the server in `accept`, and then connects.
+fn accept_with_signal(server: usize, path: &str, restart: bool) {
+    let parent = getpid();
+    let pid = fork();
+    if pid == 0 {
+        sleep(50);
+        kill(parent, Sig::SIGUSR1);
+        sleep(50);
+        let client = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+        assert!(connect(client, &SockAddrUn::new(path)) == 0);
+        close(client);
+        exit(0);
+    }
+    let handled = HANDLED.load(Ordering::Relaxed);
+    let mut peer = SockAddrUn::new("");
+    if !restart {
+        // interrupted exactly once, the next call gets the connection
+        assert!(accept(server, &mut peer) == -EINTR);
+        assert!(HANDLED.load(Ordering::Relaxed) == handled + 1);
+    }
+    let conn = accept(server, &mut peer);
+    assert!(conn >= 0);
+    assert!(HANDLED.load(Ordering::Relaxed) == handled + 1);
+    close(conn as usize);
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin restart test");
+    wait_across_stop();
+
+    let path = "/tmp/restart_test";
+    let server = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(bind(server, &SockAddrUn::new(path)) == 0);
+    assert!(listen(server, 4) == 0);
+    set_sigusr1_handler(SigActionFlag::empty());
+    accept_with_signal(server, path, false);
+    set_sigusr1_handler(SigActionFlag::SA_RESTART);
+    accept_with_signal(server, path, true);
+    close(server);
+    unlink("/tmp/restart_test\0");
+    println!("restart test pass.");
+    0
+}
@@ -0,0 +1,128 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const EBADF: isize = 9;
+const EINVAL: isize = 22;
+const EMFILE: isize = 24;
+
+fn wait_ok(pid: isize) {
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+}
+
+fn test_defaults() {
+    let mut rlim = RLimit::default();
+    assert!(getrlimit(RLIMIT_NOFILE, &mut rlim) == 0);
+    assert!(rlim.rlim_cur == 1024 && rlim.rlim_cur <= rlim.rlim_max);
+    assert!(getrlimit(RLIMIT_STACK, &mut rlim) == 0);
+    assert!(rlim.rlim_cur == 8 * 1024 * 1024);
+    assert!(getrlimit(100, &mut rlim) == -EINVAL);
+
+    let bad = RLimit {
+        rlim_cur: 2048,
+        rlim_max: 1024,
+    };
+    assert!(setrlimit(RLIMIT_NOFILE, &bad) == -EINVAL);
+}
+
+/// No fd is allocated at or above the soft limit.
+fn test_nofile() {
+    let pid = fork();
+    if pid == 0 {
+        let limit = RLimit {
+            rlim_cur: 8,
+            rlim_max: 1024,
+        };
+        let mut old = RLimit::default();
+        assert!(prlimit(0, RLIMIT_NOFILE, Some(&limit), Some(&mut old)) == 0);
+        assert!(old.rlim_cur == 1024);
+        loop {
+            let fd = dup(0);
+            if fd < 0 {
+                assert!(fd == -EMFILE);
+                break;
+            }
+            assert!(fd < 8);
+        }
+        assert!(dup3(0, 8, OpenFlags::empty()) == -EBADF);
+        assert!(dup3(0, 7, OpenFlags::empty()) == 7);
+
+        // fds above a lowered limit stay open
+        let limit = RLimit {
+            rlim_cur: 4,
+            rlim_max: 1024,
+        };
+        assert!(setrlimit(RLIMIT_NOFILE, &limit) == 0);
+        assert!(close(7) == 0);
+        assert!(dup(0) == -EMFILE);
+        exit(0);
+    }
+    wait_ok(pid);
+}
+
+/// A child inherits the limits, and changing them there leaves the parent's.
+fn test_inherit() {
+    let limit = RLimit {
+        rlim_cur: 4096,
+        rlim_max: RLIM_INFINITY,
+    };
+    assert!(setrlimit(RLIMIT_CORE, &limit) == 0);
+    let pid = fork();
+    if pid == 0 {
+        let mut rlim = RLimit::default();
+        assert!(getrlimit(RLIMIT_CORE, &mut rlim) == 0);
+        assert!(rlim == limit);
+        let zero = RLimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        assert!(setrlimit(RLIMIT_CORE, &zero) == 0);
+        exit(0);
+    }
+    wait_ok(pid);
+    let mut rlim = RLimit::default();
+    assert!(prlimit(0, RLIMIT_CORE, None, Some(&mut rlim)) == 0);
+    assert!(rlim == limit);
+}
+
+/// Only a privileged process may raise a hard limit.
+fn test_privilege() {
+    let pid = fork();
+    if pid == 0 {
+        let raised = RLimit {
+            rlim_cur: 1024,
+            rlim_max: 4096,
+        };
+        assert!(setrlimit(RLIMIT_NOFILE, &raised) == 0);
+        assert!(setuid(1000) == 0);
+        let lowered = RLimit {
+            rlim_cur: 512,
+            rlim_max: 2048,
+        };
+        assert!(setrlimit(RLIMIT_NOFILE, &lowered) == 0);
+        assert!(setrlimit(RLIMIT_NOFILE, &raised) == -EPERM);
+        let mut rlim = RLimit::default();
+        assert!(getrlimit(RLIMIT_NOFILE, &mut rlim) == 0);
+        assert!(rlim == lowered);
+        // the parent is still root
+        assert!(prlimit(getppid() as usize, RLIMIT_NOFILE, None, Some(&mut rlim)) == -EPERM);
+        exit(0);
+    }
+    wait_ok(pid);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin rlimit test");
+    test_defaults();
+    test_nofile();
+    test_inherit();
+    test_privilege();
+    println!("rlimit test pass.");
+    0
+}
@@ -0,0 +1,145 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String, vec::Vec};
+use core::convert::TryInto;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const EBUSY: isize = 16;
+const EINVAL: isize = 22;
+const ENOTEMPTY: isize = 39;
+
+const DT_DIR: u8 = 4;
+const DEPTH: usize = 4;
+
+/// Build `base` with two files and a subdirectory `d` at every level, and
+/// return the deepest directory.
+fn build(base: &str) -> String {
+    let mut dir = String::from(base);
+    for level in 0..DEPTH {
+        assert!(mkdir(&format!("{dir}\0")) == 0);
+        for file in ["f0", "f1"] {
+            let fd = openat(
+                &format!("{dir}/{file}\0"),
+                OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+            );
+            assert!(fd >= 0);
+            assert!(write(fd as usize, format!("level {level}").as_bytes()) > 0);
+            close(fd as usize);
+        }
+        dir = format!("{dir}/d");
+    }
+    assert!(mkdir(&format!("{dir}\0")) == 0);
+    dir
+}
+
+/// Names in the directory `path`, but "." and "..", with whether each is a
+/// directory.
+fn entries(path: &str) -> Vec<(String, bool)> {
+    let fd = openat(&format!("{path}\0"), OpenFlags::O_DIRECTORY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; 512];
+    let mut entries = Vec::new();
+    loop {
+        let len = getdents64(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        let mut pos = 0;
+        while pos < len as usize {
+            let rec = &buf[pos..];
+            let reclen = u16::from_ne_bytes(rec[16..18].try_into().unwrap()) as usize;
+            let name_len = rec[19..reclen].iter().position(|&b| b == 0).unwrap();
+            let name = core::str::from_utf8(&rec[19..19 + name_len]).unwrap();
+            if name != "." && name != ".." {
+                entries.push((String::from(name), rec[18] == DT_DIR));
+            }
+            pos += reclen;
+        }
+    }
+    close(fd as usize);
+    entries
+}
+
+fn remove_all(path: &str) {
+    for (name, is_dir) in entries(path) {
+        let child = format!("{path}/{name}");
+        if is_dir {
+            remove_all(&child);
+        } else {
+            assert!(unlink(&format!("{child}\0")) == 0);
+        }
+    }
+    assert!(rmdir(&format!("{path}\0")) == 0);
+}
+
+fn test_corner_cases(base: &str) {
+    let deepest = build(base);
+    assert!(rmdir(&format!("{base}\0")) == -ENOTEMPTY);
+    assert!(rmdir(&format!("{base}/d\0")) == -ENOTEMPTY);
+    assert!(rmdir(&format!("{base}/.\0")) == -EINVAL);
+    assert!(rmdir(&format!("{deepest}/.\0")) == -EINVAL);
+    assert!(rmdir(&format!("{deepest}/..\0")) == -ENOTEMPTY);
+    assert!(unlinkat(AT_FDCWD, &format!("{deepest}\0"), 0x100) == -EINVAL);
+    // the file is left alone when rmdir fails
+    let mut st = Kstat::default();
+    assert!(stat(&format!("{base}/f0\0"), &mut st) == 0);
+
+    assert!(rmdir(&format!("{deepest}\0")) == 0);
+    assert!(stat(&format!("{deepest}\0"), &mut st) == -ENOENT);
+    assert!(rmdir(&format!("{deepest}\0")) == -ENOENT);
+    // the name can be taken again
+    assert!(mkdir(&format!("{deepest}\0")) == 0);
+
+    remove_all(base);
+    assert!(stat(&format!("{base}\0"), &mut st) == -ENOENT);
+    assert!(rmdir("/\0") == -EBUSY);
+}
+
+/// A task whose cwd is removed stays there, but nothing can be made in it.
+fn test_removed_cwd(base: &str) {
+    let deepest = build(base);
+    let mut to_child = [0i32; 2];
+    let mut to_parent = [0i32; 2];
+    assert!(pipe(&mut to_child) == 0);
+    assert!(pipe(&mut to_parent) == 0);
+    let mut byte = [0u8; 1];
+    let pid = fork();
+    if pid == 0 {
+        assert!(chdir(&format!("{deepest}\0")) == 0);
+        write(to_parent[1] as usize, b"c");
+        read(to_child[0] as usize, &mut byte);
+        let mut buf = [0u8; 256];
+        assert!(getcwd(&mut buf) == -ENOENT);
+        assert!(mkdir("new\0") == -ENOENT);
+        assert!(openat("new\0", OpenFlags::O_CREATE | OpenFlags::O_RDWR) == -ENOENT);
+        assert!(chdir("/\0") == 0);
+        exit(0);
+    }
+    read(to_parent[0] as usize, &mut byte);
+    remove_all(base);
+    let mut st = Kstat::default();
+    assert!(stat(&format!("{base}\0"), &mut st) == -ENOENT);
+    write(to_child[1] as usize, b"p");
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    assert!(status == 0);
+    // nothing was made under the old name
+    assert!(stat(&format!("{deepest}/new\0"), &mut st) == -ENOENT);
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    for base in ["/tmp/rmdir_test", "/rmdir_test"] {
+        test_corner_cases(base);
+        test_removed_cwd(base);
+    }
+    println!("rmdir_test passed");
+    0
+}
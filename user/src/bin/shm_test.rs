@@ -0,0 +1,173 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const EEXIST: isize = 17;
+const EINVAL: isize = 22;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const PAGE_SIZE: usize = 4096;
+const KEY: i32 = 0x5348;
+
+fn shm_stat(shmid: usize) -> Result<ShmIdDs, isize> {
+    let mut ds = ShmIdDs::default();
+    match shmctl(shmid, IPC_STAT, &mut ds) {
+        0 => Ok(ds),
+        err => Err(err),
+    }
+}
+
+fn attach(shmid: usize, addr: *const u8, flags: i32) -> *mut u8 {
+    let ret = shmat(shmid, addr, flags);
+    assert!(ret > 0);
+    ret as usize as *mut u8
+}
+
+fn load(map: *mut u8, off: usize) -> u8 {
+    unsafe { map.add(off).read_volatile() }
+}
+
+fn store(map: *mut u8, off: usize, val: u8) {
+    unsafe { map.add(off).write_volatile(val) }
+}
+
+/// Run `f` in a child, returning whether it was killed by SIGSEGV.
+fn segfaults(f: impl FnOnce()) -> bool {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    match status & 0x7f {
+        0 => false,
+        sig => {
+            assert!(sig == Sig::SIGSEGV.raw() as i32);
+            true
+        }
+    }
+}
+
+/// A private segment is shared with children and kept while not attached.
+fn test_private() {
+    let ret = shmget(IPC_PRIVATE, PAGE_SIZE + 904, 0o600);
+    assert!(ret >= 0);
+    let shmid = ret as usize;
+    let ds = shm_stat(shmid).unwrap();
+    assert!(ds.shm_segsz == PAGE_SIZE + 904);
+    assert!(ds.shm_nattch == 0);
+    assert!(ds.shm_perm.mode & 0o777 == 0o600);
+    assert!(ds.shm_cpid as isize == getpid());
+
+    let map = attach(shmid, core::ptr::null(), 0);
+    assert!(shm_stat(shmid).unwrap().shm_nattch == 1);
+    // new segments are zeroed, and the rest of the last page is mapped too
+    for off in [0, PAGE_SIZE, 2 * PAGE_SIZE - 1] {
+        assert!(load(map, off) == 0);
+    }
+    store(map, 0, b'p');
+    store(map, PAGE_SIZE, b'q');
+
+    let pid = fork();
+    if pid == 0 {
+        // the attach is inherited, and one more sees the same pages
+        let other = attach(shmid, core::ptr::null(), 0);
+        assert!(load(map, 0) == b'p' && load(other, PAGE_SIZE) == b'q');
+        assert!(shm_stat(shmid).unwrap().shm_nattch == 3);
+        store(other, 1, b'c');
+        exit(0);
+    }
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    assert!(status == 0);
+    assert!(load(map, 1) == b'c');
+    assert!(shm_stat(shmid).unwrap().shm_nattch == 1);
+
+    assert!(shmdt(map) == 0);
+    assert!(shmdt(map) == -EINVAL);
+    let ds = shm_stat(shmid).unwrap();
+    assert!(ds.shm_nattch == 0);
+    assert!(ds.shm_lpid as isize == getpid());
+    // not removed, so the data lives on
+    let map = attach(shmid, core::ptr::null(), 0);
+    assert!(load(map, 0) == b'p' && load(map, 1) == b'c');
+    assert!(shmdt(map) == 0);
+    assert!(shmctl(shmid, IPC_RMID, &mut ShmIdDs::default()) == 0);
+    assert!(shm_stat(shmid).err() == Some(-EINVAL));
+}
+
+fn test_keyed() {
+    assert!(shmget(KEY, PAGE_SIZE, 0o666) == -ENOENT);
+    assert!(shmget(KEY, 0, IPC_CREAT | 0o666) == -EINVAL);
+    let ret = shmget(KEY, PAGE_SIZE, IPC_CREAT | 0o666);
+    assert!(ret >= 0);
+    let shmid = ret as usize;
+    assert!(shmget(KEY, PAGE_SIZE, IPC_CREAT | 0o666) == ret);
+    assert!(shmget(KEY, 16, 0) == ret);
+    assert!(shmget(KEY, PAGE_SIZE, IPC_CREAT | IPC_EXCL | 0o666) == -EEXIST);
+    assert!(shmget(KEY, 2 * PAGE_SIZE, 0) == -EINVAL);
+    assert!(shm_stat(shmid).unwrap().shm_perm.key == KEY);
+
+    // removal waits for the last detach, but the key is free at once
+    let map = attach(shmid, core::ptr::null(), 0);
+    store(map, 0, b'k');
+    assert!(shmctl(shmid, IPC_RMID, &mut ShmIdDs::default()) == 0);
+    let ds = shm_stat(shmid).unwrap();
+    assert!(ds.shm_perm.mode & SHM_DEST != 0);
+    assert!(ds.shm_perm.key == IPC_PRIVATE);
+    assert!(shmget(KEY, PAGE_SIZE, 0) == -ENOENT);
+    assert!(load(map, 0) == b'k');
+    assert!(shmdt(map) == 0);
+    assert!(shm_stat(shmid).err() == Some(-EINVAL));
+    assert!(shmat(shmid, core::ptr::null(), 0) == -EINVAL);
+}
+
+fn test_attach_flags() {
+    let shmid = shmget(IPC_PRIVATE, 2 * PAGE_SIZE, 0o600) as usize;
+    let taken = mmap(
+        core::ptr::null(),
+        2 * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    ) as usize as *mut u8;
+    store(taken, 0, b't');
+    // an address in use, or not aligned
+    assert!(shmat(shmid, taken, 0) == -EINVAL);
+    assert!(shmat(shmid, unsafe { taken.add(1) }, 0) == -EINVAL);
+    assert!(shmat(shmid, core::ptr::null(), SHM_REMAP) == -EINVAL);
+    // SHM_RND rounds it down, and SHM_REMAP takes the place over
+    let map = attach(shmid, unsafe { taken.add(1) }, SHM_RND | SHM_REMAP);
+    assert!(map == taken);
+    assert!(load(map, 0) == 0);
+    store(map, 0, b's');
+
+    let ro = attach(shmid, core::ptr::null(), SHM_RDONLY);
+    assert!(load(ro, 0) == b's');
+    assert!(segfaults(|| store(ro, 0, b'x')));
+    assert!(load(ro, 0) == b's');
+    assert!(shm_stat(shmid).unwrap().shm_nattch == 2);
+
+    assert!(shmdt(ro) == 0);
+    assert!(shmdt(map) == 0);
+    assert!(shmctl(shmid, IPC_RMID, &mut ShmIdDs::default()) == 0);
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    test_private();
+    test_keyed();
+    test_attach_flags();
+    println!("shm_test passed");
+    0
+}
@@ -0,0 +1,143 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const ENOMEM: isize = 12;
+const EINVAL: isize = 22;
+
+const SS_ONSTACK: i32 = 1;
+const SS_DISABLE: i32 = 2;
+const MINSIGSTKSZ: usize = 2048;
+
+const STACK_SIZE: usize = 16384;
+
+static mut ALT_STACK: [u8; STACK_SIZE] = [0; STACK_SIZE];
+
+/// Stack pointers of the handlers, roughly.
+static OUTER_SP: AtomicUsize = AtomicUsize::new(0);
+static INNER_SP: AtomicUsize = AtomicUsize::new(0);
+/// Flags of the alternate signal stack as seen by the outer handler.
+static OUTER_FLAGS: AtomicIsize = AtomicIsize::new(0);
+/// What sigaltstack returned in the outer handler.
+static OUTER_SET: AtomicIsize = AtomicIsize::new(0);
+
+fn alt_stack() -> usize {
+    core::ptr::addr_of_mut!(ALT_STACK) as usize
+}
+
+fn on_alt_stack(sp: usize) -> bool {
+    sp > alt_stack() && sp <= alt_stack() + STACK_SIZE
+}
+
+fn stack_pointer() -> usize {
+    let marker = 0u8;
+    core::hint::black_box(&marker) as *const u8 as usize
+}
+
+fn on_sigusr1(_signal: usize) {
+    OUTER_SP.store(stack_pointer(), Ordering::SeqCst);
+    let mut old = SignalStack::default();
+    assert!(sigaltstack(None, Some(&mut old)) == 0);
+    OUTER_FLAGS.store(old.ss_flags as isize, Ordering::SeqCst);
+    // the stack in use can not be changed
+    let ss = SignalStack {
+        ss_flags: SS_DISABLE,
+        ..Default::default()
+    };
+    OUTER_SET.store(sigaltstack(Some(&ss), None), Ordering::SeqCst);
+    // a nested handler goes on below this one
+    kill(getpid(), Sig::SIGUSR2);
+}
+
+fn on_sigusr2(_signal: usize) {
+    INNER_SP.store(stack_pointer(), Ordering::SeqCst);
+}
+
+fn set_handler(sig: Sig, handler: fn(usize), flags: SigActionFlag) {
+    let mut act = SigAction::default();
+    act.sa_handler = handler as usize;
+    act.sa_flags = flags;
+    let mut old = SigAction::default();
+    assert!(sigaction(sig, &act, &mut old) == 0);
+}
+
+fn set_alt_stack(flags: i32, size: usize) -> isize {
+    let ss = SignalStack {
+        ss_sp: alt_stack(),
+        ss_flags: flags,
+        ss_size: size,
+    };
+    sigaltstack(Some(&ss), None)
+}
+
+fn test_settings() {
+    let mut old = SignalStack::default();
+    assert!(sigaltstack(None, Some(&mut old)) == 0);
+    assert!(old.ss_flags == SS_DISABLE);
+
+    assert!(set_alt_stack(0, MINSIGSTKSZ - 1) == -ENOMEM);
+    assert!(set_alt_stack(0x10, STACK_SIZE) == -EINVAL);
+    assert!(set_alt_stack(0, STACK_SIZE) == 0);
+    assert!(sigaltstack(None, Some(&mut old)) == 0);
+    assert!(old.ss_sp == alt_stack() && old.ss_size == STACK_SIZE && old.ss_flags == 0);
+
+    // a child of fork inherits it
+    let pid = fork();
+    if pid == 0 {
+        let mut inherited = SignalStack::default();
+        assert!(sigaltstack(None, Some(&mut inherited)) == 0);
+        exit((inherited.ss_sp != alt_stack()) as i32);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+}
+
+fn test_delivery() {
+    let main_sp = stack_pointer();
+    assert!(!on_alt_stack(main_sp));
+
+    // without SA_ONSTACK the handler runs on the stack it interrupts
+    set_handler(Sig::SIGUSR2, on_sigusr2, SigActionFlag::empty());
+    kill(getpid(), Sig::SIGUSR2);
+    assert!(!on_alt_stack(INNER_SP.load(Ordering::SeqCst)));
+
+    set_handler(Sig::SIGUSR1, on_sigusr1, SigActionFlag::SA_ONSTACK);
+    set_handler(Sig::SIGUSR2, on_sigusr2, SigActionFlag::SA_ONSTACK);
+    kill(getpid(), Sig::SIGUSR1);
+    let outer = OUTER_SP.load(Ordering::SeqCst);
+    let inner = INNER_SP.load(Ordering::SeqCst);
+    assert!(on_alt_stack(outer));
+    assert!(OUTER_FLAGS.load(Ordering::SeqCst) == SS_ONSTACK as isize);
+    assert!(OUTER_SET.load(Ordering::SeqCst) == -EPERM);
+    // the nested handler stays on the alternate stack, below the outer one
+    assert!(on_alt_stack(inner) && inner < outer);
+
+    // both handlers returned, and the stack is set as before
+    let mut old = SignalStack::default();
+    assert!(sigaltstack(None, Some(&mut old)) == 0);
+    assert!(old.ss_sp == alt_stack() && old.ss_flags == 0);
+
+    // once disabled, the handler runs on the normal stack again
+    let ss = SignalStack {
+        ss_flags: SS_DISABLE,
+        ..Default::default()
+    };
+    assert!(sigaltstack(Some(&ss), None) == 0);
+    kill(getpid(), Sig::SIGUSR2);
+    assert!(!on_alt_stack(INNER_SP.load(Ordering::SeqCst)));
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin sigaltstack test");
+    test_settings();
+    test_delivery();
+    println!("sigaltstack test passed");
+    0
+}
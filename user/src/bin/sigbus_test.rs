@@ -0,0 +1,97 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use user_lib::*;
+
+const PROT_READ: i32 = 0x1;
+const MAP_SHARED: i32 = 0x01;
+
+const PAGE_SIZE: usize = 4096;
+const BUS_ADRERR: i32 = 2;
+
+/// The head of `siginfo_t`, `si_addr` is the first field of the union.
+#[repr(C)]
+struct SigInfoHead {
+    si_signo: i32,
+    si_errno: i32,
+    si_code: i32,
+    _pad: i32,
+    si_addr: usize,
+}
+
+static FAULT_ADDR: AtomicUsize = AtomicUsize::new(0);
+
+fn on_sigbus(signal: usize, info: *const SigInfoHead, _context: usize) {
+    let info = unsafe { &*info };
+    let ok = signal == Sig::SIGBUS.raw()
+        && info.si_signo == Sig::SIGBUS.raw() as i32
+        && info.si_code == BUS_ADRERR
+        && info.si_addr == FAULT_ADDR.load(Ordering::Relaxed);
+    // returning would fault again
+    exit(if ok { 0 } else { 1 });
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin sigbus test");
+    let path = "/tmp/sigbus_test\0";
+    let fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let page = [b'a'; PAGE_SIZE];
+    for _ in 0..3 {
+        assert!(write(fd, &page) == PAGE_SIZE as isize);
+    }
+    let addr = mmap(
+        core::ptr::null(),
+        3 * PAGE_SIZE,
+        PROT_READ,
+        MAP_SHARED,
+        fd,
+        0,
+    );
+    assert!(addr > 0);
+    let map = addr as usize as *const u8;
+    let at = |offset: usize| unsafe { map.add(offset).read_volatile() };
+    assert!(at(PAGE_SIZE) == b'a');
+
+    // another process truncates the file to one page
+    let pid = fork();
+    if pid == 0 {
+        assert!(truncate(path, PAGE_SIZE as isize) == 0);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    assert!(at(0) == b'a');
+
+    // both the page mapped before and the one never touched are gone
+    for offset in [PAGE_SIZE, 2 * PAGE_SIZE + 8] {
+        let pid = fork();
+        if pid == 0 {
+            let mut new = SigAction::default();
+            let mut old = SigAction::default();
+            new.sa_handler = on_sigbus as usize;
+            new.sa_flags = SigActionFlag::SA_SIGINFO;
+            assert!(sigaction(Sig::SIGBUS, &new, &mut old) == 0);
+            FAULT_ADDR.store(map as usize + offset, Ordering::Relaxed);
+            at(offset);
+            // not reached
+            exit(2);
+        }
+        assert!(waitpid(pid as usize, &mut exit_code) == pid);
+        assert!(exit_code == 0);
+    }
+
+    close(fd);
+    unlink(path);
+    println!("sigbus test pass.");
+    0
+}
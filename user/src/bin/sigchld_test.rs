@@ -0,0 +1,123 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicI32, Ordering};
+
+use user_lib::*;
+
+const EINTR: isize = 4;
+const ECHILD: isize = 10;
+
+const WNOHANG: i32 = 1;
+
+const SIG_DFL: usize = 0;
+const SIG_IGN: usize = 1;
+
+const CLD_EXITED: i32 = 1;
+const CLD_KILLED: i32 = 2;
+
+/// The head of `siginfo_t` and the fields of SIGCHLD in its union.
+#[repr(C)]
+struct SigChldInfo {
+    si_signo: i32,
+    si_errno: i32,
+    si_code: i32,
+    _pad: i32,
+    si_pid: i32,
+    si_uid: u32,
+    si_status: i32,
+}
+
+static CODE: AtomicI32 = AtomicI32::new(0);
+static PID: AtomicI32 = AtomicI32::new(0);
+static STATUS: AtomicI32 = AtomicI32::new(0);
+
+fn on_sigchld(signal: usize, info: *const SigChldInfo, _context: usize) {
+    let info = unsafe { &*info };
+    assert!(signal == Sig::SIGCHLD.raw() && info.si_signo == Sig::SIGCHLD.raw() as i32);
+    CODE.store(info.si_code, Ordering::SeqCst);
+    PID.store(info.si_pid, Ordering::SeqCst);
+    STATUS.store(info.si_status, Ordering::SeqCst);
+    sigreturn();
+}
+
+fn set_sigchld(handler: usize, flags: SigActionFlag) {
+    let mut act = SigAction::default();
+    act.sa_handler = handler;
+    act.sa_flags = flags;
+    let mut old = SigAction::default();
+    assert!(sigaction(Sig::SIGCHLD, &act, &mut old) == 0);
+}
+
+/// Take what the handler saw of the last SIGCHLD, as (code, pid, status).
+fn take_sigchld() -> (i32, isize, i32) {
+    (
+        CODE.swap(0, Ordering::SeqCst),
+        PID.swap(0, Ordering::SeqCst) as isize,
+        STATUS.swap(0, Ordering::SeqCst),
+    )
+}
+
+fn fork_exit(code: i32, delay: usize) -> isize {
+    let pid = fork();
+    if pid == 0 {
+        sleep(delay);
+        exit(code);
+    }
+    pid
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin sigchld test");
+    set_sigchld(on_sigchld as usize, SigActionFlag::SA_SIGINFO);
+
+    // the handler runs before wait4 returns to the parent
+    let pid = fork_exit(7, 50);
+    let mut status = 0;
+    assert!(wait4(pid, &mut status, 0, None) == pid);
+    assert!((status >> 8) & 0xff == 7);
+    assert!(take_sigchld() == (CLD_EXITED, pid, 7));
+
+    // a child killed by a signal
+    let pid = fork_exit(0, 10_000);
+    sleep(20);
+    assert!(kill(pid, Sig::SIGKILL) == 0);
+    assert!(wait4(pid, &mut status, 0, None) == pid);
+    assert!(status & 0x7f == Sig::SIGKILL.raw() as i32);
+    assert!(take_sigchld() == (CLD_KILLED, pid, Sig::SIGKILL.raw() as i32));
+
+    // sigsuspend is woken by the exit of a child
+    let pid = fork_exit(3, 50);
+    assert!(sigsuspend(&SigSet::empty()) == -EINTR);
+    assert!(take_sigchld() == (CLD_EXITED, pid, 3));
+    assert!(wait4(pid, &mut status, 0, None) == pid);
+
+    // with SA_NOCLDWAIT the child is reaped by itself, a wait4 waits for all
+    // children to exit and fails then, the handler still runs
+    set_sigchld(
+        on_sigchld as usize,
+        SigActionFlag::SA_SIGINFO | SigActionFlag::SA_NOCLDWAIT,
+    );
+    let pid = fork_exit(5, 50);
+    assert!(wait4(-1, &mut status, 0, None) == -ECHILD);
+    assert!(take_sigchld() == (CLD_EXITED, pid, 5));
+
+    // so it is with SIG_IGN, but without the handler
+    set_sigchld(SIG_IGN, SigActionFlag::empty());
+    let pid = fork_exit(0, 0);
+    sleep(50);
+    assert!(wait4(pid, &mut status, WNOHANG, None) == -ECHILD);
+    assert!(take_sigchld() == (0, 0, 0));
+
+    // and the default leaves the zombie for wait4 again
+    set_sigchld(SIG_DFL, SigActionFlag::empty());
+    let pid = fork_exit(9, 0);
+    sleep(50);
+    assert!(wait4(pid, &mut status, WNOHANG, None) == pid);
+    assert!((status >> 8) & 0xff == 9);
+    println!("sigchld test passed");
+    0
+}
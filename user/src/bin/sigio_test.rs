@@ -0,0 +1,165 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const ESRCH: isize = 3;
+const EINVAL: isize = 22;
+
+const F_SETFL: isize = 4;
+const F_SETOWN: isize = 8;
+const F_GETOWN: isize = 9;
+const F_SETSIG: isize = 10;
+const F_GETSIG: isize = 11;
+
+const POLL_IN: i32 = 1;
+const POLLIN: i64 = 0x1;
+
+/// The real-time signal set by `F_SETSIG`.
+const SIGRT: i32 = 40;
+
+/// The head of `siginfo_t` and the fields of SIGIO in its union.
+#[repr(C)]
+struct SigPollInfo {
+    si_signo: i32,
+    si_errno: i32,
+    si_code: i32,
+    _pad: i32,
+    si_band: i64,
+    si_fd: i32,
+}
+
+static SIGIOS: AtomicUsize = AtomicUsize::new(0);
+static SIGRTS: AtomicUsize = AtomicUsize::new(0);
+static CODE: AtomicI32 = AtomicI32::new(0);
+static BAND: AtomicI32 = AtomicI32::new(0);
+static FD: AtomicI32 = AtomicI32::new(-1);
+
+fn on_sigio(_signal: usize) {
+    SIGIOS.fetch_add(1, Ordering::SeqCst);
+}
+
+fn on_sigrt(signal: usize, info: *const SigPollInfo, _context: usize) {
+    let info = unsafe { &*info };
+    assert!(signal == SIGRT as usize && info.si_signo == SIGRT);
+    CODE.store(info.si_code, Ordering::SeqCst);
+    BAND.store(info.si_band as i32, Ordering::SeqCst);
+    FD.store(info.si_fd, Ordering::SeqCst);
+    SIGRTS.fetch_add(1, Ordering::SeqCst);
+    sigreturn();
+}
+
+fn set_handler(sig: Sig, handler: usize, flags: SigActionFlag) {
+    let mut act = SigAction::default();
+    act.sa_handler = handler;
+    act.sa_flags = flags | SigActionFlag::SA_RESTART;
+    let mut old = SigAction::default();
+    assert!(sigaction(sig, &act, &mut old) == 0);
+}
+
+/// Write each of `chunks` to the pipe from a child, `gap` milliseconds apart.
+fn write_from_child(wfd: usize, chunks: &[&[u8]], gap: usize) {
+    let pid = fork();
+    if pid == 0 {
+        for chunk in chunks {
+            assert!(write(wfd, chunk) == chunk.len() as isize);
+            sleep(gap);
+        }
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    // let the signal come
+    sleep(20);
+}
+
+fn drain(rfd: usize) {
+    let mut buf = [0u8; 64];
+    while read(rfd, &mut buf) > 0 {}
+    assert!(read(rfd, &mut buf) == -EAGAIN);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin sigio test");
+    set_handler(Sig::SIGIO, on_sigio as usize, SigActionFlag::empty());
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::O_NONBLOCK) == 0);
+    let (rfd, wfd) = (fds[0] as usize, fds[1] as usize);
+    let async_flags = (OpenFlags::O_NONBLOCK | OpenFlags::O_ASYNC).bits() as usize;
+
+    // no owner, or no O_ASYNC, no signal
+    assert!(fcntl(rfd, F_GETOWN, 0) == 0);
+    assert!(fcntl(rfd, F_SETFL, async_flags) == 0);
+    write_from_child(wfd, &[b"a"], 0);
+    drain(rfd);
+    assert!(fcntl(rfd, F_SETFL, OpenFlags::O_NONBLOCK.bits() as usize) == 0);
+    assert!(fcntl(rfd, F_SETOWN, getpid() as usize) == 0);
+    assert!(fcntl(rfd, F_GETOWN, 0) == getpid());
+    write_from_child(wfd, &[b"a"], 0);
+    drain(rfd);
+    assert!(SIGIOS.load(Ordering::SeqCst) == 0);
+
+    // one signal as the pipe turns readable, none while it stays so
+    assert!(fcntl(rfd, F_SETFL, async_flags) == 0);
+    write_from_child(wfd, &[b"a", b"b", b"c"], 20);
+    assert!(SIGIOS.load(Ordering::SeqCst) == 1);
+    drain(rfd);
+    write_from_child(wfd, &[b"d"], 0);
+    assert!(SIGIOS.load(Ordering::SeqCst) == 2);
+    drain(rfd);
+
+    // the signal set by F_SETSIG tells the fd O_ASYNC was set at
+    set_handler(
+        Sig::from_i32(SIGRT),
+        on_sigrt as usize,
+        SigActionFlag::SA_SIGINFO,
+    );
+    assert!(fcntl(rfd, F_SETSIG, 100) == -EINVAL);
+    assert!(fcntl(rfd, F_SETSIG, SIGRT as usize) == 0);
+    assert!(fcntl(rfd, F_GETSIG, 0) == SIGRT as isize);
+    assert!(fcntl(rfd, F_SETFL, OpenFlags::O_NONBLOCK.bits() as usize) == 0);
+    let dupfd = dup(rfd);
+    assert!(dupfd > 0);
+    assert!(fcntl(dupfd as usize, F_SETFL, async_flags) == 0);
+    write_from_child(wfd, &[b"e", b"f"], 20);
+    assert!(SIGRTS.load(Ordering::SeqCst) == 1);
+    assert!(CODE.load(Ordering::SeqCst) == POLL_IN);
+    assert!(BAND.load(Ordering::SeqCst) as i64 & POLLIN != 0);
+    assert!(FD.load(Ordering::SeqCst) as isize == dupfd);
+    drain(rfd);
+    close(dupfd as usize);
+    assert!(fcntl(rfd, F_SETSIG, 0) == 0);
+
+    // a process group owns it too
+    let pgid = getpgid(0);
+    assert!(fcntl(rfd, F_SETOWN, -pgid as usize) == 0);
+    assert!(fcntl(rfd, F_GETOWN, 0) == -pgid);
+    write_from_child(wfd, &[b"g"], 0);
+    assert!(SIGIOS.load(Ordering::SeqCst) == 3);
+    drain(rfd);
+
+    // no longer owned once the owner exits
+    assert!(fcntl(rfd, F_SETOWN, 1 << 20) == -ESRCH);
+    let pid = fork();
+    if pid == 0 {
+        assert!(fcntl(rfd, F_SETOWN, getpid() as usize) == 0);
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    assert!(fcntl(rfd, F_GETOWN, 0) == 0);
+    write_from_child(wfd, &[b"h"], 0);
+    drain(rfd);
+    assert!(SIGIOS.load(Ordering::SeqCst) == 3);
+
+    close(rfd);
+    close(wfd);
+    println!("sigio test passed");
+    0
+}
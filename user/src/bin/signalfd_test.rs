@@ -0,0 +1,101 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::{
+    mem::size_of,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const EINVAL: isize = 22;
+
+const SFD_NONBLOCK: i32 = 0o4000;
+
+static HANDLED: AtomicBool = AtomicBool::new(false);
+
+fn handler() {
+    HANDLED.store(true, Ordering::SeqCst);
+    sigreturn();
+}
+
+/// Read one signal of `fd`, returning its number or the error.
+fn read_signal(fd: usize) -> isize {
+    let mut buf = [0u8; size_of::<SignalFdSiginfo>()];
+    let ret = read(fd, &mut buf);
+    if ret < 0 {
+        return ret;
+    }
+    assert!(ret as usize == size_of::<SignalFdSiginfo>());
+    let info = unsafe { (buf.as_ptr() as *const SignalFdSiginfo).read_unaligned() };
+    assert!(info.ssi_pid == getpid() as u32 || info.ssi_pid == getppid() as u32);
+    info.ssi_signo as isize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin signalfd test");
+    let mut act = SigAction::default();
+    act.sa_handler = handler as usize;
+    let mut old = SigAction::default();
+    assert!(sigaction(Sig::SIGUSR1, &act, &mut old) == 0);
+    let blocked = SigSet::SIGUSR1 | SigSet::SIGUSR2;
+    assert!(sigprocmask(SIG_BLOCK, Some(&blocked), None) == 0);
+
+    // a pending signal is read instead of delivered
+    let fd = signalfd(-1, &SigSet::SIGUSR1, SFD_NONBLOCK);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(read_signal(fd) == -EAGAIN);
+    let mut fds = [PollFd::new(fd, POLLIN)];
+    assert!(ppoll(&mut fds, Some(&TimeSpec::from_ms(0))) == 0);
+    assert!(kill(getpid(), Sig::SIGUSR1) == 0);
+    assert!(ppoll(&mut fds, Some(&TimeSpec::from_ms(0))) == 1);
+    assert!(fds[0].revents == POLLIN);
+    assert!(read_signal(fd) == Sig::SIGUSR1.raw() as isize);
+    assert!(read_signal(fd) == -EAGAIN);
+    assert!(sigprocmask(SIG_UNBLOCK, Some(&SigSet::SIGUSR1), None) == 0);
+    assert!(!HANDLED.load(Ordering::SeqCst));
+    assert!(sigprocmask(SIG_BLOCK, Some(&SigSet::SIGUSR1), None) == 0);
+
+    // a read waits for a signal sent by another process
+    let blocking = signalfd(-1, &SigSet::SIGUSR1, 0) as usize;
+    let parent = getpid();
+    let pid = fork();
+    if pid == 0 {
+        sleep(50);
+        assert!(kill(parent, Sig::SIGUSR1) == 0);
+        exit(0);
+    }
+    assert!(read_signal(blocking) == Sig::SIGUSR1.raw() as isize);
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    close(blocking);
+
+    // the mask can be replaced, and several signals are read at once
+    assert!(signalfd(fd as isize, &SigSet::SIGUSR2, 0) == fd as isize);
+    assert!(kill(getpid(), Sig::SIGUSR1) == 0);
+    assert!(read_signal(fd) == -EAGAIN);
+    assert!(signalfd(fd as isize, &blocked, 0) == fd as isize);
+    assert!(kill(getpid(), Sig::SIGUSR2) == 0);
+    let mut buf = [0u8; 3 * size_of::<SignalFdSiginfo>()];
+    assert!(read(fd, &mut buf) == 2 * size_of::<SignalFdSiginfo>() as isize);
+    assert!(read_signal(fd) == -EAGAIN);
+
+    // errors
+    let mut short = [0u8; 8];
+    assert!(read(fd, &mut short) == -EINVAL);
+    let efd = eventfd(0, 0);
+    assert!(signalfd(efd, &SigSet::SIGUSR1, 0) == -EINVAL);
+    assert!(signalfd(-1, &SigSet::SIGUSR1, 1) == -EINVAL);
+    close(efd as usize);
+    close(fd);
+
+    assert!(sigprocmask(SIG_UNBLOCK, Some(&blocked), None) == 0);
+    assert!(!HANDLED.load(Ordering::SeqCst));
+    println!("signalfd test passed");
+    0
+}
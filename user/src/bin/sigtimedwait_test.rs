@@ -0,0 +1,107 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const EINTR: isize = 4;
+const EINVAL: isize = 22;
+
+const SI_USER: i32 = 0;
+
+static HANDLED: AtomicBool = AtomicBool::new(false);
+
+fn handler() {
+    HANDLED.store(true, Ordering::SeqCst);
+    sigreturn();
+}
+
+fn now_ms() -> usize {
+    let mut ts = TimeSpec::from_ms(0);
+    assert!(clock_gettime(CLOCK_MONOTONIC, &mut ts) == 0);
+    ts.into_ms()
+}
+
+fn pending() -> SigSet {
+    let mut set = SigSet::empty();
+    assert!(sigpending(&mut set) == 0);
+    set
+}
+
+/// A blocked signal is pending until it is taken, without its handler.
+fn test_pending() {
+    assert!(pending().is_empty());
+    assert!(kill(getpid(), Sig::SIGUSR1) == 0);
+    assert!(pending() == SigSet::SIGUSR1);
+
+    let mut info = LinuxSigInfo::default();
+    let set = SigSet::SIGUSR1 | SigSet::SIGUSR2;
+    assert!(sigtimedwait(&set, Some(&mut info), None) == Sig::SIGUSR1.raw() as isize);
+    assert!(info.si_signo == Sig::SIGUSR1.raw() as i32);
+    assert!(info.si_code == SI_USER);
+    // `si_pid` is the first field of the union
+    assert!(info.si_fields[0] as u32 as isize == getpid());
+    assert!(pending().is_empty());
+    assert!(!HANDLED.load(Ordering::SeqCst));
+}
+
+fn test_timeout() {
+    let set = SigSet::SIGUSR2;
+    assert!(sigtimedwait(&set, None, Some(&TimeSpec::from_ms(0))) == -EAGAIN);
+    let start = now_ms();
+    assert!(sigtimedwait(&set, None, Some(&TimeSpec::from_ms(50))) == -EAGAIN);
+    assert!(now_ms() - start >= 50);
+    let bad = TimeSpec {
+        tv_sec: 0,
+        tv_nsec: 1_000_000_000,
+    };
+    assert!(sigtimedwait(&set, None, Some(&bad)) == -EINVAL);
+    // a signal already pending is taken before the timeout is looked at
+    assert!(kill(getpid(), Sig::SIGUSR2) == 0);
+    assert!(sigtimedwait(&set, None, Some(&TimeSpec::from_ms(0))) == Sig::SIGUSR2.raw() as isize);
+}
+
+/// A child waits for the signals sent by its parent.
+fn test_wait() {
+    let pid = fork();
+    if pid == 0 {
+        let mut info = LinuxSigInfo::default();
+        let ret = sigtimedwait(&SigSet::SIGUSR1, Some(&mut info), None);
+        assert!(info.si_fields[0] as u32 as isize == getppid());
+        // a signal not blocked but not waited for interrupts the wait, and is
+        // delivered
+        let ret2 = sigtimedwait(&SigSet::SIGUSR2, None, Some(&TimeSpec::from_ms(5000)));
+        assert!(ret2 == -EINTR);
+        assert!(HANDLED.load(Ordering::SeqCst));
+        exit(ret as i32);
+    }
+    sleep(50);
+    assert!(kill(pid, Sig::SIGUSR1) == 0);
+    sleep(50);
+    // SIGTERM is not blocked, and is handled by `handler` in the child
+    assert!(kill(pid, Sig::SIGTERM) == 0);
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    assert!(status >> 8 == Sig::SIGUSR1.raw() as i32);
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut act = SigAction::default();
+    act.sa_handler = handler as usize;
+    let mut old = SigAction::default();
+    assert!(sigaction(Sig::SIGUSR1, &act, &mut old) == 0);
+    assert!(sigaction(Sig::SIGTERM, &act, &mut old) == 0);
+    let blocked = SigSet::SIGUSR1 | SigSet::SIGUSR2;
+    assert!(sigprocmask(SIG_BLOCK, Some(&blocked), None) == 0);
+
+    test_pending();
+    test_timeout();
+    test_wait();
+    println!("sigtimedwait_test passed");
+    0
+}
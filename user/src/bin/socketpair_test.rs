@@ -0,0 +1,92 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const SIG_IGN: usize = 1;
+const EAGAIN: isize = 11;
+const EPIPE: isize = 32;
+const EOPNOTSUPP: isize = 95;
+const EAFNOSUPPORT: isize = 97;
+
+/// Bigger than the buffer of a direction, so that the writer has to wait for
+/// the reader.
+const TRANSFER_LEN: usize = 64 * 1024;
+
+fn send_all(fd: usize) {
+    let mut buf = [0u8; 1000];
+    let mut sent = 0;
+    while sent < TRANSFER_LEN {
+        let len = buf.len().min(TRANSFER_LEN - sent);
+        for (i, byte) in buf[..len].iter_mut().enumerate() {
+            *byte = ((sent + i) % 251) as u8;
+        }
+        let ret = write(fd, &buf[..len]);
+        assert!(ret > 0);
+        sent += ret as usize;
+    }
+}
+
+fn recv_all(fd: usize) {
+    let mut buf = [0u8; 777];
+    let mut received = 0;
+    while received < TRANSFER_LEN {
+        let ret = read(fd, &mut buf);
+        assert!(ret > 0);
+        for (i, byte) in buf[..ret as usize].iter().enumerate() {
+            assert!(*byte == ((received + i) % 251) as u8);
+        }
+        received += ret as usize;
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin socketpair test");
+    let mut sv = [-1; 2];
+    assert!(socketpair(AF_INET, SOCK_STREAM, 0, &mut sv) == -EOPNOTSUPP);
+    assert!(socketpair(12345, SOCK_STREAM, 0, &mut sv) == -EAFNOSUPPORT);
+
+    // the flags apply to both ends
+    let types = SOCK_STREAM | SOCK_NONBLOCK | SOCK_CLOEXEC;
+    assert!(socketpair(AF_UNIX, types, 0, &mut sv) == 0);
+    let mut buf = [0u8; 16];
+    for fd in sv {
+        assert!(fcntl(fd as usize, F_GETFD, 0) == FD_CLOEXEC as isize);
+        assert!(read(fd as usize, &mut buf) == -EAGAIN);
+    }
+    assert!(write(sv[0] as usize, b"hi") == 2);
+    assert!(read(sv[1] as usize, &mut buf) == 2 && &buf[..2] == b"hi");
+    close(sv[0] as usize);
+    close(sv[1] as usize);
+
+    // both directions at once from two tasks
+    assert!(socketpair(AF_UNIX, SOCK_STREAM, 0, &mut sv) == 0);
+    let (parent, child) = (sv[0] as usize, sv[1] as usize);
+    let pid = fork();
+    if pid == 0 {
+        close(parent);
+        send_all(child);
+        recv_all(child);
+        close(child);
+        exit(0);
+    }
+    close(child);
+    recv_all(parent);
+    send_all(parent);
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+
+    // the peer is closed, like a pipe
+    assert!(read(parent, &mut buf) == 0);
+    let mut new = SigAction::default();
+    let mut old = SigAction::default();
+    new.sa_handler = SIG_IGN;
+    assert!(sigaction(Sig::SIGPIPE, &new, &mut old) == 0);
+    assert!(write(parent, b"lost") == -EPIPE);
+    close(parent);
+    println!("socketpair test pass.");
+    0
+}
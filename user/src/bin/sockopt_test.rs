@@ -0,0 +1,138 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const EINVAL: isize = 22;
+const ENOPROTOOPT: isize = 92;
+const EOPNOTSUPP: isize = 95;
+
+/// Least receive buffer, as linux `SOCK_MIN_RCVBUF`.
+const SOCK_MIN_RCVBUF: i32 = 2304;
+
+fn set_int(fd: usize, level: usize, optname: usize, val: i32) -> isize {
+    setsockopt(fd, level, optname, &val.to_ne_bytes())
+}
+
+fn get_int(fd: usize, level: usize, optname: usize) -> i32 {
+    let mut val = [0u8; 4];
+    let mut len = val.len() as u32;
+    assert!(getsockopt(fd, level, optname, &mut val, &mut len) == 0);
+    assert!(len == 4);
+    i32::from_ne_bytes(val)
+}
+
+/// `struct timeval` of 50 milliseconds.
+fn timeout_50ms() -> [u8; 16] {
+    let mut val = [0u8; 16];
+    val[8..].copy_from_slice(&50_000i64.to_ne_bytes());
+    val
+}
+
+/// Options at `SOL_SOCKET` behave the same for all kinds of sockets.
+fn check_socket_opts(fd: usize, types: i32) {
+    assert!(get_int(fd, SOL_SOCKET, SO_TYPE) == types);
+    assert!(get_int(fd, SOL_SOCKET, SO_ERROR) == 0);
+
+    // the size asked for is doubled, and kept in bounds
+    assert!(set_int(fd, SOL_SOCKET, SO_RCVBUF, 8192) == 0);
+    assert!(get_int(fd, SOL_SOCKET, SO_RCVBUF) == 16384);
+    assert!(set_int(fd, SOL_SOCKET, SO_RCVBUF, 1) == 0);
+    assert!(get_int(fd, SOL_SOCKET, SO_RCVBUF) == SOCK_MIN_RCVBUF);
+    assert!(set_int(fd, SOL_SOCKET, SO_SNDBUF, 1 << 30) == 0);
+    let max = get_int(fd, SOL_SOCKET, SO_SNDBUF);
+    assert!(max > 0 && max < 1 << 30);
+
+    for optname in [SO_REUSEADDR, SO_KEEPALIVE] {
+        assert!(get_int(fd, SOL_SOCKET, optname) == 0);
+        assert!(set_int(fd, SOL_SOCKET, optname, 1) == 0);
+        assert!(get_int(fd, SOL_SOCKET, optname) == 1);
+    }
+
+    let timeout = timeout_50ms();
+    assert!(setsockopt(fd, SOL_SOCKET, SO_SNDTIMEO, &timeout) == 0);
+    let mut val = [0u8; 16];
+    let mut len = val.len() as u32;
+    assert!(getsockopt(fd, SOL_SOCKET, SO_SNDTIMEO, &mut val, &mut len) == 0);
+    assert!(len == 16 && val == timeout);
+    // too short for a timeval
+    assert!(setsockopt(fd, SOL_SOCKET, SO_SNDTIMEO, &timeout[..4]) == -EINVAL);
+
+    // a short buffer gets as much as it holds
+    let mut val = [0u8; 2];
+    let mut len = val.len() as u32;
+    assert!(getsockopt(fd, SOL_SOCKET, SO_RCVBUF, &mut val, &mut len) == 0);
+    assert!(len == 2);
+    let mut len = u32::MAX;
+    assert!(getsockopt(fd, SOL_SOCKET, SO_RCVBUF, &mut val, &mut len) == -EINVAL);
+
+    assert!(set_int(fd, SOL_SOCKET, SO_TYPE, 0) == -ENOPROTOOPT);
+    let mut val = [0u8; 4];
+    let mut len = val.len() as u32;
+    assert!(getsockopt(fd, SOL_SOCKET, 999, &mut val, &mut len) == -ENOPROTOOPT);
+    assert!(set_int(fd, SOL_SOCKET, 999, 1) == -ENOPROTOOPT);
+}
+
+fn unix_test() {
+    let path = "/tmp/sockopt_test";
+    let addr = SockAddrUn::new(path);
+    let server = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    check_socket_opts(server, SOCK_STREAM as i32);
+    assert!(set_int(server, IPPROTO_TCP, TCP_NODELAY, 1) == -EOPNOTSUPP);
+
+    assert!(bind(server, &addr) == 0);
+    assert!(listen(server, 4) == 0);
+    // nobody connects, so accept gives up
+    assert!(setsockopt(server, SOL_SOCKET, SO_RCVTIMEO, &timeout_50ms()) == 0);
+    let mut peer = SockAddrUn::new("");
+    assert!(accept(server, &mut peer) == -EAGAIN);
+
+    assert!(set_int(server, SOL_SOCKET, SO_RCVBUF, 4096) == 0);
+    let client = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(connect(client, &addr) == 0);
+    let conn = accept(server, &mut peer);
+    assert!(conn >= 0);
+    let conn = conn as usize;
+    // the accepted socket inherits the options of the listening one
+    assert!(get_int(conn, SOL_SOCKET, SO_RCVBUF) == 8192);
+    assert!(get_int(conn, SOL_SOCKET, SO_REUSEADDR) == 1);
+    // and so the timeout, while nothing is sent
+    let mut buf = [0u8; 8];
+    assert!(recv(conn, &mut buf) == -EAGAIN);
+    assert!(send(client, b"hi") == 2);
+    assert!(recv(conn, &mut buf) == 2);
+
+    close(conn);
+    close(client);
+    close(server);
+    unlink("/tmp/sockopt_test\0");
+}
+
+fn inet_test() {
+    let tcp = socket(AF_INET, SOCK_STREAM, 0) as usize;
+    check_socket_opts(tcp, SOCK_STREAM as i32);
+    assert!(get_int(tcp, IPPROTO_TCP, TCP_NODELAY) == 0);
+    assert!(set_int(tcp, IPPROTO_TCP, TCP_NODELAY, 1) == 0);
+    assert!(get_int(tcp, IPPROTO_TCP, TCP_NODELAY) == 1);
+    let mut val = [0u8; 4];
+    let mut len = val.len() as u32;
+    assert!(getsockopt(tcp, IPPROTO_TCP, 999, &mut val, &mut len) == -ENOPROTOOPT);
+    close(tcp);
+
+    let udp = socket(AF_INET, SOCK_DGRAM, 0) as usize;
+    check_socket_opts(udp, SOCK_DGRAM as i32);
+    assert!(set_int(udp, IPPROTO_TCP, TCP_NODELAY, 1) == -EOPNOTSUPP);
+    close(udp);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin sockopt test");
+    unix_test();
+    inet_test();
+    println!("sockopt test pass.");
+    0
+}
@@ -0,0 +1,98 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String, vec::Vec};
+
+use user_lib::*;
+
+const PATH: &str = "/softirq_test.tmp\0";
+const BLOCKS: usize = 64;
+const ROUNDS: usize = 8;
+
+/// Worst time interrupts may be off in a handler, with the work deferred.
+const IRQ_OFF_BOUND_US: u64 = 20_000;
+
+fn read_all(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+/// Values of the row `name` of `/proc/softirqs`, one for each online hart.
+fn softirq_row(name: &str) -> Vec<u64> {
+    let softirqs = read_all("/proc/softirqs\0");
+    let label = format!("{name}:");
+    let line = softirqs
+        .lines()
+        .find(|line| line.split_whitespace().next() == Some(label.as_str()))
+        .unwrap();
+    line.split_whitespace()
+        .skip(1)
+        .map(|value| value.parse().unwrap())
+        .collect()
+}
+
+/// Write and read back a file on the disk, over and over.
+fn block_io() {
+    let block = [0x5a_u8; 4096];
+    let mut buf = [0u8; 4096];
+    for _ in 0..ROUNDS {
+        let fd = openat(
+            PATH,
+            OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+        );
+        assert!(fd >= 0);
+        let fd = fd as usize;
+        for _ in 0..BLOCKS {
+            assert!(write(fd, &block) == block.len() as isize);
+        }
+        assert!(fsync(fd) == 0);
+        assert!(lseek(fd, 0, 0) == 0);
+        for _ in 0..BLOCKS {
+            assert!(read(fd, &mut buf) == buf.len() as isize);
+            assert!(buf == block);
+        }
+        close(fd);
+    }
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let timers: u64 = softirq_row("TIMER").iter().sum();
+
+    let pid = fork();
+    if pid == 0 {
+        block_io();
+        exit(0);
+    }
+    // timers expire in the softirq while the child does block i/o
+    for _ in 0..10 {
+        sleep(10);
+    }
+    let mut status = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    assert!(status == 0);
+    assert!(unlink(PATH) == 0);
+
+    assert!(softirq_row("TIMER").iter().sum::<u64>() >= timers + 10);
+    let latency = softirq_row("MAX_LATENCY_US").into_iter().max().unwrap();
+    let irq_off = softirq_row("MAX_IRQ_OFF_US").into_iter().max().unwrap();
+    println!("[softirq] max latency {latency}us, max irq off {irq_off}us");
+    assert!(irq_off < IRQ_OFF_BOUND_US);
+    println!("softirq_test passed");
+    0
+}
@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{string::String, vec::Vec};
+
+use user_lib::*;
+
+fn read_all(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 256];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+fn stat_of_nlink(path: &str) -> u32 {
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    st.st_nlink
+}
+
+/// The image is built with a tree whose link counts, sizes and block sizes
+/// were recorded by Linux in /stat_conf.expected, see stat_conf_prepare.sh.
+/// What the kernel reports for the same files on disk must match.
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin stat conformance test");
+    let expected = read_all("/stat_conf.expected\0");
+    let mut checked = 0;
+    for line in expected.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [path, nlink, size, blksize] = fields[..] else {
+            panic!("bad line {line:?}");
+        };
+        let mut st = Kstat::default();
+        let mut c_path = String::from(path);
+        c_path.push('\0');
+        assert!(stat(&c_path, &mut st) == 0);
+        println!(
+            "{path}: nlink {} size {} blksize {}",
+            st.st_nlink, st.st_size, st.st_blksize
+        );
+        assert!(st.st_nlink == nlink.parse::<u32>().unwrap());
+        assert!(st.st_size == size.parse::<i64>().unwrap());
+        assert!(st.st_blksize == blksize.parse::<i32>().unwrap());
+        checked += 1;
+    }
+    assert!(checked > 0);
+    // unlinking one of two links made before boot leaves the file in place
+    if stat_of_nlink("/stat_conf/file\0") == 2 {
+        assert!(unlink("/stat_conf/file.link\0") == 0);
+        assert!(stat_of_nlink("/stat_conf/file\0") == 1);
+        assert!(read_all("/stat_conf/file\0") == "stat conformance\n");
+        // put it back for the next boot of the image
+        assert!(link("/stat_conf/file\0", "/stat_conf/file.link\0") == 0);
+    }
+    println!("stat conformance test pass.");
+    0
+}
@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+fn stat_of(path: &str) -> Kstat {
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    st
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin stat test");
+
+    // a directory links to itself and to its parent through ".."
+    let dir = "/stat_test_dir\0";
+    assert!(mkdir(dir) == 0);
+    assert!(stat_of(dir).st_nlink == 2);
+    assert!(mkdir("/stat_test_dir/a\0") == 0);
+    assert!(mkdir("/stat_test_dir/b\0") == 0);
+    assert!(stat_of(dir).st_nlink == 4);
+    assert!(rmdir("/stat_test_dir/a\0") == 0);
+    assert!(stat_of(dir).st_nlink == 3);
+
+    // directory size is a whole number of blocks
+    let st = stat_of(dir);
+    assert!(st.st_blksize >= 512 && (st.st_blksize & (st.st_blksize - 1)) == 0);
+    assert!(st.st_size > 0 && st.st_size % st.st_blksize as i64 == 0);
+
+    // hard links of a regular file
+    let file = "/stat_test_dir/file\0";
+    let fd = openat(file, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+    let st = stat_of(file);
+    assert!(st.st_nlink == 1);
+    assert!(st.st_rdev == 0);
+    assert!(link(file, "/stat_test_dir/file2\0") == 0);
+    assert!(stat_of(file).st_nlink == 2);
+    assert!(unlink("/stat_test_dir/file2\0") == 0);
+    assert!(stat_of(file).st_nlink == 1);
+    // files do not count in the link count of their directory
+    assert!(stat_of(dir).st_nlink == 3);
+
+    assert!(unlink(file) == 0);
+    assert!(rmdir("/stat_test_dir/b\0") == 0);
+    assert!(stat_of(dir).st_nlink == 2);
+    assert!(rmdir(dir) == 0);
+
+    // tmpfs prefers page sized I/O
+    let tmp_file = "/tmp/stat_test\0";
+    let fd = openat(tmp_file, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+    assert!(stat_of(tmp_file).st_blksize == 4096);
+    assert!(unlink(tmp_file) == 0);
+
+    // /dev/null is character device 1:3
+    let st = stat_of("/dev/null\0");
+    assert!(st.st_rdev == (1 << 8) | 3);
+    println!("stat test pass.");
+    0
+}
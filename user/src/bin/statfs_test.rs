@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+
+const EXT4_SUPER_MAGIC: i64 = 0xef53;
+const MSDOS_SUPER_MAGIC: i64 = 0x4d44;
+const PROC_SUPER_MAGIC: i64 = 0x9fa0;
+const TMPFS_MAGIC: i64 = 0x01021994;
+const PIPEFS_MAGIC: i64 = 0x50495045;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin statfs test");
+    let mut root = StatFs::default();
+    assert!(statfs("/\0", &mut root) == 0);
+    assert!(root.f_type == EXT4_SUPER_MAGIC || root.f_type == MSDOS_SUPER_MAGIC);
+    assert!(root.f_bsize > 0 && root.f_blocks > 0);
+    assert!(root.f_bfree <= root.f_blocks && root.f_bavail <= root.f_blocks);
+    if root.f_type == EXT4_SUPER_MAGIC {
+        assert!(root.f_files > 0 && root.f_ffree <= root.f_files);
+    }
+
+    // writing a file takes free blocks
+    let path = "/statfs_test\0";
+    let fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let data = [b'x'; 4096];
+    for _ in 0..64 {
+        assert!(write(fd as usize, &data) == data.len() as isize);
+    }
+    assert!(fsync(fd as usize) == 0);
+    let mut written = StatFs::default();
+    assert!(fstatfs(fd as usize, &mut written) == 0);
+    assert!(written.f_type == root.f_type);
+    assert!(written.f_bfree < root.f_bfree);
+    close(fd as usize);
+    unlink(path);
+
+    let mut st = StatFs::default();
+    assert!(statfs("/proc/meminfo\0", &mut st) == 0);
+    assert!(st.f_type == PROC_SUPER_MAGIC);
+    assert!(statfs("/tmp\0", &mut st) == 0);
+    assert!(st.f_type == TMPFS_MAGIC);
+    // tmpfs is sized by the memory, so that df shows it
+    assert!(st.f_bsize == 4096 && st.f_blocks > 0 && st.f_bfree <= st.f_blocks);
+    assert!(statfs("/no/such/file\0", &mut st) == -ENOENT);
+
+    let mut fds = [0i32; 2];
+    assert!(pipe(&mut fds) == 0);
+    assert!(fstatfs(fds[0] as usize, &mut st) == 0);
+    assert!(st.f_type == PIPEFS_MAGIC);
+    close(fds[0] as usize);
+    close(fds[1] as usize);
+
+    println!("statfs test pass.");
+    0
+}
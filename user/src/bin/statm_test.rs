@@ -0,0 +1,147 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String, vec::Vec};
+
+use user_lib::*;
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 64;
+/// Pages the test itself may fault in between two reads, e.g. of its heap.
+const SLACK: usize = 8;
+
+fn read_file(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 256];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+/// Fields of `/proc/[pid]/statm`: size, resident, shared, text, lib, data, dt.
+fn statm(path: &str) -> [usize; 7] {
+    let content = read_file(path);
+    let mut fields = [0; 7];
+    for (field, value) in fields.iter_mut().zip(content.split_whitespace()) {
+        *field = value.parse().unwrap();
+    }
+    fields
+}
+
+/// Value in kB of `name` in `/proc/[pid]/status`.
+fn status(path: &str, name: &str) -> usize {
+    let content = read_file(path);
+    let line = content
+        .lines()
+        .find(|line| line.starts_with(name) && line[name.len()..].starts_with(':'))
+        .unwrap();
+    line.split_whitespace().nth(1).unwrap().parse().unwrap()
+}
+
+fn vmstat(name: &str) -> usize {
+    let content = read_file("/proc/vmstat\0");
+    let line = content
+        .lines()
+        .find(|line| line.split_whitespace().next() == Some(name))
+        .unwrap();
+    line.split_whitespace().nth(1).unwrap().parse().unwrap()
+}
+
+fn assert_grown(before: usize, after: usize, pages: usize) {
+    println!("[statm] {before} -> {after}, expected {pages} more");
+    assert!(after >= before + pages && after <= before + pages + SLACK);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin statm test");
+    // warm up the paths used to read the files
+    statm("/proc/self/statm\0");
+    status("/proc/self/status\0", "VmRSS");
+    vmstat("pgfault");
+
+    let before = statm("/proc/self/statm\0");
+    let anon_before = status("/proc/self/status\0", "RssAnon");
+    let faults_before = vmstat("pgfault");
+    let addr = mmap(
+        core::ptr::null(),
+        PAGES * PAGE_SIZE,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS,
+        usize::MAX,
+        0,
+    );
+    assert!(addr > 0);
+    let addr = addr as *mut u8;
+
+    // the area counts in the size at once, but is resident only when touched
+    let mapped = statm("/proc/self/statm\0");
+    assert!(mapped[0] == before[0] + PAGES);
+    assert_grown(before[1], mapped[1], 0);
+    for i in 0..PAGES {
+        unsafe { addr.add(i * PAGE_SIZE).write_volatile(i as u8) };
+    }
+    let touched = statm("/proc/self/statm\0");
+    assert_grown(before[1], touched[1], PAGES);
+    assert_grown(
+        anon_before,
+        status("/proc/self/status\0", "RssAnon"),
+        PAGES * PAGE_SIZE / 1024,
+    );
+    assert!(vmstat("pgfault") >= faults_before + PAGES);
+    let rss = status("/proc/self/status\0", "VmRSS");
+    assert!(rss >= touched[1] * PAGE_SIZE / 1024);
+
+    // a child counts the pages it shares with its parent
+    let mut fds = [0i32; 2];
+    assert!(pipe2(&mut fds, OpenFlags::empty()) == 0);
+    let pid = fork();
+    if pid == 0 {
+        close(fds[1] as usize);
+        let child = statm("/proc/self/statm\0");
+        let mut buf = [0u8; 1];
+        read(fds[0] as usize, &mut buf);
+        exit(if child[1] >= PAGES { 0 } else { 1 });
+    }
+    close(fds[0] as usize);
+    let child = statm(&format!("/proc/{pid}/statm\0"));
+    println!("[statm] child resident {}", child[1]);
+    assert!(child[1] >= PAGES);
+    assert!(write(fds[1] as usize, b"x") == 1);
+    close(fds[1] as usize);
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    assert!(exit_code == 0);
+
+    // writing a shared page again gives the parent its own copy, which
+    // changes nothing in the count
+    let copied = statm("/proc/self/statm\0");
+    unsafe { addr.write_volatile(0xff) };
+    assert_grown(copied[1], statm("/proc/self/statm\0")[1], 0);
+
+    let mapped_before = vmstat("nr_mapped");
+    assert!(munmap(addr, PAGES * PAGE_SIZE) == 0);
+    let unmapped = statm("/proc/self/statm\0");
+    assert!(unmapped[0] == touched[0] - PAGES);
+    assert!(unmapped[1] + PAGES <= touched[1] + SLACK);
+    assert!(vmstat("nr_mapped") + PAGES <= mapped_before + SLACK);
+    println!("statm test passed");
+    0
+}
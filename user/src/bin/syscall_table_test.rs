@@ -0,0 +1,162 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EBADF: isize = 9;
+const ENOENT: isize = 2;
+const ENOSYS: isize = 38;
+
+const SYSCALL_OPEN: usize = 1024;
+const SYSCALL_LINK: usize = 1025;
+const SYSCALL_UNLINK: usize = 1026;
+const SYSCALL_MKDIR: usize = 1030;
+const SYSCALL_RMDIR: usize = 1031;
+const SYSCALL_ACCESS: usize = 1033;
+const SYSCALL_RENAME: usize = 1034;
+const SYSCALL_READLINK: usize = 1035;
+const SYSCALL_SYMLINK: usize = 1036;
+const SYSCALL_PIPE: usize = 1040;
+const SYSCALL_DUP2: usize = 1041;
+const SYSCALL_CREAT: usize = 1064;
+const SYSCALL_POLL: usize = 1068;
+const SYSCALL_FORK: usize = 1079;
+
+const CLOSE_RANGE_CLOEXEC: u32 = 1 << 2;
+
+/// Numbers of Linux syscalls the kernel knows but does not implement, which
+/// must fail rather than pretend to succeed.
+const UNIMPLEMENTED: [usize; 9] = [
+    0,   // io_setup
+    18,  // lookup_dcookie
+    42,  // nfsservctl
+    104, // kexec_load
+    280, // bpf
+    291, // statx
+    425, // io_uring_setup
+    435, // clone3
+    444, // landlock_create_ruleset
+];
+
+/// Numbers no syscall has.
+const UNKNOWN: [usize; 4] = [295, 423, 463, 5000];
+
+fn call(id: usize, args: &[usize]) -> isize {
+    let mut all = [0; 6];
+    all[..args.len()].copy_from_slice(args);
+    raw_syscall(id, all)
+}
+
+fn path(s: &str) -> usize {
+    s.as_ptr() as usize
+}
+
+fn test_enosys() {
+    for id in UNIMPLEMENTED.iter().chain(UNKNOWN.iter()) {
+        let ret = call(*id, &[]);
+        if ret != -ENOSYS {
+            println!("[syscall_table] syscall {id} returned {ret}");
+        }
+        assert!(ret == -ENOSYS);
+    }
+}
+
+fn test_legacy_paths() {
+    let dir = "/tmp/legacy_dir\0";
+    assert!(call(SYSCALL_MKDIR, &[path(dir), 0o755]) == 0);
+    let file = "/tmp/legacy_dir/file\0";
+    let fd = call(SYSCALL_CREAT, &[path(file), 0o644]);
+    assert!(fd >= 0);
+    assert!(write(fd as usize, b"legacy") == 6);
+    close(fd as usize);
+    assert!(call(SYSCALL_ACCESS, &[path(file), 4]) == 0);
+
+    let fd = call(
+        SYSCALL_OPEN,
+        &[path(file), OpenFlags::O_RDONLY.bits() as usize, 0],
+    );
+    assert!(fd >= 0);
+    let mut buf = [0u8; 16];
+    assert!(read(fd as usize, &mut buf) == 6);
+    assert!(&buf[..6] == b"legacy");
+    close(fd as usize);
+
+    let hard = "/tmp/legacy_dir/hard\0";
+    assert!(call(SYSCALL_LINK, &[path(file), path(hard)]) == 0);
+    let moved = "/tmp/legacy_dir/moved\0";
+    assert!(call(SYSCALL_RENAME, &[path(hard), path(moved)]) == 0);
+    assert!(call(SYSCALL_ACCESS, &[path(hard), 0]) == -ENOENT);
+    let sym = "/tmp/legacy_dir/sym\0";
+    assert!(call(SYSCALL_SYMLINK, &[path("file\0"), path(sym)]) == 0);
+    let len = call(
+        SYSCALL_READLINK,
+        &[path(sym), buf.as_mut_ptr() as usize, buf.len()],
+    );
+    assert!(len == 4 && &buf[..4] == b"file");
+
+    for name in [file, moved, sym] {
+        assert!(call(SYSCALL_UNLINK, &[path(name)]) == 0);
+    }
+    assert!(call(SYSCALL_RMDIR, &[path(dir)]) == 0);
+    assert!(call(SYSCALL_ACCESS, &[path(dir), 0]) == -ENOENT);
+}
+
+fn test_legacy_fds() {
+    let mut fds = [0i32; 2];
+    assert!(call(SYSCALL_PIPE, &[fds.as_mut_ptr() as usize]) == 0);
+    let (rfd, wfd) = (fds[0] as usize, fds[1] as usize);
+
+    // nothing to read yet
+    let mut poll_fd = PollFd::new(rfd, POLLIN);
+    let poll_fd_ptr = &mut poll_fd as *mut PollFd as usize;
+    assert!(call(SYSCALL_POLL, &[poll_fd_ptr, 1, 0]) == 0);
+    assert!(write(wfd, b"x") == 1);
+    assert!(call(SYSCALL_POLL, &[poll_fd_ptr, 1, usize::MAX]) == 1);
+    assert!(poll_fd.revents & POLLIN != 0);
+
+    assert!(call(SYSCALL_DUP2, &[rfd, rfd]) == rfd as isize);
+    assert!(call(SYSCALL_DUP2, &[rfd, 40]) == 40);
+    let mut buf = [0u8; 1];
+    assert!(read(40, &mut buf) == 1 && buf[0] == b'x');
+    assert!(call(SYSCALL_DUP2, &[60, 61]) == -EBADF);
+    close(40);
+    close(rfd);
+    close(wfd);
+
+    let pid = call(SYSCALL_FORK, &[]);
+    if pid == 0 {
+        exit(7);
+    }
+    assert!(pid > 0);
+    let mut status: i32 = 0;
+    assert!(waitpid(pid as usize, &mut status) == pid);
+    assert!((status >> 8) & 0xff == 7);
+}
+
+fn test_close_range() {
+    for fd in 50..55 {
+        assert!(dup3(0, fd, OpenFlags::empty()) == fd as isize);
+    }
+    assert!(close_range(50, 52, CLOSE_RANGE_CLOEXEC) == 0);
+    assert!(fcntl(50, F_GETFD, 0) == FD_CLOEXEC as isize);
+    assert!(fcntl(53, F_GETFD, 0) == 0);
+    assert!(close_range(51, usize::MAX, 0) == 0);
+    for fd in 51..55 {
+        assert!(close(fd) == -EBADF);
+    }
+    assert!(close(50) == 0);
+    assert!(close_range(2, 1, 0) < 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin syscall table test");
+    test_enosys();
+    test_legacy_paths();
+    test_legacy_fds();
+    test_close_range();
+    println!("syscall table test pass.");
+    0
+}
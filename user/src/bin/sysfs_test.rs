@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::{format, string::String, vec::Vec};
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+
+fn read_all(path: &str) -> String {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut content = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    String::from_utf8(content).unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin sysfs test");
+    // every hart is in the affinity of init's descendants by default
+    let mut online = 0;
+    assert!(sched_getaffinity(0, &mut online) == core::mem::size_of::<usize>() as isize);
+    let nharts = online.count_ones() as usize;
+
+    let expected = if nharts == 1 {
+        String::from("0\n")
+    } else {
+        format!("0-{}\n", nharts - 1)
+    };
+    for name in ["online", "possible", "present"] {
+        let list = read_all(&format!("/sys/devices/system/cpu/{name}\0"));
+        println!("cpu {name}: {}", list.trim_end());
+        assert!(list == expected);
+    }
+
+    for hart in 0..nharts {
+        let dir = format!("/sys/devices/system/cpu/cpu{hart}");
+        assert!(read_all(&format!("{dir}/online\0")) == "1\n");
+        assert!(read_all(&format!("{dir}/topology/core_id\0")) == format!("{hart}\n"));
+        assert!(read_all(&format!("{dir}/topology/physical_package_id\0")) == "0\n");
+    }
+    let beyond = format!("/sys/devices/system/cpu/cpu{nharts}/online\0");
+    assert!(openat(&beyond, OpenFlags::O_RDONLY) == -ENOENT);
+    println!("sysfs test pass.");
+    0
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin sysinfo test");
+    let mut info = Sysinfo::default();
+    assert!(sysinfo(&mut info) == 0);
+    assert!(info.mem_unit == 1);
+    assert!(info.totalram > 0 && info.freeram <= info.totalram);
+    assert!(info.totalswap == 0 && info.freeswap == 0);
+    assert!(info.procs >= 1);
+    let uptime = info.uptime;
+
+    sleep(1100);
+    let mut later = Sysinfo::default();
+    assert!(sysinfo(&mut later) == 0);
+    assert!(later.uptime > uptime);
+
+    // a sleeping child is one more task
+    let pid = fork();
+    if pid == 0 {
+        sleep(500);
+        exit(0);
+    }
+    sleep(50);
+    assert!(sysinfo(&mut later) == 0);
+    assert!(later.procs > info.procs);
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    println!("sysinfo test pass.");
+    0
+}
@@ -0,0 +1,118 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const EINVAL: isize = 22;
+
+const TFD_NONBLOCK: i32 = 0o4000;
+const TFD_TIMER_ABSTIME: i32 = 1;
+
+fn now(clockid: usize) -> Duration {
+    let mut tp = TimeSpec::default();
+    assert!(clock_gettime(clockid, &mut tp) == 0);
+    tp.into()
+}
+
+fn itimerspec(value_ms: usize, interval_ms: usize) -> ITimerSpec {
+    ITimerSpec {
+        it_interval: TimeSpec::from_ms(interval_ms),
+        it_value: TimeSpec::from_ms(value_ms),
+    }
+}
+
+/// Read the count of expirations of `fd`, returning it or the error.
+fn read_count(fd: usize) -> isize {
+    let mut buf = [0u8; 8];
+    let ret = read(fd, &mut buf);
+    if ret < 0 {
+        return ret;
+    }
+    assert!(ret == 8);
+    u64::from_ne_bytes(buf) as isize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin timerfd test");
+
+    // a read of a one-shot timer waits for it to fire
+    let fd = timerfd_create(CLOCK_MONOTONIC, 0);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let start = now(CLOCK_MONOTONIC);
+    assert!(timerfd_settime(fd, 0, &itimerspec(100, 0), None) == 0);
+    assert!(read_count(fd) == 1);
+    let elapsed = now(CLOCK_MONOTONIC) - start;
+    println!("[timerfd] one-shot fired after {elapsed:?}");
+    assert!(elapsed >= Duration::from_millis(100));
+    let mut curr = ITimerSpec::default();
+    assert!(timerfd_gettime(fd, &mut curr) == 0);
+    assert!(Duration::from(curr.it_value).is_zero());
+
+    // a periodic timer counts the expirations between reads
+    let nonblock = timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK) as usize;
+    assert!(read_count(nonblock) == -EAGAIN);
+    assert!(timerfd_settime(nonblock, 0, &itimerspec(50, 50), None) == 0);
+    let mut fds = [PollFd::new(nonblock, POLLIN)];
+    assert!(ppoll(&mut fds, Some(&TimeSpec::from_ms(0))) == 0);
+    sleep(275);
+    assert!(ppoll(&mut fds, Some(&TimeSpec::from_ms(0))) == 1);
+    assert!(fds[0].revents == POLLIN);
+    let count = read_count(nonblock);
+    println!("[timerfd] periodic count {count}");
+    assert!(count >= 4 && count <= 6);
+    assert!(read_count(nonblock) == -EAGAIN);
+    assert!(ppoll(&mut fds, Some(&TimeSpec::from_ms(500))) == 1);
+    assert!(read_count(nonblock) >= 1);
+
+    // the setting shows the time left, and a zero value disarms the timer
+    assert!(timerfd_gettime(nonblock, &mut curr) == 0);
+    let left: Duration = curr.it_value.into();
+    assert!(!left.is_zero() && left <= Duration::from_millis(50));
+    assert!(Duration::from(curr.it_interval) == Duration::from_millis(50));
+    let mut old = ITimerSpec::default();
+    assert!(timerfd_settime(nonblock, 0, &ITimerSpec::default(), Some(&mut old)) == 0);
+    assert!(Duration::from(old.it_interval) == Duration::from_millis(50));
+    sleep(100);
+    assert!(read_count(nonblock) == -EAGAIN);
+    assert!(timerfd_gettime(nonblock, &mut curr) == 0);
+    assert!(Duration::from(curr.it_value).is_zero());
+    close(nonblock);
+
+    // an absolute expiration on the realtime clock
+    let realtime = timerfd_create(CLOCK_REALTIME, 0) as usize;
+    let expire = now(CLOCK_REALTIME) + Duration::from_millis(100);
+    let abs = ITimerSpec {
+        it_interval: TimeSpec::default(),
+        it_value: expire.into(),
+    };
+    assert!(timerfd_settime(realtime, TFD_TIMER_ABSTIME, &abs, None) == 0);
+    assert!(read_count(realtime) == 1);
+    assert!(now(CLOCK_REALTIME) >= expire);
+    // one already past fires at once
+    assert!(timerfd_settime(realtime, TFD_TIMER_ABSTIME, &abs, None) == 0);
+    assert!(read_count(realtime) == 1);
+    close(realtime);
+
+    // errors
+    assert!(timerfd_create(CLOCK_PROCESS_CPUTIME_ID, 0) == -EINVAL);
+    assert!(timerfd_create(CLOCK_MONOTONIC, 1) == -EINVAL);
+    let mut bad = itimerspec(100, 0);
+    bad.it_value.tv_nsec = 1_000_000_000;
+    assert!(timerfd_settime(fd, 0, &bad, None) == -EINVAL);
+    let mut short = [0u8; 4];
+    assert!(read(fd, &mut short) == -EINVAL);
+    let efd = eventfd(0, 0) as usize;
+    assert!(timerfd_gettime(efd, &mut curr) == -EINVAL);
+    close(efd);
+    close(fd);
+
+    println!("timerfd test passed");
+    0
+}
@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::*;
+
+/// `sysconf(_SC_CLK_TCK)`
+const CLK_TCK: usize = 100;
+
+const RUSAGE_CHILDREN: i32 = -1;
+
+fn now() -> Duration {
+    let mut tp = TimeSpec::default();
+    assert!(clock_gettime(CLOCK_MONOTONIC, &mut tp) == 0);
+    tp.into()
+}
+
+/// Spin in user mode for `ms` milliseconds of wall time.
+fn burn(ms: u64) {
+    let end = now() + Duration::from_millis(ms);
+    let mut x = 0usize;
+    while now() < end {
+        for i in 0..10000 {
+            x = x.wrapping_mul(31).wrapping_add(i);
+        }
+        core::hint::black_box(x);
+    }
+}
+
+fn ticks(time: TimeVal) -> usize {
+    let time: Duration = time.into();
+    (time.as_micros() * CLK_TCK as u128 / 1_000_000) as usize
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    println!("begin times test");
+    let mut before = Tms::default();
+    let start = times(&mut before);
+    assert!(start > 0);
+    burn(300);
+    let mut after = Tms::default();
+    let end = times(&mut after);
+    let elapsed = (end - start) as usize;
+    let used = after.tms_utime + after.tms_stime - before.tms_utime - before.tms_stime;
+    println!(
+        "[times] elapsed {elapsed}, utime {} -> {}, stime {} -> {}",
+        before.tms_utime, after.tms_utime, before.tms_stime, after.tms_stime
+    );
+    // the return value and the times are both in ticks of 1/CLK_TCK seconds
+    assert!(elapsed >= 30 * CLK_TCK / 100 && elapsed <= 60 * CLK_TCK / 100);
+    assert!(after.tms_utime > before.tms_utime);
+    assert!(used >= 10 * CLK_TCK / 100 && used <= elapsed + 2);
+    assert!(after.tms_cutime == 0 && after.tms_cstime == 0);
+
+    // the times of a child count once it is waited for
+    let pid = fork();
+    if pid == 0 {
+        burn(200);
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid);
+    let mut reaped = Tms::default();
+    times(&mut reaped);
+    let children = reaped.tms_cutime + reaped.tms_cstime;
+    println!(
+        "[times] children {} + {}",
+        reaped.tms_cutime, reaped.tms_cstime
+    );
+    assert!(children >= 5 * CLK_TCK / 100 && children <= 40 * CLK_TCK / 100);
+
+    // and they are the same as the usage of the children
+    let mut usage = Rusage::default();
+    assert!(getrusage(RUSAGE_CHILDREN, &mut usage) == 0);
+    let usage_ticks = ticks(usage.utime) + ticks(usage.stime);
+    assert!(usage_ticks + 2 >= children && usage_ticks <= children + 2);
+    println!("times test passed");
+    0
+}
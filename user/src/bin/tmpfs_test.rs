@@ -0,0 +1,153 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::vec;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const ENOTEMPTY: isize = 39;
+const SEEK_SET: usize = 0;
+
+const TMPFS_MAGIC: i64 = 0x01021994;
+
+fn size_of(path: &str) -> i64 {
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    st.st_size
+}
+
+fn free_blocks() -> u64 {
+    let mut buf = StatFs::default();
+    assert!(statfs("/tmp\0", &mut buf) == 0);
+    buf.f_bfree
+}
+
+fn files() {
+    let fd = openat(
+        "/tmp/tmpfs_test/a\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(write(fd, b"hello") == 5);
+    let mut st = Kstat::default();
+    assert!(stat("/tmp/tmpfs_test/a\0", &mut st) == 0);
+    assert!(st.st_size == 5 && st.st_mtime_sec + st.st_mtime_nsec > 0);
+
+    // a write far beyond the end leaves a hole reading as zero
+    assert!(lseek(fd, 10000, SEEK_SET) == 10000);
+    assert!(write(fd, b"x") == 1);
+    assert!(size_of("/tmp/tmpfs_test/a\0") == 10001);
+    let mut buf = vec![0xffu8; 10001];
+    assert!(lseek(fd, 0, SEEK_SET) == 0);
+    assert!(read(fd, &mut buf) == 10001);
+    assert!(&buf[..5] == b"hello" && buf[5..10000].iter().all(|&b| b == 0));
+    assert!(buf[10000] == b'x');
+
+    // shrink, then grow again with zeros
+    assert!(ftruncate(fd, 3) == 0);
+    assert!(ftruncate(fd, 8192) == 0);
+    assert!(size_of("/tmp/tmpfs_test/a\0") == 8192);
+    let mut buf = vec![0xffu8; 8192];
+    assert!(lseek(fd, 0, SEEK_SET) == 0);
+    assert!(read(fd, &mut buf) == 8192);
+    assert!(&buf[..3] == b"hel" && buf[3..].iter().all(|&b| b == 0));
+    close(fd);
+
+    // rename, then link and unlink one of the links
+    assert!(rename("/tmp/tmpfs_test/a\0", "/tmp/tmpfs_test/b\0") == 0);
+    let mut st = Kstat::default();
+    assert!(stat("/tmp/tmpfs_test/a\0", &mut st) == -ENOENT);
+    assert!(link("/tmp/tmpfs_test/b\0", "/tmp/tmpfs_test/c\0") == 0);
+    assert!(stat("/tmp/tmpfs_test/c\0", &mut st) == 0 && st.st_nlink == 2);
+    assert!(unlink("/tmp/tmpfs_test/b\0") == 0);
+    assert!(stat("/tmp/tmpfs_test/c\0", &mut st) == 0 && st.st_nlink == 1);
+    assert!(st.st_size == 8192);
+    assert!(unlink("/tmp/tmpfs_test/c\0") == 0);
+}
+
+fn dirs() {
+    assert!(mkdir("/tmp/tmpfs_test/sub\0") == 0);
+    let fd = openat(
+        "/tmp/tmpfs_test/sub/f\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    assert!(write(fd as usize, b"in sub") == 6);
+    close(fd as usize);
+    assert!(rmdir("/tmp/tmpfs_test/sub\0") == -ENOTEMPTY);
+
+    // the files go along with a renamed directory
+    assert!(rename("/tmp/tmpfs_test/sub\0", "/tmp/tmpfs_test/sub2\0") == 0);
+    let mut st = Kstat::default();
+    assert!(stat("/tmp/tmpfs_test/sub/f\0", &mut st) == -ENOENT);
+    let fd = openat("/tmp/tmpfs_test/sub2/f\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; 16];
+    assert!(read(fd as usize, &mut buf) == 6 && &buf[..6] == b"in sub");
+    close(fd as usize);
+    assert!(unlink("/tmp/tmpfs_test/sub2/f\0") == 0);
+    assert!(rmdir("/tmp/tmpfs_test/sub2\0") == 0);
+}
+
+/// The memory of an unlinked file is freed once it is closed.
+fn freed_on_close() {
+    const PAGES: usize = 256;
+    let before = free_blocks();
+    let fd = openat(
+        "/tmp/tmpfs_test/big\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let page = [0x5au8; 4096];
+    for _ in 0..PAGES {
+        assert!(write(fd, &page) == 4096);
+    }
+    assert!(unlink("/tmp/tmpfs_test/big\0") == 0);
+    let open = free_blocks();
+    println!("free blocks: {before} before, {open} when unlinked but open");
+    assert!(open + PAGES as u64 / 2 <= before);
+    // still readable while open
+    assert!(lseek(fd, 0, SEEK_SET) == 0);
+    let mut buf = [0u8; 4096];
+    assert!(read(fd, &mut buf) == 4096 && buf == page);
+    close(fd);
+    let closed = free_blocks();
+    println!("free blocks: {closed} once closed");
+    assert!(closed >= open + PAGES as u64 / 2);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin tmpfs test");
+    let mut buf = StatFs::default();
+    for path in ["/tmp\0", "/dev/shm\0"] {
+        assert!(statfs(path, &mut buf) == 0 && buf.f_type == TMPFS_MAGIC);
+    }
+    assert!(mkdir("/tmp/tmpfs_test\0") == 0);
+    files();
+    dirs();
+    freed_on_close();
+    assert!(rmdir("/tmp/tmpfs_test\0") == 0);
+
+    // a tmpfs mounted by hand has no backing device
+    assert!(mkdir("/tmp/tmpfs_test_mnt\0") == 0);
+    assert!(mount("none\0", "/tmp/tmpfs_test_mnt\0", "tmpfs\0", 0) == 0);
+    assert!(statfs("/tmp/tmpfs_test_mnt\0", &mut buf) == 0 && buf.f_type == TMPFS_MAGIC);
+    let fd = openat(
+        "/tmp/tmpfs_test_mnt/f\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    close(fd as usize);
+    assert!(unlink("/tmp/tmpfs_test_mnt/f\0") == 0);
+    assert!(umount2("/tmp/tmpfs_test_mnt\0", 0) == 0);
+    assert!(rmdir("/tmp/tmpfs_test_mnt\0") == 0);
+    println!("tmpfs test pass.");
+    0
+}
@@ -0,0 +1,86 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate user_lib;
+
+use alloc::format;
+
+use user_lib::*;
+
+const ENOENT: isize = 2;
+const EEXIST: isize = 17;
+const ENOTDIR: isize = 20;
+const EISDIR: isize = 21;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+
+/// Stat `path`, returning whether it is a directory or the error.
+fn is_dir(path: &str) -> Result<bool, isize> {
+    let mut st = Kstat::default();
+    match stat(path, &mut st) {
+        0 => Ok(st.st_mode & S_IFMT == S_IFDIR),
+        ret => Err(ret),
+    }
+}
+
+fn create(path: &str) {
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    close(fd as usize);
+}
+
+fn test_in(dir: &str) {
+    let path = |name: &str| format!("{dir}/{name}\0");
+    assert!(mkdir(&format!("{dir}\0")) == 0);
+
+    // a directory may be created and named with trailing slashes
+    assert!(mkdir(&path("d/")) == 0);
+    assert!(is_dir(&path("d")) == Ok(true));
+    assert!(is_dir(&path("d//")) == Ok(true));
+    assert!(mkdir(&path("d/")) == -EEXIST);
+    let fd = openat(&path("d/"), OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+
+    // but anything else found with a trailing slash is no directory
+    create(&path("f"));
+    assert!(is_dir(&path("f")) == Ok(false));
+    assert!(is_dir(&path("f/")) == Err(-ENOTDIR));
+    assert!(openat(&path("f/"), OpenFlags::O_RDONLY) == -ENOTDIR);
+    assert!(unlink(&path("f/")) == -ENOTDIR);
+    assert!(mkdir(&path("f/")) == -EEXIST);
+
+    // a regular file can not be created with one
+    assert!(openat(&path("n/"), OpenFlags::O_CREATE | OpenFlags::O_RDWR) == -EISDIR);
+    assert!(is_dir(&path("n")) == Err(-ENOENT));
+
+    // the trailing symlink is followed to check it
+    assert!(symlink("d", &path("ld")) == 0);
+    assert!(symlink("f", &path("lf")) == 0);
+    assert!(is_dir(&path("ld/")) == Ok(true));
+    assert!(is_dir(&path("lf/")) == Err(-ENOTDIR));
+
+    // only a directory may be renamed to a name with a trailing slash
+    assert!(rename(&path("f"), &path("g/")) == -ENOTDIR);
+    assert!(is_dir(&path("f")) == Ok(false));
+    assert!(rename(&path("d/"), &path("e/")) == 0);
+    assert!(is_dir(&path("e")) == Ok(true));
+    assert!(is_dir(&path("d")) == Err(-ENOENT));
+
+    assert!(unlink(&path("ld")) == 0);
+    assert!(unlink(&path("lf")) == 0);
+    assert!(unlink(&path("f")) == 0);
+    assert!(rmdir(&path("e/")) == 0);
+    assert!(rmdir(&format!("{dir}/\0")) == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin trailing slash test");
+    test_in("/tmp/trailing_slash");
+    test_in("/trailing_slash");
+    println!("trailing slash test passed");
+    0
+}
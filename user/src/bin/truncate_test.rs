@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const SEEK_SET: usize = 0;
+
+fn size_of(path: &str) -> usize {
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    st.st_size as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin truncate test");
+    let path = "/tmp/truncate_test\0";
+    let fd = openat(
+        path,
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR | OpenFlags::O_TRUNC,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert!(write(fd, b"0123456789") == 10);
+
+    // shrink
+    assert!(ftruncate(fd, 4) == 0);
+    assert!(size_of(path) == 4);
+
+    // grow, the gap reads as zero
+    assert!(truncate(path, 8192) == 0);
+    assert!(size_of(path) == 8192);
+    let mut buf = [0xffu8; 8192];
+    assert!(lseek(fd, 0, SEEK_SET) == 0);
+    assert!(read(fd, &mut buf) == 8192);
+    assert!(&buf[..4] == b"0123");
+    assert!(buf[4..].iter().all(|&b| b == 0));
+
+    assert!(ftruncate(fd, -1) < 0);
+    assert!(truncate("/tmp\0", 0) < 0);
+
+    // O_TRUNC empties the file
+    let fd2 = openat(path, OpenFlags::O_WRONLY | OpenFlags::O_TRUNC);
+    assert!(fd2 >= 0);
+    assert!(size_of(path) == 0);
+    println!("truncate test pass.");
+    0
+}
@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EBUSY: isize = 16;
+const EINVAL: isize = 22;
+
+const MNT_FORCE: usize = 1;
+const MNT_DETACH: usize = 1 << 1;
+const MNT_EXPIRE: usize = 1 << 2;
+
+fn create(path: &str, data: &[u8]) {
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    assert!(write(fd as usize, data) == data.len() as isize);
+    close(fd as usize);
+}
+
+fn exists(path: &str) -> bool {
+    let mut st = Kstat::default();
+    stat(path, &mut st) == 0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin umount test");
+    let mut buf = [0u8; 16];
+    assert!(mkdir("/tmp/umount_test\0") == 0);
+
+    // an open file keeps the file system busy
+    assert!(mount("none\0", "/tmp/umount_test\0", "tmpfs\0", 0) == 0);
+    create("/tmp/umount_test/file\0", b"data");
+    let fd = openat("/tmp/umount_test/file\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    assert!(umount2("/tmp/umount_test\0", 0) == -EBUSY);
+    // forcing makes no difference for a local file system
+    assert!(umount2("/tmp/umount_test\0", MNT_FORCE) == -EBUSY);
+    assert!(exists("/tmp/umount_test/file\0"));
+    close(fd as usize);
+
+    // so does a mount below it
+    assert!(mkdir("/tmp/umount_test/sub\0") == 0);
+    assert!(mount("none\0", "/tmp/umount_test/sub\0", "tmpfs\0", 0) == 0);
+    assert!(umount2("/tmp/umount_test\0", 0) == -EBUSY);
+    assert!(umount2("/tmp/umount_test/sub\0", 0) == 0);
+    assert!(umount2("/tmp/umount_test\0", 0) == 0);
+    assert!(!exists("/tmp/umount_test/file\0"));
+
+    // a lazy unmount detaches at once, and open files stay usable
+    assert!(mount("none\0", "/tmp/umount_test\0", "tmpfs\0", 0) == 0);
+    create("/tmp/umount_test/file\0", b"lazy");
+    assert!(mkdir("/tmp/umount_test/sub\0") == 0);
+    assert!(mount("none\0", "/tmp/umount_test/sub\0", "tmpfs\0", 0) == 0);
+    let fd = openat("/tmp/umount_test/file\0", OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    assert!(umount2("/tmp/umount_test\0", MNT_DETACH) == 0);
+    assert!(!exists("/tmp/umount_test/file\0"));
+    assert!(!exists("/tmp/umount_test/sub\0"));
+    assert!(read(fd as usize, &mut buf) == 4 && &buf[..4] == b"lazy");
+    close(fd as usize);
+
+    // the mount point is free again
+    assert!(mount("none\0", "/tmp/umount_test\0", "tmpfs\0", 0) == 0);
+    assert!(!exists("/tmp/umount_test/file\0"));
+    assert!(umount2("/tmp/umount_test\0", MNT_FORCE) == 0);
+
+    assert!(umount2("/tmp/umount_test\0", MNT_EXPIRE) == -EINVAL);
+    assert!(umount2("/tmp/umount_test\0", 1 << 8) == -EINVAL);
+    assert!(umount2("/tmp/umount_test\0", 0) == -EINVAL);
+    assert!(rmdir("/tmp/umount_test\0") == 0);
+    println!("umount test pass.");
+    0
+}
@@ -0,0 +1,125 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EAGAIN: isize = 11;
+const ENOTCONN: isize = 107;
+const EDESTADDRREQ: isize = 89;
+const ECONNREFUSED: isize = 111;
+
+const SUN_LEN: u32 = core::mem::size_of::<SockAddrUn>() as u32;
+
+fn recv_one(sockfd: usize, buf: &mut [u8]) -> (isize, SockAddrUn, u32) {
+    let mut src = SockAddrUn::new("");
+    let mut addrlen = SUN_LEN;
+    let len = recvfrom(sockfd, buf, &mut src, &mut addrlen);
+    (len, src, addrlen)
+}
+
+/// Three datagrams sent to a path are received as three messages, each with
+/// the address of its sender.
+fn test_boundaries() {
+    let path = "/tmp/unix_dgram_test";
+    let addr = SockAddrUn::new(path);
+    let server = socket(AF_UNIX, SOCK_DGRAM, 0);
+    assert!(server >= 0);
+    let server = server as usize;
+    assert!(bind(server, &addr) == 0);
+
+    let client_addr = SockAddrUn::new_abstract("unix_dgram_client");
+    let client = socket(AF_UNIX, SOCK_DGRAM, 0) as usize;
+    assert!(bind(client, &client_addr) == 0);
+    let messages: [&[u8]; 3] = [b"one", b"second", b"3"];
+    for msg in messages {
+        assert!(sendto(client, msg, Some(&addr)) == msg.len() as isize);
+    }
+
+    let mut buf = [0u8; 64];
+    for msg in messages {
+        let (len, src, addrlen) = recv_one(server, &mut buf);
+        assert!(len == msg.len() as isize);
+        assert!(&buf[..msg.len()] == msg);
+        assert!(addrlen as usize == client_addr.len() && src.path == client_addr.path);
+    }
+
+    // the bytes of a datagram which do not fit are discarded
+    assert!(sendto(client, b"truncated", Some(&addr)) == 9);
+    assert!(sendto(client, b"next", Some(&addr)) == 4);
+    assert!(recv(server, &mut buf[..5]) == 5 && &buf[..5] == b"trunc");
+    assert!(recv(server, &mut buf) == 4 && &buf[..4] == b"next");
+
+    close(client);
+    close(server);
+    unlink("/tmp/unix_dgram_test\0");
+}
+
+/// A connected socket sends to its peer without a destination, an unconnected
+/// one needs a destination.
+fn test_connect() {
+    let addr = SockAddrUn::new_abstract("unix_dgram_server");
+    let server = socket(AF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0) as usize;
+    assert!(bind(server, &addr) == 0);
+    let mut buf = [0u8; 16];
+    assert!(recv(server, &mut buf) == -EAGAIN);
+
+    let client = socket(AF_UNIX, SOCK_DGRAM, 0) as usize;
+    assert!(send(client, b"lost") == -EDESTADDRREQ);
+    let mut peer = SockAddrUn::new("");
+    let mut addrlen = SUN_LEN;
+    assert!(getpeername(client, &mut peer, &mut addrlen) == -ENOTCONN);
+    let nobody = SockAddrUn::new_abstract("unix_dgram_nobody");
+    assert!(sendto(client, b"lost", Some(&nobody)) == -ECONNREFUSED);
+    assert!(connect(client, &nobody) == -ECONNREFUSED);
+
+    assert!(connect(client, &addr) == 0);
+    assert!(getpeername(client, &mut peer, &mut addrlen) == 0);
+    assert!(peer.path == addr.path);
+    assert!(send(client, b"hi") == 2);
+    assert!(write(client, b"there") == 5);
+    // an unnamed sender has the address of the family alone
+    let (len, _, addrlen) = recv_one(server, &mut buf);
+    assert!(len == 2 && &buf[..2] == b"hi" && addrlen == 2);
+    assert!(read(server, &mut buf) == 5 && &buf[..5] == b"there");
+    assert!(listen(server, 4) < 0);
+
+    // the peer is gone once its socket is closed
+    close(server);
+    assert!(send(client, b"lost") == -ECONNREFUSED);
+    close(client);
+}
+
+/// A child sends to the parent, which blocks until the datagrams arrive.
+fn test_fork() {
+    let addr = SockAddrUn::new_abstract("unix_dgram_fork");
+    let server = socket(AF_UNIX, SOCK_DGRAM, 0) as usize;
+    assert!(bind(server, &addr) == 0);
+    let pid = fork();
+    if pid == 0 {
+        sleep(10);
+        let client = socket(AF_UNIX, SOCK_DGRAM, 0) as usize;
+        for msg in [b"ping", b"pong"] {
+            assert!(sendto(client, msg, Some(&addr)) == 4);
+        }
+        close(client);
+        exit(0);
+    }
+    let mut buf = [0u8; 16];
+    assert!(recv(server, &mut buf) == 4 && &buf[..4] == b"ping");
+    assert!(recv(server, &mut buf) == 4 && &buf[..4] == b"pong");
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    close(server);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin unix dgram test");
+    test_boundaries();
+    test_connect();
+    test_fork();
+    println!("unix dgram test pass.");
+    0
+}
@@ -0,0 +1,183 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const SIG_IGN: usize = 1;
+const EADDRINUSE: isize = 98;
+const ECONNREFUSED: isize = 111;
+const EPIPE: isize = 32;
+const ENOTCONN: isize = 107;
+
+const SUN_LEN: u32 = core::mem::size_of::<SockAddrUn>() as u32;
+/// Length of the address of an unnamed socket, the family alone.
+const UNNAMED_LEN: u32 = 2;
+
+fn sockname(sockfd: usize) -> (SockAddrUn, u32) {
+    let mut addr = SockAddrUn::new("");
+    let mut addrlen = SUN_LEN;
+    assert!(getsockname(sockfd, &mut addr, &mut addrlen) == 0);
+    (addr, addrlen)
+}
+
+fn peername(sockfd: usize) -> (SockAddrUn, u32) {
+    let mut addr = SockAddrUn::new("");
+    let mut addrlen = SUN_LEN;
+    assert!(getpeername(sockfd, &mut addr, &mut addrlen) == 0);
+    (addr, addrlen)
+}
+
+/// The names of both ends of a connection, and of unnamed sockets.
+fn test_names() {
+    let path = "/tmp/unix_name_test";
+    let addr = SockAddrUn::new(path);
+    let path_len = 2 + path.len() as u32 + 1;
+
+    let server = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    let (name, len) = sockname(server);
+    assert!(len == UNNAMED_LEN && name.len() == 2);
+    let mut peer = SockAddrUn::new("");
+    let mut addrlen = SUN_LEN;
+    assert!(getpeername(server, &mut peer, &mut addrlen) == -ENOTCONN);
+
+    assert!(bind(server, &addr) == 0);
+    assert!(listen(server, 4) == 0);
+    let (name, len) = sockname(server);
+    assert!(len == path_len && name.path == addr.path);
+    assert!(getpeername(server, &mut peer, &mut addrlen) == -ENOTCONN);
+
+    // truncated to the buffer, with the length of the whole address
+    let mut short = SockAddrUn::new("xxxxxxxxxxxx");
+    let mut addrlen = 10;
+    assert!(getsockname(server, &mut short, &mut addrlen) == 0);
+    assert!(addrlen == path_len);
+    assert!(&short.path[..8] == &path.as_bytes()[..8] && short.path[8] == b'x');
+
+    let client = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(connect(client, &addr) == 0);
+    let (name, len) = peername(client);
+    assert!(len == path_len && name.path == addr.path);
+    assert!(sockname(client).1 == UNNAMED_LEN);
+
+    let conn = accept(server, &mut peer);
+    assert!(conn >= 0);
+    let conn = conn as usize;
+    let (name, len) = sockname(conn);
+    assert!(len == path_len && name.path == addr.path);
+    assert!(peername(conn).1 == UNNAMED_LEN);
+
+    close(conn);
+    close(client);
+    close(server);
+    unlink("/tmp/unix_name_test\0");
+}
+
+/// An abstract name is bound without a file, and freed with the socket.
+fn test_abstract() {
+    let addr = SockAddrUn::new_abstract("unix_socket_test");
+    let server = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(bind(server, &addr) == 0);
+    let (name, len) = sockname(server);
+    assert!(len as usize == addr.len() && name.path == addr.path);
+    let other = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(bind(other, &addr) == -EADDRINUSE);
+    assert!(connect(other, &addr) == -ECONNREFUSED);
+    close(other);
+    // a prefix is another name
+    let client = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(connect(client, &SockAddrUn::new_abstract("unix_socket")) == -ECONNREFUSED);
+    close(client);
+
+    assert!(listen(server, 4) == 0);
+    let pid = fork();
+    if pid == 0 {
+        let client = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+        assert!(connect(client, &addr) == 0);
+        assert!(send(client, b"hello") == 5);
+        close(client);
+        exit(0);
+    }
+    let mut peer = SockAddrUn::new("");
+    let conn = accept(server, &mut peer);
+    assert!(conn >= 0);
+    let conn = conn as usize;
+    let mut buf = [0u8; 16];
+    assert!(read(conn, &mut buf) == 5);
+    assert!(&buf[..5] == b"hello");
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    close(conn);
+    close(server);
+
+    let server = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(bind(server, &addr) == 0);
+    close(server);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin unix socket test");
+    let path = "/tmp/unix_socket_test";
+    let addr = SockAddrUn::new(path);
+
+    let server = socket(AF_UNIX, SOCK_STREAM, 0);
+    assert!(server >= 0);
+    let server = server as usize;
+    // nobody is bound to the path yet
+    assert!(connect(server, &addr) < 0);
+    assert!(bind(server, &addr) == 0);
+    // the path is visible as a socket
+    let mut st = Kstat::default();
+    assert!(stat("/tmp/unix_socket_test\0", &mut st) == 0);
+    assert!(st.st_mode & 0o170000 == 0o140000);
+
+    let other = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+    assert!(bind(other, &addr) == -EADDRINUSE);
+    // bound but not listening
+    assert!(connect(other, &addr) == -ECONNREFUSED);
+    close(other);
+
+    assert!(listen(server, 4) == 0);
+    let pid = fork();
+    if pid == 0 {
+        // the parent blocks in accept until this connects
+        sleep(10);
+        let client = socket(AF_UNIX, SOCK_STREAM, 0) as usize;
+        assert!(connect(client, &addr) == 0);
+        assert!(send(client, b"ping") == 4);
+        let mut buf = [0u8; 16];
+        assert!(recv(client, &mut buf) == 4);
+        assert!(&buf[..4] == b"pong");
+        close(client);
+        exit(0);
+    }
+    let mut peer = SockAddrUn::new("");
+    let conn = accept(server, &mut peer);
+    assert!(conn >= 0);
+    let conn = conn as usize;
+    let mut buf = [0u8; 16];
+    assert!(read(conn, &mut buf) == 4);
+    assert!(&buf[..4] == b"ping");
+    assert!(write(conn, b"pong") == 4);
+
+    let mut exit_code: i32 = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+    // the client has closed its end
+    assert!(read(conn, &mut buf) == 0);
+    let mut new = SigAction::default();
+    let mut old = SigAction::default();
+    new.sa_handler = SIG_IGN;
+    assert!(sigaction(Sig::SIGPIPE, &new, &mut old) == 0);
+    assert!(write(conn, b"lost") == -EPIPE);
+
+    close(conn);
+    close(server);
+    unlink("/tmp/unix_socket_test\0");
+
+    test_abstract();
+    test_names();
+    println!("unix socket test pass.");
+    0
+}
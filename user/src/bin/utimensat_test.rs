@@ -0,0 +1,101 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::*;
+
+const EPERM: isize = 1;
+const EINVAL: isize = 22;
+
+const UTIME_NOW: usize = 0x3fffffff;
+const UTIME_OMIT: usize = 0x3ffffffe;
+const AT_SYMLINK_NOFOLLOW: i32 = 0x100;
+
+fn ts(tv_sec: usize, tv_nsec: usize) -> TimeSpec {
+    TimeSpec { tv_sec, tv_nsec }
+}
+
+fn times_of(path: &str) -> (isize, isize, isize, isize) {
+    let mut st = Kstat::default();
+    assert!(stat(path, &mut st) == 0);
+    (
+        st.st_atime_sec,
+        st.st_atime_nsec,
+        st.st_mtime_sec,
+        st.st_mtime_nsec,
+    )
+}
+
+fn utimes(path: &str) {
+    println!("utimensat on {path}");
+    let fd = openat(path, OpenFlags::O_CREATE | OpenFlags::O_RDWR);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+
+    // set both times
+    assert!(utimensat(path, Some(&[ts(1000, 123), ts(2000, 456)]), 0) == 0);
+    assert!(times_of(path) == (1000, 123, 2000, 456));
+
+    // leave the access time alone
+    assert!(utimensat(path, Some(&[ts(0, UTIME_OMIT), ts(3000, 0)]), 0) == 0);
+    assert!(times_of(path) == (1000, 123, 3000, 0));
+    assert!(futimens(fd, Some(&[ts(4000, 0), ts(0, UTIME_OMIT)])) == 0);
+    assert!(times_of(path) == (4000, 0, 3000, 0));
+
+    // set to now, the time since boot is far below the times above
+    assert!(utimensat(path, Some(&[ts(0, UTIME_NOW), ts(0, UTIME_OMIT)]), 0) == 0);
+    let (atime, _, mtime, _) = times_of(path);
+    assert!(atime < 4000 && mtime == 3000);
+    assert!(utimensat(path, None, 0) == 0);
+    let (atime, atime_nsec, mtime, mtime_nsec) = times_of(path);
+    assert!(mtime < 3000 && (atime, atime_nsec) == (mtime, mtime_nsec));
+
+    // nanoseconds out of range and unknown flags
+    assert!(utimensat(path, Some(&[ts(0, 1_000_000_000), ts(0, 0)]), 0) == -EINVAL);
+    assert!(utimensat(path, None, 0x1) == -EINVAL);
+
+    // only the owner may set other times than now
+    let pid = fork();
+    if pid == 0 {
+        assert!(setuid(1000) == 0);
+        assert!(utimensat(path, Some(&[ts(1, 0), ts(1, 0)]), 0) == -EPERM);
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert!(waitpid(pid as usize, &mut exit_code) == pid && exit_code == 0);
+
+    close(fd);
+    assert!(unlink(path) == 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin utimensat test");
+    // a file only in memory, and one written back to the disk
+    utimes("/tmp/utimensat_test\0");
+    utimes("/utimensat_test\0");
+
+    // the symlink itself is not followed with AT_SYMLINK_NOFOLLOW
+    let fd = openat(
+        "/tmp/utimensat_target\0",
+        OpenFlags::O_CREATE | OpenFlags::O_RDWR,
+    );
+    assert!(fd >= 0);
+    close(fd as usize);
+    assert!(symlink("/tmp/utimensat_target\0", "/tmp/utimensat_link\0") == 0);
+    assert!(utimensat("/tmp/utimensat_link\0", Some(&[ts(5, 0), ts(6, 0)]), 0) == 0);
+    assert!(times_of("/tmp/utimensat_target\0") == (5, 0, 6, 0));
+    assert!(
+        utimensat(
+            "/tmp/utimensat_link\0",
+            Some(&[ts(7, 0), ts(8, 0)]),
+            AT_SYMLINK_NOFOLLOW
+        ) == 0
+    );
+    assert!(times_of("/tmp/utimensat_target\0") == (5, 0, 6, 0));
+    assert!(unlink("/tmp/utimensat_link\0") == 0);
+    assert!(unlink("/tmp/utimensat_target\0") == 0);
+    println!("utimensat test pass.");
+    0
+}
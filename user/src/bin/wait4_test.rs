@@ -0,0 +1,110 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::*;
+
+const ESRCH: isize = 3;
+const ECHILD: isize = 10;
+const WNOHANG: i32 = 1;
+
+fn now() -> Duration {
+    let mut tp = TimeSpec::default();
+    assert!(clock_gettime(CLOCK_MONOTONIC, &mut tp) == 0);
+    tp.into()
+}
+
+/// Spin in user mode for `ms` milliseconds of wall time.
+fn burn(ms: u64) {
+    let end = now() + Duration::from_millis(ms);
+    while now() < end {
+        core::hint::spin_loop();
+    }
+}
+
+/// Three children exit in an order of their own, and are reaped in another.
+fn test_reap_three() {
+    let mut pids = [0; 3];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        let ret = fork();
+        if ret == 0 {
+            // the last one forked exits first
+            sleep(30 * (3 - i));
+            exit(10 + i as i32);
+        }
+        *pid = ret;
+    }
+    let mut status = 0;
+    // the first one forked exits last, and is waited for first
+    assert!(wait4(pids[0], &mut status, 0, None) == pids[0]);
+    assert!((status >> 8) & 0xff == 10);
+    let mut reaped = [false; 3];
+    reaped[0] = true;
+    for _ in 0..2 {
+        let pid = wait4(-1, &mut status, 0, None);
+        let i = pids.iter().position(|&p| p == pid).unwrap();
+        assert!(!reaped[i]);
+        reaped[i] = true;
+        assert!((status >> 8) & 0xff == 10 + i as i32);
+    }
+    // reaped children are gone, and so are their pids
+    assert!(wait4(-1, &mut status, WNOHANG, None) == -ECHILD);
+    for pid in pids {
+        assert!(wait4(pid, &mut status, 0, None) == -ECHILD);
+        assert!(getpgid(pid as usize) == -ESRCH);
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin wait4 test");
+    let mut status = 0;
+    assert!(wait4(-1, &mut status, 0, None) == -ECHILD);
+    assert!(wait4(-1, &mut status, WNOHANG, None) == -ECHILD);
+
+    // a child in a group of its own
+    let other = fork();
+    if other == 0 {
+        assert!(setpgid(0, 0) == 0);
+        burn(100);
+        exit(1);
+    }
+    assert!(setpgid(other as usize, other as usize) == 0);
+    assert!(getpgid(other as usize) == other);
+    // and one in the group of the parent
+    let same = fork();
+    if same == 0 {
+        sleep(50);
+        exit(2);
+    }
+    assert!(getpgid(same as usize) == getpgid(0));
+
+    // matching children exist but none has exited
+    assert!(wait4(-other, &mut status, WNOHANG, None) == 0);
+    assert!(wait4(same, &mut status, WNOHANG, None) == 0);
+    // no child in a group that does not exist
+    assert!(wait4(-9999, &mut status, WNOHANG, None) == -ECHILD);
+    assert!(wait4(9999, &mut status, 0, None) == -ECHILD);
+
+    // the group of the caller only has the second one
+    assert!(wait4(0, &mut status, 0, None) == same);
+    assert!((status >> 8) & 0xff == 2);
+    assert!(wait4(0, &mut status, WNOHANG, None) == -ECHILD);
+
+    // the first one is waited for by its group, with its usage
+    let mut usage = Rusage::default();
+    assert!(wait4(-other, &mut status, 0, Some(&mut usage)) == other);
+    assert!((status >> 8) & 0xff == 1);
+    let utime: Duration = usage.utime.into();
+    let stime: Duration = usage.stime.into();
+    println!("[wait4] child utime {:?}, stime {:?}", utime, stime);
+    assert!(utime + stime >= Duration::from_millis(50));
+    assert!(wait4(-1, &mut status, WNOHANG, None) == -ECHILD);
+
+    test_reap_three();
+    println!("wait4 test passed");
+    0
+}
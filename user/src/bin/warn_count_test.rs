@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use core::str;
+
+use user_lib::*;
+
+fn read_number(path: &str) -> usize {
+    let fd = openat(path, OpenFlags::O_RDONLY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; 32];
+    let len = read(fd as usize, &mut buf);
+    close(fd as usize);
+    assert!(len > 0);
+    str::from_utf8(&buf[..len as usize])
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+/// Make the kernel log a warning on purpose.
+fn provoke_warn() {
+    let fd = openat("/proc/sys/kernel/provoke_warn\0", OpenFlags::O_WRONLY);
+    assert!(fd >= 0);
+    assert!(write(fd as usize, b"warn_count_test\n") == 16);
+    close(fd as usize);
+}
+
+/// Failed syscalls are expected and must not count as warnings, or the kernel
+/// would panic on them with `panic_on_warn`.
+fn test_errno_is_not_warn() {
+    let before = read_number("/proc/sys/kernel/warn_count\0");
+    assert!(openat("/warn_count_test_missing\0", OpenFlags::O_RDONLY) < 0);
+    let mut buf = [0u8; 1];
+    assert!(read(usize::MAX >> 1, &mut buf) < 0);
+    assert!(read_number("/proc/sys/kernel/warn_count\0") == before);
+}
+
+/// With kernel feature `panic_on_warn`, the kernel panics on the provoked
+/// warning, with the message "panic on warn: [procfs] warning provoked by user:
+/// warn_count_test", so returning from it is a failure.
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("begin warn count test");
+    test_errno_is_not_warn();
+    if read_number("/proc/sys/kernel/panic_on_warn\0") == 1 {
+        println!("expect the kernel to panic on the warning below");
+        provoke_warn();
+        println!("warn count test fail: the kernel did not panic on warn");
+        return 1;
+    }
+    let before = read_number("/proc/sys/kernel/warn_count\0");
+    provoke_warn();
+    let after = read_number("/proc/sys/kernel/warn_count\0");
+    println!("warn count {} -> {}", before, after);
+    assert!(after == before + 1);
+    println!("warn count test pass.");
+    0
+}
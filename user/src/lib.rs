@@ -82,13 +82,18 @@ macro_rules! wexitstatus {
     };
 }
 
-// pub fn getcwd(path: usize, len: usize) -> isize {
-//     sys_getcwd(path, len)
-// }
-
-// pub fn mount(dev_name: usize, target_path: usize, ftype: usize, flags: u32,
-// data: usize) -> isize {     sys_mount(dev_name, target_path, ftype, flags,
-// data) }
+pub fn mount(source: &str, target: &str, fstype: &str, flags: usize) -> isize {
+    sys_mount(
+        source.as_ptr(),
+        target.as_ptr(),
+        fstype.as_ptr(),
+        flags,
+        core::ptr::null(),
+    )
+}
+pub fn umount2(target: &str, flags: usize) -> isize {
+    sys_umount2(target.as_ptr(), flags)
+}
 
 // pub fn uname(buf: usize) -> isize {
 //     sys_uname(buf)
@@ -109,12 +114,151 @@ pub fn openat(path: &str, flags: OpenFlags) -> isize {
     // TODO: change to the version that has `mode` arg
     sys_openat(AT_FDCWD as usize, path.as_ptr(), flags.bits() as usize, 0)
 }
+pub fn openat2(dirfd: isize, path: &str, how: &OpenHow, size: usize) -> isize {
+    sys_openat2(dirfd, path.as_ptr(), how as *const OpenHow, size)
+}
+pub fn mkdir(path: &str) -> isize {
+    sys_mkdirat(AT_FDCWD, path.as_ptr(), 0o777)
+}
+pub fn mknod(path: &str, mode: usize, dev: usize) -> isize {
+    sys_mknodat(AT_FDCWD, path.as_ptr(), mode, dev)
+}
+pub fn unlink(path: &str) -> isize {
+    sys_unlinkat(AT_FDCWD, path.as_ptr(), 0)
+}
+pub fn rmdir(path: &str) -> isize {
+    sys_unlinkat(AT_FDCWD, path.as_ptr(), AT_REMOVEDIR)
+}
+pub fn unlinkat(dirfd: isize, path: &str, flags: i32) -> isize {
+    sys_unlinkat(dirfd, path.as_ptr(), flags)
+}
+pub fn getcwd(buf: &mut [u8]) -> isize {
+    sys_getcwd(buf.as_mut_ptr(), buf.len())
+}
+pub fn chdir(path: &str) -> isize {
+    sys_chdir(path.as_ptr())
+}
+pub fn fchdir(fd: usize) -> isize {
+    sys_fchdir(fd)
+}
+pub fn pivot_root(new_root: &str, put_old: &str) -> isize {
+    sys_pivot_root(new_root.as_ptr(), put_old.as_ptr())
+}
+pub fn link(oldpath: &str, newpath: &str) -> isize {
+    sys_linkat(AT_FDCWD, oldpath.as_ptr(), AT_FDCWD, newpath.as_ptr(), 0)
+}
+pub fn symlink(target: &str, linkpath: &str) -> isize {
+    sys_symlinkat(target.as_ptr(), AT_FDCWD, linkpath.as_ptr())
+}
+pub fn rename(oldpath: &str, newpath: &str) -> isize {
+    sys_renameat2(AT_FDCWD, oldpath.as_ptr(), AT_FDCWD, newpath.as_ptr(), 0)
+}
+pub fn stat(path: &str, st: &mut Kstat) -> isize {
+    sys_fstatat(AT_FDCWD, path.as_ptr(), st as *mut Kstat, 0)
+}
+pub fn fstatat(dirfd: isize, path: &str, st: &mut Kstat, flags: i32) -> isize {
+    sys_fstatat(dirfd, path.as_ptr(), st as *mut Kstat, flags)
+}
+pub fn fstat(fd: usize, st: &mut Kstat) -> isize {
+    sys_fstat(fd, st as *mut Kstat)
+}
+pub fn chmod(path: &str, mode: u32) -> isize {
+    sys_fchmodat(AT_FDCWD, path.as_ptr(), mode)
+}
+pub fn fchmod(fd: usize, mode: u32) -> isize {
+    sys_fchmod(fd, mode)
+}
+/// Pass `u32::MAX` to leave an id unchanged.
+pub fn fchown(fd: usize, uid: u32, gid: u32) -> isize {
+    sys_fchown(fd, uid, gid)
+}
+/// Pass `u32::MAX` to leave an id unchanged.
+pub fn fchownat(dirfd: isize, path: &str, uid: u32, gid: u32, flags: i32) -> isize {
+    sys_fchownat(dirfd, path.as_ptr(), uid, gid, flags)
+}
+pub fn fchmodat2(path: &str, mode: u32, flags: i32) -> isize {
+    sys_fchmodat2(AT_FDCWD, path.as_ptr(), mode, flags)
+}
+/// Pass `None` as `times` to set both times to now.
+pub fn utimensat(path: &str, times: Option<&[TimeSpec; 2]>, flags: i32) -> isize {
+    let times = times.map_or(core::ptr::null(), |times| times.as_ptr());
+    sys_utimensat(AT_FDCWD, path.as_ptr(), times, flags)
+}
+pub fn futimens(fd: usize, times: Option<&[TimeSpec; 2]>) -> isize {
+    let times = times.map_or(core::ptr::null(), |times| times.as_ptr());
+    sys_utimensat(fd as isize, core::ptr::null(), times, 0)
+}
+pub fn statfs(path: &str, buf: &mut StatFs) -> isize {
+    sys_statfs(path.as_ptr(), buf as *mut StatFs)
+}
+pub fn fstatfs(fd: usize, buf: &mut StatFs) -> isize {
+    sys_fstatfs(fd, buf as *mut StatFs)
+}
+pub fn faccessat(path: &str, mode: u32) -> isize {
+    sys_faccessat(AT_FDCWD, path.as_ptr(), mode)
+}
+/// There is no `access` syscall on riscv64, like libc it is `faccessat` on
+/// the current directory.
+pub fn access(path: &str, mode: u32) -> isize {
+    faccessat(path, mode)
+}
+pub fn faccessat2(path: &str, mode: u32, flags: i32) -> isize {
+    sys_faccessat2(AT_FDCWD, path.as_ptr(), mode, flags)
+}
 pub fn read(fd: usize, buf: &mut [u8]) -> isize {
     sys_read(fd, buf.as_mut_ptr(), buf.len())
 }
 pub fn write(fd: usize, buf: &[u8]) -> isize {
     sys_write(fd, buf.as_ptr(), buf.len())
 }
+pub fn writev(fd: usize, iov: &[IoVec]) -> isize {
+    sys_writev(fd, iov.as_ptr(), iov.len())
+}
+pub fn lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    sys_lseek(fd, offset, whence)
+}
+/// Fill `buf` with `struct linux_dirent64` records of the directory `fd`.
+pub fn getdents64(fd: usize, buf: &mut [u8]) -> isize {
+    sys_getdents64(fd, buf.as_mut_ptr(), buf.len())
+}
+pub fn truncate(path: &str, length: isize) -> isize {
+    sys_truncate(path.as_ptr(), length)
+}
+pub fn ftruncate(fd: usize, length: isize) -> isize {
+    sys_ftruncate(fd, length)
+}
+pub fn sync() -> isize {
+    sys_sync()
+}
+
+pub fn fsync(fd: usize) -> isize {
+    sys_fsync(fd)
+}
+pub fn fdatasync(fd: usize) -> isize {
+    sys_fdatasync(fd)
+}
+pub fn readlink(path: &str, buf: &mut [u8]) -> isize {
+    sys_readlinkat(AT_FDCWD, path.as_ptr(), buf.as_mut_ptr(), buf.len())
+}
+pub fn getrandom(buf: &mut [u8], flags: usize) -> isize {
+    sys_getrandom(buf.as_mut_ptr(), buf.len(), flags)
+}
+pub fn statmount(req: &MntIdReq, buf: &mut [u8]) -> isize {
+    sys_statmount(
+        req as *const MntIdReq as *const usize,
+        buf.as_mut_ptr(),
+        buf.len(),
+        0,
+    )
+}
+pub fn listmount(req: &MntIdReq, mnt_ids: &mut [u64]) -> isize {
+    sys_listmount(
+        req as *const MntIdReq as *const usize,
+        mnt_ids.as_mut_ptr(),
+        mnt_ids.len(),
+        0,
+    )
+}
 pub fn mmap(
     addr: *const u8,
     length: usize,
@@ -132,6 +276,48 @@ pub fn mmap(
         offset,
     )
 }
+pub fn munmap(addr: *const u8, length: usize) -> isize {
+    sys_munmap(addr as usize, length)
+}
+pub fn mremap(
+    old_addr: *const u8,
+    old_size: usize,
+    new_size: usize,
+    flags: i32,
+    new_addr: *const u8,
+) -> isize {
+    sys_mremap(
+        old_addr as usize,
+        old_size,
+        new_size,
+        flags,
+        new_addr as usize,
+    )
+}
+pub fn shmget(key: i32, size: usize, shmflg: i32) -> isize {
+    sys_shmget(key, size, shmflg)
+}
+pub fn shmat(shmid: usize, shmaddr: *const u8, shmflg: i32) -> isize {
+    sys_shmat(shmid, shmaddr as usize, shmflg)
+}
+pub fn shmdt(shmaddr: *const u8) -> isize {
+    sys_shmdt(shmaddr as usize)
+}
+pub fn shmctl(shmid: usize, cmd: i32, buf: &mut ShmIdDs) -> isize {
+    sys_shmctl(shmid, cmd, buf as *mut ShmIdDs)
+}
+pub fn mprotect(addr: *const u8, length: usize, prot: i32) -> isize {
+    sys_mprotect(addr as usize, length, prot)
+}
+pub fn madvise(addr: *const u8, length: usize, advice: i32) -> isize {
+    sys_madvise(addr as usize, length, advice)
+}
+pub fn msync(addr: *const u8, length: usize, flags: i32) -> isize {
+    sys_msync(addr as usize, length, flags as usize)
+}
+pub fn membarrier(cmd: i32, flags: u32) -> isize {
+    sys_membarrier(cmd, flags, 0)
+}
 
 //************ task ***************/
 pub fn exit(exit_code: i32) -> ! {
@@ -146,14 +332,211 @@ pub fn yield_() -> isize {
     sys_yield()
 }
 
+pub fn sched_getscheduler(pid: usize) -> isize {
+    sys_sched_getscheduler(pid)
+}
+
+/// Bit `i` of `mask` allows the task to run on hart `i`.
+pub fn sched_setaffinity(pid: usize, mask: usize) -> isize {
+    sys_sched_setaffinity(pid, core::mem::size_of::<usize>(), &mask)
+}
+
+pub fn sched_getaffinity(pid: usize, mask: &mut usize) -> isize {
+    sys_sched_getaffinity(pid, core::mem::size_of::<usize>(), mask)
+}
+
+/// The hart the caller is running on.
+pub fn getcpu() -> isize {
+    let mut cpu = 0u32;
+    let ret = sys_getcpu(&mut cpu, core::ptr::null_mut());
+    if ret < 0 {
+        ret
+    } else {
+        cpu as isize
+    }
+}
+
+pub fn sched_getparam(pid: usize, sched_priority: &mut i32) -> isize {
+    sys_sched_getparam(pid, sched_priority)
+}
+
+pub fn setpriority(which: i32, who: usize, nice: i32) -> isize {
+    sys_setpriority(which, who, nice)
+}
+
+/// Returns `20 - nice` of the highest priority task matched, as the raw
+/// syscall does.
+pub fn getpriority(which: i32, who: usize) -> isize {
+    sys_getpriority(which, who)
+}
+
+pub fn getuid() -> isize {
+    sys_getuid()
+}
+
+pub fn geteuid() -> isize {
+    sys_geteuid()
+}
+
+pub fn setuid(uid: u32) -> isize {
+    sys_setuid(uid)
+}
+
+/// Pass `u32::MAX` to leave an id unchanged.
+pub fn setresuid(ruid: u32, euid: u32, suid: u32) -> isize {
+    sys_setresuid(ruid, euid, suid)
+}
+
+/// Pass `None` as `data` to probe the preferred version, which is written
+/// back to `hdr`.
+pub fn capget(hdr: &mut CapUserHeader, data: Option<&mut [CapUserData; 2]>) -> isize {
+    let data = data.map_or(core::ptr::null_mut(), |data| data.as_mut_ptr());
+    sys_capget(hdr as *mut CapUserHeader, data)
+}
+
+pub fn capset(hdr: &mut CapUserHeader, data: &[CapUserData; 2]) -> isize {
+    sys_capset(hdr as *mut CapUserHeader, data.as_ptr())
+}
+
+pub fn getegid() -> isize {
+    sys_getegid()
+}
+
+/// Pass `u32::MAX` to leave an id unchanged.
+pub fn setresgid(rgid: u32, egid: u32, sgid: u32) -> isize {
+    sys_setresgid(rgid, egid, sgid)
+}
+
+/// Returns the real, effective and saved group ids.
+pub fn getresgid() -> (u32, u32, u32) {
+    let (mut rgid, mut egid, mut sgid) = (0, 0, 0);
+    assert!(sys_getresgid(&mut rgid, &mut egid, &mut sgid) == 0);
+    (rgid, egid, sgid)
+}
+
+/// Returns the previous filesystem user id.
+pub fn setfsuid(fsuid: u32) -> isize {
+    sys_setfsuid(fsuid)
+}
+
+/// Returns the previous filesystem group id.
+pub fn setfsgid(fsgid: u32) -> isize {
+    sys_setfsgid(fsgid)
+}
+
+pub fn setgroups(groups: &[u32]) -> isize {
+    sys_setgroups(groups.len(), groups.as_ptr())
+}
+
+/// Pass an empty `groups` to get only the number of groups.
+pub fn getgroups(groups: &mut [u32]) -> isize {
+    sys_getgroups(groups.len(), groups.as_mut_ptr())
+}
+
 pub fn getpid() -> isize {
     sys_getpid()
 }
 
+pub fn gettid() -> isize {
+    sys_gettid()
+}
+
+pub fn getppid() -> isize {
+    sys_getppid()
+}
+
+pub fn setpgid(pid: usize, pgid: usize) -> isize {
+    sys_setpgid(pid, pgid)
+}
+
+pub fn getpgid(pid: usize) -> isize {
+    sys_getpgid(pid)
+}
+
+pub fn getsid(pid: usize) -> isize {
+    sys_getsid(pid)
+}
+
+pub fn setsid() -> isize {
+    sys_setsid()
+}
+
+pub fn getrusage(who: i32, usage: &mut Rusage) -> isize {
+    sys_getrusage(who, usage)
+}
+
+pub fn sysinfo(info: &mut Sysinfo) -> isize {
+    sys_sysinfo(info)
+}
+
+pub fn getitimer(which: usize, curr_value: &mut ITimerVal) -> isize {
+    sys_getitimer(which, curr_value)
+}
+
+pub fn setitimer(which: usize, new_value: &ITimerVal, old_value: Option<&mut ITimerVal>) -> isize {
+    sys_setitimer(which, new_value, opt_mut(old_value))
+}
+
+pub fn alarm(seconds: u32) -> isize {
+    sys_alarm(seconds)
+}
+
+pub fn timer_create(clockid: usize, sevp: Option<&SigEvent>, timerid: &mut i32) -> isize {
+    sys_timer_create(
+        clockid,
+        sevp.map_or(core::ptr::null(), |e| e as *const SigEvent),
+        timerid,
+    )
+}
+
+pub fn timer_settime(
+    timerid: i32,
+    flags: usize,
+    new_value: &ITimerSpec,
+    old_value: Option<&mut ITimerSpec>,
+) -> isize {
+    sys_timer_settime(timerid, flags, new_value, opt_mut(old_value))
+}
+
+pub fn timer_gettime(timerid: i32, curr_value: &mut ITimerSpec) -> isize {
+    sys_timer_gettime(timerid, curr_value)
+}
+
+pub fn timer_getoverrun(timerid: i32) -> isize {
+    sys_timer_getoverrun(timerid)
+}
+
+pub fn timer_delete(timerid: i32) -> isize {
+    sys_timer_delete(timerid)
+}
+
+pub fn getrlimit(resource: i32, rlim: &mut RLimit) -> isize {
+    sys_getrlimit(resource, rlim)
+}
+
+pub fn setrlimit(resource: i32, rlim: &RLimit) -> isize {
+    sys_setrlimit(resource, rlim)
+}
+
+pub fn prlimit(
+    pid: usize,
+    resource: i32,
+    new_limit: Option<&RLimit>,
+    old_limit: Option<&mut RLimit>,
+) -> isize {
+    let new_limit = new_limit.map_or(core::ptr::null(), |rlim| rlim as *const RLimit);
+    sys_prlimit64(pid, resource, new_limit, opt_mut(old_limit))
+}
+
 pub fn fork() -> isize {
     sys_fork()
 }
 
+/// Create a child process like `fork`, sharing the resources given in `flags`.
+pub fn clone(flags: CloneFlags) -> isize {
+    sys_clone(flags.bits() as usize | Sig::SIGCHLD.raw(), 0, 0, 0)
+}
+
 pub fn create_thread(flags: CloneFlags) -> isize {
     let mut stack: [usize; 1024] = [0; 1024];
     sys_clone(flags.bits() as _, stack.as_mut_ptr() as usize, 0, 0)
@@ -162,6 +545,12 @@ pub fn create_thread(flags: CloneFlags) -> isize {
 pub fn kill(pid: isize, sig: Sig) -> isize {
     sys_kill(pid as usize, sig.raw() as i32)
 }
+pub fn tkill(tid: isize, sig: Sig) -> isize {
+    sys_tkill(tid as usize, sig.raw() as i32)
+}
+pub fn tgkill(tgid: isize, tid: isize, sig: Sig) -> isize {
+    sys_tgkill(tgid as usize, tid as usize, sig.raw() as i32)
+}
 pub fn execve(path: &str, argv: &[&str], envp: &[&str]) -> isize {
     let path = CString::new(path).unwrap();
     let argv: Vec<_> = argv.iter().map(|s| CString::new(*s).unwrap()).collect();
@@ -181,19 +570,125 @@ pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
     sys_waitpid(pid as isize, exit_code as *mut _)
 }
 
+pub fn wait4(pid: isize, exit_code: &mut i32, options: i32, rusage: Option<&mut Rusage>) -> isize {
+    sys_wait4(pid, exit_code as *mut _, options, opt_mut(rusage))
+}
+
 pub fn pipe(pipe_fd: &mut [i32]) -> isize {
     sys_pipe(pipe_fd[0] as *mut _)
 }
 
+fn opt_mut<T>(opt: Option<&mut T>) -> *mut T {
+    opt.map_or(core::ptr::null_mut(), |r| r as *mut T)
+}
+pub fn ppoll(fds: &mut [PollFd], timeout: Option<&TimeSpec>) -> isize {
+    sys_ppoll(
+        fds.as_mut_ptr(),
+        fds.len(),
+        timeout.map_or(core::ptr::null(), |t| t as *const TimeSpec),
+        0,
+    )
+}
+pub fn pselect6(
+    nfds: usize,
+    readfds: Option<&mut FdSet>,
+    writefds: Option<&mut FdSet>,
+    exceptfds: Option<&mut FdSet>,
+    timeout: Option<&TimeSpec>,
+) -> isize {
+    sys_pselect6(
+        nfds,
+        opt_mut(readfds),
+        opt_mut(writefds),
+        opt_mut(exceptfds),
+        timeout.map_or(core::ptr::null(), |t| t as *const TimeSpec),
+        0,
+    )
+}
+pub fn ioprio_set(who: usize, ioprio: u32) -> isize {
+    sys_ioprio_set(IOPRIO_WHO_PROCESS, who, ioprio)
+}
+pub fn ioprio_get(who: usize) -> isize {
+    sys_ioprio_get(IOPRIO_WHO_PROCESS, who)
+}
+pub fn pipe2(pipe_fd: &mut [i32; 2], flags: OpenFlags) -> isize {
+    sys_pipe2(pipe_fd.as_mut_ptr(), flags.bits() as i32)
+}
+
+pub fn eventfd(initval: u32, flags: i32) -> isize {
+    sys_eventfd2(initval, flags)
+}
+
+pub fn fcntl(fd: usize, cmd: isize, arg: usize) -> isize {
+    sys_fcntl(fd, cmd, arg)
+}
+
+pub fn ioctl(fd: usize, cmd: usize, arg: usize) -> isize {
+    sys_ioctl(fd, cmd, arg)
+}
+
+pub fn perf_event_open(
+    attr: &PerfEventAttr,
+    pid: isize,
+    cpu: isize,
+    group_fd: isize,
+    flags: usize,
+) -> isize {
+    sys_perf_event_open(attr, pid, cpu, group_fd, flags)
+}
+
 pub fn close(fd: usize) -> isize {
     sys_close(fd)
 }
 
+pub fn close_range(first: usize, last: usize, flags: u32) -> isize {
+    sys_close_range(first, last, flags)
+}
+
+/// Make the syscall numbered `id` as is, for numbers without a wrapper.
+pub fn raw_syscall(id: usize, args: [usize; 6]) -> isize {
+    syscall(id, args)
+}
+
 //************ time ***************/
 pub fn gettimeofday(time_val: &mut TimeVal) -> isize {
     sys_gettimeofday(time_val as *mut TimeVal as *mut usize, 0 as *mut usize)
 }
 
+pub fn clock_gettime(clockid: usize, tp: &mut TimeSpec) -> isize {
+    sys_clock_gettime(clockid, tp as *mut TimeSpec)
+}
+
+pub fn clock_getres(clockid: usize, res: &mut TimeSpec) -> isize {
+    sys_clock_getres(clockid, res as *mut TimeSpec)
+}
+
+pub fn times(tms: &mut Tms) -> isize {
+    sys_times(tms as *mut Tms)
+}
+
+pub fn timerfd_create(clockid: usize, flags: i32) -> isize {
+    sys_timerfd_create(clockid, flags)
+}
+
+pub fn timerfd_settime(
+    fd: usize,
+    flags: i32,
+    new_value: &ITimerSpec,
+    old_value: Option<&mut ITimerSpec>,
+) -> isize {
+    sys_timerfd_settime(
+        fd,
+        flags,
+        new_value as *const ITimerSpec,
+        opt_mut(old_value),
+    )
+}
+
+pub fn timerfd_gettime(fd: usize, curr_value: &mut ITimerSpec) -> isize {
+    sys_timerfd_gettime(fd, curr_value as *mut ITimerSpec)
+}
+
 pub fn nanosleep(req: &TimeSpec, rem: &mut TimeSpec) -> isize {
     sys_nanosleep(
         req as *const TimeSpec as *const usize,
@@ -219,3 +714,140 @@ pub fn sigaction(sig_no: Sig, act: &SigAction, old_act: &mut SigAction) -> isize
 pub fn sigreturn() -> isize {
     sys_sigreturn()
 }
+
+pub fn sigprocmask(how: usize, set: Option<&SigSet>, old_set: Option<&mut SigSet>) -> isize {
+    sys_sigprocmask(
+        how,
+        set.map_or(core::ptr::null(), |s| s as *const SigSet),
+        opt_mut(old_set),
+        core::mem::size_of::<SigSet>(),
+    )
+}
+
+pub fn sigaltstack(ss: Option<&SignalStack>, old_ss: Option<&mut SignalStack>) -> isize {
+    sys_sigaltstack(
+        ss.map_or(core::ptr::null(), |s| s as *const SignalStack),
+        opt_mut(old_ss),
+    )
+}
+
+pub fn sigpending(set: &mut SigSet) -> isize {
+    sys_sigpending(set as *mut SigSet, core::mem::size_of::<SigSet>())
+}
+
+pub fn sigtimedwait(
+    set: &SigSet,
+    info: Option<&mut LinuxSigInfo>,
+    timeout: Option<&TimeSpec>,
+) -> isize {
+    sys_sigtimedwait(
+        set as *const SigSet,
+        opt_mut(info),
+        timeout.map_or(core::ptr::null(), |t| t as *const TimeSpec),
+        core::mem::size_of::<SigSet>(),
+    )
+}
+
+pub fn sigsuspend(mask: &SigSet) -> isize {
+    sys_sigsuspend(mask as *const SigSet)
+}
+
+pub fn signalfd(fd: isize, mask: &SigSet, flags: i32) -> isize {
+    sys_signalfd4(
+        fd,
+        mask as *const SigSet,
+        core::mem::size_of::<SigSet>(),
+        flags,
+    )
+}
+
+//************ socket ***************/
+pub fn socket(domain: usize, types: usize, protocol: usize) -> isize {
+    sys_socket(domain, types, protocol)
+}
+
+pub fn socketpair(domain: usize, types: usize, protocol: usize, sv: &mut [i32; 2]) -> isize {
+    sys_socketpair(domain, types, protocol, sv as *mut [i32; 2])
+}
+
+pub fn bind(sockfd: usize, addr: &SockAddrUn) -> isize {
+    sys_bind(sockfd, addr as *const SockAddrUn, addr.len())
+}
+
+pub fn listen(sockfd: usize, backlog: usize) -> isize {
+    sys_listen(sockfd, backlog)
+}
+
+pub fn accept(sockfd: usize, addr: &mut SockAddrUn) -> isize {
+    let mut addrlen = core::mem::size_of::<SockAddrUn>() as u32;
+    sys_accept(sockfd, addr as *mut SockAddrUn, &mut addrlen as *mut u32)
+}
+
+/// `addrlen` is the length of `addr` to fill, and is set to the length of the
+/// whole address.
+pub fn getsockname(sockfd: usize, addr: &mut SockAddrUn, addrlen: &mut u32) -> isize {
+    sys_getsockname(sockfd, addr as *mut SockAddrUn, addrlen as *mut u32)
+}
+
+pub fn getpeername(sockfd: usize, addr: &mut SockAddrUn, addrlen: &mut u32) -> isize {
+    sys_getpeername(sockfd, addr as *mut SockAddrUn, addrlen as *mut u32)
+}
+
+pub fn connect(sockfd: usize, addr: &SockAddrUn) -> isize {
+    sys_connect(sockfd, addr as *const SockAddrUn, addr.len())
+}
+
+pub fn send(sockfd: usize, buf: &[u8]) -> isize {
+    sys_sendto(sockfd, buf.as_ptr(), buf.len(), 0, 0, 0)
+}
+
+pub fn recv(sockfd: usize, buf: &mut [u8]) -> isize {
+    sys_recvfrom(sockfd, buf.as_mut_ptr(), buf.len(), 0, 0, 0)
+}
+
+/// Send `buf` to `dest`, or to the peer of `sockfd` if `None`.
+pub fn sendto(sockfd: usize, buf: &[u8], dest: Option<&SockAddrUn>) -> isize {
+    let (addr, addrlen) = dest.map_or((0, 0), |addr| (addr as *const _ as usize, addr.len()));
+    sys_sendto(sockfd, buf.as_ptr(), buf.len(), 0, addr, addrlen)
+}
+
+/// Receive into `buf`, with the address of the sender in `src`, whose
+/// `addrlen` is set as by `getsockname`.
+pub fn recvfrom(sockfd: usize, buf: &mut [u8], src: &mut SockAddrUn, addrlen: &mut u32) -> isize {
+    sys_recvfrom(
+        sockfd,
+        buf.as_mut_ptr(),
+        buf.len(),
+        0,
+        src as *mut SockAddrUn as usize,
+        addrlen as *mut u32 as usize,
+    )
+}
+
+pub fn sendmsg(sockfd: usize, msg: &MsgHdr, flags: usize) -> isize {
+    sys_sendmsg(sockfd, msg as *const MsgHdr, flags)
+}
+
+pub fn recvmsg(sockfd: usize, msg: &mut MsgHdr, flags: usize) -> isize {
+    sys_recvmsg(sockfd, msg as *mut MsgHdr, flags)
+}
+
+pub fn setsockopt(sockfd: usize, level: usize, optname: usize, optval: &[u8]) -> isize {
+    sys_setsockopt(sockfd, level, optname, optval.as_ptr(), optval.len())
+}
+
+pub fn getsockopt(
+    sockfd: usize,
+    level: usize,
+    optname: usize,
+    optval: &mut [u8],
+    optlen: &mut u32,
+) -> isize {
+    sys_getsockopt(
+        sockfd,
+        level,
+        optname,
+        optval.as_mut_ptr(),
+        optlen as *mut u32,
+    )
+}
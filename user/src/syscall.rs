@@ -1,22 +1,41 @@
 use core::arch::asm;
 
+use crate::{
+    CapUserData, CapUserHeader, FdSet, ITimerSpec, ITimerVal, IoVec, Kstat, LinuxSigInfo, MsgHdr,
+    OpenHow, PerfEventAttr, PollFd, RLimit, Rusage, ShmIdDs, SigEvent, SigSet, SignalStack,
+    SockAddrUn, StatFs, Sysinfo, TimeSpec, Tms,
+};
+
 const SYSCALL_GETCWD: usize = 17;
 const SYSCALL_DUP: usize = 23;
+const SYSCALL_EVENTFD2: usize = 19;
 const SYSCALL_DUP3: usize = 24;
 const SYSCALL_FCNTL: usize = 25;
 const SYSCALL_IOCTL: usize = 29;
+const SYSCALL_IOPRIO_SET: usize = 30;
+const SYSCALL_IOPRIO_GET: usize = 31;
 const SYSCALL_UNLINK: usize = 35;
+const SYSCALL_SYMLINKAT: usize = 36;
+const SYSCALL_LINKAT: usize = 37;
 const SYSCALL_MKNOD: usize = 33;
 const SYSCALL_MKDIR: usize = 34;
 const SYSCALL_UMOUNT: usize = 39;
 const SYSCALL_MOUNT: usize = 40;
+const SYSCALL_PIVOT_ROOT: usize = 41;
 const SYSCALL_STATFS: usize = 43;
+const SYSCALL_FSTATFS: usize = 44;
+const SYSCALL_TRUNCATE: usize = 45;
 const SYSCALL_FTRUNCATE: usize = 46;
 const SYSCALL_FACCESSAT: usize = 48;
 const SYSCALL_CHDIR: usize = 49;
+const SYSCALL_FCHDIR: usize = 50;
+const SYSCALL_FCHMOD: usize = 52;
 const SYSCALL_FCHMODAT: usize = 53;
+const SYSCALL_FCHOWNAT: usize = 54;
+const SYSCALL_FCHOWN: usize = 55;
 const SYSCALL_OPEN: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_CLOSE_RANGE: usize = 436;
 const SYSCALL_PIPE: usize = 59;
 const SYSCALL_GETDENTS: usize = 61;
 const SYSCALL_LSEEK: usize = 62;
@@ -29,11 +48,16 @@ const SYSCALL_PWRITE64: usize = 68;
 const SYSCALL_SENDFILE: usize = 71;
 const SYSCALL_PSELECT6: usize = 72;
 const SYSCALL_PPOLL: usize = 73;
+const SYSCALL_SIGNALFD4: usize = 74;
 const SYSCALL_READLINKAT: usize = 78;
 const SYSCALL_NEWFSTATAT: usize = 79;
 const SYSCALL_FSTAT: usize = 80;
 const SYSCALL_SYNC: usize = 81;
 const SYSCALL_FSYNC: usize = 82;
+const SYSCALL_FDATASYNC: usize = 83;
+const SYSCALL_TIMERFD_CREATE: usize = 85;
+const SYSCALL_TIMERFD_SETTIME: usize = 86;
+const SYSCALL_TIMERFD_GETTIME: usize = 87;
 const SYSCALL_UTIMENSAT: usize = 88;
 const SYSCALL_EXIT: usize = 93;
 const SYSCALL_EXIT_GROUP: usize = 94;
@@ -44,6 +68,11 @@ const SYSCALL_GET_ROBUST_LIST: usize = 100;
 const SYSCALL_NANOSLEEP: usize = 101;
 const SYSCALL_GETITIMER: usize = 102;
 const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_TIMER_CREATE: usize = 107;
+const SYSCALL_TIMER_GETTIME: usize = 108;
+const SYSCALL_TIMER_GETOVERRUN: usize = 109;
+const SYSCALL_TIMER_SETTIME: usize = 110;
+const SYSCALL_TIMER_DELETE: usize = 111;
 const SYSCALL_CLOCK_SETTIME: usize = 112;
 const SYSCALL_CLOCK_GETTIME: usize = 113;
 const SYSCALL_CLOCK_GETRES: usize = 114;
@@ -55,21 +84,39 @@ const SYSCALL_SCHED_GETPARAM: usize = 121;
 const SYSCALL_SCHED_SETAFFINITY: usize = 122;
 const SYSCALL_SCHED_GETAFFINITY: usize = 123;
 const SYSCALL_SCHED_YIELD: usize = 124;
+const SYSCALL_CAPGET: usize = 90;
+const SYSCALL_CAPSET: usize = 91;
 const SYSCALL_KILL: usize = 129;
 const SYSCALL_TKILL: usize = 130;
 const SYSCALL_TGKILL: usize = 131;
+const SYSCALL_SIGALTSTACK: usize = 132;
 const SYSCALL_RT_SIGSUSPEND: usize = 133;
 const SYSCALL_RT_SIGACTION: usize = 134;
 const SYSCALL_RT_SIGPROCMASK: usize = 135;
+const SYSCALL_RT_SIGPENDING: usize = 136;
 const SYSCALL_RT_SIGTIMEDWAIT: usize = 137;
 const SYSCALL_RT_SIGRETURN: usize = 139;
+const SYSCALL_SETPRIORITY: usize = 140;
+const SYSCALL_GETPRIORITY: usize = 141;
+const SYSCALL_SETUID: usize = 146;
+const SYSCALL_SETRESUID: usize = 147;
+const SYSCALL_SETRESGID: usize = 149;
+const SYSCALL_GETRESGID: usize = 150;
+const SYSCALL_SETFSUID: usize = 151;
+const SYSCALL_SETFSGID: usize = 152;
 const SYSCALL_TIMES: usize = 153;
 const SYSCALL_SETPGID: usize = 154;
 const SYSCALL_GETPGID: usize = 155;
+const SYSCALL_GETSID: usize = 156;
+const SYSCALL_GETGROUPS: usize = 158;
+const SYSCALL_SETGROUPS: usize = 159;
 const SYSCALL_SETSID: usize = 157;
 const SYSCALL_UNAME: usize = 160;
+const SYSCALL_GETRLIMIT: usize = 163;
+const SYSCALL_SETRLIMIT: usize = 164;
 const SYSCALL_GETRUSAGE: usize = 165;
 const SYSCALL_UMASK: usize = 166;
+const SYSCALL_GETCPU: usize = 168;
 const SYSCALL_GETTIMEOFDAY: usize = 169;
 const SYSCALL_GETPID: usize = 172;
 const SYSCALL_GETPPID: usize = 173;
@@ -82,6 +129,7 @@ const SYSCALL_SYSINFO: usize = 179;
 const SYSCALL_SHMGET: usize = 194;
 const SYSCALL_SHMCTL: usize = 195;
 const SYSCALL_SHMAT: usize = 196;
+const SYSCALL_SHMDT: usize = 197;
 const SYSCALL_SOCKET: usize = 198;
 const SYSCALL_SOCKETPAIR: usize = 199;
 const SYSCALL_BIND: usize = 200;
@@ -95,20 +143,31 @@ const SYSCALL_RECVFROM: usize = 207;
 const SYSCALL_SETSOCKOPT: usize = 208;
 const SYSCALL_GETSOCKOPT: usize = 209;
 const SYSCALL_SHUTDOWN: usize = 210;
+const SYSCALL_SENDMSG: usize = 211;
+const SYSCALL_RECVMSG: usize = 212;
 const SYSCALL_BRK: usize = 214;
 const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_MREMAP: usize = 216;
 const SYSCALL_CLONE: usize = 220;
 const SYSCALL_EXECVE: usize = 221;
 const SYSCALL_MMAP: usize = 222;
 const SYSCALL_MPROTECT: usize = 226;
 const SYSCALL_MSYNC: usize = 227;
 const SYSCALL_MADVISE: usize = 233;
+const SYSCALL_PERF_EVENT_OPEN: usize = 241;
 const SYSCALL_WAIT4: usize = 260;
 const SYSCALL_PRLIMIT64: usize = 261;
-const SYSCALL_REMANEAT2: usize = 276;
+const SYSCALL_RENAMEAT2: usize = 276;
 const SYSCALL_GETRANDOM: usize = 278;
 const SYSCALL_MEMBARRIER: usize = 283;
 const SYSCALL_COPY_FILE_RANGE: usize = 285;
+const SYSCALL_OPENAT2: usize = 437;
+const SYSCALL_FACCESSAT2: usize = 439;
+const SYSCALL_FCHMODAT2: usize = 452;
+const SYSCALL_STATMOUNT: usize = 457;
+const SYSCALL_LISTMOUNT: usize = 458;
+// legacy, not in the riscv64 table
+const SYSCALL_ALARM: usize = 1059;
 
 // it seams that we can't simply the follows
 #[macro_export]
@@ -173,7 +232,7 @@ macro_rules! syscall {
     };
 }
 
-fn syscall(id: usize, args: [usize; 6]) -> isize {
+pub fn syscall(id: usize, args: [usize; 6]) -> isize {
     let mut ret: isize;
     unsafe {
         asm!(
@@ -205,14 +264,95 @@ syscall!(sys_futex, SYSCALL_FUTEX, usize, i32, u32, usize, usize, u32);
 
 // file system
 syscall!(sys_close, SYSCALL_CLOSE, usize);
+syscall!(sys_close_range, SYSCALL_CLOSE_RANGE, usize, usize, u32);
 syscall!(sys_getcwd, SYSCALL_GETCWD, *mut u8, usize);
 syscall!(sys_chdir, SYSCALL_CHDIR, *const u8);
+syscall!(sys_fchdir, SYSCALL_FCHDIR, usize);
+syscall!(sys_pivot_root, SYSCALL_PIVOT_ROOT, *const u8, *const u8);
+syscall!(sys_umount2, SYSCALL_UMOUNT, *const u8, usize);
 syscall!(sys_mkdir, SYSCALL_MKDIR, *const u8);
+syscall!(sys_mkdirat, SYSCALL_MKDIR, isize, *const u8, usize);
+syscall!(sys_mknodat, SYSCALL_MKNOD, isize, *const u8, usize, usize);
+syscall!(
+    sys_fstatat,
+    SYSCALL_NEWFSTATAT,
+    isize,
+    *const u8,
+    *mut Kstat,
+    i32
+);
+syscall!(sys_fstat, SYSCALL_FSTAT, usize, *mut Kstat);
+syscall!(sys_fchmod, SYSCALL_FCHMOD, usize, u32);
+syscall!(sys_fchmodat, SYSCALL_FCHMODAT, isize, *const u8, u32);
+syscall!(sys_fchown, SYSCALL_FCHOWN, usize, u32, u32);
+syscall!(
+    sys_fchownat,
+    SYSCALL_FCHOWNAT,
+    isize,
+    *const u8,
+    u32,
+    u32,
+    i32
+);
+syscall!(sys_fchmodat2, SYSCALL_FCHMODAT2, isize, *const u8, u32, i32);
+syscall!(
+    sys_utimensat,
+    SYSCALL_UTIMENSAT,
+    isize,
+    *const u8,
+    *const TimeSpec,
+    i32
+);
+syscall!(sys_statfs, SYSCALL_STATFS, *const u8, *mut StatFs);
+syscall!(sys_fstatfs, SYSCALL_FSTATFS, usize, *mut StatFs);
+syscall!(sys_unlinkat, SYSCALL_UNLINK, isize, *const u8, i32);
+syscall!(
+    sys_linkat,
+    SYSCALL_LINKAT,
+    isize,
+    *const u8,
+    isize,
+    *const u8,
+    i32
+);
+syscall!(
+    sys_renameat2,
+    SYSCALL_RENAMEAT2,
+    isize,
+    *const u8,
+    isize,
+    *const u8,
+    i32
+);
 syscall!(sys_uname, SYSCALL_UNAME, *mut usize);
 syscall!(sys_dup, SYSCALL_DUP, usize);
 syscall!(sys_dup3, SYSCALL_DUP3, usize, usize, usize);
 syscall!(sys_read, SYSCALL_READ, usize, *mut u8, usize);
+syscall!(sys_lseek, SYSCALL_LSEEK, usize, isize, usize);
+syscall!(sys_getdents64, SYSCALL_GETDENTS, usize, *mut u8, usize);
+syscall!(sys_truncate, SYSCALL_TRUNCATE, *const u8, isize);
+syscall!(sys_ftruncate, SYSCALL_FTRUNCATE, usize, isize);
+syscall!(sys_sync, SYSCALL_SYNC);
+syscall!(sys_fsync, SYSCALL_FSYNC, usize);
+syscall!(sys_fdatasync, SYSCALL_FDATASYNC, usize);
+syscall!(
+    sys_readlinkat,
+    SYSCALL_READLINKAT,
+    isize,
+    *const u8,
+    *mut u8,
+    usize
+);
+syscall!(
+    sys_symlinkat,
+    SYSCALL_SYMLINKAT,
+    *const u8,
+    isize,
+    *const u8
+);
+syscall!(sys_getrandom, SYSCALL_GETRANDOM, *mut u8, usize, usize);
 syscall!(sys_write, SYSCALL_WRITE, usize, *const u8, usize);
+syscall!(sys_writev, SYSCALL_WRITEV, usize, *const IoVec, usize);
 syscall!(
     sys_mmap,
     SYSCALL_MMAP,
@@ -223,19 +363,196 @@ syscall!(
     usize,
     usize
 );
+syscall!(sys_msync, SYSCALL_MSYNC, usize, usize, usize);
+syscall!(sys_munmap, SYSCALL_MUNMAP, usize, usize);
+syscall!(sys_mremap, SYSCALL_MREMAP, usize, usize, usize, i32, usize);
+syscall!(sys_shmget, SYSCALL_SHMGET, i32, usize, i32);
+syscall!(sys_shmat, SYSCALL_SHMAT, usize, usize, i32);
+syscall!(sys_shmdt, SYSCALL_SHMDT, usize);
+syscall!(sys_shmctl, SYSCALL_SHMCTL, usize, i32, *mut ShmIdDs);
+syscall!(sys_mprotect, SYSCALL_MPROTECT, usize, usize, i32);
+syscall!(sys_madvise, SYSCALL_MADVISE, usize, usize, i32);
+syscall!(sys_membarrier, SYSCALL_MEMBARRIER, i32, u32, i32);
 syscall!(sys_openat, SYSCALL_OPEN, usize, *const u8, usize, usize);
+syscall!(
+    sys_statmount,
+    SYSCALL_STATMOUNT,
+    *const usize,
+    *mut u8,
+    usize,
+    u32
+);
+syscall!(
+    sys_listmount,
+    SYSCALL_LISTMOUNT,
+    *const usize,
+    *mut u64,
+    usize,
+    u32
+);
 
 // task
 syscall!(sys_getpid, SYSCALL_GETPID);
+syscall!(sys_gettid, SYSCALL_GETTID);
+syscall!(sys_getppid, SYSCALL_GETPPID);
+syscall!(sys_setpgid, SYSCALL_SETPGID, usize, usize);
+syscall!(sys_getpgid, SYSCALL_GETPGID, usize);
+syscall!(sys_getsid, SYSCALL_GETSID, usize);
+syscall!(sys_setsid, SYSCALL_SETSID);
+syscall!(sys_getrlimit, SYSCALL_GETRLIMIT, i32, *mut RLimit);
+syscall!(sys_setrlimit, SYSCALL_SETRLIMIT, i32, *const RLimit);
+syscall!(sys_getrusage, SYSCALL_GETRUSAGE, i32, *mut Rusage);
+syscall!(sys_sysinfo, SYSCALL_SYSINFO, *mut Sysinfo);
+syscall!(sys_getitimer, SYSCALL_GETITIMER, usize, *mut ITimerVal);
+syscall!(
+    sys_setitimer,
+    SYSCALL_SETITIMER,
+    usize,
+    *const ITimerVal,
+    *mut ITimerVal
+);
+syscall!(sys_alarm, SYSCALL_ALARM, u32);
+syscall!(
+    sys_timer_create,
+    SYSCALL_TIMER_CREATE,
+    usize,
+    *const SigEvent,
+    *mut i32
+);
+syscall!(
+    sys_timer_settime,
+    SYSCALL_TIMER_SETTIME,
+    i32,
+    usize,
+    *const ITimerSpec,
+    *mut ITimerSpec
+);
+syscall!(
+    sys_timer_gettime,
+    SYSCALL_TIMER_GETTIME,
+    i32,
+    *mut ITimerSpec
+);
+syscall!(sys_timer_getoverrun, SYSCALL_TIMER_GETOVERRUN, i32);
+syscall!(sys_timer_delete, SYSCALL_TIMER_DELETE, i32);
+syscall!(
+    sys_prlimit64,
+    SYSCALL_PRLIMIT64,
+    usize,
+    i32,
+    *const RLimit,
+    *mut RLimit
+);
+syscall!(
+    sys_capget,
+    SYSCALL_CAPGET,
+    *mut CapUserHeader,
+    *mut CapUserData
+);
+syscall!(
+    sys_capset,
+    SYSCALL_CAPSET,
+    *mut CapUserHeader,
+    *const CapUserData
+);
 syscall!(sys_exit, SYSCALL_EXIT, i32);
 syscall!(sys_exit_group, SYSCALL_EXIT_GROUP, i32);
 syscall!(sys_kill, SYSCALL_KILL, usize, i32);
+syscall!(sys_tkill, SYSCALL_TKILL, usize, i32);
+syscall!(sys_tgkill, SYSCALL_TGKILL, usize, usize, i32);
 syscall!(sys_fork, SYSCALL_CLONE);
 syscall!(sys_clone, SYSCALL_CLONE, usize, usize, usize, usize);
 syscall!(sys_waitpid, SYSCALL_WAIT4, isize, *mut i32);
+syscall!(sys_wait4, SYSCALL_WAIT4, isize, *mut i32, i32, *mut Rusage);
 syscall!(sys_pipe, SYSCALL_PIPE, *mut i32);
+syscall!(sys_pipe2, SYSCALL_PIPE, *mut i32, i32);
+syscall!(sys_eventfd2, SYSCALL_EVENTFD2, u32, i32);
+syscall!(sys_ioprio_set, SYSCALL_IOPRIO_SET, i32, usize, u32);
+syscall!(sys_ioprio_get, SYSCALL_IOPRIO_GET, i32, usize);
+syscall!(
+    sys_openat2,
+    SYSCALL_OPENAT2,
+    isize,
+    *const u8,
+    *const OpenHow,
+    usize
+);
+syscall!(
+    sys_ppoll,
+    SYSCALL_PPOLL,
+    *mut PollFd,
+    usize,
+    *const TimeSpec,
+    usize
+);
+syscall!(
+    sys_pselect6,
+    SYSCALL_PSELECT6,
+    usize,
+    *mut FdSet,
+    *mut FdSet,
+    *mut FdSet,
+    *const TimeSpec,
+    usize
+);
+syscall!(sys_fcntl, SYSCALL_FCNTL, usize, isize, usize);
+syscall!(sys_ioctl, SYSCALL_IOCTL, usize, usize, usize);
+syscall!(
+    sys_perf_event_open,
+    SYSCALL_PERF_EVENT_OPEN,
+    *const PerfEventAttr,
+    isize,
+    isize,
+    isize,
+    usize
+);
 syscall!(sys_brk, SYSCALL_BRK, usize);
 syscall!(sys_yield, SYSCALL_SCHED_YIELD);
+syscall!(sys_sched_getscheduler, SYSCALL_SCHED_GETSCHEDULER, usize);
+syscall!(sys_sched_getparam, SYSCALL_SCHED_GETPARAM, usize, *mut i32);
+syscall!(sys_setpriority, SYSCALL_SETPRIORITY, i32, usize, i32);
+syscall!(sys_getpriority, SYSCALL_GETPRIORITY, i32, usize);
+syscall!(
+    sys_sched_setaffinity,
+    SYSCALL_SCHED_SETAFFINITY,
+    usize,
+    usize,
+    *const usize
+);
+syscall!(
+    sys_sched_getaffinity,
+    SYSCALL_SCHED_GETAFFINITY,
+    usize,
+    usize,
+    *mut usize
+);
+syscall!(sys_getcpu, SYSCALL_GETCPU, *mut u32, *mut u32);
+syscall!(sys_getuid, SYSCALL_GETUID);
+syscall!(sys_geteuid, SYSCALL_GETEUID);
+syscall!(sys_setuid, SYSCALL_SETUID, u32);
+syscall!(sys_setresuid, SYSCALL_SETRESUID, u32, u32, u32);
+syscall!(sys_getegid, SYSCALL_GETEGID);
+syscall!(sys_setresgid, SYSCALL_SETRESGID, u32, u32, u32);
+syscall!(
+    sys_getresgid,
+    SYSCALL_GETRESGID,
+    *mut u32,
+    *mut u32,
+    *mut u32
+);
+syscall!(sys_setfsuid, SYSCALL_SETFSUID, u32);
+syscall!(sys_setfsgid, SYSCALL_SETFSGID, u32);
+syscall!(sys_setgroups, SYSCALL_SETGROUPS, usize, *const u32);
+syscall!(sys_getgroups, SYSCALL_GETGROUPS, usize, *mut u32);
+syscall!(sys_faccessat, SYSCALL_FACCESSAT, isize, *const u8, u32);
+syscall!(
+    sys_faccessat2,
+    SYSCALL_FACCESSAT2,
+    isize,
+    *const u8,
+    u32,
+    i32
+);
 syscall!(
     sys_execve,
     SYSCALL_EXECVE,
@@ -253,12 +570,52 @@ syscall!(
     *mut usize
 );
 syscall!(sys_sigreturn, SYSCALL_RT_SIGRETURN);
+syscall!(
+    sys_sigaltstack,
+    SYSCALL_SIGALTSTACK,
+    *const SignalStack,
+    *mut SignalStack
+);
 syscall!(
     sys_sigprocmask,
     SYSCALL_RT_SIGPROCMASK,
     usize,
+    *const SigSet,
+    *mut SigSet,
+    usize
+);
+syscall!(sys_sigsuspend, SYSCALL_RT_SIGSUSPEND, *const SigSet);
+syscall!(sys_sigpending, SYSCALL_RT_SIGPENDING, *mut SigSet, usize);
+syscall!(
+    sys_sigtimedwait,
+    SYSCALL_RT_SIGTIMEDWAIT,
+    *const SigSet,
+    *mut LinuxSigInfo,
+    *const TimeSpec,
+    usize
+);
+syscall!(
+    sys_signalfd4,
+    SYSCALL_SIGNALFD4,
+    isize,
+    *const SigSet,
+    usize,
+    i32
+);
+syscall!(sys_timerfd_create, SYSCALL_TIMERFD_CREATE, usize, i32);
+syscall!(
+    sys_timerfd_settime,
+    SYSCALL_TIMERFD_SETTIME,
     usize,
-    *mut usize
+    i32,
+    *const ITimerSpec,
+    *mut ITimerSpec
+);
+syscall!(
+    sys_timerfd_gettime,
+    SYSCALL_TIMERFD_GETTIME,
+    usize,
+    *mut ITimerSpec
 );
 
 // Time
@@ -270,3 +627,86 @@ syscall!(
 );
 syscall!(sys_nanosleep, SYSCALL_NANOSLEEP, *const usize, *mut usize);
 syscall!(sys_sleep, SYSCALL_NANOSLEEP, *const usize);
+syscall!(
+    sys_clock_gettime,
+    SYSCALL_CLOCK_GETTIME,
+    usize,
+    *mut TimeSpec
+);
+syscall!(sys_clock_getres, SYSCALL_CLOCK_GETRES, usize, *mut TimeSpec);
+syscall!(sys_times, SYSCALL_TIMES, *mut Tms);
+
+// Socket
+syscall!(sys_socket, SYSCALL_SOCKET, usize, usize, usize);
+syscall!(
+    sys_socketpair,
+    SYSCALL_SOCKETPAIR,
+    usize,
+    usize,
+    usize,
+    *mut [i32; 2]
+);
+syscall!(sys_bind, SYSCALL_BIND, usize, *const SockAddrUn, usize);
+syscall!(sys_sendmsg, SYSCALL_SENDMSG, usize, *const MsgHdr, usize);
+syscall!(sys_recvmsg, SYSCALL_RECVMSG, usize, *mut MsgHdr, usize);
+syscall!(sys_listen, SYSCALL_LISTEN, usize, usize);
+syscall!(sys_accept, SYSCALL_ACCEPT, usize, *mut SockAddrUn, *mut u32);
+syscall!(
+    sys_getsockname,
+    SYSCALL_GETSOCKNAME,
+    usize,
+    *mut SockAddrUn,
+    *mut u32
+);
+syscall!(
+    sys_getpeername,
+    SYSCALL_GETPEERNAME,
+    usize,
+    *mut SockAddrUn,
+    *mut u32
+);
+syscall!(
+    sys_connect,
+    SYSCALL_CONNECT,
+    usize,
+    *const SockAddrUn,
+    usize
+);
+syscall!(
+    sys_sendto,
+    SYSCALL_SENDTO,
+    usize,
+    *const u8,
+    usize,
+    usize,
+    usize,
+    usize
+);
+syscall!(
+    sys_recvfrom,
+    SYSCALL_RECVFROM,
+    usize,
+    *mut u8,
+    usize,
+    usize,
+    usize,
+    usize
+);
+syscall!(
+    sys_setsockopt,
+    SYSCALL_SETSOCKOPT,
+    usize,
+    usize,
+    usize,
+    *const u8,
+    usize
+);
+syscall!(
+    sys_getsockopt,
+    SYSCALL_GETSOCKOPT,
+    usize,
+    usize,
+    usize,
+    *mut u8,
+    *mut u32
+);
@@ -1,6 +1,11 @@
 pub use signal::*;
 pub use sigset::*;
-pub use time::{timespec::TimeSpec, timeval::TimeVal};
+pub use time::{
+    timespec::{ITimerSpec, TimeSpec},
+    timeval::{ITimerVal, TimeVal},
+    CLOCK_BOOTTIME, CLOCK_MONOTONIC, CLOCK_MONOTONIC_COARSE, CLOCK_MONOTONIC_RAW,
+    CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME,
+};
 
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
@@ -36,14 +41,289 @@ bitflags! {
 bitflags! {
     pub struct OpenFlags: u32 {
         const O_RDONLY = 0;
-        const O_WRONLY = 1 << 0;
-        const O_RDWR = 1 << 1;
-        const O_CLOEXEC = 1 << 7;
-        const O_CREATE = 1 << 9;
-        const O_TRUNC = 1 << 10;
+        const O_WRONLY = 0o1;
+        const O_RDWR = 0o2;
+        const O_CREATE = 0o100;
+        const O_TRUNC = 0o1000;
+        const O_APPEND = 0o2000;
+        const O_NONBLOCK = 0o4000;
+        const O_ASYNC = 0o20000;
+        const O_DIRECTORY = 0o200000;
+        const O_NOFOLLOW = 0o400000;
+        const O_CLOEXEC = 0o2000000;
     }
 }
 pub const AT_FDCWD: isize = -100;
+pub const AT_SYMLINK_NOFOLLOW: i32 = 0x100;
+pub const AT_EACCESS: i32 = 0x200;
+pub const AT_REMOVEDIR: i32 = 0x200;
+pub const AT_EMPTY_PATH: i32 = 0x1000;
+
+/// Arguments of `openat2`.
+pub const LINUX_CAPABILITY_VERSION_3: u32 = 0x20080522;
+
+pub const CAP_CHOWN: u32 = 0;
+pub const CAP_DAC_OVERRIDE: u32 = 1;
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct CapUserHeader {
+    pub version: u32,
+    pub pid: i32,
+}
+
+/// The first one holds the low 32 capabilities, the second the high ones.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct CapUserData {
+    pub effective: u32,
+    pub permitted: u32,
+    pub inheritable: u32,
+}
+
+pub const RUSAGE_SELF: i32 = 0;
+pub const RUSAGE_CHILDREN: i32 = -1;
+
+/// Process times of times(2), in clock ticks.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct Tms {
+    pub tms_utime: usize,
+    pub tms_stime: usize,
+    pub tms_cutime: usize,
+    pub tms_cstime: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct Rusage {
+    pub utime: TimeVal,
+    pub stime: TimeVal,
+    pub maxrss: usize,
+    pub ixrss: usize,
+    pub idrss: usize,
+    pub isrss: usize,
+    pub minflt: usize,
+    pub majflt: usize,
+    pub nswap: usize,
+    pub inblock: usize,
+    pub oublock: usize,
+    pub msgsnd: usize,
+    pub msgrcv: usize,
+    pub nsignals: usize,
+    pub nvcsw: usize,
+    pub nivcsw: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct Sysinfo {
+    pub uptime: i64,
+    pub loads: [u64; 3],
+    pub totalram: u64,
+    pub freeram: u64,
+    pub sharedram: u64,
+    pub bufferram: u64,
+    pub totalswap: u64,
+    pub freeswap: u64,
+    pub procs: u16,
+    pub pad: u16,
+    pub totalhigh: u64,
+    pub freehigh: u64,
+    pub mem_unit: u32,
+}
+
+pub const ITIMER_REAL: usize = 0;
+pub const ITIMER_VIRTUAL: usize = 1;
+pub const ITIMER_PROF: usize = 2;
+
+/// The time of timer_settime(2) is absolute.
+pub const TIMER_ABSTIME: usize = 1;
+
+pub const RLIMIT_STACK: i32 = 3;
+pub const RLIMIT_CORE: i32 = 4;
+pub const RLIMIT_NOFILE: i32 = 7;
+pub const RLIM_INFINITY: usize = usize::MAX;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct RLimit {
+    pub rlim_cur: usize,
+    pub rlim_max: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct OpenHow {
+    pub flags: u64,
+    pub mode: u64,
+    pub resolve: u64,
+}
+
+pub const RESOLVE_NO_XDEV: u64 = 0x01;
+pub const RESOLVE_NO_MAGICLINKS: u64 = 0x02;
+pub const RESOLVE_NO_SYMLINKS: u64 = 0x04;
+pub const RESOLVE_BENEATH: u64 = 0x08;
+
+pub const PRIO_PROCESS: i32 = 0;
+pub const PRIO_PGRP: i32 = 1;
+pub const PRIO_USER: i32 = 2;
+
+pub const IOPRIO_WHO_PROCESS: i32 = 1;
+pub const IOPRIO_CLASS_RT: u32 = 1;
+pub const IOPRIO_CLASS_BE: u32 = 2;
+pub const IOPRIO_CLASS_IDLE: u32 = 3;
+
+pub const fn ioprio_value(class: u32, level: u32) -> u32 {
+    class << 13 | level
+}
+
+pub const F_OK: u32 = 0;
+pub const R_OK: u32 = 4;
+pub const W_OK: u32 = 2;
+pub const X_OK: u32 = 1;
+
+pub const F_DUPFD_CLOEXEC: isize = 1030;
+pub const F_GETFD: isize = 1;
+pub const F_SETFD: isize = 2;
+pub const FD_CLOEXEC: usize = 1;
+
+pub const FD_SETSIZE: usize = 1024;
+
+/// Bitmap of file descriptors for `pselect6`.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct FdSet {
+    pub fds_bits: [u64; FD_SETSIZE / 64],
+}
+
+impl FdSet {
+    pub fn zero() -> Self {
+        Self {
+            fds_bits: [0; FD_SETSIZE / 64],
+        }
+    }
+
+    pub fn set(&mut self, fd: usize) {
+        self.fds_bits[fd / 64] |= 1 << (fd % 64);
+    }
+
+    pub fn is_set(&self, fd: usize) -> bool {
+        self.fds_bits[fd / 64] & (1 << (fd % 64)) != 0
+    }
+}
+
+pub const POLLIN: i16 = 0x001;
+pub const POLLOUT: i16 = 0x004;
+pub const POLLERR: i16 = 0x008;
+pub const POLLHUP: i16 = 0x010;
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct PollFd {
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16,
+}
+
+impl PollFd {
+    pub fn new(fd: usize, events: i16) -> Self {
+        Self {
+            fd: fd as i32,
+            events,
+            revents: 0,
+        }
+    }
+}
+
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+pub const SIG_SETMASK: usize = 2;
+
+/// What a read of a signal file returns for each signal.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct SignalFdSiginfo {
+    pub ssi_signo: u32,
+    pub ssi_errno: i32,
+    pub ssi_code: i32,
+    pub ssi_pid: u32,
+    pub ssi_uid: u32,
+    pub ssi_fd: i32,
+    pub ssi_tid: u32,
+    pub ssi_band: u32,
+    pub ssi_overrun: u32,
+    pub ssi_trapno: u32,
+    pub ssi_status: i32,
+    pub ssi_int: i32,
+    pub ssi_ptr: u64,
+    pub ssi_utime: u64,
+    pub ssi_stime: u64,
+    pub ssi_addr: u64,
+    pub ssi_addr_lsb: u16,
+    pub __pad2: u16,
+    pub ssi_syscall: i32,
+    pub ssi_call_addr: u64,
+    pub ssi_arch: u32,
+    pub __pad: [u8; 28],
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct Kstat {
+    pub st_dev: u64,
+    pub st_ino: u64,
+    pub st_mode: u32,
+    pub st_nlink: u32,
+    pub st_uid: u32,
+    pub st_gid: u32,
+    pub st_rdev: u64,
+    pub __pad: u64,
+    pub st_size: i64,
+    pub st_blksize: i32,
+    pub __pad2: i32,
+    pub st_blocks: i64,
+    pub st_atime_sec: isize,
+    pub st_atime_nsec: isize,
+    pub st_mtime_sec: isize,
+    pub st_mtime_nsec: isize,
+    pub st_ctime_sec: isize,
+    pub st_ctime_nsec: isize,
+}
+
+/// Statistics of a file system, see statfs(2).
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct StatFs {
+    pub f_type: i64,
+    pub f_bsize: i64,
+    pub f_blocks: u64,
+    pub f_bfree: u64,
+    pub f_bavail: u64,
+    pub f_files: u64,
+    pub f_ffree: u64,
+    pub f_fsid: [i32; 2],
+    pub f_namelen: i64,
+    pub f_frsize: i64,
+    pub f_flags: i64,
+    pub f_spare: [i64; 4],
+}
+
+/// Request for `statmount` and `listmount`.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct MntIdReq {
+    pub size: u32,
+    pub spare: u32,
+    pub mnt_id: u64,
+    pub param: u64,
+}
+pub const LSMT_ROOT: u64 = u64::MAX;
+pub const STATMOUNT_MNT_BASIC: u64 = 0x2;
+pub const STATMOUNT_MNT_POINT: u64 = 0x10;
+pub const STATMOUNT_FS_TYPE: u64 = 0x20;
+/// Size of the fixed part of `struct statmount`.
+pub const STATMOUNT_SIZE: usize = 512;
 
 pub const FUTEX_PRIVATE_FLAG: i32 = 0x80;
 pub const FUTEX_WAIT: i32 = 0;
@@ -109,7 +389,7 @@ bitflags! {
 /// 那么这些函数的栈帧也会被压入信号栈。每个栈帧通常包含了函数参数、
 /// 局部变量以及返回地址。 4.信号处理程序的返回地址：当信号处理程序完成执行后，
 /// 系统需要知道从哪里返回继续执行，因此信号栈上会保存一个返回地址。
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 #[repr(C)]
 pub struct SignalStack {
     /// Base address of stack
@@ -140,3 +420,210 @@ pub struct MContext {
     pub sepc: usize,
     pub user_x: [usize; 32],
 }
+
+pub const AF_UNIX: usize = 1;
+pub const AF_INET: usize = 2;
+pub const SOCK_STREAM: usize = 1;
+pub const SOCK_DGRAM: usize = 2;
+pub const SOCK_NONBLOCK: usize = 0o4000;
+pub const SOCK_CLOEXEC: usize = 0o2000000;
+
+pub const SOL_SOCKET: usize = 1;
+pub const IPPROTO_TCP: usize = 6;
+pub const SO_REUSEADDR: usize = 2;
+pub const SO_TYPE: usize = 3;
+pub const SO_ERROR: usize = 4;
+pub const SO_SNDBUF: usize = 7;
+pub const SO_RCVBUF: usize = 8;
+pub const SO_KEEPALIVE: usize = 9;
+pub const SO_PASSCRED: usize = 16;
+pub const SO_PEERCRED: usize = 17;
+pub const SO_RCVTIMEO: usize = 20;
+pub const SO_SNDTIMEO: usize = 21;
+pub const TCP_NODELAY: usize = 1;
+
+/// Unix domain socket address
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct SockAddrUn {
+    pub family: u16,
+    pub path: [u8; 108],
+}
+
+impl SockAddrUn {
+    pub fn new(path: &str) -> Self {
+        let mut addr = Self {
+            family: AF_UNIX as u16,
+            path: [0; 108],
+        };
+        addr.path[..path.len()].copy_from_slice(path.as_bytes());
+        addr
+    }
+
+    /// An address in the abstract namespace, `name` after a null byte.
+    pub fn new_abstract(name: &str) -> Self {
+        let mut addr = Self::new("");
+        addr.path[1..1 + name.len()].copy_from_slice(name.as_bytes());
+        addr
+    }
+
+    /// Length of the address up to the end of the path, or of the abstract
+    /// name.
+    pub fn len(&self) -> usize {
+        let path_len = if self.path[0] == 0 {
+            self.path.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1)
+        } else {
+            self.path.iter().position(|&c| c == 0).unwrap_or(108)
+        };
+        core::mem::size_of::<u16>() + path_len
+    }
+}
+
+pub const SCM_CREDENTIALS: i32 = 2;
+pub const MSG_CTRUNC: i32 = 0x8;
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct IoVec {
+    pub base: usize,
+    pub len: usize,
+}
+
+/// `struct msghdr` of sendmsg(2) and recvmsg(2).
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct MsgHdr {
+    pub name: usize,
+    pub namelen: u32,
+    pub iov: usize,
+    pub iovlen: usize,
+    pub control: usize,
+    pub controllen: usize,
+    pub flags: i32,
+}
+
+impl MsgHdr {
+    /// A message of the data in `iov`, without address and control data.
+    pub fn new(iov: &[IoVec]) -> Self {
+        Self {
+            name: 0,
+            namelen: 0,
+            iov: iov.as_ptr() as usize,
+            iovlen: iov.len(),
+            control: 0,
+            controllen: 0,
+            flags: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct UCred {
+    pub pid: u32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// A control message of `SCM_CREDENTIALS`, padded to `CMSG_SPACE`.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct CMsgCred {
+    pub len: usize,
+    pub level: i32,
+    pub type_: i32,
+    pub cred: UCred,
+    _pad: u32,
+}
+
+impl CMsgCred {
+    /// `CMSG_LEN` of the credentials.
+    pub const LEN: usize = 16 + core::mem::size_of::<UCred>();
+
+    pub fn new(cred: UCred) -> Self {
+        Self {
+            len: Self::LEN,
+            level: SOL_SOCKET as i32,
+            type_: SCM_CREDENTIALS,
+            cred,
+            _pad: 0,
+        }
+    }
+}
+
+/// `struct perf_event_attr` of the first published size.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct PerfEventAttr {
+    pub type_: u32,
+    pub size: u32,
+    pub config: u64,
+    pub sample_period: u64,
+    pub sample_type: u64,
+    pub read_format: u64,
+    pub flags: u64,
+    pub wakeup_events: u32,
+    pub bp_type: u32,
+    pub config1: u64,
+}
+pub const PERF_TYPE_HARDWARE: u32 = 0;
+pub const PERF_TYPE_SOFTWARE: u32 = 1;
+pub const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+pub const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+pub const PERF_ATTR_DISABLED: u64 = 1;
+pub const PERF_FLAG_FD_CLOEXEC: usize = 8;
+pub const PERF_EVENT_IOC_ENABLE: usize = 0x2400;
+pub const PERF_EVENT_IOC_DISABLE: usize = 0x2401;
+pub const PERF_EVENT_IOC_RESET: usize = 0x2403;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct WinSize {
+    pub ws_row: u16,
+    pub ws_col: u16,
+    pub ws_xpixel: u16,
+    pub ws_ypixel: u16,
+}
+pub const TIOCGPGRP: usize = 0x540F;
+pub const TIOCSPGRP: usize = 0x5410;
+pub const TIOCGWINSZ: usize = 0x5413;
+pub const TIOCSWINSZ: usize = 0x5414;
+pub const TIOCGPTN: usize = 0x80045430;
+pub const TIOCSPTLCK: usize = 0x40045431;
+
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct IpcPerm {
+    pub key: i32,
+    pub uid: u32,
+    pub gid: u32,
+    pub cuid: u32,
+    pub cgid: u32,
+    pub mode: u32,
+    pub seq: u16,
+    pub pad: u16,
+    pub unused: [usize; 2],
+}
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct ShmIdDs {
+    pub shm_perm: IpcPerm,
+    pub shm_segsz: usize,
+    pub shm_atime: usize,
+    pub shm_dtime: usize,
+    pub shm_ctime: usize,
+    pub shm_cpid: i32,
+    pub shm_lpid: i32,
+    pub shm_nattch: usize,
+    pub unused: [usize; 2],
+}
+pub const IPC_PRIVATE: i32 = 0;
+pub const IPC_CREAT: i32 = 0o1000;
+pub const IPC_EXCL: i32 = 0o2000;
+pub const IPC_RMID: i32 = 0;
+pub const IPC_SET: i32 = 1;
+pub const IPC_STAT: i32 = 2;
+pub const SHM_DEST: u32 = 0o1000;
+pub const SHM_RDONLY: i32 = 0o10000;
+pub const SHM_RND: i32 = 0o20000;
+pub const SHM_REMAP: i32 = 0o40000;
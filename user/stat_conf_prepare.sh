@@ -0,0 +1,20 @@
+#!/bin/sh
+# Build a small tree on the mounted image at $1 and record the link count,
+# size and preferred I/O block size Linux reports for it, one
+# "<path> <nlink> <size> <blksize>" line per file in $1/stat_conf.expected.
+# stat_conf_test compares what the kernel reports for the same tree.
+set -e
+root=$1
+mkdir -p $root/stat_conf/dir/sub1 $root/stat_conf/dir/sub2 $root/stat_conf/dir/sub3
+mkdir -p $root/stat_conf/empty $root/stat_conf/big
+for i in $(seq 1 300); do
+    touch $root/stat_conf/big/a_rather_long_file_name_number_$i
+done
+printf "stat conformance\n" > $root/stat_conf/file
+# FAT has no hard links
+ln $root/stat_conf/file $root/stat_conf/file.link 2>/dev/null || true
+sync
+cd $root
+for p in stat_conf stat_conf/dir stat_conf/dir/sub1 stat_conf/empty stat_conf/big stat_conf/file; do
+    stat -c "/$p %h %s %o" $p
+done > stat_conf.expected